@@ -1,6 +1,45 @@
 //! Build script for compiling protobuf definitions
 
+use std::path::Path;
+use std::process::Command;
+
+/// Trimmed output of a successful git command
+fn git(args: &[&str]) -> Option<String> {
+    Command::new("git")
+        .args(args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|output| output.trim().to_string())
+        .filter(|output| !output.is_empty())
+}
+
+fn rerun_if_git_path_changed(path: &str) {
+    // A missing path would rerun the script on every build
+    if let Some(path) = git(&["rev-parse", "--git-path", path]).filter(|path| Path::new(path).exists()) {
+        println!("cargo:rerun-if-changed={}", path);
+    }
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Compile protobuf - will be handled by services-common
+
+    // Stamp run records with the revision the service was built from,
+    // marked `-dirty` when built with uncommitted changes
+    let revision = git(&["describe", "--always", "--dirty", "--abbrev=12"]).unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=BACKTESTING_GIT_REVISION={}", revision);
+    println!("cargo:rerun-if-changed=build.rs");
+
+    // HEAD moves on checkout, the branch ref or packed-refs on commit, and
+    // the index when changes are staged or committed
+    rerun_if_git_path_changed("HEAD");
+    rerun_if_git_path_changed("packed-refs");
+    rerun_if_git_path_changed("index");
+    if let Some(reference) = git(&["rev-parse", "--symbolic-full-name", "HEAD"]).filter(|r| r.starts_with("refs/")) {
+        rerun_if_git_path_changed(&reference);
+    }
+    // Editing this crate's sources makes the tree dirty
+    println!("cargo:rerun-if-changed=src");
     Ok(())
-}
\ No newline at end of file
+}
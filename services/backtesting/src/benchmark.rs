@@ -0,0 +1,180 @@
+//! Benchmark-relative performance
+//!
+//! A benchmark is a price series the strategy is measured against, such as
+//! NIFTY 50 or a BTCUSDT buy-and-hold. The benchmark is sampled at each
+//! point of the equity curve (using its last price at or before the point),
+//! both series are turned into period returns, and `PerformanceAnalyzer`
+//! reports alpha, beta, tracking error, information ratio and up/down
+//! capture over the whole run and over a rolling window.
+//!
+//! Alpha and the information ratio come from the `reporting::analytics`
+//! calculators and are annualized with the same 252 periods per year as
+//! `PerformanceMetrics`.
+
+use anyhow::{Result, bail};
+use chrono::{DateTime, Utc};
+use reporting::analytics::{PortfolioMetricsCalculator, StatisticalAnalyzer};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Periods per year used to annualize, as in `PerformanceAnalyzer`
+const PERIODS_PER_YEAR: f64 = 252.0;
+
+/// Benchmark a backtest is measured against
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BenchmarkConfig {
+    /// Symbol whose closing prices form the benchmark series
+    pub symbol: String,
+    /// Number of returns in each window of the rolling metrics
+    #[serde(default = "default_rolling_window")]
+    pub rolling_window: usize,
+}
+
+fn default_rolling_window() -> usize {
+    63
+}
+
+impl BenchmarkConfig {
+    /// Benchmark on `symbol` with the default rolling window of one quarter
+    pub fn new(symbol: impl Into<String>) -> Self {
+        Self {
+            symbol: symbol.into(),
+            rolling_window: default_rolling_window(),
+        }
+    }
+}
+
+/// Strategy performance relative to a benchmark over some span of returns
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct RelativeMetrics {
+    /// Annualized Jensen's alpha
+    pub alpha: f64,
+    /// Sensitivity of strategy returns to benchmark returns
+    pub beta: f64,
+    /// Correlation of strategy and benchmark returns
+    pub correlation: f64,
+    /// Annualized standard deviation of active returns
+    pub tracking_error: f64,
+    /// Annualized mean active return over tracking error
+    pub information_ratio: f64,
+    /// Mean strategy return over mean benchmark return in periods the benchmark rose
+    pub up_capture: f64,
+    /// Mean strategy return over mean benchmark return in periods the benchmark fell
+    pub down_capture: f64,
+}
+
+impl RelativeMetrics {
+    /// Metrics of `returns` against `benchmark_returns`, which must line up
+    ///
+    /// `risk_free_rate` is annual.
+    pub fn of(returns: &[f64], benchmark_returns: &[f64], risk_free_rate: f64) -> Self {
+        debug_assert_eq!(returns.len(), benchmark_returns.len());
+        if returns.len() < 2 {
+            return Self::default();
+        }
+        let active: Vec<f64> = returns.iter().zip(benchmark_returns).map(|(r, b)| r - b).collect();
+        let rate_per_period = risk_free_rate / PERIODS_PER_YEAR;
+        Self {
+            alpha: PortfolioMetricsCalculator::jensens_alpha(returns, benchmark_returns, rate_per_period) * PERIODS_PER_YEAR,
+            beta: StatisticalAnalyzer::beta(returns, benchmark_returns),
+            correlation: StatisticalAnalyzer::correlation(returns, benchmark_returns),
+            tracking_error: population_std_dev(&active) * PERIODS_PER_YEAR.sqrt(),
+            information_ratio: PortfolioMetricsCalculator::information_ratio(returns, benchmark_returns) * PERIODS_PER_YEAR.sqrt(),
+            up_capture: capture(returns, benchmark_returns, |b| b > 0.0),
+            down_capture: capture(returns, benchmark_returns, |b| b < 0.0),
+        }
+    }
+}
+
+/// Relative metrics over the window of returns ending at `timestamp`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RollingRelativeMetrics {
+    /// End of the window
+    pub timestamp: DateTime<Utc>,
+    /// Metrics over the window
+    pub metrics: RelativeMetrics,
+}
+
+/// Benchmark-relative analytics of a backtest
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct BenchmarkMetrics {
+    /// Symbol of the benchmark
+    pub symbol: String,
+    /// Benchmark return over the equity curve's span as a decimal
+    pub benchmark_return: f64,
+    /// Strategy return minus benchmark return
+    pub excess_return: f64,
+    /// Metrics over the whole run
+    pub overall: RelativeMetrics,
+    /// Number of returns in each rolling window
+    pub rolling_window: usize,
+    /// Metrics over each window of `rolling_window` returns, oldest first
+    pub rolling: Vec<RollingRelativeMetrics>,
+}
+
+/// Strategy and benchmark returns over the same periods
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AlignedReturns {
+    /// End of each period
+    pub timestamps: Vec<DateTime<Utc>>,
+    /// Strategy return of each period
+    pub strategy: Vec<f64>,
+    /// Benchmark return of each period
+    pub benchmark: Vec<f64>,
+}
+
+impl AlignedReturns {
+    /// Returns of `equity_curve` and of `benchmark` sampled at its timestamps
+    ///
+    /// Points of the equity curve before the first benchmark price are
+    /// dropped. Fails if fewer than two points remain.
+    pub fn of(equity_curve: &[(DateTime<Utc>, f64)], benchmark: &[(DateTime<Utc>, f64)]) -> Result<Self> {
+        let prices: BTreeMap<DateTime<Utc>, f64> = benchmark.iter().copied().collect();
+        let sampled: Vec<(DateTime<Utc>, f64, f64)> = equity_curve.iter()
+            .filter_map(|(timestamp, equity)| {
+                let (_, price) = prices.range(..=*timestamp).next_back()?;
+                Some((*timestamp, *equity, *price))
+            })
+            .collect();
+        if sampled.len() < 2 {
+            bail!("Benchmark covers {} point(s) of the equity curve, at least 2 are needed", sampled.len());
+        }
+        if sampled.iter().any(|(_, equity, price)| *equity <= 0.0 || *price <= 0.0) {
+            bail!("Equity and benchmark prices must be positive to compute returns");
+        }
+
+        let mut aligned = Self::default();
+        for pair in sampled.windows(2) {
+            let ((_, equity_before, price_before), (timestamp, equity, price)) = (pair[0], pair[1]);
+            aligned.timestamps.push(timestamp);
+            aligned.strategy.push(equity / equity_before - 1.0);
+            aligned.benchmark.push(price / price_before - 1.0);
+        }
+        Ok(aligned)
+    }
+
+    /// Compounded benchmark return over all periods
+    pub fn benchmark_total_return(&self) -> f64 {
+        self.benchmark.iter().fold(1.0, |growth, r| growth * (1.0 + r)) - 1.0
+    }
+}
+
+/// Mean strategy return over mean benchmark return, in periods selected by `include`
+fn capture(returns: &[f64], benchmark_returns: &[f64], include: impl Fn(f64) -> bool) -> f64 {
+    let (mut strategy_sum, mut benchmark_sum) = (0.0, 0.0);
+    for (r, b) in returns.iter().zip(benchmark_returns) {
+        if include(*b) {
+            strategy_sum += r;
+            benchmark_sum += b;
+        }
+    }
+    if benchmark_sum == 0.0 { 0.0 } else { strategy_sum / benchmark_sum }
+}
+
+fn population_std_dev(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64).sqrt()
+}
@@ -0,0 +1,328 @@
+//! Commission, tax and fee models
+//!
+//! A `CommissionModel` prices every fill produced by the `ExecutionSimulator`
+//! and returns the charges split by component, so results show what was paid
+//! in brokerage versus statutory levies. Built-in schedules cover Zerodha
+//! (NSE/BSE equity and F&O) and Binance spot VIP tiers; `FlatCommission`
+//! keeps the single-rate behaviour of `BacktestConfig::commission_rate`.
+
+use crate::OrderSide;
+use serde::{Deserialize, Serialize};
+use std::ops::AddAssign;
+use std::sync::Arc;
+
+/// Whether a fill added or removed liquidity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Liquidity {
+    /// Resting order filled by another participant
+    Maker,
+    /// Order that crossed the spread
+    Taker,
+}
+
+/// Trading costs of one or more fills, split by component
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct CostBreakdown {
+    /// Broker or venue trading fee
+    pub brokerage: f64,
+    /// Securities transaction tax (STT/CTT)
+    pub transaction_tax: f64,
+    /// Exchange transaction charges
+    pub exchange_charges: f64,
+    /// Regulator turnover fees (SEBI)
+    pub regulatory_fees: f64,
+    /// Stamp duty
+    pub stamp_duty: f64,
+    /// GST on brokerage, exchange and regulatory charges
+    pub gst: f64,
+}
+
+impl CostBreakdown {
+    /// Brokerage-only breakdown, used for flat-rate commissions
+    pub fn brokerage_only(brokerage: f64) -> Self {
+        Self { brokerage, ..Self::default() }
+    }
+
+    /// Sum of all components
+    pub fn total(&self) -> f64 {
+        self.brokerage
+            + self.transaction_tax
+            + self.exchange_charges
+            + self.regulatory_fees
+            + self.stamp_duty
+            + self.gst
+    }
+}
+
+impl AddAssign for CostBreakdown {
+    fn add_assign(&mut self, other: Self) {
+        self.brokerage += other.brokerage;
+        self.transaction_tax += other.transaction_tax;
+        self.exchange_charges += other.exchange_charges;
+        self.regulatory_fees += other.regulatory_fees;
+        self.stamp_duty += other.stamp_duty;
+        self.gst += other.gst;
+    }
+}
+
+/// Details of a fill needed to price its costs
+#[derive(Debug, Clone, Copy)]
+pub struct FillContext<'a> {
+    /// Instrument traded
+    pub symbol: &'a str,
+    /// Direction of the fill
+    pub side: OrderSide,
+    /// Quantity filled
+    pub quantity: f64,
+    /// Fill price (the premium for options)
+    pub price: f64,
+    /// Whether the fill added or removed liquidity
+    pub liquidity: Liquidity,
+    /// Brokerage already charged on earlier fills of the same order
+    ///
+    /// Lets per-order caps and flat fees apply once across partial fills.
+    pub order_brokerage_paid: f64,
+}
+
+impl FillContext<'_> {
+    /// Traded value of the fill
+    pub fn notional(&self) -> f64 {
+        self.price * self.quantity
+    }
+}
+
+/// Pricing of trading costs for fills
+pub trait CommissionModel: Send + Sync + std::fmt::Debug {
+    /// Returns the costs charged for one fill
+    fn calculate(&self, fill: &FillContext<'_>) -> CostBreakdown;
+}
+
+/// Single commission rate on traded notional
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlatCommission {
+    /// Commission as a decimal of notional (e.g., 0.001 = 0.1%)
+    pub rate: f64,
+}
+
+impl CommissionModel for FlatCommission {
+    fn calculate(&self, fill: &FillContext<'_>) -> CostBreakdown {
+        CostBreakdown::brokerage_only(self.rate * fill.notional())
+    }
+}
+
+/// Indian exchange an order is routed to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndianExchange {
+    /// National Stock Exchange
+    #[default]
+    Nse,
+    /// Bombay Stock Exchange
+    Bse,
+}
+
+/// Product segment, which decides which Zerodha schedule applies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ZerodhaSegment {
+    /// Equity held overnight (CNC)
+    EquityDelivery,
+    /// Equity squared off the same day (MIS)
+    EquityIntraday,
+    /// Index and stock futures
+    Futures,
+    /// Index and stock options, priced on premium
+    Options,
+}
+
+/// Zerodha charges for NSE/BSE, per the schedule effective 1 October 2024
+///
+/// All rates are decimals of turnover unless noted. Each fill is priced on
+/// its own turnover; STT and stamp duty are charged on the sell and buy
+/// side respectively as on the contract note.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZerodhaCharges {
+    /// Product segment of the orders
+    pub segment: ZerodhaSegment,
+    /// Exchange the orders are routed to
+    pub exchange: IndianExchange,
+}
+
+/// Maximum brokerage per executed order, in rupees
+const ZERODHA_BROKERAGE_CAP: f64 = 20.0;
+/// Intraday and futures brokerage before the per-order cap
+const ZERODHA_BROKERAGE_RATE: f64 = 0.0003;
+/// SEBI turnover fee: ₹10 per crore
+const SEBI_TURNOVER_FEE: f64 = 10.0 / 10_000_000.0;
+/// GST on brokerage, exchange charges and SEBI fees
+const GST_RATE: f64 = 0.18;
+
+impl ZerodhaCharges {
+    /// Charges for `segment` on the NSE
+    pub fn new(segment: ZerodhaSegment) -> Self {
+        Self { segment, exchange: IndianExchange::Nse }
+    }
+
+    /// Routes the orders to `exchange` instead
+    pub fn on_exchange(mut self, exchange: IndianExchange) -> Self {
+        self.exchange = exchange;
+        self
+    }
+
+    fn exchange_rate(&self) -> f64 {
+        match (self.segment, self.exchange) {
+            (ZerodhaSegment::EquityDelivery | ZerodhaSegment::EquityIntraday, IndianExchange::Nse) => 0.000_029_7,
+            (ZerodhaSegment::EquityDelivery | ZerodhaSegment::EquityIntraday, IndianExchange::Bse) => 0.000_037_5,
+            (ZerodhaSegment::Futures, IndianExchange::Nse) => 0.000_017_3,
+            (ZerodhaSegment::Futures, IndianExchange::Bse) => 0.0,
+            (ZerodhaSegment::Options, IndianExchange::Nse) => 0.000_350_3,
+            (ZerodhaSegment::Options, IndianExchange::Bse) => 0.000_325,
+        }
+    }
+
+    /// STT rate for a buy and a sell
+    fn stt_rates(&self) -> (f64, f64) {
+        match self.segment {
+            ZerodhaSegment::EquityDelivery => (0.001, 0.001),
+            ZerodhaSegment::EquityIntraday => (0.0, 0.000_25),
+            ZerodhaSegment::Futures => (0.0, 0.0002),
+            ZerodhaSegment::Options => (0.0, 0.001),
+        }
+    }
+
+    /// Stamp duty rate on buys
+    fn stamp_duty_rate(&self) -> f64 {
+        match self.segment {
+            ZerodhaSegment::EquityDelivery => 0.000_15,
+            ZerodhaSegment::EquityIntraday | ZerodhaSegment::Options => 0.000_03,
+            ZerodhaSegment::Futures => 0.000_02,
+        }
+    }
+
+    fn brokerage(&self, fill: &FillContext<'_>) -> f64 {
+        let remaining_cap = (ZERODHA_BROKERAGE_CAP - fill.order_brokerage_paid).max(0.0);
+        match self.segment {
+            ZerodhaSegment::EquityDelivery => 0.0,
+            ZerodhaSegment::EquityIntraday | ZerodhaSegment::Futures => {
+                (ZERODHA_BROKERAGE_RATE * fill.notional()).min(remaining_cap)
+            }
+            ZerodhaSegment::Options => remaining_cap,
+        }
+    }
+}
+
+impl CommissionModel for ZerodhaCharges {
+    fn calculate(&self, fill: &FillContext<'_>) -> CostBreakdown {
+        let turnover = fill.notional();
+        let (stt_buy, stt_sell) = self.stt_rates();
+        let (transaction_tax, stamp_duty) = match fill.side {
+            OrderSide::Buy => (stt_buy * turnover, self.stamp_duty_rate() * turnover),
+            OrderSide::Sell => (stt_sell * turnover, 0.0),
+        };
+        let brokerage = self.brokerage(fill);
+        let exchange_charges = self.exchange_rate() * turnover;
+        let regulatory_fees = SEBI_TURNOVER_FEE * turnover;
+
+        CostBreakdown {
+            brokerage,
+            transaction_tax,
+            exchange_charges,
+            regulatory_fees,
+            stamp_duty,
+            gst: GST_RATE * (brokerage + exchange_charges + regulatory_fees),
+        }
+    }
+}
+
+/// Binance spot maker/taker rates by VIP level, as decimals
+const BINANCE_SPOT_TIERS: [(f64, f64); 10] = [
+    (0.001, 0.001),
+    (0.0009, 0.001),
+    (0.0008, 0.001),
+    (0.000_42, 0.0006),
+    (0.000_42, 0.000_54),
+    (0.000_36, 0.000_48),
+    (0.0003, 0.000_42),
+    (0.000_24, 0.000_36),
+    (0.000_18, 0.0003),
+    (0.000_12, 0.000_24),
+];
+
+/// Discount on trading fees when they are paid in BNB
+const BNB_FEE_DISCOUNT: f64 = 0.25;
+
+/// Binance spot maker/taker fee schedule
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BinanceFees {
+    /// Fee rate for fills that add liquidity
+    pub maker_rate: f64,
+    /// Fee rate for fills that remove liquidity
+    pub taker_rate: f64,
+}
+
+impl BinanceFees {
+    /// Spot rates for a VIP level (0 = regular user); levels above 9 use VIP 9
+    pub fn vip(level: u8) -> Self {
+        let index = usize::from(level).min(BINANCE_SPOT_TIERS.len() - 1);
+        let (maker_rate, taker_rate) = BINANCE_SPOT_TIERS.get(index).copied().unwrap_or((0.001, 0.001));
+        Self { maker_rate, taker_rate }
+    }
+
+    /// Applies the discount for paying fees in BNB
+    pub fn with_bnb_discount(self) -> Self {
+        Self {
+            maker_rate: self.maker_rate * (1.0 - BNB_FEE_DISCOUNT),
+            taker_rate: self.taker_rate * (1.0 - BNB_FEE_DISCOUNT),
+        }
+    }
+}
+
+impl CommissionModel for BinanceFees {
+    fn calculate(&self, fill: &FillContext<'_>) -> CostBreakdown {
+        let rate = match fill.liquidity {
+            Liquidity::Maker => self.maker_rate,
+            Liquidity::Taker => self.taker_rate,
+        };
+        CostBreakdown::brokerage_only(rate * fill.notional())
+    }
+}
+
+/// Commission schedule selected in `BacktestConfig`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CommissionSchedule {
+    /// `BacktestConfig::commission_rate` on traded notional
+    #[default]
+    Flat,
+    /// Zerodha charges for a segment and exchange
+    Zerodha {
+        /// Product segment of the orders
+        segment: ZerodhaSegment,
+        /// Exchange the orders are routed to
+        #[serde(default)]
+        exchange: IndianExchange,
+    },
+    /// Binance spot VIP tier
+    Binance {
+        /// VIP level, 0 for regular users
+        #[serde(default)]
+        vip_level: u8,
+        /// Whether fees are paid in BNB
+        #[serde(default)]
+        bnb_discount: bool,
+    },
+}
+
+impl CommissionSchedule {
+    /// Instantiates the model; `flat_rate` is used by `Flat`
+    pub fn build(&self, flat_rate: f64) -> Arc<dyn CommissionModel> {
+        match *self {
+            Self::Flat => Arc::new(FlatCommission { rate: flat_rate }),
+            Self::Zerodha { segment, exchange } => Arc::new(ZerodhaCharges::new(segment).on_exchange(exchange)),
+            Self::Binance { vip_level, bnb_discount } => {
+                let fees = BinanceFees::vip(vip_level);
+                Arc::new(if bnb_discount { fees.with_bnb_discount() } else { fees })
+            }
+        }
+    }
+}
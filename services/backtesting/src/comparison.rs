@@ -0,0 +1,130 @@
+//! Side-by-side comparison of backtest runs
+//!
+//! `RunComparison` lines up the headline metrics of several runs, showing
+//! each run's difference from the first (the baseline) and which run did
+//! best, and rescales their equity curves to a common starting value of 1.0
+//! so runs with different capital can be overlaid on one chart.
+
+use crate::run_store::RunRecord;
+use crate::PerformanceMetrics;
+use anyhow::{Result, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Reads one metric from a run's results
+type MetricValue = fn(&PerformanceMetrics) -> f64;
+
+/// Metrics compared across runs, and whether higher values are better
+///
+/// `None` marks metrics without a better direction, such as trade counts.
+const COMPARED_METRICS: [(&str, Option<bool>, MetricValue); 13] = [
+    ("total_return", Some(true), |m| m.total_return),
+    ("annualized_return", Some(true), |m| m.annualized_return),
+    ("volatility", Some(false), |m| m.volatility),
+    ("sharpe_ratio", Some(true), |m| m.sharpe_ratio),
+    ("sortino_ratio", Some(true), |m| m.sortino_ratio),
+    ("calmar_ratio", Some(true), |m| m.calmar_ratio),
+    ("max_drawdown", Some(false), |m| m.max_drawdown),
+    ("max_drawdown_duration_days", Some(false), |m| m.max_drawdown_duration as f64),
+    ("total_trades", None, |m| m.total_trades as f64),
+    ("win_rate", Some(true), |m| m.win_rate),
+    ("profit_factor", Some(true), |m| m.profit_factor),
+    ("expectancy", Some(true), |m| m.expectancy),
+    ("total_commission", Some(false), |m| m.total_commission),
+];
+
+/// One metric across the compared runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricComparison {
+    /// Metric name, as in `PerformanceMetrics`
+    pub name: String,
+    /// Value of each run, in comparison order
+    pub values: Vec<f64>,
+    /// Each run's value minus the baseline's
+    pub differences: Vec<f64>,
+    /// Index of the run with the best value, for metrics with a better direction
+    pub best: Option<usize>,
+}
+
+/// Equity curve of one run, rescaled to start at 1.0
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EquityOverlay {
+    /// Run the curve belongs to
+    pub backtest_id: String,
+    /// Growth of one unit of starting equity over time
+    pub points: Vec<(DateTime<Utc>, f64)>,
+}
+
+/// Metrics and equity curves of several runs, against the first as baseline
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct RunComparison {
+    /// Compared runs, baseline first
+    pub backtest_ids: Vec<String>,
+    /// Headline metrics of every run
+    pub metrics: Vec<MetricComparison>,
+    /// Rescaled equity curve of every run
+    pub equity_curves: Vec<EquityOverlay>,
+}
+
+impl RunComparison {
+    /// Compares `runs`, using the first as the baseline
+    ///
+    /// Fails unless every run completed with results.
+    pub fn of(runs: &[RunRecord]) -> Result<Self> {
+        if runs.is_empty() {
+            bail!("At least one run is required for a comparison");
+        }
+        let mut results = Vec::with_capacity(runs.len());
+        for run in runs {
+            let Some(result) = run.result.as_ref() else {
+                bail!("Backtest {} has no results (status: {})", run.backtest_id, run.status.as_str());
+            };
+            results.push(result);
+        }
+
+        let metrics = COMPARED_METRICS.iter()
+            .map(|(name, higher_is_better, value)| {
+                let values: Vec<f64> = results.iter().map(|result| value(&result.metrics)).collect();
+                let differences = values.iter().map(|v| v - values[0]).collect();
+                let best = higher_is_better.and_then(|higher| {
+                    values.iter()
+                        .enumerate()
+                        .filter(|(_, v)| v.is_finite())
+                        .max_by(|(_, a), (_, b)| {
+                            let order = a.total_cmp(b);
+                            if higher { order } else { order.reverse() }
+                        })
+                        .map(|(index, _)| index)
+                });
+                MetricComparison { name: (*name).to_string(), values, differences, best }
+            })
+            .collect();
+
+        let equity_curves = runs.iter()
+            .zip(&results)
+            .map(|(run, result)| EquityOverlay {
+                backtest_id: run.backtest_id.clone(),
+                points: rebased(&result.equity_curve),
+            })
+            .collect();
+
+        Ok(Self {
+            backtest_ids: runs.iter().map(|run| run.backtest_id.clone()).collect(),
+            metrics,
+            equity_curves,
+        })
+    }
+
+    /// Comparison of the metric called `name`
+    pub fn metric(&self, name: &str) -> Option<&MetricComparison> {
+        self.metrics.iter().find(|metric| metric.name == name)
+    }
+}
+
+/// `equity_curve` divided by its first positive value
+fn rebased(equity_curve: &[(DateTime<Utc>, f64)]) -> Vec<(DateTime<Utc>, f64)> {
+    let Some(base) = equity_curve.iter().map(|(_, value)| *value).find(|value| *value > 0.0) else {
+        return Vec::new();
+    };
+    equity_curve.iter().map(|(timestamp, value)| (*timestamp, value / base)).collect()
+}
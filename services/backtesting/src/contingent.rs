@@ -0,0 +1,343 @@
+//! Contingent orders and intrabar trigger ordering
+//!
+//! `ExecutionSimulator` handles three kinds of linked orders:
+//!
+//! * **Bracket** - an entry with a stop-loss and a take-profit attached.
+//!   The exits are armed for the filled quantity as the entry fills and form
+//!   an OCO pair; a fill on either exit cancels what is left of the entry.
+//! * **OCO** - two orders where a fill on one leg cancels the same quantity
+//!   of the other, so legs of equal size cancel each other outright.
+//! * **Trailing stop** - a stop order whose stop price follows the best
+//!   price seen since it was submitted and never moves back.
+//!
+//! Only fills propagate along links. A leg that is cancelled, rejected or
+//! expires leaves its sibling working as a standalone order, and cancelling
+//! a bracket entry before it fills discards its exits. Siblings cancelled by
+//! a fill are reported as `RejectReason::LinkedOrderFilled`.
+//!
+//! With bar data the simulator cannot see the order in which prices traded
+//! within a bar. Orders working before a bar started are walked along a
+//! path from its open through both extremes to its close, chosen by
+//! `IntrabarOrdering`, so a stop-loss and take-profit inside the same bar
+//! resolve the same way every run. Prices between path points are assumed
+//! to trade continuously: a stop triggered at the open fills at the open,
+//! one triggered later fills at its stop price.
+
+use crate::{Order, OrderSide, OrderType, OHLCV};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Order in which bar extremes are assumed to have traded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum IntrabarOrdering {
+    /// Each order sees the extreme that goes against it first
+    ///
+    /// Sell orders see the low before the high and buy orders the high
+    /// before the low, so protective stops trigger before take-profits and
+    /// trailing stops trigger before they ratchet.
+    #[default]
+    Pessimistic,
+    /// Each order sees the extreme in its favour first
+    Optimistic,
+    /// The extreme nearer the open trades first, the low on a tie
+    NearestExtreme,
+    /// Open, high, low, close for every bar
+    HighFirst,
+    /// Open, low, high, close for every bar
+    LowFirst,
+}
+
+impl IntrabarOrdering {
+    /// Prices an order on `side` is assumed to see within `bar`, in order
+    pub fn path(self, bar: &OHLCV, side: OrderSide) -> [f64; 4] {
+        let high_first = match self {
+            Self::Pessimistic => side == OrderSide::Buy,
+            Self::Optimistic => side == OrderSide::Sell,
+            Self::NearestExtreme => bar.high - bar.open < bar.open - bar.low,
+            Self::HighFirst => true,
+            Self::LowFirst => false,
+        };
+        if high_first {
+            [bar.open, bar.high, bar.low, bar.close]
+        } else {
+            [bar.open, bar.low, bar.high, bar.close]
+        }
+    }
+}
+
+/// Distance a trailing stop keeps from the best price seen
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Trail {
+    /// Fixed distance in price units
+    Amount(f64),
+    /// Fraction of the best price (e.g., 0.02 = 2%)
+    Fraction(f64),
+}
+
+impl Trail {
+    /// Stop price for a stop on `side` when the best price seen is `best`
+    pub fn stop_price(self, side: OrderSide, best: f64) -> f64 {
+        let distance = match self {
+            Self::Amount(amount) => amount,
+            Self::Fraction(fraction) => best * fraction,
+        };
+        match side {
+            OrderSide::Sell => best - distance,
+            OrderSide::Buy => best + distance,
+        }
+    }
+
+    fn validate(self) -> Result<()> {
+        match self {
+            Self::Amount(amount) if !(amount.is_finite() && amount > 0.0) => {
+                anyhow::bail!("Trailing amount must be positive, got {}", amount)
+            }
+            Self::Fraction(fraction) if !(fraction > 0.0 && fraction < 1.0) => {
+                anyhow::bail!("Trailing fraction must be between 0 and 1, got {}", fraction)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Linked orders submitted together
+#[derive(Debug, Clone)]
+pub enum ContingentOrder {
+    /// Entry whose fills arm a stop-loss and a take-profit
+    ///
+    /// The exits' quantities are set from the entry's fills.
+    Bracket {
+        /// Order opening the position
+        entry: Order,
+        /// Stop order on the opposite side
+        stop_loss: Order,
+        /// Limit order on the opposite side
+        take_profit: Order,
+    },
+    /// Two orders where a fill on one cancels the same quantity of the other
+    Oco {
+        /// First leg
+        first: Order,
+        /// Second leg
+        second: Order,
+    },
+    /// Stop order that follows the market by `trail`
+    ///
+    /// A stop price set on the order is the starting stop; otherwise the
+    /// stop is placed `trail` away from the first price seen.
+    TrailingStop {
+        /// The stop order
+        order: Order,
+        /// Distance kept from the best price seen
+        trail: Trail,
+    },
+}
+
+impl ContingentOrder {
+    /// Every order in the group, including bracket exits not yet armed
+    pub fn orders(&self) -> Vec<&Order> {
+        match self {
+            Self::Bracket { entry, stop_loss, take_profit } => vec![entry, stop_loss, take_profit],
+            Self::Oco { first, second } => vec![first, second],
+            Self::TrailingStop { order, .. } => vec![order],
+        }
+    }
+
+    /// Orders that start working as soon as the group is submitted
+    pub fn working_orders(&self) -> Vec<&Order> {
+        match self {
+            Self::Bracket { entry, .. } => vec![entry],
+            other => other.orders(),
+        }
+    }
+
+    /// Checks that the orders can be linked as requested
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            Self::Bracket { entry, stop_loss, take_profit } => {
+                for exit in [stop_loss, take_profit] {
+                    if exit.symbol != entry.symbol || exit.side == entry.side {
+                        anyhow::bail!("Bracket exit {} must close entry {}", exit.id, entry.id);
+                    }
+                }
+                let (Some(stop), Some(target)) = (stop_loss.stop_price, take_profit.price) else {
+                    anyhow::bail!("Bracket {} needs a stop-loss and a take-profit price", entry.id);
+                };
+                let ordered = match entry.side {
+                    OrderSide::Buy => stop < target,
+                    OrderSide::Sell => stop > target,
+                };
+                if !ordered {
+                    anyhow::bail!("Bracket {} stop-loss {} is not on the losing side of take-profit {}", entry.id, stop, target);
+                }
+                Ok(())
+            }
+            Self::Oco { first, second } => {
+                if first.symbol != second.symbol {
+                    anyhow::bail!("OCO legs {} and {} trade different symbols", first.id, second.id);
+                }
+                Ok(())
+            }
+            Self::TrailingStop { order, trail } => {
+                if order.order_type != OrderType::Stop {
+                    anyhow::bail!("Trailing order {} must be a stop order", order.id);
+                }
+                trail.validate()
+            }
+        }
+    }
+}
+
+/// IDs of the orders making up a bracket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BracketIds {
+    /// Entry order
+    pub entry: String,
+    /// Stop-loss exit
+    pub stop_loss: String,
+    /// Take-profit exit
+    pub take_profit: String,
+}
+
+/// Exits of a bracket whose entry is still working
+#[derive(Debug, Clone)]
+struct BracketExits {
+    stop_loss: Order,
+    take_profit: Order,
+    armed: bool,
+}
+
+/// Trailing rule of a stop and the best price it has seen
+#[derive(Debug, Clone, Copy)]
+struct TrailState {
+    trail: Trail,
+    best: Option<f64>,
+}
+
+/// What a fill changes in linked orders
+#[derive(Debug, Default)]
+pub(crate) struct LinkedEffects {
+    /// Exits to put on the book
+    pub arm: Vec<Order>,
+    /// Orders whose quantity grows by the filled quantity
+    pub grow: Vec<String>,
+    /// Orders whose quantity shrinks by the filled quantity
+    pub shrink: Vec<String>,
+    /// Orders to cancel outright
+    pub cancel: Vec<String>,
+}
+
+/// Links between the orders the simulator is working
+#[derive(Debug, Default)]
+pub(crate) struct ContingencyBook {
+    /// Exits by bracket entry ID, while the entry is working
+    brackets: HashMap<String, BracketExits>,
+    /// Bracket entry ID by exit ID
+    entries: HashMap<String, String>,
+    /// Other leg of each OCO leg
+    siblings: HashMap<String, String>,
+    /// Trailing stops by order ID
+    trails: HashMap<String, TrailState>,
+}
+
+impl ContingencyBook {
+    /// Records the links of a group and returns the orders to work now
+    pub fn add(&mut self, group: ContingentOrder) -> Vec<Order> {
+        match group {
+            ContingentOrder::Bracket { entry, stop_loss, take_profit } => {
+                self.entries.insert(stop_loss.id.clone(), entry.id.clone());
+                self.entries.insert(take_profit.id.clone(), entry.id.clone());
+                self.brackets.insert(entry.id.clone(), BracketExits { stop_loss, take_profit, armed: false });
+                vec![entry]
+            }
+            ContingentOrder::Oco { first, second } => {
+                self.link(&first.id, &second.id);
+                vec![first, second]
+            }
+            ContingentOrder::TrailingStop { order, trail } => {
+                self.trails.insert(order.id.clone(), TrailState { trail, best: None });
+                vec![order]
+            }
+        }
+    }
+
+    fn link(&mut self, first: &str, second: &str) {
+        self.siblings.insert(first.to_string(), second.to_string());
+        self.siblings.insert(second.to_string(), first.to_string());
+    }
+
+    /// Effects of `quantity` of `order` filling at `timestamp`
+    pub fn on_fill(&mut self, order: &Order, quantity: f64, timestamp: DateTime<Utc>) -> LinkedEffects {
+        let mut effects = LinkedEffects::default();
+        if let Some(sibling) = self.siblings.get(&order.id) {
+            effects.shrink.push(sibling.clone());
+        }
+        if let Some(entry) = self.entries.get(&order.id) {
+            effects.cancel.push(entry.clone());
+        }
+        if let Some(exits) = self.brackets.get_mut(&order.id) {
+            if exits.armed {
+                effects.grow.push(exits.stop_loss.id.clone());
+                effects.grow.push(exits.take_profit.id.clone());
+            } else {
+                exits.armed = true;
+                for exit in [&mut exits.stop_loss, &mut exits.take_profit] {
+                    exit.quantity = quantity;
+                    exit.timestamp = timestamp;
+                    effects.arm.push(exit.clone());
+                }
+                let (stop_loss, take_profit) = (exits.stop_loss.id.clone(), exits.take_profit.id.clone());
+                self.link(&stop_loss, &take_profit);
+            }
+        }
+        effects
+    }
+
+    /// Exits of the bracket entered by `order_id`, armed or not
+    pub fn exits(&self, order_id: &str) -> Option<[String; 2]> {
+        self.brackets
+            .get(order_id)
+            .map(|exits| [exits.stop_loss.id.clone(), exits.take_profit.id.clone()])
+    }
+
+    /// Other leg of an OCO leg
+    pub fn sibling(&self, order_id: &str) -> Option<&String> {
+        self.siblings.get(order_id)
+    }
+
+    /// Moves a trailing stop with a new `price`, returning its stop price
+    ///
+    /// Returns `None` for orders that do not trail.
+    pub fn trail(&mut self, order: &Order, price: f64) -> Option<f64> {
+        let state = self.trails.get_mut(&order.id)?;
+        let best = match (state.best, order.side) {
+            (None, _) => price,
+            (Some(best), OrderSide::Sell) => best.max(price),
+            (Some(best), OrderSide::Buy) => best.min(price),
+        };
+        state.best = Some(best);
+        let trailed = state.trail.stop_price(order.side, best);
+        Some(match (order.stop_price, order.side) {
+            (None, _) => trailed,
+            (Some(stop), OrderSide::Sell) => stop.max(trailed),
+            (Some(stop), OrderSide::Buy) => stop.min(trailed),
+        })
+    }
+
+    /// Drops every link of an order that has left the book
+    pub fn forget(&mut self, order_id: &str) {
+        self.trails.remove(order_id);
+        if let Some(sibling) = self.siblings.remove(order_id) {
+            self.siblings.remove(&sibling);
+        }
+        self.entries.remove(order_id);
+        if let Some(exits) = self.brackets.remove(order_id) {
+            if !exits.armed {
+                self.entries.remove(&exits.stop_loss.id);
+                self.entries.remove(&exits.take_profit.id);
+            }
+        }
+    }
+}
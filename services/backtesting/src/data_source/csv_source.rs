@@ -0,0 +1,236 @@
+//! Bars from CSV files
+//!
+//! Each row is one OHLCV bar. Column names are configurable, so exports from
+//! brokers and data vendors load without rewriting them, and timestamps
+//! without an offset are read in a configured fixed UTC offset (e.g. IST,
+//! `+05:30`) and converted to UTC.
+
+use super::MarketDataSource;
+use crate::OHLCV;
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Layouts tried for naive timestamps when no `timestamp_format` is set
+const NAIVE_FORMATS: [&str; 3] = ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M"];
+
+/// Epoch values at or above this are read as milliseconds
+const EPOCH_MILLIS_THRESHOLD: i64 = 100_000_000_000;
+
+/// Header names of the CSV columns
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CsvColumns {
+    /// Bar timestamp
+    pub timestamp: String,
+    /// Opening price
+    pub open: String,
+    /// Highest price
+    pub high: String,
+    /// Lowest price
+    pub low: String,
+    /// Closing price
+    pub close: String,
+    /// Traded volume; bars get zero volume when the column is absent
+    pub volume: String,
+    /// Symbol of each row, for files holding several symbols
+    pub symbol: Option<String>,
+}
+
+impl Default for CsvColumns {
+    fn default() -> Self {
+        Self {
+            timestamp: "timestamp".to_string(),
+            open: "open".to_string(),
+            high: "high".to_string(),
+            low: "low".to_string(),
+            close: "close".to_string(),
+            volume: "volume".to_string(),
+            symbol: None,
+        }
+    }
+}
+
+/// Location and layout of CSV bar files
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CsvSourceConfig {
+    /// File path; `{symbol}` is replaced by the symbol being loaded
+    pub path: String,
+    /// Field delimiter
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+    /// Column names
+    #[serde(default)]
+    pub columns: CsvColumns,
+    /// chrono format of the timestamp column
+    ///
+    /// When unset, RFC 3339, `YYYY-MM-DD[ HH:MM[:SS]]` and Unix epoch
+    /// seconds or milliseconds are accepted.
+    #[serde(default)]
+    pub timestamp_format: Option<String>,
+    /// Offset of timestamps that carry none, e.g. `+05:30`; defaults to UTC
+    #[serde(default)]
+    pub utc_offset: Option<String>,
+}
+
+fn default_delimiter() -> char {
+    ','
+}
+
+/// Data source reading OHLCV bars from CSV files
+#[derive(Debug, Clone)]
+pub struct CsvDataSource {
+    config: CsvSourceConfig,
+    offset: FixedOffset,
+    delimiter: u8,
+}
+
+impl CsvDataSource {
+    /// Creates a source for `config`, validating its delimiter and offset
+    pub fn new(config: CsvSourceConfig) -> Result<Self> {
+        let delimiter = u8::try_from(config.delimiter)
+            .ok()
+            .filter(u8::is_ascii)
+            .with_context(|| format!("CSV delimiter {:?} must be a single ASCII character", config.delimiter))?;
+        let offset = match config.utc_offset.as_deref() {
+            None => FixedOffset::east_opt(0).context("UTC offset out of range")?,
+            Some(offset) => parse_offset(offset)?,
+        };
+        Ok(Self { config, offset, delimiter })
+    }
+
+    fn path_for(&self, symbol: &str) -> PathBuf {
+        PathBuf::from(self.config.path.replace("{symbol}", symbol))
+    }
+
+    /// Parses a timestamp field to UTC
+    fn parse_timestamp(&self, value: &str) -> Result<DateTime<Utc>> {
+        let value = value.trim();
+        if let Some(format) = &self.config.timestamp_format {
+            if let Ok(timestamp) = DateTime::parse_from_str(value, format) {
+                return Ok(timestamp.with_timezone(&Utc));
+            }
+            if let Ok(naive) = NaiveDateTime::parse_from_str(value, format) {
+                return self.localize(naive);
+            }
+            let date = NaiveDate::parse_from_str(value, format)
+                .with_context(|| format!("Timestamp {:?} does not match format {:?}", value, format))?;
+            return self.localize(date.and_hms_opt(0, 0, 0).context("Invalid midnight")?);
+        }
+
+        if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+            return Ok(timestamp.with_timezone(&Utc));
+        }
+        if let Ok(epoch) = value.parse::<i64>() {
+            let timestamp = if epoch.abs() >= EPOCH_MILLIS_THRESHOLD {
+                DateTime::from_timestamp_millis(epoch)
+            } else {
+                DateTime::from_timestamp(epoch, 0)
+            };
+            return timestamp.with_context(|| format!("Epoch timestamp {} out of range", epoch));
+        }
+        for format in NAIVE_FORMATS {
+            if let Ok(naive) = NaiveDateTime::parse_from_str(value, format) {
+                return self.localize(naive);
+            }
+        }
+        let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .with_context(|| format!("Unrecognised timestamp {:?}", value))?;
+        self.localize(date.and_hms_opt(0, 0, 0).context("Invalid midnight")?)
+    }
+
+    /// Reads a naive timestamp in the configured offset
+    fn localize(&self, naive: NaiveDateTime) -> Result<DateTime<Utc>> {
+        self.offset
+            .from_local_datetime(&naive)
+            .single()
+            .map(|timestamp| timestamp.with_timezone(&Utc))
+            .with_context(|| format!("Timestamp {} cannot be placed in offset {}", naive, self.offset))
+    }
+}
+
+impl MarketDataSource for CsvDataSource {
+    fn load_ohlcv(
+        &self,
+        symbol: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<(DateTime<Utc>, OHLCV)>> {
+        let path = self.path_for(symbol);
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .trim(csv::Trim::All)
+            .from_path(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+
+        let headers = reader.headers()?.clone();
+        let column = |name: &str| headers.iter().position(|header| header == name);
+        let required = |name: &str| {
+            column(name).with_context(|| format!("Column {:?} not found in {}", name, path.display()))
+        };
+        let columns = &self.config.columns;
+        let timestamp_col = required(&columns.timestamp)?;
+        let open_col = required(&columns.open)?;
+        let high_col = required(&columns.high)?;
+        let low_col = required(&columns.low)?;
+        let close_col = required(&columns.close)?;
+        let volume_col = column(&columns.volume);
+        let symbol_col = columns.symbol.as_deref().map(required).transpose()?;
+
+        let mut bars = Vec::new();
+        for (index, record) in reader.records().enumerate() {
+            // Header is line 1
+            let line = index + 2;
+            let record = record.with_context(|| format!("{}:{}: malformed row", path.display(), line))?;
+            if symbol_col.is_some_and(|col| record.get(col) != Some(symbol)) {
+                continue;
+            }
+
+            let field = |col: usize| record.get(col).unwrap_or_default();
+            let number = |col: usize| {
+                field(col)
+                    .parse::<f64>()
+                    .with_context(|| format!("{}:{}: invalid number {:?}", path.display(), line, field(col)))
+            };
+            let timestamp = self
+                .parse_timestamp(field(timestamp_col))
+                .with_context(|| format!("{}:{}", path.display(), line))?;
+            if timestamp < start || timestamp > end {
+                continue;
+            }
+
+            bars.push((timestamp, OHLCV {
+                open: number(open_col)?,
+                high: number(high_col)?,
+                low: number(low_col)?,
+                close: number(close_col)?,
+                volume: volume_col.map(number).transpose()?.unwrap_or(0.0),
+            }));
+        }
+
+        if bars.is_empty() {
+            bail!("No rows for {} between {} and {} in {}", symbol, start, end, path.display());
+        }
+        bars.sort_by_key(|(timestamp, _)| *timestamp);
+        Ok(bars)
+    }
+}
+
+/// Parses `UTC`, `Z` or a `+HH:MM` / `-HH:MM` offset
+fn parse_offset(offset: &str) -> Result<FixedOffset> {
+    let offset = offset.trim();
+    if offset.eq_ignore_ascii_case("utc") || offset == "Z" {
+        return FixedOffset::east_opt(0).context("UTC offset out of range");
+    }
+    let (sign, rest) = match offset.split_at_checked(1) {
+        Some(("+", rest)) => (1, rest),
+        Some(("-", rest)) => (-1, rest),
+        _ => bail!("UTC offset {:?} must look like +05:30 (named time zones are not supported)", offset),
+    };
+    let (hours, minutes) = rest.split_once(':').unwrap_or((rest, "0"));
+    let hours: i32 = hours.parse().with_context(|| format!("Invalid UTC offset {:?}", offset))?;
+    let minutes: i32 = minutes.parse().with_context(|| format!("Invalid UTC offset {:?}", offset))?;
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+        .with_context(|| format!("UTC offset {:?} out of range", offset))
+}
@@ -0,0 +1,111 @@
+//! Historical data sources for backtests
+//!
+//! The gRPC service does not know where bars live; it asks a
+//! `MarketDataSource` for each requested symbol and loads the result into
+//! the `BacktestEngine` before the run starts. A run can name its own source
+//! through `BacktestConfig::data_source`, e.g. the data-aggregator WAL or a
+//! directory of CSV files.
+
+pub mod csv_source;
+pub mod wal_source;
+
+pub use csv_source::{CsvColumns, CsvDataSource, CsvSourceConfig};
+pub use wal_source::{WalDataSource, WalRecords};
+
+use crate::OHLCV;
+use anyhow::{Result, bail};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Provider of historical OHLCV bars for a symbol and date range
+pub trait MarketDataSource: Send + Sync + std::fmt::Debug {
+    /// Loads bars for `symbol` with timestamps in `[start, end]`, in ascending order
+    ///
+    /// Returns an error if the source has no data for the symbol.
+    fn load_ohlcv(
+        &self,
+        symbol: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<(DateTime<Utc>, OHLCV)>>;
+}
+
+/// Data source backed by bars held in memory
+///
+/// Used by the service when no persistent source is configured and by tests.
+#[derive(Debug, Default)]
+pub struct InMemoryDataSource {
+    bars: DashMap<String, Vec<(DateTime<Utc>, OHLCV)>>,
+}
+
+impl InMemoryDataSource {
+    /// Creates an empty in-memory data source
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the bars stored for `symbol`
+    pub fn insert(&self, symbol: &str, mut bars: Vec<(DateTime<Utc>, OHLCV)>) {
+        bars.sort_by_key(|(timestamp, _)| *timestamp);
+        self.bars.insert(symbol.to_string(), bars);
+    }
+}
+
+impl MarketDataSource for InMemoryDataSource {
+    fn load_ohlcv(
+        &self,
+        symbol: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<(DateTime<Utc>, OHLCV)>> {
+        let Some(bars) = self.bars.get(symbol) else {
+            bail!("No market data available for {}", symbol);
+        };
+
+        Ok(bars.iter()
+            .filter(|(timestamp, _)| *timestamp >= start && *timestamp <= end)
+            .cloned()
+            .collect())
+    }
+}
+
+/// Data source selected in `BacktestConfig::data_source`
+///
+/// Tagged by `type` in the config JSON, e.g.
+/// `{"type": "wal", "dir": "/data/wal", "symbols": {"NIFTY": 26000}}` or
+/// `{"type": "csv", "path": "/data/{symbol}.csv", "utc_offset": "+05:30"}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DataSourceConfig {
+    /// Records written by data-aggregator's WAL
+    Wal {
+        /// WAL directory holding the `.wal` segments
+        dir: PathBuf,
+        /// Symbol name to the numeric `Symbol` id used in the WAL
+        ///
+        /// Names missing from the map must be the numeric id itself.
+        #[serde(default)]
+        symbols: HashMap<String, u32>,
+        /// Which records are turned into bars
+        #[serde(default)]
+        records: WalRecords,
+    },
+    /// OHLCV rows in CSV files
+    Csv(CsvSourceConfig),
+}
+
+impl DataSourceConfig {
+    /// Instantiates the configured source
+    pub fn build(&self) -> Result<Arc<dyn MarketDataSource>> {
+        Ok(match self {
+            Self::Wal { dir, symbols, records } => {
+                Arc::new(WalDataSource::new(dir.clone(), symbols.clone(), *records))
+            }
+            Self::Csv(config) => Arc::new(CsvDataSource::new(config.clone())?),
+        })
+    }
+}
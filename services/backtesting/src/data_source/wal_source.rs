@@ -0,0 +1,166 @@
+//! Bars from the data-aggregator write-ahead log
+//!
+//! data-aggregator persists every `DataEvent` it sees to a `Wal`. This source
+//! reads those segments back, keeping either the `CandleEvent`s it wrote for
+//! a symbol or aggregating its `TradeEvent`s into fixed-interval bars.
+
+use super::MarketDataSource;
+use crate::event_replay::{to_datetime, to_ts};
+use crate::OHLCV;
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use data_aggregator::{DataEvent, Wal};
+use serde::{Deserialize, Serialize};
+use services_common::{Symbol, Ts};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+
+/// Which WAL records are turned into bars
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WalRecords {
+    /// `CandleEvent`s, used as written
+    Candles {
+        /// Candle timeframe in seconds
+        ///
+        /// May be omitted when the WAL holds a single timeframe for the symbol.
+        #[serde(default)]
+        timeframe_secs: Option<u32>,
+    },
+    /// `TradeEvent`s aggregated into bars
+    Trades {
+        /// Bar length in seconds; bars are stamped with their start time
+        bar_secs: u32,
+    },
+}
+
+impl Default for WalRecords {
+    fn default() -> Self {
+        Self::Candles { timeframe_secs: None }
+    }
+}
+
+/// Data source reading a data-aggregator WAL directory
+#[derive(Debug, Clone)]
+pub struct WalDataSource {
+    dir: PathBuf,
+    symbols: HashMap<String, u32>,
+    records: WalRecords,
+}
+
+impl WalDataSource {
+    /// Creates a source over the WAL in `dir`
+    ///
+    /// `symbols` maps symbol names to the numeric ids stored in the WAL;
+    /// names not in the map must be the id itself.
+    pub fn new(dir: PathBuf, symbols: HashMap<String, u32>, records: WalRecords) -> Self {
+        Self { dir, symbols, records }
+    }
+
+    fn symbol_id(&self, symbol: &str) -> Result<Symbol> {
+        match self.symbols.get(symbol) {
+            Some(id) => Ok(Symbol(*id)),
+            None => symbol
+                .parse()
+                .map(Symbol)
+                .with_context(|| format!("No WAL symbol id configured for {}", symbol)),
+        }
+    }
+}
+
+impl MarketDataSource for WalDataSource {
+    fn load_ohlcv(
+        &self,
+        symbol: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<(DateTime<Utc>, OHLCV)>> {
+        if !self.dir.is_dir() {
+            bail!("WAL directory {} does not exist", self.dir.display());
+        }
+        let id = self.symbol_id(symbol)?;
+        let events = Wal::new(&self.dir, None)?
+            .read_range::<DataEvent>(to_ts(start), to_ts(end))
+            .with_context(|| format!("Failed to read WAL at {}", self.dir.display()))?;
+
+        let bars = match self.records {
+            WalRecords::Candles { timeframe_secs } => candles(&events, id, timeframe_secs)?,
+            WalRecords::Trades { bar_secs } => trade_bars(&events, id, bar_secs)?,
+        };
+        if bars.is_empty() {
+            bail!("No WAL records for {} between {} and {}", symbol, start, end);
+        }
+        Ok(bars)
+    }
+}
+
+/// Candles for `symbol`, restricted to one timeframe
+fn candles(events: &[DataEvent], symbol: Symbol, timeframe_secs: Option<u32>) -> Result<Vec<(DateTime<Utc>, OHLCV)>> {
+    let candles: Vec<_> = events
+        .iter()
+        .filter_map(|event| match event {
+            DataEvent::Candle(candle) if candle.symbol == symbol => Some(candle),
+            _ => None,
+        })
+        .collect();
+
+    let timeframe = match timeframe_secs {
+        Some(timeframe) => timeframe,
+        None => {
+            let timeframes: BTreeSet<_> = candles.iter().map(|candle| candle.timeframe).collect();
+            if timeframes.len() > 1 {
+                bail!("WAL holds {:?} second candles for symbol {}; set timeframe_secs", timeframes, symbol.0);
+            }
+            timeframes.first().copied().unwrap_or_default()
+        }
+    };
+
+    Ok(candles
+        .into_iter()
+        .filter(|candle| candle.timeframe == timeframe)
+        .map(|candle| (to_datetime(candle.ts), OHLCV {
+            open: candle.open.as_f64(),
+            high: candle.high.as_f64(),
+            low: candle.low.as_f64(),
+            close: candle.close.as_f64(),
+            volume: candle.volume.as_f64(),
+        }))
+        .collect())
+}
+
+/// Trades for `symbol` aggregated into `bar_secs` bars
+fn trade_bars(events: &[DataEvent], symbol: Symbol, bar_secs: u32) -> Result<Vec<(DateTime<Utc>, OHLCV)>> {
+    if bar_secs == 0 {
+        bail!("Trade bar length must be positive");
+    }
+    let bar_nanos = u64::from(bar_secs) * NANOS_PER_SECOND;
+
+    // Events come back sorted by timestamp, so the first trade opens the bar
+    let mut bars: BTreeMap<u64, OHLCV> = BTreeMap::new();
+    for event in events {
+        let DataEvent::Trade(trade) = event else {
+            continue;
+        };
+        if trade.symbol != symbol {
+            continue;
+        }
+        let price = trade.price.as_f64();
+        let quantity = trade.quantity.as_f64();
+        let bucket = trade.ts.as_nanos() / bar_nanos * bar_nanos;
+        bars.entry(bucket)
+            .and_modify(|bar| {
+                bar.high = bar.high.max(price);
+                bar.low = bar.low.min(price);
+                bar.close = price;
+                bar.volume += quantity;
+            })
+            .or_insert(OHLCV { open: price, high: price, low: price, close: price, volume: quantity });
+    }
+
+    Ok(bars
+        .into_iter()
+        .map(|(bucket, bar)| (to_datetime(Ts::from_nanos(bucket)), bar))
+        .collect())
+}
@@ -0,0 +1,313 @@
+//! Order book event replay for tick-level backtests
+//!
+//! When `orderbook::events::OrderBookEvent` streams are loaded into the
+//! `BacktestEngine`, the run steps over individual events instead of bars.
+//! Each symbol keeps a `LiveBook` that is rebuilt from the stream, and the
+//! `ExecutionSimulator` matches orders against its depth.
+//!
+//! Like `orderbook::replay::ReplayEngine`, the book checks sequence numbers.
+//! A recorded stream cannot be asked for the missing events, so a gap clears
+//! the book rather than leaving stale depth to fill against, and incremental
+//! updates are ignored until the next snapshot resyncs it.
+
+use crate::OrderbookSnapshot;
+use chrono::{DateTime, Utc};
+use orderbook::events::{OrderBookDelta, OrderBookEvent, OrderBookSnapshot, OrderUpdate, Side, UpdateType};
+use services_common::{Px, Qty, Ts};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+use tracing::{debug, info, warn};
+
+/// Resting exchange order tracked from L3 updates
+#[derive(Debug, Clone, Copy)]
+struct RestingOrder {
+    side: Side,
+    price: i64,
+    quantity: i64,
+}
+
+/// Price-level book for one symbol, rebuilt from replayed events
+///
+/// Accepts both L3 order updates and L2 snapshots/deltas. Prices and
+/// quantities are kept in fixed-point ticks so level lookups are exact.
+#[derive(Debug, Clone, Default)]
+pub struct LiveBook {
+    /// Bid levels keyed by price ticks
+    bids: BTreeMap<i64, i64>,
+    /// Ask levels keyed by price ticks
+    asks: BTreeMap<i64, i64>,
+    /// Resting orders from L3 updates, used to resolve modifies and deletes
+    orders: HashMap<u64, RestingOrder>,
+    /// Price of the most recent trade print
+    last_trade: Option<Px>,
+    /// Sequence number of the last applied event
+    sequence: u64,
+    /// Exchange time of the last applied event
+    last_update: Option<Ts>,
+    /// Set by a sequence gap until a snapshot resyncs the book
+    awaiting_snapshot: bool,
+    /// Sequence gaps detected
+    sequence_gaps: u64,
+}
+
+impl LiveBook {
+    /// Creates an empty book
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies one event to the book
+    ///
+    /// Events at or below the last applied sequence are skipped as
+    /// duplicates. An event that skips sequence numbers clears the book, and
+    /// order updates and deltas are then ignored until a snapshot arrives.
+    /// Trade prints are always applied since they do not depend on depth.
+    pub fn apply(&mut self, event: &OrderBookEvent) {
+        if matches!(event, OrderBookEvent::Market(_)) {
+            return;
+        }
+        let sequence = event.sequence();
+        if self.last_update.is_some() {
+            if sequence <= self.sequence {
+                debug!("Skipping event {} already applied at {}", sequence, self.sequence);
+                return;
+            }
+            // A delta covers the sequences after its `prev_sequence`
+            let first = match event {
+                OrderBookEvent::Delta(delta) => delta.prev_sequence.saturating_add(1),
+                _ => sequence,
+            };
+            let is_snapshot = matches!(event, OrderBookEvent::Snapshot(_));
+            if first > self.sequence + 1 && !is_snapshot && !self.awaiting_snapshot {
+                warn!("Sequence gap after {}: next event is {}, clearing the book until a snapshot", self.sequence, first);
+                self.sequence_gaps += 1;
+                self.awaiting_snapshot = true;
+                self.clear();
+            }
+        }
+
+        match event {
+            OrderBookEvent::Trade(trade) => self.last_trade = Some(trade.price),
+            OrderBookEvent::Snapshot(snapshot) => {
+                if self.awaiting_snapshot {
+                    info!("Book resynced from snapshot at {}", sequence);
+                    self.awaiting_snapshot = false;
+                }
+                self.apply_snapshot(snapshot);
+            }
+            _ if self.awaiting_snapshot => {}
+            OrderBookEvent::Order(update) => self.apply_order(update),
+            OrderBookEvent::Delta(delta) => self.apply_delta(delta),
+            OrderBookEvent::Market(_) => return,
+        }
+        self.sequence = sequence;
+        self.last_update = Some(event.exchange_time());
+    }
+
+    fn apply_order(&mut self, update: &OrderUpdate) {
+        match update.update_type {
+            UpdateType::Add => self.add_order(update),
+            UpdateType::Modify => {
+                self.remove_order(update.order_id);
+                self.add_order(update);
+            }
+            UpdateType::Delete => self.remove_order(update.order_id),
+            UpdateType::Clear => self.clear(),
+            // Trades arrive as separate events and the feed follows them with
+            // the resulting modify/delete, so the book is unchanged here
+            UpdateType::Trade | UpdateType::Snapshot | UpdateType::Delta => {}
+        }
+    }
+
+    fn add_order(&mut self, update: &OrderUpdate) {
+        let quantity = update.quantity.as_i64();
+        if quantity <= 0 {
+            return;
+        }
+        let price = update.price.as_i64();
+        *self.levels_mut(update.side).entry(price).or_insert(0) += quantity;
+        self.orders.insert(update.order_id, RestingOrder { side: update.side, price, quantity });
+    }
+
+    fn remove_order(&mut self, order_id: u64) {
+        let Some(order) = self.orders.remove(&order_id) else {
+            return;
+        };
+        let levels = self.levels_mut(order.side);
+        if let Some(level) = levels.get_mut(&order.price) {
+            *level -= order.quantity;
+            if *level <= 0 {
+                levels.remove(&order.price);
+            }
+        }
+    }
+
+    fn apply_snapshot(&mut self, snapshot: &OrderBookSnapshot) {
+        self.clear();
+        for (levels, updates) in [(&mut self.bids, &snapshot.bids), (&mut self.asks, &snapshot.asks)] {
+            for level in updates.iter().filter(|level| level.quantity.as_i64() > 0) {
+                levels.insert(level.price.as_i64(), level.quantity.as_i64());
+            }
+        }
+    }
+
+    fn apply_delta(&mut self, delta: &OrderBookDelta) {
+        let sides = [
+            (&mut self.bids, &delta.bid_updates, &delta.bid_deletions),
+            (&mut self.asks, &delta.ask_updates, &delta.ask_deletions),
+        ];
+        for (levels, updates, deletions) in sides {
+            for level in updates {
+                if level.quantity.as_i64() > 0 {
+                    levels.insert(level.price.as_i64(), level.quantity.as_i64());
+                } else {
+                    levels.remove(&level.price.as_i64());
+                }
+            }
+            for price in deletions {
+                levels.remove(&price.as_i64());
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.bids.clear();
+        self.asks.clear();
+        self.orders.clear();
+    }
+
+    fn levels_mut(&mut self, side: Side) -> &mut BTreeMap<i64, i64> {
+        match side {
+            Side::Buy => &mut self.bids,
+            Side::Sell => &mut self.asks,
+        }
+    }
+
+    /// Best bid price and size
+    pub fn best_bid(&self) -> Option<(Px, Qty)> {
+        self.bids.iter().next_back().map(|(price, qty)| (Px::from_i64(*price), Qty::from_i64(*qty)))
+    }
+
+    /// Best ask price and size
+    pub fn best_ask(&self) -> Option<(Px, Qty)> {
+        self.asks.iter().next().map(|(price, qty)| (Px::from_i64(*price), Qty::from_i64(*qty)))
+    }
+
+    /// Mid price when both sides are populated
+    pub fn mid_price(&self) -> Option<f64> {
+        match (self.best_bid(), self.best_ask()) {
+            (Some((bid, _)), Some((ask, _))) => Some((bid.as_f64() + ask.as_f64()) / 2.0),
+            _ => None,
+        }
+    }
+
+    /// Price of the most recent trade print
+    pub fn last_trade(&self) -> Option<Px> {
+        self.last_trade
+    }
+
+    /// Price used to mark positions: the mid, falling back to the last trade
+    pub fn reference_price(&self) -> Option<f64> {
+        self.mid_price().or_else(|| self.last_trade.map(|price| price.as_f64()))
+    }
+
+    /// Displayed quantity at `price` on `side`
+    pub fn quantity_at(&self, side: Side, price: Px) -> Qty {
+        let levels = match side {
+            Side::Buy => &self.bids,
+            Side::Sell => &self.asks,
+        };
+        Qty::from_i64(levels.get(&price.as_i64()).copied().unwrap_or(0))
+    }
+
+    /// Sequence number of the last applied event
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Exchange time of the last applied event
+    pub fn last_update(&self) -> Option<Ts> {
+        self.last_update
+    }
+
+    /// Whether a sequence gap left the book waiting for a snapshot
+    pub fn is_awaiting_snapshot(&self) -> bool {
+        self.awaiting_snapshot
+    }
+
+    /// Number of sequence gaps detected
+    pub fn sequence_gaps(&self) -> u64 {
+        self.sequence_gaps
+    }
+
+    /// Top `levels` of each side in the execution simulator's format
+    pub fn to_snapshot(&self, levels: usize, timestamp: DateTime<Utc>) -> OrderbookSnapshot {
+        let to_level = |(price, qty): (&i64, &i64)| (Px::from_i64(*price).as_f64(), Qty::from_i64(*qty).as_f64());
+        OrderbookSnapshot {
+            bids: self.bids.iter().rev().take(levels).map(to_level).collect(),
+            asks: self.asks.iter().take(levels).map(to_level).collect(),
+            timestamp,
+        }
+    }
+}
+
+/// Sorts a single symbol's stream into sequence order
+///
+/// Market events carry no sequence number; they keep their position
+/// relative to the sequenced event that preceded them.
+pub fn sort_by_sequence(events: &mut Vec<OrderBookEvent>) {
+    let mut last_sequence = 0;
+    let mut keyed: Vec<_> = events
+        .drain(..)
+        .map(|event| {
+            if !matches!(event, OrderBookEvent::Market(_)) {
+                last_sequence = event.sequence();
+            }
+            (last_sequence, event)
+        })
+        .collect();
+    keyed.sort_by_key(|(sequence, _)| *sequence);
+    events.extend(keyed.into_iter().map(|(_, event)| event));
+}
+
+/// Merges per-symbol streams into one timeline ordered by exchange time
+///
+/// Each input stream must already be in sequence order; its relative order
+/// is preserved even when exchange timestamps tie or step backwards.
+/// Ties across symbols are broken by the order of `streams`.
+pub fn merge_streams(streams: Vec<(String, Vec<OrderBookEvent>)>) -> Vec<(String, OrderBookEvent)> {
+    let total = streams.iter().map(|(_, events)| events.len()).sum();
+    let mut iters: Vec<_> = streams
+        .into_iter()
+        .map(|(symbol, events)| (symbol, events.into_iter().peekable()))
+        .collect();
+
+    let mut heap = BinaryHeap::new();
+    for (index, (_, events)) in iters.iter_mut().enumerate() {
+        if let Some(event) = events.peek() {
+            heap.push(Reverse((event.exchange_time().as_nanos(), index)));
+        }
+    }
+
+    let mut merged = Vec::with_capacity(total);
+    while let Some(Reverse((_, index))) = heap.pop() {
+        let (symbol, events) = &mut iters[index];
+        if let Some(event) = events.next() {
+            merged.push((symbol.clone(), event));
+        }
+        if let Some(next) = events.peek() {
+            heap.push(Reverse((next.exchange_time().as_nanos(), index)));
+        }
+    }
+    merged
+}
+
+/// Converts an event timestamp to the engine's clock
+pub fn to_datetime(ts: Ts) -> DateTime<Utc> {
+    DateTime::from_timestamp_nanos(i64::try_from(ts.as_nanos()).unwrap_or(i64::MAX))
+}
+
+/// Converts the engine's clock to an event timestamp
+pub fn to_ts(timestamp: DateTime<Utc>) -> Ts {
+    Ts::from_nanos(timestamp.timestamp_nanos_opt().and_then(|nanos| u64::try_from(nanos).ok()).unwrap_or(0))
+}
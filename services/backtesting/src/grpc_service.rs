@@ -0,0 +1,761 @@
+//! gRPC service implementation for backtesting
+//!
+//! `RunBacktest` validates the request, registers a job and spawns a task that
+//! loads market data, runs the `BacktestEngine` and keeps the `BacktestResult`
+//! so the status, results and stop RPCs can report on it.
+//!
+//! With a `RunStore` attached, every finished job is also written to disk,
+//! so results, comparisons and tearsheets stay available for runs from
+//! before a restart and `ListBacktests` shows the full history.
+
+use crate::benchmark::{BenchmarkMetrics, RelativeMetrics};
+use crate::commission::CostBreakdown;
+use crate::comparison::RunComparison;
+use crate::data_source::{InMemoryDataSource, MarketDataSource};
+use crate::robustness::{ConfidenceInterval, MonteCarloAnalyzer, MonteCarloConfig, ResamplingSummary, RobustnessReport};
+use crate::run_store::{DataCoverage, RunRecord, RunStore, CODE_VERSION};
+use crate::strategies::StrategySpec;
+use crate::tearsheet::Tearsheet;
+use crate::{BacktestConfig, BacktestEngine, BacktestResult, OrderSide, Strategy};
+use chrono::{DateTime, Utc};
+use services_common::proto::backtesting::v1 as pb;
+use services_common::proto::{
+    BacktestingService as BacktestingTrait,
+    RunBacktestRequest, RunBacktestResponse,
+    GetBacktestStatusRequest, GetBacktestStatusResponse,
+    GetBacktestResultsRequest, GetBacktestResultsResponse,
+    StopBacktestRequest, StopBacktestResponse,
+    ListBacktestsRequest, ListBacktestsResponse,
+    CompareBacktestsRequest, CompareBacktestsResponse,
+    ExportTearsheetRequest, ExportTearsheetResponse,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tonic::{Request, Response, Status};
+use tracing::{info, warn, error};
+
+/// Most Monte Carlo simulations a results request may ask for
+pub const MAX_MONTE_CARLO_SIMULATIONS: u32 = 10_000;
+
+/// Lifecycle status of a backtest job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BacktestStatus {
+    /// Accepted but not yet started
+    Pending,
+    /// Loading data or running the engine
+    Running,
+    /// Finished successfully; results are available
+    Completed,
+    /// Finished with an error
+    Failed,
+    /// Stopped through `StopBacktest`
+    Cancelled,
+}
+
+impl BacktestStatus {
+    /// Wire representation used in status and list responses
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "PENDING",
+            Self::Running => "RUNNING",
+            Self::Completed => "COMPLETED",
+            Self::Failed => "FAILED",
+            Self::Cancelled => "CANCELLED",
+        }
+    }
+
+    /// Whether the job can still make progress
+    pub const fn is_active(self) -> bool {
+        matches!(self, Self::Pending | Self::Running)
+    }
+}
+
+/// A backtest registered with the service
+#[derive(Debug)]
+struct BacktestJob {
+    engine: Arc<BacktestEngine>,
+    status: BacktestStatus,
+    message: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    completed_at: Option<DateTime<Utc>>,
+    result: Option<BacktestResult>,
+    strategy_code: String,
+    symbols: Vec<String>,
+    data: Vec<DataCoverage>,
+}
+
+impl BacktestJob {
+    fn to_record(&self, backtest_id: &str) -> RunRecord {
+        RunRecord {
+            backtest_id: backtest_id.to_string(),
+            status: self.status,
+            message: self.message.clone(),
+            config: self.engine.config.clone(),
+            strategy_code: self.strategy_code.clone(),
+            symbols: self.symbols.clone(),
+            code_version: CODE_VERSION.to_string(),
+            data: self.data.clone(),
+            created_at: self.created_at,
+            completed_at: self.completed_at,
+            result: self.result.clone(),
+        }
+    }
+}
+
+type JobMap = Arc<RwLock<HashMap<String, BacktestJob>>>;
+
+/// Backtesting service for running strategy backtests
+pub struct BacktestingService {
+    /// Backtest jobs indexed by backtest ID
+    jobs: JobMap,
+    /// Source of historical bars for each run
+    data_source: Arc<dyn MarketDataSource>,
+    /// History of finished runs, if persistence is enabled
+    run_store: Option<Arc<RunStore>>,
+}
+
+impl std::fmt::Debug for BacktestingService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BacktestingService")
+            .field("jobs", &"Arc<RwLock<HashMap<String, BacktestJob>>>")
+            .field("data_source", &self.data_source)
+            .field("run_store", &self.run_store)
+            .finish()
+    }
+}
+
+impl Default for BacktestingService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BacktestingService {
+    /// Create new backtesting service with an empty in-memory data source
+    pub fn new() -> Self {
+        Self::with_data_source(Arc::new(InMemoryDataSource::new()))
+    }
+
+    /// Create a backtesting service that loads bars from `data_source`
+    ///
+    /// Runs whose config sets `data_source` load from that source instead.
+    pub fn with_data_source(data_source: Arc<dyn MarketDataSource>) -> Self {
+        Self {
+            jobs: Arc::new(RwLock::new(HashMap::new())),
+            data_source,
+            run_store: None,
+        }
+    }
+
+    /// Persists every finished run to `run_store` and serves history from it
+    pub fn with_run_store(mut self, run_store: RunStore) -> Self {
+        self.run_store = Some(Arc::new(run_store));
+        self
+    }
+
+    /// Applies the explicit request fields on top of the JSON configuration
+    fn resolve_config(req: &RunBacktestRequest) -> Result<BacktestConfig, Status> {
+        let mut config: BacktestConfig = serde_json::from_str(&req.config)
+            .map_err(|e| Status::invalid_argument(format!("Invalid config: {}", e)))?;
+
+        if let Some(start) = req.start_date.as_ref().and_then(from_timestamp) {
+            config.start_date = start;
+        }
+        if let Some(end) = req.end_date.as_ref().and_then(from_timestamp) {
+            config.end_date = end;
+        }
+        if req.initial_capital > 0.0 {
+            config.initial_capital = req.initial_capital;
+        }
+
+        if config.end_date <= config.start_date {
+            return Err(Status::invalid_argument("end_date must be after start_date"));
+        }
+        if config.initial_capital <= 0.0 {
+            return Err(Status::invalid_argument("initial_capital must be positive"));
+        }
+
+        Ok(config)
+    }
+
+    /// Runs the job, then writes its outcome to the run store
+    async fn execute(
+        jobs: JobMap,
+        data_source: Arc<dyn MarketDataSource>,
+        run_store: Option<Arc<RunStore>>,
+        backtest_id: String,
+        symbols: Vec<String>,
+        strategy: Box<dyn Strategy>,
+    ) {
+        Self::run_job(&jobs, data_source, &backtest_id, symbols, strategy).await;
+        if let Some(store) = run_store {
+            Self::persist(&jobs, &store, &backtest_id).await;
+        }
+    }
+
+    /// Loads data, runs the engine and records the outcome on the job
+    async fn run_job(
+        jobs: &JobMap,
+        data_source: Arc<dyn MarketDataSource>,
+        backtest_id: &str,
+        symbols: Vec<String>,
+        mut strategy: Box<dyn Strategy>,
+    ) {
+        let Some(engine) = Self::transition(jobs, backtest_id, BacktestStatus::Running, "Loading market data").await else {
+            return;
+        };
+
+        let (start, end) = (engine.config.start_date, engine.config.end_date);
+        for symbol in &symbols {
+            let loaded = match data_source.load_ohlcv(symbol, start, end) {
+                Ok(bars) => {
+                    if let Some(coverage) = DataCoverage::of(symbol, &bars)
+                        && let Some(job) = jobs.write().await.get_mut(backtest_id)
+                    {
+                        job.data.push(coverage);
+                    }
+                    engine.load_data(symbol, bars).await
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = loaded {
+                error!("Backtest {} failed to load {}: {:#}", backtest_id, symbol, e);
+                Self::finish(jobs, backtest_id, BacktestStatus::Failed, format!("Failed to load {}: {:#}", symbol, e), None).await;
+                return;
+            }
+        }
+
+        if let Some(benchmark) = &engine.config.benchmark {
+            let loaded = match data_source.load_ohlcv(&benchmark.symbol, start, end) {
+                Ok(bars) => {
+                    if let Some(coverage) = DataCoverage::of(&benchmark.symbol, &bars)
+                        && let Some(job) = jobs.write().await.get_mut(backtest_id)
+                    {
+                        job.data.push(coverage);
+                    }
+                    engine.load_benchmark(bars).await
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = loaded {
+                error!("Backtest {} failed to load benchmark {}: {:#}", backtest_id, benchmark.symbol, e);
+                Self::finish(jobs, backtest_id, BacktestStatus::Failed, format!("Failed to load benchmark {}: {:#}", benchmark.symbol, e), None).await;
+                return;
+            }
+        }
+
+        if Self::transition(jobs, backtest_id, BacktestStatus::Running, "Backtest in progress").await.is_none() {
+            return;
+        }
+
+        match engine.run(strategy.as_mut()).await {
+            Ok(result) => {
+                info!("Backtest {} completed: return {:.2}%", backtest_id, result.metrics.total_return * 100.0);
+                Self::finish(jobs, backtest_id, BacktestStatus::Completed, "Backtest completed".to_string(), Some(result)).await;
+            }
+            Err(_) if engine.is_stop_requested() => {
+                Self::finish(jobs, backtest_id, BacktestStatus::Cancelled, "Backtest stopped by request".to_string(), None).await;
+            }
+            Err(e) => {
+                error!("Backtest {} failed: {:#}", backtest_id, e);
+                Self::finish(jobs, backtest_id, BacktestStatus::Failed, format!("{:#}", e), None).await;
+            }
+        }
+    }
+
+    /// Moves an active job to `status`, returning its engine
+    ///
+    /// Returns `None` if the job is gone or was already stopped.
+    async fn transition(jobs: &JobMap, backtest_id: &str, status: BacktestStatus, message: &str) -> Option<Arc<BacktestEngine>> {
+        let mut jobs = jobs.write().await;
+        let job = jobs.get_mut(backtest_id)?;
+        if !job.status.is_active() {
+            return None;
+        }
+        if job.engine.is_stop_requested() {
+            job.status = BacktestStatus::Cancelled;
+            job.message = "Backtest stopped by request".to_string();
+            job.completed_at = Some(Utc::now());
+            return None;
+        }
+        job.status = status;
+        job.message = message.to_string();
+        job.updated_at = Utc::now();
+        Some(job.engine.clone())
+    }
+
+    /// Writes the record of a finished job to `store`
+    ///
+    /// A failed write is logged rather than failing the job, whose results
+    /// stay available in memory.
+    async fn persist(jobs: &JobMap, store: &RunStore, backtest_id: &str) {
+        let record = match jobs.read().await.get(backtest_id) {
+            Some(job) if !job.status.is_active() => job.to_record(backtest_id),
+            _ => return,
+        };
+        match store.save(&record) {
+            Ok(()) => info!("Backtest {} saved to {}", backtest_id, store.root().display()),
+            Err(e) => error!("Failed to save backtest {}: {:#}", backtest_id, e),
+        }
+    }
+
+    /// Record of `backtest_id`, from the live jobs or the run store
+    async fn find_run(&self, backtest_id: &str) -> Result<RunRecord, Status> {
+        if let Some(job) = self.jobs.read().await.get(backtest_id) {
+            return Ok(job.to_record(backtest_id));
+        }
+        let stored = match &self.run_store {
+            Some(store) => store.load(backtest_id)
+                .map_err(|e| Status::internal(format!("Failed to read backtest {}: {:#}", backtest_id, e)))?,
+            None => None,
+        };
+        stored.ok_or_else(|| Status::not_found(format!("Backtest {} not found", backtest_id)))
+    }
+
+    async fn finish(jobs: &JobMap, backtest_id: &str, status: BacktestStatus, message: String, result: Option<BacktestResult>) {
+        if let Some(job) = jobs.write().await.get_mut(backtest_id) {
+            let now = Utc::now();
+            job.status = status;
+            job.message = message;
+            job.updated_at = now;
+            job.completed_at = Some(now);
+            job.result = result;
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl BacktestingTrait for BacktestingService {
+    async fn run_backtest(
+        &self,
+        request: Request<RunBacktestRequest>,
+    ) -> Result<Response<RunBacktestResponse>, Status> {
+        let req = request.into_inner();
+
+        info!("Starting backtest: {}", req.backtest_id);
+
+        if req.backtest_id.is_empty() {
+            return Err(Status::invalid_argument("backtest_id is required"));
+        }
+
+        let config = Self::resolve_config(&req)?;
+        let spec = StrategySpec::parse(&req.strategy_code)
+            .map_err(|e| Status::invalid_argument(format!("{:#}", e)))?;
+        let strategy = spec.build().map_err(|e| Status::invalid_argument(format!("{:#}", e)))?;
+        let symbols = if req.symbols.is_empty() { spec.symbols() } else { req.symbols.clone() };
+        let data_source = match &config.data_source {
+            Some(source) => source.build().map_err(|e| Status::invalid_argument(format!("Invalid data source: {:#}", e)))?,
+            None => self.data_source.clone(),
+        };
+
+        let engine = Arc::new(BacktestEngine::new(config));
+
+        {
+            let mut jobs = self.jobs.write().await;
+            if jobs.get(&req.backtest_id).is_some_and(|job| job.status.is_active()) {
+                return Err(Status::already_exists(format!("Backtest {} is already running", req.backtest_id)));
+            }
+            let now = Utc::now();
+            jobs.insert(req.backtest_id.clone(), BacktestJob {
+                engine,
+                status: BacktestStatus::Pending,
+                message: "Backtest queued".to_string(),
+                created_at: now,
+                updated_at: now,
+                completed_at: None,
+                result: None,
+                strategy_code: req.strategy_code.clone(),
+                symbols: symbols.clone(),
+                data: Vec::new(),
+            });
+        }
+
+        tokio::spawn(Self::execute(
+            self.jobs.clone(),
+            data_source,
+            self.run_store.clone(),
+            req.backtest_id.clone(),
+            symbols,
+            strategy,
+        ));
+
+        Ok(Response::new(RunBacktestResponse {
+            backtest_id: req.backtest_id,
+            status: "STARTED".to_string(),
+            message: "Backtest started successfully".to_string(),
+        }))
+    }
+
+    async fn get_backtest_status(
+        &self,
+        request: Request<GetBacktestStatusRequest>,
+    ) -> Result<Response<GetBacktestStatusResponse>, Status> {
+        let req = request.into_inner();
+
+        let jobs = self.jobs.read().await;
+        let Some(job) = jobs.get(&req.backtest_id) else {
+            drop(jobs);
+            let record = self.find_run(&req.backtest_id).await?;
+            let finished_at = record.completed_at.unwrap_or(record.created_at);
+            return Ok(Response::new(GetBacktestStatusResponse {
+                backtest_id: req.backtest_id,
+                status: record.status.as_str().to_string(),
+                progress_pct: if record.status == BacktestStatus::Completed { 100.0 } else { 0.0 },
+                message: record.message,
+                started_at: Some(to_timestamp(record.created_at)),
+                updated_at: Some(to_timestamp(finished_at)),
+            }));
+        };
+
+        let (progress_pct, updated_at) = match job.status {
+            BacktestStatus::Running => (job.engine.get_state().progress_pct, Utc::now()),
+            BacktestStatus::Completed => (100.0, job.updated_at),
+            _ => (job.engine.get_state().progress_pct, job.updated_at),
+        };
+
+        Ok(Response::new(GetBacktestStatusResponse {
+            backtest_id: req.backtest_id,
+            status: job.status.as_str().to_string(),
+            progress_pct,
+            message: job.message.clone(),
+            started_at: Some(to_timestamp(job.created_at)),
+            updated_at: Some(to_timestamp(updated_at)),
+        }))
+    }
+
+    async fn get_backtest_results(
+        &self,
+        request: Request<GetBacktestResultsRequest>,
+    ) -> Result<Response<GetBacktestResultsResponse>, Status> {
+        let req = request.into_inner();
+
+        info!("Getting results for backtest: {}", req.backtest_id);
+
+        if req.monte_carlo_simulations > MAX_MONTE_CARLO_SIMULATIONS {
+            return Err(Status::invalid_argument(format!(
+                "{} Monte Carlo simulations exceeds the maximum of {}",
+                req.monte_carlo_simulations, MAX_MONTE_CARLO_SIMULATIONS
+            )));
+        }
+
+        let mut record = self.find_run(&req.backtest_id).await.inspect_err(|status| {
+            if status.code() == tonic::Code::NotFound {
+                error!("Backtest {} not found when retrieving results", req.backtest_id);
+            }
+        })?;
+
+        let Some(result) = record.result.take() else {
+            return Err(Status::failed_precondition(format!(
+                "Backtest {} has no results (status: {})", req.backtest_id, record.status.as_str()
+            )));
+        };
+
+        let final_portfolio = serde_json::to_string(&result.final_portfolio)
+            .map_err(|e| Status::internal(format!("Failed to encode portfolio: {}", e)))?;
+
+        let (result, robustness) = if req.monte_carlo_simulations > 0 {
+            let analyzer = MonteCarloAnalyzer::new(MonteCarloConfig {
+                simulations: req.monte_carlo_simulations as usize,
+                seed: req.monte_carlo_seed,
+                ..MonteCarloConfig::default()
+            });
+            // Resampling is CPU-bound, so keep it off the async workers
+            let risk_free_rate = record.config.risk_free_rate;
+            let (result, report) = tokio::task::spawn_blocking(move || {
+                let report = analyzer.analyze(&result, risk_free_rate);
+                (result, report)
+            })
+            .await
+            .map_err(|e| Status::internal(format!("Monte Carlo analysis did not complete: {}", e)))?;
+            let report =
+                report.map_err(|e| Status::failed_precondition(format!("Monte Carlo analysis failed: {:#}", e)))?;
+            (result, Some(to_proto_robustness(&report)))
+        } else {
+            (result, None)
+        };
+
+        Ok(Response::new(GetBacktestResultsResponse {
+            backtest_id: req.backtest_id,
+            metrics: Some(to_proto_metrics(&result.metrics)),
+            equity_curve: result.equity_curve.iter()
+                .map(|(timestamp, value)| pb::EquityPoint {
+                    timestamp: Some(to_timestamp(*timestamp)),
+                    value: *value,
+                })
+                .collect(),
+            trades: result.trades.iter()
+                .map(|trade| pb::Trade {
+                    symbol: trade.symbol.clone(),
+                    side: match trade.side {
+                        OrderSide::Buy => "BUY",
+                        OrderSide::Sell => "SELL",
+                    }.to_string(),
+                    quantity: trade.quantity,
+                    entry_price: trade.entry_price,
+                    exit_price: trade.exit_price,
+                    pnl: trade.pnl,
+                    return_pct: trade.return_pct,
+                    entry_time: Some(to_timestamp(trade.entry_time)),
+                    exit_time: Some(to_timestamp(trade.exit_time)),
+                })
+                .collect(),
+            final_portfolio,
+            costs: Some(to_proto_costs(&result.costs)),
+            robustness,
+            benchmark: result.benchmark.as_ref().map(to_proto_benchmark),
+        }))
+    }
+
+    async fn stop_backtest(
+        &self,
+        request: Request<StopBacktestRequest>,
+    ) -> Result<Response<StopBacktestResponse>, Status> {
+        let req = request.into_inner();
+
+        info!("Stopping backtest: {}", req.backtest_id);
+
+        let jobs = self.jobs.read().await;
+
+        let (success, message) = match jobs.get(&req.backtest_id) {
+            Some(job) if job.status.is_active() => {
+                job.engine.stop();
+                (true, "Stop requested".to_string())
+            }
+            Some(job) => {
+                warn!("Backtest {} is not running ({})", req.backtest_id, job.status.as_str());
+                (false, format!("Backtest already {}", job.status.as_str()))
+            }
+            None => (false, "Backtest not found".to_string()),
+        };
+
+        Ok(Response::new(StopBacktestResponse {
+            backtest_id: req.backtest_id,
+            success,
+            message,
+        }))
+    }
+
+    async fn list_backtests(
+        &self,
+        request: Request<ListBacktestsRequest>,
+    ) -> Result<Response<ListBacktestsResponse>, Status> {
+        let req = request.into_inner();
+
+        let mut summaries: Vec<(BacktestStatus, DateTime<Utc>, pb::BacktestSummary)> = self.jobs.read().await.iter()
+            .map(|(backtest_id, job)| (job.status, job.created_at, pb::BacktestSummary {
+                backtest_id: backtest_id.clone(),
+                status: job.status.as_str().to_string(),
+                created_at: Some(to_timestamp(job.created_at)),
+                completed_at: job.completed_at.map(to_timestamp),
+                total_return: job.result.as_ref().map_or(0.0, |r| r.metrics.total_return),
+                sharpe_ratio: job.result.as_ref().map_or(0.0, |r| r.metrics.sharpe_ratio),
+                code_version: CODE_VERSION.to_string(),
+            }))
+            .collect();
+
+        // Stored runs not rerun since the last restart
+        if let Some(store) = &self.run_store {
+            let stored = store.list().map_err(|e| Status::internal(format!("Failed to read run history: {:#}", e)))?;
+            for record in stored {
+                if summaries.iter().any(|(_, _, summary)| summary.backtest_id == record.backtest_id) {
+                    continue;
+                }
+                summaries.push((record.status, record.created_at, pb::BacktestSummary {
+                    status: record.status.as_str().to_string(),
+                    created_at: Some(to_timestamp(record.created_at)),
+                    completed_at: record.completed_at.map(to_timestamp),
+                    total_return: record.result.as_ref().map_or(0.0, |r| r.metrics.total_return),
+                    sharpe_ratio: record.result.as_ref().map_or(0.0, |r| r.metrics.sharpe_ratio),
+                    code_version: record.code_version,
+                    backtest_id: record.backtest_id,
+                }));
+            }
+        }
+
+        summaries.retain(|(status, _, _)| {
+            req.status_filter.is_empty() || status.as_str().eq_ignore_ascii_case(&req.status_filter)
+        });
+        summaries.sort_by_key(|(_, created_at, _)| std::cmp::Reverse(*created_at));
+
+        let total = u32::try_from(summaries.len()).unwrap_or(u32::MAX);
+        let limit = if req.limit == 0 { usize::MAX } else { req.limit as usize };
+        let backtests = summaries.into_iter()
+            .skip(req.offset as usize)
+            .take(limit)
+            .map(|(_, _, summary)| summary)
+            .collect();
+
+        Ok(Response::new(ListBacktestsResponse {
+            backtests,
+            total,
+        }))
+    }
+
+    async fn compare_backtests(
+        &self,
+        request: Request<CompareBacktestsRequest>,
+    ) -> Result<Response<CompareBacktestsResponse>, Status> {
+        let req = request.into_inner();
+
+        info!("Comparing backtests: {:?}", req.backtest_ids);
+
+        if req.backtest_ids.is_empty() {
+            return Err(Status::invalid_argument("At least one backtest_id is required"));
+        }
+        let mut runs = Vec::with_capacity(req.backtest_ids.len());
+        for backtest_id in &req.backtest_ids {
+            runs.push(self.find_run(backtest_id).await?);
+        }
+        let comparison = RunComparison::of(&runs)
+            .map_err(|e| Status::failed_precondition(format!("{:#}", e)))?;
+
+        Ok(Response::new(CompareBacktestsResponse {
+            backtest_ids: comparison.backtest_ids,
+            metrics: comparison.metrics.into_iter()
+                .map(|metric| pb::MetricComparison {
+                    name: metric.name,
+                    values: metric.values,
+                    differences: metric.differences,
+                    best_index: metric.best.and_then(|index| u32::try_from(index).ok()),
+                })
+                .collect(),
+            equity_curves: comparison.equity_curves.into_iter()
+                .map(|curve| pb::EquityOverlay {
+                    backtest_id: curve.backtest_id,
+                    points: curve.points.into_iter()
+                        .map(|(timestamp, value)| pb::EquityPoint {
+                            timestamp: Some(to_timestamp(timestamp)),
+                            value,
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }))
+    }
+
+    async fn export_tearsheet(
+        &self,
+        request: Request<ExportTearsheetRequest>,
+    ) -> Result<Response<ExportTearsheetResponse>, Status> {
+        let req = request.into_inner();
+
+        info!("Exporting tearsheet for backtest: {}", req.backtest_id);
+
+        let record = self.find_run(&req.backtest_id).await?;
+        let tearsheet = Tearsheet::from_record(&record)
+            .map_err(|e| Status::failed_precondition(format!("{:#}", e)))?;
+
+        let (content_type, content) = match req.format.to_ascii_uppercase().as_str() {
+            "" | "HTML" => ("text/html", tearsheet.to_html()),
+            "JSON" => {
+                let json = tearsheet.to_json().map_err(|e| Status::internal(format!("{:#}", e)))?;
+                ("application/json", json)
+            }
+            other => return Err(Status::invalid_argument(format!("Unsupported tearsheet format: {}", other))),
+        };
+
+        Ok(Response::new(ExportTearsheetResponse {
+            backtest_id: req.backtest_id,
+            content_type: content_type.to_string(),
+            content,
+        }))
+    }
+}
+
+fn to_timestamp(time: DateTime<Utc>) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: time.timestamp(),
+        nanos: i32::try_from(time.timestamp_subsec_nanos()).unwrap_or(0),
+    }
+}
+
+fn from_timestamp(timestamp: &prost_types::Timestamp) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(timestamp.seconds, u32::try_from(timestamp.nanos).ok()?)
+}
+
+fn to_proto_metrics(metrics: &crate::PerformanceMetrics) -> pb::PerformanceMetrics {
+    pb::PerformanceMetrics {
+        total_return: metrics.total_return,
+        annualized_return: metrics.annualized_return,
+        volatility: metrics.volatility,
+        sharpe_ratio: metrics.sharpe_ratio,
+        sortino_ratio: metrics.sortino_ratio,
+        calmar_ratio: metrics.calmar_ratio,
+        max_drawdown: metrics.max_drawdown,
+        max_drawdown_duration_days: metrics.max_drawdown_duration,
+        value_at_risk: metrics.value_at_risk,
+        conditional_var: metrics.conditional_var,
+        total_trades: metrics.total_trades,
+        winning_trades: metrics.winning_trades,
+        losing_trades: metrics.losing_trades,
+        win_rate: metrics.win_rate,
+        average_win: metrics.average_win,
+        average_loss: metrics.average_loss,
+        profit_factor: metrics.profit_factor,
+        expectancy: metrics.expectancy,
+        total_commission: metrics.total_commission,
+        total_slippage: metrics.total_slippage,
+    }
+}
+
+fn to_proto_robustness(report: &RobustnessReport) -> pb::RobustnessReport {
+    let interval = |interval: &ConfidenceInterval| pb::ConfidenceInterval {
+        lower: interval.lower,
+        median: interval.median,
+        upper: interval.upper,
+        mean: interval.mean,
+    };
+    let summary = |summary: &ResamplingSummary| pb::ResamplingSummary {
+        simulations: u32::try_from(summary.simulations).unwrap_or(u32::MAX),
+        total_return: Some(interval(&summary.total_return)),
+        max_drawdown: Some(interval(&summary.max_drawdown)),
+        sharpe_ratio: Some(interval(&summary.sharpe_ratio)),
+        probability_of_ruin: summary.probability_of_ruin,
+    };
+    pb::RobustnessReport {
+        confidence_level: report.confidence_level,
+        trade_bootstrap: report.trade_bootstrap.as_ref().map(summary),
+        block_bootstrap: report.block_bootstrap.as_ref().map(summary),
+    }
+}
+
+fn to_proto_benchmark(benchmark: &BenchmarkMetrics) -> pb::BenchmarkMetrics {
+    let relative = |metrics: &RelativeMetrics| pb::RelativeMetrics {
+        alpha: metrics.alpha,
+        beta: metrics.beta,
+        correlation: metrics.correlation,
+        tracking_error: metrics.tracking_error,
+        information_ratio: metrics.information_ratio,
+        up_capture: metrics.up_capture,
+        down_capture: metrics.down_capture,
+    };
+    pb::BenchmarkMetrics {
+        symbol: benchmark.symbol.clone(),
+        benchmark_return: benchmark.benchmark_return,
+        excess_return: benchmark.excess_return,
+        overall: Some(relative(&benchmark.overall)),
+        rolling_window: u32::try_from(benchmark.rolling_window).unwrap_or(u32::MAX),
+        rolling: benchmark.rolling.iter()
+            .map(|point| pb::RollingRelativeMetrics {
+                timestamp: Some(to_timestamp(point.timestamp)),
+                metrics: Some(relative(&point.metrics)),
+            })
+            .collect(),
+    }
+}
+
+fn to_proto_costs(costs: &CostBreakdown) -> pb::CostBreakdown {
+    pb::CostBreakdown {
+        brokerage: costs.brokerage,
+        transaction_tax: costs.transaction_tax,
+        exchange_charges: costs.exchange_charges,
+        regulatory_fees: costs.regulatory_fees,
+        stamp_duty: costs.stamp_duty,
+        gst: costs.gst,
+        total: costs.total(),
+    }
+}
@@ -0,0 +1,306 @@
+//! Market data, order entry and acknowledgement latency
+//!
+//! Three one-way delays are simulated, each drawn from a `LatencyModel`:
+//!
+//! - market data: from the exchange time of a bar or book event until the
+//!   strategy sees it
+//! - order entry: from the strategy issuing an order, amendment or
+//!   cancellation until it reaches the simulated book
+//! - acknowledgement: from a fill or rejection until the strategy hears
+//!   about it
+//!
+//! Delays can differ by venue, with symbols assigned to venues in the
+//! config. Each venue's channels are FIFO, so a message never overtakes one
+//! sent before it on the same link. Draws are seeded, so a run with the same
+//! config and data is reproducible.
+
+use crate::{splitmix_unit, SPLITMIX_GAMMA};
+use anyhow::{bail, Result};
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Distribution of a one-way delay in milliseconds
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LatencyModel {
+    /// No delay
+    #[default]
+    Zero,
+    /// Same delay every time
+    Constant {
+        /// Delay in milliseconds
+        millis: f64,
+    },
+    /// Delay drawn uniformly between two bounds
+    ///
+    /// Example: `Uniform { min_millis: 50.0, max_millis: 200.0 }` for a
+    /// retail broker round trip
+    Uniform {
+        /// Shortest delay in milliseconds
+        min_millis: f64,
+        /// Longest delay in milliseconds
+        max_millis: f64,
+    },
+    /// Normally distributed delay, truncated at zero
+    Normal {
+        /// Mean delay in milliseconds
+        mean_millis: f64,
+        /// Standard deviation in milliseconds
+        std_dev_millis: f64,
+    },
+    /// Log-normally distributed delay, which has the long right tail seen
+    /// on real network links
+    LogNormal {
+        /// Median delay in milliseconds
+        median_millis: f64,
+        /// Standard deviation of the delay's logarithm
+        sigma: f64,
+    },
+}
+
+impl LatencyModel {
+    /// Checks that the parameters describe a valid distribution
+    pub fn validate(&self) -> Result<()> {
+        let parameters: &[f64] = match self {
+            Self::Zero => &[],
+            Self::Constant { millis } => &[*millis],
+            Self::Uniform { min_millis, max_millis } => {
+                if min_millis > max_millis {
+                    bail!("Minimum latency {} ms exceeds maximum {} ms", min_millis, max_millis);
+                }
+                &[*min_millis, *max_millis]
+            }
+            Self::Normal { mean_millis, std_dev_millis } => &[*mean_millis, *std_dev_millis],
+            Self::LogNormal { median_millis, sigma } => &[*median_millis, *sigma],
+        };
+        if parameters.iter().any(|value| !value.is_finite() || *value < 0.0) {
+            bail!("Latency parameters must be finite and non-negative: {:?}", self);
+        }
+        Ok(())
+    }
+
+    /// Delay in milliseconds for the uniform draws `u1` and `u2` in `[0, 1)`
+    pub fn millis(&self, u1: f64, u2: f64) -> f64 {
+        match *self {
+            Self::Zero => 0.0,
+            Self::Constant { millis } => millis,
+            Self::Uniform { min_millis, max_millis } => min_millis + u1 * (max_millis - min_millis),
+            Self::Normal { mean_millis, std_dev_millis } => {
+                (mean_millis + std_dev_millis * standard_normal(u1, u2)).max(0.0)
+            }
+            Self::LogNormal { median_millis, sigma } => median_millis * (sigma * standard_normal(u1, u2)).exp(),
+        }
+    }
+}
+
+/// Box-Muller transform of two uniform draws
+fn standard_normal(u1: f64, u2: f64) -> f64 {
+    (-2.0 * (1.0 - u1).ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+/// Delays of the three links to one venue
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LatencyProfile {
+    /// Exchange to strategy, for market data
+    pub market_data: LatencyModel,
+    /// Strategy to exchange, for orders, amendments and cancellations
+    pub order_entry: LatencyModel,
+    /// Exchange to strategy, for fills and rejections
+    pub ack: LatencyModel,
+}
+
+impl LatencyProfile {
+    /// Whether every link is instantaneous
+    pub fn is_zero(&self) -> bool {
+        [self.market_data, self.order_entry, self.ack].iter().all(|model| *model == LatencyModel::Zero)
+    }
+
+    fn model(&self, channel: LatencyChannel) -> &LatencyModel {
+        match channel {
+            LatencyChannel::MarketData => &self.market_data,
+            LatencyChannel::OrderEntry => &self.order_entry,
+            LatencyChannel::Ack => &self.ack,
+        }
+    }
+}
+
+/// Latency of a backtest's links to its venues
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LatencyConfig {
+    /// Delays of symbols without a venue
+    pub default: LatencyProfile,
+    /// Delays by venue name
+    pub venues: HashMap<String, LatencyProfile>,
+    /// Venue of each symbol
+    pub symbols: HashMap<String, String>,
+    /// Seed of the latency draws
+    pub seed: u64,
+}
+
+impl LatencyConfig {
+    /// Uses `profile` for every symbol without a venue
+    pub fn with_default(mut self, profile: LatencyProfile) -> Self {
+        self.default = profile;
+        self
+    }
+
+    /// Adds or replaces the delays of `venue`
+    pub fn with_venue(mut self, venue: &str, profile: LatencyProfile) -> Self {
+        self.venues.insert(venue.to_string(), profile);
+        self
+    }
+
+    /// Routes `symbol` to `venue`
+    pub fn with_symbol(mut self, symbol: &str, venue: &str) -> Self {
+        self.symbols.insert(symbol.to_string(), venue.to_string());
+        self
+    }
+
+    /// Venue of `symbol` and its delays
+    ///
+    /// Symbols routed to a venue without a profile use the default delays.
+    pub fn profile_for(&self, symbol: &str) -> (Option<&str>, &LatencyProfile) {
+        let venue = self.symbols.get(symbol).map(String::as_str);
+        let profile = venue.and_then(|venue| self.venues.get(venue)).unwrap_or(&self.default);
+        (venue, profile)
+    }
+
+    /// Whether no link has any delay
+    pub fn is_zero(&self) -> bool {
+        self.default.is_zero() && self.venues.values().all(LatencyProfile::is_zero)
+    }
+
+    /// Checks every configured model
+    pub fn validate(&self) -> Result<()> {
+        let profiles = std::iter::once((None, &self.default))
+            .chain(self.venues.iter().map(|(venue, profile)| (Some(venue), profile)));
+        for (venue, profile) in profiles {
+            for model in [profile.market_data, profile.order_entry, profile.ack] {
+                model.validate().map_err(|e| match venue {
+                    Some(venue) => e.context(format!("Invalid latency for venue {}", venue)),
+                    None => e.context("Invalid default latency"),
+                })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One-way link a delay applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LatencyChannel {
+    /// Market data to the strategy
+    MarketData,
+    /// Orders, amendments and cancellations to the book
+    OrderEntry,
+    /// Fills and rejections to the strategy
+    Ack,
+}
+
+/// Draws delays and keeps every venue link FIFO
+#[derive(Debug)]
+pub struct LatencySimulator {
+    config: LatencyConfig,
+    rng_state: AtomicU64,
+    last_arrival: Mutex<HashMap<(LatencyChannel, Option<String>), DateTime<Utc>>>,
+}
+
+impl LatencySimulator {
+    /// Creates a simulator drawing from `config`
+    pub fn new(config: LatencyConfig) -> Self {
+        Self {
+            rng_state: AtomicU64::new(config.seed),
+            config,
+            last_arrival: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Latency configuration in use
+    pub fn config(&self) -> &LatencyConfig {
+        &self.config
+    }
+
+    /// Draws the delay of a message about `symbol` on `channel`
+    pub fn delay(&self, channel: LatencyChannel, symbol: &str) -> Duration {
+        let model = self.config.profile_for(symbol).1.model(channel);
+        if *model == LatencyModel::Zero {
+            return Duration::zero();
+        }
+        let millis = model.millis(self.next_unit(), self.next_unit());
+        Duration::nanoseconds((millis * 1e6).round() as i64)
+    }
+
+    /// When a message about `symbol` sent on `channel` at `sent_at` arrives
+    ///
+    /// A message arrives no earlier than the last one sent on the same link.
+    pub fn arrival(&self, channel: LatencyChannel, symbol: &str, sent_at: DateTime<Utc>) -> DateTime<Utc> {
+        let delay = self.delay(channel, symbol);
+        let venue = self.config.profile_for(symbol).0.map(str::to_string);
+        let mut last_arrival = self.last_arrival.lock();
+        let last = last_arrival.entry((channel, venue)).or_insert(sent_at + delay);
+        *last = (*last).max(sent_at + delay);
+        *last
+    }
+
+    /// Forgets the link state of a previous run
+    pub fn reset(&self) {
+        self.rng_state.store(self.config.seed, Ordering::Relaxed);
+        self.last_arrival.lock().clear();
+    }
+
+    fn next_unit(&self) -> f64 {
+        let state = self.rng_state
+            .fetch_add(SPLITMIX_GAMMA, Ordering::Relaxed)
+            .wrapping_add(SPLITMIX_GAMMA);
+        splitmix_unit(state)
+    }
+}
+
+/// Messages on their way, released in order of arrival
+#[derive(Debug)]
+pub(crate) struct InFlight<T> {
+    messages: BTreeMap<(DateTime<Utc>, u64), T>,
+    sent: u64,
+}
+
+impl<T> Default for InFlight<T> {
+    fn default() -> Self {
+        Self {
+            messages: BTreeMap::new(),
+            sent: 0,
+        }
+    }
+}
+
+impl<T> InFlight<T> {
+    /// Queues `message` to arrive at `arrival`
+    pub(crate) fn push(&mut self, arrival: DateTime<Utc>, message: T) {
+        self.messages.insert((arrival, self.sent), message);
+        self.sent += 1;
+    }
+
+    /// Removes the next message that has arrived by `now`
+    pub(crate) fn pop_arrived(&mut self, now: DateTime<Utc>) -> Option<(DateTime<Utc>, T)> {
+        let entry = self.messages.first_entry()?;
+        if entry.key().0 > now {
+            return None;
+        }
+        let ((arrival, _), message) = entry.remove_entry();
+        Some((arrival, message))
+    }
+
+    /// Messages still on their way, in order of arrival
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &T> {
+        self.messages.values()
+    }
+
+    /// Drops every message
+    pub(crate) fn clear(&mut self) {
+        self.messages.clear();
+    }
+}
@@ -5,13 +5,42 @@
 //! Current Status: DEVELOPMENT - Basic framework implemented
 //! Production Readiness: 0% - Not tested
 
+pub mod benchmark;
+pub mod commission;
+pub mod comparison;
+pub mod contingent;
+pub mod data_source;
+pub mod event_replay;
+pub mod grpc_service;
+pub mod latency;
+pub mod lifecycle;
+pub mod margin;
+pub mod optimization;
+pub mod options;
+pub mod robustness;
+pub mod run_store;
+pub mod strategies;
+pub mod tearsheet;
+
 use anyhow::{Result, Context};
+use benchmark::{AlignedReturns, BenchmarkConfig, BenchmarkMetrics, RelativeMetrics, RollingRelativeMetrics};
 use chrono::{DateTime, Utc, Duration};
+use commission::{CommissionModel, CommissionSchedule, CostBreakdown, FillContext, FlatCommission, Liquidity};
+use contingent::{ContingencyBook, ContingentOrder, IntrabarOrdering};
 use dashmap::DashMap;
+use data_source::DataSourceConfig;
+use event_replay::LiveBook;
+use latency::{InFlight, LatencyChannel, LatencyConfig, LatencySimulator};
+use lifecycle::{OrderCommand, StrategyContext};
+use margin::{InstrumentMargin, LiquidationEvent, MarginConfig, MarginReport, MarginStatus};
+use options::{ExpirySettlement, GreeksPoint, OptionPricer, OptionsConfig, OptionsReport};
+use orderbook::events::{OrderBookEvent, Side as BookSide};
+use services_common::Px;
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 // OrderStatistics imported locally where needed
 use tracing::{info, warn, error, debug};
 
@@ -22,7 +51,14 @@ pub struct BacktestEngine {
     execution_simulator: Arc<ExecutionSimulator>,
     portfolio_tracker: Arc<PortfolioTracker>,
     performance_analyzer: Arc<PerformanceAnalyzer>,
+    option_pricer: Arc<OptionPricer>,
+    options_report: Arc<RwLock<OptionsReport>>,
+    benchmark_prices: Arc<RwLock<Vec<(DateTime<Utc>, f64)>>>,
+    latency: Arc<LatencySimulator>,
+    orders_in_flight: Arc<RwLock<InFlight<OrderCommand>>>,
+    order_symbols: Arc<DashMap<String, String>>,
     state: Arc<RwLock<BacktestState>>,
+    stop_requested: Arc<AtomicBool>,
 }
 
 /// Configuration for backtesting
@@ -40,24 +76,56 @@ pub struct BacktestConfig {
     /// Commission rate as a decimal (e.g., 0.001 = 0.1%)
     /// Applied to both buy and sell transactions
     pub commission_rate: f64,
+    /// Fee schedule used to price fills
+    /// The default `Flat` schedule charges `commission_rate`
+    #[serde(default)]
+    pub commission_model: CommissionSchedule,
     /// Model used to simulate market impact and slippage
     pub slippage_model: SlippageModel,
     /// Time frequency of the market data
     pub data_frequency: DataFrequency,
     /// Whether short selling is allowed in the backtest
+    /// When set, symbols without their own `margin` terms trade in a margin
+    /// account built from `margin_requirement`
     pub enable_shorting: bool,
     /// Margin requirement as a decimal (e.g., 0.5 = 50% margin)
-    /// Only relevant when shorting is enabled
+    /// Only relevant when shorting is enabled; maintenance is half of it
     pub margin_requirement: f64,
     /// Annual risk-free rate as a decimal (e.g., 0.02 = 2%)
     /// Used for calculating risk-adjusted returns like Sharpe ratio
     pub risk_free_rate: f64,
+    /// Model deciding when resting limit orders are filled
+    #[serde(default)]
+    pub fill_model: FillModel,
+    /// Order in which stops and limits inside the same bar are assumed to trigger
+    #[serde(default)]
+    pub intrabar_ordering: IntrabarOrdering,
+    /// Where the gRPC service loads bars from for this run
+    /// Falls back to the service's own data source when unset
+    #[serde(default)]
+    pub data_source: Option<DataSourceConfig>,
+    /// Per-instrument margin, financing and liquidation terms
+    /// Configured symbols can be sold short whether or not `enable_shorting` is set
+    #[serde(default)]
+    pub margin: MarginConfig,
+    /// Volatility surfaces and contracts used to price option positions
+    /// Writing options needs `margin` terms for the option symbols
+    #[serde(default)]
+    pub options: OptionsConfig,
+    /// Market data, order entry and acknowledgement delays
+    /// Zero by default, so orders reach the book in the step they are placed
+    #[serde(default)]
+    pub latency: LatencyConfig,
+    /// Benchmark the results are measured against
+    /// Its prices are loaded with `BacktestEngine::load_benchmark`
+    #[serde(default)]
+    pub benchmark: Option<BenchmarkConfig>,
 }
 
 /// Models for simulating slippage and market impact
 /// 
 /// Determines how much the execution price deviates from the quoted price
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum SlippageModel {
     /// Fixed slippage in basis points (1 bp = 0.01%)
     /// 
@@ -82,10 +150,41 @@ pub enum SlippageModel {
     },
 }
 
+/// Models for deciding when passive limit orders are filled
+/// 
+/// Marketable orders always fill against available prices or depth; these
+/// models only govern orders resting at their limit price.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum FillModel {
+    /// Fill as soon as the market price reaches the limit price
+    /// 
+    /// Optimistic: assumes our order is always first in the queue.
+    #[default]
+    Touch,
+    /// Track the volume queued ahead of our order at its price level
+    /// 
+    /// Only applies to event-driven runs. The queue ahead starts at the
+    /// displayed size when the order rests, shrinks with trades at the level
+    /// and with cancellations (assumed spread evenly through the queue),
+    /// and the order fills only once it has been consumed. A trade through
+    /// the limit price fills the order outright. Bar runs fall back to `Touch`.
+    QueuePosition,
+    /// Fill touched orders with a fixed probability per bar
+    /// 
+    /// Approximates queue effects when only bar data is available.
+    /// Event-driven runs use queue tracking instead.
+    Probabilistic {
+        /// Probability (0.0 to 1.0) that a touched order fills on a given bar
+        fill_probability: f64,
+        /// Seed for the deterministic fill draws
+        seed: u64,
+    },
+}
+
 /// Time frequency for market data and backtesting simulation
 /// 
 /// Determines the granularity of the backtesting time steps
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DataFrequency {
     /// Tick-by-tick data (highest granularity)
     /// Each market event is processed individually
@@ -129,6 +228,7 @@ pub struct BacktestState {
 pub struct MarketDataStore {
     price_data: DashMap<String, BTreeMap<DateTime<Utc>, OHLCV>>,
     orderbook_snapshots: DashMap<String, BTreeMap<DateTime<Utc>, OrderbookSnapshot>>,
+    book_events: DashMap<String, Vec<OrderBookEvent>>,
 }
 
 /// Open, High, Low, Close, Volume market data
@@ -167,12 +267,38 @@ pub struct OrderbookSnapshot {
 pub struct ExecutionSimulator {
     pending_orders: Arc<DashMap<String, Order>>,
     fill_history: Arc<RwLock<Vec<Fill>>>,
+    rejection_history: Arc<RwLock<Vec<OrderRejection>>>,
     config: ExecutionConfig,
     rejection_counter: Arc<std::sync::atomic::AtomicU64>,
+    queue_positions: Arc<DashMap<String, QueuePosition>>,
+    fill_rng_state: Arc<std::sync::atomic::AtomicU64>,
+    commission_model: Arc<dyn CommissionModel>,
+    order_brokerage: Arc<DashMap<String, f64>>,
+    contingencies: Arc<RwLock<ContingencyBook>>,
+}
+
+/// Queue state of a resting limit order under `FillModel::QueuePosition`
+#[derive(Debug, Clone, Copy)]
+struct QueuePosition {
+    /// Displayed quantity ahead of our order
+    ahead: f64,
+    /// Displayed level quantity when last observed
+    level_quantity: f64,
+    /// Traded volume at the level not yet reflected in a level update
+    unreconciled_trades: f64,
 }
 
 const REJECTION_RATE_PRECISION: u64 = 10000; // 0.01% precision
 
+/// Number of levels per side offered to the simulator in event-driven runs
+const BOOK_MATCH_DEPTH: usize = 50;
+
+/// Minimum spacing between equity curve points in event-driven runs
+const EVENT_EQUITY_INTERVAL_SECS: i64 = 1;
+
+/// Margined positions smaller than this are treated as closed
+const QUANTITY_EPSILON: f64 = 1e-9;
+
 /// Configuration for realistic order execution simulation
 /// 
 /// Controls how orders are processed and filled in the backtesting environment,
@@ -188,6 +314,29 @@ pub struct ExecutionConfig {
     /// Probability of order rejection (0.0 to 1.0)
     /// Simulates real-world order rejections due to various factors
     pub reject_rate: f64,  // Probability of order rejection (0.0 to 1.0)
+    /// Commission rate as a decimal of traded notional
+    /// Used when no `commission_model` is set
+    pub commission_rate: f64,
+    /// Fee model pricing each fill
+    pub commission_model: Option<Arc<dyn CommissionModel>>,
+    /// Model deciding when resting limit orders are filled
+    pub fill_model: FillModel,
+    /// Order in which bar extremes are assumed to have traded
+    pub intrabar_ordering: IntrabarOrdering,
+}
+
+impl Default for ExecutionConfig {
+    fn default() -> Self {
+        Self {
+            use_limit_order_book: true,
+            partial_fills: true,
+            reject_rate: 0.001,
+            commission_rate: 0.001,
+            commission_model: None,
+            fill_model: FillModel::Touch,
+            intrabar_ordering: IntrabarOrdering::default(),
+        }
+    }
 }
 
 /// Trading order with all necessary execution parameters
@@ -208,6 +357,9 @@ pub struct Order {
     pub quantity: f64,
     /// Limit price for limit orders (None for market orders)
     pub price: Option<f64>,
+    /// Trigger price for stop and stop-limit orders
+    #[serde(default)]
+    pub stop_price: Option<f64>,
     /// When this order was submitted
     pub timestamp: DateTime<Utc>,
     /// Time-in-force instruction controlling order lifetime
@@ -217,7 +369,7 @@ pub struct Order {
 /// Direction of a trading order
 /// 
 /// Indicates whether the order is to purchase (buy) or sell securities.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum OrderSide {
     /// Purchase order - acquire a long position
     Buy,
@@ -229,7 +381,7 @@ pub enum OrderSide {
 /// 
 /// Determines how the order will be executed in the market,
 /// including price constraints and timing considerations.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum OrderType {
     /// Execute immediately at current market price
     Market,
@@ -245,7 +397,7 @@ pub enum OrderType {
 /// 
 /// Specifies how long an order remains active and under what
 /// conditions it should be cancelled if not immediately filled.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TimeInForce {
     /// Order valid until end of trading day
     Day,
@@ -279,6 +431,42 @@ pub struct Fill {
     pub slippage: f64,
     /// Timestamp when the fill occurred
     pub timestamp: DateTime<Utc>,
+    /// Commission split by component; sums to `commission`
+    #[serde(default)]
+    pub costs: CostBreakdown,
+}
+
+/// Notice that an order or order instruction did not go through
+/// 
+/// Delivered to `Strategy::on_order_rejected` so strategies can forget
+/// orders that will never fill.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderRejection {
+    /// Order the rejection refers to
+    pub order_id: String,
+    /// Why the order or instruction was refused
+    pub reason: RejectReason,
+    /// When the rejection happened
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Cause of an `OrderRejection`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RejectReason {
+    /// Rejected by the simulator's configured rejection rate
+    Simulated,
+    /// IOC or FOK order cancelled because it could not be filled
+    Unfilled,
+    /// Amend or cancel for an order that is not pending
+    UnknownOrder,
+    /// Fill refused because the portfolio could not pay for it
+    InsufficientFunds,
+    /// Sell refused because a cash account does not hold enough of the instrument
+    InsufficientPosition,
+    /// Cancelled because a linked order filled
+    LinkedOrderFilled,
+    /// Contingent order group that cannot be linked as requested
+    InvalidOrder,
 }
 
 /// Portfolio tracker for position and P&L management
@@ -287,6 +475,12 @@ pub struct PortfolioTracker {
     cash_balance: Arc<RwLock<f64>>,
     equity_curve: Arc<RwLock<Vec<(DateTime<Utc>, f64)>>>,
     transaction_history: Arc<RwLock<Vec<Transaction>>>,
+    entry_times: Arc<DashMap<String, DateTime<Utc>>>,
+    costs: Arc<RwLock<CostBreakdown>>,
+    margin: MarginConfig,
+    margin_report: Arc<RwLock<MarginReport>>,
+    in_margin_call: Arc<RwLock<bool>>,
+    last_accrual: Arc<RwLock<Option<DateTime<Utc>>>>,
 }
 
 /// Portfolio position in a specific security
@@ -329,6 +523,9 @@ pub struct Transaction {
     pub price: f64,
     /// Commission cost for this transaction
     pub commission: f64,
+    /// Slippage cost for this transaction
+    #[serde(default)]
+    pub slippage: f64,
     /// Net cash impact (negative for purchases, positive for sales)
     pub net_amount: f64,
 }
@@ -431,6 +628,7 @@ impl std::fmt::Debug for BacktestEngine {
             .field("portfolio_tracker", &"Arc<PortfolioTracker>")
             .field("performance_analyzer", &"Arc<PerformanceAnalyzer>")
             .field("state", &*self.state.read())
+            .field("stop_requested", &self.stop_requested.load(Ordering::Relaxed))
             .finish()
     }
 }
@@ -440,8 +638,12 @@ impl std::fmt::Debug for ExecutionSimulator {
         f.debug_struct("ExecutionSimulator")
             .field("pending_orders", &self.pending_orders.len())
             .field("fill_history", &self.fill_history.read().len())
+            .field("rejection_history", &self.rejection_history.read().len())
             .field("config", &self.config)
             .field("rejection_counter", &self.rejection_counter.load(std::sync::atomic::Ordering::Relaxed))
+            .field("queue_positions", &self.queue_positions.len())
+            .field("commission_model", &self.commission_model)
+            .field("contingencies", &*self.contingencies.read())
             .finish()
     }
 }
@@ -453,6 +655,9 @@ impl std::fmt::Debug for PortfolioTracker {
             .field("cash_balance", &*self.cash_balance.read())
             .field("equity_curve", &self.equity_curve.read().len())
             .field("transaction_history", &self.transaction_history.read().len())
+            .field("costs", &*self.costs.read())
+            .field("margin", &self.margin)
+            .field("margin_report", &*self.margin_report.read())
             .finish()
     }
 }
@@ -484,7 +689,11 @@ impl BacktestEngine {
     /// # Examples
     /// 
     /// ```
-    /// use backtesting::{BacktestEngine, BacktestConfig, SlippageModel, DataFrequency};
+    /// use backtesting::{BacktestEngine, BacktestConfig, SlippageModel, DataFrequency, FillModel};
+    /// use backtesting::commission::CommissionSchedule;
+    /// use backtesting::margin::MarginConfig;
+    /// use backtesting::latency::LatencyConfig;
+    /// use backtesting::options::OptionsConfig;
     /// use chrono::{Utc, Duration};
     /// 
     /// let config = BacktestConfig {
@@ -492,11 +701,19 @@ impl BacktestEngine {
     ///     end_date: Utc::now(),
     ///     initial_capital: 100_000.0,
     ///     commission_rate: 0.001,
+    ///     commission_model: CommissionSchedule::Flat,
     ///     slippage_model: SlippageModel::Fixed { bps: 5.0 },
     ///     data_frequency: DataFrequency::Daily,
     ///     enable_shorting: false,
     ///     margin_requirement: 0.5,
     ///     risk_free_rate: 0.02,
+    ///     fill_model: FillModel::Touch,
+    ///     intrabar_ordering: Default::default(),
+    ///     data_source: None,
+    ///     margin: MarginConfig::default(),
+    ///     options: OptionsConfig::default(),
+    ///     latency: LatencyConfig::default(),
+    ///     benchmark: None,
     /// };
     /// 
     /// let engine = BacktestEngine::new(config);
@@ -504,16 +721,29 @@ impl BacktestEngine {
     pub fn new(config: BacktestConfig) -> Self {
         info!("Initializing BacktestEngine with config: {:?}", config);
         
+        let mut margin = config.margin.clone();
+        if config.enable_shorting && margin.default.is_none() {
+            margin.default = Some(InstrumentMargin::from_requirement(config.margin_requirement));
+        }
+        
         Self {
             config: config.clone(),
             market_data: Arc::new(MarketDataStore::new()),
             execution_simulator: Arc::new(ExecutionSimulator::new(ExecutionConfig {
-                use_limit_order_book: true,
-                partial_fills: true,
-                reject_rate: 0.001,
+                commission_rate: config.commission_rate,
+                commission_model: Some(config.commission_model.build(config.commission_rate)),
+                fill_model: config.fill_model,
+                intrabar_ordering: config.intrabar_ordering,
+                ..ExecutionConfig::default()
             })),
-            portfolio_tracker: Arc::new(PortfolioTracker::new(config.initial_capital)),
+            portfolio_tracker: Arc::new(PortfolioTracker::new(config.initial_capital).with_margin(margin)),
             performance_analyzer: Arc::new(PerformanceAnalyzer::new()),
+            option_pricer: Arc::new(OptionPricer::new(config.options.clone(), config.risk_free_rate)),
+            options_report: Arc::new(RwLock::new(OptionsReport::default())),
+            benchmark_prices: Arc::new(RwLock::new(Vec::new())),
+            latency: Arc::new(LatencySimulator::new(config.latency.clone())),
+            orders_in_flight: Arc::new(RwLock::new(InFlight::default())),
+            order_symbols: Arc::new(DashMap::new()),
             state: Arc::new(RwLock::new(BacktestState {
                 current_time: config.start_date,
                 is_running: false,
@@ -521,9 +751,30 @@ impl BacktestEngine {
                 orders_processed: 0,
                 trades_executed: 0,
             })),
+            stop_requested: Arc::new(AtomicBool::new(false)),
         }
     }
     
+    /// Returns a copy of the current backtest state
+    /// 
+    /// Safe to call from another task while `run` is in progress, which is
+    /// how the gRPC service reports live progress.
+    pub fn get_state(&self) -> BacktestState {
+        self.state.read().clone()
+    }
+    
+    /// Requests that a running backtest stop at the next time step
+    /// 
+    /// `run` checks this flag once per step and returns an error when it is set.
+    pub fn stop(&self) {
+        self.stop_requested.store(true, Ordering::Release);
+    }
+    
+    /// Whether `stop` has been called on this engine
+    pub fn is_stop_requested(&self) -> bool {
+        self.stop_requested.load(Ordering::Acquire)
+    }
+    
     /// Load historical data for backtesting
     pub async fn load_data(&self, symbol: &str, data: Vec<(DateTime<Utc>, OHLCV)>) -> Result<()> {
         info!("Loading {} data points for {}", data.len(), symbol);
@@ -554,8 +805,74 @@ impl BacktestEngine {
         Ok(())
     }
     
+    /// Load closing prices of the benchmark named in `BacktestConfig::benchmark`
+    /// 
+    /// The benchmark is only measured against, never traded, so it does not
+    /// appear in market snapshots.
+    pub async fn load_benchmark(&self, data: Vec<(DateTime<Utc>, OHLCV)>) -> Result<()> {
+        let Some(benchmark) = &self.config.benchmark else {
+            anyhow::bail!("No benchmark is configured");
+        };
+        info!("Loading {} benchmark points for {}", data.len(), benchmark.symbol);
+        
+        let mut prices: Vec<(DateTime<Utc>, f64)> = data.into_iter()
+            .filter(|(_, ohlcv)| ohlcv.close > 0.0)
+            .map(|(timestamp, ohlcv)| (timestamp, ohlcv.close))
+            .collect();
+        if prices.is_empty() {
+            return Err(anyhow::Error::msg("No positive closing prices"))
+                .context(format!("Failed to load benchmark {}", benchmark.symbol));
+        }
+        prices.sort_by_key(|(timestamp, _)| *timestamp);
+        *self.benchmark_prices.write() = prices;
+        Ok(())
+    }
+    
+    /// Load an order book event stream for event-driven backtesting
+    /// 
+    /// Events outside the configured date range are dropped and the rest are
+    /// put into sequence order. Once any stream is loaded, `run` replays
+    /// events and fills orders against the rebuilt book instead of stepping
+    /// over OHLCV bars.
+    pub async fn load_events(&self, symbol: &str, mut events: Vec<OrderBookEvent>) -> Result<()> {
+        info!("Loading {} order book events for {}", events.len(), symbol);
+        
+        let start = event_replay::to_ts(self.config.start_date);
+        let end = event_replay::to_ts(self.config.end_date);
+        events.retain(|event| (start..=end).contains(&event.exchange_time()));
+        
+        if events.is_empty() {
+            error!("No order book events in range for symbol {}", symbol);
+            return Err(anyhow::Error::msg("No events within the backtest period"))
+                .context(format!("Failed to load events for {}", symbol));
+        }
+        
+        event_replay::sort_by_sequence(&mut events);
+        self.market_data.book_events.insert(symbol.to_string(), events);
+        Ok(())
+    }
+    
     /// Run the backtest with a strategy
-    pub async fn run<S: Strategy>(&self, strategy: &S) -> Result<BacktestResult> {
+    /// 
+    /// Steps over OHLCV bars at the configured frequency, or replays order
+    /// book events one at a time when any were loaded with `load_events`.
+    /// The strategy's lifecycle hooks are called as described on `Strategy`.
+    /// 
+    /// With `BacktestConfig::latency` set, the strategy sees each bar or
+    /// event once its market data delay has passed, its orders, amendments
+    /// and cancellations are matched at the first step or event at or after
+    /// they reach the book, and fills and rejections are delivered once
+    /// their acknowledgement delay has passed. Fills are booked into the
+    /// portfolio when they happen.
+    pub async fn run<S: Strategy + ?Sized>(&self, strategy: &mut S) -> Result<BacktestResult> {
+        self.config.options.validate()?;
+        self.config.latency.validate()?;
+        self.latency.reset();
+        self.orders_in_flight.write().clear();
+        if self.market_data.has_book_events() {
+            return self.run_events(strategy).await;
+        }
+        
         info!("Starting backtest from {} to {}", self.config.start_date, self.config.end_date);
         
         {
@@ -567,8 +884,18 @@ impl BacktestEngine {
         // Main backtest loop
         let mut current = self.config.start_date;
         let total_duration = (self.config.end_date - self.config.start_date).num_seconds() as f64;
+        let mut cursor = ExecutionCursor::default();
+        let mut previous: Option<DateTime<Utc>> = None;
+        let mut market_snapshot = self.get_market_snapshot(current)?;
+        self.with_context(&market_snapshot, strategy, |strategy, ctx| strategy.on_start(ctx))?;
         
         while current <= self.config.end_date {
+            if self.is_stop_requested() {
+                self.state.write().is_running = false;
+                warn!("Backtest stopped at {}", current);
+                anyhow::bail!("Backtest stopped before completion");
+            }
+            
             // Update progress
             {
                 let mut state = self.state.write();
@@ -578,29 +905,192 @@ impl BacktestEngine {
             }
             
             // Get market data for current time
-            let market_snapshot = self.get_market_snapshot(current)?;
+            market_snapshot = self.get_market_snapshot(current)?;
+            self.mark_options(&market_snapshot);
             
             // Update portfolio with current prices
             self.portfolio_tracker.update_prices(&market_snapshot)?;
             
-            // Generate signals from strategy
-            let signals = strategy.generate_signals(&market_snapshot, &self.get_portfolio_state());
+            // Charge financing and liquidate if maintenance margin is breached
+            self.enforce_margin(&market_snapshot, &mut cursor)?;
             
-            // Process signals into orders
-            for signal in signals {
-                self.process_signal(signal, current).await?;
-            }
+            // Cash-settle options that have expired
+            self.settle_expiries(&market_snapshot, &mut cursor)?;
             
-            // Simulate order execution
-            self.execution_simulator.process_pending_orders(&market_snapshot, current)?;
+            // Let the strategy trade on the new bar once it has arrived
+            let view = self.bar_view(&market_snapshot);
+            self.settle_executions(strategy, &view, &mut cursor)?;
+            self.with_context(&view, strategy, |strategy, ctx| strategy.on_bar(ctx))?;
+            
+            // Simulate order execution through the bars completed since the last step
+            self.release_orders(current)?;
+            let bars = previous
+                .map(|previous| self.market_data.bars_between(previous, current))
+                .unwrap_or_default();
+            self.execution_simulator.process_pending_orders_intrabar(&market_snapshot, &bars, current)?;
+            
+            // Book new fills into the portfolio and report them
+            self.settle_executions(strategy, &view, &mut cursor)?;
             
             // Record equity curve point
             self.portfolio_tracker.record_equity(current)?;
+            self.record_greeks(&market_snapshot);
             
             // Advance time
+            previous = Some(current);
             current = self.advance_time(current);
         }
         
+        self.with_context(&market_snapshot, strategy, |strategy, ctx| strategy.on_end(ctx))?;
+        self.finish_run()
+    }
+    
+    /// Snapshot of a bar as the strategy sees it
+    /// 
+    /// The strategy acts once the bar of every symbol has arrived, so the
+    /// snapshot is stamped with the latest market data arrival.
+    fn bar_view(&self, market: &MarketSnapshot) -> MarketSnapshot {
+        let mut symbols: Vec<String> = market.prices.iter().map(|e| e.key().clone()).collect();
+        symbols.sort();
+        let seen_at = symbols
+            .iter()
+            .map(|symbol| self.latency.arrival(LatencyChannel::MarketData, symbol, market.timestamp))
+            .max()
+            .unwrap_or(market.timestamp);
+        market.restamped(seen_at)
+    }
+    
+    /// Event-driven main loop
+    /// 
+    /// Streams are merged by exchange time, each event updates the live book
+    /// for its symbol, and the strategy sees mid prices after every event.
+    /// Pending orders are matched against the top of each live book; the
+    /// liquidity they take is not removed from later events, so the replay
+    /// assumes our orders are small relative to displayed depth.
+    /// 
+    /// Delayed events are handed to the strategy, with the book as it was
+    /// after them, at the first event at or after their arrival. Events that
+    /// would arrive after the last one are not delivered.
+    async fn run_events<S: Strategy + ?Sized>(&self, strategy: &mut S) -> Result<BacktestResult> {
+        let mut streams: Vec<_> = self.market_data.book_events
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+        streams.sort_by(|a, b| a.0.cmp(&b.0));
+        let timeline = event_replay::merge_streams(streams);
+        let total_events = timeline.len();
+        
+        info!("Starting event-driven backtest over {} events", total_events);
+        
+        {
+            let mut state = self.state.write();
+            state.is_running = true;
+            state.current_time = self.config.start_date;
+        }
+        
+        let mut books: HashMap<String, LiveBook> = HashMap::new();
+        let prices = DashMap::new();
+        let quotes = DashMap::new();
+        let mut cursor = ExecutionCursor::default();
+        let mut last_equity_at: Option<DateTime<Utc>> = None;
+        let mut current = self.config.start_date;
+        let mut market_snapshot = MarketSnapshot {
+            timestamp: current,
+            prices: DashMap::new(),
+            quotes: DashMap::new(),
+        };
+        let mut market_data_in_flight = InFlight::default();
+        let mut view = market_snapshot.clone();
+        self.with_context(&market_snapshot, strategy, |strategy, ctx| strategy.on_start(ctx))?;
+        
+        for (index, (symbol, event)) in timeline.into_iter().enumerate() {
+            if self.is_stop_requested() {
+                self.state.write().is_running = false;
+                warn!("Backtest stopped at event {} of {}", index, total_events);
+                anyhow::bail!("Backtest stopped before completion");
+            }
+            
+            current = event_replay::to_datetime(event.exchange_time());
+            {
+                let mut state = self.state.write();
+                state.current_time = current;
+                state.progress_pct = index as f64 / total_events as f64 * 100.0;
+            }
+            
+            // Rebuild the book and mark positions
+            let book = books.entry(symbol.clone()).or_default();
+            book.apply(&event);
+            self.execution_simulator.on_book_event(&symbol, &event, book, current)?;
+            match (book.best_bid(), book.best_ask()) {
+                (Some((bid_price, bid_size)), Some((ask_price, ask_size))) => {
+                    quotes.insert(symbol.clone(), Quote {
+                        bid_price: bid_price.as_f64(),
+                        bid_size: bid_size.as_f64(),
+                        ask_price: ask_price.as_f64(),
+                        ask_size: ask_size.as_f64(),
+                    });
+                }
+                _ => {
+                    quotes.remove(&symbol);
+                }
+            }
+            if let Some(price) = book.reference_price() {
+                prices.insert(symbol.clone(), price);
+            }
+            market_snapshot = MarketSnapshot {
+                timestamp: current,
+                prices: prices.clone(),
+                quotes: quotes.clone(),
+            };
+            self.mark_options(&market_snapshot);
+            self.portfolio_tracker.update_prices(&market_snapshot)?;
+            self.enforce_margin(&market_snapshot, &mut cursor)?;
+            self.settle_expiries(&market_snapshot, &mut cursor)?;
+            
+            // Hand the strategy every report and event that has reached it
+            let seen_at = self.latency.arrival(LatencyChannel::MarketData, &symbol, current);
+            market_data_in_flight.push(seen_at, (event, market_snapshot.clone()));
+            let mut arrived = Vec::new();
+            while let Some((seen_at, (event, snapshot))) = market_data_in_flight.pop_arrived(current) {
+                arrived.push((event, snapshot.restamped(seen_at)));
+            }
+            if let Some((_, latest)) = arrived.last() {
+                view = latest.clone();
+            }
+            self.settle_executions(strategy, &view, &mut cursor)?;
+            for (event, snapshot) in arrived {
+                self.with_context(&snapshot, strategy, |strategy, ctx| strategy.on_event(&event, ctx))?;
+            }
+            
+            // Match pending orders against current depth
+            self.release_orders(current)?;
+            for pending_symbol in self.execution_simulator.pending_symbols() {
+                if let Some(book) = books.get(&pending_symbol) {
+                    let depth = book.to_snapshot(BOOK_MATCH_DEPTH, current);
+                    self.execution_simulator.process_orders_against_book(&pending_symbol, &depth, current)?;
+                }
+            }
+            
+            self.settle_executions(strategy, &view, &mut cursor)?;
+            
+            if last_equity_at.is_none_or(|last| current - last >= Duration::seconds(EVENT_EQUITY_INTERVAL_SECS)) {
+                self.portfolio_tracker.record_equity(current)?;
+                self.record_greeks(&market_snapshot);
+                last_equity_at = Some(current);
+            }
+        }
+        
+        if last_equity_at.is_some_and(|last| last != current) {
+            self.portfolio_tracker.record_equity(current)?;
+            self.record_greeks(&market_snapshot);
+        }
+        
+        self.with_context(&view, strategy, |strategy, ctx| strategy.on_end(ctx))?;
+        self.finish_run()
+    }
+    
+    /// Marks the run finished and assembles the result
+    fn finish_run(&self) -> Result<BacktestResult> {
         {
             let mut state = self.state.write();
             state.is_running = false;
@@ -612,19 +1102,43 @@ impl BacktestEngine {
             &self.portfolio_tracker,
             self.config.risk_free_rate
         )?;
+        let equity_curve = self.portfolio_tracker.get_equity_curve();
+        let benchmark = self.benchmark_metrics(&equity_curve);
         
         Ok(BacktestResult {
             metrics,
-            equity_curve: self.portfolio_tracker.get_equity_curve(),
+            equity_curve,
             trades: self.performance_analyzer.get_trades(),
             final_portfolio: self.portfolio_tracker.get_final_state(),
+            costs: self.portfolio_tracker.get_cost_breakdown(),
+            margin: self.portfolio_tracker.get_margin_report(),
+            options: self.options_report.read().clone(),
+            benchmark,
         })
     }
     
+    /// Benchmark-relative metrics, if a benchmark was configured and loaded
+    /// 
+    /// A benchmark that cannot be measured against is logged rather than
+    /// failing a run that otherwise completed.
+    fn benchmark_metrics(&self, equity_curve: &[(DateTime<Utc>, f64)]) -> Option<BenchmarkMetrics> {
+        let config = self.config.benchmark.as_ref()?;
+        let prices = self.benchmark_prices.read();
+        if prices.is_empty() {
+            warn!("Benchmark {} was configured but not loaded", config.symbol);
+            return None;
+        }
+        self.performance_analyzer
+            .calculate_benchmark_metrics(equity_curve, config, &prices, self.config.risk_free_rate)
+            .inspect_err(|e| warn!("Skipping benchmark {}: {:#}", config.symbol, e))
+            .ok()
+    }
+    
     fn get_market_snapshot(&self, timestamp: DateTime<Utc>) -> Result<MarketSnapshot> {
         let snapshot = MarketSnapshot {
             timestamp,
             prices: DashMap::new(),
+            quotes: DashMap::new(),
         };
         
         for entry in self.market_data.price_data.iter() {
@@ -640,31 +1154,288 @@ impl BacktestEngine {
         Ok(snapshot)
     }
     
-    fn get_portfolio_state(&self) -> PortfolioState {
-        self.portfolio_tracker.get_current_state()
+    /// Books fills into the portfolio, returning those that were accepted
+    /// 
+    /// Fills the portfolio cannot pay for are reported as rejections.
+    fn apply_fills(&self, fills: &[Fill]) -> Vec<Fill> {
+        let mut booked = Vec::with_capacity(fills.len());
+        for fill in fills {
+            match self.portfolio_tracker.process_fill(fill) {
+                Ok(completed) => {
+                    self.state.write().trades_executed += 1;
+                    if let Some(trade) = completed {
+                        self.performance_analyzer.record_trade(trade);
+                    }
+                    booked.push(fill.clone());
+                }
+                Err(e) => {
+                    warn!("Fill {} not booked: {}", fill.order_id, e);
+                    let reason = if fill.side == OrderSide::Sell && !self.portfolio_tracker.is_margined(&fill.symbol) {
+                        RejectReason::InsufficientPosition
+                    } else {
+                        RejectReason::InsufficientFunds
+                    };
+                    self.execution_simulator.record_rejection(&fill.order_id, reason, fill.timestamp);
+                }
+            }
+        }
+        booked
     }
     
-    async fn process_signal(&self, signal: TradingSignal, timestamp: DateTime<Utc>) -> Result<()> {
-        // Convert signal to order
-        let order = self.signal_to_order(signal, timestamp)?;
+    /// Books new fills and sends them, then any new rejections, to the strategy
+    /// 
+    /// Each report arrives after its acknowledgement delay.
+    fn book_executions(&self, cursor: &mut ExecutionCursor) {
+        let fills = self.execution_simulator.fills_since(cursor.fills);
+        cursor.fills += fills.len();
+        for fill in self.apply_fills(&fills) {
+            let arrival = self.latency.arrival(LatencyChannel::Ack, &fill.symbol, fill.timestamp);
+            cursor.reports.push(arrival, ExecutionReport::Fill(fill));
+        }
         
-        // Submit order to execution simulator
-        self.execution_simulator.submit_order(order)?;
+        let rejections = self.execution_simulator.rejections_since(cursor.rejections);
+        cursor.rejections += rejections.len();
+        for rejection in rejections {
+            let symbol = self.order_symbols.get(&rejection.order_id).map(|symbol| symbol.clone()).unwrap_or_default();
+            let arrival = self.latency.arrival(LatencyChannel::Ack, &symbol, rejection.timestamp);
+            cursor.reports.push(arrival, ExecutionReport::Rejection(rejection));
+        }
+    }
+    
+    /// Books new fills, then delivers every report that has reached the strategy
+    /// 
+    /// Hooks see `view` stamped with the time the report arrived.
+    fn settle_executions<S: Strategy + ?Sized>(
+        &self,
+        strategy: &mut S,
+        view: &MarketSnapshot,
+        cursor: &mut ExecutionCursor,
+    ) -> Result<()> {
+        self.book_executions(cursor);
+        let now = self.state.read().current_time;
+        while let Some((arrival, report)) = cursor.reports.pop_arrived(now) {
+            let restamped;
+            let market = if arrival == view.timestamp {
+                view
+            } else {
+                restamped = view.restamped(arrival);
+                &restamped
+            };
+            match report {
+                ExecutionReport::Fill(fill) => self.with_context(market, strategy, |strategy, ctx| strategy.on_fill(&fill, ctx))?,
+                ExecutionReport::Rejection(rejection) => {
+                    self.with_context(market, strategy, |strategy, ctx| strategy.on_order_rejected(&rejection, ctx))?
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    /// Accrues financing up to the snapshot time and liquidates every
+    /// margined position if equity is below maintenance margin
+    /// 
+    /// Positions are closed at the snapshot price with taker fees. The
+    /// liquidation fills are booked and sent to `Strategy::on_fill` like any
+    /// other; pending orders are left in place.
+    fn enforce_margin(&self, market: &MarketSnapshot, cursor: &mut ExecutionCursor) -> Result<()> {
+        self.portfolio_tracker.accrue_financing(market.timestamp);
+        let status = self.portfolio_tracker.check_margin(market.timestamp);
+        if !status.is_below_maintenance() {
+            return Ok(());
+        }
         
+        warn!("Equity {:.2} below maintenance margin {:.2} at {}, liquidating",
+              status.equity, status.maintenance_requirement, market.timestamp);
+        for position in self.portfolio_tracker.margined_positions() {
+            let side = if position.quantity > 0.0 { OrderSide::Sell } else { OrderSide::Buy };
+            let order_id = self.execution_simulator.liquidate(
+                &position.symbol,
+                side,
+                position.quantity.abs(),
+                position.current_price,
+                market.timestamp,
+            );
+            self.portfolio_tracker.record_liquidation(LiquidationEvent {
+                timestamp: market.timestamp,
+                order_id,
+                symbol: position.symbol,
+                quantity: position.quantity,
+                price: position.current_price,
+                equity: status.equity,
+                maintenance_requirement: status.maintenance_requirement,
+            });
+        }
+        self.book_executions(cursor);
         Ok(())
     }
     
-    fn signal_to_order(&self, signal: TradingSignal, timestamp: DateTime<Utc>) -> Result<Order> {
-        Ok(Order {
-            id: format!("BT_{}", uuid::Uuid::new_v4()),
-            symbol: signal.symbol,
-            side: signal.side,
-            order_type: signal.order_type,
-            quantity: signal.quantity,
-            price: signal.price,
-            timestamp,
-            time_in_force: TimeInForce::GTC,
-        })
+    /// Adds model prices for unquoted options that are held, have pending
+    /// orders or are listed in `OptionsConfig::contracts`
+    fn mark_options(&self, market: &MarketSnapshot) {
+        let mut symbols: BTreeSet<String> = self.portfolio_tracker.positions.iter().map(|e| e.key().clone()).collect();
+        symbols.extend(self.execution_simulator.pending_symbols());
+        symbols.extend(self.orders_in_flight.read().iter().flat_map(OrderCommand::placed_orders).map(|order| order.symbol.clone()));
+        self.option_pricer.mark(market, symbols.iter().map(String::as_str));
+    }
+    
+    /// Closes option positions past expiry at intrinsic value
+    /// 
+    /// Options settle against the underlying's last price at or before
+    /// expiry. Pending orders in expired contracts are cancelled and
+    /// reported as `RejectReason::Unfilled`; the settlement fills carry no
+    /// costs and are sent to `Strategy::on_fill` like any other.
+    fn settle_expiries(&self, market: &MarketSnapshot, cursor: &mut ExecutionCursor) -> Result<()> {
+        for order in self.execution_simulator.open_orders() {
+            let expired = self.option_pricer.contract(&order.symbol)
+                .is_some_and(|contract| contract.is_expired(market.timestamp));
+            if expired && self.execution_simulator.cancel_order(&order.id).is_ok() {
+                self.execution_simulator.record_rejection(&order.id, RejectReason::Unfilled, market.timestamp);
+            }
+        }
+        
+        for position in self.portfolio_tracker.get_current_state().positions {
+            let Some(contract) = self.option_pricer.contract(&position.symbol) else {
+                continue;
+            };
+            if !contract.is_expired(market.timestamp) || position.quantity == 0.0 {
+                continue;
+            }
+            let Some(underlying_price) = self.underlying_price_at(&contract.underlying, contract.expiry, market) else {
+                warn!("No price for {} to settle {} against", contract.underlying, contract.symbol);
+                continue;
+            };
+            let settlement_price = contract.intrinsic_value(underlying_price);
+            let side = if position.quantity > 0.0 { OrderSide::Sell } else { OrderSide::Buy };
+            let order_id = self.execution_simulator.settle_expiry(
+                &position.symbol,
+                side,
+                position.quantity.abs(),
+                settlement_price,
+                market.timestamp,
+            );
+            self.options_report.write().settlements.push(ExpirySettlement {
+                timestamp: market.timestamp,
+                order_id,
+                symbol: position.symbol,
+                quantity: position.quantity,
+                underlying_price,
+                settlement_price,
+            });
+        }
+        self.book_executions(cursor);
+        Ok(())
+    }
+    
+    /// Last bar close of `symbol` at or before `at`, falling back to the
+    /// snapshot price
+    fn underlying_price_at(&self, symbol: &str, at: DateTime<Utc>, market: &MarketSnapshot) -> Option<f64> {
+        self.market_data.price_data
+            .get(symbol)
+            .and_then(|bars| bars.range(..=at).last().map(|(_, ohlcv)| ohlcv.close))
+            .or_else(|| market.prices.get(symbol).map(|price| *price))
+    }
+    
+    /// Adds the option book's Greeks to the report when options are held
+    fn record_greeks(&self, market: &MarketSnapshot) {
+        let positions = self.portfolio_tracker.get_current_state().positions;
+        let by_underlying = self.option_pricer.exposure(&positions, market);
+        if !by_underlying.is_empty() {
+            self.options_report.write().greeks.push(GreeksPoint {
+                timestamp: market.timestamp,
+                by_underlying,
+            });
+        }
+    }
+    
+    fn get_portfolio_state(&self) -> PortfolioState {
+        self.portfolio_tracker.get_current_state()
+    }
+    
+    /// Calls a strategy hook with a fresh context and sends the order
+    /// instructions it issued
+    /// 
+    /// The context lists orders still on their way to the book after the
+    /// pending ones. An error the strategy raised in the hook fails the run.
+    fn with_context<S, F>(&self, market: &MarketSnapshot, strategy: &mut S, hook: F) -> Result<()>
+    where
+        S: Strategy + ?Sized,
+        F: FnOnce(&mut S, &mut StrategyContext<'_>),
+    {
+        let portfolio = self.get_portfolio_state();
+        let mut open_orders = self.execution_simulator.open_orders();
+        open_orders.extend(self.orders_in_flight.read().iter().flat_map(OrderCommand::working_orders).cloned());
+        let mut ctx = StrategyContext::new(market, &portfolio, &open_orders);
+        hook(strategy, &mut ctx);
+        if let Some(error) = strategy.take_error() {
+            return Err(error.context("Strategy failed"));
+        }
+        self.send_commands(ctx.into_commands(), market.timestamp)
+    }
+    
+    /// Sends order instructions issued at `sent_at` towards the book
+    /// 
+    /// Instructions travel with the order entry delay of their symbol's
+    /// venue; those that have already arrived are carried out immediately.
+    fn send_commands(&self, commands: Vec<OrderCommand>, sent_at: DateTime<Utc>) -> Result<()> {
+        for command in commands {
+            for order in command.placed_orders() {
+                self.order_symbols.insert(order.id.clone(), order.symbol.clone());
+            }
+            let symbol = self.order_symbols.get(command.order_id()).map(|symbol| symbol.clone()).unwrap_or_default();
+            let arrival = self.latency.arrival(LatencyChannel::OrderEntry, &symbol, sent_at);
+            self.orders_in_flight.write().push(arrival, command);
+        }
+        let now = self.state.read().current_time;
+        self.release_orders(now)
+    }
+    
+    /// Carries out the order instructions that have reached the book by `now`
+    fn release_orders(&self, now: DateTime<Utc>) -> Result<()> {
+        loop {
+            let Some((arrival, command)) = self.orders_in_flight.write().pop_arrived(now) else {
+                return Ok(());
+            };
+            self.apply_command(command, arrival)?;
+        }
+    }
+    
+    /// Submits, amends or cancels an order for the strategy
+    /// 
+    /// Instructions for orders that are no longer pending are reported back
+    /// as `RejectReason::UnknownOrder` rejections, and every order of an
+    /// invalid contingent group as `RejectReason::InvalidOrder`.
+    fn apply_command(&self, command: OrderCommand, timestamp: DateTime<Utc>) -> Result<()> {
+        match command {
+            OrderCommand::Place(order) => {
+                self.execution_simulator.submit_order(order)?;
+                self.state.write().orders_processed += 1;
+            }
+            OrderCommand::PlaceContingent(group) => {
+                let order_ids: Vec<String> = group.orders().iter().map(|order| order.id.clone()).collect();
+                match self.execution_simulator.submit_contingent(group) {
+                    Ok(()) => self.state.write().orders_processed += 1,
+                    Err(e) => {
+                        warn!("Contingent order {} refused: {}", order_ids[0], e);
+                        for order_id in &order_ids {
+                            self.execution_simulator.record_rejection(order_id, RejectReason::InvalidOrder, timestamp);
+                        }
+                    }
+                }
+            }
+            OrderCommand::Amend { order_id, quantity, price } => {
+                if let Err(e) = self.execution_simulator.amend_order(&order_id, quantity, price) {
+                    debug!("Amend refused: {}", e);
+                    self.execution_simulator.record_rejection(&order_id, RejectReason::UnknownOrder, timestamp);
+                }
+            }
+            OrderCommand::Cancel { order_id } => {
+                if let Err(e) = self.execution_simulator.cancel_order(&order_id) {
+                    debug!("Cancel refused: {}", e);
+                    self.execution_simulator.record_rejection(&order_id, RejectReason::UnknownOrder, timestamp);
+                }
+            }
+        }
+        Ok(())
     }
     
     fn advance_time(&self, current: DateTime<Utc>) -> DateTime<Utc> {
@@ -679,14 +1450,123 @@ impl BacktestEngine {
     }
 }
 
+/// Positions in the simulator's fill and rejection histories already
+/// booked, and the reports still on their way to the strategy
+#[derive(Debug, Default)]
+struct ExecutionCursor {
+    fills: usize,
+    rejections: usize,
+    reports: InFlight<ExecutionReport>,
+}
+
+/// Fill or rejection sent to the strategy
+#[derive(Debug)]
+enum ExecutionReport {
+    Fill(Fill),
+    Rejection(OrderRejection),
+}
+
+/// Increment of the SplitMix64 generator state
+pub(crate) const SPLITMIX_GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// Maps a SplitMix64 state to a uniform draw in `[0, 1)`
+/// 
+/// Seeded simulations use this instead of an RNG crate so runs are
+/// reproducible for a given seed.
+pub(crate) fn splitmix_unit(state: u64) -> f64 {
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    (z >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Whether `price` has reached the stop price of a stop order on `side`
+fn stop_triggered(side: OrderSide, price: f64, stop: f64) -> bool {
+    match side {
+        OrderSide::Buy => price >= stop,
+        OrderSide::Sell => price <= stop,
+    }
+}
+
+/// Mid price of a two-sided book
+fn book_mid(book: &OrderbookSnapshot) -> Option<f64> {
+    match (book.bids.first(), book.asks.first()) {
+        (Some((bid, _)), Some((ask, _))) => Some((bid + ask) / 2.0),
+        _ => None,
+    }
+}
+
+/// Whether a resting level at `price` is within an order's limit
+fn crosses_limit(side: OrderSide, price: f64, limit: Option<f64>) -> bool {
+    match (side, limit) {
+        (_, None) => true,
+        (OrderSide::Buy, Some(limit)) => price <= limit,
+        (OrderSide::Sell, Some(limit)) => price >= limit,
+    }
+}
+
 // Additional trait definitions and implementations
 
 /// Strategy trait that users must implement
+/// 
+/// The engine drives a strategy through its lifecycle: `on_start` once before
+/// the first step, `on_bar` for every time step of a bar backtest (or
+/// `on_event` for every replayed order book event), `on_fill` and
+/// `on_order_rejected` as its orders are executed or refused, and `on_end`
+/// once the data is exhausted. Hooks take `&mut self`, so strategies can keep
+/// indicator state between calls, and receive a `StrategyContext` through
+/// which they place, amend and cancel orders by ID.
+/// 
+/// Every hook has a default. Stateless strategies only need
+/// `generate_signals`, which the default `on_bar` submits as orders.
 pub trait Strategy: Send + Sync {
+    /// Called once before the first time step or event
+    fn on_start(&mut self, _ctx: &mut StrategyContext<'_>) {}
+    
+    /// Called on every time step of a bar backtest
+    /// 
+    /// The default submits the result of `generate_signals` as
+    /// good-till-cancelled orders.
+    fn on_bar(&mut self, ctx: &mut StrategyContext<'_>) {
+        for signal in self.generate_signals(ctx.market(), ctx.portfolio()) {
+            ctx.place(signal);
+        }
+    }
+    
+    /// Called after every replayed order book event in event-driven backtests
+    /// 
+    /// `ctx` already reflects the book after `event`. The default treats
+    /// each event as a bar.
+    fn on_event(&mut self, _event: &OrderBookEvent, ctx: &mut StrategyContext<'_>) {
+        self.on_bar(ctx);
+    }
+    
+    /// Called for each fill of the strategy's orders once it is booked
+    fn on_fill(&mut self, _fill: &Fill, _ctx: &mut StrategyContext<'_>) {}
+    
+    /// Called when an order, amendment or cancellation is refused, or an
+    /// order is cancelled by the simulator without filling
+    fn on_order_rejected(&mut self, _rejection: &OrderRejection, _ctx: &mut StrategyContext<'_>) {}
+    
+    /// Called once after the last time step or event
+    fn on_end(&mut self, _ctx: &mut StrategyContext<'_>) {}
+    
+    /// Error that stops the run, raised by the hook that was just called
+    /// 
+    /// Hooks cannot return errors, so a strategy that hits one it cannot
+    /// recover from (such as a script exceeding its sandbox limits) keeps it
+    /// here. The engine checks after every hook and fails the run with it,
+    /// discarding the orders that hook issued.
+    fn take_error(&mut self) -> Option<anyhow::Error> {
+        None
+    }
+    
     /// Generates trading signals based on current market conditions and portfolio state
     /// 
-    /// This is the core method that strategy implementations must provide. It analyzes
-    /// the current market snapshot and portfolio state to make trading decisions.
+    /// Stateless strategies can implement just this method; the default `on_bar`
+    /// turns its signals into orders. Strategies that override `on_bar` may
+    /// leave it returning no signals.
     /// 
     /// # Parameters
     /// 
@@ -722,7 +1602,9 @@ pub trait Strategy: Send + Sync {
     ///     }
     /// }
     /// ```
-    fn generate_signals(&self, market: &MarketSnapshot, portfolio: &PortfolioState) -> Vec<TradingSignal>;
+    fn generate_signals(&self, _market: &MarketSnapshot, _portfolio: &PortfolioState) -> Vec<TradingSignal> {
+        Vec::new()
+    }
 }
 
 /// Point-in-time snapshot of market conditions
@@ -735,6 +1617,33 @@ pub struct MarketSnapshot {
     pub timestamp: DateTime<Utc>,
     /// Current prices for all symbols (symbol -> price mapping)
     pub prices: DashMap<String, f64>,
+    /// Best bid and offer of symbols with a two-sided book
+    ///
+    /// Only populated in event-driven backtests; bars carry no quotes.
+    pub quotes: DashMap<String, Quote>,
+}
+
+impl MarketSnapshot {
+    /// Copy of the snapshot stamped with `timestamp`
+    pub fn restamped(&self, timestamp: DateTime<Utc>) -> Self {
+        Self {
+            timestamp,
+            ..self.clone()
+        }
+    }
+}
+
+/// Best bid and offer of one symbol
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Quote {
+    /// Best bid price
+    pub bid_price: f64,
+    /// Quantity at the best bid
+    pub bid_size: f64,
+    /// Best ask price
+    pub ask_price: f64,
+    /// Quantity at the best ask
+    pub ask_size: f64,
 }
 
 /// Current state of the portfolio including cash and positions
@@ -781,6 +1690,18 @@ pub struct BacktestResult {
     pub trades: Vec<CompletedTrade>,
     /// Final state of the portfolio at backtest completion
     pub final_portfolio: PortfolioState,
+    /// Trading costs paid over the run, split by component
+    #[serde(default)]
+    pub costs: CostBreakdown,
+    /// Financing, margin calls and liquidations over the run
+    #[serde(default)]
+    pub margin: MarginReport,
+    /// Option settlements and Greeks exposure over the run
+    #[serde(default)]
+    pub options: OptionsReport,
+    /// Performance relative to `BacktestConfig::benchmark`, when one was loaded
+    #[serde(default)]
+    pub benchmark: Option<BenchmarkMetrics>,
 }
 
 // Implementation stubs for sub-components
@@ -807,9 +1728,38 @@ impl MarketDataStore {
         Self {
             price_data: DashMap::new(),
             orderbook_snapshots: DashMap::new(),
+            book_events: DashMap::new(),
         }
     }
     
+    /// Whether any order book event streams have been loaded
+    pub fn has_book_events(&self) -> bool {
+        !self.book_events.is_empty()
+    }
+    
+    /// Bars of each symbol stamped after `after` and up to `until`
+    /// 
+    /// Several bars in the interval are merged into one, stamped with the
+    /// first bar's time.
+    pub fn bars_between(&self, after: DateTime<Utc>, until: DateTime<Utc>) -> HashMap<String, (DateTime<Utc>, OHLCV)> {
+        use std::ops::Bound::{Excluded, Included};
+        self.price_data
+            .iter()
+            .filter_map(|entry| {
+                let mut bars = entry.value().range((Excluded(after), Included(until)));
+                let (start, first) = bars.next()?;
+                let merged = bars.fold(first.clone(), |merged, (_, bar)| OHLCV {
+                    open: merged.open,
+                    high: merged.high.max(bar.high),
+                    low: merged.low.min(bar.low),
+                    close: bar.close,
+                    volume: merged.volume + bar.volume,
+                });
+                Some((entry.key().clone(), (*start, merged)))
+            })
+            .collect()
+    }
+    
     /// Store orderbook snapshot for a symbol
     pub fn add_orderbook_snapshot(&self, symbol: &str, timestamp: DateTime<Utc>, snapshot: OrderbookSnapshot) {
         self.orderbook_snapshots
@@ -846,22 +1796,39 @@ impl ExecutionSimulator {
     /// # Examples
     /// 
     /// ```
-    /// use backtesting::{ExecutionSimulator, ExecutionConfig};
+    /// use backtesting::{ExecutionSimulator, ExecutionConfig, FillModel};
     /// 
     /// let config = ExecutionConfig {
     ///     use_limit_order_book: true,
     ///     partial_fills: true,
     ///     reject_rate: 0.001, // 0.1% rejection rate
+    ///     commission_rate: 0.001,
+    ///     commission_model: None,
+    ///     fill_model: FillModel::Touch,
+    ///     intrabar_ordering: Default::default(),
     /// };
     /// let simulator = ExecutionSimulator::new(config);
     /// ```
     pub fn new(config: ExecutionConfig) -> Self {
         use std::sync::atomic::AtomicU64;
+        let seed = match config.fill_model {
+            FillModel::Probabilistic { seed, .. } => seed,
+            _ => 0,
+        };
+        let commission_model = config.commission_model
+            .clone()
+            .unwrap_or_else(|| Arc::new(FlatCommission { rate: config.commission_rate }));
         Self {
             pending_orders: Arc::new(DashMap::new()),
             fill_history: Arc::new(RwLock::new(Vec::new())),
+            rejection_history: Arc::new(RwLock::new(Vec::new())),
             config,
             rejection_counter: Arc::new(AtomicU64::new(0)),
+            queue_positions: Arc::new(DashMap::new()),
+            fill_rng_state: Arc::new(AtomicU64::new(seed)),
+            commission_model,
+            order_brokerage: Arc::new(DashMap::new()),
+            contingencies: Arc::new(RwLock::new(ContingencyBook::default())),
         }
     }
     
@@ -892,6 +1859,7 @@ impl ExecutionSimulator {
     ///     order_type: OrderType::Market,
     ///     quantity: 100.0,
     ///     price: None,
+    ///     stop_price: None,
     ///     timestamp: Utc::now(),
     ///     time_in_force: TimeInForce::GTC,
     /// };
@@ -922,7 +1890,12 @@ impl ExecutionSimulator {
     /// # Behavior
     /// 
     /// - Market orders are filled immediately at current market price
-    /// - Limit orders are filled only when price conditions are met
+    /// - Limit orders are filled only when price conditions are met; under
+    ///   `FillModel::Probabilistic` a touched order fills with the configured
+    ///   probability on each call
+    /// - Stop orders fill at the market price once it reaches their stop
+    ///   price, and stop-limit orders become limit orders; trailing stops
+    ///   move their stop price first
     /// - Orders may be rejected based on configured rejection rate
     /// - Partial fills may occur if configured
     /// 
@@ -935,89 +1908,667 @@ impl ExecutionSimulator {
     /// let market = MarketSnapshot {
     ///     timestamp: Utc::now(),
     ///     prices: DashMap::new(),
+    ///     quotes: DashMap::new(),
     /// };
     /// market.prices.insert("AAPL".to_string(), 150.0);
     /// 
     /// simulator.process_pending_orders(&market, Utc::now())?;
     /// ```
     pub fn process_pending_orders(&self, market: &MarketSnapshot, timestamp: DateTime<Utc>) -> Result<()> {
-        // Process orders with configuration-based behavior
-        let orders: Vec<_> = self.pending_orders.iter().map(|e| e.value().clone()).collect();
-        
-        for order in orders {
-            // Check for deterministic rejection based on config
-            if self.config.reject_rate > 0.0 {
-                use std::sync::atomic::Ordering;
-                let counter = self.rejection_counter.fetch_add(1, Ordering::Relaxed);
-                let threshold = (self.config.reject_rate * REJECTION_RATE_PRECISION as f64) as u64;
-                let should_reject = (counter % REJECTION_RATE_PRECISION) < threshold;
-                
-                if should_reject {
-                    warn!("Order {} rejected due to simulated rejection (rate: {:.2}%)", 
-                          order.id, self.config.reject_rate * 100.0);
-                    self.pending_orders.remove(&order.id);
+        self.process_pending_orders_intrabar(market, &HashMap::new(), timestamp)
+    }
+    
+    /// Processes pending orders against the bars completed since the last call
+    /// 
+    /// `bars` holds the start time and prices of each symbol's new bar.
+    /// Limit and stop orders working before their symbol's bar started are
+    /// walked along the bar's `IntrabarOrdering` path (see the `contingent`
+    /// module); market orders and orders placed since are checked against
+    /// the snapshot price as in `process_pending_orders`.
+    /// 
+    /// # Behavior
+    /// 
+    /// - OCO legs are walked together along the path of the older leg, so
+    ///   the leg whose price is reached first fills
+    /// - Bracket exits armed within the bar follow the rest of their entry's
+    ///   path
+    /// - Stops triggered at the first price of a path fill at that price,
+    ///   later ones at their stop price
+    pub fn process_pending_orders_intrabar(
+        &self,
+        market: &MarketSnapshot,
+        bars: &HashMap<String, (DateTime<Utc>, OHLCV)>,
+        timestamp: DateTime<Utc>,
+    ) -> Result<()> {
+        let mut walked = HashSet::new();
+        for order in self.open_orders() {
+            if !walked.insert(order.id.clone()) {
+                continue;
+            }
+            let sibling = self.contingencies.read().sibling(&order.id).cloned();
+            let mut legs = vec![order.id.clone()];
+            legs.extend(sibling.filter(|sibling| walked.insert(sibling.clone())));
+            // Linked fills may have changed the legs since the listing
+            legs.retain(|id| self.pending_order(id).is_some_and(|leg| !self.reject_if_simulated(&leg, timestamp)));
+            let Some(first) = legs.first().and_then(|id| self.pending_order(id)) else {
+                continue;
+            };
+            
+            let Some(price) = market.prices.get(&first.symbol).map(|price| *price) else {
+                continue;
+            };
+            let path = match bars.get(&first.symbol) {
+                Some((start, bar)) if first.timestamp < *start && first.order_type != OrderType::Market => {
+                    self.config.intrabar_ordering.path(bar, first.side).to_vec()
+                }
+                _ => vec![price],
+            };
+            self.walk_path(&legs, &path, 0, timestamp, &mut walked)?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Moves linked `legs` through `path` from `start`, filling them where
+    /// they trigger or are touched
+    fn walk_path(
+        &self,
+        legs: &[String],
+        path: &[f64],
+        start: usize,
+        timestamp: DateTime<Utc>,
+        walked: &mut HashSet<String>,
+    ) -> Result<()> {
+        let mut draws = HashMap::new();
+        for (index, &price) in path.iter().enumerate().skip(start) {
+            for id in legs {
+                let Some(order) = self.pending_order(id) else {
+                    continue;
+                };
+                let exits = self.contingencies.read().exits(id);
+                if !self.step_order(order, price, index == 0, &mut draws, timestamp)? {
+                    continue;
+                }
+                let armed: Vec<String> = exits
+                    .into_iter()
+                    .flatten()
+                    .filter(|exit| self.pending_orders.contains_key(exit) && walked.insert(exit.clone()))
+                    .collect();
+                if !armed.is_empty() {
+                    self.walk_path(&armed, path, index, timestamp, walked)?;
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    /// Moves an order to `price`, the next price on its path, and fills it
+    /// if it triggers or is touched
+    /// 
+    /// `gapped` marks a price reached without trading through the prices
+    /// before it. Stops triggered there fill at that price rather than at
+    /// their stop price. Returns whether the order filled.
+    fn step_order(
+        &self,
+        mut order: Order,
+        price: f64,
+        gapped: bool,
+        draws: &mut HashMap<String, bool>,
+        timestamp: DateTime<Utc>,
+    ) -> Result<bool> {
+        let mut reached = price;
+        if matches!(order.order_type, OrderType::Stop | OrderType::StopLimit) {
+            let Some(stop) = self.update_stop(&mut order, price) else {
+                return Ok(false);
+            };
+            if !stop_triggered(order.side, price, stop) {
+                return Ok(false);
+            }
+            reached = if gapped { price } else { stop };
+            if order.order_type == OrderType::Stop {
+                debug!("Stop order {} triggered at {}", order.id, reached);
+                self.record_fill(&order, order.quantity, reached, 0.0001 * reached, Liquidity::Taker, timestamp);
+                self.remove_pending(&order.id);
+                return Ok(true);
+            }
+            order.order_type = OrderType::Limit;
+            self.pending_orders.insert(order.id.clone(), order.clone());
+        }
+        
+        match order.order_type {
+            OrderType::Market => {
+                self.execute_fill(order, price, timestamp)?;
+                Ok(true)
+            }
+            OrderType::Limit => {
+                let Some(limit) = order.price else {
+                    return Ok(false);
+                };
+                if !crosses_limit(order.side, reached, Some(limit))
+                    || !*draws.entry(order.id.clone()).or_insert_with(|| self.passive_fill_drawn())
+                {
+                    return Ok(false);
+                }
+                self.execute_fill(order, limit, timestamp)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+    
+    /// Current stop price of a stop order, after moving it if it trails `price`
+    fn update_stop(&self, order: &mut Order, price: f64) -> Option<f64> {
+        let trailed = self.contingencies.write().trail(order, price);
+        if let Some(stop) = trailed.filter(|stop| order.stop_price != Some(*stop)) {
+            order.stop_price = Some(stop);
+            if let Some(mut pending) = self.pending_orders.get_mut(&order.id) {
+                pending.stop_price = Some(stop);
+            }
+        }
+        order.stop_price
+    }
+    
+    /// Current state of a pending order
+    fn pending_order(&self, order_id: &str) -> Option<Order> {
+        self.pending_orders.get(order_id).map(|order| order.value().clone())
+    }
+    
+    /// Matches pending orders for `symbol` against order book depth
+    /// 
+    /// Market and marketable limit orders walk the opposite side of `book`
+    /// from the touch, taking liquidity level by level up to their limit
+    /// price. The fill is booked at the volume-weighted price of the levels
+    /// taken, so the cost of walking the book shows up in the fill price.
+    /// 
+    /// # Behavior
+    /// 
+    /// - Orders are matched in submission order, and liquidity taken by one
+    ///   order is not available to the next within the same call
+    /// - With `partial_fills` the available quantity is filled and the rest
+    ///   stays pending; without it, orders wait until the book can fill them
+    ///   completely
+    /// - `IOC` orders cancel any unfilled remainder and `FOK` orders cancel
+    ///   unless they can be filled completely
+    /// - Non-marketable limit orders rest until the book crosses them
+    /// - Stops trigger on the mid price; triggered stops become market
+    ///   orders and stop-limits become limit orders
+    pub fn process_orders_against_book(&self, symbol: &str, book: &OrderbookSnapshot, timestamp: DateTime<Utc>) -> Result<()> {
+        let mut orders: Vec<_> = self.pending_orders
+            .iter()
+            .filter(|e| e.value().symbol == symbol)
+            .map(|e| e.value().clone())
+            .collect();
+        orders.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then_with(|| a.id.cmp(&b.id)));
+        
+        let mut bids = book.bids.clone();
+        let mut asks = book.asks.clone();
+        
+        for queued in orders {
+            // Linked fills may have changed the order since the listing
+            let Some(mut order) = self.pending_order(&queued.id) else {
+                continue;
+            };
+            if self.reject_if_simulated(&order, timestamp) {
+                continue;
+            }
+            
+            if matches!(order.order_type, OrderType::Stop | OrderType::StopLimit) {
+                let Some(mid) = book_mid(book) else {
+                    continue;
+                };
+                let Some(stop) = self.update_stop(&mut order, mid) else {
+                    continue;
+                };
+                if !stop_triggered(order.side, mid, stop) {
                     continue;
                 }
+                debug!("Stop order {} triggered at mid {}", order.id, mid);
+                order.order_type = match order.order_type {
+                    OrderType::Stop => OrderType::Market,
+                    _ => OrderType::Limit,
+                };
+                self.pending_orders.insert(order.id.clone(), order.clone());
             }
             
-            if let Some(price) = market.prices.get(&order.symbol) {
-                // Simulate fill based on order type
-                match order.order_type {
-                    OrderType::Market => {
-                        self.execute_fill(order, *price, timestamp)?;
-                    }
-                    OrderType::Limit => {
-                        if let Some(limit_price) = order.price {
-                            let should_fill = match order.side {
-                                OrderSide::Buy => *price <= limit_price,
-                                OrderSide::Sell => *price >= limit_price,
-                            };
-                            if should_fill && self.config.use_limit_order_book {
-                                // When using LOB simulation, may only partially fill
-                                if self.config.partial_fills {
-                                    debug!("Processing potential partial fill for order {}", order.id);
-                                }
-                                self.execute_fill(order, limit_price, timestamp)?;
-                            } else if should_fill {
-                                self.execute_fill(order, limit_price, timestamp)?;
-                            }
-                        }
+            let limit = match order.order_type {
+                OrderType::Market => None,
+                OrderType::Limit => match order.price {
+                    Some(price) => Some(price),
+                    None => continue,
+                },
+                _ => continue,
+            };
+            let levels = match order.side {
+                OrderSide::Buy => &mut asks,
+                OrderSide::Sell => &mut bids,
+            };
+            
+            let available: f64 = levels.iter()
+                .take_while(|(price, _)| crosses_limit(order.side, *price, limit))
+                .map(|(_, quantity)| quantity)
+                .sum();
+            let complete = available >= order.quantity;
+            let must_complete = order.time_in_force == TimeInForce::FOK || !self.config.partial_fills;
+            
+            if available <= 0.0 || (must_complete && !complete) {
+                if matches!(order.time_in_force, TimeInForce::IOC | TimeInForce::FOK) {
+                    debug!("Cancelling {:?} order {}: book cannot fill it", order.time_in_force, order.id);
+                    self.remove_pending(&order.id);
+                    self.record_rejection(&order.id, RejectReason::Unfilled, timestamp);
+                } else {
+                    self.join_queue(&order, book);
+                }
+                continue;
+            }
+            
+            let mut remaining = order.quantity;
+            let mut notional = 0.0;
+            for (price, quantity) in levels.iter_mut() {
+                if remaining <= 0.0 || !crosses_limit(order.side, *price, limit) {
+                    break;
+                }
+                let take = remaining.min(*quantity);
+                notional += take * *price;
+                *quantity -= take;
+                remaining -= take;
+            }
+            levels.retain(|(_, quantity)| *quantity > 0.0);
+            
+            let filled = order.quantity - remaining;
+            self.record_fill(&order, filled, notional / filled, 0.0, Liquidity::Taker, timestamp);
+            
+            if remaining > 0.0 && order.time_in_force != TimeInForce::IOC {
+                order.quantity = remaining;
+                self.join_queue(&order, book);
+                self.pending_orders.insert(order.id.clone(), order);
+            } else {
+                self.remove_pending(&order.id);
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Updates resting limit orders for `symbol` after a replayed book event
+    /// 
+    /// Applies the configured `FillModel` to passive orders: under `Touch` a
+    /// trade at or through the limit price fills the order, while queue
+    /// tracking fills only the traded volume that reaches past the queue
+    /// ahead. `book` must already include `event`.
+    pub fn on_book_event(&self, symbol: &str, event: &OrderBookEvent, book: &LiveBook, timestamp: DateTime<Utc>) -> Result<()> {
+        let resting: Vec<_> = self.pending_orders
+            .iter()
+            .filter(|e| e.value().symbol == symbol && e.value().order_type == OrderType::Limit)
+            .map(|e| e.value().clone())
+            .collect();
+        
+        for queued in resting {
+            let Some(mut order) = self.pending_order(&queued.id) else {
+                continue;
+            };
+            let Some(limit) = order.price else {
+                continue;
+            };
+            let book_side = match order.side {
+                OrderSide::Buy => BookSide::Buy,
+                OrderSide::Sell => BookSide::Sell,
+            };
+            let level_quantity = book.quantity_at(book_side, Px::new(limit)).as_f64();
+            
+            let filled = match event {
+                OrderBookEvent::Trade(trade) if trade.aggressor_side != book_side => {
+                    let through = match order.side {
+                        OrderSide::Buy => trade.price < Px::new(limit),
+                        OrderSide::Sell => trade.price > Px::new(limit),
+                    };
+                    let at_limit = trade.price == Px::new(limit);
+                    if through || (at_limit && self.config.fill_model == FillModel::Touch) {
+                        order.quantity
+                    } else if at_limit {
+                        self.consume_queue(&order.id, trade.quantity.as_f64(), level_quantity)
+                            .min(order.quantity)
+                    } else {
+                        0.0
                     }
-                    _ => {} // Other order types not implemented yet
                 }
+                OrderBookEvent::Trade(_) => 0.0,
+                _ => {
+                    self.observe_level(&order.id, level_quantity);
+                    0.0
+                }
+            };
+            
+            if filled <= 0.0 {
+                continue;
+            }
+            self.record_fill(&order, filled, limit, 0.0, Liquidity::Maker, timestamp);
+            if filled < order.quantity {
+                order.quantity -= filled;
+                self.pending_orders.insert(order.id.clone(), order);
+            } else {
+                self.remove_pending(&order.id);
             }
         }
         
         Ok(())
     }
     
+    /// Displayed quantity queued ahead of a resting order, if it is tracked
+    pub fn queue_ahead(&self, order_id: &str) -> Option<f64> {
+        self.queue_positions.get(order_id).map(|queue| queue.ahead)
+    }
+    
+    /// Starts queue tracking for an order resting at its limit price
+    fn join_queue(&self, order: &Order, book: &OrderbookSnapshot) {
+        if self.config.fill_model == FillModel::Touch || self.queue_positions.contains_key(&order.id) {
+            return;
+        }
+        let Some(limit) = order.price else {
+            return;
+        };
+        let levels = match order.side {
+            OrderSide::Buy => &book.bids,
+            OrderSide::Sell => &book.asks,
+        };
+        let level_quantity = levels.iter()
+            .find(|(price, _)| Px::new(*price) == Px::new(limit))
+            .map_or(0.0, |(_, quantity)| *quantity);
+        self.queue_positions.insert(order.id.clone(), QueuePosition {
+            ahead: level_quantity,
+            level_quantity,
+            unreconciled_trades: 0.0,
+        });
+    }
+    
+    /// Applies a trade at the order's level, returning the quantity that reached the order
+    fn consume_queue(&self, order_id: &str, traded: f64, level_quantity: f64) -> f64 {
+        let mut queue = self.queue_positions
+            .entry(order_id.to_string())
+            .or_insert(QueuePosition { ahead: level_quantity, level_quantity, unreconciled_trades: 0.0 });
+        queue.unreconciled_trades += traded;
+        let reached = (traded - queue.ahead).max(0.0);
+        queue.ahead = (queue.ahead - traded).max(0.0);
+        reached
+    }
+    
+    /// Reconciles a level size change with the queue ahead of the order
+    /// 
+    /// Shrinkage not explained by earlier trades is treated as cancellations
+    /// spread evenly through the level.
+    fn observe_level(&self, order_id: &str, level_quantity: f64) {
+        let mut queue = self.queue_positions
+            .entry(order_id.to_string())
+            .or_insert(QueuePosition { ahead: level_quantity, level_quantity, unreconciled_trades: 0.0 });
+        let decrease = queue.level_quantity - level_quantity;
+        if decrease > 0.0 {
+            let traded = decrease.min(queue.unreconciled_trades);
+            queue.unreconciled_trades -= traded;
+            let cancelled = decrease - traded;
+            if cancelled > 0.0 && queue.level_quantity > 0.0 {
+                queue.ahead -= cancelled * queue.ahead / queue.level_quantity;
+            }
+        }
+        queue.ahead = queue.ahead.clamp(0.0, level_quantity);
+        queue.level_quantity = level_quantity;
+    }
+    
+    /// Draws whether a touched passive order fills under the configured model
+    fn passive_fill_drawn(&self) -> bool {
+        let FillModel::Probabilistic { fill_probability, .. } = self.config.fill_model else {
+            return true;
+        };
+        let state = self.fill_rng_state
+            .fetch_add(SPLITMIX_GAMMA, Ordering::Relaxed)
+            .wrapping_add(SPLITMIX_GAMMA);
+        splitmix_unit(state) < fill_probability
+    }
+    
+    /// Removes an order and any queue state or links kept for it
+    fn remove_pending(&self, order_id: &str) {
+        self.pending_orders.remove(order_id);
+        self.queue_positions.remove(order_id);
+        self.order_brokerage.remove(order_id);
+        self.contingencies.write().forget(order_id);
+    }
+    
+    /// Submits a group of linked orders
+    /// 
+    /// See the `contingent` module for how the orders interact. Nothing is
+    /// submitted if the group is invalid.
+    pub fn submit_contingent(&self, group: ContingentOrder) -> Result<()> {
+        group.validate()?;
+        let working = self.contingencies.write().add(group);
+        for order in working {
+            self.submit_order(order)?;
+        }
+        Ok(())
+    }
+    
+    /// Carries `quantity` of `order` filling over to its linked orders
+    fn propagate_fill(&self, order: &Order, quantity: f64, timestamp: DateTime<Utc>) {
+        let effects = self.contingencies.write().on_fill(order, quantity, timestamp);
+        for exit in effects.arm {
+            debug!("Arming bracket exit {} for {} of {}", exit.id, quantity, order.id);
+            self.pending_orders.insert(exit.id.clone(), exit);
+        }
+        for order_id in effects.grow {
+            if let Some(mut exit) = self.pending_orders.get_mut(&order_id) {
+                exit.quantity += quantity;
+            }
+        }
+        let mut cancelled = effects.cancel;
+        for order_id in effects.shrink {
+            let remaining = match self.pending_orders.get_mut(&order_id) {
+                Some(mut sibling) => {
+                    sibling.quantity -= quantity;
+                    sibling.quantity
+                }
+                None => continue,
+            };
+            if remaining <= QUANTITY_EPSILON {
+                cancelled.push(order_id);
+            }
+        }
+        for order_id in cancelled {
+            if self.pending_orders.contains_key(&order_id) {
+                self.remove_pending(&order_id);
+                self.record_rejection(&order_id, RejectReason::LinkedOrderFilled, timestamp);
+            }
+        }
+    }
+    
+    /// Cancels a pending order
+    /// 
+    /// Fails if the order is not pending, e.g. because it already filled.
+    pub fn cancel_order(&self, order_id: &str) -> Result<()> {
+        if !self.pending_orders.contains_key(order_id) {
+            anyhow::bail!("Order {} is not pending", order_id);
+        }
+        self.remove_pending(order_id);
+        Ok(())
+    }
+    
+    /// Changes the remaining quantity and/or limit price of a pending order
+    /// 
+    /// Reducing the quantity keeps the order's queue position; a new price
+    /// or a larger quantity sends it to the back of the queue. The price of
+    /// a stop order is its stop price, which for a trailing stop becomes the
+    /// floor it trails from.
+    pub fn amend_order(&self, order_id: &str, quantity: Option<f64>, price: Option<f64>) -> Result<()> {
+        let Some(mut order) = self.pending_orders.get_mut(order_id) else {
+            anyhow::bail!("Order {} is not pending", order_id);
+        };
+        if quantity.is_some_and(|quantity| quantity <= 0.0) {
+            anyhow::bail!("Amended quantity for order {} must be positive", order_id);
+        }
+        let requeue = price.is_some_and(|price| order.price != Some(price))
+            || quantity.is_some_and(|quantity| quantity > order.quantity);
+        if let Some(quantity) = quantity {
+            order.quantity = quantity;
+        }
+        if price.is_some() {
+            if order.order_type == OrderType::Stop {
+                order.stop_price = price;
+            } else {
+                order.price = price;
+            }
+        }
+        drop(order);
+        if requeue {
+            self.queue_positions.remove(order_id);
+        }
+        Ok(())
+    }
+    
+    /// Pending orders, oldest first
+    pub fn open_orders(&self) -> Vec<Order> {
+        let mut orders: Vec<_> = self.pending_orders.iter().map(|e| e.value().clone()).collect();
+        orders.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then_with(|| a.id.cmp(&b.id)));
+        orders
+    }
+    
+    /// Symbols that currently have pending orders
+    pub fn pending_symbols(&self) -> Vec<String> {
+        self.pending_orders
+            .iter()
+            .map(|e| e.value().symbol.clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+    
+    /// Returns fills recorded after the first `start` entries of the fill history
+    /// 
+    /// The engine keeps a cursor into the history so each fill is booked
+    /// into the portfolio exactly once.
+    pub fn fills_since(&self, start: usize) -> Vec<Fill> {
+        self.fill_history.read().get(start..).map(<[Fill]>::to_vec).unwrap_or_default()
+    }
+    
+    /// Returns rejections recorded after the first `start` entries, like `fills_since`
+    pub fn rejections_since(&self, start: usize) -> Vec<OrderRejection> {
+        self.rejection_history.read().get(start..).map(<[OrderRejection]>::to_vec).unwrap_or_default()
+    }
+    
+    /// Adds a rejection to the history delivered to the strategy
+    pub fn record_rejection(&self, order_id: &str, reason: RejectReason, timestamp: DateTime<Utc>) {
+        self.rejection_history.write().push(OrderRejection {
+            order_id: order_id.to_string(),
+            reason,
+            timestamp,
+        });
+    }
+    
+    /// Applies the configured rejection rate, removing the order if it is rejected
+    fn reject_if_simulated(&self, order: &Order, timestamp: DateTime<Utc>) -> bool {
+        if self.config.reject_rate <= 0.0 {
+            return false;
+        }
+        
+        let counter = self.rejection_counter.fetch_add(1, Ordering::Relaxed);
+        let threshold = (self.config.reject_rate * REJECTION_RATE_PRECISION as f64) as u64;
+        let should_reject = (counter % REJECTION_RATE_PRECISION) < threshold;
+        
+        if should_reject {
+            warn!("Order {} rejected due to simulated rejection (rate: {:.2}%)", 
+                  order.id, self.config.reject_rate * 100.0);
+            self.remove_pending(&order.id);
+            self.record_rejection(&order.id, RejectReason::Simulated, timestamp);
+        }
+        should_reject
+    }
+    
+    /// Records a taker fill closing `quantity` of `symbol` at `price`
+    /// 
+    /// Used for forced liquidations, which bypass pending orders, the book
+    /// and simulated rejections. Returns the ID of the fill.
+    pub fn liquidate(&self, symbol: &str, side: OrderSide, quantity: f64, price: f64, timestamp: DateTime<Utc>) -> String {
+        let order = Order {
+            id: format!("LIQ_{}", uuid::Uuid::new_v4()),
+            symbol: symbol.to_string(),
+            side,
+            order_type: OrderType::Market,
+            quantity,
+            price: None,
+            stop_price: None,
+            timestamp,
+            time_in_force: TimeInForce::IOC,
+        };
+        self.record_fill(&order, quantity, price, 0.0, Liquidity::Taker, timestamp);
+        order.id
+    }
+    
+    /// Records a cost-free fill closing `quantity` of an expired option at
+    /// its settlement `price`
+    /// 
+    /// Returns the ID of the fill.
+    pub fn settle_expiry(&self, symbol: &str, side: OrderSide, quantity: f64, price: f64, timestamp: DateTime<Utc>) -> String {
+        let order_id = format!("EXP_{}", uuid::Uuid::new_v4());
+        self.fill_history.write().push(Fill {
+            order_id: order_id.clone(),
+            symbol: symbol.to_string(),
+            side,
+            quantity,
+            price,
+            commission: 0.0,
+            slippage: 0.0,
+            timestamp,
+            costs: CostBreakdown::default(),
+        });
+        order_id
+    }
+    
     fn execute_fill(&self, order: Order, price: f64, timestamp: DateTime<Utc>) -> Result<()> {
-        let commission = 0.001 * price * order.quantity;
-        let slippage = 0.0001 * price;
+        let liquidity = match order.order_type {
+            OrderType::Market => Liquidity::Taker,
+            _ => Liquidity::Maker,
+        };
+        self.record_fill(&order, order.quantity, price, 0.0001 * price, liquidity, timestamp);
+        self.remove_pending(&order.id);
+        Ok(())
+    }
+    
+    fn record_fill(
+        &self,
+        order: &Order,
+        quantity: f64,
+        price: f64,
+        slippage: f64,
+        liquidity: Liquidity,
+        timestamp: DateTime<Utc>,
+    ) {
+        let costs = self.commission_model.calculate(&FillContext {
+            symbol: &order.symbol,
+            side: order.side,
+            quantity,
+            price,
+            liquidity,
+            order_brokerage_paid: self.order_brokerage.get(&order.id).map_or(0.0, |paid| *paid),
+        });
+        *self.order_brokerage.entry(order.id.clone()).or_insert(0.0) += costs.brokerage;
+        let commission = costs.total();
         let fill = Fill {
             order_id: order.id.clone(),
             symbol: order.symbol.clone(),
-            side: order.side.clone(),
-            quantity: order.quantity,
+            side: order.side,
+            quantity,
             price,
             commission,
             slippage,
             timestamp,
+            costs,
         };
         
-        self.fill_history.write().push(fill.clone());
-        self.pending_orders.remove(&order.id);
+        self.fill_history.write().push(fill);
+        self.propagate_fill(order, quantity, timestamp);
         
-        // Notify portfolio tracker of the fill
         debug!("Executed fill for {} - {} {} @ {} (commission: {}, slippage: {})",
                order.symbol, 
                match order.side {
                    OrderSide::Buy => "BUY",
                    OrderSide::Sell => "SELL",
                },
-               order.quantity, price, commission, slippage);
-        
-        Ok(())
+               quantity, price, commission, slippage);
     }
 }
 
@@ -1050,12 +2601,41 @@ impl PortfolioTracker {
             cash_balance: Arc::new(RwLock::new(initial_capital)),
             equity_curve: Arc::new(RwLock::new(Vec::new())),
             transaction_history: Arc::new(RwLock::new(Vec::new())),
+            entry_times: Arc::new(DashMap::new()),
+            costs: Arc::new(RwLock::new(CostBreakdown::default())),
+            margin: MarginConfig::default(),
+            margin_report: Arc::new(RwLock::new(MarginReport::default())),
+            in_margin_call: Arc::new(RwLock::new(false)),
+            last_accrual: Arc::new(RwLock::new(None)),
         }
     }
     
+    /// Trades the instruments in `margin` on margin instead of in cash
+    /// 
+    /// See the `margin` module for how margined positions are accounted.
+    pub fn with_margin(mut self, margin: MarginConfig) -> Self {
+        self.margin = margin;
+        self
+    }
+    
     /// Process a fill and update portfolio
-    pub fn process_fill(&self, fill: &Fill) -> Result<()> {
+    /// 
+    /// Returns the completed round trip when the fill reduces or closes
+    /// an existing long position, or any position in a margined instrument.
+    /// Buys the cash balance cannot pay for, and sells of more than is held
+    /// in an instrument not traded on margin, are refused.
+    pub fn process_fill(&self, fill: &Fill) -> Result<Option<CompletedTrade>> {
+        if fill.quantity <= 0.0 {
+            debug!("Ignoring fill {} with non-positive quantity {}", fill.order_id, fill.quantity);
+            return Ok(None);
+        }
+        
+        if let Some(terms) = self.margin.for_symbol(&fill.symbol).copied() {
+            return self.process_margined_fill(fill, &terms);
+        }
+        
         let mut cash = self.cash_balance.write();
+        let mut completed = None;
         
         let transaction = match fill.side {
             OrderSide::Buy => {
@@ -1083,6 +2663,7 @@ impl PortfolioTracker {
                         unrealized_pnl: 0.0,
                         commission_paid: fill.commission,
                     });
+                self.entry_times.entry(fill.symbol.clone()).or_insert(fill.timestamp);
                 
                 Transaction {
                     timestamp: fill.timestamp,
@@ -1091,23 +2672,51 @@ impl PortfolioTracker {
                     quantity: fill.quantity,
                     price: fill.price,
                     commission: fill.commission,
+                    slippage: fill.slippage,
                     net_amount: -total_cost,
                 }
             }
             OrderSide::Sell => {
+                // A cash account can only sell what it holds
+                let held = self.positions.get(&fill.symbol).map_or(0.0, |pos| pos.quantity);
+                if fill.quantity > held + QUANTITY_EPSILON {
+                    error!("Insufficient position for sell order: selling {} {}, holding {}", fill.quantity, fill.symbol, held);
+                    return Err(anyhow::Error::msg("Insufficient position"));
+                }
                 let total_proceeds = fill.price * fill.quantity - fill.commission - fill.slippage;
                 *cash += total_proceeds;
                 
-                // Update position
-                if let Some(mut pos) = self.positions.get_mut(&fill.symbol) {
-                    let realized = (fill.price - pos.average_price) * fill.quantity;
+                // Update position; the shard guard must be released before removal
+                let closed = if let Some(mut pos) = self.positions.get_mut(&fill.symbol) {
+                    let closed_qty = fill.quantity.min(pos.quantity);
+                    let realized = (fill.price - pos.average_price) * closed_qty;
                     pos.realized_pnl += realized;
-                    pos.quantity -= fill.quantity;
+                    pos.quantity -= closed_qty;
                     pos.commission_paid += fill.commission;
                     
-                    if pos.quantity <= 0.0 {
-                        self.positions.remove(&fill.symbol);
+                    if closed_qty > 0.0 {
+                        let pnl = (fill.price - pos.average_price) * closed_qty - fill.commission - fill.slippage;
+                        completed = Some(CompletedTrade {
+                            entry_time: self.entry_times.get(&fill.symbol).map_or(fill.timestamp, |t| *t),
+                            exit_time: fill.timestamp,
+                            symbol: fill.symbol.clone(),
+                            side: OrderSide::Buy,
+                            entry_price: pos.average_price,
+                            exit_price: fill.price,
+                            quantity: closed_qty,
+                            pnl,
+                            return_pct: pnl / (pos.average_price * closed_qty),
+                        });
                     }
+                    
+                    pos.quantity <= QUANTITY_EPSILON
+                } else {
+                    false
+                };
+                
+                if closed {
+                    self.positions.remove(&fill.symbol);
+                    self.entry_times.remove(&fill.symbol);
                 }
                 
                 Transaction {
@@ -1117,13 +2726,202 @@ impl PortfolioTracker {
                     quantity: fill.quantity,
                     price: fill.price,
                     commission: fill.commission,
+                    slippage: fill.slippage,
                     net_amount: total_proceeds,
                 }
             }
         };
         
+        self.book_transaction(transaction, fill);
+        Ok(completed)
+    }
+    
+    /// Books a fill in a margined instrument
+    /// 
+    /// No notional changes hands: realized P&L and costs settle to cash and
+    /// the position may cross from long to short. Fills that add exposure
+    /// are refused unless equity covers the initial margin of every margined
+    /// position afterwards.
+    fn process_margined_fill(&self, fill: &Fill, terms: &InstrumentMargin) -> Result<Option<CompletedTrade>> {
+        let signed_quantity = match fill.side {
+            OrderSide::Buy => fill.quantity,
+            OrderSide::Sell => -fill.quantity,
+        };
+        let (held, average_price, mark) = self.positions
+            .get(&fill.symbol)
+            .map_or((0.0, fill.price, fill.price), |pos| (pos.quantity, pos.average_price, pos.current_price));
+        let closed = if held * signed_quantity < 0.0 { fill.quantity.min(held.abs()) } else { 0.0 };
+        let opened = fill.quantity - closed;
+        let fees = fill.commission + fill.slippage;
+        
+        if opened > 0.0 {
+            let status = self.margin_status();
+            let required = status.initial_requirement + (opened * fill.price - closed * mark) * terms.initial_margin;
+            if status.equity - fees < required {
+                error!("Insufficient margin for {} {}: need {}, have {}", fill.quantity, fill.symbol, required, status.equity - fees);
+                return Err(anyhow::Error::msg("Insufficient margin"));
+            }
+        }
+        
+        let realized = (fill.price - average_price) * closed * held.signum();
+        *self.cash_balance.write() += realized - fees;
+        
+        let mut completed = None;
+        if closed > 0.0 {
+            let pnl = realized - fees;
+            completed = Some(CompletedTrade {
+                entry_time: self.entry_times.get(&fill.symbol).map_or(fill.timestamp, |t| *t),
+                exit_time: fill.timestamp,
+                symbol: fill.symbol.clone(),
+                side: if held > 0.0 { OrderSide::Buy } else { OrderSide::Sell },
+                entry_price: average_price,
+                exit_price: fill.price,
+                quantity: closed,
+                pnl,
+                return_pct: pnl / (average_price * closed),
+            });
+        }
+        
+        let new_quantity = held + signed_quantity;
+        if new_quantity.abs() < QUANTITY_EPSILON {
+            self.positions.remove(&fill.symbol);
+            self.entry_times.remove(&fill.symbol);
+        } else {
+            let new_average = if closed > 0.0 && opened > 0.0 {
+                // Flipped through flat: the remainder opens a new position
+                self.entry_times.insert(fill.symbol.clone(), fill.timestamp);
+                fill.price
+            } else if opened > 0.0 {
+                self.entry_times.entry(fill.symbol.clone()).or_insert(fill.timestamp);
+                (average_price * held.abs() + fill.price * opened) / new_quantity.abs()
+            } else {
+                average_price
+            };
+            let mut pos = self.positions.entry(fill.symbol.clone()).or_insert(Position {
+                symbol: fill.symbol.clone(),
+                current_price: fill.price,
+                ..Position::default()
+            });
+            pos.quantity = new_quantity;
+            pos.average_price = new_average;
+            pos.realized_pnl += realized;
+            pos.unrealized_pnl = (pos.current_price - new_average) * new_quantity;
+            pos.commission_paid += fill.commission;
+        }
+        
+        self.book_transaction(Transaction {
+            timestamp: fill.timestamp,
+            symbol: fill.symbol.clone(),
+            side: fill.side,
+            quantity: fill.quantity,
+            price: fill.price,
+            commission: fill.commission,
+            slippage: fill.slippage,
+            net_amount: realized - fees,
+        }, fill);
+        Ok(completed)
+    }
+    
+    /// Appends a transaction and adds the fill's costs to the running totals
+    fn book_transaction(&self, transaction: Transaction, fill: &Fill) {
         self.transaction_history.write().push(transaction);
-        Ok(())
+        
+        // Fills priced without a breakdown are attributed to brokerage
+        *self.costs.write() += if fill.costs.total() > 0.0 {
+            fill.costs
+        } else {
+            CostBreakdown::brokerage_only(fill.commission)
+        };
+    }
+    
+    /// Equity and margin requirements at current prices
+    pub fn margin_status(&self) -> MarginStatus {
+        let mut status = MarginStatus {
+            equity: self.calculate_total_value(),
+            ..MarginStatus::default()
+        };
+        for pos in self.positions.iter() {
+            if let Some(terms) = self.margin.for_symbol(pos.key()) {
+                let notional = pos.quantity.abs() * pos.current_price;
+                status.initial_requirement += notional * terms.initial_margin;
+                status.maintenance_requirement += notional * terms.maintenance_margin;
+            }
+        }
+        status
+    }
+    
+    /// Returns the margin status, recording a margin call when equity has
+    /// just fallen below the initial requirement
+    pub fn check_margin(&self, timestamp: DateTime<Utc>) -> MarginStatus {
+        let status = self.margin_status();
+        let was_margin_call = std::mem::replace(&mut *self.in_margin_call.write(), status.is_margin_call());
+        if status.is_margin_call() && !was_margin_call {
+            warn!("Margin call at {}: equity {:.2} below initial margin {:.2}",
+                  timestamp, status.equity, status.initial_requirement);
+            self.margin_report.write().margin_calls.push(timestamp);
+        }
+        status
+    }
+    
+    /// Charges financing on margined positions for the time since the last call
+    /// 
+    /// The first call only starts the clock. Charges are taken from cash at
+    /// current prices and counted against each position's realized P&L.
+    pub fn accrue_financing(&self, timestamp: DateTime<Utc>) {
+        let Some(previous) = self.last_accrual.write().replace(timestamp) else {
+            return;
+        };
+        let seconds = (timestamp - previous).num_milliseconds() as f64 / 1000.0;
+        if seconds <= 0.0 {
+            return;
+        }
+        
+        let mut charged = 0.0;
+        for mut pos in self.positions.iter_mut() {
+            if let Some(terms) = self.margin.for_symbol(pos.key()) {
+                let charge = terms.financing.charge(pos.quantity, pos.current_price, seconds);
+                pos.realized_pnl -= charge;
+                charged += charge;
+            }
+        }
+        if charged != 0.0 {
+            *self.cash_balance.write() -= charged;
+            self.margin_report.write().financing_paid += charged;
+        }
+    }
+    
+    /// Whether `symbol` trades on margin rather than in cash
+    pub fn is_margined(&self, symbol: &str) -> bool {
+        self.margin.for_symbol(symbol).is_some()
+    }
+    
+    /// Open positions in margined instruments
+    pub fn margined_positions(&self) -> Vec<Position> {
+        self.positions
+            .iter()
+            .filter(|pos| self.margin.for_symbol(pos.key()).is_some())
+            .map(|pos| pos.value().clone())
+            .collect()
+    }
+    
+    /// Adds a forced liquidation to the margin report
+    pub fn record_liquidation(&self, event: LiquidationEvent) {
+        self.margin_report.write().liquidations.push(event);
+    }
+    
+    /// Returns the financing, margin calls and liquidations booked so far
+    pub fn get_margin_report(&self) -> MarginReport {
+        self.margin_report.read().clone()
+    }
+    
+    /// Returns a copy of all transactions booked so far
+    pub fn get_transactions(&self) -> Vec<Transaction> {
+        self.transaction_history.read().clone()
+    }
+    
+    /// Returns the trading costs booked so far, split by component
+    pub fn get_cost_breakdown(&self) -> CostBreakdown {
+        *self.costs.read()
     }
     
     /// Updates current prices for all positions and recalculates unrealized P&L
@@ -1150,6 +2948,7 @@ impl PortfolioTracker {
     /// let market = MarketSnapshot {
     ///     timestamp: chrono::Utc::now(),
     ///     prices: DashMap::new(),
+    ///     quotes: DashMap::new(),
     /// };
     /// market.prices.insert("AAPL".to_string(), 155.0);
     /// 
@@ -1263,9 +3062,13 @@ impl PortfolioTracker {
     
     fn calculate_total_value(&self) -> f64 {
         let cash = *self.cash_balance.read();
+        // Margined positions contribute their open P&L, not their notional
         let positions_value: f64 = self.positions
             .iter()
-            .map(|e| e.current_price * e.quantity)
+            .map(|e| match self.margin.for_symbol(e.key()) {
+                Some(_) => (e.current_price - e.average_price) * e.quantity,
+                None => e.current_price * e.quantity,
+            })
             .sum();
         cash + positions_value
     }
@@ -1368,7 +3171,9 @@ impl PerformanceAnalyzer {
         // Calculate max drawdown
         let max_drawdown = self.calculate_max_drawdown(&equity_curve);
         
-        let metrics = PerformanceMetrics {
+        let transactions = portfolio.get_transactions();
+        
+        let mut metrics = PerformanceMetrics {
             total_return,
             annualized_return: total_return * 252.0 / returns.len() as f64,
             volatility: volatility * (252.0_f64).sqrt(),
@@ -1381,8 +3186,11 @@ impl PerformanceAnalyzer {
                 .copied()
                 .collect::<Vec<f64>>()
                 .mean(),
+            total_commission: transactions.iter().map(|t| t.commission).sum(),
+            total_slippage: transactions.iter().map(|t| t.slippage).sum(),
             ..Default::default()
         };
+        self.apply_trade_statistics(&mut metrics);
         
         // Log key metrics including percentile_95
         info!("Backtest complete - Return: {:.2}%, Sharpe: {:.2}, 95th percentile: {:.4}", 
@@ -1392,6 +3200,65 @@ impl PerformanceAnalyzer {
         Ok(metrics)
     }
     
+    /// Calculates performance relative to a benchmark price series
+    /// 
+    /// The benchmark is sampled at each point of `equity_curve` and both are
+    /// turned into period returns. Alpha, beta, tracking error, information
+    /// ratio and up/down capture are reported over the whole curve and over
+    /// every window of `config.rolling_window` returns.
+    /// 
+    /// # Parameters
+    /// 
+    /// * `equity_curve` - Portfolio value over time
+    /// * `config` - Benchmark symbol and rolling window
+    /// * `benchmark` - Benchmark prices over time
+    /// * `risk_free_rate` - Annual risk-free rate used for alpha
+    /// 
+    /// # Returns
+    /// 
+    /// * `Ok(BenchmarkMetrics)` with overall and rolling metrics
+    /// * `Err` if the benchmark covers fewer than two points of the curve
+    pub fn calculate_benchmark_metrics(
+        &self,
+        equity_curve: &[(DateTime<Utc>, f64)],
+        config: &BenchmarkConfig,
+        benchmark: &[(DateTime<Utc>, f64)],
+        risk_free_rate: f64,
+    ) -> Result<BenchmarkMetrics> {
+        let aligned = AlignedReturns::of(equity_curve, benchmark)?;
+        let strategy_return = aligned.strategy.iter().fold(1.0, |growth, r| growth * (1.0 + r)) - 1.0;
+        let benchmark_return = aligned.benchmark_total_return();
+        
+        let window = config.rolling_window;
+        let rolling = if window >= 2 && aligned.strategy.len() >= window {
+            (window..=aligned.strategy.len())
+                .map(|end| RollingRelativeMetrics {
+                    timestamp: aligned.timestamps[end - 1],
+                    metrics: RelativeMetrics::of(
+                        &aligned.strategy[end - window..end],
+                        &aligned.benchmark[end - window..end],
+                        risk_free_rate,
+                    ),
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        
+        let overall = RelativeMetrics::of(&aligned.strategy, &aligned.benchmark, risk_free_rate);
+        info!("Benchmark {} - Alpha: {:.4}, Beta: {:.2}, IR: {:.2}", 
+              config.symbol, overall.alpha, overall.beta, overall.information_ratio);
+        
+        Ok(BenchmarkMetrics {
+            symbol: config.symbol.clone(),
+            benchmark_return,
+            excess_return: strategy_return - benchmark_return,
+            overall,
+            rolling_window: window,
+            rolling,
+        })
+    }
+    
     /// Returns a copy of all completed trades from the backtest
     /// 
     /// Provides access to the complete trade log showing entry/exit details,
@@ -1422,6 +3289,37 @@ impl PerformanceAnalyzer {
         self.trade_log.read().clone()
     }
     
+    /// Records a completed round-trip trade for the trade statistics
+    pub fn record_trade(&self, trade: CompletedTrade) {
+        self.trade_log.write().push(trade);
+    }
+    
+    fn apply_trade_statistics(&self, metrics: &mut PerformanceMetrics) {
+        let trades = self.trade_log.read();
+        if trades.is_empty() {
+            return;
+        }
+        
+        let gross_profit: f64 = trades.iter().filter(|t| t.pnl > 0.0).map(|t| t.pnl).sum();
+        let gross_loss: f64 = trades.iter().filter(|t| t.pnl < 0.0).map(|t| -t.pnl).sum();
+        let winning = trades.iter().filter(|t| t.pnl > 0.0).count() as u64;
+        let losing = trades.iter().filter(|t| t.pnl < 0.0).count() as u64;
+        let total = trades.len() as u64;
+        
+        metrics.total_trades = total;
+        metrics.winning_trades = winning;
+        metrics.losing_trades = losing;
+        metrics.win_rate = winning as f64 / total as f64;
+        metrics.average_win = if winning > 0 { gross_profit / winning as f64 } else { 0.0 };
+        metrics.average_loss = if losing > 0 { gross_loss / losing as f64 } else { 0.0 };
+        metrics.profit_factor = if gross_loss > 0.0 { gross_profit / gross_loss } else { 0.0 };
+        metrics.expectancy = metrics.average_win * metrics.win_rate
+            - metrics.average_loss * (losing as f64 / total as f64);
+        metrics.average_trade_duration = trades.iter()
+            .map(|t| (t.exit_time - t.entry_time).num_minutes())
+            .sum::<i64>() / total as i64;
+    }
+    
     fn calculate_max_drawdown(&self, equity_curve: &[(DateTime<Utc>, f64)]) -> f64 {
         let mut max_drawdown = 0.0;
         let mut peak = 0.0;
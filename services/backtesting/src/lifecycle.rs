@@ -0,0 +1,307 @@
+//! Strategy lifecycle context and order management
+//!
+//! The engine hands a `StrategyContext` to every `Strategy` hook. It exposes
+//! the market, portfolio and open orders at that point of the run and
+//! collects the orders the strategy places, amends or cancels. Order IDs are
+//! assigned when an order is placed, so a strategy can track its own orders
+//! and match them against the `Fill`s and `OrderRejection`s it is sent later.
+//! Linked orders placed together, such as brackets, get an ID per order.
+
+use crate::contingent::{BracketIds, ContingentOrder, Trail};
+use crate::{uuid, MarketSnapshot, Order, OrderSide, OrderType, PortfolioState, Position, Quote, TimeInForce, TradingSignal};
+use chrono::{DateTime, Utc};
+
+/// Order a strategy asks the engine to submit
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderRequest {
+    /// Symbol to trade
+    pub symbol: String,
+    /// Buy or sell
+    pub side: OrderSide,
+    /// Type of order to place
+    pub order_type: OrderType,
+    /// Quantity to trade (must be positive)
+    pub quantity: f64,
+    /// Limit price for limit orders (None for market orders)
+    pub price: Option<f64>,
+    /// Trigger price for stop and stop-limit orders
+    pub stop_price: Option<f64>,
+    /// How long the order stays active
+    pub time_in_force: TimeInForce,
+}
+
+impl OrderRequest {
+    /// Good-till-cancelled market order
+    pub fn market(symbol: &str, side: OrderSide, quantity: f64) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            side,
+            order_type: OrderType::Market,
+            quantity,
+            price: None,
+            stop_price: None,
+            time_in_force: TimeInForce::GTC,
+        }
+    }
+
+    /// Good-till-cancelled limit order at `price`
+    pub fn limit(symbol: &str, side: OrderSide, quantity: f64, price: f64) -> Self {
+        Self {
+            order_type: OrderType::Limit,
+            price: Some(price),
+            ..Self::market(symbol, side, quantity)
+        }
+    }
+
+    /// Good-till-cancelled stop order triggered at `stop_price`
+    pub fn stop(symbol: &str, side: OrderSide, quantity: f64, stop_price: f64) -> Self {
+        Self {
+            order_type: OrderType::Stop,
+            stop_price: Some(stop_price),
+            ..Self::market(symbol, side, quantity)
+        }
+    }
+
+    /// Good-till-cancelled stop-limit order that becomes a limit order at
+    /// `price` once triggered at `stop_price`
+    pub fn stop_limit(symbol: &str, side: OrderSide, quantity: f64, stop_price: f64, price: f64) -> Self {
+        Self {
+            order_type: OrderType::StopLimit,
+            price: Some(price),
+            stop_price: Some(stop_price),
+            ..Self::market(symbol, side, quantity)
+        }
+    }
+
+    /// Uses `time_in_force` instead of good-till-cancelled
+    pub fn with_time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.time_in_force = time_in_force;
+        self
+    }
+}
+
+impl From<TradingSignal> for OrderRequest {
+    fn from(signal: TradingSignal) -> Self {
+        Self {
+            symbol: signal.symbol,
+            side: signal.side,
+            order_type: signal.order_type,
+            quantity: signal.quantity,
+            price: signal.price,
+            stop_price: None,
+            time_in_force: TimeInForce::GTC,
+        }
+    }
+}
+
+/// Order instruction issued by a strategy hook
+#[derive(Debug, Clone)]
+pub enum OrderCommand {
+    /// Submit a new order
+    Place(Order),
+    /// Submit linked orders, see the `contingent` module
+    PlaceContingent(ContingentOrder),
+    /// Change the quantity and/or limit price of a pending order
+    ///
+    /// Changing the price or increasing the quantity loses queue priority.
+    Amend {
+        /// Order to change
+        order_id: String,
+        /// New remaining quantity, if changed
+        quantity: Option<f64>,
+        /// New limit price, if changed
+        price: Option<f64>,
+    },
+    /// Cancel a pending order
+    Cancel {
+        /// Order to cancel
+        order_id: String,
+    },
+}
+
+impl OrderCommand {
+    /// Order the instruction refers to, the first one for linked orders
+    pub fn order_id(&self) -> &str {
+        match self {
+            Self::Place(order) => &order.id,
+            Self::PlaceContingent(group) => &group.orders()[0].id,
+            Self::Amend { order_id, .. } | Self::Cancel { order_id } => order_id,
+        }
+    }
+
+    /// Orders the instruction submits, including bracket exits not yet armed
+    pub fn placed_orders(&self) -> Vec<&Order> {
+        match self {
+            Self::Place(order) => vec![order],
+            Self::PlaceContingent(group) => group.orders(),
+            Self::Amend { .. } | Self::Cancel { .. } => Vec::new(),
+        }
+    }
+
+    /// Orders that start working as soon as the instruction reaches the book
+    pub fn working_orders(&self) -> Vec<&Order> {
+        match self {
+            Self::PlaceContingent(group) => group.working_orders(),
+            other => other.placed_orders(),
+        }
+    }
+}
+
+/// View of the run handed to `Strategy` hooks
+///
+/// Orders issued through the context are submitted after the hook returns,
+/// so `open_orders` does not include orders placed in the same hook.
+#[derive(Debug)]
+pub struct StrategyContext<'a> {
+    market: &'a MarketSnapshot,
+    portfolio: &'a PortfolioState,
+    open_orders: &'a [Order],
+    commands: Vec<OrderCommand>,
+}
+
+impl<'a> StrategyContext<'a> {
+    /// Creates a context for the given market, portfolio and pending orders
+    pub fn new(market: &'a MarketSnapshot, portfolio: &'a PortfolioState, open_orders: &'a [Order]) -> Self {
+        Self {
+            market,
+            portfolio,
+            open_orders,
+            commands: Vec::new(),
+        }
+    }
+
+    /// Simulation time of the hook
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        self.market.timestamp
+    }
+
+    /// Current market snapshot
+    pub fn market(&self) -> &MarketSnapshot {
+        self.market
+    }
+
+    /// Current portfolio state
+    pub fn portfolio(&self) -> &PortfolioState {
+        self.portfolio
+    }
+
+    /// Latest price of `symbol`, if it has traded
+    pub fn price(&self, symbol: &str) -> Option<f64> {
+        self.market.prices.get(symbol).map(|price| *price)
+    }
+
+    /// Best bid and offer of `symbol`, if its book is two-sided
+    pub fn quote(&self, symbol: &str) -> Option<Quote> {
+        self.market.quotes.get(symbol).map(|quote| *quote)
+    }
+
+    /// Open position in `symbol`, if any
+    pub fn position(&self, symbol: &str) -> Option<&Position> {
+        self.portfolio.positions.iter().find(|p| p.symbol == symbol && p.quantity != 0.0)
+    }
+
+    /// Orders still pending when the hook was called, oldest first, then
+    /// orders still on their way to the book
+    pub fn open_orders(&self) -> &[Order] {
+        self.open_orders
+    }
+
+    /// Places an order and returns the ID it will be tracked by
+    pub fn place(&mut self, request: impl Into<OrderRequest>) -> String {
+        self.place_as(next_order_id(), request.into())
+    }
+
+    /// Places an order under an ID taken from `next_order_id` earlier
+    pub(crate) fn place_as(&mut self, id: String, request: OrderRequest) -> String {
+        let order = self.order(id.clone(), request);
+        self.commands.push(OrderCommand::Place(order));
+        id
+    }
+
+    /// Places an entry with a stop-loss and a take-profit attached
+    ///
+    /// The exits close the filled quantity of the entry at `stop_loss`
+    /// (a stop order) or `take_profit` (a limit order), whichever is reached
+    /// first.
+    pub fn place_bracket(&mut self, entry: impl Into<OrderRequest>, stop_loss: f64, take_profit: f64) -> BracketIds {
+        let entry = self.order(next_order_id(), entry.into());
+        let exit_side = match entry.side {
+            OrderSide::Buy => OrderSide::Sell,
+            OrderSide::Sell => OrderSide::Buy,
+        };
+        let stop_loss = self.order(next_order_id(), OrderRequest::stop(&entry.symbol, exit_side, entry.quantity, stop_loss));
+        let take_profit = self.order(next_order_id(), OrderRequest::limit(&entry.symbol, exit_side, entry.quantity, take_profit));
+        let ids = BracketIds {
+            entry: entry.id.clone(),
+            stop_loss: stop_loss.id.clone(),
+            take_profit: take_profit.id.clone(),
+        };
+        self.commands.push(OrderCommand::PlaceContingent(ContingentOrder::Bracket { entry, stop_loss, take_profit }));
+        ids
+    }
+
+    /// Places two orders where a fill on one cancels the same quantity of the
+    /// other, returning their IDs
+    pub fn place_oco(&mut self, first: impl Into<OrderRequest>, second: impl Into<OrderRequest>) -> (String, String) {
+        let first = self.order(next_order_id(), first.into());
+        let second = self.order(next_order_id(), second.into());
+        let ids = (first.id.clone(), second.id.clone());
+        self.commands.push(OrderCommand::PlaceContingent(ContingentOrder::Oco { first, second }));
+        ids
+    }
+
+    /// Places a stop order that trails the best price seen by `trail`
+    pub fn place_trailing_stop(&mut self, symbol: &str, side: OrderSide, quantity: f64, trail: Trail) -> String {
+        let mut order = self.order(next_order_id(), OrderRequest::market(symbol, side, quantity));
+        order.order_type = OrderType::Stop;
+        let id = order.id.clone();
+        self.commands.push(OrderCommand::PlaceContingent(ContingentOrder::TrailingStop { order, trail }));
+        id
+    }
+
+    /// Order for `request` submitted now under `id`
+    fn order(&self, id: String, request: OrderRequest) -> Order {
+        Order {
+            id,
+            symbol: request.symbol,
+            side: request.side,
+            order_type: request.order_type,
+            quantity: request.quantity,
+            price: request.price,
+            stop_price: request.stop_price,
+            timestamp: self.timestamp(),
+            time_in_force: request.time_in_force,
+        }
+    }
+
+    /// Changes the remaining quantity and/or limit price of a pending order
+    pub fn amend(&mut self, order_id: &str, quantity: Option<f64>, price: Option<f64>) {
+        self.commands.push(OrderCommand::Amend {
+            order_id: order_id.to_string(),
+            quantity,
+            price,
+        });
+    }
+
+    /// Cancels a pending order
+    pub fn cancel(&mut self, order_id: &str) {
+        self.commands.push(OrderCommand::Cancel {
+            order_id: order_id.to_string(),
+        });
+    }
+
+    /// Instructions issued so far, in order
+    pub fn commands(&self) -> &[OrderCommand] {
+        &self.commands
+    }
+
+    /// Consumes the context, returning the instructions it collected
+    pub fn into_commands(self) -> Vec<OrderCommand> {
+        self.commands
+    }
+}
+
+/// Fresh ID for an order placed through a `StrategyContext`
+pub(crate) fn next_order_id() -> String {
+    format!("BT_{}", uuid::Uuid::new_v4())
+}
@@ -0,0 +1,211 @@
+//! Margin, leverage, financing and liquidation
+//!
+//! Instruments with an `InstrumentMargin` are traded on margin: fills move
+//! no notional, profit and loss settles to cash as positions are reduced,
+//! and positions may be long or short. Opening exposure requires the
+//! portfolio's equity to cover the initial margin of every margined
+//! position, financing (short borrow fees or perpetual funding) accrues pro
+//! rata over simulated time, and once equity falls below the combined
+//! maintenance margin the engine liquidates every margined position at the
+//! current price.
+//!
+//! Instruments without an entry are settled in cash as before.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Seconds in the 365-day year borrow rates are quoted over
+const SECONDS_PER_YEAR: f64 = 365.0 * 24.0 * 3600.0;
+
+/// Binance USDⓈ-M maintenance margin rate of the lowest notional tier
+const BINANCE_USDM_MAINTENANCE: f64 = 0.004;
+
+/// Binance USDⓈ-M funding rate when the premium index is zero (0.01% per 8h)
+const BINANCE_USDM_BASE_FUNDING: f64 = 0.0001;
+
+/// Hours between Binance USDⓈ-M funding settlements
+const BINANCE_USDM_FUNDING_HOURS: f64 = 8.0;
+
+/// Cost of carrying a margined position
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Financing {
+    /// No financing charges (e.g., exchange-traded futures)
+    #[default]
+    None,
+    /// Stock borrow fee charged on short notional
+    Borrow {
+        /// Annual fee as a decimal of short notional (e.g., 0.03 = 3%)
+        annual_rate: f64,
+    },
+    /// Perpetual swap funding
+    ///
+    /// With a positive rate longs pay shorts; with a negative rate shorts
+    /// pay longs.
+    Funding {
+        /// Funding rate per interval as a decimal of position notional
+        rate: f64,
+        /// Hours between funding settlements
+        interval_hours: f64,
+    },
+}
+
+impl Financing {
+    /// Charge for holding `quantity` at `price` for `seconds`
+    ///
+    /// Positive values are paid by the portfolio, negative values received.
+    pub fn charge(&self, quantity: f64, price: f64, seconds: f64) -> f64 {
+        match *self {
+            Financing::None => 0.0,
+            Financing::Borrow { annual_rate } if quantity < 0.0 => {
+                annual_rate * quantity.abs() * price * seconds / SECONDS_PER_YEAR
+            }
+            Financing::Borrow { .. } => 0.0,
+            Financing::Funding { rate, interval_hours } if interval_hours > 0.0 => {
+                rate * quantity * price * seconds / (interval_hours * 3600.0)
+            }
+            Financing::Funding { .. } => 0.0,
+        }
+    }
+}
+
+/// Margin terms of one instrument
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct InstrumentMargin {
+    /// Margin required to open exposure, as a decimal of notional
+    /// (e.g., 0.1 = 10x leverage)
+    pub initial_margin: f64,
+    /// Margin below which positions are liquidated, as a decimal of notional
+    pub maintenance_margin: f64,
+    /// Financing charged while a position is held
+    #[serde(default)]
+    pub financing: Financing,
+}
+
+impl InstrumentMargin {
+    /// Binance USDⓈ-M perpetual at `leverage`
+    ///
+    /// Uses the lowest-tier maintenance margin rate (0.4%) and the base
+    /// funding rate of 0.01% every 8 hours. Large positions sit in higher
+    /// maintenance tiers and live funding follows the premium index, so
+    /// override either with a custom entry where that matters.
+    pub fn binance_usdm(leverage: f64) -> Self {
+        Self {
+            initial_margin: 1.0 / leverage.max(1.0),
+            maintenance_margin: BINANCE_USDM_MAINTENANCE,
+            financing: Financing::Funding {
+                rate: BINANCE_USDM_BASE_FUNDING,
+                interval_hours: BINANCE_USDM_FUNDING_HOURS,
+            },
+        }
+    }
+
+    /// NSE index or stock future with SPAN plus exposure margin of `initial_margin`
+    ///
+    /// NSE requires the full margin to be maintained at every daily
+    /// mark-to-market, so the maintenance rate equals the initial rate.
+    /// The cost of carry is already in the futures price, so there is no
+    /// financing charge.
+    pub fn nse_futures(initial_margin: f64) -> Self {
+        Self {
+            initial_margin,
+            maintenance_margin: initial_margin,
+            financing: Financing::None,
+        }
+    }
+
+    /// Margin account terms derived from `BacktestConfig::margin_requirement`
+    ///
+    /// Maintenance is half the initial requirement, matching the Reg T 50%
+    /// initial and 25% maintenance split.
+    pub fn from_requirement(margin_requirement: f64) -> Self {
+        Self {
+            initial_margin: margin_requirement,
+            maintenance_margin: margin_requirement / 2.0,
+            financing: Financing::None,
+        }
+    }
+
+    /// Uses `financing` instead of the preset's charges
+    pub fn with_financing(mut self, financing: Financing) -> Self {
+        self.financing = financing;
+        self
+    }
+}
+
+/// Margin terms of the instruments in a backtest
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarginConfig {
+    /// Terms by symbol
+    pub instruments: HashMap<String, InstrumentMargin>,
+    /// Terms of symbols without an entry; cash-settled when unset
+    pub default: Option<InstrumentMargin>,
+}
+
+impl MarginConfig {
+    /// Adds or replaces the terms of `symbol`
+    pub fn with_instrument(mut self, symbol: &str, margin: InstrumentMargin) -> Self {
+        self.instruments.insert(symbol.to_string(), margin);
+        self
+    }
+
+    /// Terms that apply to `symbol`, if it is traded on margin
+    pub fn for_symbol(&self, symbol: &str) -> Option<&InstrumentMargin> {
+        self.instruments.get(symbol).or(self.default.as_ref())
+    }
+}
+
+/// Margin position of the portfolio at one point in time
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct MarginStatus {
+    /// Cash plus the value of cash positions and open P&L of margined ones
+    pub equity: f64,
+    /// Initial margin of all margined positions at current prices
+    pub initial_requirement: f64,
+    /// Maintenance margin of all margined positions at current prices
+    pub maintenance_requirement: f64,
+}
+
+impl MarginStatus {
+    /// Equity no longer covers the initial margin, so only reducing fills are accepted
+    pub fn is_margin_call(&self) -> bool {
+        self.initial_requirement > 0.0 && self.equity < self.initial_requirement
+    }
+
+    /// Equity no longer covers the maintenance margin and positions must be liquidated
+    pub fn is_below_maintenance(&self) -> bool {
+        self.maintenance_requirement > 0.0 && self.equity < self.maintenance_requirement
+    }
+}
+
+/// Forced close of a margined position
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LiquidationEvent {
+    /// When the position was liquidated
+    pub timestamp: DateTime<Utc>,
+    /// ID of the liquidation fill
+    pub order_id: String,
+    /// Symbol liquidated
+    pub symbol: String,
+    /// Position closed (positive for long, negative for short)
+    pub quantity: f64,
+    /// Price the position was closed at
+    pub price: f64,
+    /// Portfolio equity when liquidation was triggered
+    pub equity: f64,
+    /// Maintenance margin equity had fallen below
+    pub maintenance_requirement: f64,
+}
+
+/// Margin activity over a run
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct MarginReport {
+    /// Net financing paid (negative when funding was received)
+    pub financing_paid: f64,
+    /// Times equity fell below the initial margin requirement
+    pub margin_calls: Vec<DateTime<Utc>>,
+    /// Positions closed by the engine
+    pub liquidations: Vec<LiquidationEvent>,
+}
@@ -0,0 +1,525 @@
+//! Parameter optimization and walk-forward analysis
+//!
+//! The `Optimizer` runs one `BacktestEngine` per candidate parameter set,
+//! spreading runs over the tokio worker threads, and ranks them by an
+//! `Objective`. Walk-forward analysis repeats the search on rolling
+//! in-sample windows and scores the winner on the following out-of-sample
+//! window, so overfit parameters show up as poor out-of-sample results and
+//! unstable parameter choices from window to window.
+
+use crate::{splitmix_unit, BacktestConfig, BacktestEngine, PerformanceMetrics, Strategy, OHLCV, SPLITMIX_GAMMA};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::{debug, info, warn};
+
+/// Values for each named strategy parameter
+pub type ParameterSet = BTreeMap<String, f64>;
+
+/// Builds a strategy instance for a parameter set
+pub type StrategyFactory = dyn Fn(&ParameterSet) -> Result<Box<dyn Strategy>> + Send + Sync;
+
+/// Historical bars per symbol shared by all optimization runs
+pub type MarketData = HashMap<String, Vec<(DateTime<Utc>, OHLCV)>>;
+
+/// Values a single parameter may take
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ParameterRange {
+    /// Explicit list of values
+    Values {
+        /// Candidate values
+        values: Vec<f64>,
+    },
+    /// Evenly spaced values from `min` to `max` inclusive
+    Linear {
+        /// First value
+        min: f64,
+        /// Last value (included when reached by whole steps)
+        max: f64,
+        /// Spacing between values; must be positive
+        step: f64,
+    },
+    /// Any value in `[min, max)`; only usable with random search
+    Continuous {
+        /// Lower bound
+        min: f64,
+        /// Upper bound
+        max: f64,
+    },
+}
+
+impl ParameterRange {
+    /// Enumerates the range for grid search
+    fn grid_values(&self, name: &str) -> Result<Vec<f64>> {
+        match self {
+            Self::Values { values } => Ok(values.clone()),
+            Self::Linear { min, max, step } => {
+                if *step <= 0.0 || max < min {
+                    bail!("Invalid linear range for {}: {}..={} step {}", name, min, max, step);
+                }
+                let count = ((max - min) / step + 1e-9).floor() as usize + 1;
+                Ok((0..count).map(|i| min + step * i as f64).collect())
+            }
+            Self::Continuous { .. } => bail!("Parameter {} is continuous and cannot be grid searched", name),
+        }
+    }
+
+    /// Draws one value using the uniform draw `u` in `[0, 1)`
+    fn sample(&self, name: &str, u: f64) -> Result<f64> {
+        match self {
+            Self::Continuous { min, max } => Ok(min + (max - min) * u),
+            _ => {
+                let values = self.grid_values(name)?;
+                let index = ((u * values.len() as f64) as usize).min(values.len().saturating_sub(1));
+                values.get(index).copied().with_context(|| format!("Parameter {} has no values", name))
+            }
+        }
+    }
+}
+
+/// Search space: one range per parameter name
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ParameterSpace {
+    /// Ranges keyed by parameter name
+    pub parameters: BTreeMap<String, ParameterRange>,
+}
+
+impl ParameterSpace {
+    /// Creates an empty search space
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or replaces the range for `name`
+    pub fn with(mut self, name: &str, range: ParameterRange) -> Self {
+        self.parameters.insert(name.to_string(), range);
+        self
+    }
+
+    /// Every combination of the parameter values
+    pub fn grid(&self) -> Result<Vec<ParameterSet>> {
+        let mut sets = vec![ParameterSet::new()];
+        for (name, range) in &self.parameters {
+            let values = range.grid_values(name)?;
+            sets = sets
+                .into_iter()
+                .flat_map(|set| {
+                    values.iter().map(move |value| {
+                        let mut next = set.clone();
+                        next.insert(name.clone(), *value);
+                        next
+                    })
+                })
+                .collect();
+        }
+        Ok(sets)
+    }
+
+    /// `samples` random parameter sets, reproducible for a given `seed`
+    pub fn random(&self, samples: usize, seed: u64) -> Result<Vec<ParameterSet>> {
+        let mut state = seed;
+        (0..samples)
+            .map(|_| {
+                self.parameters
+                    .iter()
+                    .map(|(name, range)| {
+                        state = state.wrapping_add(SPLITMIX_GAMMA);
+                        Ok((name.clone(), range.sample(name, splitmix_unit(state))?))
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// How candidate parameter sets are chosen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SearchMethod {
+    /// Every combination of the parameter values
+    Grid,
+    /// Random draws from the parameter ranges
+    Random {
+        /// Number of parameter sets to evaluate
+        samples: usize,
+        /// Seed for reproducible draws
+        seed: u64,
+    },
+}
+
+impl SearchMethod {
+    fn candidates(&self, space: &ParameterSpace) -> Result<Vec<ParameterSet>> {
+        match *self {
+            Self::Grid => space.grid(),
+            Self::Random { samples, seed } => space.random(samples, seed),
+        }
+    }
+}
+
+/// Metric that candidate parameter sets are ranked by (higher is better)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Objective {
+    /// Sharpe ratio
+    #[default]
+    SharpeRatio,
+    /// Sortino ratio
+    SortinoRatio,
+    /// Calmar ratio
+    CalmarRatio,
+    /// Total return over the period
+    TotalReturn,
+    /// Gross profit over gross loss
+    ProfitFactor,
+}
+
+impl Objective {
+    /// Score of a run under this objective; non-finite values score lowest
+    pub fn score(&self, metrics: &PerformanceMetrics) -> f64 {
+        let value = match self {
+            Self::SharpeRatio => metrics.sharpe_ratio,
+            Self::SortinoRatio => metrics.sortino_ratio,
+            Self::CalmarRatio => metrics.calmar_ratio,
+            Self::TotalReturn => metrics.total_return,
+            Self::ProfitFactor => metrics.profit_factor,
+        };
+        if value.is_finite() { value } else { f64::MIN }
+    }
+}
+
+/// Outcome of one backtest in a sweep
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrialResult {
+    /// Parameters the strategy was built with
+    pub parameters: ParameterSet,
+    /// Metrics of the run
+    pub metrics: PerformanceMetrics,
+    /// Objective score of the run
+    pub score: f64,
+}
+
+/// Ranked results of a parameter sweep
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptimizationResult {
+    /// Successful runs, best score first
+    pub trials: Vec<TrialResult>,
+    /// Number of runs that failed (e.g. no data or strategy build errors)
+    pub failed_trials: usize,
+}
+
+impl OptimizationResult {
+    /// Highest scoring run
+    pub fn best(&self) -> Option<&TrialResult> {
+        self.trials.first()
+    }
+}
+
+/// Rolling window layout for walk-forward analysis
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WalkForwardConfig {
+    /// Length of each in-sample (optimization) window
+    pub in_sample: Duration,
+    /// Length of each out-of-sample (validation) window
+    pub out_of_sample: Duration,
+    /// How far windows advance; defaults to the out-of-sample length
+    pub step: Option<Duration>,
+    /// Keep every in-sample window starting at the beginning of the period
+    pub anchored: bool,
+}
+
+/// Results for one walk-forward window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalkForwardWindow {
+    /// Start of the in-sample period
+    pub in_sample_start: DateTime<Utc>,
+    /// End of the in-sample period, where the out-of-sample period begins
+    pub in_sample_end: DateTime<Utc>,
+    /// End of the out-of-sample period
+    pub out_of_sample_end: DateTime<Utc>,
+    /// Parameters chosen on the in-sample period
+    pub best_parameters: ParameterSet,
+    /// In-sample metrics of the chosen parameters
+    pub in_sample_metrics: PerformanceMetrics,
+    /// Out-of-sample metrics of the chosen parameters
+    pub out_of_sample_metrics: PerformanceMetrics,
+    /// In-sample objective score
+    pub in_sample_score: f64,
+    /// Out-of-sample objective score
+    pub out_of_sample_score: f64,
+}
+
+/// Spread of one parameter's chosen values across windows
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParameterStability {
+    /// Parameter name
+    pub name: String,
+    /// Mean of the chosen values
+    pub mean: f64,
+    /// Standard deviation of the chosen values
+    pub std_dev: f64,
+    /// Standard deviation relative to the mean; large values suggest overfitting
+    pub coefficient_of_variation: f64,
+    /// Smallest chosen value
+    pub min: f64,
+    /// Largest chosen value
+    pub max: f64,
+}
+
+/// Complete walk-forward analysis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalkForwardReport {
+    /// Per-window results in chronological order
+    pub windows: Vec<WalkForwardWindow>,
+    /// Stability of each parameter's chosen values
+    pub parameter_stability: Vec<ParameterStability>,
+    /// Mean in-sample objective score
+    pub mean_in_sample_score: f64,
+    /// Mean out-of-sample objective score
+    pub mean_out_of_sample_score: f64,
+    /// Out-of-sample over in-sample mean score, when the in-sample mean is positive
+    ///
+    /// Values well below 1.0 mean in-sample performance does not carry over.
+    pub walk_forward_efficiency: Option<f64>,
+}
+
+/// Parallel parameter search around `BacktestEngine`
+pub struct Optimizer {
+    base_config: BacktestConfig,
+    data: Arc<MarketData>,
+    factory: Arc<StrategyFactory>,
+    objective: Objective,
+    max_parallel: usize,
+}
+
+impl std::fmt::Debug for Optimizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Optimizer")
+            .field("base_config", &self.base_config)
+            .field("symbols", &self.data.len())
+            .field("objective", &self.objective)
+            .field("max_parallel", &self.max_parallel)
+            .finish()
+    }
+}
+
+impl Optimizer {
+    /// Creates an optimizer over `data` for the period in `base_config`
+    ///
+    /// `factory` builds a fresh strategy for each parameter set. Runs use
+    /// as many concurrent backtests as there are cores by default.
+    pub fn new<F>(base_config: BacktestConfig, data: MarketData, factory: F) -> Self
+    where
+        F: Fn(&ParameterSet) -> Result<Box<dyn Strategy>> + Send + Sync + 'static,
+    {
+        Self {
+            base_config,
+            data: Arc::new(data),
+            factory: Arc::new(factory),
+            objective: Objective::default(),
+            max_parallel: std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get),
+        }
+    }
+
+    /// Ranks runs by `objective` instead of the Sharpe ratio
+    pub fn with_objective(mut self, objective: Objective) -> Self {
+        self.objective = objective;
+        self
+    }
+
+    /// Limits the number of backtests running at once
+    pub fn with_max_parallel(mut self, max_parallel: usize) -> Self {
+        self.max_parallel = max_parallel.max(1);
+        self
+    }
+
+    /// Searches `space` over the full period of the base configuration
+    pub async fn optimize(&self, space: &ParameterSpace, method: &SearchMethod) -> Result<OptimizationResult> {
+        let candidates = method.candidates(space)?;
+        self.run_sweep(&self.base_config, candidates).await
+    }
+
+    /// Runs a walk-forward analysis over the base configuration's period
+    ///
+    /// Each window is optimized in sample and the best parameters are then
+    /// run on the out-of-sample window that follows. Windows start with no
+    /// history, so strategies needing a warm-up lose the start of each one.
+    pub async fn walk_forward(
+        &self,
+        space: &ParameterSpace,
+        method: &SearchMethod,
+        walk_forward: &WalkForwardConfig,
+    ) -> Result<WalkForwardReport> {
+        let candidates = method.candidates(space)?;
+        let layout = self.window_layout(walk_forward)?;
+        info!("Walk-forward analysis over {} windows, {} candidates each", layout.len(), candidates.len());
+
+        let mut windows = Vec::with_capacity(layout.len());
+        for (in_sample_start, in_sample_end, out_of_sample_end) in layout {
+            let sweep = self
+                .run_sweep(&self.window_config(in_sample_start, in_sample_end), candidates.clone())
+                .await?;
+            let Some(best) = sweep.best() else {
+                warn!("No successful in-sample runs for window starting {}", in_sample_start);
+                continue;
+            };
+
+            let out_of_sample_config = self.window_config(in_sample_end, out_of_sample_end);
+            let metrics = run_trial(out_of_sample_config, self.data.clone(), self.factory.clone(), best.parameters.clone())
+                .await
+                .with_context(|| format!("Out-of-sample run failed for window ending {}", out_of_sample_end))?;
+
+            windows.push(WalkForwardWindow {
+                in_sample_start,
+                in_sample_end,
+                out_of_sample_end,
+                best_parameters: best.parameters.clone(),
+                in_sample_metrics: best.metrics.clone(),
+                out_of_sample_score: self.objective.score(&metrics),
+                out_of_sample_metrics: metrics,
+                in_sample_score: best.score,
+            });
+        }
+
+        if windows.is_empty() {
+            bail!("Walk-forward analysis produced no windows with results");
+        }
+        Ok(build_report(windows))
+    }
+
+    /// (in-sample start, in-sample end, out-of-sample end) for each window
+    fn window_layout(&self, config: &WalkForwardConfig) -> Result<Vec<(DateTime<Utc>, DateTime<Utc>, DateTime<Utc>)>> {
+        let step = config.step.unwrap_or(config.out_of_sample);
+        if config.in_sample <= Duration::zero() || config.out_of_sample <= Duration::zero() || step <= Duration::zero() {
+            bail!("Walk-forward window lengths and step must be positive");
+        }
+
+        let start = self.base_config.start_date;
+        let mut layout = Vec::new();
+        let mut in_sample_end = start + config.in_sample;
+        while in_sample_end + config.out_of_sample <= self.base_config.end_date {
+            let in_sample_start = if config.anchored { start } else { in_sample_end - config.in_sample };
+            layout.push((in_sample_start, in_sample_end, in_sample_end + config.out_of_sample));
+            in_sample_end += step;
+        }
+
+        if layout.is_empty() {
+            bail!("Backtest period is too short for a {} day in-sample and {} day out-of-sample window",
+                  config.in_sample.num_days(), config.out_of_sample.num_days());
+        }
+        Ok(layout)
+    }
+
+    fn window_config(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> BacktestConfig {
+        BacktestConfig {
+            start_date: start,
+            end_date: end,
+            ..self.base_config.clone()
+        }
+    }
+
+    /// Runs every candidate on `config`'s period, at most `max_parallel` at a time
+    async fn run_sweep(&self, config: &BacktestConfig, candidates: Vec<ParameterSet>) -> Result<OptimizationResult> {
+        let permits = Arc::new(Semaphore::new(self.max_parallel));
+        let mut tasks = JoinSet::new();
+
+        for parameters in candidates {
+            let permits = permits.clone();
+            let config = config.clone();
+            let data = self.data.clone();
+            let factory = self.factory.clone();
+            tasks.spawn(async move {
+                let _permit = permits.acquire_owned().await?;
+                let metrics = run_trial(config, data, factory, parameters.clone()).await;
+                Ok::<_, anyhow::Error>((parameters, metrics))
+            });
+        }
+
+        let mut trials = Vec::new();
+        let mut failed_trials = 0;
+        while let Some(joined) = tasks.join_next().await {
+            let (parameters, metrics) = joined.context("Optimization task panicked")??;
+            match metrics {
+                Ok(metrics) => trials.push(TrialResult {
+                    score: self.objective.score(&metrics),
+                    parameters,
+                    metrics,
+                }),
+                Err(e) => {
+                    debug!("Trial {:?} failed: {:#}", parameters, e);
+                    failed_trials += 1;
+                }
+            }
+        }
+
+        // Ties keep a deterministic order regardless of completion order
+        trials.sort_by(|a, b| {
+            b.score.total_cmp(&a.score).then_with(|| {
+                a.parameters.values().map(|v| v.to_bits()).cmp(b.parameters.values().map(|v| v.to_bits()))
+            })
+        });
+        Ok(OptimizationResult { trials, failed_trials })
+    }
+}
+
+/// Runs one backtest on the bars inside `config`'s period
+async fn run_trial(
+    config: BacktestConfig,
+    data: Arc<MarketData>,
+    factory: Arc<StrategyFactory>,
+    parameters: ParameterSet,
+) -> Result<PerformanceMetrics> {
+    let mut strategy = factory(&parameters)?;
+    let engine = BacktestEngine::new(config.clone());
+    for (symbol, bars) in data.iter() {
+        let window: Vec<_> = bars
+            .iter()
+            .filter(|(timestamp, _)| *timestamp >= config.start_date && *timestamp <= config.end_date)
+            .cloned()
+            .collect();
+        engine.load_data(symbol, window).await?;
+    }
+    Ok(engine.run(strategy.as_mut()).await?.metrics)
+}
+
+fn build_report(windows: Vec<WalkForwardWindow>) -> WalkForwardReport {
+    let mean = |values: &[f64]| values.iter().sum::<f64>() / values.len().max(1) as f64;
+
+    let mut chosen: BTreeMap<&str, Vec<f64>> = BTreeMap::new();
+    for window in &windows {
+        for (name, value) in &window.best_parameters {
+            chosen.entry(name.as_str()).or_default().push(*value);
+        }
+    }
+    let parameter_stability = chosen
+        .into_iter()
+        .map(|(name, values)| {
+            let avg = mean(&values);
+            let variance = values.iter().map(|v| (v - avg).powi(2)).sum::<f64>() / values.len().max(1) as f64;
+            let std_dev = variance.sqrt();
+            ParameterStability {
+                name: name.to_string(),
+                mean: avg,
+                std_dev,
+                coefficient_of_variation: if avg.abs() > f64::EPSILON { std_dev / avg.abs() } else { 0.0 },
+                min: values.iter().copied().fold(f64::INFINITY, f64::min),
+                max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            }
+        })
+        .collect();
+
+    let in_sample: Vec<_> = windows.iter().map(|w| w.in_sample_score).collect();
+    let out_of_sample: Vec<_> = windows.iter().map(|w| w.out_of_sample_score).collect();
+    let mean_in_sample_score = mean(&in_sample);
+    let mean_out_of_sample_score = mean(&out_of_sample);
+
+    WalkForwardReport {
+        windows,
+        parameter_stability,
+        mean_in_sample_score,
+        mean_out_of_sample_score,
+        walk_forward_efficiency: (mean_in_sample_score > 0.0).then(|| mean_out_of_sample_score / mean_in_sample_score),
+    }
+}
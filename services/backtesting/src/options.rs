@@ -0,0 +1,413 @@
+//! Option positions: contract terms, model marking, expiry and Greeks
+//!
+//! Symbols that `options_engine::IndexOption::parse_symbol` accepts (e.g.
+//! `NIFTY24JAN25000CE`) are treated as European index options on the
+//! underlying's backtest symbol. Quantities are in units of the underlying,
+//! so one NIFTY lot is a quantity of `lot_size`. Monthly contracts expire on
+//! the index's expiry weekday at the time, moved off configured holidays.
+//!
+//! When an option has no price of its own in the market snapshot it is
+//! marked with `BlackScholes::price` at the underlying's price and the
+//! volatility from the configured surface. Positions still open at expiry
+//! are cash-settled at intrinsic value against the underlying's last price
+//! at or before expiry, and the run records the Greeks of the option book
+//! by underlying whenever it records an equity point.
+
+use crate::{MarketSnapshot, Position};
+use anyhow::{bail, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use dashmap::DashMap;
+use options_engine::{BlackScholes, IndexOption, OptionType};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Seconds in the 365-day year option expiries are measured in
+const SECONDS_PER_YEAR: f64 = 365.0 * 24.0 * 3600.0;
+
+/// At-the-money volatility used when no surface is configured
+const DEFAULT_VOLATILITY: f64 = 0.15;
+
+/// Implied volatility by moneyness and time to expiry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum VolSurface {
+    /// Same volatility at every strike and expiry
+    Flat {
+        /// Annualized volatility as a decimal (e.g., 0.15 = 15%)
+        volatility: f64,
+    },
+    /// Volatility grid interpolated linearly in moneyness and in total
+    /// variance between expiries, and held flat beyond its edges
+    Grid {
+        /// Days to expiry of each row, ascending
+        expiry_days: Vec<f64>,
+        /// Strike over spot of each column, ascending
+        moneyness: Vec<f64>,
+        /// Annualized volatilities, one row per expiry
+        volatilities: Vec<Vec<f64>>,
+    },
+}
+
+impl Default for VolSurface {
+    fn default() -> Self {
+        Self::Flat { volatility: DEFAULT_VOLATILITY }
+    }
+}
+
+impl VolSurface {
+    /// Checks that the surface is well formed
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            Self::Flat { volatility } => {
+                if !volatility.is_finite() || *volatility <= 0.0 {
+                    bail!("Volatility must be positive");
+                }
+            }
+            Self::Grid { expiry_days, moneyness, volatilities } => {
+                if expiry_days.is_empty() || moneyness.is_empty() {
+                    bail!("Volatility grid needs at least one expiry and one moneyness");
+                }
+                if !is_ascending(expiry_days) || !is_ascending(moneyness) {
+                    bail!("Volatility grid axes must be strictly ascending");
+                }
+                if volatilities.len() != expiry_days.len() || volatilities.iter().any(|row| row.len() != moneyness.len()) {
+                    bail!("Volatility grid must have one row per expiry and one column per moneyness");
+                }
+                if volatilities.iter().flatten().any(|vol| !vol.is_finite() || *vol <= 0.0) {
+                    bail!("Volatilities must be positive");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Volatility at `moneyness` (strike over spot) and `years` to expiry
+    pub fn volatility(&self, moneyness: f64, years: f64) -> f64 {
+        let (expiry_days, axis, volatilities) = match self {
+            Self::Flat { volatility } => return *volatility,
+            Self::Grid { expiry_days, moneyness, volatilities } => (expiry_days, moneyness, volatilities),
+        };
+        let smile = |row: usize| interpolate(axis, &volatilities[row], moneyness);
+
+        let days = years * 365.0;
+        let upper = expiry_days.partition_point(|d| *d < days);
+        if upper == 0 {
+            return smile(0);
+        }
+        if upper == expiry_days.len() {
+            return smile(expiry_days.len() - 1);
+        }
+        let lower = upper - 1;
+        let (t0, t1) = (expiry_days[lower], expiry_days[upper]);
+        let (v0, v1) = (smile(lower), smile(upper));
+        let weight = (days - t0) / (t1 - t0);
+        let variance = v0 * v0 * t0 + weight * (v1 * v1 * t1 - v0 * v0 * t0);
+        (variance / days).max(0.0).sqrt()
+    }
+}
+
+fn is_ascending(values: &[f64]) -> bool {
+    values.windows(2).all(|pair| pair[0] < pair[1])
+}
+
+/// Linear interpolation of `ys` over `xs` at `x`, flat beyond the ends
+fn interpolate(xs: &[f64], ys: &[f64], x: f64) -> f64 {
+    let upper = xs.partition_point(|value| *value < x);
+    if upper == 0 {
+        return ys[0];
+    }
+    if upper == xs.len() {
+        return ys[xs.len() - 1];
+    }
+    let lower = upper - 1;
+    let weight = (x - xs[lower]) / (xs[upper] - xs[lower]);
+    ys[lower] + weight * (ys[upper] - ys[lower])
+}
+
+/// How option positions are priced
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OptionsConfig {
+    /// Surfaces by underlying backtest symbol
+    pub surfaces: HashMap<String, VolSurface>,
+    /// Surface of underlyings without an entry
+    pub default_surface: VolSurface,
+    /// Backtest symbol of each index (e.g., "NIFTY" -> "NIFTY 50"); the
+    /// index name itself is used when unset
+    pub underlyings: HashMap<String, String>,
+    /// Continuous dividend yield of the underlyings
+    pub dividend_yield: f64,
+    /// Option symbols to model-price at every step even when not held, so
+    /// strategies can trade contracts without quotes
+    pub contracts: Vec<String>,
+    /// Exchange holidays; a monthly expiry falling on one moves to the
+    /// previous trading day
+    pub expiry_holidays: Vec<NaiveDate>,
+}
+
+impl OptionsConfig {
+    /// Uses `surface` for options on `underlying`
+    pub fn with_surface(mut self, underlying: &str, surface: VolSurface) -> Self {
+        self.surfaces.insert(underlying.to_string(), surface);
+        self
+    }
+
+    /// Surface that applies to options on `underlying`
+    pub fn surface_for(&self, underlying: &str) -> &VolSurface {
+        self.surfaces.get(underlying).unwrap_or(&self.default_surface)
+    }
+
+    /// Checks every configured surface
+    pub fn validate(&self) -> Result<()> {
+        self.default_surface.validate()?;
+        for (underlying, surface) in &self.surfaces {
+            surface.validate().map_err(|e| e.context(format!("Invalid volatility surface for {}", underlying)))?;
+        }
+        Ok(())
+    }
+}
+
+/// Terms of an option contract traded in a backtest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OptionContract {
+    /// Option symbol
+    pub symbol: String,
+    /// Backtest symbol of the underlying
+    pub underlying: String,
+    /// Index the option is written on
+    pub index: IndexOption,
+    /// Call or put
+    pub option_type: OptionType,
+    /// Strike price
+    pub strike: f64,
+    /// Expiry time
+    pub expiry: DateTime<Utc>,
+    /// Units of the underlying in one lot
+    pub lot_size: u32,
+}
+
+impl OptionContract {
+    /// Parses `symbol`, returning `None` when it is not an option symbol
+    pub fn parse(symbol: &str, config: &OptionsConfig) -> Option<Self> {
+        let (index, option_type, strike, expiry) =
+            IndexOption::parse_symbol_with_holidays(symbol, &config.expiry_holidays).ok()?;
+        let root = index_root(&index);
+        Some(Self {
+            symbol: symbol.to_string(),
+            underlying: config.underlyings.get(root).cloned().unwrap_or_else(|| root.to_string()),
+            lot_size: index.lot_size(),
+            index,
+            option_type,
+            strike,
+            expiry,
+        })
+    }
+
+    /// Payoff per unit when the underlying settles at `spot`
+    pub fn intrinsic_value(&self, spot: f64) -> f64 {
+        match self.option_type {
+            OptionType::Call => (spot - self.strike).max(0.0),
+            OptionType::Put => (self.strike - spot).max(0.0),
+        }
+    }
+
+    /// Years from `timestamp` to expiry, zero once expired
+    pub fn years_to_expiry(&self, timestamp: DateTime<Utc>) -> f64 {
+        ((self.expiry - timestamp).num_seconds() as f64 / SECONDS_PER_YEAR).max(0.0)
+    }
+
+    /// Whether the contract has expired at `timestamp`
+    pub fn is_expired(&self, timestamp: DateTime<Utc>) -> bool {
+        timestamp >= self.expiry
+    }
+}
+
+/// Symbol root of `index` in NSE option symbols
+fn index_root(index: &IndexOption) -> &'static str {
+    match index {
+        IndexOption::Nifty50 => "NIFTY",
+        IndexOption::BankNifty => "BANKNIFTY",
+        IndexOption::FinNifty => "FINNIFTY",
+        IndexOption::MidCapNifty => "MIDCPNIFTY",
+    }
+}
+
+/// Position-weighted Greeks of the options on one underlying
+///
+/// Delta and gamma are in units of the underlying, theta is value per
+/// calendar day, and vega and rho are value per percentage point.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct GreeksExposure {
+    /// Change in value per unit move of the underlying, counted in units
+    /// of the underlying and including linear positions in it
+    pub delta: f64,
+    /// Change in delta per unit move of the underlying
+    pub gamma: f64,
+    /// Change in value per calendar day
+    pub theta: f64,
+    /// Change in value per percentage point of volatility
+    pub vega: f64,
+    /// Change in value per percentage point of the interest rate
+    pub rho: f64,
+}
+
+/// Greeks of the book at one point of the run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GreeksPoint {
+    /// Time of the equity point the Greeks were recorded with
+    pub timestamp: DateTime<Utc>,
+    /// Exposure by underlying backtest symbol
+    pub by_underlying: BTreeMap<String, GreeksExposure>,
+}
+
+/// Cash settlement of an option position at expiry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExpirySettlement {
+    /// When the settlement was booked
+    pub timestamp: DateTime<Utc>,
+    /// ID of the settlement fill
+    pub order_id: String,
+    /// Option settled
+    pub symbol: String,
+    /// Position settled (positive for long, negative for short)
+    pub quantity: f64,
+    /// Underlying price the option settled against
+    pub underlying_price: f64,
+    /// Intrinsic value per unit the position was closed at
+    pub settlement_price: f64,
+}
+
+/// Option activity over a run
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct OptionsReport {
+    /// Greeks by underlying at each equity point with open options
+    pub greeks: Vec<GreeksPoint>,
+    /// Positions cash-settled at expiry
+    pub settlements: Vec<ExpirySettlement>,
+}
+
+/// Prices option contracts from their underlying
+#[derive(Debug)]
+pub struct OptionPricer {
+    config: OptionsConfig,
+    risk_free_rate: f64,
+    contracts: DashMap<String, Option<OptionContract>>,
+}
+
+impl OptionPricer {
+    /// Creates a pricer discounting at `risk_free_rate`
+    pub fn new(config: OptionsConfig, risk_free_rate: f64) -> Self {
+        Self {
+            config,
+            risk_free_rate,
+            contracts: DashMap::new(),
+        }
+    }
+
+    /// Contract terms of `symbol`, if it is an option
+    pub fn contract(&self, symbol: &str) -> Option<OptionContract> {
+        if let Some(contract) = self.contracts.get(symbol) {
+            return contract.clone();
+        }
+        let contract = OptionContract::parse(symbol, &self.config);
+        self.contracts.insert(symbol.to_string(), contract.clone());
+        contract
+    }
+
+    /// Volatility of `contract` with the underlying at `spot`
+    pub fn volatility(&self, contract: &OptionContract, spot: f64, timestamp: DateTime<Utc>) -> f64 {
+        self.config
+            .surface_for(&contract.underlying)
+            .volatility(contract.strike / spot, contract.years_to_expiry(timestamp))
+    }
+
+    /// Black-Scholes value of one unit of `contract` with the underlying at `spot`
+    pub fn model_price(&self, contract: &OptionContract, spot: f64, timestamp: DateTime<Utc>) -> f64 {
+        BlackScholes::price(
+            contract.option_type,
+            spot,
+            contract.strike,
+            self.risk_free_rate,
+            self.volatility(contract, spot, timestamp),
+            contract.years_to_expiry(timestamp),
+            self.config.dividend_yield,
+        )
+    }
+
+    /// Greeks of one unit of `contract` with the underlying at `spot`
+    pub fn unit_greeks(&self, contract: &OptionContract, spot: f64, timestamp: DateTime<Utc>) -> GreeksExposure {
+        let greeks = BlackScholes::calculate_greeks(
+            contract.option_type,
+            spot,
+            contract.strike,
+            self.risk_free_rate,
+            self.volatility(contract, spot, timestamp),
+            contract.years_to_expiry(timestamp),
+            self.config.dividend_yield,
+        );
+        GreeksExposure {
+            delta: greeks.delta,
+            gamma: greeks.gamma,
+            theta: greeks.theta,
+            vega: greeks.vega,
+            rho: greeks.rho,
+        }
+    }
+
+    /// Adds model prices to `market` for unquoted, unexpired options in `symbols`
+    ///
+    /// Options whose underlying has no price yet are left unpriced.
+    pub fn mark<'a>(&self, market: &MarketSnapshot, symbols: impl IntoIterator<Item = &'a str>) {
+        for symbol in symbols {
+            self.mark_symbol(market, symbol);
+        }
+        for symbol in &self.config.contracts {
+            self.mark_symbol(market, symbol);
+        }
+    }
+
+    fn mark_symbol(&self, market: &MarketSnapshot, symbol: &str) {
+        if market.prices.contains_key(symbol) {
+            return;
+        }
+        let Some(contract) = self.contract(symbol) else {
+            return;
+        };
+        if contract.is_expired(market.timestamp) {
+            return;
+        }
+        if let Some(spot) = market.prices.get(&contract.underlying).map(|price| *price) {
+            market.prices.insert(symbol.to_string(), self.model_price(&contract, spot, market.timestamp));
+        }
+    }
+
+    /// Greeks of the option positions in `positions` by underlying
+    ///
+    /// Linear positions in an underlying with open options add their
+    /// quantity to its delta. Options whose underlying has no price are
+    /// left out.
+    pub fn exposure(&self, positions: &[Position], market: &MarketSnapshot) -> BTreeMap<String, GreeksExposure> {
+        let mut exposures: BTreeMap<String, GreeksExposure> = BTreeMap::new();
+        for position in positions {
+            let Some(contract) = self.contract(&position.symbol) else {
+                continue;
+            };
+            let Some(spot) = market.prices.get(&contract.underlying).map(|price| *price) else {
+                continue;
+            };
+            let unit = self.unit_greeks(&contract, spot, market.timestamp);
+            let exposure = exposures.entry(contract.underlying).or_default();
+            exposure.delta += unit.delta * position.quantity;
+            exposure.gamma += unit.gamma * position.quantity;
+            exposure.theta += unit.theta * position.quantity;
+            exposure.vega += unit.vega * position.quantity;
+            exposure.rho += unit.rho * position.quantity;
+        }
+        for position in positions {
+            if let Some(exposure) = exposures.get_mut(&position.symbol) {
+                exposure.delta += position.quantity;
+            }
+        }
+        exposures
+    }
+}
@@ -0,0 +1,355 @@
+//! Monte Carlo robustness analysis of backtest results
+//!
+//! A backtest produces one equity path out of the many the strategy could
+//! have produced. `MonteCarloAnalyzer` builds alternative paths from the
+//! same result in two ways:
+//!
+//! * Trade bootstrap: the `CompletedTrade` P&Ls are drawn with replacement,
+//!   so the paths differ in the order and mix of trades.
+//! * Block bootstrap: daily returns of the equity curve are drawn in blocks
+//!   of consecutive days (wrapping around the end of the series), which
+//!   keeps short-range effects such as volatility clustering intact.
+//!
+//! Each path is scored for total return, maximum drawdown and Sharpe ratio,
+//! percentiles across paths give confidence intervals, and the share of
+//! paths that lose `ruin_threshold` of starting equity at any point gives
+//! the probability of ruin.
+
+use crate::{splitmix_unit, BacktestResult, CompletedTrade, SPLITMIX_GAMMA};
+use anyhow::{bail, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::debug;
+
+/// Trading days per year used to annualize daily returns, as in `PerformanceAnalyzer`
+const TRADING_DAYS_PER_YEAR: f64 = 252.0;
+
+/// Days per year used to annualize per-trade returns
+const DAYS_PER_YEAR: f64 = 365.25;
+
+/// Settings for a Monte Carlo analysis
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MonteCarloConfig {
+    /// Number of resampled paths per method
+    pub simulations: usize,
+    /// Consecutive daily returns drawn together in the block bootstrap
+    pub block_days: usize,
+    /// Two-sided confidence level of the reported intervals (e.g., 0.95)
+    pub confidence_level: f64,
+    /// Loss from starting equity, as a decimal, that counts as ruin (e.g., 0.5 = 50%)
+    pub ruin_threshold: f64,
+    /// Seed for the resampling draws
+    pub seed: u64,
+}
+
+impl Default for MonteCarloConfig {
+    fn default() -> Self {
+        Self {
+            simulations: 1_000,
+            block_days: 5,
+            confidence_level: 0.95,
+            ruin_threshold: 0.5,
+            seed: 0,
+        }
+    }
+}
+
+/// Distribution of one metric across simulated paths
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct ConfidenceInterval {
+    /// Lower bound of the interval
+    pub lower: f64,
+    /// Median across paths
+    pub median: f64,
+    /// Upper bound of the interval
+    pub upper: f64,
+    /// Mean across paths
+    pub mean: f64,
+}
+
+/// Results of one resampling method
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ResamplingSummary {
+    /// Number of paths simulated
+    pub simulations: usize,
+    /// Total return over each path as a decimal
+    pub total_return: ConfidenceInterval,
+    /// Maximum peak-to-trough decline of each path as a decimal
+    pub max_drawdown: ConfidenceInterval,
+    /// Annualized Sharpe ratio of each path
+    pub sharpe_ratio: ConfidenceInterval,
+    /// Share of paths that hit the ruin threshold
+    pub probability_of_ruin: f64,
+}
+
+/// Monte Carlo analysis of a backtest
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct RobustnessReport {
+    /// Confidence level of the intervals
+    pub confidence_level: f64,
+    /// Bootstrap of completed trades; `None` without trades
+    pub trade_bootstrap: Option<ResamplingSummary>,
+    /// Block bootstrap of daily returns; `None` with less than two days of equity
+    pub block_bootstrap: Option<ResamplingSummary>,
+}
+
+/// Metrics of one simulated path
+#[derive(Debug, Clone, Copy)]
+struct PathMetrics {
+    total_return: f64,
+    max_drawdown: f64,
+    sharpe_ratio: f64,
+    ruined: bool,
+}
+
+/// Runs Monte Carlo resampling over backtest results
+#[derive(Debug, Clone, Default)]
+pub struct MonteCarloAnalyzer {
+    config: MonteCarloConfig,
+}
+
+impl MonteCarloAnalyzer {
+    /// Creates an analyzer with the given settings
+    pub fn new(config: MonteCarloConfig) -> Self {
+        Self { config }
+    }
+
+    /// Resamples the trades and equity curve of `result`
+    ///
+    /// Paths start from the first point of the equity curve, which is also
+    /// the base `PerformanceAnalyzer` measures returns from. Results are
+    /// reproducible for a given seed.
+    pub fn analyze(&self, result: &BacktestResult, risk_free_rate: f64) -> Result<RobustnessReport> {
+        self.validate()?;
+        let Some(&(_, start_equity)) = result.equity_curve.first() else {
+            bail!("Backtest result has no equity curve");
+        };
+        if start_equity <= 0.0 {
+            bail!("Starting equity must be positive, got {}", start_equity);
+        }
+
+        let daily = daily_returns(&result.equity_curve);
+        Ok(RobustnessReport {
+            confidence_level: self.config.confidence_level,
+            trade_bootstrap: self.trade_bootstrap(&result.trades, start_equity, risk_free_rate),
+            block_bootstrap: self.block_bootstrap(&daily, start_equity, risk_free_rate),
+        })
+    }
+
+    /// Bootstraps completed trade P&Ls into alternative equity paths
+    pub fn trade_bootstrap(
+        &self,
+        trades: &[CompletedTrade],
+        start_equity: f64,
+        risk_free_rate: f64,
+    ) -> Option<ResamplingSummary> {
+        if trades.is_empty() {
+            return None;
+        }
+        let pnls: Vec<f64> = trades.iter().map(|trade| trade.pnl).collect();
+        let periods_per_year = trades_per_year(trades);
+        let mut state = self.config.seed;
+
+        let paths = (0..self.config.simulations)
+            .map(|_| {
+                let mut equity = start_equity;
+                let mut path = PathBuilder::new(start_equity, self.ruin_level(start_equity));
+                for _ in 0..pnls.len() {
+                    state = state.wrapping_add(SPLITMIX_GAMMA);
+                    let pnl = pnls[draw_index(state, pnls.len())];
+                    let previous = equity;
+                    equity += pnl;
+                    if !path.push(previous, equity) {
+                        break;
+                    }
+                }
+                path.finish(periods_per_year, risk_free_rate)
+            })
+            .collect();
+        Some(self.summarize(paths))
+    }
+
+    /// Block-resamples daily returns into alternative equity paths
+    pub fn block_bootstrap(&self, returns: &[f64], start_equity: f64, risk_free_rate: f64) -> Option<ResamplingSummary> {
+        if returns.is_empty() {
+            return None;
+        }
+        let block = self.config.block_days.min(returns.len());
+        let mut state = self.config.seed ^ SPLITMIX_GAMMA.rotate_left(32);
+
+        let paths = (0..self.config.simulations)
+            .map(|_| {
+                let mut equity = start_equity;
+                let mut path = PathBuilder::new(start_equity, self.ruin_level(start_equity));
+                let mut drawn = 0;
+                'path: while drawn < returns.len() {
+                    state = state.wrapping_add(SPLITMIX_GAMMA);
+                    let first = draw_index(state, returns.len());
+                    for offset in 0..block.min(returns.len() - drawn) {
+                        let previous = equity;
+                        equity *= 1.0 + returns[(first + offset) % returns.len()];
+                        drawn += 1;
+                        if !path.push(previous, equity) {
+                            break 'path;
+                        }
+                    }
+                }
+                path.finish(TRADING_DAYS_PER_YEAR, risk_free_rate)
+            })
+            .collect();
+        Some(self.summarize(paths))
+    }
+
+    fn validate(&self) -> Result<()> {
+        let config = &self.config;
+        if config.simulations == 0 {
+            bail!("Monte Carlo analysis needs at least one simulation");
+        }
+        if config.block_days == 0 {
+            bail!("Block length must be at least one day");
+        }
+        if !(config.confidence_level > 0.0 && config.confidence_level < 1.0) {
+            bail!("Confidence level must be between 0 and 1, got {}", config.confidence_level);
+        }
+        if !(config.ruin_threshold > 0.0 && config.ruin_threshold <= 1.0) {
+            bail!("Ruin threshold must be in (0, 1], got {}", config.ruin_threshold);
+        }
+        Ok(())
+    }
+
+    fn ruin_level(&self, start_equity: f64) -> f64 {
+        start_equity * (1.0 - self.config.ruin_threshold)
+    }
+
+    fn summarize(&self, paths: Vec<PathMetrics>) -> ResamplingSummary {
+        let tail = (1.0 - self.config.confidence_level) / 2.0;
+        let interval = |metric: fn(&PathMetrics) -> f64| {
+            let mut values: Vec<f64> = paths.iter().map(metric).filter(|v| v.is_finite()).collect();
+            values.sort_by(f64::total_cmp);
+            ConfidenceInterval {
+                lower: quantile(&values, tail),
+                median: quantile(&values, 0.5),
+                upper: quantile(&values, 1.0 - tail),
+                mean: if values.is_empty() { 0.0 } else { values.iter().sum::<f64>() / values.len() as f64 },
+            }
+        };
+
+        let ruined = paths.iter().filter(|path| path.ruined).count();
+        debug!("Monte Carlo: {} of {} paths ruined", ruined, paths.len());
+        ResamplingSummary {
+            simulations: paths.len(),
+            total_return: interval(|path| path.total_return),
+            max_drawdown: interval(|path| path.max_drawdown),
+            sharpe_ratio: interval(|path| path.sharpe_ratio),
+            probability_of_ruin: ruined as f64 / paths.len() as f64,
+        }
+    }
+}
+
+/// Tracks drawdown, per-period returns and ruin along one path
+struct PathBuilder {
+    start: f64,
+    ruin_level: f64,
+    equity: f64,
+    peak: f64,
+    max_drawdown: f64,
+    returns: Vec<f64>,
+    ruined: bool,
+}
+
+impl PathBuilder {
+    fn new(start: f64, ruin_level: f64) -> Self {
+        Self {
+            start,
+            ruin_level,
+            equity: start,
+            peak: start,
+            max_drawdown: 0.0,
+            returns: Vec::new(),
+            ruined: false,
+        }
+    }
+
+    /// Adds the next equity point; returns false once equity is wiped out
+    fn push(&mut self, previous: f64, equity: f64) -> bool {
+        let equity = equity.max(0.0);
+        self.returns.push(equity / previous - 1.0);
+        self.equity = equity;
+        self.peak = self.peak.max(equity);
+        self.max_drawdown = self.max_drawdown.max((self.peak - equity) / self.peak);
+        self.ruined |= equity <= self.ruin_level;
+        equity > 0.0
+    }
+
+    fn finish(self, periods_per_year: f64, risk_free_rate: f64) -> PathMetrics {
+        PathMetrics {
+            total_return: self.equity / self.start - 1.0,
+            max_drawdown: self.max_drawdown,
+            sharpe_ratio: sharpe_ratio(&self.returns, periods_per_year, risk_free_rate),
+            ruined: self.ruined,
+        }
+    }
+}
+
+/// Close-to-close returns of the last equity point of each UTC day
+pub fn daily_returns(equity_curve: &[(DateTime<Utc>, f64)]) -> Vec<f64> {
+    let closes: BTreeMap<NaiveDate, f64> = equity_curve
+        .iter()
+        .map(|(timestamp, value)| (timestamp.date_naive(), *value))
+        .collect();
+    closes
+        .values()
+        .collect::<Vec<_>>()
+        .windows(2)
+        .filter(|pair| *pair[0] > 0.0)
+        .map(|pair| pair[1] / pair[0] - 1.0)
+        .collect()
+}
+
+/// Annualized Sharpe ratio of per-period returns
+fn sharpe_ratio(returns: &[f64], periods_per_year: f64, risk_free_rate: f64) -> f64 {
+    if returns.len() < 2 {
+        return 0.0;
+    }
+    let n = returns.len() as f64;
+    let mean = returns.iter().sum::<f64>() / n;
+    let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    let std_dev = variance.sqrt();
+    if std_dev > 0.0 {
+        (mean - risk_free_rate / periods_per_year) / std_dev * periods_per_year.sqrt()
+    } else {
+        0.0
+    }
+}
+
+/// Trades per year over the span from the first entry to the last exit
+fn trades_per_year(trades: &[CompletedTrade]) -> f64 {
+    let first = trades.iter().map(|trade| trade.entry_time).min();
+    let last = trades.iter().map(|trade| trade.exit_time).max();
+    let days = match (first, last) {
+        (Some(first), Some(last)) => (last - first).num_seconds() as f64 / 86_400.0,
+        _ => 0.0,
+    };
+    trades.len() as f64 / (days.max(1.0) / DAYS_PER_YEAR)
+}
+
+/// Uniform index into a slice of `len` elements
+fn draw_index(state: u64, len: usize) -> usize {
+    ((splitmix_unit(state) * len as f64) as usize).min(len - 1)
+}
+
+/// Linearly interpolated quantile of sorted values
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    match sorted.len() {
+        0 => 0.0,
+        1 => sorted[0],
+        len => {
+            let position = q.clamp(0.0, 1.0) * (len - 1) as f64;
+            let below = position.floor() as usize;
+            let above = position.ceil() as usize;
+            sorted[below] + (sorted[above] - sorted[below]) * (position - below as f64)
+        }
+    }
+}
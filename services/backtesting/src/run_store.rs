@@ -0,0 +1,183 @@
+//! Persistent history of backtest runs
+//!
+//! `RunStore` keeps one JSON file per run in a local directory, holding
+//! everything needed to reproduce and report on the run: the resolved
+//! config, the strategy specification, the version of the code that ran it,
+//! the bars that were available and the full `BacktestResult` with its
+//! trades. The gRPC service writes a record when a job finishes and reads
+//! the directory back to answer for runs from before a restart.
+//!
+//! Records are written to a temporary file and renamed into place, so a
+//! crash mid-write never leaves a truncated record behind.
+
+use crate::grpc_service::BacktestStatus;
+use crate::{BacktestConfig, BacktestResult, OHLCV};
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Version of the backtesting code, with the git revision it was built from
+/// (suffixed `-dirty` when built with uncommitted changes)
+pub const CODE_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "+", env!("BACKTESTING_GIT_REVISION"));
+
+/// Extension of run record files
+const RECORD_EXTENSION: &str = "json";
+
+/// Bars of one symbol that were loaded for a run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataCoverage {
+    /// Symbol the bars belong to
+    pub symbol: String,
+    /// Timestamp of the first bar
+    pub first_bar: DateTime<Utc>,
+    /// Timestamp of the last bar
+    pub last_bar: DateTime<Utc>,
+    /// Number of bars loaded
+    pub bars: usize,
+}
+
+impl DataCoverage {
+    /// Coverage of `bars`, or `None` if there are none
+    pub fn of(symbol: &str, bars: &[(DateTime<Utc>, OHLCV)]) -> Option<Self> {
+        Some(Self {
+            symbol: symbol.to_string(),
+            first_bar: bars.iter().map(|(timestamp, _)| *timestamp).min()?,
+            last_bar: bars.iter().map(|(timestamp, _)| *timestamp).max()?,
+            bars: bars.len(),
+        })
+    }
+}
+
+/// Everything recorded about one finished backtest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    /// ID the run was submitted under
+    pub backtest_id: String,
+    /// Final status of the run
+    pub status: BacktestStatus,
+    /// Completion or failure message
+    pub message: String,
+    /// Resolved configuration the engine ran with
+    pub config: BacktestConfig,
+    /// Strategy specification as submitted
+    pub strategy_code: String,
+    /// Symbols data was loaded for
+    pub symbols: Vec<String>,
+    /// `CODE_VERSION` of the service that ran the backtest
+    pub code_version: String,
+    /// Bars available for each symbol
+    #[serde(default)]
+    pub data: Vec<DataCoverage>,
+    /// When the run was submitted
+    pub created_at: DateTime<Utc>,
+    /// When the run finished
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Metrics, equity curve and trades, for completed runs
+    pub result: Option<BacktestResult>,
+}
+
+impl RunRecord {
+    /// Earliest and latest bar loaded across all symbols
+    pub fn data_range(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let first = self.data.iter().map(|coverage| coverage.first_bar).min()?;
+        let last = self.data.iter().map(|coverage| coverage.last_bar).max()?;
+        Some((first, last))
+    }
+}
+
+/// Directory of run records, one file per backtest ID
+#[derive(Debug, Clone)]
+pub struct RunStore {
+    root: PathBuf,
+}
+
+impl RunStore {
+    /// Opens the store in `root`, creating the directory if needed
+    pub fn open(root: impl Into<PathBuf>) -> Result<Self> {
+        let root = root.into();
+        std::fs::create_dir_all(&root)
+            .with_context(|| format!("Failed to create run store {}", root.display()))?;
+        Ok(Self { root })
+    }
+
+    /// Directory the records live in
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Writes `record`, replacing any earlier run with the same ID
+    pub fn save(&self, record: &RunRecord) -> Result<()> {
+        let path = self.path_for(&record.backtest_id)?;
+        let temporary = path.with_extension("tmp");
+        let encoded = serde_json::to_vec(record)
+            .with_context(|| format!("Failed to encode run {}", record.backtest_id))?;
+        std::fs::write(&temporary, encoded)
+            .and_then(|()| std::fs::rename(&temporary, &path))
+            .with_context(|| format!("Failed to write run {} to {}", record.backtest_id, path.display()))
+    }
+
+    /// Reads the record of `backtest_id`, or `None` if it was never stored
+    pub fn load(&self, backtest_id: &str) -> Result<Option<RunRecord>> {
+        let path = self.path_for(backtest_id)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        Self::read(&path).map(Some)
+    }
+
+    /// Every stored run, newest first
+    ///
+    /// Unreadable files are skipped with a warning so one corrupt record
+    /// does not hide the rest of the history.
+    pub fn list(&self) -> Result<Vec<RunRecord>> {
+        let entries = std::fs::read_dir(&self.root)
+            .with_context(|| format!("Failed to read run store {}", self.root.display()))?;
+        let mut records = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some(RECORD_EXTENSION) {
+                continue;
+            }
+            match Self::read(&path) {
+                Ok(record) => records.push(record),
+                Err(e) => warn!("Skipping unreadable run record: {:#}", e),
+            }
+        }
+        records.sort_by_key(|record| std::cmp::Reverse(record.created_at));
+        Ok(records)
+    }
+
+    /// Removes the record of `backtest_id`, returning whether one existed
+    pub fn delete(&self, backtest_id: &str) -> Result<bool> {
+        let path = self.path_for(backtest_id)?;
+        match std::fs::remove_file(&path) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e).with_context(|| format!("Failed to delete {}", path.display())),
+        }
+    }
+
+    fn read(path: &Path) -> Result<RunRecord> {
+        let bytes = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_slice(&bytes).with_context(|| format!("Invalid run record {}", path.display()))
+    }
+
+    /// File of `backtest_id`, with characters unsafe in file names escaped
+    fn path_for(&self, backtest_id: &str) -> Result<PathBuf> {
+        if backtest_id.is_empty() {
+            bail!("Backtest ID must not be empty");
+        }
+        let mut name = String::with_capacity(backtest_id.len());
+        for byte in backtest_id.bytes() {
+            if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+                name.push(char::from(byte));
+            } else {
+                let _ = write!(name, "%{:02X}", byte);
+            }
+        }
+        Ok(self.root.join(format!("{}.{}", name, RECORD_EXTENSION)))
+    }
+}
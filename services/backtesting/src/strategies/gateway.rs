@@ -0,0 +1,402 @@
+//! Adapter running trading-gateway strategies in the backtester
+//!
+//! `TradingStrategyAdapter` wraps a `trading_gateway::TradingStrategy` so the
+//! strategy deployed in the gateway is the one being backtested. Every step
+//! of the run is turned into `TradingEvent::MarketUpdate`s, signals go
+//! through the gateway's `SignalAggregator` exactly as they do live, and the
+//! resulting `OrderRequest`s are submitted to the `ExecutionSimulator`.
+//! Fills and rejections come back to the strategy as `ExecutionReport`s.
+//!
+//! Event-driven runs quote the replayed top of book. Bars carry no quotes,
+//! so bar runs synthesize a two-sided quote around the close with a
+//! configurable spread and depth. VPIN and Kyle's lambda are not derived
+//! from backtest data and are reported as zero, and the live gateway's
+//! `RiskGate` is not applied; the portfolio's own funding and margin checks
+//! still are.
+
+use crate::event_replay::to_ts;
+use crate::lifecycle::{OrderRequest, StrategyContext};
+use crate::{Fill, OrderRejection, OrderSide, OrderType, Quote, RejectReason, Strategy, TimeInForce};
+use anyhow::{bail, Result};
+use orderbook::events::OrderBookEvent;
+use services_common::{Px, Qty, Symbol, Ts};
+use std::collections::HashMap;
+use std::future::Future;
+use std::task::{Context, Poll, Waker};
+use tracing::{debug, warn};
+use serde::{Deserialize, Serialize};
+use trading_gateway::market_maker::MarketMakingStrategy;
+use trading_gateway::signal_aggregator::SignalAggregator;
+use trading_gateway::strategy::{ArbitrageStrategy, MomentumStrategy};
+use trading_gateway::{
+    OrderStatus, OrderType as GatewayOrderType, Side, TimeInForce as GatewayTimeInForce,
+    TradingEvent, TradingStrategy,
+};
+
+/// Default spread of synthesized bar quotes in basis points
+const DEFAULT_SYNTHETIC_SPREAD_BPS: f64 = 10.0;
+
+/// Default quantity on each side of synthesized bar quotes
+const DEFAULT_SYNTHETIC_DEPTH: f64 = 1.0;
+
+/// Remaining quantity below which an order counts as filled
+const FILLED_EPSILON: f64 = 1e-9;
+
+/// Built-in trading-gateway strategies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GatewayStrategy {
+    /// `trading_gateway::strategy::MomentumStrategy`
+    Momentum,
+    /// `trading_gateway::strategy::ArbitrageStrategy`
+    Arbitrage,
+    /// `trading_gateway::market_maker::MarketMakingStrategy`
+    MarketMaking,
+}
+
+impl GatewayStrategy {
+    /// Instantiates the strategy with its live defaults
+    pub fn build(self) -> Box<dyn TradingStrategy> {
+        match self {
+            Self::Momentum => Box::new(MomentumStrategy::new()),
+            Self::Arbitrage => Box::new(ArbitrageStrategy::new()),
+            Self::MarketMaking => Box::new(MarketMakingStrategy::new()),
+        }
+    }
+}
+
+/// Gateway order the adapter submitted to the simulator
+#[derive(Debug, Clone)]
+struct TrackedOrder {
+    gateway_id: u64,
+    symbol: Symbol,
+    side: Side,
+    quantity: f64,
+    filled: f64,
+}
+
+/// Runs a `trading_gateway::TradingStrategy` as a backtesting `Strategy`
+///
+/// Backtest symbols are mapped to gateway `Symbol` IDs with `with_symbol`;
+/// symbols seen without a mapping are given the next free ID. Fills the
+/// strategy did not order, such as margin liquidations, are still reported
+/// to it with order ID 0 so its inventory stays in line with the portfolio.
+pub struct TradingStrategyAdapter {
+    strategy: Box<dyn TradingStrategy>,
+    aggregator: SignalAggregator,
+    ids: HashMap<String, Symbol>,
+    names: HashMap<Symbol, String>,
+    synthetic_spread_bps: f64,
+    synthetic_depth: f64,
+    orders: HashMap<String, TrackedOrder>,
+    last_updates: HashMap<String, (f64, Option<Quote>)>,
+    errors: u64,
+}
+
+impl std::fmt::Debug for TradingStrategyAdapter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TradingStrategyAdapter")
+            .field("strategy", &self.strategy.name())
+            .field("symbols", &self.ids)
+            .field("synthetic_spread_bps", &self.synthetic_spread_bps)
+            .field("synthetic_depth", &self.synthetic_depth)
+            .field("open_orders", &self.orders.len())
+            .field("errors", &self.errors)
+            .finish()
+    }
+}
+
+impl TradingStrategyAdapter {
+    /// Wraps `strategy` with a 10 bps synthetic bar spread and unit depth
+    pub fn new(strategy: Box<dyn TradingStrategy>) -> Self {
+        Self {
+            strategy,
+            aggregator: SignalAggregator::new(),
+            ids: HashMap::new(),
+            names: HashMap::new(),
+            synthetic_spread_bps: DEFAULT_SYNTHETIC_SPREAD_BPS,
+            synthetic_depth: DEFAULT_SYNTHETIC_DEPTH,
+            orders: HashMap::new(),
+            last_updates: HashMap::new(),
+            errors: 0,
+        }
+    }
+
+    /// Feeds `name` to the strategy as gateway symbol `symbol`
+    pub fn with_symbol(mut self, name: &str, symbol: Symbol) -> Self {
+        if let Some(previous) = self.ids.insert(name.to_string(), symbol) {
+            self.names.remove(&previous);
+        }
+        self.names.insert(symbol, name.to_string());
+        self
+    }
+
+    /// Uses a full spread of `bps` basis points for synthesized bar quotes
+    pub fn with_synthetic_spread(mut self, bps: f64) -> Self {
+        self.synthetic_spread_bps = bps;
+        self
+    }
+
+    /// Quotes `quantity` on each side of synthesized bar quotes
+    pub fn with_synthetic_depth(mut self, quantity: f64) -> Self {
+        self.synthetic_depth = quantity;
+        self
+    }
+
+    /// Wrapped strategy
+    pub fn strategy(&self) -> &dyn TradingStrategy {
+        self.strategy.as_ref()
+    }
+
+    /// Strategy calls that returned an error or could not complete
+    pub fn errors(&self) -> u64 {
+        self.errors
+    }
+
+    /// Gateway symbol of `name`, assigning the next free ID if unmapped
+    fn symbol_id(&mut self, name: &str) -> Symbol {
+        if let Some(symbol) = self.ids.get(name) {
+            return *symbol;
+        }
+        let next = self.names.keys().map(|symbol| symbol.0 + 1).max().unwrap_or(0);
+        let symbol = Symbol(next);
+        self.ids.insert(name.to_string(), symbol);
+        self.names.insert(symbol, name.to_string());
+        symbol
+    }
+
+    /// Builds the market update for `symbol` at `price`
+    fn market_update(&self, symbol: Symbol, price: f64, quote: Option<Quote>, timestamp: Ts) -> TradingEvent {
+        let quote = quote.unwrap_or_else(|| {
+            let half_spread = price * self.synthetic_spread_bps / 20_000.0;
+            Quote {
+                bid_price: price - half_spread,
+                bid_size: self.synthetic_depth,
+                ask_price: price + half_spread,
+                ask_size: self.synthetic_depth,
+            }
+        });
+        let bid = Px::new(quote.bid_price);
+        let ask = Px::new(quote.ask_price);
+        let depth = quote.bid_size + quote.ask_size;
+        let imbalance = if depth > 0.0 {
+            (quote.bid_size - quote.ask_size) / depth * 100.0
+        } else {
+            0.0
+        };
+
+        TradingEvent::MarketUpdate {
+            symbol,
+            bid: Some((bid, Qty::new(quote.bid_size))),
+            ask: Some((ask, Qty::new(quote.ask_size))),
+            mid: Px::new((quote.bid_price + quote.ask_price) / 2.0),
+            spread: ask.as_i64() - bid.as_i64(),
+            imbalance,
+            vpin: 0.0,
+            kyles_lambda: 0.0,
+            timestamp,
+        }
+    }
+
+    /// Sends market updates for the symbols in `ctx`, in name order
+    ///
+    /// With `changed_only`, symbols whose price and quote are the same as
+    /// in their last update are skipped.
+    fn publish(&mut self, ctx: &mut StrategyContext<'_>, changed_only: bool) {
+        let timestamp = to_ts(ctx.timestamp());
+        let mut prices: Vec<(String, f64)> = ctx
+            .market()
+            .prices
+            .iter()
+            .map(|entry| (entry.key().clone(), *entry.value()))
+            .collect();
+        prices.sort_by(|a, b| a.0.cmp(&b.0));
+
+        for (name, price) in prices {
+            let quote = ctx.quote(&name);
+            if changed_only && self.last_updates.get(&name) == Some(&(price, quote)) {
+                continue;
+            }
+            self.last_updates.insert(name.clone(), (price, quote));
+
+            let symbol = self.symbol_id(&name);
+            let update = self.market_update(symbol, price, quote, timestamp);
+            match ready(self.strategy.on_market_update(&update)) {
+                Ok(Some(event)) => self.route(event, ctx),
+                Ok(None) => {}
+                Err(e) => self.record_error("on_market_update", &e),
+            }
+        }
+    }
+
+    /// Turns strategy output into simulator orders
+    fn route(&mut self, event: TradingEvent, ctx: &mut StrategyContext<'_>) {
+        match event {
+            TradingEvent::Signal { .. } => match ready(self.aggregator.aggregate(event)) {
+                Ok(Some(order)) => self.submit(order, ctx),
+                Ok(None) => {}
+                Err(e) => self.record_error("aggregate", &e),
+            },
+            TradingEvent::OrderRequest { .. } => self.submit(event, ctx),
+            other => debug!("Ignoring {:?} from {}", other, self.strategy.name()),
+        }
+    }
+
+    /// Places a gateway `OrderRequest` with the simulator
+    fn submit(&mut self, order: TradingEvent, ctx: &mut StrategyContext<'_>) {
+        let TradingEvent::OrderRequest { id, symbol, side, order_type, quantity, price, time_in_force, .. } = order
+        else {
+            return;
+        };
+        let Some(name) = self.names.get(&symbol).cloned() else {
+            warn!("{} ordered unknown symbol {}", self.strategy.name(), symbol);
+            return;
+        };
+
+        let (order_type, price) = match (order_type, price) {
+            // Algorithmic parent orders execute as a single market order
+            (GatewayOrderType::Market | GatewayOrderType::Twap | GatewayOrderType::Vwap, _) => (OrderType::Market, None),
+            // Iceberg orders rest their full size at the limit price
+            (GatewayOrderType::Limit | GatewayOrderType::Iceberg, Some(price)) => (OrderType::Limit, Some(price.as_f64())),
+            (order_type, _) => {
+                warn!("{} order {}: {:?} orders are not simulated", self.strategy.name(), id, order_type);
+                self.report(id, symbol, side, 0.0, 0.0, quantity.as_f64(), OrderStatus::Rejected, to_ts(ctx.timestamp()));
+                return;
+            }
+        };
+        let time_in_force = match time_in_force {
+            GatewayTimeInForce::Gtc => TimeInForce::GTC,
+            GatewayTimeInForce::Ioc => TimeInForce::IOC,
+            GatewayTimeInForce::Fok => TimeInForce::FOK,
+            GatewayTimeInForce::Day => TimeInForce::Day,
+        };
+
+        let order_id = ctx.place(OrderRequest {
+            symbol: name,
+            side: match side {
+                Side::Buy => OrderSide::Buy,
+                Side::Sell => OrderSide::Sell,
+            },
+            order_type,
+            quantity: quantity.as_f64(),
+            price,
+            stop_price: None,
+            time_in_force,
+        });
+        self.orders.insert(order_id, TrackedOrder {
+            gateway_id: id,
+            symbol,
+            side,
+            quantity: quantity.as_f64(),
+            filled: 0.0,
+        });
+    }
+
+    /// Delivers an execution report to the strategy
+    #[allow(clippy::too_many_arguments)]
+    fn report(
+        &mut self,
+        order_id: u64,
+        symbol: Symbol,
+        side: Side,
+        executed_qty: f64,
+        executed_price: f64,
+        remaining_qty: f64,
+        status: OrderStatus,
+        timestamp: Ts,
+    ) {
+        let report = TradingEvent::ExecutionReport {
+            order_id,
+            symbol,
+            side,
+            executed_qty: Qty::new(executed_qty),
+            executed_price: Px::new(executed_price),
+            remaining_qty: Qty::new(remaining_qty),
+            status,
+            timestamp,
+        };
+        if let Err(e) = ready(self.strategy.on_execution(&report)) {
+            self.record_error("on_execution", &e);
+        }
+    }
+
+    fn record_error(&mut self, call: &str, error: &anyhow::Error) {
+        self.errors += 1;
+        warn!("{} {} failed: {:#}", self.strategy.name(), call, error);
+    }
+}
+
+impl Strategy for TradingStrategyAdapter {
+    fn on_start(&mut self, _ctx: &mut StrategyContext<'_>) {
+        self.aggregator = SignalAggregator::new();
+        self.orders.clear();
+        self.last_updates.clear();
+        self.errors = 0;
+        if let Err(e) = ready(self.strategy.reset()) {
+            self.record_error("reset", &e);
+        }
+    }
+
+    fn on_bar(&mut self, ctx: &mut StrategyContext<'_>) {
+        self.publish(ctx, false);
+    }
+
+    fn on_event(&mut self, _event: &OrderBookEvent, ctx: &mut StrategyContext<'_>) {
+        self.publish(ctx, true);
+    }
+
+    fn on_fill(&mut self, fill: &Fill, _ctx: &mut StrategyContext<'_>) {
+        let side = match fill.side {
+            OrderSide::Buy => Side::Buy,
+            OrderSide::Sell => Side::Sell,
+        };
+        let timestamp = to_ts(fill.timestamp);
+
+        let Some(order) = self.orders.get_mut(&fill.order_id) else {
+            let symbol = self.symbol_id(&fill.symbol);
+            self.report(0, symbol, side, fill.quantity, fill.price, 0.0, OrderStatus::Filled, timestamp);
+            return;
+        };
+        order.filled += fill.quantity;
+        let order = order.clone();
+        let remaining = (order.quantity - order.filled).max(0.0);
+        let status = if remaining <= FILLED_EPSILON {
+            self.orders.remove(&fill.order_id);
+            OrderStatus::Filled
+        } else {
+            OrderStatus::PartiallyFilled
+        };
+        self.report(order.gateway_id, order.symbol, order.side, fill.quantity, fill.price, remaining, status, timestamp);
+    }
+
+    fn on_order_rejected(&mut self, rejection: &OrderRejection, _ctx: &mut StrategyContext<'_>) {
+        let status = match rejection.reason {
+            RejectReason::Simulated
+            | RejectReason::InsufficientFunds
+            | RejectReason::InsufficientPosition
+            | RejectReason::InvalidOrder => OrderStatus::Rejected,
+            RejectReason::Unfilled | RejectReason::LinkedOrderFilled => OrderStatus::Cancelled,
+            // The adapter never amends or cancels, so there is nothing to report
+            RejectReason::UnknownOrder => return,
+        };
+        let Some(order) = self.orders.remove(&rejection.order_id) else {
+            return;
+        };
+        let remaining = (order.quantity - order.filled).max(0.0);
+        self.report(order.gateway_id, order.symbol, order.side, 0.0, 0.0, remaining, status, to_ts(rejection.timestamp));
+    }
+}
+
+/// Runs a gateway strategy future that completes without waiting
+///
+/// Gateway strategies do their work synchronously behind `async_trait`, so
+/// their futures are ready on the first poll and the backtest stays
+/// single-threaded and deterministic. A future that would wait is reported
+/// as an error instead of blocking the run.
+fn ready<T>(future: impl Future<Output = Result<T>>) -> Result<T> {
+    let mut future = std::pin::pin!(future);
+    match future.as_mut().poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => output,
+        Poll::Pending => bail!("Strategy call did not complete without waiting"),
+    }
+}
@@ -0,0 +1,96 @@
+//! Built-in strategies that can be run by the backtesting service
+
+pub mod gateway;
+pub mod moving_average;
+pub mod script;
+
+pub use gateway::{GatewayStrategy, TradingStrategyAdapter};
+pub use moving_average::MAStrategy;
+pub use script::{ScriptLimits, ScriptStrategy};
+
+use crate::Strategy;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use services_common::Symbol;
+
+/// Strategy selection carried in `RunBacktestRequest.strategy_code`
+///
+/// The request field holds JSON tagged by `type`, e.g.
+/// `{"type": "moving_average_crossover", "symbol": "NIFTY", "fast_period": 10, "slow_period": 30}`,
+/// `{"type": "gateway", "strategy": "momentum", "symbols": ["NIFTY"]}`
+/// or `{"type": "script", "source": "fn on_bar(ctx) { ... }", "symbols": ["NIFTY"]}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StrategySpec {
+    /// Simple moving average crossover on a single symbol
+    MovingAverageCrossover {
+        /// Symbol to trade
+        symbol: String,
+        /// Fast moving average period in bars
+        fast_period: usize,
+        /// Slow moving average period in bars
+        slow_period: usize,
+    },
+    /// Trading-gateway strategy run through `TradingStrategyAdapter`
+    Gateway {
+        /// Gateway strategy to run
+        strategy: GatewayStrategy,
+        /// Symbols fed to the strategy, given gateway IDs 0, 1, ... in order
+        symbols: Vec<String>,
+        /// Full spread of synthesized bar quotes in basis points
+        #[serde(default)]
+        synthetic_spread_bps: Option<f64>,
+    },
+    /// Rhai script run in a sandbox by `ScriptStrategy`
+    Script {
+        /// Script source defining the strategy hooks
+        source: String,
+        /// Symbols the script trades
+        symbols: Vec<String>,
+        /// Values exposed to the script as `ctx.params`
+        #[serde(default)]
+        parameters: serde_json::Map<String, serde_json::Value>,
+        /// Sandbox limits
+        #[serde(default)]
+        limits: ScriptLimits,
+    },
+}
+
+impl StrategySpec {
+    /// Parses a strategy specification from its JSON form
+    pub fn parse(code: &str) -> Result<Self> {
+        serde_json::from_str(code).context("Invalid strategy specification")
+    }
+
+    /// Symbols the strategy needs market data for
+    pub fn symbols(&self) -> Vec<String> {
+        match self {
+            Self::MovingAverageCrossover { symbol, .. } => vec![symbol.clone()],
+            Self::Gateway { symbols, .. } | Self::Script { symbols, .. } => symbols.clone(),
+        }
+    }
+
+    /// Instantiates the strategy described by this specification
+    ///
+    /// Fails if a script does not compile.
+    pub fn build(&self) -> Result<Box<dyn Strategy>> {
+        Ok(match self {
+            Self::MovingAverageCrossover { symbol, fast_period, slow_period } => {
+                Box::new(MAStrategy::new(symbol.clone(), *fast_period, *slow_period))
+            }
+            Self::Gateway { strategy, symbols, synthetic_spread_bps } => {
+                let mut adapter = TradingStrategyAdapter::new(strategy.build());
+                for (id, symbol) in (0..).zip(symbols) {
+                    adapter = adapter.with_symbol(symbol, Symbol(id));
+                }
+                if let Some(bps) = synthetic_spread_bps {
+                    adapter = adapter.with_synthetic_spread(*bps);
+                }
+                Box::new(adapter)
+            }
+            Self::Script { source, parameters, limits, .. } => {
+                Box::new(ScriptStrategy::new(source, parameters, *limits)?)
+            }
+        })
+    }
+}
@@ -1,11 +1,10 @@
-//! Simple Moving Average Crossover Strategy Example
+//! Simple Moving Average Crossover Strategy
 
-use backtesting::{
+use crate::{
     Strategy, MarketSnapshot, PortfolioState, TradingSignal,
-    OrderSide, OrderType, BacktestEngine, BacktestConfig,
-    DataFrequency, SlippageModel, OHLCV,
+    OrderSide, OrderType,
 };
-use chrono::{Utc, Duration};
+use crate::lifecycle::StrategyContext;
 use std::collections::VecDeque;
 
 /// Simple Moving Average Crossover Strategy
@@ -18,6 +17,7 @@ pub struct MAStrategy {
 }
 
 impl MAStrategy {
+    /// Creates a crossover strategy trading `symbol` with the given fast and slow periods
     pub fn new(symbol: String, fast_period: usize, slow_period: usize) -> Self {
         Self {
             symbol,
@@ -43,6 +43,25 @@ impl MAStrategy {
 }
 
 impl Strategy for MAStrategy {
+    fn on_start(&mut self, _ctx: &mut StrategyContext<'_>) {
+        self.price_history.clear();
+    }
+    
+    fn on_bar(&mut self, ctx: &mut StrategyContext<'_>) {
+        // Record the bar before evaluating so the averages include it
+        let Some(price) = ctx.price(&self.symbol) else {
+            return;
+        };
+        if self.price_history.len() >= self.slow_period.max(self.fast_period).max(1) {
+            self.price_history.pop_front();
+        }
+        self.price_history.push_back(price);
+        
+        for signal in self.generate_signals(ctx.market(), ctx.portfolio()) {
+            ctx.place(signal);
+        }
+    }
+    
     fn generate_signals(&self, market: &MarketSnapshot, portfolio: &PortfolioState) -> Vec<TradingSignal> {
         let mut signals = Vec::new();
         
@@ -96,86 +115,3 @@ impl Strategy for MAStrategy {
         signals
     }
 }
-
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    println!("Simple Moving Average Strategy Backtest Example");
-    
-    // Create backtest configuration
-    let config = BacktestConfig {
-        start_date: Utc::now() - Duration::days(365),
-        end_date: Utc::now(),
-        initial_capital: 100000.0,
-        commission_rate: 0.001,
-        slippage_model: SlippageModel::Fixed { bps: 5.0 },
-        data_frequency: DataFrequency::Daily,
-        enable_shorting: false,
-        margin_requirement: 0.5,
-        risk_free_rate: 0.05,
-    };
-    
-    // Create backtest engine
-    let engine = BacktestEngine::new(config);
-    
-    // Generate sample data (in production, load from database)
-    let mut sample_data = Vec::new();
-    let mut price = 100.0;
-    let mut current = Utc::now() - Duration::days(365);
-    
-    for _ in 0..365 {
-        // Random walk for price
-        let change = (rand::random::<f64>() - 0.5) * 2.0;
-        price = (price + change).max(1.0);
-        
-        sample_data.push((
-            current,
-            OHLCV {
-                open: price - 0.5,
-                high: price + 1.0,
-                low: price - 1.0,
-                close: price,
-                volume: 1000000.0,
-            },
-        ));
-        
-        current = current + Duration::days(1);
-    }
-    
-    // Load data
-    engine.load_data("TEST", sample_data).await?;
-    
-    // Create strategy
-    let strategy = MAStrategy::new("TEST".to_string(), 10, 30);
-    
-    // Run backtest
-    println!("Running backtest...");
-    let result = engine.run(&strategy).await?;
-    
-    // Print results
-    println!("\n=== Backtest Results ===");
-    println!("Total Return: {:.2}%", result.metrics.total_return * 100.0);
-    println!("Sharpe Ratio: {:.2}", result.metrics.sharpe_ratio);
-    println!("Max Drawdown: {:.2}%", result.metrics.max_drawdown * 100.0);
-    println!("Total Trades: {}", result.metrics.total_trades);
-    println!("Win Rate: {:.2}%", result.metrics.win_rate * 100.0);
-    
-    Ok(())
-}
-
-// Simple random number generator (in production use rand crate)
-mod rand {
-    use std::sync::atomic::{AtomicU64, Ordering};
-    
-    static SEED: AtomicU64 = AtomicU64::new(12345);
-    
-    pub fn random<T>() -> T 
-    where 
-        T: From<f64>
-    {
-        let mut x = SEED.load(Ordering::Relaxed);
-        x = x.wrapping_mul(1103515245).wrapping_add(12345);
-        SEED.store(x, Ordering::Relaxed);
-        let val = ((x / 65536) % 1000) as f64 / 1000.0;
-        T::from(val)
-    }
-}
\ No newline at end of file
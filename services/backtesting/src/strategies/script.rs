@@ -0,0 +1,396 @@
+//! Sandboxed scripted strategies
+//!
+//! `ScriptStrategy` runs a strategy written in Rhai, an embedded scripting
+//! language, so researchers can submit one in `RunBacktestRequest.strategy_code`
+//! without recompiling the service. The script defines the hooks it needs as
+//! functions; hooks it leaves out do nothing, except `on_event`, which falls
+//! back to `on_bar`:
+//!
+//! ```text
+//! fn on_start(ctx) { this.closes = []; }
+//!
+//! fn on_bar(ctx) {
+//!     let price = ctx.price("NIFTY");
+//!     if price == () { return; }
+//!     this.closes.push(price);
+//!     if this.closes.len() >= ctx.params.lookback && ctx.position("NIFTY") == 0.0 {
+//!         this.entry = ctx.buy("NIFTY", 10);
+//!     }
+//! }
+//!
+//! fn on_fill(fill, ctx) { print(`filled ${fill.quantity} @ ${fill.price}`); }
+//! fn on_order_rejected(rejection, ctx) { this.entry = (); }
+//! fn on_end(ctx) {}
+//! ```
+//!
+//! State kept between hooks lives on `this`, an object map that starts empty
+//! for each run. Fills, rejections, quotes, positions and open orders are
+//! passed as object maps with the fields of their Rust types.
+//!
+//! The `ctx` argument exposes the host API:
+//!
+//! * `ctx.time` (milliseconds since the Unix epoch), `ctx.cash`, `ctx.equity`,
+//!   `ctx.params` (the spec's `parameters`), `ctx.positions` (by symbol) and
+//!   `ctx.open_orders`
+//! * `ctx.price(symbol)` and `ctx.quote(symbol)`, `()` when unknown
+//! * `ctx.position(symbol)`, the signed quantity held
+//! * `ctx.buy(symbol, quantity)`, `ctx.sell(...)`, `ctx.buy_limit(symbol,
+//!   quantity, price)` and `ctx.sell_limit(...)`, which return the order ID
+//! * `ctx.amend(order_id, quantity, price)`, with `()` for values that stay,
+//!   and `ctx.cancel(order_id)`
+//!
+//! Scripts run in a sandbox: there is no file, network or module access,
+//! `eval` is disabled and `print`/`debug` go to the service log. Each hook
+//! call may run at most `ScriptLimits::max_operations` operations, and every
+//! string, array and object map is capped in size. Rhai does not track total
+//! heap use, so memory is bounded through those caps rather than overall. A
+//! script that exceeds a limit or raises an error fails the backtest.
+
+use crate::lifecycle::{next_order_id, OrderRequest, StrategyContext};
+use crate::{Fill, OrderRejection, OrderSide, Strategy};
+use anyhow::{anyhow, Result};
+use parking_lot::Mutex;
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::serde::to_dynamic;
+use rhai::{Array, CallFnOptions, Dynamic, Engine, EvalAltResult, FuncArgs, ImmutableString, Map, Scope, AST};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::{debug, info};
+
+/// Hooks a script may define, with the number of arguments each takes
+const HOOKS: [(&str, usize); 6] = [
+    ("on_start", 1),
+    ("on_bar", 1),
+    ("on_event", 1),
+    ("on_fill", 2),
+    ("on_order_rejected", 2),
+    ("on_end", 1),
+];
+
+/// Resource limits of the script sandbox
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScriptLimits {
+    /// Operations one hook call may run before it is stopped
+    pub max_operations: u64,
+    /// Deepest nesting of script function calls
+    pub max_call_depth: usize,
+    /// Longest string in bytes
+    pub max_string_size: usize,
+    /// Most elements in one array
+    pub max_array_size: usize,
+    /// Most properties in one object map
+    pub max_map_size: usize,
+}
+
+impl Default for ScriptLimits {
+    fn default() -> Self {
+        Self {
+            max_operations: 1_000_000,
+            max_call_depth: 32,
+            max_string_size: 64 * 1024,
+            max_array_size: 100_000,
+            max_map_size: 10_000,
+        }
+    }
+}
+
+/// Order instruction issued by a script, applied once its hook returns
+#[derive(Debug, Clone)]
+enum ScriptCommand {
+    Place(String, OrderRequest),
+    Amend { order_id: String, quantity: Option<f64>, price: Option<f64> },
+    Cancel(String),
+}
+
+/// The `ctx` handed to script hooks
+///
+/// Script arguments are passed by value, so the commands live behind a
+/// shared handle that every copy appends to.
+#[derive(Debug, Clone)]
+struct ScriptContext {
+    time: i64,
+    cash: f64,
+    equity: f64,
+    prices: Map,
+    quotes: Map,
+    positions: Map,
+    open_orders: Array,
+    params: Map,
+    commands: Arc<Mutex<Vec<ScriptCommand>>>,
+}
+
+impl ScriptContext {
+    /// Copies what the script may see out of `ctx`
+    fn capture(ctx: &StrategyContext<'_>, params: &Map) -> Result<Self> {
+        let market = ctx.market();
+        let mut quotes = Map::new();
+        for entry in market.quotes.iter() {
+            quotes.insert(entry.key().as_str().into(), to_dynamic(entry.value())?);
+        }
+        let mut positions = Map::new();
+        for position in ctx.portfolio().positions.iter().filter(|position| position.quantity != 0.0) {
+            positions.insert(position.symbol.as_str().into(), to_dynamic(position)?);
+        }
+        Ok(Self {
+            time: ctx.timestamp().timestamp_millis(),
+            cash: ctx.portfolio().cash,
+            equity: ctx.portfolio().total_value,
+            prices: market.prices.iter()
+                .map(|entry| (entry.key().as_str().into(), Dynamic::from_float(*entry.value())))
+                .collect(),
+            quotes,
+            positions,
+            open_orders: ctx.open_orders().iter().map(to_dynamic).collect::<Result<_, _>>()?,
+            params: params.clone(),
+            commands: Arc::default(),
+        })
+    }
+
+    fn place(&mut self, request: OrderRequest) -> Result<ImmutableString, Box<EvalAltResult>> {
+        if !(request.quantity.is_finite() && request.quantity > 0.0) {
+            return Err(format!("Order quantity must be positive, got {}", request.quantity).into());
+        }
+        if let Some(price) = request.price.filter(|price| !(price.is_finite() && *price > 0.0)) {
+            return Err(format!("Limit price must be positive, got {}", price).into());
+        }
+        let id = next_order_id();
+        self.commands.lock().push(ScriptCommand::Place(id.clone(), request));
+        Ok(id.into())
+    }
+}
+
+/// Number passed from a script, which may be an integer or a float
+fn number(value: &Dynamic, what: &str) -> Result<f64, Box<EvalAltResult>> {
+    value.as_float()
+        .or_else(|_| value.as_int().map(|value| value as f64))
+        .map_err(|_| format!("{} must be a number, got {}", what, value.type_name()).into())
+}
+
+/// Optional number passed from a script, where `()` means none
+fn optional_number(value: &Dynamic, what: &str) -> Result<Option<f64>, Box<EvalAltResult>> {
+    if value.is_unit() { Ok(None) } else { number(value, what).map(Some) }
+}
+
+/// Engine with the sandbox limits and the `ctx` host API
+fn sandboxed_engine(limits: &ScriptLimits) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_max_operations(limits.max_operations.max(1))
+        .set_max_call_levels(limits.max_call_depth)
+        .set_max_string_size(limits.max_string_size.max(1))
+        .set_max_array_size(limits.max_array_size.max(1))
+        .set_max_map_size(limits.max_map_size.max(1))
+        .set_max_modules(0)
+        .set_module_resolver(DummyModuleResolver::new())
+        .disable_symbol("eval")
+        .on_print(|text| info!("Script: {}", text))
+        .on_debug(|text, _, position| debug!("Script {}: {}", position, text));
+
+    engine
+        .register_type_with_name::<ScriptContext>("Context")
+        .register_get("time", |ctx: &mut ScriptContext| ctx.time)
+        .register_get("cash", |ctx: &mut ScriptContext| ctx.cash)
+        .register_get("equity", |ctx: &mut ScriptContext| ctx.equity)
+        .register_get("params", |ctx: &mut ScriptContext| ctx.params.clone())
+        .register_get("positions", |ctx: &mut ScriptContext| ctx.positions.clone())
+        .register_get("open_orders", |ctx: &mut ScriptContext| ctx.open_orders.clone())
+        .register_fn("price", |ctx: &mut ScriptContext, symbol: &str| {
+            ctx.prices.get(symbol).cloned().unwrap_or(Dynamic::UNIT)
+        })
+        .register_fn("quote", |ctx: &mut ScriptContext, symbol: &str| {
+            ctx.quotes.get(symbol).cloned().unwrap_or(Dynamic::UNIT)
+        })
+        .register_fn("position", |ctx: &mut ScriptContext, symbol: &str| {
+            ctx.positions.get(symbol)
+                .and_then(|position| position.read_lock::<Map>()?.get("quantity")?.as_float().ok())
+                .unwrap_or(0.0)
+        })
+        .register_fn("buy", |ctx: &mut ScriptContext, symbol: &str, quantity: Dynamic| {
+            ctx.place(OrderRequest::market(symbol, OrderSide::Buy, number(&quantity, "quantity")?))
+        })
+        .register_fn("sell", |ctx: &mut ScriptContext, symbol: &str, quantity: Dynamic| {
+            ctx.place(OrderRequest::market(symbol, OrderSide::Sell, number(&quantity, "quantity")?))
+        })
+        .register_fn("buy_limit", |ctx: &mut ScriptContext, symbol: &str, quantity: Dynamic, price: Dynamic| {
+            ctx.place(OrderRequest::limit(symbol, OrderSide::Buy, number(&quantity, "quantity")?, number(&price, "price")?))
+        })
+        .register_fn("sell_limit", |ctx: &mut ScriptContext, symbol: &str, quantity: Dynamic, price: Dynamic| {
+            ctx.place(OrderRequest::limit(symbol, OrderSide::Sell, number(&quantity, "quantity")?, number(&price, "price")?))
+        })
+        .register_fn("amend", |ctx: &mut ScriptContext, order_id: &str, quantity: Dynamic, price: Dynamic| {
+            let command = ScriptCommand::Amend {
+                order_id: order_id.to_string(),
+                quantity: optional_number(&quantity, "quantity")?,
+                price: optional_number(&price, "price")?,
+            };
+            ctx.commands.lock().push(command);
+            Ok::<_, Box<EvalAltResult>>(())
+        })
+        .register_fn("cancel", |ctx: &mut ScriptContext, order_id: &str| {
+            ctx.commands.lock().push(ScriptCommand::Cancel(order_id.to_string()));
+        });
+    engine
+}
+
+/// Strategy whose hooks are functions of a Rhai script
+pub struct ScriptStrategy {
+    engine: Engine,
+    ast: AST,
+    hooks: HashSet<&'static str>,
+    params: Map,
+    state: Dynamic,
+    error: Option<anyhow::Error>,
+}
+
+impl std::fmt::Debug for ScriptStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScriptStrategy")
+            .field("hooks", &self.hooks)
+            .field("params", &self.params)
+            .field("state", &self.state)
+            .finish()
+    }
+}
+
+impl ScriptStrategy {
+    /// Compiles `source` to run within `limits`, with `parameters` as `ctx.params`
+    ///
+    /// Fails on syntax errors, on hooks defined with the wrong number of
+    /// arguments and on top-level statements, which would otherwise run
+    /// outside any hook.
+    pub fn new(source: &str, parameters: &serde_json::Map<String, serde_json::Value>, limits: ScriptLimits) -> Result<Self> {
+        let engine = sandboxed_engine(&limits);
+        let ast = engine.compile(source).map_err(|e| anyhow!("Invalid strategy script: {}", e))?;
+        if !AsRef::<[_]>::as_ref(&ast).is_empty() {
+            anyhow::bail!("Invalid strategy script: only function definitions are allowed at the top level");
+        }
+
+        let mut hooks = HashSet::new();
+        for function in ast.iter_functions() {
+            if let Some((name, arity)) = HOOKS.iter().find(|(name, _)| *name == function.name) {
+                if function.params.len() != *arity {
+                    anyhow::bail!("Invalid strategy script: {} takes {} argument(s), not {}", name, arity, function.params.len());
+                }
+                hooks.insert(*name);
+            }
+        }
+        if hooks.is_empty() {
+            anyhow::bail!("Invalid strategy script: no hooks defined (expected one of on_start, on_bar, on_event, on_fill, on_order_rejected, on_end)");
+        }
+
+        let params = match to_dynamic(parameters)?.try_cast::<Map>() {
+            Some(params) => params,
+            None => Map::new(),
+        };
+        Ok(Self {
+            engine,
+            ast,
+            hooks,
+            params,
+            state: Dynamic::from_map(Map::new()),
+            error: None,
+        })
+    }
+
+    /// Whether the script defines `hook`
+    pub fn defines(&self, hook: &str) -> bool {
+        self.hooks.contains(hook)
+    }
+
+    /// Calls `hook` with `args` followed by `ctx`, then applies the orders it issued
+    fn call(&mut self, hook: &'static str, ctx: &mut StrategyContext<'_>, args: impl FnOnce(Dynamic) -> Vec<Dynamic>) {
+        if self.error.is_some() || !self.defines(hook) {
+            return;
+        }
+        let script_ctx = match ScriptContext::capture(ctx, &self.params) {
+            Ok(script_ctx) => script_ctx,
+            Err(e) => {
+                self.error = Some(e.context(format!("Failed to prepare {} context", hook)));
+                return;
+            }
+        };
+        let commands = script_ctx.commands.clone();
+
+        let options = CallFnOptions::new().eval_ast(false).bind_this_ptr(&mut self.state);
+        let outcome = self.engine.call_fn_with_options::<Dynamic>(
+            options,
+            &mut Scope::new(),
+            &self.ast,
+            hook,
+            HookArgs(args(Dynamic::from(script_ctx))),
+        );
+        if let Err(e) = outcome {
+            self.error = Some(anyhow!("Script {} failed: {}", hook, e));
+            return;
+        }
+
+        for command in commands.lock().drain(..) {
+            match command {
+                ScriptCommand::Place(id, request) => {
+                    ctx.place_as(id, request);
+                }
+                ScriptCommand::Amend { order_id, quantity, price } => ctx.amend(&order_id, quantity, price),
+                ScriptCommand::Cancel(order_id) => ctx.cancel(&order_id),
+            }
+        }
+    }
+
+    /// Converts a report passed to a hook into an object map
+    fn report<T: Serialize>(&mut self, hook: &str, report: &T) -> Option<Dynamic> {
+        match to_dynamic(report) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.error = Some(anyhow!("Failed to pass report to {}: {}", hook, e));
+                None
+            }
+        }
+    }
+}
+
+/// Hook arguments assembled at call time
+struct HookArgs(Vec<Dynamic>);
+
+impl FuncArgs for HookArgs {
+    fn parse<C: Extend<Dynamic>>(self, args: &mut C) {
+        args.extend(self.0);
+    }
+}
+
+impl Strategy for ScriptStrategy {
+    fn on_start(&mut self, ctx: &mut StrategyContext<'_>) {
+        self.state = Dynamic::from_map(Map::new());
+        self.error = None;
+        self.call("on_start", ctx, |ctx| vec![ctx]);
+    }
+
+    fn on_bar(&mut self, ctx: &mut StrategyContext<'_>) {
+        self.call("on_bar", ctx, |ctx| vec![ctx]);
+    }
+
+    fn on_event(&mut self, _event: &orderbook::events::OrderBookEvent, ctx: &mut StrategyContext<'_>) {
+        let hook = if self.defines("on_event") { "on_event" } else { "on_bar" };
+        self.call(hook, ctx, |ctx| vec![ctx]);
+    }
+
+    fn on_fill(&mut self, fill: &Fill, ctx: &mut StrategyContext<'_>) {
+        if let Some(fill) = self.report("on_fill", fill) {
+            self.call("on_fill", ctx, |ctx| vec![fill, ctx]);
+        }
+    }
+
+    fn on_order_rejected(&mut self, rejection: &OrderRejection, ctx: &mut StrategyContext<'_>) {
+        if let Some(rejection) = self.report("on_order_rejected", rejection) {
+            self.call("on_order_rejected", ctx, |ctx| vec![rejection, ctx]);
+        }
+    }
+
+    fn on_end(&mut self, ctx: &mut StrategyContext<'_>) {
+        self.call("on_end", ctx, |ctx| vec![ctx]);
+    }
+
+    fn take_error(&mut self) -> Option<anyhow::Error> {
+        self.error.take()
+    }
+}
@@ -0,0 +1,416 @@
+//! Tearsheet reports of finished backtests
+//!
+//! A `Tearsheet` condenses a run into the series a reviewer looks at first:
+//! the equity curve, the drawdown from the running peak, returns by calendar
+//! month and the distribution of trade returns, alongside the headline
+//! metrics and costs. It exports as JSON for tooling or as a single HTML
+//! page with inline styles and SVG charts, which opens offline and can be
+//! attached to a review as-is.
+
+use crate::commission::CostBreakdown;
+use crate::run_store::RunRecord;
+use crate::{BacktestConfig, BacktestResult, CompletedTrade, PerformanceMetrics};
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Datelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+
+/// Bins of the trade return histogram
+const TRADE_BINS: usize = 20;
+
+/// Chart size in SVG units
+const CHART_WIDTH: f64 = 800.0;
+const CHART_HEIGHT: f64 = 220.0;
+
+/// Return over one calendar month
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MonthlyReturn {
+    /// Calendar year
+    pub year: i32,
+    /// Month of the year, from 1
+    pub month: u32,
+    /// Change in equity over the month as a decimal (e.g., 0.02 = 2%)
+    pub return_pct: f64,
+}
+
+/// Trades whose return fell in `[lower, upper)`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HistogramBin {
+    /// Lowest return in the bin as a decimal
+    pub lower: f64,
+    /// Highest return in the bin as a decimal; the last bin includes it
+    pub upper: f64,
+    /// Number of trades in the bin
+    pub count: usize,
+}
+
+/// Spread of returns across completed trades
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct TradeDistribution {
+    /// Histogram of trade returns, lowest first
+    pub bins: Vec<HistogramBin>,
+    /// Trades with a positive P&L
+    pub winning: usize,
+    /// Trades with a negative P&L
+    pub losing: usize,
+    /// Best trade return as a decimal
+    pub best: f64,
+    /// Worst trade return as a decimal
+    pub worst: f64,
+    /// Mean trade return as a decimal
+    pub mean: f64,
+}
+
+impl TradeDistribution {
+    /// Distribution of `trades` in a histogram of `bins` equal-width bins
+    pub fn of(trades: &[CompletedTrade], bins: usize) -> Self {
+        if trades.is_empty() || bins == 0 {
+            return Self::default();
+        }
+        let returns: Vec<f64> = trades.iter().map(|trade| trade.return_pct).collect();
+        let worst = returns.iter().copied().fold(f64::INFINITY, f64::min);
+        let best = returns.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+        let bins = if best > worst { bins } else { 1 };
+        let width = (best - worst) / bins as f64;
+        let mut histogram: Vec<HistogramBin> = (0..bins)
+            .map(|i| HistogramBin {
+                lower: worst + width * i as f64,
+                upper: if i + 1 == bins { best } else { worst + width * (i + 1) as f64 },
+                count: 0,
+            })
+            .collect();
+        for value in &returns {
+            let index = if width > 0.0 { ((value - worst) / width) as usize } else { 0 };
+            histogram[index.min(bins - 1)].count += 1;
+        }
+
+        Self {
+            bins: histogram,
+            winning: trades.iter().filter(|trade| trade.pnl > 0.0).count(),
+            losing: trades.iter().filter(|trade| trade.pnl < 0.0).count(),
+            best,
+            worst,
+            mean: returns.iter().sum::<f64>() / returns.len() as f64,
+        }
+    }
+}
+
+/// Report of one finished backtest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tearsheet {
+    /// Run the report describes
+    pub backtest_id: String,
+    /// Version of the code that ran the backtest, if known
+    pub code_version: Option<String>,
+    /// First day of the backtest period
+    pub start_date: DateTime<Utc>,
+    /// Last day of the backtest period
+    pub end_date: DateTime<Utc>,
+    /// Starting capital
+    pub initial_capital: f64,
+    /// Equity at the end of the run
+    pub final_equity: f64,
+    /// Headline performance and risk metrics
+    pub metrics: PerformanceMetrics,
+    /// Trading costs by component
+    pub costs: CostBreakdown,
+    /// Portfolio value over time
+    pub equity: Vec<(DateTime<Utc>, f64)>,
+    /// Decline from the running equity peak, as a negative decimal
+    pub drawdown: Vec<(DateTime<Utc>, f64)>,
+    /// Returns by calendar month, oldest first
+    pub monthly_returns: Vec<MonthlyReturn>,
+    /// Spread of trade returns
+    pub trade_distribution: TradeDistribution,
+}
+
+impl Tearsheet {
+    /// Builds the report of `result`, produced by a run with `config`
+    pub fn new(backtest_id: &str, config: &BacktestConfig, result: &BacktestResult) -> Self {
+        Self {
+            backtest_id: backtest_id.to_string(),
+            code_version: None,
+            start_date: config.start_date,
+            end_date: config.end_date,
+            initial_capital: config.initial_capital,
+            final_equity: result.equity_curve.last().map_or(config.initial_capital, |(_, value)| *value),
+            metrics: result.metrics.clone(),
+            costs: result.costs,
+            equity: result.equity_curve.clone(),
+            drawdown: drawdown_series(&result.equity_curve),
+            monthly_returns: monthly_returns(&result.equity_curve),
+            trade_distribution: TradeDistribution::of(&result.trades, TRADE_BINS),
+        }
+    }
+
+    /// Builds the report of a stored run
+    ///
+    /// Fails if the run did not complete with results.
+    pub fn from_record(record: &RunRecord) -> Result<Self> {
+        let Some(result) = record.result.as_ref() else {
+            bail!("Backtest {} has no results (status: {})", record.backtest_id, record.status.as_str());
+        };
+        Ok(Self {
+            code_version: Some(record.code_version.clone()),
+            ..Self::new(&record.backtest_id, &record.config, result)
+        })
+    }
+
+    /// Report as pretty-printed JSON
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).context("Failed to encode tearsheet")
+    }
+
+    /// Report as a self-contained HTML page
+    pub fn to_html(&self) -> String {
+        let mut html = String::with_capacity(64 * 1024);
+        let title = format!("Backtest {}", escape(&self.backtest_id));
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n<p class=\"meta\">{} to {} &middot; initial capital {:.2} &middot; final equity {:.2}{}</p>\n",
+            self.start_date.format("%Y-%m-%d"),
+            self.end_date.format("%Y-%m-%d"),
+            self.initial_capital,
+            self.final_equity,
+            self.code_version.as_deref().map(|version| format!(" &middot; code {}", escape(version))).unwrap_or_default(),
+        );
+
+        html.push_str("<h2>Summary</h2>\n<table class=\"metrics\">\n");
+        let metrics = &self.metrics;
+        let rows = [
+            ("Total return", percent(metrics.total_return)),
+            ("Annualized return", percent(metrics.annualized_return)),
+            ("Volatility", percent(metrics.volatility)),
+            ("Sharpe ratio", format!("{:.2}", metrics.sharpe_ratio)),
+            ("Sortino ratio", format!("{:.2}", metrics.sortino_ratio)),
+            ("Calmar ratio", format!("{:.2}", metrics.calmar_ratio)),
+            ("Max drawdown", percent(metrics.max_drawdown)),
+            ("Max drawdown duration", format!("{} days", metrics.max_drawdown_duration)),
+            ("Trades", metrics.total_trades.to_string()),
+            ("Win rate", percent(metrics.win_rate)),
+            ("Profit factor", format!("{:.2}", metrics.profit_factor)),
+            ("Expectancy", format!("{:.2}", metrics.expectancy)),
+            ("Trading costs", format!("{:.2}", self.costs.total())),
+        ];
+        for (label, value) in rows {
+            let _ = writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", label, value);
+        }
+        html.push_str("</table>\n");
+
+        html.push_str("<h2>Equity</h2>\n");
+        html.push_str(&line_chart(&self.equity, "#1f6feb", false));
+        html.push_str("<h2>Drawdown</h2>\n");
+        html.push_str(&line_chart(&self.drawdown, "#cf222e", true));
+        html.push_str("<h2>Monthly returns</h2>\n");
+        html.push_str(&monthly_table(&self.monthly_returns));
+        html.push_str("<h2>Trade returns</h2>\n");
+        let distribution = &self.trade_distribution;
+        let _ = writeln!(
+            html,
+            "<p class=\"meta\">{} winning &middot; {} losing &middot; best {} &middot; worst {} &middot; mean {}</p>",
+            distribution.winning,
+            distribution.losing,
+            percent(distribution.best),
+            percent(distribution.worst),
+            percent(distribution.mean),
+        );
+        html.push_str(&histogram(&distribution.bins));
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+/// Decline of each point from the highest equity before it, as a negative decimal
+pub fn drawdown_series(equity_curve: &[(DateTime<Utc>, f64)]) -> Vec<(DateTime<Utc>, f64)> {
+    let mut peak = f64::NEG_INFINITY;
+    equity_curve.iter()
+        .map(|(timestamp, value)| {
+            peak = peak.max(*value);
+            let drawdown = if peak > 0.0 { value / peak - 1.0 } else { 0.0 };
+            (*timestamp, drawdown)
+        })
+        .collect()
+}
+
+/// Compounded return of each calendar month in `equity_curve`
+///
+/// Each month runs from the last value of the month before (or the first
+/// value of the curve) to its own last value.
+pub fn monthly_returns(equity_curve: &[(DateTime<Utc>, f64)]) -> Vec<MonthlyReturn> {
+    let Some((_, first)) = equity_curve.first() else {
+        return Vec::new();
+    };
+    let mut months: Vec<(i32, u32, f64)> = Vec::new();
+    for (timestamp, value) in equity_curve {
+        let month = (timestamp.year(), timestamp.month());
+        match months.last_mut() {
+            Some(last) if (last.0, last.1) == month => last.2 = *value,
+            _ => months.push((month.0, month.1, *value)),
+        }
+    }
+
+    let mut previous = *first;
+    months.into_iter()
+        .map(|(year, month, close)| {
+            let return_pct = if previous > 0.0 { close / previous - 1.0 } else { 0.0 };
+            previous = close;
+            MonthlyReturn { year, month, return_pct }
+        })
+        .collect()
+}
+
+const STYLE: &str = "body{font-family:-apple-system,Segoe UI,Helvetica,Arial,sans-serif;margin:2em auto;max-width:860px;color:#24292f}\
+h1{font-size:1.6em;margin-bottom:0.2em}h2{font-size:1.15em;margin-top:1.6em;border-bottom:1px solid #d0d7de}\
+.meta{color:#57606a}table{border-collapse:collapse}th,td{padding:0.25em 0.7em;text-align:right;font-variant-numeric:tabular-nums}\
+.metrics th{text-align:left;font-weight:normal;color:#57606a}.monthly td,.monthly th{border:1px solid #d0d7de;font-size:0.85em}\
+svg{width:100%;height:auto}.axis{font-size:11px;fill:#57606a}";
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn percent(value: f64) -> String {
+    format!("{:.2}%", value * 100.0)
+}
+
+/// SVG line chart of `points` against time, optionally shaded down from zero
+fn line_chart(points: &[(DateTime<Utc>, f64)], color: &str, shade: bool) -> String {
+    if points.len() < 2 {
+        return "<p class=\"meta\">Not enough data to chart.</p>\n".to_string();
+    }
+    let (start, end) = (points[0].0, points[points.len() - 1].0);
+    let span = (end - start).num_milliseconds().max(1) as f64;
+    let mut low = points.iter().map(|(_, value)| *value).fold(f64::INFINITY, f64::min);
+    let mut high = points.iter().map(|(_, value)| *value).fold(f64::NEG_INFINITY, f64::max);
+    if shade {
+        high = high.max(0.0);
+        low = low.min(0.0);
+    }
+    let range = if high > low { high - low } else { 1.0 };
+    let x = |timestamp: DateTime<Utc>| (timestamp - start).num_milliseconds() as f64 / span * CHART_WIDTH;
+    let y = |value: f64| (high - value) / range * CHART_HEIGHT;
+
+    let mut path = String::new();
+    for (timestamp, value) in points {
+        let _ = write!(path, "{:.1},{:.1} ", x(*timestamp), y(*value));
+    }
+    let mut svg = format!(
+        "<svg viewBox=\"-60 -10 {} {}\" xmlns=\"http://www.w3.org/2000/svg\">\n",
+        CHART_WIDTH + 70.0,
+        CHART_HEIGHT + 30.0,
+    );
+    if shade {
+        let _ = writeln!(
+            svg,
+            "<polygon points=\"0,{zero:.1} {path}{CHART_WIDTH:.1},{zero:.1}\" fill=\"{color}\" fill-opacity=\"0.2\"/>",
+            zero = y(0.0),
+        );
+    }
+    let _ = writeln!(svg, "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\"/>", path.trim_end(), color);
+    let label = |value: f64| if shade { percent(value) } else { format!("{:.0}", value) };
+    let _ = writeln!(svg, "<text class=\"axis\" x=\"-6\" y=\"4\" text-anchor=\"end\">{}</text>", label(high));
+    let _ = writeln!(svg, "<text class=\"axis\" x=\"-6\" y=\"{:.1}\" text-anchor=\"end\">{}</text>", CHART_HEIGHT + 4.0, label(low));
+    let _ = writeln!(svg, "<text class=\"axis\" x=\"0\" y=\"{:.1}\">{}</text>", CHART_HEIGHT + 18.0, start.format("%Y-%m-%d"));
+    let _ = writeln!(
+        svg,
+        "<text class=\"axis\" x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>",
+        CHART_WIDTH,
+        CHART_HEIGHT + 18.0,
+        end.format("%Y-%m-%d"),
+    );
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Year by month grid of returns, with each year's compounded total
+fn monthly_table(returns: &[MonthlyReturn]) -> String {
+    if returns.is_empty() {
+        return "<p class=\"meta\">No monthly returns.</p>\n".to_string();
+    }
+    let mut table = String::from("<table class=\"monthly\">\n<tr><th>Year</th>");
+    for month in ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"] {
+        let _ = write!(table, "<th>{}</th>", month);
+    }
+    table.push_str("<th>Year</th></tr>\n");
+
+    let mut years: Vec<i32> = returns.iter().map(|r| r.year).collect();
+    years.dedup();
+    for year in years {
+        let _ = write!(table, "<tr><th>{}</th>", year);
+        let mut total = 1.0;
+        for month in 1..=12 {
+            match returns.iter().find(|r| r.year == year && r.month == month) {
+                Some(r) => {
+                    total *= 1.0 + r.return_pct;
+                    let _ = write!(table, "<td style=\"background:{}\">{}</td>", heat(r.return_pct), percent(r.return_pct));
+                }
+                None => table.push_str("<td></td>"),
+            }
+        }
+        let _ = writeln!(table, "<td style=\"background:{}\">{}</td></tr>", heat(total - 1.0), percent(total - 1.0));
+    }
+    table.push_str("</table>\n");
+    table
+}
+
+/// Cell colour for a return, saturating at 10% either way
+fn heat(return_pct: f64) -> String {
+    let strength = (return_pct.abs() / 0.10).min(1.0) * 0.6;
+    if return_pct >= 0.0 {
+        format!("rgba(26,127,55,{:.2})", strength)
+    } else {
+        format!("rgba(207,34,46,{:.2})", strength)
+    }
+}
+
+/// SVG bar chart of the trade return histogram
+fn histogram(bins: &[HistogramBin]) -> String {
+    let Some(tallest) = bins.iter().map(|bin| bin.count).max().filter(|count| *count > 0) else {
+        return "<p class=\"meta\">No completed trades.</p>\n".to_string();
+    };
+    let width = CHART_WIDTH / bins.len() as f64;
+    let mut svg = format!(
+        "<svg viewBox=\"-60 -10 {} {}\" xmlns=\"http://www.w3.org/2000/svg\">\n",
+        CHART_WIDTH + 70.0,
+        CHART_HEIGHT + 30.0,
+    );
+    for (i, bin) in bins.iter().enumerate() {
+        let height = bin.count as f64 / tallest as f64 * CHART_HEIGHT;
+        let color = if bin.upper <= 0.0 { "#cf222e" } else if bin.lower >= 0.0 { "#1a7f37" } else { "#8c959f" };
+        let _ = writeln!(
+            svg,
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"><title>{} to {}: {}</title></rect>",
+            i as f64 * width + 1.0,
+            CHART_HEIGHT - height,
+            (width - 2.0).max(1.0),
+            height,
+            color,
+            percent(bin.lower),
+            percent(bin.upper),
+            bin.count,
+        );
+    }
+    let _ = writeln!(svg, "<text class=\"axis\" x=\"-6\" y=\"4\" text-anchor=\"end\">{}</text>", tallest);
+    let _ = writeln!(svg, "<text class=\"axis\" x=\"0\" y=\"{:.1}\">{}</text>", CHART_HEIGHT + 18.0, percent(bins[0].lower));
+    let _ = writeln!(
+        svg,
+        "<text class=\"axis\" x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>",
+        CHART_WIDTH,
+        CHART_HEIGHT + 18.0,
+        percent(bins[bins.len() - 1].upper),
+    );
+    svg.push_str("</svg>\n");
+    svg
+}
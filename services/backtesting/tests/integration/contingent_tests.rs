@@ -0,0 +1,87 @@
+//! Integration tests for contingent orders placed by strategies
+
+use backtesting::*;
+use backtesting::contingent::BracketIds;
+use backtesting::lifecycle::*;
+use chrono::{Duration, Utc};
+use crate::test_utils::*;
+
+/// Enters one bracket on BRK, recording fills and rejections
+#[derive(Default)]
+struct BracketStrategy {
+    bracket: Option<BracketIds>,
+    entered: bool,
+    fills: Vec<Fill>,
+    rejections: Vec<OrderRejection>,
+}
+
+impl Strategy for BracketStrategy {
+    fn on_bar(&mut self, ctx: &mut StrategyContext<'_>) {
+        if !self.entered && self.bracket.is_none() {
+            self.bracket = Some(ctx.place_bracket(OrderRequest::market("BRK", OrderSide::Buy, 10.0), 95.0, 105.0));
+        }
+    }
+
+    fn on_fill(&mut self, fill: &Fill, _ctx: &mut StrategyContext<'_>) {
+        self.entered = true;
+        self.fills.push(fill.clone());
+    }
+
+    fn on_order_rejected(&mut self, rejection: &OrderRejection, _ctx: &mut StrategyContext<'_>) {
+        if self.bracket.as_ref().is_some_and(|ids| ids.entry == rejection.order_id) {
+            self.bracket = None;
+        }
+        self.rejections.push(rejection.clone());
+    }
+}
+
+/// Flat bars around 100 with one bar reaching both 90 and 110 near the end
+fn wide_bar_data() -> Vec<(chrono::DateTime<Utc>, OHLCV)> {
+    let start = Utc::now() - Duration::days(28);
+    (0..27)
+        .map(|day| {
+            let (high, low) = if day == 24 { (110.0, 90.0) } else { (100.2, 99.8) };
+            (start + Duration::days(day), OHLCV { open: 100.0, high, low, close: 100.0, volume: 10_000.0 })
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_engine_runs_bracket_through_bar_extremes() {
+    let engine = BacktestEngine::new(TestConfigFactory::basic_config());
+    engine.load_data("BRK", wide_bar_data()).await.unwrap();
+
+    let mut strategy = BracketStrategy::default();
+    let result = engine.run(&mut strategy).await.unwrap();
+
+    let ids = strategy.bracket.unwrap();
+    let fills: Vec<_> = strategy.fills.iter().map(|f| (f.order_id.clone(), f.price, f.quantity)).collect();
+    assert_eq!(fills, [(ids.entry.clone(), 100.0, 10.0), (ids.stop_loss.clone(), 95.0, 10.0)]);
+    let cancelled = strategy.rejections.last().unwrap();
+    assert_eq!((cancelled.order_id.as_str(), cancelled.reason), (ids.take_profit.as_str(), RejectReason::LinkedOrderFilled));
+    assert!(result.final_portfolio.positions.iter().all(|p| p.symbol != "BRK" || p.quantity == 0.0));
+}
+
+#[tokio::test]
+async fn test_engine_reports_invalid_bracket() {
+    /// Places a bracket whose stop-loss is above its take-profit
+    struct InvertedBracket(Option<BracketIds>);
+
+    impl Strategy for InvertedBracket {
+        fn on_bar(&mut self, ctx: &mut StrategyContext<'_>) {
+            if self.0.is_none() {
+                self.0 = Some(ctx.place_bracket(OrderRequest::market("BRK", OrderSide::Buy, 1.0), 105.0, 95.0));
+            }
+        }
+
+        fn on_order_rejected(&mut self, rejection: &OrderRejection, _ctx: &mut StrategyContext<'_>) {
+            assert_eq!(rejection.reason, RejectReason::InvalidOrder);
+        }
+    }
+
+    let engine = BacktestEngine::new(TestConfigFactory::basic_config());
+    engine.load_data("BRK", wide_bar_data()).await.unwrap();
+    let mut strategy = InvertedBracket(None);
+    let result = engine.run(&mut strategy).await.unwrap();
+    assert!(result.trades.is_empty());
+}
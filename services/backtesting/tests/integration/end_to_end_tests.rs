@@ -18,12 +18,12 @@ async fn test_complete_backtest_trending_market() {
     engine.load_data("TREND", data).await.unwrap();
     
     // Run with buy-and-hold strategy
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "TREND".to_string(),
         position_size: 1000.0,
     };
     
-    let result = engine.run(&strategy).await;
+    let result = engine.run(&mut strategy).await;
     assert!(result.is_ok(), "Complete backtest should succeed");
     
     let backtest_result = result.unwrap();
@@ -59,12 +59,12 @@ async fn test_complete_backtest_volatile_market() {
     engine.load_data("VOLATILE", data).await.unwrap();
     
     // Run with more conservative strategy
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "VOLATILE".to_string(),
         position_size: 500.0, // Smaller position in volatile market
     };
     
-    let result = engine.run(&strategy).await;
+    let result = engine.run(&mut strategy).await;
     assert!(result.is_ok());
     
     let backtest_result = result.unwrap();
@@ -91,12 +91,12 @@ async fn test_complete_backtest_sideways_market() {
     let data = TestDataFactory::sideways_data(40, 200.0, 10.0);
     engine.load_data("SIDEWAYS", data).await.unwrap();
     
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "SIDEWAYS".to_string(),
         position_size: 800.0,
     };
     
-    let result = engine.run(&strategy).await.unwrap();
+    let result = engine.run(&mut strategy).await.unwrap();
     
     // In sideways market, return should be close to zero (minus costs)
     TestAssertions::assert_metric_reasonable(result.metrics.total_return, "total_return", -0.2, 0.2);
@@ -122,12 +122,12 @@ async fn test_multi_symbol_backtest() {
     }
     
     // Strategy that focuses on one symbol
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "TECH".to_string(),
         position_size: 2000.0,
     };
     
-    let result = engine.run(&strategy).await.unwrap();
+    let result = engine.run(&mut strategy).await.unwrap();
     
     // Should successfully handle multiple symbols
     TestAssertions::assert_portfolio_valid(&result.final_portfolio);
@@ -155,12 +155,12 @@ async fn test_backtest_with_commission_impact() {
     let data = TestDataFactory::trending_up_data(20, 100.0);
     engine.load_data("COMMISSION", data).await.unwrap();
     
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "COMMISSION".to_string(),
         position_size: 5000.0,
     };
     
-    let result = engine.run(&strategy).await.unwrap();
+    let result = engine.run(&mut strategy).await.unwrap();
     
     // With high commission, should impact returns
     assert!(result.metrics.total_commission > 0.0, "Should have incurred commission costs");
@@ -183,12 +183,12 @@ async fn test_backtest_with_high_slippage() {
     let data = TestDataFactory::trending_up_data(25, 150.0);
     engine.load_data("SLIPPAGE", data).await.unwrap();
     
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "SLIPPAGE".to_string(),
         position_size: 3000.0,
     };
     
-    let result = engine.run(&strategy).await.unwrap();
+    let result = engine.run(&mut strategy).await.unwrap();
     
     // Should have slippage costs
     assert!(result.metrics.total_slippage >= 0.0, "Should have incurred slippage costs");
@@ -216,12 +216,12 @@ async fn test_buy_and_sell_complete_cycle() {
     engine.load_data("CYCLE", data).await.unwrap();
     
     // Strategy that might buy and then sell
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "CYCLE".to_string(),
         position_size: 1500.0,
     };
     
-    let result = engine.run(&strategy).await.unwrap();
+    let result = engine.run(&mut strategy).await.unwrap();
     
     // Should handle the complete cycle
     TestAssertions::assert_portfolio_valid(&result.final_portfolio);
@@ -251,8 +251,8 @@ async fn test_backtest_different_frequencies() {
         
         engine.load_data("FREQ_TEST", data).await.unwrap();
         
-        let strategy = DoNothingStrategy;
-        let result = engine.run(&strategy).await;
+        let mut strategy = DoNothingStrategy;
+        let result = engine.run(&mut strategy).await;
         
         assert!(result.is_ok(), "Should handle frequency: {:?}", frequency);
         
@@ -272,12 +272,12 @@ async fn test_backtest_with_gaps_in_data() {
     let data = TestDataFactory::gapped_data(30, 120.0, 0.3); // 30% gap probability
     engine.load_data("GAPPED", data).await.unwrap();
     
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "GAPPED".to_string(),
         position_size: 1000.0,
     };
     
-    let result = engine.run(&strategy).await;
+    let result = engine.run(&mut strategy).await;
     assert!(result.is_ok(), "Should handle gaps in data gracefully");
     
     let backtest_result = result.unwrap();
@@ -300,12 +300,12 @@ async fn test_backtest_performance_consistency() {
         let data = TestDataFactory::trending_up_data(20, 100.0);
         engine.load_data("CONSISTENT", data).await.unwrap();
         
-        let strategy = AlwaysBuyStrategy {
+        let mut strategy = AlwaysBuyStrategy {
             symbol: "CONSISTENT".to_string(),
             position_size: 1000.0,
         };
         
-        let result = engine.run(&strategy).await.unwrap();
+        let result = engine.run(&mut strategy).await.unwrap();
         results.push(result);
     }
     
@@ -344,12 +344,12 @@ async fn test_backtest_with_shorting_enabled() {
     let data = TestDataFactory::trending_down_data(15, 200.0);
     engine.load_data("SHORT", data).await.unwrap();
     
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "SHORT".to_string(),
         position_size: 500.0,
     };
     
-    let result = engine.run(&strategy).await;
+    let result = engine.run(&mut strategy).await;
     assert!(result.is_ok(), "Should handle shorting configuration");
     
     let backtest_result = result.unwrap();
@@ -370,12 +370,12 @@ async fn test_large_scale_backtest() {
     let data = TestDataFactory::volatile_data(100, 500.0, 20.0);
     engine.load_data("LARGE", data).await.unwrap();
     
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "LARGE".to_string(),
         position_size: 10_000.0,
     };
     
-    let result = engine.run(&strategy).await;
+    let result = engine.run(&mut strategy).await;
     assert!(result.is_ok(), "Should handle large-scale backtests");
     
     let backtest_result = result.unwrap();
@@ -422,12 +422,12 @@ async fn test_backtest_with_extreme_market_conditions() {
     
     engine.load_data("EXTREME", data).await.unwrap();
     
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "EXTREME".to_string(),
         position_size: 1000.0,
     };
     
-    let result = engine.run(&strategy).await;
+    let result = engine.run(&mut strategy).await;
     assert!(result.is_ok(), "Should handle extreme market conditions");
     
     let backtest_result = result.unwrap();
@@ -451,12 +451,12 @@ async fn test_backtest_result_completeness() {
     let data = TestDataFactory::trending_up_data(30, 100.0);
     engine.load_data("COMPLETE", data).await.unwrap();
     
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "COMPLETE".to_string(),
         position_size: 1200.0,
     };
     
-    let result = engine.run(&strategy).await.unwrap();
+    let result = engine.run(&mut strategy).await.unwrap();
     
     // Verify all result components are present and valid
     
@@ -0,0 +1,83 @@
+//! Integration tests for event-driven backtests over order book streams
+
+use backtesting::*;
+use chrono::{DateTime, Duration, Utc};
+use orderbook::events::{OrderBookEvent, Side};
+use crate::test_utils::*;
+
+fn event_config(start: DateTime<Utc>) -> BacktestConfig {
+    let mut config = TestConfigFactory::basic_config();
+    config.start_date = start;
+    config.end_date = start + Duration::minutes(10);
+    config.data_frequency = DataFrequency::Tick;
+    config.commission_rate = 0.0;
+    config
+}
+
+/// Snapshot, a run of trade prints, then deltas that move the book up
+///
+/// The default execution config rejects the first ten orders, so the
+/// trade prints give a strategy enough events to get an order through.
+fn book_stream(start: DateTime<Utc>) -> Vec<OrderBookEvent> {
+    let mut events = vec![
+        TestEventFactory::snapshot(1, start + Duration::seconds(1), &[(99.0, 10.0)], &[(101.0, 10.0), (102.0, 20.0)]),
+    ];
+    for sequence in 2..=13 {
+        events.push(TestEventFactory::trade(sequence, start + Duration::seconds(2), 100.0, 1.0, Side::Buy));
+    }
+    events.push(TestEventFactory::level_delta(14, start + Duration::seconds(3), Side::Sell, 101.0, 0.0));
+    events.push(TestEventFactory::level_delta(15, start + Duration::seconds(3), Side::Sell, 102.0, 0.0));
+    events.push(TestEventFactory::level_delta(16, start + Duration::seconds(3), Side::Buy, 104.0, 5.0));
+    events.push(TestEventFactory::level_delta(17, start + Duration::seconds(4), Side::Sell, 106.0, 5.0));
+    events
+}
+
+#[tokio::test]
+async fn test_event_driven_run_fills_against_depth() {
+    TestRandom::reset();
+    let start = Utc::now() - Duration::hours(1);
+    let engine = BacktestEngine::new(event_config(start));
+    // Deliver the stream out of order; the engine replays it by sequence
+    let mut events = book_stream(start);
+    events.reverse();
+    engine.load_events("BOOK", events).await.unwrap();
+
+    let mut strategy = AlwaysBuyStrategy { symbol: "BOOK".to_string(), position_size: 15.0 };
+    let result = engine.run(&mut strategy).await.unwrap();
+
+    let position = result.final_portfolio.positions.iter().find(|p| p.symbol == "BOOK").unwrap();
+    assert_eq!(position.quantity, 15.0);
+    TestAssertions::assert_approx_eq(position.average_price, (10.0 * 101.0 + 5.0 * 102.0) / 15.0, 1e-9);
+    // Final mark is the mid of the last book: (104 + 106) / 2
+    assert_eq!(position.current_price, 105.0);
+
+    let state = engine.get_state();
+    assert_eq!(state.trades_executed, 1);
+    assert_eq!(state.progress_pct, 100.0);
+    assert!(result.equity_curve.len() >= 2);
+    assert_eq!(result.equity_curve.last().unwrap().0, start + Duration::seconds(4));
+}
+
+#[tokio::test]
+async fn test_event_driven_run_merges_symbols() {
+    TestRandom::reset();
+    let start = Utc::now() - Duration::hours(1);
+    let engine = BacktestEngine::new(event_config(start));
+    engine.load_events("BOOK", book_stream(start)).await.unwrap();
+    engine.load_events("OTHER", vec![
+        TestEventFactory::snapshot(1, start + Duration::seconds(2), &[(49.0, 1.0)], &[(51.0, 1.0)]),
+    ]).await.unwrap();
+
+    let result = engine.run(&mut DoNothingStrategy).await.unwrap();
+    assert_eq!(result.final_portfolio.total_value, 100_000.0);
+    assert!(result.trades.is_empty());
+}
+
+#[tokio::test]
+async fn test_load_events_outside_period_rejected() {
+    let start = Utc::now() - Duration::hours(1);
+    let engine = BacktestEngine::new(event_config(start));
+    let stale = vec![TestEventFactory::trade(1, start - Duration::days(1), 100.0, 1.0, Side::Buy)];
+
+    assert!(engine.load_events("BOOK", stale).await.is_err());
+}
@@ -0,0 +1,80 @@
+//! Integration tests for backtesting trading-gateway strategies
+
+use backtesting::*;
+use backtesting::strategies::{GatewayStrategy, TradingStrategyAdapter};
+use chrono::{DateTime, Duration, Utc};
+use services_common::{Qty, Symbol};
+use trading_gateway::{OrderStatus, OrderType as GatewayOrderType, Side, TimeInForce as GatewayTimeInForce, TradingEvent};
+use crate::test_utils::*;
+
+fn market_buy(id: u64, quantity: f64) -> TradingEvent {
+    TradingEvent::OrderRequest {
+        id,
+        symbol: Symbol(1),
+        side: Side::Buy,
+        order_type: GatewayOrderType::Market,
+        quantity: Qty::new(quantity),
+        price: None,
+        time_in_force: GatewayTimeInForce::Ioc,
+        strategy_id: "Scripted".to_string(),
+    }
+}
+
+fn daily_config(start: DateTime<Utc>, days: i64) -> BacktestConfig {
+    let mut config = TestConfigFactory::basic_config();
+    config.start_date = start;
+    config.end_date = start + Duration::days(days - 1);
+    config.data_frequency = DataFrequency::Daily;
+    config
+}
+
+#[tokio::test]
+async fn test_gateway_orders_execute_and_report_back() {
+    TestRandom::reset();
+    let start = Utc::now() - Duration::days(30);
+    let engine = BacktestEngine::new(daily_config(start, 30));
+    engine.load_data("NIFTY", TestDataFactory::sideways_data(30, 100.0, 2.0)).await.unwrap();
+
+    // Retry until the simulated rejections run out
+    let (scripted, log) = ScriptedGatewayStrategy::new((0..20).map(|id| market_buy(id, 10.0)).collect());
+    let mut strategy = TradingStrategyAdapter::new(Box::new(scripted)).with_symbol("NIFTY", Symbol(1));
+    let result = engine.run(&mut strategy).await.unwrap();
+
+    let log = log.lock();
+    assert_eq!(log.resets, 1);
+    assert!(log.updates.len() >= 20);
+    assert_eq!(strategy.errors(), 0);
+
+    let (rejected, filled): (Vec<_>, Vec<_>) = log.reports.iter().partition(|report| {
+        matches!(report, TradingEvent::ExecutionReport { status: OrderStatus::Rejected, .. })
+    });
+    assert_eq!(rejected.len(), 10);
+    assert_eq!(filled.len(), 1);
+    let TradingEvent::ExecutionReport { order_id, symbol, executed_qty, executed_price, status, .. } = filled[0] else {
+        unreachable!();
+    };
+    assert!(matches!(status, OrderStatus::Filled));
+    assert_eq!((*order_id, *symbol, *executed_qty), (10, Symbol(1), Qty::new(10.0)));
+
+    let position = &result.final_portfolio.positions[0];
+    assert_eq!(position.symbol, "NIFTY");
+    assert_eq!(position.quantity, 10.0);
+    TestAssertions::assert_approx_eq(position.average_price, executed_price.as_f64(), 1e-4);
+}
+
+#[tokio::test]
+async fn test_market_making_strategy_runs_in_backtest() {
+    TestRandom::reset();
+    let start = Utc::now() - Duration::days(60);
+    let engine = BacktestEngine::new(daily_config(start, 60));
+    engine.load_data("NIFTY", TestDataFactory::volatile_data(60, 100.0, 4.0)).await.unwrap();
+
+    let mut strategy = TradingStrategyAdapter::new(GatewayStrategy::MarketMaking.build()).with_symbol("NIFTY", Symbol(1));
+    let result = engine.run(&mut strategy).await.unwrap();
+
+    // Resting bids below the close fill once the market trades down to them
+    assert_eq!(strategy.errors(), 0);
+    let position = result.final_portfolio.positions.iter().find(|p| p.symbol == "NIFTY").unwrap();
+    assert!(position.quantity > 0.0);
+    TestAssertions::assert_portfolio_valid(&result.final_portfolio);
+}
@@ -0,0 +1,407 @@
+//! Integration tests for the backtesting gRPC service job lifecycle
+
+use backtesting::data_source::InMemoryDataSource;
+use backtesting::benchmark::BenchmarkConfig;
+use backtesting::grpc_service::{BacktestingService, MAX_MONTE_CARLO_SIMULATIONS};
+use backtesting::run_store::{RunStore, CODE_VERSION};
+use backtesting::DataFrequency;
+use chrono::{Duration, Utc};
+use services_common::proto::{
+    BacktestingService as BacktestingTrait,
+    CompareBacktestsRequest, ExportTearsheetRequest,
+    GetBacktestResultsRequest, GetBacktestStatusRequest, ListBacktestsRequest,
+    RunBacktestRequest, StopBacktestRequest,
+};
+use std::sync::Arc;
+use tonic::{Code, Request};
+use crate::test_utils::*;
+
+const MA_STRATEGY: &str = r#"{"type": "moving_average_crossover", "symbol": "TREND", "fast_period": 3, "slow_period": 5}"#;
+
+fn service_with_trend_data() -> BacktestingService {
+    TestRandom::reset();
+    let source = InMemoryDataSource::new();
+    source.insert("TREND", TestDataFactory::trending_up_data(40, 100.0));
+    BacktestingService::with_data_source(Arc::new(source))
+}
+
+fn run_request(backtest_id: &str, config: &backtesting::BacktestConfig) -> RunBacktestRequest {
+    RunBacktestRequest {
+        backtest_id: backtest_id.to_string(),
+        strategy_code: MA_STRATEGY.to_string(),
+        config: serde_json::to_string(config).unwrap(),
+        symbols: vec![],
+        start_date: None,
+        end_date: None,
+        initial_capital: 0.0,
+    }
+}
+
+async fn wait_for_status(service: &BacktestingService, backtest_id: &str, expected: &str) {
+    for _ in 0..500 {
+        let status = service
+            .get_backtest_status(Request::new(GetBacktestStatusRequest { backtest_id: backtest_id.to_string() }))
+            .await
+            .unwrap()
+            .into_inner();
+        if status.status == expected {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    panic!("Backtest {} never reached {}", backtest_id, expected);
+}
+
+#[tokio::test]
+async fn test_run_backtest_completes_and_returns_results() {
+    let service = service_with_trend_data();
+    let config = TestConfigFactory::basic_config();
+
+    let response = service.run_backtest(Request::new(run_request("bt-complete", &config))).await.unwrap();
+    assert_eq!(response.into_inner().status, "STARTED");
+
+    wait_for_status(&service, "bt-complete", "COMPLETED").await;
+
+    let status = service
+        .get_backtest_status(Request::new(GetBacktestStatusRequest { backtest_id: "bt-complete".to_string() }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(status.progress_pct, 100.0);
+
+    let results = service
+        .get_backtest_results(Request::new(GetBacktestResultsRequest { backtest_id: "bt-complete".to_string(), ..Default::default() }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(results.metrics.is_some());
+    assert!(results.costs.is_some());
+    assert!(results.equity_curve.len() >= 2, "Equity curve should cover the run");
+    let portfolio: backtesting::PortfolioState = serde_json::from_str(&results.final_portfolio).unwrap();
+    TestAssertions::assert_portfolio_valid(&portfolio);
+}
+
+#[tokio::test]
+async fn test_results_unavailable_until_completed() {
+    let service = service_with_trend_data();
+    let mut config = TestConfigFactory::basic_config();
+    config.data_frequency = DataFrequency::Minute;
+    config.start_date = Utc::now() - Duration::days(365);
+
+    service.run_backtest(Request::new(run_request("bt-pending", &config))).await.unwrap();
+
+    let err = service
+        .get_backtest_results(Request::new(GetBacktestResultsRequest { backtest_id: "bt-pending".to_string(), ..Default::default() }))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::FailedPrecondition);
+
+    service.stop_backtest(Request::new(StopBacktestRequest { backtest_id: "bt-pending".to_string() })).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_stop_backtest_cancels_job() {
+    let service = service_with_trend_data();
+    let mut config = TestConfigFactory::basic_config();
+    config.data_frequency = DataFrequency::Minute;
+    config.start_date = Utc::now() - Duration::days(365);
+
+    service.run_backtest(Request::new(run_request("bt-stop", &config))).await.unwrap();
+
+    let stop = service
+        .stop_backtest(Request::new(StopBacktestRequest { backtest_id: "bt-stop".to_string() }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(stop.success);
+
+    wait_for_status(&service, "bt-stop", "CANCELLED").await;
+
+    let again = service
+        .stop_backtest(Request::new(StopBacktestRequest { backtest_id: "bt-stop".to_string() }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!again.success, "Stopping a finished backtest should not succeed");
+}
+
+#[tokio::test]
+async fn test_missing_data_fails_backtest() {
+    let service = BacktestingService::new();
+    let config = TestConfigFactory::basic_config();
+
+    service.run_backtest(Request::new(run_request("bt-nodata", &config))).await.unwrap();
+    wait_for_status(&service, "bt-nodata", "FAILED").await;
+}
+
+#[tokio::test]
+async fn test_invalid_requests_rejected() {
+    let service = service_with_trend_data();
+    let config = TestConfigFactory::basic_config();
+
+    let mut bad_strategy = run_request("bt-bad-strategy", &config);
+    bad_strategy.strategy_code = "not json".to_string();
+    let err = service.run_backtest(Request::new(bad_strategy)).await.unwrap_err();
+    assert_eq!(err.code(), Code::InvalidArgument);
+
+    let mut bad_config = run_request("bt-bad-config", &config);
+    bad_config.config = "{}".to_string();
+    let err = service.run_backtest(Request::new(bad_config)).await.unwrap_err();
+    assert_eq!(err.code(), Code::InvalidArgument);
+
+    let err = service
+        .get_backtest_status(Request::new(GetBacktestStatusRequest { backtest_id: "missing".to_string() }))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn test_list_backtests_filters_by_status() {
+    let service = service_with_trend_data();
+    let config = TestConfigFactory::basic_config();
+
+    service.run_backtest(Request::new(run_request("bt-list-1", &config))).await.unwrap();
+    service.run_backtest(Request::new(run_request("bt-list-2", &config))).await.unwrap();
+    wait_for_status(&service, "bt-list-1", "COMPLETED").await;
+    wait_for_status(&service, "bt-list-2", "COMPLETED").await;
+
+    let all = service
+        .list_backtests(Request::new(ListBacktestsRequest { limit: 0, offset: 0, status_filter: String::new() }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(all.total, 2);
+    assert_eq!(all.backtests.len(), 2);
+    assert!(all.backtests.iter().all(|b| b.completed_at.is_some()));
+
+    let page = service
+        .list_backtests(Request::new(ListBacktestsRequest { limit: 1, offset: 1, status_filter: "completed".to_string() }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(page.total, 2);
+    assert_eq!(page.backtests.len(), 1);
+
+    let failed = service
+        .list_backtests(Request::new(ListBacktestsRequest { limit: 0, offset: 0, status_filter: "FAILED".to_string() }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(failed.total, 0);
+}
+
+#[tokio::test]
+async fn test_run_backtest_loads_data_source_from_config() {
+    TestRandom::reset();
+    let dir = tempfile::tempdir().unwrap();
+    let mut csv = String::from("time,o,h,l,c,v\n");
+    for (timestamp, bar) in TestDataFactory::trending_up_data(40, 100.0) {
+        csv.push_str(&format!("{},{},{},{},{},{}\n", timestamp.to_rfc3339(), bar.open, bar.high, bar.low, bar.close, bar.volume));
+    }
+    std::fs::write(dir.path().join("TREND.csv"), csv).unwrap();
+
+    let mut config = TestConfigFactory::basic_config();
+    config.data_source = Some(serde_json::from_value(serde_json::json!({
+        "type": "csv",
+        "path": dir.path().join("{symbol}.csv"),
+        "columns": {"timestamp": "time", "open": "o", "high": "h", "low": "l", "close": "c", "volume": "v"},
+    })).unwrap());
+
+    // The service's own source has no data, so bars must come from the CSV
+    let service = BacktestingService::new();
+    service.run_backtest(Request::new(run_request("bt-csv", &config))).await.unwrap();
+    wait_for_status(&service, "bt-csv", "COMPLETED").await;
+
+    config.data_source = Some(serde_json::from_value(serde_json::json!({"type": "csv", "path": "x.csv", "utc_offset": "IST"})).unwrap());
+    let error = service.run_backtest(Request::new(run_request("bt-bad-source", &config))).await.unwrap_err();
+    assert_eq!(error.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn test_results_include_monte_carlo_analysis_on_request() {
+    let service = service_with_trend_data();
+    let config = TestConfigFactory::basic_config();
+    service.run_backtest(Request::new(run_request("bt-monte-carlo", &config))).await.unwrap();
+    wait_for_status(&service, "bt-monte-carlo", "COMPLETED").await;
+
+    let plain = service
+        .get_backtest_results(Request::new(GetBacktestResultsRequest {
+            backtest_id: "bt-monte-carlo".to_string(),
+            ..Default::default()
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(plain.robustness.is_none(), "Analysis is opt-in");
+
+    let request = || GetBacktestResultsRequest {
+        backtest_id: "bt-monte-carlo".to_string(),
+        monte_carlo_simulations: 200,
+        monte_carlo_seed: 7,
+    };
+    let robustness = service.get_backtest_results(Request::new(request())).await.unwrap().into_inner().robustness.unwrap();
+    assert_eq!(robustness.confidence_level, 0.95);
+
+    let block = robustness.block_bootstrap.as_ref().expect("A month of equity supports block resampling");
+    assert_eq!(block.simulations, 200);
+    let total_return = block.total_return.as_ref().unwrap();
+    assert!(total_return.lower <= total_return.median && total_return.median <= total_return.upper);
+    assert!((0.0..=1.0).contains(&block.probability_of_ruin));
+
+    let again = service.get_backtest_results(Request::new(request())).await.unwrap().into_inner().robustness.unwrap();
+    assert_eq!(again, robustness, "Same seed gives the same analysis");
+
+    let too_many = GetBacktestResultsRequest { monte_carlo_simulations: MAX_MONTE_CARLO_SIMULATIONS + 1, ..request() };
+    let error = service.get_backtest_results(Request::new(too_many)).await.unwrap_err();
+    assert_eq!(error.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn test_results_measured_against_configured_benchmark() {
+    TestRandom::reset();
+    let source = InMemoryDataSource::new();
+    source.insert("TREND", TestDataFactory::trending_up_data(40, 100.0));
+    source.insert("NIFTY", TestDataFactory::sideways_data(40, 20_000.0, 200.0));
+    let service = BacktestingService::with_data_source(Arc::new(source));
+
+    let mut config = TestConfigFactory::basic_config();
+    config.benchmark = Some(BenchmarkConfig { symbol: "NIFTY".to_string(), rolling_window: 5 });
+    service.run_backtest(Request::new(run_request("bt-benchmark", &config))).await.unwrap();
+    wait_for_status(&service, "bt-benchmark", "COMPLETED").await;
+
+    let benchmark = service
+        .get_backtest_results(Request::new(GetBacktestResultsRequest { backtest_id: "bt-benchmark".to_string(), ..Default::default() }))
+        .await
+        .unwrap()
+        .into_inner()
+        .benchmark
+        .expect("Benchmark metrics are reported when configured");
+    assert_eq!(benchmark.symbol, "NIFTY");
+    assert_eq!(benchmark.rolling_window, 5);
+    assert!(benchmark.overall.is_some());
+    assert!(!benchmark.rolling.is_empty());
+    assert!(benchmark.rolling.iter().all(|point| point.timestamp.is_some() && point.metrics.is_some()));
+
+    // A benchmark without data fails the run rather than silently dropping it
+    config.benchmark = Some(BenchmarkConfig::new("MISSING"));
+    service.run_backtest(Request::new(run_request("bt-no-benchmark", &config))).await.unwrap();
+    wait_for_status(&service, "bt-no-benchmark", "FAILED").await;
+}
+
+fn service_with_store(dir: &std::path::Path) -> BacktestingService {
+    service_with_trend_data().with_run_store(RunStore::open(dir).unwrap())
+}
+
+#[tokio::test]
+async fn test_finished_runs_survive_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = TestConfigFactory::basic_config();
+    {
+        let service = service_with_store(dir.path());
+        service.run_backtest(Request::new(run_request("bt-persist", &config))).await.unwrap();
+        wait_for_status(&service, "bt-persist", "COMPLETED").await;
+        let mut no_data = run_request("bt-persist-failed", &config);
+        no_data.symbols = vec!["MISSING".to_string()];
+        service.run_backtest(Request::new(no_data)).await.unwrap();
+        wait_for_status(&service, "bt-persist-failed", "FAILED").await;
+    }
+
+    // Finished jobs are saved once their status is reported
+    let store = RunStore::open(dir.path()).unwrap();
+    for _ in 0..500 {
+        if store.list().unwrap().len() == 2 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    let record = store.load("bt-persist").unwrap().unwrap();
+    assert_eq!(record.strategy_code, MA_STRATEGY);
+    assert_eq!(record.symbols, ["TREND"]);
+    assert_eq!(record.code_version, CODE_VERSION);
+    let coverage = &record.data[0];
+    assert_eq!(coverage.symbol, "TREND");
+    assert!(coverage.bars > 0 && coverage.first_bar >= config.start_date && coverage.last_bar <= config.end_date);
+    let stored = record.result.as_ref().unwrap();
+
+    // A new service answers from the stored history
+    let restarted = BacktestingService::new().with_run_store(store);
+    wait_for_status(&restarted, "bt-persist", "COMPLETED").await;
+    let results = restarted
+        .get_backtest_results(Request::new(GetBacktestResultsRequest { backtest_id: "bt-persist".to_string(), ..Default::default() }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(results.trades.len(), stored.trades.len());
+    assert_eq!(results.equity_curve.len(), stored.equity_curve.len());
+    assert_eq!(results.metrics.unwrap().total_return, stored.metrics.total_return);
+
+    let all = restarted
+        .list_backtests(Request::new(ListBacktestsRequest { limit: 0, offset: 0, status_filter: String::new() }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(all.total, 2);
+    assert!(all.backtests.iter().all(|summary| summary.code_version == CODE_VERSION));
+    let failed = restarted
+        .list_backtests(Request::new(ListBacktestsRequest { limit: 0, offset: 0, status_filter: "failed".to_string() }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(failed.backtests[0].backtest_id, "bt-persist-failed");
+}
+
+#[tokio::test]
+async fn test_compare_backtests_across_live_and_stored_runs() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = TestConfigFactory::basic_config();
+    let service = service_with_store(dir.path());
+    service.run_backtest(Request::new(run_request("bt-compare-1", &config))).await.unwrap();
+    wait_for_status(&service, "bt-compare-1", "COMPLETED").await;
+
+    let restarted = service_with_store(dir.path());
+    config.initial_capital *= 2.0;
+    restarted.run_backtest(Request::new(run_request("bt-compare-2", &config))).await.unwrap();
+    wait_for_status(&restarted, "bt-compare-2", "COMPLETED").await;
+
+    let request = |ids: &[&str]| Request::new(CompareBacktestsRequest {
+        backtest_ids: ids.iter().map(|id| id.to_string()).collect(),
+    });
+    let comparison = restarted.compare_backtests(request(&["bt-compare-1", "bt-compare-2"])).await.unwrap().into_inner();
+    assert_eq!(comparison.backtest_ids, ["bt-compare-1", "bt-compare-2"]);
+    let total_return = comparison.metrics.iter().find(|metric| metric.name == "total_return").unwrap();
+    assert_eq!(total_return.values.len(), 2);
+    assert_eq!(total_return.differences[0], 0.0);
+    assert!(total_return.best_index.is_some());
+    assert!(comparison.equity_curves.iter().all(|curve| curve.points[0].value == 1.0));
+
+    let error = restarted.compare_backtests(request(&["bt-compare-1", "missing"])).await.unwrap_err();
+    assert_eq!(error.code(), Code::NotFound);
+    let error = restarted.compare_backtests(request(&[])).await.unwrap_err();
+    assert_eq!(error.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn test_export_tearsheet_formats() {
+    let service = service_with_trend_data();
+    let config = TestConfigFactory::basic_config();
+    service.run_backtest(Request::new(run_request("bt-tearsheet", &config))).await.unwrap();
+    wait_for_status(&service, "bt-tearsheet", "COMPLETED").await;
+
+    let export = |format: &str| service.export_tearsheet(Request::new(ExportTearsheetRequest {
+        backtest_id: "bt-tearsheet".to_string(),
+        format: format.to_string(),
+    }));
+
+    let html = export("").await.unwrap().into_inner();
+    assert_eq!(html.content_type, "text/html");
+    assert!(html.content.contains("<h2>Monthly returns</h2>"));
+
+    let json = export("json").await.unwrap().into_inner();
+    assert_eq!(json.content_type, "application/json");
+    let tearsheet: serde_json::Value = serde_json::from_str(&json.content).unwrap();
+    assert_eq!(tearsheet["backtest_id"], "bt-tearsheet");
+    assert!(!tearsheet["drawdown"].as_array().unwrap().is_empty());
+
+    assert_eq!(export("pdf").await.unwrap_err().code(), Code::InvalidArgument);
+}
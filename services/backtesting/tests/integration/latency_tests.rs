@@ -0,0 +1,198 @@
+//! Integration tests for market data, order entry and acknowledgement latency
+
+use rstest::*;
+use backtesting::*;
+use backtesting::latency::*;
+use backtesting::lifecycle::*;
+use chrono::{DateTime, Duration, Utc};
+use orderbook::events::{OrderBookEvent, Side};
+use std::collections::HashMap;
+use crate::test_utils::*;
+
+/// Buys one unit with market orders until one fills, recording what it sees
+#[derive(Default)]
+struct LatencyProbe {
+    symbol: String,
+    working_order: Option<String>,
+    sent_at: HashMap<String, DateTime<Utc>>,
+    /// Exchange time and delivery time of each replayed event
+    events: Vec<(DateTime<Utc>, DateTime<Utc>)>,
+    /// Each fill and when it was delivered
+    fills: Vec<(Fill, DateTime<Utc>)>,
+    /// Each rejection and when it was delivered
+    rejections: Vec<(OrderRejection, DateTime<Utc>)>,
+}
+
+impl LatencyProbe {
+    fn new(symbol: &str) -> Self {
+        Self { symbol: symbol.to_string(), ..Self::default() }
+    }
+
+    fn buy(&mut self, ctx: &mut StrategyContext<'_>) {
+        if self.working_order.is_none() && self.fills.is_empty() && ctx.price(&self.symbol).is_some() {
+            let id = ctx.place(OrderRequest::market(&self.symbol, OrderSide::Buy, 1.0));
+            self.sent_at.insert(id.clone(), ctx.timestamp());
+            self.working_order = Some(id);
+        }
+    }
+
+    fn filled_order(&self) -> (&Fill, DateTime<Utc>, DateTime<Utc>) {
+        let (fill, delivered_at) = &self.fills[0];
+        (fill, self.sent_at[&fill.order_id], *delivered_at)
+    }
+}
+
+impl Strategy for LatencyProbe {
+    fn on_bar(&mut self, ctx: &mut StrategyContext<'_>) {
+        self.buy(ctx);
+    }
+
+    fn on_event(&mut self, event: &OrderBookEvent, ctx: &mut StrategyContext<'_>) {
+        self.events.push((event_replay::to_datetime(event.exchange_time()), ctx.timestamp()));
+        self.buy(ctx);
+    }
+
+    fn on_fill(&mut self, fill: &Fill, ctx: &mut StrategyContext<'_>) {
+        self.fills.push((fill.clone(), ctx.timestamp()));
+        self.working_order = None;
+    }
+
+    fn on_order_rejected(&mut self, rejection: &OrderRejection, ctx: &mut StrategyContext<'_>) {
+        self.rejections.push((rejection.clone(), ctx.timestamp()));
+        if self.working_order.as_deref() == Some(rejection.order_id.as_str()) {
+            self.working_order = None;
+        }
+    }
+}
+
+fn latency(market_data: f64, order_entry: f64, ack: f64) -> LatencyConfig {
+    let millis = |millis: f64| if millis > 0.0 { LatencyModel::Constant { millis } } else { LatencyModel::Zero };
+    LatencyConfig::default().with_default(LatencyProfile {
+        market_data: millis(market_data),
+        order_entry: millis(order_entry),
+        ack: millis(ack),
+    })
+}
+
+fn bar_config(latency: LatencyConfig) -> BacktestConfig {
+    let mut config = TestConfigFactory::basic_config();
+    config.latency = latency;
+    config
+}
+
+/// Prints after the opening snapshot in `ticking_stream`
+const PRINTS: u64 = 300;
+
+/// Snapshot then a trade print every 10ms, with the book moving up a tick
+/// every 100ms
+///
+/// Long enough for a strategy to get an order past the ten simulated
+/// rejections with 100ms between attempts.
+fn ticking_stream(start: DateTime<Utc>) -> Vec<OrderBookEvent> {
+    (0..=PRINTS)
+        .map(|i| {
+            let time = start + Duration::milliseconds(10 * i as i64);
+            if i % 10 == 0 {
+                let tick = (i / 10) as f64;
+                TestEventFactory::snapshot(i + 1, time, &[(99.0 + tick, 100.0)], &[(101.0 + tick, 100.0)])
+            } else {
+                TestEventFactory::trade(i + 1, time, 100.0, 1.0, Side::Buy)
+            }
+        })
+        .collect()
+}
+
+fn event_config(start: DateTime<Utc>, latency: LatencyConfig) -> BacktestConfig {
+    let mut config = TestConfigFactory::basic_config();
+    config.start_date = start;
+    config.end_date = start + Duration::minutes(1);
+    config.data_frequency = DataFrequency::Tick;
+    config.latency = latency;
+    config
+}
+
+#[rstest]
+#[case(latency(0.0, 0.0, 0.0), Duration::zero())]
+#[case(latency(0.0, 150.0, 0.0), Duration::days(1))]
+#[case(latency(50.0, 50.0, 0.0), Duration::days(1))]
+#[tokio::test]
+async fn test_bar_orders_matched_at_first_step_after_arrival(#[case] latency: LatencyConfig, #[case] wait: Duration) {
+    TestRandom::reset();
+    let config = bar_config(latency);
+    let start = config.start_date;
+    let data = TestDataFactory::trending_up_data(30, 100.0);
+    let bars = data.clone();
+    let step_at = |time: DateTime<Utc>| bars.iter().rev().find(|(bar_time, _)| *bar_time <= time).unwrap();
+    let engine = BacktestEngine::new(config);
+    engine.load_data("LAT", data).await.unwrap();
+
+    let mut strategy = LatencyProbe::new("LAT");
+    engine.run(&mut strategy).await.unwrap();
+
+    // Bars are stamped with the time they are seen, after their step
+    let (fill, sent_at, _) = strategy.filled_order();
+    let step = start + Duration::days((sent_at - start).num_days());
+    assert_eq!(fill.timestamp, step + wait);
+    TestAssertions::assert_approx_eq(fill.price, step_at(fill.timestamp).1.close, 1e-9);
+}
+
+#[tokio::test]
+async fn test_bar_reports_delivered_after_ack_latency() {
+    TestRandom::reset();
+    let engine = BacktestEngine::new(bar_config(latency(0.0, 0.0, 200.0)));
+    engine.load_data("LAT", TestDataFactory::trending_up_data(30, 100.0)).await.unwrap();
+
+    let mut strategy = LatencyProbe::new("LAT");
+    let result = engine.run(&mut strategy).await.unwrap();
+
+    // The order is rejected ten times; each retry waits for the last rejection to arrive
+    assert_eq!(strategy.rejections.len(), 10);
+    for (rejection, delivered_at) in &strategy.rejections {
+        assert_eq!(*delivered_at, rejection.timestamp + Duration::milliseconds(200));
+    }
+    let (fill, _, delivered_at) = strategy.filled_order();
+    assert_eq!(delivered_at, fill.timestamp + Duration::milliseconds(200));
+    // Booked when it happened, not when it was reported
+    assert_eq!(result.final_portfolio.positions[0].quantity, 1.0);
+}
+
+#[tokio::test]
+async fn test_events_delivered_after_market_data_latency() {
+    TestRandom::reset();
+    let start = Utc::now() - Duration::hours(1);
+    let engine = BacktestEngine::new(event_config(start, latency(25.0, 0.0, 0.0)));
+    let stream = ticking_stream(start);
+    let last_event = start + Duration::milliseconds(10 * PRINTS as i64);
+    engine.load_events("LAT", stream.clone()).await.unwrap();
+
+    let mut strategy = LatencyProbe::new("LAT");
+    engine.run(&mut strategy).await.unwrap();
+
+    // Every event is seen 25ms late, at the first event at or after that
+    for (exchange_time, seen_at) in &strategy.events {
+        assert_eq!(*seen_at, *exchange_time + Duration::milliseconds(25));
+    }
+    let expected = stream.iter()
+        .filter(|event| event_replay::to_datetime(event.exchange_time()) + Duration::milliseconds(25) <= last_event)
+        .count();
+    assert_eq!(strategy.events.len(), expected);
+}
+
+#[tokio::test]
+async fn test_event_orders_fill_against_book_at_arrival() {
+    TestRandom::reset();
+    let start = Utc::now() - Duration::hours(1);
+    let engine = BacktestEngine::new(event_config(start, latency(0.0, 95.0, 0.0)));
+    engine.load_events("LAT", ticking_stream(start)).await.unwrap();
+
+    let mut strategy = LatencyProbe::new("LAT");
+    engine.run(&mut strategy).await.unwrap();
+
+    // Sent on a print, the order reaches the book 95ms later and is matched
+    // at the next event, after the ask has stepped up
+    let (fill, sent_at, _) = strategy.filled_order();
+    assert_eq!(fill.timestamp, sent_at + Duration::milliseconds(100));
+    let steps = (fill.timestamp - start).num_milliseconds() / 100;
+    assert_eq!(fill.price, 101.0 + steps as f64);
+    assert!(fill.price > 101.0 + ((sent_at - start).num_milliseconds() / 100) as f64);
+}
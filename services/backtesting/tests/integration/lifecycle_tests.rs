@@ -0,0 +1,144 @@
+//! Integration tests for strategy lifecycle hooks and order management
+
+use backtesting::*;
+use backtesting::lifecycle::*;
+use backtesting::strategies::MAStrategy;
+use crate::test_utils::*;
+
+/// Buys once, recording every hook the engine calls
+#[derive(Default)]
+struct RecordingStrategy {
+    hooks: Vec<&'static str>,
+    working_order: Option<String>,
+    placed: Vec<String>,
+    fills: Vec<Fill>,
+    rejections: Vec<OrderRejection>,
+}
+
+impl Strategy for RecordingStrategy {
+    fn on_start(&mut self, _ctx: &mut StrategyContext<'_>) {
+        self.hooks.push("start");
+    }
+
+    fn on_bar(&mut self, ctx: &mut StrategyContext<'_>) {
+        self.hooks.push("bar");
+        if self.working_order.is_none() && self.fills.is_empty() && ctx.price("REC").is_some() {
+            let id = ctx.place(OrderRequest::market("REC", OrderSide::Buy, 10.0));
+            self.placed.push(id.clone());
+            self.working_order = Some(id);
+        }
+    }
+
+    fn on_fill(&mut self, fill: &Fill, ctx: &mut StrategyContext<'_>) {
+        self.hooks.push("fill");
+        assert!(ctx.position("REC").is_some(), "Fills are booked before they are delivered");
+        self.fills.push(fill.clone());
+        self.working_order = None;
+    }
+
+    fn on_order_rejected(&mut self, rejection: &OrderRejection, _ctx: &mut StrategyContext<'_>) {
+        self.hooks.push("rejected");
+        self.rejections.push(rejection.clone());
+        if self.working_order.as_deref() == Some(rejection.order_id.as_str()) {
+            self.working_order = None;
+        }
+    }
+
+    fn on_end(&mut self, _ctx: &mut StrategyContext<'_>) {
+        self.hooks.push("end");
+    }
+}
+
+#[tokio::test]
+async fn test_engine_delivers_fills_and_rejections() {
+    TestRandom::reset();
+    let engine = BacktestEngine::new(TestConfigFactory::basic_config());
+    engine.load_data("REC", TestDataFactory::trending_up_data(30, 100.0)).await.unwrap();
+
+    let mut strategy = RecordingStrategy::default();
+    let result = engine.run(&mut strategy).await.unwrap();
+
+    assert_eq!(strategy.hooks.first(), Some(&"start"));
+    assert_eq!(strategy.hooks.last(), Some(&"end"));
+    assert_eq!(strategy.hooks.iter().filter(|h| **h == "start" || **h == "end").count(), 2);
+
+    // The default execution config rejects the first ten orders
+    assert_eq!(strategy.rejections.len(), 10);
+    assert!(strategy.rejections.iter().all(|r| r.reason == RejectReason::Simulated));
+    assert_eq!(strategy.fills.len(), 1);
+    assert_eq!(strategy.fills[0].order_id, *strategy.placed.last().unwrap());
+    assert_eq!(strategy.placed.len(), 11);
+
+    let position = result.final_portfolio.positions.iter().find(|p| p.symbol == "REC").unwrap();
+    assert_eq!(position.quantity, 10.0);
+}
+
+/// Rests a limit order far below the market, then amends it up to the market
+#[derive(Default)]
+struct AmendingStrategy {
+    resting: Option<String>,
+    amended_to: Option<f64>,
+    fill_price: Option<f64>,
+    cancel_rejections: Vec<OrderRejection>,
+}
+
+impl Strategy for AmendingStrategy {
+    fn on_bar(&mut self, ctx: &mut StrategyContext<'_>) {
+        let Some(price) = ctx.price("AMD") else {
+            return;
+        };
+        match &self.resting {
+            None if self.fill_price.is_none() => {
+                self.resting = Some(ctx.place(OrderRequest::limit("AMD", OrderSide::Buy, 5.0, price * 0.5)));
+            }
+            Some(id) if self.amended_to.is_none() && ctx.open_orders().iter().any(|o| o.id == *id) => {
+                let limit = price * 1.05;
+                ctx.amend(id, None, Some(limit));
+                self.amended_to = Some(limit);
+            }
+            _ => {}
+        }
+    }
+
+    fn on_fill(&mut self, fill: &Fill, ctx: &mut StrategyContext<'_>) {
+        self.fill_price = Some(fill.price);
+        // The order is complete, so cancelling it must be refused
+        ctx.cancel(&fill.order_id);
+    }
+
+    fn on_order_rejected(&mut self, rejection: &OrderRejection, _ctx: &mut StrategyContext<'_>) {
+        match rejection.reason {
+            RejectReason::UnknownOrder => self.cancel_rejections.push(rejection.clone()),
+            _ => self.resting = None,
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_strategy_amends_and_cancels_by_id() {
+    TestRandom::reset();
+    let engine = BacktestEngine::new(TestConfigFactory::basic_config());
+    engine.load_data("AMD", TestDataFactory::trending_up_data(30, 100.0)).await.unwrap();
+
+    let mut strategy = AmendingStrategy::default();
+    engine.run(&mut strategy).await.unwrap();
+
+    assert!(strategy.amended_to.is_some(), "A resting order should have been amended");
+    assert_eq!(strategy.fill_price, strategy.amended_to, "Amended limit orders fill at the new limit");
+    assert_eq!(strategy.cancel_rejections.len(), 1);
+    assert_eq!(strategy.cancel_rejections[0].order_id, *strategy.resting.as_ref().unwrap());
+}
+
+#[tokio::test]
+async fn test_moving_average_strategy_tracks_prices_and_trades() {
+    TestRandom::reset();
+    let engine = BacktestEngine::new(TestConfigFactory::basic_config());
+    engine.load_data("MA", TestDataFactory::trending_up_data(30, 100.0)).await.unwrap();
+
+    let mut strategy = MAStrategy::new("MA".to_string(), 3, 5);
+    let result = engine.run(&mut strategy).await.unwrap();
+
+    // A steady uptrend keeps the fast average above the slow one
+    let position = result.final_portfolio.positions.iter().find(|p| p.symbol == "MA");
+    assert!(position.is_some_and(|p| p.quantity > 0.0), "Crossover strategy should be long in an uptrend");
+}
@@ -0,0 +1,155 @@
+//! Integration tests for margin, financing and liquidation in engine runs
+
+use backtesting::*;
+use backtesting::lifecycle::*;
+use backtesting::margin::*;
+use chrono::{DateTime, Duration, Utc};
+use crate::test_utils::*;
+
+/// Opens one position and holds it, recording the fills it receives
+struct OpenOnceStrategy {
+    symbol: String,
+    side: OrderSide,
+    quantity: f64,
+    fills: Vec<Fill>,
+}
+
+impl OpenOnceStrategy {
+    fn new(symbol: &str, side: OrderSide, quantity: f64) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            side,
+            quantity,
+            fills: Vec::new(),
+        }
+    }
+}
+
+impl Strategy for OpenOnceStrategy {
+    fn on_bar(&mut self, ctx: &mut StrategyContext<'_>) {
+        // Retry until the simulated rejections run out
+        if self.fills.is_empty() && ctx.open_orders().is_empty() && ctx.price(&self.symbol).is_some() {
+            ctx.place(OrderRequest::market(&self.symbol, self.side, self.quantity));
+        }
+    }
+
+    fn on_fill(&mut self, fill: &Fill, _ctx: &mut StrategyContext<'_>) {
+        self.fills.push(fill.clone());
+    }
+}
+
+/// Daily bars closing at `closes`, starting at `start`
+fn daily_bars(start: DateTime<Utc>, closes: &[f64]) -> Vec<(DateTime<Utc>, OHLCV)> {
+    closes
+        .iter()
+        .enumerate()
+        .map(|(day, &close)| (start + Duration::days(day as i64), OHLCV {
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1_000_000.0,
+        }))
+        .collect()
+}
+
+fn perp_config(start: DateTime<Utc>, days: i64, terms: InstrumentMargin) -> BacktestConfig {
+    let mut config = TestConfigFactory::basic_config();
+    config.start_date = start;
+    config.end_date = start + Duration::days(days - 1);
+    config.margin = MarginConfig::default().with_instrument("BTCUSDT", terms);
+    config
+}
+
+#[tokio::test]
+async fn test_liquidation_when_equity_falls_below_maintenance() {
+    TestRandom::reset();
+    let start = Utc::now() - Duration::days(30);
+    let mut closes = vec![100.0; 15];
+    closes.extend([90.0, 85.0, 81.0, 80.2, 78.0, 79.0]);
+    let config = perp_config(start, closes.len() as i64, InstrumentMargin::binance_usdm(10.0));
+    let engine = BacktestEngine::new(config);
+    engine.load_data("BTCUSDT", daily_bars(start, &closes)).await.unwrap();
+
+    let mut strategy = OpenOnceStrategy::new("BTCUSDT", OrderSide::Buy, 5_000.0);
+    let result = engine.run(&mut strategy).await.unwrap();
+
+    assert_eq!(result.margin.liquidations.len(), 1);
+    let liquidation = &result.margin.liquidations[0];
+    assert_eq!(liquidation.timestamp, start + Duration::days(18));
+    assert_eq!(liquidation.quantity, 5_000.0);
+    assert_eq!(liquidation.price, 80.2);
+    assert!(liquidation.equity < liquidation.maintenance_requirement);
+    assert!(liquidation.order_id.starts_with("LIQ_"));
+
+    // The liquidation is booked as a sell and delivered to the strategy
+    assert_eq!(strategy.fills.len(), 2);
+    assert_eq!(strategy.fills[1].order_id, liquidation.order_id);
+    assert_eq!(strategy.fills[1].side, OrderSide::Sell);
+
+    assert_eq!(result.trades.len(), 1);
+    assert!(result.trades[0].pnl < 0.0);
+    assert_eq!(result.trades[0].exit_price, 80.2);
+    assert!(result.final_portfolio.positions.is_empty());
+    assert!(!result.margin.margin_calls.is_empty());
+}
+
+#[tokio::test]
+async fn test_no_liquidation_while_maintenance_is_covered() {
+    TestRandom::reset();
+    let start = Utc::now() - Duration::days(30);
+    let mut closes = vec![100.0; 15];
+    closes.extend([95.0, 90.0, 92.0]);
+    let config = perp_config(start, closes.len() as i64, InstrumentMargin::binance_usdm(10.0));
+    let engine = BacktestEngine::new(config);
+    engine.load_data("BTCUSDT", daily_bars(start, &closes)).await.unwrap();
+
+    let mut strategy = OpenOnceStrategy::new("BTCUSDT", OrderSide::Buy, 5_000.0);
+    let result = engine.run(&mut strategy).await.unwrap();
+
+    assert!(result.margin.liquidations.is_empty());
+    assert_eq!(result.final_portfolio.positions[0].quantity, 5_000.0);
+}
+
+#[tokio::test]
+async fn test_funding_accrues_daily_on_perpetual_long() {
+    TestRandom::reset();
+    let start = Utc::now() - Duration::days(30);
+    let closes = vec![100.0; 20];
+    let config = perp_config(start, closes.len() as i64, InstrumentMargin::binance_usdm(5.0));
+    let end = config.end_date;
+    let engine = BacktestEngine::new(config);
+    engine.load_data("BTCUSDT", daily_bars(start, &closes)).await.unwrap();
+
+    let mut strategy = OpenOnceStrategy::new("BTCUSDT", OrderSide::Buy, 100.0);
+    let result = engine.run(&mut strategy).await.unwrap();
+
+    // 0.01% of 10,000 notional every 8 hours is 3 per day held
+    let days_held = (end - strategy.fills[0].timestamp).num_days() as f64;
+    assert!(days_held > 0.0);
+    TestAssertions::assert_approx_eq(result.margin.financing_paid, 3.0 * days_held, 1e-6);
+
+    let position = &result.final_portfolio.positions[0];
+    TestAssertions::assert_approx_eq(position.realized_pnl, -3.0 * days_held, 1e-6);
+}
+
+#[tokio::test]
+async fn test_enable_shorting_profits_from_falling_market() {
+    TestRandom::reset();
+    let mut config = TestConfigFactory::shorting_config();
+    config.start_date = Utc::now() - Duration::days(30);
+    config.end_date = Utc::now() - Duration::days(1);
+    config.data_frequency = DataFrequency::Daily;
+    let initial_capital = config.initial_capital;
+    let engine = BacktestEngine::new(config);
+    engine.load_data("SHORT", TestDataFactory::trending_down_data(30, 200.0)).await.unwrap();
+
+    let mut strategy = OpenOnceStrategy::new("SHORT", OrderSide::Sell, 100.0);
+    let result = engine.run(&mut strategy).await.unwrap();
+
+    let position = &result.final_portfolio.positions[0];
+    assert_eq!(position.quantity, -100.0);
+    assert!(position.unrealized_pnl > 0.0);
+    assert!(result.final_portfolio.total_value > initial_capital);
+    TestAssertions::assert_portfolio_valid(&result.final_portfolio);
+}
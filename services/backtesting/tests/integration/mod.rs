@@ -2,4 +2,13 @@
 
 pub mod end_to_end_tests;
 pub mod strategy_integration_tests;
-pub mod performance_integration_tests;
\ No newline at end of file
+pub mod performance_integration_tests;
+pub mod grpc_service_tests;
+pub mod event_driven_tests;
+pub mod optimization_tests;
+pub mod lifecycle_tests;
+pub mod margin_tests;
+pub mod gateway_adapter_tests;
+pub mod options_tests;
+pub mod latency_tests;
+pub mod contingent_tests;
\ No newline at end of file
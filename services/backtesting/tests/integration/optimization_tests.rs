@@ -0,0 +1,89 @@
+//! Integration tests for parameter sweeps and walk-forward analysis
+
+use backtesting::*;
+use backtesting::optimization::*;
+use chrono::{Duration, Utc};
+use std::collections::HashMap;
+use crate::test_utils::*;
+
+/// Optimizer over 120 days of rising prices, sizing `AlwaysBuyStrategy` by `size`
+fn optimizer() -> Optimizer {
+    TestRandom::reset();
+    let mut config = TestConfigFactory::basic_config();
+    config.start_date = Utc::now() - Duration::days(121);
+    config.end_date = Utc::now();
+
+    let data = HashMap::from([("TREND".to_string(), TestDataFactory::trending_up_data(120, 100.0))]);
+    Optimizer::new(config, data, |parameters: &ParameterSet| {
+        Ok(Box::new(AlwaysBuyStrategy {
+            symbol: "TREND".to_string(),
+            position_size: parameters.get("size").copied().unwrap_or(1.0),
+        }) as Box<dyn Strategy>)
+    })
+    .with_objective(Objective::TotalReturn)
+}
+
+fn sizes() -> ParameterSpace {
+    ParameterSpace::new().with("size", ParameterRange::Values { values: vec![10.0, 50.0, 100.0] })
+}
+
+#[tokio::test]
+async fn test_grid_sweep_ranks_trials_by_objective() {
+    let result = optimizer().with_max_parallel(2).optimize(&sizes(), &SearchMethod::Grid).await.unwrap();
+
+    assert_eq!(result.trials.len(), 3);
+    assert_eq!(result.failed_trials, 0);
+    // Holding more of a rising asset returns more
+    assert_eq!(result.best().unwrap().parameters["size"], 100.0);
+    assert!(result.trials.windows(2).all(|pair| pair[0].score >= pair[1].score));
+}
+
+#[tokio::test]
+async fn test_failed_trials_are_counted() {
+    let space = sizes().with("size", ParameterRange::Values { values: vec![10.0, f64::NAN] });
+    let optimizer = Optimizer::new(TestConfigFactory::basic_config(), HashMap::new(), |_: &ParameterSet| {
+        anyhow::bail!("strategy cannot be built")
+    });
+
+    let result = optimizer.optimize(&space, &SearchMethod::Grid).await.unwrap();
+    assert!(result.trials.is_empty());
+    assert_eq!(result.failed_trials, 2);
+}
+
+#[tokio::test]
+async fn test_walk_forward_reports_windows_and_stability() {
+    let walk_forward = WalkForwardConfig {
+        in_sample: Duration::days(40),
+        out_of_sample: Duration::days(20),
+        step: None,
+        anchored: false,
+    };
+    let report = optimizer().walk_forward(&sizes(), &SearchMethod::Grid, &walk_forward).await.unwrap();
+
+    assert_eq!(report.windows.len(), 4);
+    for pair in report.windows.windows(2) {
+        assert_eq!(pair[1].in_sample_start - pair[0].in_sample_start, Duration::days(20));
+        assert_eq!(pair[1].in_sample_end, pair[0].out_of_sample_end, "Out-of-sample windows should tile the period");
+    }
+    for window in &report.windows {
+        assert_eq!(window.in_sample_end - window.in_sample_start, Duration::days(40));
+        assert_eq!(window.out_of_sample_end - window.in_sample_end, Duration::days(20));
+    }
+
+    let stability = &report.parameter_stability[0];
+    assert_eq!(stability.name, "size");
+    assert_eq!(stability.mean, 100.0);
+    assert_eq!(stability.coefficient_of_variation, 0.0, "A steady trend should pick the same size every window");
+    assert!(report.walk_forward_efficiency.is_some());
+}
+
+#[tokio::test]
+async fn test_walk_forward_rejects_period_shorter_than_one_window() {
+    let walk_forward = WalkForwardConfig {
+        in_sample: Duration::days(100),
+        out_of_sample: Duration::days(30),
+        step: None,
+        anchored: true,
+    };
+    assert!(optimizer().walk_forward(&sizes(), &SearchMethod::Grid, &walk_forward).await.is_err());
+}
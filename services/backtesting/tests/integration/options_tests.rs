@@ -0,0 +1,142 @@
+//! Integration tests for option positions in backtests
+
+use backtesting::*;
+use backtesting::lifecycle::*;
+use backtesting::options::*;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use crate::test_utils::*;
+
+const CALL: &str = "NIFTY24JAN21500CE";
+
+/// NIFTY rising 50 points a day through January 2024, with bars at midnight
+fn nifty_january() -> Vec<(DateTime<Utc>, OHLCV)> {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    (0..31)
+        .map(|day| {
+            let close = 21000.0 + 50.0 * day as f64;
+            (start + Duration::days(day), OHLCV { open: close, high: close + 20.0, low: close - 20.0, close, volume: 1_000_000.0 })
+        })
+        .collect()
+}
+
+fn january_config(options: OptionsConfig) -> BacktestConfig {
+    let mut config = TestConfigFactory::basic_config();
+    config.start_date = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    config.end_date = Utc.with_ymd_and_hms(2024, 1, 31, 0, 0, 0).unwrap();
+    config.options = options;
+    config
+}
+
+/// Buys one lot of an option once, retrying rejected orders
+#[derive(Default)]
+struct BuyOnce {
+    symbol: &'static str,
+    limit: Option<f64>,
+    working_order: Option<String>,
+    prices: Vec<(DateTime<Utc>, Option<f64>)>,
+    fills: Vec<Fill>,
+    rejections: Vec<OrderRejection>,
+}
+
+impl Strategy for BuyOnce {
+    fn on_bar(&mut self, ctx: &mut StrategyContext<'_>) {
+        self.prices.push((ctx.timestamp(), ctx.price(self.symbol)));
+        if self.working_order.is_none() && self.fills.is_empty() && ctx.price(self.symbol).is_some() {
+            let request = match self.limit {
+                Some(price) => OrderRequest::limit(self.symbol, OrderSide::Buy, 50.0, price),
+                None => OrderRequest::market(self.symbol, OrderSide::Buy, 50.0),
+            };
+            self.working_order = Some(ctx.place(request));
+        }
+    }
+
+    fn on_fill(&mut self, fill: &Fill, _ctx: &mut StrategyContext<'_>) {
+        self.fills.push(fill.clone());
+        self.working_order = None;
+    }
+
+    fn on_order_rejected(&mut self, rejection: &OrderRejection, _ctx: &mut StrategyContext<'_>) {
+        self.rejections.push(rejection.clone());
+        if self.working_order.as_deref() == Some(rejection.order_id.as_str()) {
+            self.working_order = None;
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_long_call_settles_at_intrinsic_value() {
+    let options = OptionsConfig { contracts: vec![CALL.to_string()], ..OptionsConfig::default() };
+    let engine = BacktestEngine::new(january_config(options));
+    engine.load_data("NIFTY", nifty_january()).await.unwrap();
+
+    let mut strategy = BuyOnce { symbol: CALL, ..BuyOnce::default() };
+    let result = engine.run(&mut strategy).await.unwrap();
+
+    // Bought at the model price, then closed by the expiry settlement
+    assert_eq!(strategy.fills.len(), 2);
+    let (entry, exit) = (&strategy.fills[0], &strategy.fills[1]);
+    assert!(entry.order_id.starts_with("BT_") && entry.price > 0.0);
+    assert!(exit.order_id.starts_with("EXP_"));
+    assert_eq!((exit.side, exit.quantity, exit.commission), (OrderSide::Sell, 50.0, 0.0));
+
+    // Settled against the Jan 25 close on the first step after the 15:30 IST expiry
+    let settlement = &result.options.settlements[..];
+    assert_eq!(settlement.len(), 1);
+    assert_eq!(settlement[0].order_id, exit.order_id);
+    assert_eq!(settlement[0].timestamp, Utc.with_ymd_and_hms(2024, 1, 26, 0, 0, 0).unwrap());
+    assert_eq!((settlement[0].quantity, settlement[0].underlying_price), (50.0, 22200.0));
+    assert_eq!(settlement[0].settlement_price, 700.0);
+    assert_eq!(exit.price, 700.0);
+
+    assert!(result.final_portfolio.positions.iter().all(|p| p.symbol != CALL));
+    let trade = result.trades.iter().find(|t| t.symbol == CALL).unwrap();
+    TestAssertions::assert_approx_eq(trade.pnl, (700.0 - trade.entry_price) * 50.0, 1e-6);
+
+    // No price once the contract has expired
+    assert!(strategy.prices.iter().all(|(at, price)| price.is_some() == (*at < settlement[0].timestamp)));
+}
+
+#[tokio::test]
+async fn test_greeks_recorded_while_options_are_held() {
+    let options = OptionsConfig { contracts: vec![CALL.to_string()], ..OptionsConfig::default() }
+        .with_surface("NIFTY", VolSurface::Flat { volatility: 0.18 });
+    let engine = BacktestEngine::new(january_config(options));
+    engine.load_data("NIFTY", nifty_january()).await.unwrap();
+
+    let mut strategy = BuyOnce { symbol: CALL, ..BuyOnce::default() };
+    let result = engine.run(&mut strategy).await.unwrap();
+
+    let entry_time = strategy.fills[0].timestamp;
+    let expiry_step = result.options.settlements[0].timestamp;
+    let greeks = &result.options.greeks;
+    assert!(!greeks.is_empty());
+    assert_eq!(greeks[0].timestamp, entry_time);
+    assert_eq!(greeks.last().unwrap().timestamp, expiry_step - Duration::days(1));
+    assert_eq!(greeks.len() as i64, (expiry_step - entry_time).num_days());
+    for point in greeks {
+        let nifty = point.by_underlying["NIFTY"];
+        assert!(nifty.delta > 0.0 && nifty.delta <= 50.0);
+        assert!(nifty.gamma >= 0.0 && nifty.vega >= 0.0 && nifty.theta <= 0.0);
+    }
+    // A call moving into the money picks up delta
+    assert!(greeks.last().unwrap().by_underlying["NIFTY"].delta > greeks[0].by_underlying["NIFTY"].delta);
+}
+
+#[tokio::test]
+async fn test_orders_in_expired_contracts_cancelled() {
+    let options = OptionsConfig { contracts: vec![CALL.to_string()], ..OptionsConfig::default() };
+    let engine = BacktestEngine::new(january_config(options));
+    engine.load_data("NIFTY", nifty_january()).await.unwrap();
+
+    // A bid far below the model price rests until expiry
+    let mut strategy = BuyOnce { symbol: CALL, limit: Some(1.0), ..BuyOnce::default() };
+    let result = engine.run(&mut strategy).await.unwrap();
+
+    assert!(strategy.fills.is_empty());
+    assert!(result.options.settlements.is_empty());
+    assert!(result.options.greeks.is_empty());
+    let resting = strategy.rejections.last().unwrap();
+    assert_eq!(resting.reason, RejectReason::Unfilled);
+    assert_eq!(resting.timestamp, Utc.with_ymd_and_hms(2024, 1, 26, 0, 0, 0).unwrap());
+    assert!(strategy.working_order.is_none());
+}
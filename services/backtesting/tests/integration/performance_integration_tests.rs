@@ -38,8 +38,8 @@ async fn test_edge_case_single_data_point() {
     let load_result = engine.load_data("SINGLE", single_point).await;
     
     if load_result.is_ok() {
-        let strategy = DoNothingStrategy;
-        let result = engine.run(&strategy).await;
+        let mut strategy = DoNothingStrategy;
+        let result = engine.run(&mut strategy).await;
         
         assert!(result.is_ok(), "Should handle single data point gracefully");
         
@@ -62,8 +62,8 @@ async fn test_edge_case_very_short_backtest_period() {
     let data = TestDataFactory::intraday_data(1, 150.0); // 1 hour of minute data
     engine.load_data("SHORT", data).await.unwrap();
     
-    let strategy = DoNothingStrategy;
-    let result = engine.run(&strategy).await;
+    let mut strategy = DoNothingStrategy;
+    let result = engine.run(&mut strategy).await;
     
     assert!(result.is_ok(), "Should handle very short backtest periods");
     
@@ -82,12 +82,12 @@ async fn test_edge_case_zero_initial_capital() {
     let data = TestDataFactory::trending_up_data(10, 100.0);
     engine.load_data("ZERO_CAPITAL", data).await.unwrap();
     
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "ZERO_CAPITAL".to_string(),
         position_size: 1000.0,
     };
     
-    let result = engine.run(&strategy).await;
+    let result = engine.run(&mut strategy).await;
     assert!(result.is_ok(), "Should handle zero initial capital");
     
     let backtest_result = result.unwrap();
@@ -108,12 +108,12 @@ async fn test_edge_case_extremely_small_capital() {
     let data = TestDataFactory::trending_up_data(5, 100.0);
     engine.load_data("TINY_CAPITAL", data).await.unwrap();
     
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "TINY_CAPITAL".to_string(),
         position_size: 1000.0, // Way more than available capital
     };
     
-    let result = engine.run(&strategy).await.unwrap();
+    let result = engine.run(&mut strategy).await.unwrap();
     
     // Should not be able to make any trades
     assert!(result.final_portfolio.positions.is_empty() || 
@@ -133,12 +133,12 @@ async fn test_edge_case_extremely_large_capital() {
     let data = TestDataFactory::trending_up_data(20, 10.0);
     engine.load_data("HUGE_CAPITAL", data).await.unwrap();
     
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "HUGE_CAPITAL".to_string(),
         position_size: 1_000_000.0, // $1M position
     };
     
-    let result = engine.run(&strategy).await.unwrap();
+    let result = engine.run(&mut strategy).await.unwrap();
     
     // Should handle large numbers without overflow
     TestAssertions::assert_portfolio_valid(&result.final_portfolio);
@@ -164,12 +164,12 @@ async fn test_single_trade_scenario() {
     
     engine.load_data("SINGLE_TRADE", data).await.unwrap();
     
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "SINGLE_TRADE".to_string(),
         position_size: 1000.0,
     };
     
-    let result = engine.run(&strategy).await.unwrap();
+    let result = engine.run(&mut strategy).await.unwrap();
     
     // Should handle single trade scenario
     TestAssertions::assert_portfolio_valid(&result.final_portfolio);
@@ -200,12 +200,12 @@ async fn test_concurrent_backtest_runs() {
             let data = TestDataFactory::trending_up_data(15, 100.0 + i as f64 * 10.0);
             engine.load_data(&format!("CONCURRENT_{}", i), data).await.unwrap();
             
-            let strategy = AlwaysBuyStrategy {
+            let mut strategy = AlwaysBuyStrategy {
                 symbol: format!("CONCURRENT_{}", i),
                 position_size: 500.0,
             };
             
-            engine.run(&strategy).await
+            engine.run(&mut strategy).await
         });
     }
     
@@ -259,10 +259,10 @@ async fn test_performance_with_high_frequency_data() {
     
     engine.load_data("HF", data).await.unwrap();
     
-    let strategy = DoNothingStrategy; // Use simple strategy to test data processing
+    let mut strategy = DoNothingStrategy; // Use simple strategy to test data processing
     
     let start_time = std::time::Instant::now();
-    let result = engine.run(&strategy).await;
+    let result = engine.run(&mut strategy).await;
     let elapsed = start_time.elapsed();
     
     assert!(result.is_ok(), "Should handle high-frequency data");
@@ -301,12 +301,12 @@ async fn test_backtest_with_price_precision_edge_cases() {
     
     engine.load_data("PRECISION", data).await.unwrap();
     
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "PRECISION".to_string(),
         position_size: 123.456789,
     };
     
-    let result = engine.run(&strategy).await.unwrap();
+    let result = engine.run(&mut strategy).await.unwrap();
     
     // Should handle high precision numbers correctly
     TestAssertions::assert_portfolio_valid(&result.final_portfolio);
@@ -343,12 +343,12 @@ async fn test_backtest_with_extreme_price_movements() {
     
     engine.load_data("EXTREME", data).await.unwrap();
     
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "EXTREME".to_string(),
         position_size: 100.0,
     };
     
-    let result = engine.run(&strategy).await.unwrap();
+    let result = engine.run(&mut strategy).await.unwrap();
     
     // Should handle extreme movements without crashing
     TestAssertions::assert_portfolio_valid(&result.final_portfolio);
@@ -381,12 +381,12 @@ async fn test_backtest_with_zero_volume_data() {
     
     engine.load_data("ZERO_VOL", data).await.unwrap();
     
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "ZERO_VOL".to_string(),
         position_size: 1000.0,
     };
     
-    let result = engine.run(&strategy).await.unwrap();
+    let result = engine.run(&mut strategy).await.unwrap();
     
     // Should handle zero volume data gracefully
     TestAssertions::assert_portfolio_valid(&result.final_portfolio);
@@ -405,11 +405,11 @@ async fn test_memory_usage_large_backtest() {
     let data = TestDataFactory::trending_up_data(365, 100.0);
     engine.load_data("LARGE_MEM", data).await.unwrap();
     
-    let strategy = DoNothingStrategy; // Simple strategy to focus on data handling
+    let mut strategy = DoNothingStrategy; // Simple strategy to focus on data handling
     
     // Measure memory usage (approximate)
     let start_memory = get_memory_usage();
-    let result = engine.run(&strategy).await.unwrap();
+    let result = engine.run(&mut strategy).await.unwrap();
     let end_memory = get_memory_usage();
     
     TestAssertions::assert_portfolio_valid(&result.final_portfolio);
@@ -446,12 +446,12 @@ async fn test_backtest_numerical_stability() {
     
     engine.load_data("NUMERICAL", data).await.unwrap();
     
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "NUMERICAL".to_string(),
         position_size: 1e12, // Very large position
     };
     
-    let result = engine.run(&strategy).await.unwrap();
+    let result = engine.run(&mut strategy).await.unwrap();
     
     // All values should remain finite
     assert!(result.final_portfolio.total_value.is_finite());
@@ -493,8 +493,8 @@ async fn test_backtest_error_recovery() {
     // Should either filter invalid data or reject the load
     match load_result {
         Ok(_) => {
-            let strategy = DoNothingStrategy;
-            let result = engine.run(&strategy).await;
+            let mut strategy = DoNothingStrategy;
+            let result = engine.run(&mut strategy).await;
             assert!(result.is_ok(), "Should run successfully after filtering invalid data");
         },
         Err(_) => {
@@ -514,8 +514,8 @@ async fn test_backtest_time_zone_handling() {
     
     engine.load_data("UTC", utc_data).await.unwrap();
     
-    let strategy = DoNothingStrategy;
-    let result = engine.run(&strategy).await.unwrap();
+    let mut strategy = DoNothingStrategy;
+    let result = engine.run(&mut strategy).await.unwrap();
     
     // All timestamps in equity curve should be valid UTC
     for (timestamp, _) in &result.equity_curve {
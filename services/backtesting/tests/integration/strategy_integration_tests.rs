@@ -3,8 +3,7 @@
 use rstest::*;
 use tokio_test;
 use backtesting::*;
-// Note: MAStrategy would be imported here when properly structured
-// use backtesting::strategies::MAStrategy;
+use backtesting::strategies::MAStrategy;
 use chrono::{Utc, Duration};
 use crate::test_utils::*;
 
@@ -21,9 +20,9 @@ async fn test_ma_strategy_trending_up_market() {
     engine.load_data("MA_TREND", data).await.unwrap();
     
     // Use MA strategy with typical parameters
-    let strategy = MAStrategy::new("MA_TREND".to_string(), 5, 20);
+    let mut strategy = MAStrategy::new("MA_TREND".to_string(), 5, 20);
     
-    let result = engine.run(&strategy).await;
+    let result = engine.run(&mut strategy).await;
     assert!(result.is_ok(), "MA strategy should work in trending market");
     
     let backtest_result = result.unwrap();
@@ -50,9 +49,9 @@ async fn test_ma_strategy_sideways_market() {
     let data = TestDataFactory::sideways_data(60, 150.0, 20.0);
     engine.load_data("MA_SIDEWAYS", data).await.unwrap();
     
-    let strategy = MAStrategy::new("MA_SIDEWAYS".to_string(), 10, 30);
+    let mut strategy = MAStrategy::new("MA_SIDEWAYS".to_string(), 10, 30);
     
-    let result = engine.run(&strategy).await.unwrap();
+    let result = engine.run(&mut strategy).await.unwrap();
     
     // In sideways market, MA strategies often struggle due to whipsaws
     TestAssertions::assert_portfolio_valid(&result.final_portfolio);
@@ -79,9 +78,9 @@ async fn test_ma_strategy_different_parameters() {
         let data = TestDataFactory::volatile_data(40, 200.0, 8.0);
         engine.load_data("MA_PARAMS", data).await.unwrap();
         
-        let strategy = MAStrategy::new("MA_PARAMS".to_string(), fast, slow);
+        let mut strategy = MAStrategy::new("MA_PARAMS".to_string(), fast, slow);
         
-        let result = engine.run(&strategy).await;
+        let result = engine.run(&mut strategy).await;
         assert!(result.is_ok(), "MA strategy should work with parameters ({}, {})", fast, slow);
         
         let backtest_result = result.unwrap();
@@ -102,12 +101,12 @@ async fn test_always_buy_strategy_integration() {
     let data = TestDataFactory::trending_up_data(30, 50.0);
     engine.load_data("BUY_STRAT", data).await.unwrap();
     
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "BUY_STRAT".to_string(),
         position_size: 2000.0,
     };
     
-    let result = engine.run(&strategy).await.unwrap();
+    let result = engine.run(&mut strategy).await.unwrap();
     
     // Should have bought and held the position
     let has_position = result.final_portfolio.positions
@@ -132,9 +131,9 @@ async fn test_strategy_with_insufficient_data() {
     engine.load_data("LIMITED", data).await.unwrap();
     
     // MA strategy needs more data than available
-    let strategy = MAStrategy::new("LIMITED".to_string(), 5, 20);
+    let mut strategy = MAStrategy::new("LIMITED".to_string(), 5, 20);
     
-    let result = engine.run(&strategy).await;
+    let result = engine.run(&mut strategy).await;
     assert!(result.is_ok(), "Should handle insufficient data gracefully");
     
     let backtest_result = result.unwrap();
@@ -155,16 +154,16 @@ async fn test_strategy_comparison() {
     // Test do-nothing strategy
     let engine1 = BacktestEngine::new(config.clone());
     engine1.load_data("COMPARE", data.clone()).await.unwrap();
-    let do_nothing_result = engine1.run(&DoNothingStrategy).await.unwrap();
+    let do_nothing_result = engine1.run(&mut DoNothingStrategy).await.unwrap();
     
     // Test buy strategy
     let engine2 = BacktestEngine::new(config.clone());
     engine2.load_data("COMPARE", data.clone()).await.unwrap();
-    let buy_strategy = AlwaysBuyStrategy {
+    let mut buy_strategy = AlwaysBuyStrategy {
         symbol: "COMPARE".to_string(),
         position_size: 1000.0,
     };
-    let buy_result = engine2.run(&buy_strategy).await.unwrap();
+    let buy_result = engine2.run(&mut buy_strategy).await.unwrap();
     
     // Do-nothing should have no trades, buy strategy should have activity
     assert_eq!(do_nothing_result.trades.len(), 0);
@@ -197,12 +196,12 @@ async fn test_strategy_with_high_transaction_costs() {
     engine.load_data("HIGH_COST", data).await.unwrap();
     
     // Strategy that would trade frequently in sideways market
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "HIGH_COST".to_string(),
         position_size: 1000.0,
     };
     
-    let result = engine.run(&strategy).await.unwrap();
+    let result = engine.run(&mut strategy).await.unwrap();
     
     // High transaction costs should impact performance
     assert!(result.metrics.total_commission >= 0.0);
@@ -232,12 +231,12 @@ async fn test_multi_asset_strategy() {
     }
     
     // Strategy that targets one specific asset
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "ASSET2".to_string(),
         position_size: 1500.0,
     };
     
-    let result = engine.run(&strategy).await.unwrap();
+    let result = engine.run(&mut strategy).await.unwrap();
     
     // Should have focused on the target asset
     let has_target_position = result.final_portfolio.positions
@@ -268,12 +267,12 @@ async fn test_strategy_with_extreme_volatility() {
     let data = TestDataFactory::volatile_data(30, 500.0, 100.0); // Very high volatility
     engine.load_data("EXTREME_VOL", data).await.unwrap();
     
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "EXTREME_VOL".to_string(),
         position_size: 500.0,
     };
     
-    let result = engine.run(&strategy).await.unwrap();
+    let result = engine.run(&mut strategy).await.unwrap();
     
     // Should handle extreme volatility without crashing
     TestAssertions::assert_portfolio_valid(&result.final_portfolio);
@@ -295,12 +294,12 @@ async fn test_strategy_performance_attribution() {
     let data = TestDataFactory::trending_up_data(40, 80.0);
     engine.load_data("ATTRIBUTION", data).await.unwrap();
     
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "ATTRIBUTION".to_string(),
         position_size: 2500.0,
     };
     
-    let result = engine.run(&strategy).await.unwrap();
+    let result = engine.run(&mut strategy).await.unwrap();
     
     // Analyze performance attribution
     let initial_value = 100_000.0; // From config
@@ -327,12 +326,12 @@ async fn test_strategy_with_data_gaps() {
     let data = TestDataFactory::gapped_data(50, 150.0, 0.4); // 40% chance of gaps
     engine.load_data("GAPPED", data).await.unwrap();
     
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "GAPPED".to_string(),
         position_size: 800.0,
     };
     
-    let result = engine.run(&strategy).await;
+    let result = engine.run(&mut strategy).await;
     assert!(result.is_ok(), "Strategy should handle gaps in data");
     
     let backtest_result = result.unwrap();
@@ -355,12 +354,12 @@ async fn test_strategy_risk_management() {
     let data = TestDataFactory::trending_down_data(30, 200.0);
     engine.load_data("RISKY", data).await.unwrap();
     
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "RISKY".to_string(),
         position_size: 1000.0, // Relatively large position
     };
     
-    let result = engine.run(&strategy).await.unwrap();
+    let result = engine.run(&mut strategy).await.unwrap();
     
     // Should not lose everything (basic risk management)
     assert!(result.final_portfolio.total_value > 0.0, "Should not lose all capital");
@@ -389,12 +388,12 @@ async fn test_strategy_with_different_market_regimes() {
         
         engine.load_data("REGIME", data).await.unwrap();
         
-        let strategy = AlwaysBuyStrategy {
+        let mut strategy = AlwaysBuyStrategy {
             symbol: "REGIME".to_string(),
             position_size: 1000.0,
         };
         
-        let result = engine.run(&strategy).await;
+        let result = engine.run(&mut strategy).await;
         assert!(result.is_ok(), "Strategy should work in {}", regime_name);
         
         let backtest_result = result.unwrap();
@@ -1,8 +1,21 @@
 //! Test utilities and factories for backtesting tests
 
 use backtesting::*;
+use backtesting::commission::CommissionSchedule;
+use backtesting::contingent::IntrabarOrdering;
+use backtesting::event_replay::to_ts;
+use backtesting::latency::LatencyConfig;
+use backtesting::margin::MarginConfig;
+use backtesting::options::OptionsConfig;
 use chrono::{DateTime, Utc, Duration};
-use std::collections::HashMap;
+use orderbook::events::{
+    LevelUpdate, OrderBookDelta, OrderBookEvent, OrderBookSnapshot, OrderUpdate, Side, TradeEvent, UpdateType,
+};
+use services_common::{Px, Qty, Symbol};
+use std::collections::{HashMap, VecDeque};
+use parking_lot::Mutex;
+use std::sync::Arc;
+use trading_gateway::{ComponentHealth, OrderStatus, TradingEvent, TradingStrategy};
 
 /// Factory for creating test configurations
 pub struct TestConfigFactory;
@@ -15,11 +28,19 @@ impl TestConfigFactory {
             end_date: Utc::now() - Duration::days(1),
             initial_capital: 100_000.0,
             commission_rate: 0.001,
+            commission_model: CommissionSchedule::Flat,
             slippage_model: SlippageModel::Fixed { bps: 5.0 },
             data_frequency: DataFrequency::Daily,
             enable_shorting: false,
             margin_requirement: 0.5,
             risk_free_rate: 0.02,
+            fill_model: FillModel::Touch,
+            intrabar_ordering: IntrabarOrdering::default(),
+            data_source: None,
+            margin: MarginConfig::default(),
+            options: OptionsConfig::default(),
+            latency: LatencyConfig::default(),
+            benchmark: None,
         }
     }
 
@@ -30,11 +51,19 @@ impl TestConfigFactory {
             end_date: Utc::now() - Duration::hours(1),
             initial_capital: 50_000.0,
             commission_rate: 0.0005,
+            commission_model: CommissionSchedule::Flat,
             slippage_model: SlippageModel::Linear { impact: 0.01 },
             data_frequency: DataFrequency::Minute,
             enable_shorting: true,
             margin_requirement: 0.3,
             risk_free_rate: 0.025,
+            fill_model: FillModel::Touch,
+            intrabar_ordering: IntrabarOrdering::default(),
+            data_source: None,
+            margin: MarginConfig::default(),
+            options: OptionsConfig::default(),
+            latency: LatencyConfig::default(),
+            benchmark: None,
         }
     }
 
@@ -45,11 +74,19 @@ impl TestConfigFactory {
             end_date: Utc::now() - Duration::days(1),
             initial_capital: 200_000.0,
             commission_rate: 0.0015,
+            commission_model: CommissionSchedule::Flat,
             slippage_model: SlippageModel::Square { impact: 0.05 },
             data_frequency: DataFrequency::Hour,
             enable_shorting: true,
             margin_requirement: 0.4,
             risk_free_rate: 0.03,
+            fill_model: FillModel::Touch,
+            intrabar_ordering: IntrabarOrdering::default(),
+            data_source: None,
+            margin: MarginConfig::default(),
+            options: OptionsConfig::default(),
+            latency: LatencyConfig::default(),
+            benchmark: None,
         }
     }
 }
@@ -290,6 +327,7 @@ impl TestOrderFactory {
             order_type: OrderType::Market,
             quantity,
             price: None,
+            stop_price: None,
             timestamp: Utc::now(),
             time_in_force: TimeInForce::GTC,
         }
@@ -304,6 +342,7 @@ impl TestOrderFactory {
             order_type: OrderType::Market,
             quantity,
             price: None,
+            stop_price: None,
             timestamp: Utc::now(),
             time_in_force: TimeInForce::GTC,
         }
@@ -318,6 +357,7 @@ impl TestOrderFactory {
             order_type: OrderType::Limit,
             quantity,
             price: Some(price),
+            stop_price: None,
             timestamp: Utc::now(),
             time_in_force: TimeInForce::GTC,
         }
@@ -332,12 +372,99 @@ impl TestOrderFactory {
             order_type: OrderType::Limit,
             quantity,
             price: Some(price),
+            stop_price: None,
             timestamp: Utc::now(),
             time_in_force: TimeInForce::GTC,
         }
     }
 }
 
+/// Factory for creating order book events
+pub struct TestEventFactory;
+
+impl TestEventFactory {
+    fn levels(levels: &[(f64, f64)], side: Side) -> Vec<LevelUpdate> {
+        levels.iter()
+            .map(|(price, quantity)| LevelUpdate {
+                price: Px::new(*price),
+                quantity: Qty::new(*quantity),
+                order_count: 1,
+                side,
+            })
+            .collect()
+    }
+
+    /// Create a full L2 snapshot from (price, quantity) levels
+    pub fn snapshot(sequence: u64, time: DateTime<Utc>, bids: &[(f64, f64)], asks: &[(f64, f64)]) -> OrderBookEvent {
+        OrderBookEvent::Snapshot(OrderBookSnapshot {
+            symbol: Symbol(1),
+            bids: Self::levels(bids, Side::Buy),
+            asks: Self::levels(asks, Side::Sell),
+            sequence,
+            exchange_time: to_ts(time),
+            local_time: to_ts(time),
+            checksum: 0,
+        })
+    }
+
+    /// Create a delta setting the total quantity of one level (0 removes it)
+    pub fn level_delta(sequence: u64, time: DateTime<Utc>, side: Side, price: f64, quantity: f64) -> OrderBookEvent {
+        let update = Self::levels(&[(price, quantity)], side);
+        let (bid_updates, ask_updates) = match side {
+            Side::Buy => (update, vec![]),
+            Side::Sell => (vec![], update),
+        };
+        OrderBookEvent::Delta(OrderBookDelta {
+            symbol: Symbol(1),
+            bid_updates,
+            ask_updates,
+            bid_deletions: vec![],
+            ask_deletions: vec![],
+            prev_sequence: sequence.saturating_sub(1),
+            sequence,
+            exchange_time: to_ts(time),
+            local_time: to_ts(time),
+        })
+    }
+
+    /// Create an L3 order update
+    pub fn order(
+        sequence: u64,
+        time: DateTime<Utc>,
+        order_id: u64,
+        update_type: UpdateType,
+        side: Side,
+        price: f64,
+        quantity: f64,
+    ) -> OrderBookEvent {
+        OrderBookEvent::Order(OrderUpdate {
+            order_id,
+            price: Px::new(price),
+            quantity: Qty::new(quantity),
+            side,
+            update_type,
+            exchange_time: to_ts(time),
+            local_time: to_ts(time),
+            sequence,
+        })
+    }
+
+    /// Create a trade print
+    pub fn trade(sequence: u64, time: DateTime<Utc>, price: f64, quantity: f64, aggressor_side: Side) -> OrderBookEvent {
+        OrderBookEvent::Trade(TradeEvent {
+            trade_id: sequence,
+            price: Px::new(price),
+            quantity: Qty::new(quantity),
+            aggressor_side,
+            maker_order_id: None,
+            taker_order_id: None,
+            exchange_time: to_ts(time),
+            local_time: to_ts(time),
+            sequence,
+        })
+    }
+}
+
 /// Simple test strategy implementations
 pub struct AlwaysBuyStrategy {
     pub symbol: String,
@@ -394,6 +521,73 @@ impl Strategy for DoNothingStrategy {
     }
 }
 
+/// What a `ScriptedGatewayStrategy` was sent
+#[derive(Debug, Default)]
+pub struct GatewayLog {
+    pub updates: Vec<TradingEvent>,
+    pub reports: Vec<TradingEvent>,
+    pub resets: usize,
+}
+
+impl GatewayLog {
+    pub fn filled(&self) -> bool {
+        self.reports
+            .iter()
+            .any(|report| matches!(report, TradingEvent::ExecutionReport { status: OrderStatus::Filled, .. }))
+    }
+}
+
+/// trading-gateway strategy that records its events and answers market
+/// updates with queued orders until one of them fills
+pub struct ScriptedGatewayStrategy {
+    pub log: Arc<Mutex<GatewayLog>>,
+    orders: VecDeque<TradingEvent>,
+}
+
+impl ScriptedGatewayStrategy {
+    pub fn new(orders: Vec<TradingEvent>) -> (Self, Arc<Mutex<GatewayLog>>) {
+        let log = Arc::new(Mutex::new(GatewayLog::default()));
+        (Self { log: log.clone(), orders: orders.into() }, log)
+    }
+}
+
+#[async_trait::async_trait]
+impl TradingStrategy for ScriptedGatewayStrategy {
+    fn name(&self) -> &str {
+        "Scripted"
+    }
+
+    async fn on_market_update(&mut self, event: &TradingEvent) -> anyhow::Result<Option<TradingEvent>> {
+        let mut log = self.log.lock();
+        log.updates.push(event.clone());
+        if log.filled() {
+            return Ok(None);
+        }
+        Ok(self.orders.pop_front())
+    }
+
+    async fn on_execution(&mut self, report: &TradingEvent) -> anyhow::Result<()> {
+        self.log.lock().reports.push(report.clone());
+        Ok(())
+    }
+
+    fn health(&self) -> ComponentHealth {
+        ComponentHealth {
+            name: "Scripted".to_string(),
+            is_healthy: true,
+            last_heartbeat: std::time::Instant::now(),
+            error_count: 0,
+            success_count: 0,
+            avg_latency_us: 0,
+        }
+    }
+
+    async fn reset(&mut self) -> anyhow::Result<()> {
+        self.log.lock().resets += 1;
+        Ok(())
+    }
+}
+
 /// Deterministic random number generator for reproducible tests
 pub struct TestRandom {
     state: std::sync::atomic::AtomicU64,
@@ -506,6 +700,7 @@ impl MarketSnapshotBuilder {
         let snapshot = MarketSnapshot {
             timestamp: self.timestamp,
             prices: DashMap::new(),
+            quotes: DashMap::new(),
         };
         
         for (symbol, price) in self.prices {
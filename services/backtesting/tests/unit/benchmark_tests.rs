@@ -0,0 +1,114 @@
+//! Unit tests for benchmark-relative analytics
+
+use rstest::*;
+use backtesting::*;
+use backtesting::benchmark::*;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use crate::test_utils::*;
+
+fn day(n: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap() + Duration::days(n)
+}
+
+/// Series starting at `start` that compounds `returns`, one point per day
+fn series(start: f64, returns: &[f64]) -> Vec<(DateTime<Utc>, f64)> {
+    let mut value = start;
+    let mut points = vec![(day(0), value)];
+    for (i, r) in returns.iter().enumerate() {
+        value *= 1.0 + r;
+        points.push((day(i as i64 + 1), value));
+    }
+    points
+}
+
+const BENCHMARK_RETURNS: [f64; 8] = [0.01, -0.02, 0.015, 0.005, -0.01, 0.02, -0.005, 0.01];
+
+#[rstest]
+fn test_levered_strategy_has_beta_and_capture_of_leverage() {
+    let levered: Vec<f64> = BENCHMARK_RETURNS.iter().map(|r| 2.0 * r).collect();
+    let analyzer = PerformanceAnalyzer::new();
+    let metrics = analyzer
+        .calculate_benchmark_metrics(&series(100_000.0, &levered), &BenchmarkConfig::new("NIFTY"), &series(20_000.0, &BENCHMARK_RETURNS), 0.0)
+        .unwrap();
+
+    let overall = metrics.overall;
+    assert_eq!(metrics.symbol, "NIFTY");
+    TestAssertions::assert_approx_eq(overall.beta, 2.0, 1e-9);
+    TestAssertions::assert_approx_eq(overall.correlation, 1.0, 1e-9);
+    TestAssertions::assert_approx_eq(overall.up_capture, 2.0, 1e-9);
+    TestAssertions::assert_approx_eq(overall.down_capture, 2.0, 1e-9);
+    // Twice the benchmark's returns leaves nothing for alpha to explain
+    TestAssertions::assert_approx_eq(overall.alpha, 0.0, 1e-9);
+    assert!(overall.tracking_error > 0.0);
+    // Too few returns for the default quarterly window
+    assert!(metrics.rolling.is_empty());
+}
+
+#[rstest]
+fn test_matching_the_benchmark_has_no_active_risk() {
+    let analyzer = PerformanceAnalyzer::new();
+    let metrics = analyzer
+        .calculate_benchmark_metrics(&series(40_000.0, &BENCHMARK_RETURNS), &BenchmarkConfig::new("BTCUSDT"), &series(40_000.0, &BENCHMARK_RETURNS), 0.05)
+        .unwrap();
+
+    assert_eq!(metrics.overall.tracking_error, 0.0);
+    assert_eq!(metrics.overall.information_ratio, 0.0);
+    TestAssertions::assert_approx_eq(metrics.overall.beta, 1.0, 1e-9);
+    TestAssertions::assert_approx_eq(metrics.excess_return, 0.0, 1e-12);
+    let growth: f64 = BENCHMARK_RETURNS.iter().map(|r| 1.0 + r).product();
+    TestAssertions::assert_approx_eq(metrics.benchmark_return, growth - 1.0, 1e-12);
+}
+
+#[rstest]
+fn test_constant_outperformance_is_alpha() {
+    let boosted: Vec<f64> = BENCHMARK_RETURNS.iter().map(|r| r + 0.001).collect();
+    let analyzer = PerformanceAnalyzer::new();
+    let metrics = analyzer
+        .calculate_benchmark_metrics(&series(100.0, &boosted), &BenchmarkConfig::new("NIFTY"), &series(100.0, &BENCHMARK_RETURNS), 0.0)
+        .unwrap();
+
+    TestAssertions::assert_approx_eq(metrics.overall.beta, 1.0, 1e-9);
+    TestAssertions::assert_approx_eq(metrics.overall.alpha, 0.001 * 252.0, 1e-9);
+    assert!(metrics.excess_return > 0.0);
+}
+
+#[rstest]
+fn test_rolling_metrics_cover_every_full_window() {
+    let config = BenchmarkConfig { symbol: "NIFTY".to_string(), rolling_window: 3 };
+    let levered: Vec<f64> = BENCHMARK_RETURNS.iter().map(|r| 1.5 * r).collect();
+    let metrics = PerformanceAnalyzer::new()
+        .calculate_benchmark_metrics(&series(100.0, &levered), &config, &series(100.0, &BENCHMARK_RETURNS), 0.0)
+        .unwrap();
+
+    assert_eq!(metrics.rolling_window, 3);
+    assert_eq!(metrics.rolling.len(), BENCHMARK_RETURNS.len() - 2);
+    assert_eq!(metrics.rolling[0].timestamp, day(3));
+    assert_eq!(metrics.rolling.last().unwrap().timestamp, day(8));
+    for point in &metrics.rolling {
+        TestAssertions::assert_approx_eq(point.metrics.beta, 1.5, 1e-9);
+    }
+}
+
+#[rstest]
+fn test_benchmark_is_sampled_at_equity_timestamps() {
+    // Equity every day, benchmark every other day from day 1
+    let equity = series(100.0, &[0.01, 0.01, 0.01, 0.01]);
+    let benchmark = vec![(day(1), 10.0), (day(3), 11.0)];
+    let aligned = AlignedReturns::of(&equity, &benchmark).unwrap();
+
+    // Day 0 precedes the benchmark and is dropped
+    assert_eq!(aligned.timestamps, [day(2), day(3), day(4)]);
+    assert_eq!(aligned.benchmark[0], 0.0);
+    TestAssertions::assert_approx_eq(aligned.benchmark[1], 0.1, 1e-12);
+    assert_eq!(aligned.benchmark[2], 0.0);
+    TestAssertions::assert_approx_eq(aligned.benchmark_total_return(), 0.1, 1e-12);
+
+    assert!(AlignedReturns::of(&equity, &[(day(4), 10.0)]).is_err());
+    assert!(AlignedReturns::of(&equity, &[]).is_err());
+}
+
+#[rstest]
+fn test_benchmark_config_defaults_rolling_window() {
+    let config: BenchmarkConfig = serde_json::from_str(r#"{"symbol": "NIFTY"}"#).unwrap();
+    assert_eq!(config, BenchmarkConfig::new("NIFTY"));
+}
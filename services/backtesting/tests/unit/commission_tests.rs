@@ -0,0 +1,148 @@
+//! Unit tests for commission and fee models
+
+use rstest::*;
+use backtesting::*;
+use backtesting::commission::*;
+use chrono::Utc;
+use std::sync::Arc;
+use crate::test_utils::*;
+
+fn fill<'a>(side: OrderSide, quantity: f64, price: f64, liquidity: Liquidity) -> FillContext<'a> {
+    FillContext {
+        symbol: "RELIANCE",
+        side,
+        quantity,
+        price,
+        liquidity,
+        order_brokerage_paid: 0.0,
+    }
+}
+
+#[rstest]
+fn test_flat_commission_is_brokerage_only() {
+    let costs = FlatCommission { rate: 0.001 }.calculate(&fill(OrderSide::Buy, 10.0, 100.0, Liquidity::Taker));
+    assert_eq!(costs, CostBreakdown::brokerage_only(1.0));
+    assert_eq!(costs.total(), 1.0);
+}
+
+#[rstest]
+fn test_zerodha_intraday_buy_and_sell() {
+    let model = ZerodhaCharges::new(ZerodhaSegment::EquityIntraday);
+
+    // ₹1,00,000 turnover: 0.03% brokerage would be ₹30, capped at ₹20
+    let buy = model.calculate(&fill(OrderSide::Buy, 100.0, 1000.0, Liquidity::Taker));
+    TestAssertions::assert_approx_eq(buy.brokerage, 20.0, 1e-9);
+    TestAssertions::assert_approx_eq(buy.transaction_tax, 0.0, 1e-9);
+    TestAssertions::assert_approx_eq(buy.exchange_charges, 2.97, 1e-9);
+    TestAssertions::assert_approx_eq(buy.regulatory_fees, 0.1, 1e-9);
+    TestAssertions::assert_approx_eq(buy.stamp_duty, 3.0, 1e-9);
+    TestAssertions::assert_approx_eq(buy.gst, 0.18 * (20.0 + 2.97 + 0.1), 1e-9);
+
+    let sell = model.calculate(&fill(OrderSide::Sell, 100.0, 1000.0, Liquidity::Taker));
+    TestAssertions::assert_approx_eq(sell.transaction_tax, 25.0, 1e-9);
+    TestAssertions::assert_approx_eq(sell.stamp_duty, 0.0, 1e-9);
+}
+
+#[rstest]
+fn test_zerodha_delivery_charges_stt_both_sides_and_no_brokerage() {
+    let model = ZerodhaCharges::new(ZerodhaSegment::EquityDelivery).on_exchange(IndianExchange::Bse);
+
+    let buy = model.calculate(&fill(OrderSide::Buy, 100.0, 1000.0, Liquidity::Maker));
+    assert_eq!(buy.brokerage, 0.0);
+    TestAssertions::assert_approx_eq(buy.transaction_tax, 100.0, 1e-9);
+    TestAssertions::assert_approx_eq(buy.exchange_charges, 3.75, 1e-9);
+    TestAssertions::assert_approx_eq(buy.stamp_duty, 15.0, 1e-9);
+
+    let sell = model.calculate(&fill(OrderSide::Sell, 100.0, 1000.0, Liquidity::Maker));
+    TestAssertions::assert_approx_eq(sell.transaction_tax, 100.0, 1e-9);
+}
+
+#[rstest]
+fn test_zerodha_options_flat_brokerage_once_per_order() {
+    let model = ZerodhaCharges::new(ZerodhaSegment::Options);
+
+    let first = model.calculate(&fill(OrderSide::Sell, 75.0, 100.0, Liquidity::Taker));
+    TestAssertions::assert_approx_eq(first.brokerage, 20.0, 1e-9);
+    TestAssertions::assert_approx_eq(first.transaction_tax, 7.5, 1e-9);
+    TestAssertions::assert_approx_eq(first.exchange_charges, 0.000_350_3 * 7500.0, 1e-9);
+
+    let second = model.calculate(&FillContext { order_brokerage_paid: 20.0, ..fill(OrderSide::Sell, 75.0, 100.0, Liquidity::Taker) });
+    assert_eq!(second.brokerage, 0.0, "Partial fills of one order share the flat fee");
+}
+
+#[rstest]
+fn test_binance_tiers_and_bnb_discount() {
+    let regular = BinanceFees::vip(0);
+    let taker = regular.calculate(&fill(OrderSide::Buy, 1.0, 10_000.0, Liquidity::Taker));
+    TestAssertions::assert_approx_eq(taker.total(), 10.0, 1e-9);
+
+    let vip3 = BinanceFees::vip(3);
+    let maker = vip3.calculate(&fill(OrderSide::Sell, 1.0, 10_000.0, Liquidity::Maker));
+    TestAssertions::assert_approx_eq(maker.brokerage, 4.2, 1e-9);
+
+    let discounted = BinanceFees::vip(0).with_bnb_discount();
+    TestAssertions::assert_approx_eq(discounted.taker_rate, 0.00075, 1e-12);
+    assert_eq!(BinanceFees::vip(42), BinanceFees::vip(9), "Levels above the top tier use VIP 9");
+}
+
+#[rstest]
+fn test_schedule_parses_from_config_json() {
+    let schedule: CommissionSchedule = serde_json::from_str(r#"{"type": "zerodha", "segment": "futures"}"#).unwrap();
+    assert_eq!(schedule, CommissionSchedule::Zerodha { segment: ZerodhaSegment::Futures, exchange: IndianExchange::Nse });
+
+    let schedule: CommissionSchedule = serde_json::from_str(r#"{"type": "binance", "vip_level": 2, "bnb_discount": true}"#).unwrap();
+    let costs = schedule.build(0.0).calculate(&fill(OrderSide::Buy, 1.0, 1000.0, Liquidity::Maker));
+    TestAssertions::assert_approx_eq(costs.brokerage, 1000.0 * 0.0008 * 0.75, 1e-9);
+
+    let mut config: serde_json::Value = serde_json::to_value(TestConfigFactory::basic_config()).unwrap();
+    config.as_object_mut().unwrap().remove("commission_model");
+    let config: BacktestConfig = serde_json::from_value(config).unwrap();
+    assert_eq!(config.commission_model, CommissionSchedule::Flat, "Older configs default to the flat rate");
+}
+
+#[rstest]
+fn test_simulator_fills_carry_breakdown_into_portfolio() {
+    let simulator = ExecutionSimulator::new(ExecutionConfig {
+        reject_rate: 0.0,
+        commission_model: Some(Arc::new(ZerodhaCharges::new(ZerodhaSegment::EquityIntraday))),
+        ..Default::default()
+    });
+    let portfolio = PortfolioTracker::new(1_000_000.0);
+    let market = MarketSnapshotBuilder::new().with_price("RELIANCE", 1000.0).build();
+
+    simulator.submit_order(TestOrderFactory::market_buy("RELIANCE", 100.0)).unwrap();
+    simulator.process_pending_orders(&market, Utc::now()).unwrap();
+    simulator.submit_order(TestOrderFactory::market_sell("RELIANCE", 100.0)).unwrap();
+    simulator.process_pending_orders(&market, Utc::now()).unwrap();
+
+    let fills = simulator.fills_since(0);
+    assert_eq!(fills.len(), 2);
+    for fill in &fills {
+        TestAssertions::assert_approx_eq(fill.commission, fill.costs.total(), 1e-9);
+        portfolio.process_fill(fill).unwrap();
+    }
+
+    let costs = portfolio.get_cost_breakdown();
+    TestAssertions::assert_approx_eq(costs.brokerage, 40.0, 1e-9);
+    TestAssertions::assert_approx_eq(costs.transaction_tax, 25.0, 1e-9);
+    TestAssertions::assert_approx_eq(costs.total(), fills.iter().map(|f| f.commission).sum::<f64>(), 1e-9);
+}
+
+#[rstest]
+fn test_fills_without_breakdown_count_as_brokerage() {
+    let portfolio = PortfolioTracker::new(100_000.0);
+    let fill = Fill {
+        order_id: "legacy".to_string(),
+        symbol: "AAPL".to_string(),
+        side: OrderSide::Buy,
+        quantity: 10.0,
+        price: 100.0,
+        commission: 5.0,
+        slippage: 0.0,
+        timestamp: Utc::now(),
+        costs: CostBreakdown::default(),
+    };
+    portfolio.process_fill(&fill).unwrap();
+
+    assert_eq!(portfolio.get_cost_breakdown(), CostBreakdown::brokerage_only(5.0));
+}
@@ -0,0 +1,289 @@
+//! Unit tests for contingent orders and intrabar trigger ordering
+
+use rstest::*;
+use backtesting::*;
+use backtesting::contingent::*;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use crate::test_utils::*;
+
+fn simulator(intrabar_ordering: IntrabarOrdering) -> ExecutionSimulator {
+    ExecutionSimulator::new(ExecutionConfig {
+        reject_rate: 0.0,
+        commission_rate: 0.0,
+        intrabar_ordering,
+        ..Default::default()
+    })
+}
+
+fn bar(open: f64, high: f64, low: f64, close: f64) -> OHLCV {
+    OHLCV { open, high, low, close, volume: 1_000.0 }
+}
+
+fn order(id: &str, side: OrderSide, order_type: OrderType, quantity: f64, price: Option<f64>, stop_price: Option<f64>, timestamp: DateTime<Utc>) -> Order {
+    Order {
+        id: id.to_string(),
+        symbol: "TEST".to_string(),
+        side,
+        order_type,
+        quantity,
+        price,
+        stop_price,
+        timestamp,
+        time_in_force: TimeInForce::GTC,
+    }
+}
+
+/// Processes `simulator` at `time` through a bar for TEST that started at `start`
+fn process_bar(simulator: &ExecutionSimulator, start: DateTime<Utc>, bar: OHLCV, time: DateTime<Utc>) {
+    let market = MarketSnapshotBuilder::new().with_timestamp(time).with_price("TEST", bar.close).build();
+    let bars = HashMap::from([("TEST".to_string(), (start, bar))]);
+    simulator.process_pending_orders_intrabar(&market, &bars, time).unwrap();
+}
+
+fn process_price(simulator: &ExecutionSimulator, price: f64, time: DateTime<Utc>) {
+    let market = MarketSnapshotBuilder::new().with_timestamp(time).with_price("TEST", price).build();
+    simulator.process_pending_orders(&market, time).unwrap();
+}
+
+/// A bought bracket with its stop at 95 and target at 105, entered at 100
+fn entered_bracket(simulator: &ExecutionSimulator, entered: DateTime<Utc>) {
+    simulator
+        .submit_contingent(ContingentOrder::Bracket {
+            entry: order("ENTRY", OrderSide::Buy, OrderType::Market, 10.0, None, None, entered),
+            stop_loss: order("STOP", OrderSide::Sell, OrderType::Stop, 10.0, None, Some(95.0), entered),
+            take_profit: order("TARGET", OrderSide::Sell, OrderType::Limit, 10.0, Some(105.0), None, entered),
+        })
+        .unwrap();
+    process_price(simulator, 100.0, entered);
+}
+
+#[rstest]
+#[case::pessimistic_sell(IntrabarOrdering::Pessimistic, OrderSide::Sell, [100.0, 90.0, 110.0, 104.0])]
+#[case::pessimistic_buy(IntrabarOrdering::Pessimistic, OrderSide::Buy, [100.0, 110.0, 90.0, 104.0])]
+#[case::optimistic_sell(IntrabarOrdering::Optimistic, OrderSide::Sell, [100.0, 110.0, 90.0, 104.0])]
+#[case::nearest_extreme(IntrabarOrdering::NearestExtreme, OrderSide::Sell, [100.0, 90.0, 110.0, 104.0])]
+#[case::high_first(IntrabarOrdering::HighFirst, OrderSide::Sell, [100.0, 110.0, 90.0, 104.0])]
+#[case::low_first(IntrabarOrdering::LowFirst, OrderSide::Buy, [100.0, 90.0, 110.0, 104.0])]
+fn test_intrabar_paths(#[case] ordering: IntrabarOrdering, #[case] side: OrderSide, #[case] expected: [f64; 4]) {
+    assert_eq!(ordering.path(&bar(100.0, 110.0, 90.0, 104.0), side), expected);
+}
+
+#[rstest]
+#[case::pessimistic(IntrabarOrdering::Pessimistic, "STOP", 95.0, "TARGET")]
+#[case::optimistic(IntrabarOrdering::Optimistic, "TARGET", 105.0, "STOP")]
+fn test_bracket_resolves_by_intrabar_ordering(
+    #[case] ordering: IntrabarOrdering,
+    #[case] exit: &str,
+    #[case] exit_price: f64,
+    #[case] cancelled: &str,
+) {
+    let simulator = simulator(ordering);
+    let entered = Utc::now() - Duration::days(1);
+    entered_bracket(&simulator, entered);
+    let exits: Vec<_> = simulator.open_orders().into_iter().map(|o| (o.id, o.quantity)).collect();
+    assert_eq!(exits, [("STOP".to_string(), 10.0), ("TARGET".to_string(), 10.0)]);
+
+    let now = entered + Duration::days(1);
+    process_bar(&simulator, now, bar(100.0, 106.0, 94.0, 100.0), now);
+
+    let fills = simulator.fills_since(1);
+    assert_eq!(fills.len(), 1);
+    assert_eq!((fills[0].order_id.as_str(), fills[0].price, fills[0].quantity), (exit, exit_price, 10.0));
+    let rejections = simulator.rejections_since(0);
+    assert_eq!(rejections.len(), 1);
+    assert_eq!((rejections[0].order_id.as_str(), rejections[0].reason), (cancelled, RejectReason::LinkedOrderFilled));
+    assert!(simulator.open_orders().is_empty());
+}
+
+#[rstest]
+fn test_bracket_entered_within_bar_exits_on_rest_of_path() {
+    let simulator = simulator(IntrabarOrdering::LowFirst);
+    let placed = Utc::now() - Duration::days(1);
+    simulator
+        .submit_contingent(ContingentOrder::Bracket {
+            entry: order("ENTRY", OrderSide::Buy, OrderType::Limit, 10.0, Some(98.0), None, placed),
+            stop_loss: order("STOP", OrderSide::Sell, OrderType::Stop, 10.0, None, Some(95.0), placed),
+            take_profit: order("TARGET", OrderSide::Sell, OrderType::Limit, 10.0, Some(105.0), None, placed),
+        })
+        .unwrap();
+
+    // Open 100, low 97 fills the entry at 98, then the high of 106 reaches the target
+    let now = placed + Duration::days(1);
+    process_bar(&simulator, now, bar(100.0, 106.0, 97.0, 101.0), now);
+
+    let fills: Vec<_> = simulator.fills_since(0).into_iter().map(|f| (f.order_id, f.price)).collect();
+    assert_eq!(fills, [("ENTRY".to_string(), 98.0), ("TARGET".to_string(), 105.0)]);
+    assert!(simulator.open_orders().is_empty());
+}
+
+#[rstest]
+fn test_stop_gapping_through_fills_at_open() {
+    let simulator = simulator(IntrabarOrdering::default());
+    let placed = Utc::now() - Duration::days(1);
+    simulator.submit_order(order("STOP", OrderSide::Sell, OrderType::Stop, 5.0, None, Some(95.0), placed)).unwrap();
+
+    let now = placed + Duration::days(1);
+    process_bar(&simulator, now, bar(90.0, 92.0, 88.0, 91.0), now);
+
+    let fills = simulator.fills_since(0);
+    assert_eq!(fills.len(), 1);
+    assert_eq!(fills[0].price, 90.0);
+}
+
+#[rstest]
+fn test_orders_placed_during_bar_ignore_its_extremes() {
+    let simulator = simulator(IntrabarOrdering::default());
+    let now = Utc::now();
+    simulator.submit_order(order("STOP", OrderSide::Sell, OrderType::Stop, 5.0, None, Some(95.0), now)).unwrap();
+
+    // The bar started before the order was placed, so only its close counts
+    process_bar(&simulator, now - Duration::hours(1), bar(100.0, 101.0, 90.0, 100.0), now);
+    assert!(simulator.fills_since(0).is_empty());
+}
+
+#[rstest]
+fn test_oco_fill_shrinks_sibling() {
+    let simulator = simulator(IntrabarOrdering::default());
+    let now = Utc::now();
+    simulator
+        .submit_contingent(ContingentOrder::Oco {
+            first: order("SMALL", OrderSide::Sell, OrderType::Limit, 4.0, Some(101.0), None, now),
+            second: order("LARGE", OrderSide::Sell, OrderType::Stop, 10.0, None, Some(95.0), now),
+        })
+        .unwrap();
+
+    process_price(&simulator, 102.0, now);
+    let open = simulator.open_orders();
+    assert_eq!(open.len(), 1);
+    assert_eq!((open[0].id.as_str(), open[0].quantity), ("LARGE", 6.0));
+    assert!(simulator.rejections_since(0).is_empty());
+
+    // Once the shrunk leg fills there is nothing left to cancel
+    process_price(&simulator, 94.0, now);
+    assert_eq!(simulator.fills_since(0).len(), 2);
+    assert!(simulator.open_orders().is_empty());
+}
+
+#[rstest]
+fn test_oco_equal_legs_cancel_each_other() {
+    let simulator = simulator(IntrabarOrdering::default());
+    let now = Utc::now();
+    simulator
+        .submit_contingent(ContingentOrder::Oco {
+            first: order("UP", OrderSide::Buy, OrderType::Stop, 3.0, None, Some(105.0), now),
+            second: order("DOWN", OrderSide::Sell, OrderType::Stop, 3.0, None, Some(95.0), now),
+        })
+        .unwrap();
+
+    process_price(&simulator, 106.0, now);
+    assert_eq!(simulator.fills_since(0)[0].order_id, "UP");
+    let rejections = simulator.rejections_since(0);
+    assert_eq!(rejections.len(), 1);
+    assert_eq!((rejections[0].order_id.as_str(), rejections[0].reason), ("DOWN", RejectReason::LinkedOrderFilled));
+}
+
+#[rstest]
+fn test_cancelled_oco_leg_leaves_sibling_standalone() {
+    let simulator = simulator(IntrabarOrdering::default());
+    let now = Utc::now();
+    simulator
+        .submit_contingent(ContingentOrder::Oco {
+            first: order("A", OrderSide::Sell, OrderType::Limit, 3.0, Some(110.0), None, now),
+            second: order("B", OrderSide::Sell, OrderType::Limit, 3.0, Some(120.0), None, now),
+        })
+        .unwrap();
+
+    simulator.cancel_order("A").unwrap();
+    process_price(&simulator, 100.0, now);
+    let open = simulator.open_orders();
+    assert_eq!((open.len(), open[0].id.as_str(), open[0].quantity), (1, "B", 3.0));
+    assert!(simulator.rejections_since(0).is_empty());
+}
+
+#[rstest]
+fn test_trailing_stop_ratchets_and_triggers() {
+    let simulator = simulator(IntrabarOrdering::default());
+    let now = Utc::now();
+    simulator
+        .submit_contingent(ContingentOrder::TrailingStop {
+            order: order("TRAIL", OrderSide::Sell, OrderType::Stop, 2.0, None, None, now),
+            trail: Trail::Amount(5.0),
+        })
+        .unwrap();
+
+    let mut stops = Vec::new();
+    for price in [100.0, 110.0, 106.0] {
+        process_price(&simulator, price, now);
+        stops.push(simulator.open_orders()[0].stop_price);
+    }
+    assert_eq!(stops, [Some(95.0), Some(105.0), Some(105.0)]);
+    assert!(simulator.fills_since(0).is_empty());
+
+    process_price(&simulator, 104.0, now);
+    let fills = simulator.fills_since(0);
+    assert_eq!((fills.len(), fills[0].price), (1, 104.0));
+}
+
+#[rstest]
+fn test_trailing_stop_follows_bar_extremes() {
+    let simulator = simulator(IntrabarOrdering::default());
+    let placed = Utc::now() - Duration::days(2);
+    simulator
+        .submit_contingent(ContingentOrder::TrailingStop {
+            order: order("TRAIL", OrderSide::Sell, OrderType::Stop, 2.0, None, None, placed),
+            trail: Trail::Fraction(0.1),
+        })
+        .unwrap();
+
+    // Sells see the low first: the stop starts at 90 from the open, then trails the high of 110 to 99
+    let start = placed + Duration::days(1);
+    process_bar(&simulator, start, bar(100.0, 110.0, 95.0, 105.0), start);
+    assert_eq!(simulator.open_orders()[0].stop_price, Some(99.0));
+
+    let next = start + Duration::days(1);
+    process_bar(&simulator, next, bar(104.0, 106.0, 97.0, 98.0), next);
+    let fills = simulator.fills_since(0);
+    assert_eq!((fills.len(), fills[0].price), (1, 99.0));
+}
+
+#[rstest]
+#[case::exit_on_entry_side(OrderSide::Buy, 95.0, 105.0)]
+#[case::stop_above_target(OrderSide::Sell, 105.0, 95.0)]
+fn test_invalid_bracket_is_refused(#[case] exit_side: OrderSide, #[case] stop: f64, #[case] target: f64) {
+    let simulator = simulator(IntrabarOrdering::default());
+    let now = Utc::now();
+    let group = ContingentOrder::Bracket {
+        entry: order("ENTRY", OrderSide::Buy, OrderType::Market, 1.0, None, None, now),
+        stop_loss: order("STOP", exit_side, OrderType::Stop, 1.0, None, Some(stop), now),
+        take_profit: order("TARGET", exit_side, OrderType::Limit, 1.0, Some(target), None, now),
+    };
+    assert!(simulator.submit_contingent(group).is_err());
+    assert!(simulator.open_orders().is_empty());
+}
+
+#[rstest]
+fn test_invalid_trails_are_refused() {
+    let simulator = simulator(IntrabarOrdering::default());
+    let now = Utc::now();
+    for (order_type, trail) in [(OrderType::Stop, Trail::Fraction(1.5)), (OrderType::Stop, Trail::Amount(0.0)), (OrderType::Limit, Trail::Amount(1.0))] {
+        let group = ContingentOrder::TrailingStop {
+            order: order("TRAIL", OrderSide::Sell, order_type, 1.0, Some(90.0), Some(90.0), now),
+            trail,
+        };
+        assert!(simulator.submit_contingent(group).is_err());
+    }
+}
+
+#[rstest]
+fn test_amending_stop_moves_stop_price() {
+    let simulator = simulator(IntrabarOrdering::default());
+    let now = Utc::now();
+    simulator.submit_order(order("STOP", OrderSide::Sell, OrderType::Stop, 1.0, None, Some(95.0), now)).unwrap();
+    simulator.amend_order("STOP", None, Some(99.0)).unwrap();
+
+    let amended = &simulator.open_orders()[0];
+    assert_eq!((amended.stop_price, amended.price), (Some(99.0), None));
+    process_price(&simulator, 98.5, now);
+    assert_eq!(simulator.fills_since(0).len(), 1);
+}
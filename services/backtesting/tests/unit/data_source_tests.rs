@@ -0,0 +1,206 @@
+//! Unit tests for WAL and CSV market data sources
+
+use rstest::*;
+use backtesting::data_source::*;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use data_aggregator::{CandleEvent, DataEvent, TradeEvent, Wal};
+use services_common::{Px, Qty, Symbol, Ts};
+use std::collections::HashMap;
+use std::io::Write;
+use crate::test_utils::*;
+
+fn ts(time: DateTime<Utc>) -> Ts {
+    Ts::from_nanos(time.timestamp_nanos_opt().unwrap() as u64)
+}
+
+fn day(d: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 3, d, 0, 0, 0).unwrap()
+}
+
+fn all_time() -> (DateTime<Utc>, DateTime<Utc>) {
+    (day(1) - Duration::days(365), day(1) + Duration::days(365))
+}
+
+fn write_csv(dir: &tempfile::TempDir, name: &str, contents: &str) -> String {
+    let path = dir.path().join(name);
+    std::fs::File::create(&path).unwrap().write_all(contents.as_bytes()).unwrap();
+    path.to_string_lossy().into_owned()
+}
+
+fn csv_config(path: String) -> CsvSourceConfig {
+    serde_json::from_value(serde_json::json!({ "path": path })).unwrap()
+}
+
+fn candle(time: DateTime<Utc>, symbol: u32, timeframe: u32, close: f64) -> DataEvent {
+    DataEvent::Candle(CandleEvent {
+        ts: ts(time),
+        symbol: Symbol(symbol),
+        timeframe,
+        open: Px::new(close - 1.0),
+        high: Px::new(close + 1.0),
+        low: Px::new(close - 2.0),
+        close: Px::new(close),
+        volume: Qty::new(100.0),
+        trades: 10,
+    })
+}
+
+fn trade(time: DateTime<Utc>, symbol: u32, price: f64, quantity: f64) -> DataEvent {
+    DataEvent::Trade(TradeEvent {
+        ts: ts(time),
+        symbol: Symbol(symbol),
+        price: Px::new(price),
+        quantity: Qty::new(quantity),
+        is_buy: true,
+        trade_id: 0,
+    })
+}
+
+fn write_wal(dir: &tempfile::TempDir, events: &[DataEvent]) {
+    let mut wal = Wal::new(dir.path(), Some(1024 * 1024)).unwrap();
+    for event in events {
+        wal.append(event).unwrap();
+    }
+    wal.flush().unwrap();
+}
+
+#[rstest]
+fn test_csv_default_columns_and_range_filter() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_csv(&dir, "AAPL.csv", "\
+timestamp,open,high,low,close,volume
+2024-03-03T00:00:00Z,11,12,10,11.5,900
+2024-03-01T00:00:00Z,10,11,9,10.5,1000
+2024-03-02T00:00:00Z,10.5,11.5,10,11,800
+");
+    let source = CsvDataSource::new(csv_config(path.replace("AAPL", "{symbol}"))).unwrap();
+
+    let bars = source.load_ohlcv("AAPL", day(1), day(2)).unwrap();
+    assert_eq!(bars.len(), 2);
+    assert_eq!(bars[0].0, day(1), "Rows are sorted by time");
+    assert_eq!(bars[1].1.close, 11.0);
+    assert_eq!(bars[0].1.volume, 1000.0);
+}
+
+#[rstest]
+fn test_csv_column_mapping_offset_and_symbol_column() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_csv(&dir, "nse.csv", "\
+Date;Ticker;O;H;L;C
+01-03-2024 09:15;NIFTY;22000;22100;21900;22050
+01-03-2024 09:15;BANKNIFTY;47000;47100;46900;47050
+");
+    let config: CsvSourceConfig = serde_json::from_value(serde_json::json!({
+        "path": path,
+        "delimiter": ";",
+        "columns": {"timestamp": "Date", "open": "O", "high": "H", "low": "L", "close": "C", "symbol": "Ticker"},
+        "timestamp_format": "%d-%m-%Y %H:%M",
+        "utc_offset": "+05:30",
+    })).unwrap();
+    let (start, end) = all_time();
+
+    let bars = CsvDataSource::new(config).unwrap().load_ohlcv("NIFTY", start, end).unwrap();
+    assert_eq!(bars.len(), 1);
+    assert_eq!(bars[0].0, Utc.with_ymd_and_hms(2024, 3, 1, 3, 45, 0).unwrap(), "09:15 IST is 03:45 UTC");
+    assert_eq!(bars[0].1.close, 22050.0);
+    assert_eq!(bars[0].1.volume, 0.0, "Missing volume column means zero volume");
+}
+
+#[rstest]
+fn test_csv_epoch_timestamps() {
+    let dir = tempfile::tempdir().unwrap();
+    let seconds = day(1).timestamp();
+    let path = write_csv(&dir, "BTC.csv", &format!(
+        "timestamp,open,high,low,close,volume\n{},1,1,1,1,1\n{},2,2,2,2,2\n",
+        seconds,
+        (seconds + 60) * 1000,
+    ));
+    let (start, end) = all_time();
+
+    let bars = CsvDataSource::new(csv_config(path)).unwrap().load_ohlcv("BTC", start, end).unwrap();
+    assert_eq!(bars[0].0, day(1));
+    assert_eq!(bars[1].0, day(1) + Duration::minutes(1), "13 digit epochs are milliseconds");
+}
+
+#[rstest]
+fn test_csv_errors_name_the_problem() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_csv(&dir, "BAD.csv", "timestamp,open,high,low,close\n2024-03-01,1,x,1,1\n");
+    let (start, end) = all_time();
+
+    let error = CsvDataSource::new(csv_config(path.clone())).unwrap().load_ohlcv("BAD", start, end).unwrap_err();
+    assert!(format!("{:#}", error).contains("BAD.csv:2"), "Error should point at the row: {:#}", error);
+
+    let mut config = csv_config(path);
+    config.columns.close = "Close".to_string();
+    assert!(CsvDataSource::new(config.clone()).unwrap().load_ohlcv("BAD", start, end).is_err());
+
+    config.utc_offset = Some("Asia/Kolkata".to_string());
+    assert!(CsvDataSource::new(config).is_err());
+}
+
+#[rstest]
+fn test_wal_candles_for_symbol_and_timeframe() {
+    let dir = tempfile::tempdir().unwrap();
+    write_wal(&dir, &[
+        candle(day(1), 7, 60, 100.0),
+        candle(day(1), 7, 300, 101.0),
+        candle(day(1), 8, 60, 500.0),
+        candle(day(2), 7, 60, 102.0),
+        trade(day(2), 7, 102.0, 1.0),
+    ]);
+    let symbols = HashMap::from([("NIFTY".to_string(), 7)]);
+    let (start, end) = all_time();
+
+    let source = WalDataSource::new(dir.path().to_path_buf(), symbols.clone(), WalRecords::Candles { timeframe_secs: Some(60) });
+    let bars = source.load_ohlcv("NIFTY", start, end).unwrap();
+    assert_eq!(bars.iter().map(|(_, bar)| bar.close).collect::<Vec<_>>(), vec![100.0, 102.0]);
+    assert_eq!(bars[0].0, day(1));
+    TestAssertions::assert_approx_eq(bars[0].1.low, 98.0, 1e-9);
+
+    let ambiguous = WalDataSource::new(dir.path().to_path_buf(), symbols, WalRecords::default());
+    assert!(ambiguous.load_ohlcv("NIFTY", start, end).is_err(), "Two timeframes need timeframe_secs");
+
+    // Unmapped names are read as the numeric id
+    let by_id = WalDataSource::new(dir.path().to_path_buf(), HashMap::new(), WalRecords::default());
+    assert_eq!(by_id.load_ohlcv("8", start, end).unwrap()[0].1.close, 500.0);
+    assert!(by_id.load_ohlcv("NIFTY", start, end).is_err());
+}
+
+#[rstest]
+fn test_wal_trades_aggregate_into_bars() {
+    let dir = tempfile::tempdir().unwrap();
+    let open = day(1) + Duration::hours(10);
+    write_wal(&dir, &[
+        trade(open + Duration::seconds(5), 1, 100.0, 2.0),
+        trade(open + Duration::seconds(20), 1, 103.0, 1.0),
+        trade(open + Duration::seconds(40), 1, 99.0, 1.0),
+        trade(open + Duration::seconds(50), 1, 101.0, 3.0),
+        trade(open + Duration::seconds(65), 1, 102.0, 1.0),
+        trade(open + Duration::seconds(66), 2, 1.0, 1.0),
+    ]);
+    let source = WalDataSource::new(dir.path().to_path_buf(), HashMap::new(), WalRecords::Trades { bar_secs: 60 });
+    let (start, end) = all_time();
+
+    let bars = source.load_ohlcv("1", start, end).unwrap();
+    assert_eq!(bars.len(), 2);
+    assert_eq!(bars[0].0, open);
+    let first = &bars[0].1;
+    assert_eq!((first.open, first.high, first.low, first.close, first.volume), (100.0, 103.0, 99.0, 101.0, 7.0));
+    assert_eq!(bars[1].0, open + Duration::minutes(1));
+}
+
+#[rstest]
+fn test_data_source_config_from_backtest_json() {
+    let json = serde_json::json!({"type": "wal", "dir": "/var/lib/wal", "symbols": {"NIFTY": 26000}, "records": {"kind": "trades", "bar_secs": 60}});
+    let config: DataSourceConfig = serde_json::from_value(json).unwrap();
+    assert_eq!(config, DataSourceConfig::Wal {
+        dir: "/var/lib/wal".into(),
+        symbols: HashMap::from([("NIFTY".to_string(), 26000)]),
+        records: WalRecords::Trades { bar_secs: 60 },
+    });
+
+    let missing = WalDataSource::new("/nonexistent/wal".into(), HashMap::new(), WalRecords::default());
+    assert!(missing.load_ohlcv("1", day(1), day(2)).is_err());
+    assert!(!std::path::Path::new("/nonexistent/wal").exists(), "Reading must not create the WAL directory");
+}
@@ -102,8 +102,8 @@ async fn test_run_backtest_with_do_nothing_strategy() {
     engine.load_data("TEST", data).await.unwrap();
     
     // Run with do-nothing strategy
-    let strategy = DoNothingStrategy;
-    let result = engine.run(&strategy).await;
+    let mut strategy = DoNothingStrategy;
+    let result = engine.run(&mut strategy).await;
     
     assert!(result.is_ok(), "Backtest should complete successfully");
     
@@ -135,11 +135,11 @@ async fn test_run_backtest_with_always_buy_strategy() {
     engine.load_data("TEST", data).await.unwrap();
     
     // Run with always buy strategy
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "TEST".to_string(),
         position_size: 1000.0,
     };
-    let result = engine.run(&strategy).await;
+    let result = engine.run(&mut strategy).await;
     
     assert!(result.is_ok(), "Backtest should complete successfully");
     
@@ -175,12 +175,12 @@ async fn test_backtest_with_multiple_symbols() {
     engine.load_data("SYMBOL2", data2).await.unwrap();
     
     // Simple strategy that buys first symbol
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "SYMBOL1".to_string(),
         position_size: 500.0,
     };
     
-    let result = engine.run(&strategy).await;
+    let result = engine.run(&mut strategy).await;
     
     assert!(result.is_ok(), "Multi-symbol backtest should complete");
     
@@ -198,8 +198,8 @@ async fn test_backtest_progress_tracking() {
     let data = TestDataFactory::sideways_data(5, 100.0, 2.0);
     engine.load_data("TEST", data).await.unwrap();
     
-    let strategy = DoNothingStrategy;
-    let result = engine.run(&strategy).await;
+    let mut strategy = DoNothingStrategy;
+    let result = engine.run(&mut strategy).await;
     
     assert!(result.is_ok());
     
@@ -230,8 +230,8 @@ async fn test_backtest_with_different_frequencies() {
         
         engine.load_data("TEST", data).await.unwrap();
         
-        let strategy = DoNothingStrategy;
-        let result = engine.run(&strategy).await;
+        let mut strategy = DoNothingStrategy;
+        let result = engine.run(&mut strategy).await;
         
         assert!(result.is_ok(), "Backtest should work with frequency: {:?}", frequency);
     }
@@ -250,8 +250,8 @@ async fn test_backtest_time_bounds_validation() {
     let data = TestDataFactory::trending_up_data(30, 100.0);
     engine.load_data("TEST", data).await.unwrap();
     
-    let strategy = DoNothingStrategy;
-    let result = engine.run(&strategy).await;
+    let mut strategy = DoNothingStrategy;
+    let result = engine.run(&mut strategy).await;
     
     // Should complete immediately with no processing
     assert!(result.is_ok());
@@ -273,12 +273,12 @@ async fn test_backtest_with_commission_and_slippage() {
     let data = TestDataFactory::trending_up_data(10, 100.0);
     engine.load_data("TEST", data).await.unwrap();
     
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "TEST".to_string(),
         position_size: 1000.0,
     };
     
-    let result = engine.run(&strategy).await.unwrap();
+    let result = engine.run(&mut strategy).await.unwrap();
     
     // With high commission and slippage, final value should be less than initial
     // due to transaction costs
@@ -299,8 +299,8 @@ async fn test_backtest_state_consistency() {
     let data = TestDataFactory::trending_up_data(5, 100.0);
     engine.load_data("TEST", data).await.unwrap();
     
-    let strategy = DoNothingStrategy;
-    let result = engine.run(&strategy).await.unwrap();
+    let mut strategy = DoNothingStrategy;
+    let result = engine.run(&mut strategy).await.unwrap();
     
     // Equity curve should have consistent timestamps
     let equity_curve = &result.equity_curve;
@@ -335,11 +335,11 @@ async fn test_different_slippage_models(#[case] slippage_model: SlippageModel) {
     let data = TestDataFactory::trending_up_data(5, 100.0);
     engine.load_data("TEST", data).await.unwrap();
     
-    let strategy = AlwaysBuyStrategy {
+    let mut strategy = AlwaysBuyStrategy {
         symbol: "TEST".to_string(),
         position_size: 100.0,
     };
     
-    let result = engine.run(&strategy).await;
+    let result = engine.run(&mut strategy).await;
     assert!(result.is_ok(), "Backtest should work with slippage model: {:?}", slippage_model);
 }
\ No newline at end of file
@@ -0,0 +1,216 @@
+//! Unit tests for live book reconstruction and depth-based order matching
+
+use rstest::*;
+use backtesting::*;
+use backtesting::event_replay::{LiveBook, merge_streams, sort_by_sequence};
+use chrono::{Utc, Duration};
+use orderbook::events::{Side, UpdateType};
+use services_common::{Px, Qty};
+use crate::test_utils::*;
+
+fn depth_simulator(partial_fills: bool) -> ExecutionSimulator {
+    ExecutionSimulator::new(ExecutionConfig {
+        partial_fills,
+        reject_rate: 0.0,
+        commission_rate: 0.0,
+        ..Default::default()
+    })
+}
+
+fn sample_book() -> OrderbookSnapshot {
+    OrderbookSnapshot {
+        bids: vec![(99.0, 10.0), (98.0, 20.0)],
+        asks: vec![(101.0, 10.0), (102.0, 20.0), (103.0, 50.0)],
+        timestamp: Utc::now(),
+    }
+}
+
+#[rstest]
+fn test_live_book_applies_snapshot_and_deltas() {
+    let now = Utc::now();
+    let mut book = LiveBook::new();
+    book.apply(&TestEventFactory::snapshot(1, now, &[(99.0, 5.0), (98.0, 7.0)], &[(101.0, 3.0)]));
+
+    assert_eq!(book.best_bid(), Some((Px::new(99.0), Qty::new(5.0))));
+    assert_eq!(book.best_ask(), Some((Px::new(101.0), Qty::new(3.0))));
+    assert_eq!(book.mid_price(), Some(100.0));
+
+    book.apply(&TestEventFactory::level_delta(2, now, Side::Buy, 99.0, 0.0));
+    book.apply(&TestEventFactory::level_delta(3, now, Side::Sell, 100.5, 4.0));
+
+    assert_eq!(book.best_bid(), Some((Px::new(98.0), Qty::new(7.0))));
+    assert_eq!(book.best_ask(), Some((Px::new(100.5), Qty::new(4.0))));
+    assert_eq!(book.sequence(), 3);
+}
+
+#[rstest]
+fn test_live_book_tracks_l3_orders() {
+    let now = Utc::now();
+    let mut book = LiveBook::new();
+    book.apply(&TestEventFactory::order(1, now, 10, UpdateType::Add, Side::Sell, 101.0, 5.0));
+    book.apply(&TestEventFactory::order(2, now, 11, UpdateType::Add, Side::Sell, 101.0, 3.0));
+    assert_eq!(book.quantity_at(Side::Sell, Px::new(101.0)), Qty::new(8.0));
+
+    book.apply(&TestEventFactory::order(3, now, 10, UpdateType::Modify, Side::Sell, 101.0, 2.0));
+    assert_eq!(book.quantity_at(Side::Sell, Px::new(101.0)), Qty::new(5.0));
+
+    book.apply(&TestEventFactory::order(4, now, 11, UpdateType::Delete, Side::Sell, 101.0, 0.0));
+    book.apply(&TestEventFactory::order(5, now, 10, UpdateType::Delete, Side::Sell, 101.0, 0.0));
+    assert!(book.best_ask().is_none());
+
+    // Unknown order IDs are ignored rather than corrupting levels
+    book.apply(&TestEventFactory::order(6, now, 99, UpdateType::Delete, Side::Sell, 101.0, 0.0));
+    assert_eq!(book.quantity_at(Side::Sell, Px::new(101.0)), Qty::ZERO);
+}
+
+#[rstest]
+fn test_live_book_clears_on_sequence_gap_until_snapshot() {
+    let now = Utc::now();
+    let mut book = LiveBook::new();
+    book.apply(&TestEventFactory::snapshot(1, now, &[(99.0, 5.0)], &[(101.0, 3.0)]));
+    book.apply(&TestEventFactory::level_delta(2, now, Side::Buy, 99.5, 2.0));
+    assert!(!book.is_awaiting_snapshot());
+
+    // Sequence 3 is missing, so the book can no longer be trusted
+    book.apply(&TestEventFactory::level_delta(4, now, Side::Sell, 100.5, 1.0));
+    assert!(book.is_awaiting_snapshot());
+    assert_eq!(book.sequence_gaps(), 1);
+    assert!(book.best_bid().is_none());
+    assert!(book.best_ask().is_none());
+
+    // Incremental updates are ignored until a snapshot arrives; trades are not
+    book.apply(&TestEventFactory::order(5, now, 10, UpdateType::Add, Side::Buy, 99.0, 4.0));
+    book.apply(&TestEventFactory::trade(6, now, 100.0, 1.0, Side::Buy));
+    assert!(book.best_bid().is_none());
+    assert_eq!(book.reference_price(), Some(100.0));
+    assert_eq!(book.sequence_gaps(), 1, "An open gap is only counted once");
+
+    book.apply(&TestEventFactory::snapshot(9, now, &[(98.0, 6.0)], &[(102.0, 2.0)]));
+    book.apply(&TestEventFactory::level_delta(10, now, Side::Sell, 101.5, 1.0));
+    assert!(!book.is_awaiting_snapshot());
+    assert_eq!(book.best_bid(), Some((Px::new(98.0), Qty::new(6.0))));
+    assert_eq!(book.best_ask(), Some((Px::new(101.5), Qty::new(1.0))));
+    assert_eq!(book.sequence(), 10);
+}
+
+#[rstest]
+fn test_live_book_skips_duplicate_events() {
+    let now = Utc::now();
+    let mut book = LiveBook::new();
+    book.apply(&TestEventFactory::order(1, now, 10, UpdateType::Add, Side::Sell, 101.0, 5.0));
+    book.apply(&TestEventFactory::order(1, now, 10, UpdateType::Add, Side::Sell, 101.0, 5.0));
+    book.apply(&TestEventFactory::order(2, now, 11, UpdateType::Add, Side::Sell, 101.0, 3.0));
+
+    assert_eq!(book.quantity_at(Side::Sell, Px::new(101.0)), Qty::new(8.0));
+    assert_eq!(book.sequence_gaps(), 0);
+}
+
+#[rstest]
+fn test_reference_price_falls_back_to_last_trade() {
+    let now = Utc::now();
+    let mut book = LiveBook::new();
+    assert!(book.reference_price().is_none());
+
+    book.apply(&TestEventFactory::trade(1, now, 100.25, 1.0, Side::Buy));
+    assert_eq!(book.reference_price(), Some(100.25));
+}
+
+#[rstest]
+fn test_sort_by_sequence_and_merge_streams() {
+    let now = Utc::now();
+    let mut first = vec![
+        TestEventFactory::trade(3, now + Duration::seconds(3), 100.0, 1.0, Side::Buy),
+        TestEventFactory::trade(1, now + Duration::seconds(1), 100.0, 1.0, Side::Buy),
+    ];
+    sort_by_sequence(&mut first);
+    assert_eq!(first.iter().map(|e| e.sequence()).collect::<Vec<_>>(), vec![1, 3]);
+
+    let second = vec![TestEventFactory::trade(7, now + Duration::seconds(2), 50.0, 1.0, Side::Sell)];
+    let merged = merge_streams(vec![("A".to_string(), first), ("B".to_string(), second)]);
+    let order: Vec<_> = merged.iter().map(|(symbol, event)| (symbol.as_str(), event.sequence())).collect();
+    assert_eq!(order, vec![("A", 1), ("B", 7), ("A", 3)]);
+}
+
+#[rstest]
+fn test_market_order_walks_the_book() {
+    let simulator = depth_simulator(true);
+    simulator.submit_order(TestOrderFactory::market_buy("BOOK", 25.0)).unwrap();
+    simulator.process_orders_against_book("BOOK", &sample_book(), Utc::now()).unwrap();
+
+    let fills = simulator.fills_since(0);
+    assert_eq!(fills.len(), 1);
+    assert_eq!(fills[0].quantity, 25.0);
+    // 10 @ 101 + 15 @ 102
+    TestAssertions::assert_approx_eq(fills[0].price, (10.0 * 101.0 + 15.0 * 102.0) / 25.0, 1e-9);
+    assert!(simulator.pending_symbols().is_empty());
+}
+
+#[rstest]
+fn test_limit_order_partially_fills_up_to_limit() {
+    let simulator = depth_simulator(true);
+    simulator.submit_order(TestOrderFactory::limit_buy("BOOK", 40.0, 102.0)).unwrap();
+    simulator.process_orders_against_book("BOOK", &sample_book(), Utc::now()).unwrap();
+
+    let fills = simulator.fills_since(0);
+    assert_eq!(fills.len(), 1);
+    assert_eq!(fills[0].quantity, 30.0);
+    assert!(fills[0].price <= 102.0);
+    assert_eq!(simulator.pending_symbols(), vec!["BOOK".to_string()], "Remainder should stay pending");
+}
+
+#[rstest]
+fn test_non_marketable_limit_rests() {
+    let simulator = depth_simulator(true);
+    simulator.submit_order(TestOrderFactory::limit_sell("BOOK", 5.0, 100.0)).unwrap();
+    simulator.process_orders_against_book("BOOK", &sample_book(), Utc::now()).unwrap();
+
+    assert!(simulator.fills_since(0).is_empty());
+    assert_eq!(simulator.pending_symbols(), vec!["BOOK".to_string()]);
+}
+
+#[rstest]
+fn test_without_partial_fills_orders_wait_for_full_depth() {
+    let simulator = depth_simulator(false);
+    simulator.submit_order(TestOrderFactory::limit_buy("BOOK", 40.0, 102.0)).unwrap();
+    simulator.process_orders_against_book("BOOK", &sample_book(), Utc::now()).unwrap();
+
+    assert!(simulator.fills_since(0).is_empty());
+    assert_eq!(simulator.pending_symbols(), vec!["BOOK".to_string()]);
+}
+
+#[rstest]
+fn test_ioc_and_fok_cancel_unfilled_quantity() {
+    let simulator = depth_simulator(true);
+    let mut ioc = TestOrderFactory::limit_buy("BOOK", 15.0, 101.0);
+    ioc.time_in_force = TimeInForce::IOC;
+    let mut fok = TestOrderFactory::market_sell("BOOK", 100.0);
+    fok.time_in_force = TimeInForce::FOK;
+    simulator.submit_order(ioc).unwrap();
+    simulator.submit_order(fok).unwrap();
+
+    simulator.process_orders_against_book("BOOK", &sample_book(), Utc::now()).unwrap();
+
+    let fills = simulator.fills_since(0);
+    assert_eq!(fills.len(), 1);
+    assert_eq!(fills[0].side, OrderSide::Buy);
+    assert_eq!(fills[0].quantity, 10.0);
+    assert!(simulator.pending_symbols().is_empty(), "IOC remainder and FOK should be cancelled");
+}
+
+#[rstest]
+fn test_orders_share_displayed_liquidity() {
+    let simulator = depth_simulator(true);
+    let mut first = TestOrderFactory::limit_buy("BOOK", 8.0, 101.0);
+    first.timestamp = Utc::now() - Duration::seconds(1);
+    let second = TestOrderFactory::limit_buy("BOOK", 8.0, 101.0);
+    simulator.submit_order(second).unwrap();
+    simulator.submit_order(first.clone()).unwrap();
+
+    simulator.process_orders_against_book("BOOK", &sample_book(), Utc::now()).unwrap();
+
+    let fills = simulator.fills_since(0);
+    assert_eq!(fills.len(), 2);
+    assert_eq!(fills[0].order_id, first.id, "Earlier order should match first");
+    assert_eq!(fills[0].quantity, 8.0);
+    assert_eq!(fills[1].quantity, 2.0, "Only the remaining displayed size is available");
+}
@@ -11,6 +11,7 @@ fn test_execution_simulator_creation() {
         use_limit_order_book: true,
         partial_fills: true,
         reject_rate: 0.001,
+        ..Default::default()
     };
     
     let simulator = ExecutionSimulator::new(config.clone());
@@ -24,16 +25,19 @@ fn test_execution_config_variations() {
             use_limit_order_book: false,
             partial_fills: false,
             reject_rate: 0.0,
+            ..Default::default()
         },
         ExecutionConfig {
             use_limit_order_book: true,
             partial_fills: true,
             reject_rate: 0.1,
+            ..Default::default()
         },
         ExecutionConfig {
             use_limit_order_book: true,
             partial_fills: false,
             reject_rate: 0.05,
+            ..Default::default()
         },
     ];
     
@@ -49,6 +53,7 @@ fn test_submit_market_buy_order() {
         use_limit_order_book: false,
         partial_fills: false,
         reject_rate: 0.0,
+        ..Default::default()
     };
     
     let simulator = ExecutionSimulator::new(config);
@@ -64,6 +69,7 @@ fn test_submit_market_sell_order() {
         use_limit_order_book: false,
         partial_fills: false,
         reject_rate: 0.0,
+        ..Default::default()
     };
     
     let simulator = ExecutionSimulator::new(config);
@@ -79,6 +85,7 @@ fn test_submit_limit_orders() {
         use_limit_order_book: true,
         partial_fills: false,
         reject_rate: 0.0,
+        ..Default::default()
     };
     
     let simulator = ExecutionSimulator::new(config);
@@ -97,6 +104,7 @@ fn test_process_market_order_execution() {
         use_limit_order_book: false,
         partial_fills: false,
         reject_rate: 0.0,
+        ..Default::default()
     };
     
     let simulator = ExecutionSimulator::new(config);
@@ -122,6 +130,7 @@ fn test_limit_order_execution_conditions() {
         use_limit_order_book: true,
         partial_fills: false,
         reject_rate: 0.0,
+        ..Default::default()
     };
     
     let simulator = ExecutionSimulator::new(config);
@@ -170,6 +179,7 @@ fn test_order_rejection_simulation() {
         use_limit_order_book: false,
         partial_fills: false,
         reject_rate: 1.0, // 100% rejection rate for testing
+        ..Default::default()
     };
     
     let simulator = ExecutionSimulator::new(config);
@@ -196,6 +206,7 @@ fn test_zero_rejection_rate() {
         use_limit_order_book: false,
         partial_fills: false,
         reject_rate: 0.0, // No rejections
+        ..Default::default()
     };
     
     let simulator = ExecutionSimulator::new(config);
@@ -222,12 +233,14 @@ fn test_partial_fills_configuration() {
         use_limit_order_book: true,
         partial_fills: true,
         reject_rate: 0.0,
+        ..Default::default()
     };
     
     let config_no_partial = ExecutionConfig {
         use_limit_order_book: true,
         partial_fills: false,
         reject_rate: 0.0,
+        ..Default::default()
     };
     
     let simulator_partial = ExecutionSimulator::new(config_partial);
@@ -247,6 +260,7 @@ fn test_order_with_different_time_in_force() {
         use_limit_order_book: false,
         partial_fills: false,
         reject_rate: 0.0,
+        ..Default::default()
     };
     
     let simulator = ExecutionSimulator::new(config);
@@ -274,6 +288,7 @@ fn test_process_orders_without_market_data() {
         use_limit_order_book: false,
         partial_fills: false,
         reject_rate: 0.0,
+        ..Default::default()
     };
     
     let simulator = ExecutionSimulator::new(config);
@@ -299,6 +314,7 @@ fn test_multiple_symbols_execution() {
         use_limit_order_book: false,
         partial_fills: false,
         reject_rate: 0.0,
+        ..Default::default()
     };
     
     let simulator = ExecutionSimulator::new(config);
@@ -330,6 +346,7 @@ fn test_large_order_quantities() {
         use_limit_order_book: false,
         partial_fills: false,
         reject_rate: 0.0,
+        ..Default::default()
     };
     
     let simulator = ExecutionSimulator::new(config);
@@ -358,6 +375,7 @@ fn test_extreme_price_levels() {
         use_limit_order_book: false,
         partial_fills: false,
         reject_rate: 0.0,
+        ..Default::default()
     };
     
     let simulator = ExecutionSimulator::new(config);
@@ -385,6 +403,7 @@ fn test_execution_timing() {
         use_limit_order_book: false,
         partial_fills: false,
         reject_rate: 0.0,
+        ..Default::default()
     };
     
     let simulator = ExecutionSimulator::new(config);
@@ -411,6 +430,7 @@ fn test_stop_and_stop_limit_orders() {
         use_limit_order_book: false,
         partial_fills: false,
         reject_rate: 0.0,
+        ..Default::default()
     };
     
     let simulator = ExecutionSimulator::new(config);
@@ -443,6 +463,7 @@ fn test_concurrent_order_processing() {
         use_limit_order_book: false,
         partial_fills: false,
         reject_rate: 0.0,
+        ..Default::default()
     };
     
     let simulator = std::sync::Arc::new(ExecutionSimulator::new(config));
@@ -469,6 +490,7 @@ fn test_deterministic_rejection_behavior() {
         use_limit_order_book: false,
         partial_fills: false,
         reject_rate: 0.1, // 10% rejection rate
+        ..Default::default()
     };
     
     // Run the same test multiple times to ensure deterministic behavior
@@ -500,6 +522,7 @@ fn test_fill_calculation_accuracy() {
         use_limit_order_book: false,
         partial_fills: false,
         reject_rate: 0.0,
+        ..Default::default()
     };
     
     let simulator = ExecutionSimulator::new(config);
@@ -0,0 +1,121 @@
+//! Unit tests for passive limit order fill models
+
+use rstest::*;
+use backtesting::*;
+use backtesting::event_replay::LiveBook;
+use chrono::Utc;
+use orderbook::events::{OrderBookEvent, Side};
+use crate::test_utils::*;
+
+fn simulator(fill_model: FillModel) -> ExecutionSimulator {
+    ExecutionSimulator::new(ExecutionConfig {
+        reject_rate: 0.0,
+        commission_rate: 0.0,
+        fill_model,
+        ..Default::default()
+    })
+}
+
+/// Book with 10 bid at 100 and a resting 5 lot buy joined behind it
+fn resting_buy(fill_model: FillModel) -> (ExecutionSimulator, LiveBook, String) {
+    let now = Utc::now();
+    let mut book = LiveBook::new();
+    book.apply(&TestEventFactory::snapshot(1, now, &[(100.0, 10.0)], &[(101.0, 10.0)]));
+
+    let simulator = simulator(fill_model);
+    let order = TestOrderFactory::limit_buy("Q", 5.0, 100.0);
+    let id = order.id.clone();
+    simulator.submit_order(order).unwrap();
+    simulator.process_orders_against_book("Q", &book.to_snapshot(10, now), now).unwrap();
+    (simulator, book, id)
+}
+
+fn replay(simulator: &ExecutionSimulator, book: &mut LiveBook, event: OrderBookEvent) {
+    book.apply(&event);
+    simulator.on_book_event("Q", &event, book, Utc::now()).unwrap();
+}
+
+fn filled(simulator: &ExecutionSimulator) -> f64 {
+    simulator.fills_since(0).iter().map(|f| f.quantity).sum()
+}
+
+#[rstest]
+fn test_queue_position_fills_after_queue_ahead_is_consumed() {
+    let (simulator, mut book, id) = resting_buy(FillModel::QueuePosition);
+    let now = Utc::now();
+    assert_eq!(simulator.queue_ahead(&id), Some(10.0));
+
+    // Sell aggressor takes 6 at our level, then the feed shows the level shrinking
+    replay(&simulator, &mut book, TestEventFactory::trade(2, now, 100.0, 6.0, Side::Sell));
+    replay(&simulator, &mut book, TestEventFactory::level_delta(3, now, Side::Buy, 100.0, 4.0));
+    assert_eq!(filled(&simulator), 0.0);
+    assert_eq!(simulator.queue_ahead(&id), Some(4.0), "Trade volume must not be counted twice");
+
+    // 2 cancelled out of a 4 lot level with 4 ahead of us: all of it was ahead
+    replay(&simulator, &mut book, TestEventFactory::level_delta(4, now, Side::Buy, 100.0, 2.0));
+    assert_eq!(simulator.queue_ahead(&id), Some(2.0));
+
+    replay(&simulator, &mut book, TestEventFactory::trade(5, now, 100.0, 5.0, Side::Sell));
+    assert_eq!(filled(&simulator), 3.0);
+    assert_eq!(simulator.pending_symbols(), vec!["Q".to_string()]);
+}
+
+#[rstest]
+fn test_queue_position_cancels_are_spread_through_the_level() {
+    let (simulator, mut book, id) = resting_buy(FillModel::QueuePosition);
+    let now = Utc::now();
+
+    // Orders joining behind us don't move us forward
+    replay(&simulator, &mut book, TestEventFactory::level_delta(2, now, Side::Buy, 100.0, 20.0));
+    assert_eq!(simulator.queue_ahead(&id), Some(10.0));
+
+    // Half the level cancels: half of those were ahead of us
+    replay(&simulator, &mut book, TestEventFactory::level_delta(3, now, Side::Buy, 100.0, 10.0));
+    assert_eq!(simulator.queue_ahead(&id), Some(5.0));
+}
+
+#[rstest]
+fn test_trade_through_fills_resting_order() {
+    let (simulator, mut book, _) = resting_buy(FillModel::QueuePosition);
+    replay(&simulator, &mut book, TestEventFactory::trade(2, Utc::now(), 99.5, 1.0, Side::Sell));
+
+    assert_eq!(filled(&simulator), 5.0);
+    assert!(simulator.pending_symbols().is_empty());
+}
+
+#[rstest]
+fn test_buy_aggressor_does_not_fill_resting_bid() {
+    let (simulator, mut book, _) = resting_buy(FillModel::QueuePosition);
+    replay(&simulator, &mut book, TestEventFactory::trade(2, Utc::now(), 100.0, 50.0, Side::Buy));
+
+    assert_eq!(filled(&simulator), 0.0);
+}
+
+#[rstest]
+fn test_touch_model_fills_on_first_trade_at_limit() {
+    let (simulator, mut book, id) = resting_buy(FillModel::Touch);
+    assert_eq!(simulator.queue_ahead(&id), None, "Touch model does not track queues");
+
+    replay(&simulator, &mut book, TestEventFactory::trade(2, Utc::now(), 100.0, 1.0, Side::Sell));
+    assert_eq!(filled(&simulator), 5.0);
+}
+
+#[rstest]
+fn test_probabilistic_model_on_bars() {
+    let market = MarketSnapshotBuilder::new().with_price("BAR", 99.0).build();
+    let fills_with = |fill_probability: f64, seed: u64| {
+        let simulator = simulator(FillModel::Probabilistic { fill_probability, seed });
+        for _ in 0..200 {
+            simulator.submit_order(TestOrderFactory::limit_buy("BAR", 1.0, 100.0)).unwrap();
+        }
+        simulator.process_pending_orders(&market, Utc::now()).unwrap();
+        simulator.fills_since(0).len()
+    };
+
+    assert_eq!(fills_with(0.0, 7), 0);
+    assert_eq!(fills_with(1.0, 7), 200);
+
+    let half = fills_with(0.5, 7);
+    assert!((60..=140).contains(&half), "Expected roughly half to fill, got {}", half);
+    assert_eq!(half, fills_with(0.5, 7), "Draws should be reproducible for a seed");
+}
@@ -0,0 +1,264 @@
+//! Unit tests for running trading-gateway strategies through the adapter
+
+use rstest::*;
+use backtesting::*;
+use backtesting::commission::CostBreakdown;
+use backtesting::event_replay::to_ts;
+use backtesting::lifecycle::*;
+use backtesting::strategies::{StrategySpec, TradingStrategyAdapter};
+use chrono::{TimeZone, Utc};
+use orderbook::events::Side as BookSide;
+use services_common::{Px, Qty, Symbol};
+use trading_gateway::{OrderStatus, OrderType as GatewayOrderType, Side, TimeInForce as GatewayTimeInForce, TradingEvent};
+use crate::test_utils::*;
+
+fn empty_portfolio() -> PortfolioState {
+    PortfolioState { cash: 10_000.0, positions: vec![], total_value: 10_000.0 }
+}
+
+fn order_request(order_type: GatewayOrderType, price: Option<f64>, time_in_force: GatewayTimeInForce) -> TradingEvent {
+    TradingEvent::OrderRequest {
+        id: 42,
+        symbol: Symbol(7),
+        side: Side::Buy,
+        order_type,
+        quantity: Qty::new(2.0),
+        price: price.map(Px::new),
+        time_in_force,
+        strategy_id: "Scripted".to_string(),
+    }
+}
+
+fn adapter(orders: Vec<TradingEvent>) -> (TradingStrategyAdapter, std::sync::Arc<parking_lot::Mutex<GatewayLog>>) {
+    let (strategy, log) = ScriptedGatewayStrategy::new(orders);
+    (TradingStrategyAdapter::new(Box::new(strategy)).with_symbol("NIFTY", Symbol(7)), log)
+}
+
+fn fill(order_id: &str, side: OrderSide, quantity: f64, price: f64) -> Fill {
+    Fill {
+        order_id: order_id.to_string(),
+        symbol: "NIFTY".to_string(),
+        side,
+        quantity,
+        price,
+        commission: 0.0,
+        slippage: 0.0,
+        timestamp: Utc.with_ymd_and_hms(2024, 3, 1, 9, 15, 0).unwrap(),
+        costs: CostBreakdown::default(),
+    }
+}
+
+/// Places one order through `adapter` and returns the simulator order ID
+fn place_one(adapter: &mut TradingStrategyAdapter) -> String {
+    let market = MarketSnapshotBuilder::new().with_price("NIFTY", 100.0).build();
+    let portfolio = empty_portfolio();
+    let mut ctx = StrategyContext::new(&market, &portfolio, &[]);
+    adapter.on_bar(&mut ctx);
+    match &ctx.into_commands()[0] {
+        OrderCommand::Place(order) => order.id.clone(),
+        other => panic!("Expected a placed order, got {:?}", other),
+    }
+}
+
+#[rstest]
+fn test_bar_update_synthesizes_quote_around_close() {
+    let (adapter, log) = adapter(vec![]);
+    let mut adapter = adapter.with_synthetic_spread(20.0).with_synthetic_depth(3.0);
+    let timestamp = Utc.with_ymd_and_hms(2024, 3, 1, 9, 15, 0).unwrap();
+    let market = MarketSnapshotBuilder::new().with_timestamp(timestamp).with_price("NIFTY", 100.0).build();
+    let portfolio = empty_portfolio();
+    adapter.on_bar(&mut StrategyContext::new(&market, &portfolio, &[]));
+
+    let log = log.lock();
+    assert_eq!(log.updates.len(), 1);
+    let TradingEvent::MarketUpdate { symbol, bid, ask, mid, spread, imbalance, timestamp: ts, .. } = &log.updates[0] else {
+        panic!("Expected a market update");
+    };
+    assert_eq!(*symbol, Symbol(7));
+    assert_eq!(*bid, Some((Px::new(99.9), Qty::new(3.0))));
+    assert_eq!(*ask, Some((Px::new(100.1), Qty::new(3.0))));
+    assert_eq!(*mid, Px::new(100.0));
+    assert_eq!(*spread, Px::new(0.2).as_i64());
+    assert_eq!(*imbalance, 0.0);
+    assert_eq!(*ts, to_ts(timestamp));
+}
+
+#[rstest]
+fn test_event_update_uses_book_quote_and_skips_unchanged_symbols() {
+    let (mut adapter, log) = adapter(vec![]);
+    let market = MarketSnapshotBuilder::new().with_price("NIFTY", 100.5).with_price("OTHER", 50.0).build();
+    market.quotes.insert("NIFTY".to_string(), Quote { bid_price: 100.0, bid_size: 30.0, ask_price: 101.0, ask_size: 10.0 });
+    let portfolio = empty_portfolio();
+    let event = TestEventFactory::trade(1, market.timestamp, 100.5, 1.0, BookSide::Buy);
+
+    adapter.on_event(&event, &mut StrategyContext::new(&market, &portfolio, &[]));
+    adapter.on_event(&event, &mut StrategyContext::new(&market, &portfolio, &[]));
+
+    let log = log.lock();
+    assert_eq!(log.updates.len(), 2);
+    let TradingEvent::MarketUpdate { bid, ask, mid, imbalance, .. } = &log.updates[0] else {
+        panic!("Expected a market update");
+    };
+    assert_eq!(*bid, Some((Px::new(100.0), Qty::new(30.0))));
+    assert_eq!(*ask, Some((Px::new(101.0), Qty::new(10.0))));
+    assert_eq!(*mid, Px::new(100.5));
+    assert_eq!(*imbalance, 50.0);
+}
+
+#[rstest]
+fn test_unmapped_symbols_get_next_free_id() {
+    let (strategy, log) = ScriptedGatewayStrategy::new(vec![]);
+    let mut adapter = TradingStrategyAdapter::new(Box::new(strategy)).with_symbol("B", Symbol(5));
+    let market = MarketSnapshotBuilder::new().with_price("C", 1.0).with_price("A", 1.0).with_price("B", 1.0).build();
+    let portfolio = empty_portfolio();
+    adapter.on_bar(&mut StrategyContext::new(&market, &portfolio, &[]));
+
+    let symbols: Vec<Symbol> = log.lock().updates.iter().map(|update| match update {
+        TradingEvent::MarketUpdate { symbol, .. } => *symbol,
+        _ => unreachable!(),
+    }).collect();
+    assert_eq!(symbols, vec![Symbol(6), Symbol(5), Symbol(7)]);
+}
+
+#[rstest]
+#[case(GatewayOrderType::Limit, Some(99.5), GatewayTimeInForce::Gtc, OrderType::Limit, Some(99.5), TimeInForce::GTC)]
+#[case(GatewayOrderType::Iceberg, Some(99.5), GatewayTimeInForce::Day, OrderType::Limit, Some(99.5), TimeInForce::Day)]
+#[case(GatewayOrderType::Twap, None, GatewayTimeInForce::Ioc, OrderType::Market, None, TimeInForce::IOC)]
+#[case(GatewayOrderType::Market, Some(1.0), GatewayTimeInForce::Fok, OrderType::Market, None, TimeInForce::FOK)]
+fn test_order_requests_are_placed_with_simulator_types(
+    #[case] gateway_type: GatewayOrderType,
+    #[case] gateway_price: Option<f64>,
+    #[case] gateway_time_in_force: GatewayTimeInForce,
+    #[case] order_type: OrderType,
+    #[case] price: Option<f64>,
+    #[case] time_in_force: TimeInForce,
+) {
+    let (mut adapter, _log) = adapter(vec![order_request(gateway_type, gateway_price, gateway_time_in_force)]);
+    let market = MarketSnapshotBuilder::new().with_price("NIFTY", 100.0).build();
+    let portfolio = empty_portfolio();
+    let mut ctx = StrategyContext::new(&market, &portfolio, &[]);
+    adapter.on_bar(&mut ctx);
+
+    let commands = ctx.into_commands();
+    assert_eq!(commands.len(), 1);
+    let OrderCommand::Place(order) = &commands[0] else {
+        panic!("Expected a placed order");
+    };
+    assert_eq!(order.symbol, "NIFTY");
+    assert_eq!(order.side, OrderSide::Buy);
+    assert_eq!(order.quantity, 2.0);
+    assert_eq!(order.order_type, order_type);
+    assert_eq!(order.price, price);
+    assert_eq!(order.time_in_force, time_in_force);
+}
+
+#[rstest]
+fn test_unsupported_orders_are_rejected_back_to_strategy() {
+    let (mut adapter, log) = adapter(vec![order_request(GatewayOrderType::Stop, Some(95.0), GatewayTimeInForce::Gtc)]);
+    let market = MarketSnapshotBuilder::new().with_price("NIFTY", 100.0).build();
+    let portfolio = empty_portfolio();
+    let mut ctx = StrategyContext::new(&market, &portfolio, &[]);
+    adapter.on_bar(&mut ctx);
+
+    assert!(ctx.commands().is_empty());
+    let log = log.lock();
+    assert!(matches!(
+        log.reports[..],
+        [TradingEvent::ExecutionReport { order_id: 42, status: OrderStatus::Rejected, .. }]
+    ));
+}
+
+#[rstest]
+fn test_fills_report_partial_then_complete_execution() {
+    let (mut adapter, log) = adapter(vec![order_request(GatewayOrderType::Limit, Some(99.5), GatewayTimeInForce::Gtc)]);
+    let order_id = place_one(&mut adapter);
+
+    let market = MarketSnapshotBuilder::new().with_price("NIFTY", 99.0).build();
+    let portfolio = empty_portfolio();
+    let mut ctx = StrategyContext::new(&market, &portfolio, &[]);
+    adapter.on_fill(&fill(&order_id, OrderSide::Buy, 0.5, 99.5), &mut ctx);
+    adapter.on_fill(&fill(&order_id, OrderSide::Buy, 1.5, 99.5), &mut ctx);
+
+    let log = log.lock();
+    assert_eq!(log.reports.len(), 2);
+    let TradingEvent::ExecutionReport { order_id, symbol, side, executed_qty, executed_price, remaining_qty, status, .. } =
+        &log.reports[0]
+    else {
+        panic!("Expected an execution report");
+    };
+    assert_eq!((*order_id, *symbol, *side), (42, Symbol(7), Side::Buy));
+    assert_eq!((*executed_qty, *executed_price, *remaining_qty), (Qty::new(0.5), Px::new(99.5), Qty::new(1.5)));
+    assert!(matches!(status, OrderStatus::PartiallyFilled));
+    assert!(matches!(
+        log.reports[1],
+        TradingEvent::ExecutionReport { order_id: 42, status: OrderStatus::Filled, remaining_qty, .. } if remaining_qty == Qty::ZERO
+    ));
+}
+
+#[rstest]
+#[case(RejectReason::Simulated, "rejected")]
+#[case(RejectReason::InsufficientFunds, "rejected")]
+#[case(RejectReason::Unfilled, "cancelled")]
+fn test_rejections_report_order_status(#[case] reason: RejectReason, #[case] expected: &str) {
+    let (mut adapter, log) = adapter(vec![order_request(GatewayOrderType::Market, None, GatewayTimeInForce::Ioc)]);
+    let order_id = place_one(&mut adapter);
+
+    let market = MarketSnapshotBuilder::new().with_price("NIFTY", 100.0).build();
+    let portfolio = empty_portfolio();
+    let rejection = OrderRejection { order_id, reason, timestamp: market.timestamp };
+    adapter.on_order_rejected(&rejection, &mut StrategyContext::new(&market, &portfolio, &[]));
+    // A second rejection of the same order is not reported again
+    adapter.on_order_rejected(&rejection, &mut StrategyContext::new(&market, &portfolio, &[]));
+
+    let log = log.lock();
+    assert_eq!(log.reports.len(), 1);
+    let TradingEvent::ExecutionReport { order_id, status, remaining_qty, .. } = &log.reports[0] else {
+        panic!("Expected an execution report");
+    };
+    assert_eq!(*order_id, 42);
+    assert_eq!(*remaining_qty, Qty::new(2.0));
+    let status = match status {
+        OrderStatus::Rejected => "rejected",
+        OrderStatus::Cancelled => "cancelled",
+        other => panic!("Unexpected status {:?}", other),
+    };
+    assert_eq!(status, expected);
+}
+
+#[rstest]
+fn test_fills_not_ordered_by_strategy_are_reported_with_order_zero() {
+    let (mut adapter, log) = adapter(vec![]);
+    let market = MarketSnapshotBuilder::new().with_price("NIFTY", 80.0).build();
+    let portfolio = empty_portfolio();
+    adapter.on_fill(&fill("LIQ_1", OrderSide::Sell, 5.0, 80.0), &mut StrategyContext::new(&market, &portfolio, &[]));
+
+    let log = log.lock();
+    assert!(matches!(
+        log.reports[..],
+        [TradingEvent::ExecutionReport { order_id: 0, symbol: Symbol(7), side: Side::Sell, status: OrderStatus::Filled, .. }]
+    ));
+}
+
+#[rstest]
+fn test_start_resets_strategy() {
+    let (mut adapter, log) = adapter(vec![]);
+    let market = MarketSnapshotBuilder::new().build();
+    let portfolio = empty_portfolio();
+    adapter.on_start(&mut StrategyContext::new(&market, &portfolio, &[]));
+
+    assert_eq!(log.lock().resets, 1);
+    assert_eq!(adapter.errors(), 0);
+    assert_eq!(adapter.strategy().name(), "Scripted");
+}
+
+#[rstest]
+fn test_gateway_strategy_spec() {
+    let spec = StrategySpec::parse(
+        r#"{"type": "gateway", "strategy": "market_making", "symbols": ["NIFTY", "BANKNIFTY"], "synthetic_spread_bps": 12.0}"#,
+    )
+    .unwrap();
+    assert_eq!(spec.symbols(), vec!["NIFTY".to_string(), "BANKNIFTY".to_string()]);
+    let _strategy = spec.build().unwrap();
+
+    assert!(StrategySpec::parse(r#"{"type": "gateway", "strategy": "unknown", "symbols": []}"#).is_err());
+}
@@ -0,0 +1,100 @@
+//! Unit tests for latency models and the latency simulator
+
+use rstest::*;
+use backtesting::latency::*;
+use chrono::{Duration, Utc};
+use crate::test_utils::*;
+
+fn constant(millis: f64) -> LatencyModel {
+    LatencyModel::Constant { millis }
+}
+
+#[rstest]
+#[case(LatencyModel::Zero, 0.3, 0.7, 0.0)]
+#[case(constant(75.0), 0.3, 0.7, 75.0)]
+#[case(LatencyModel::Uniform { min_millis: 50.0, max_millis: 200.0 }, 0.0, 0.7, 50.0)]
+#[case(LatencyModel::Uniform { min_millis: 50.0, max_millis: 200.0 }, 0.5, 0.7, 125.0)]
+// u2 = 0.25 gives a standard normal draw of zero
+#[case(LatencyModel::Normal { mean_millis: 80.0, std_dev_millis: 20.0 }, 0.5, 0.25, 80.0)]
+#[case(LatencyModel::LogNormal { median_millis: 60.0, sigma: 0.5 }, 0.5, 0.25, 60.0)]
+fn test_model_delays(#[case] model: LatencyModel, #[case] u1: f64, #[case] u2: f64, #[case] expected: f64) {
+    model.validate().unwrap();
+    TestAssertions::assert_approx_eq(model.millis(u1, u2), expected, 1e-9);
+}
+
+#[rstest]
+fn test_normal_delays_never_negative() {
+    let model = LatencyModel::Normal { mean_millis: 1.0, std_dev_millis: 50.0 };
+    // u2 = 0.5 gives a strongly negative draw
+    assert_eq!(model.millis(0.99, 0.5), 0.0);
+}
+
+#[rstest]
+#[case(constant(-1.0))]
+#[case(constant(f64::INFINITY))]
+#[case(LatencyModel::Uniform { min_millis: 200.0, max_millis: 50.0 })]
+#[case(LatencyModel::Normal { mean_millis: 50.0, std_dev_millis: f64::NAN })]
+#[case(LatencyModel::LogNormal { median_millis: 50.0, sigma: -0.1 })]
+fn test_invalid_models_rejected(#[case] model: LatencyModel) {
+    assert!(model.validate().is_err());
+    let profile = LatencyProfile { ack: model, ..LatencyProfile::default() };
+    assert!(LatencyConfig::default().with_venue("NSE", profile).validate().is_err());
+}
+
+#[rstest]
+fn test_symbols_use_their_venue_profile() {
+    let nse = LatencyProfile { order_entry: constant(100.0), ..LatencyProfile::default() };
+    let config = LatencyConfig::default()
+        .with_default(LatencyProfile { order_entry: constant(5.0), ..LatencyProfile::default() })
+        .with_venue("NSE", nse)
+        .with_symbol("NIFTY", "NSE")
+        .with_symbol("BTCUSDT", "BINANCE");
+    assert!(!config.is_zero());
+
+    assert_eq!(config.profile_for("NIFTY"), (Some("NSE"), &nse));
+    // Venues without a profile and unrouted symbols use the default
+    assert_eq!(config.profile_for("BTCUSDT").1.order_entry, constant(5.0));
+    assert_eq!(config.profile_for("AAPL"), (None, &config.default));
+
+    let simulator = LatencySimulator::new(config);
+    assert_eq!(simulator.delay(LatencyChannel::OrderEntry, "NIFTY"), Duration::milliseconds(100));
+    assert_eq!(simulator.delay(LatencyChannel::OrderEntry, "AAPL"), Duration::milliseconds(5));
+    assert_eq!(simulator.delay(LatencyChannel::MarketData, "NIFTY"), Duration::zero());
+}
+
+#[rstest]
+fn test_links_are_fifo() {
+    let profile = LatencyProfile {
+        order_entry: LatencyModel::Uniform { min_millis: 0.0, max_millis: 500.0 },
+        ..LatencyProfile::default()
+    };
+    let simulator = LatencySimulator::new(LatencyConfig::default().with_default(profile));
+    let start = Utc::now();
+
+    let arrivals: Vec<_> = (0..200)
+        .map(|i| simulator.arrival(LatencyChannel::OrderEntry, "NIFTY", start + Duration::milliseconds(i)))
+        .collect();
+    assert!(arrivals.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert!((0..200).all(|i| arrivals[i as usize] >= start + Duration::milliseconds(i)));
+    assert!(arrivals.iter().any(|arrival| *arrival > start + Duration::milliseconds(200)));
+}
+
+#[rstest]
+fn test_draws_reproducible_for_a_seed() {
+    let profile = LatencyProfile {
+        ack: LatencyModel::LogNormal { median_millis: 80.0, sigma: 0.4 },
+        ..LatencyProfile::default()
+    };
+    let draws = |seed: u64| {
+        let simulator = LatencySimulator::new(LatencyConfig { seed, ..LatencyConfig::default().with_default(profile) });
+        (0..20).map(|_| simulator.delay(LatencyChannel::Ack, "NIFTY")).collect::<Vec<_>>()
+    };
+    assert_eq!(draws(7), draws(7));
+    assert_ne!(draws(7), draws(8));
+
+    let simulator = LatencySimulator::new(LatencyConfig::default().with_default(profile));
+    let first: Vec<_> = (0..5).map(|_| simulator.delay(LatencyChannel::Ack, "NIFTY")).collect();
+    simulator.reset();
+    let again: Vec<_> = (0..5).map(|_| simulator.delay(LatencyChannel::Ack, "NIFTY")).collect();
+    assert_eq!(first, again);
+}
@@ -0,0 +1,116 @@
+//! Unit tests for strategy contexts and order amendment
+
+use rstest::*;
+use backtesting::*;
+use backtesting::event_replay::LiveBook;
+use backtesting::lifecycle::*;
+use chrono::Utc;
+use crate::test_utils::*;
+
+fn empty_portfolio() -> PortfolioState {
+    PortfolioState { cash: 10_000.0, positions: vec![], total_value: 10_000.0 }
+}
+
+#[rstest]
+fn test_context_assigns_ids_and_collects_commands() {
+    let market = MarketSnapshotBuilder::new().with_price("AAPL", 150.0).build();
+    let portfolio = empty_portfolio();
+    let mut ctx = StrategyContext::new(&market, &portfolio, &[]);
+
+    let first = ctx.place(OrderRequest::limit("AAPL", OrderSide::Buy, 10.0, 149.0).with_time_in_force(TimeInForce::IOC));
+    let second = ctx.place(TradingSignal {
+        symbol: "AAPL".to_string(),
+        side: OrderSide::Sell,
+        order_type: OrderType::Market,
+        quantity: 5.0,
+        price: None,
+    });
+    ctx.amend(&first, Some(8.0), None);
+    ctx.cancel(&second);
+
+    assert_ne!(first, second);
+    assert_eq!(ctx.price("AAPL"), Some(150.0));
+    let commands = ctx.into_commands();
+    assert_eq!(commands.len(), 4);
+    match &commands[0] {
+        OrderCommand::Place(order) => {
+            assert_eq!(order.id, first);
+            assert_eq!(order.price, Some(149.0));
+            assert_eq!(order.time_in_force, TimeInForce::IOC);
+            assert_eq!(order.timestamp, market.timestamp);
+        }
+        other => panic!("Expected a placement, got {:?}", other),
+    }
+    match &commands[1] {
+        OrderCommand::Place(order) => assert_eq!(order.time_in_force, TimeInForce::GTC),
+        other => panic!("Expected a placement, got {:?}", other),
+    }
+    assert!(matches!(&commands[3], OrderCommand::Cancel { order_id } if *order_id == second));
+}
+
+#[rstest]
+fn test_default_on_bar_places_generated_signals() {
+    let market = MarketSnapshotBuilder::new().with_price("BUY", 10.0).build();
+    let portfolio = empty_portfolio();
+    let mut ctx = StrategyContext::new(&market, &portfolio, &[]);
+    let mut strategy = AlwaysBuyStrategy { symbol: "BUY".to_string(), position_size: 100.0 };
+
+    strategy.on_bar(&mut ctx);
+    assert_eq!(ctx.commands().len(), 1);
+}
+
+#[rstest]
+fn test_cancel_and_amend_require_pending_order() {
+    let simulator = ExecutionSimulator::new(ExecutionConfig { reject_rate: 0.0, ..Default::default() });
+    let order = TestOrderFactory::limit_buy("X", 10.0, 100.0);
+    let id = order.id.clone();
+    simulator.submit_order(order).unwrap();
+
+    assert!(simulator.amend_order(&id, Some(0.0), None).is_err());
+    simulator.amend_order(&id, Some(4.0), Some(99.0)).unwrap();
+    let open = simulator.open_orders();
+    assert_eq!((open[0].quantity, open[0].price), (4.0, Some(99.0)));
+
+    simulator.cancel_order(&id).unwrap();
+    assert!(simulator.open_orders().is_empty());
+    assert!(simulator.cancel_order(&id).is_err());
+    assert!(simulator.amend_order(&id, Some(1.0), None).is_err());
+}
+
+#[rstest]
+fn test_amend_keeps_queue_position_only_when_reducing() {
+    let now = Utc::now();
+    let mut book = LiveBook::new();
+    book.apply(&TestEventFactory::snapshot(1, now, &[(100.0, 10.0)], &[(101.0, 10.0)]));
+    let simulator = ExecutionSimulator::new(ExecutionConfig {
+        reject_rate: 0.0,
+        fill_model: FillModel::QueuePosition,
+        ..Default::default()
+    });
+    let order = TestOrderFactory::limit_buy("Q", 5.0, 100.0);
+    let id = order.id.clone();
+    simulator.submit_order(order).unwrap();
+    simulator.process_orders_against_book("Q", &book.to_snapshot(10, now), now).unwrap();
+
+    simulator.amend_order(&id, Some(3.0), None).unwrap();
+    assert_eq!(simulator.queue_ahead(&id), Some(10.0));
+
+    simulator.amend_order(&id, None, Some(100.5)).unwrap();
+    assert_eq!(simulator.queue_ahead(&id), None, "A new price loses queue priority");
+}
+
+#[rstest]
+fn test_simulated_rejections_are_recorded() {
+    let simulator = ExecutionSimulator::new(ExecutionConfig { reject_rate: 1.0, ..Default::default() });
+    let market = MarketSnapshotBuilder::new().with_price("X", 10.0).build();
+    let order = TestOrderFactory::market_buy("X", 1.0);
+    let id = order.id.clone();
+    simulator.submit_order(order).unwrap();
+    simulator.process_pending_orders(&market, market.timestamp).unwrap();
+
+    let rejections = simulator.rejections_since(0);
+    assert_eq!(rejections.len(), 1);
+    assert_eq!(rejections[0].order_id, id);
+    assert_eq!(rejections[0].reason, RejectReason::Simulated);
+    assert!(simulator.rejections_since(1).is_empty());
+}
@@ -0,0 +1,195 @@
+//! Unit tests for margined positions, financing and margin status
+
+use rstest::*;
+use backtesting::*;
+use backtesting::commission::CostBreakdown;
+use backtesting::margin::*;
+use chrono::{DateTime, Duration, Utc};
+use crate::test_utils::*;
+
+fn fill(symbol: &str, side: OrderSide, quantity: f64, price: f64, commission: f64, timestamp: DateTime<Utc>) -> Fill {
+    Fill {
+        order_id: format!("margin_{}", TestRandom::next_id()),
+        symbol: symbol.to_string(),
+        side,
+        quantity,
+        price,
+        commission,
+        slippage: 0.0,
+        timestamp,
+        costs: CostBreakdown::default(),
+    }
+}
+
+fn margined(capital: f64, symbol: &str, terms: InstrumentMargin) -> PortfolioTracker {
+    PortfolioTracker::new(capital).with_margin(MarginConfig::default().with_instrument(symbol, terms))
+}
+
+#[rstest]
+fn test_margined_fill_moves_no_notional() {
+    let portfolio = margined(100_000.0, "BTCUSDT", InstrumentMargin::binance_usdm(10.0));
+    portfolio.process_fill(&fill("BTCUSDT", OrderSide::Buy, 1.0, 50_000.0, 20.0, Utc::now())).unwrap();
+
+    let state = portfolio.get_current_state();
+    TestAssertions::assert_approx_eq(state.cash, 100_000.0 - 20.0, 1e-9);
+    TestAssertions::assert_approx_eq(state.total_value, 100_000.0 - 20.0, 1e-9);
+    assert_eq!(state.positions[0].quantity, 1.0);
+
+    let status = portfolio.margin_status();
+    TestAssertions::assert_approx_eq(status.initial_requirement, 5_000.0, 1e-9);
+    TestAssertions::assert_approx_eq(status.maintenance_requirement, 200.0, 1e-9);
+}
+
+#[rstest]
+fn test_cash_instruments_unaffected_by_other_margin_terms() {
+    let portfolio = margined(100_000.0, "BTCUSDT", InstrumentMargin::binance_usdm(10.0));
+    portfolio.process_fill(&fill("AAPL", OrderSide::Buy, 100.0, 150.0, 0.0, Utc::now())).unwrap();
+
+    let state = portfolio.get_current_state();
+    TestAssertions::assert_approx_eq(state.cash, 85_000.0, 1e-9);
+    TestAssertions::assert_approx_eq(state.total_value, 100_000.0, 1e-9);
+    assert!(portfolio.margined_positions().is_empty());
+}
+
+#[rstest]
+fn test_short_round_trip_settles_pnl() {
+    let portfolio = margined(50_000.0, "NIFTY", InstrumentMargin::nse_futures(0.12));
+    let opened_at = Utc::now() - Duration::days(2);
+    portfolio.process_fill(&fill("NIFTY", OrderSide::Sell, 50.0, 100.0, 5.0, opened_at)).unwrap();
+
+    let position = &portfolio.get_current_state().positions[0];
+    assert_eq!(position.quantity, -50.0);
+    assert_eq!(position.average_price, 100.0);
+
+    let trade = portfolio
+        .process_fill(&fill("NIFTY", OrderSide::Buy, 50.0, 90.0, 5.0, Utc::now()))
+        .unwrap()
+        .expect("Closing a short completes a trade");
+
+    assert_eq!(trade.side, OrderSide::Sell);
+    assert_eq!(trade.entry_time, opened_at);
+    TestAssertions::assert_approx_eq(trade.pnl, 500.0 - 5.0, 1e-9);
+
+    let state = portfolio.get_current_state();
+    assert!(state.positions.is_empty());
+    TestAssertions::assert_approx_eq(state.cash, 50_000.0 + 500.0 - 10.0, 1e-9);
+}
+
+#[rstest]
+fn test_fill_through_flat_opens_opposite_position() {
+    let portfolio = margined(100_000.0, "ETHUSDT", InstrumentMargin::binance_usdm(5.0));
+    portfolio.process_fill(&fill("ETHUSDT", OrderSide::Buy, 10.0, 100.0, 0.0, Utc::now())).unwrap();
+    let trade = portfolio
+        .process_fill(&fill("ETHUSDT", OrderSide::Sell, 15.0, 110.0, 0.0, Utc::now()))
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(trade.quantity, 10.0);
+    TestAssertions::assert_approx_eq(trade.pnl, 100.0, 1e-9);
+
+    let position = &portfolio.get_current_state().positions[0];
+    assert_eq!(position.quantity, -5.0);
+    assert_eq!(position.average_price, 110.0);
+}
+
+#[rstest]
+fn test_initial_margin_limits_new_exposure() {
+    let portfolio = margined(10_000.0, "BANKNIFTY", InstrumentMargin::nse_futures(0.12));
+
+    // 100,000 notional needs 12,000 of margin
+    let rejected = portfolio.process_fill(&fill("BANKNIFTY", OrderSide::Buy, 1_000.0, 100.0, 0.0, Utc::now()));
+    assert!(rejected.is_err());
+    assert!(portfolio.get_current_state().positions.is_empty());
+    assert!(portfolio.get_transactions().is_empty());
+
+    portfolio.process_fill(&fill("BANKNIFTY", OrderSide::Buy, 800.0, 100.0, 0.0, Utc::now())).unwrap();
+    assert!(portfolio.process_fill(&fill("BANKNIFTY", OrderSide::Buy, 100.0, 100.0, 0.0, Utc::now())).is_err());
+}
+
+#[rstest]
+fn test_margin_call_recorded_once_and_reductions_allowed() {
+    let portfolio = margined(10_000.0, "FUT", InstrumentMargin::nse_futures(0.1));
+    let start = Utc::now() - Duration::days(3);
+    portfolio.process_fill(&fill("FUT", OrderSide::Buy, 900.0, 100.0, 0.0, start)).unwrap();
+
+    let market = MarketSnapshotBuilder::new().with_price("FUT", 95.0).build();
+    portfolio.update_prices(&market).unwrap();
+
+    let status = portfolio.check_margin(start + Duration::days(1));
+    assert!(status.is_margin_call());
+    assert!(status.is_below_maintenance());
+    portfolio.check_margin(start + Duration::days(2));
+    assert_eq!(portfolio.get_margin_report().margin_calls, vec![start + Duration::days(1)]);
+
+    assert!(portfolio.process_fill(&fill("FUT", OrderSide::Buy, 1.0, 95.0, 0.0, start)).is_err());
+    assert!(portfolio.process_fill(&fill("FUT", OrderSide::Sell, 900.0, 95.0, 0.0, start)).is_ok());
+}
+
+#[rstest]
+fn test_borrow_fee_accrues_on_shorts() {
+    let terms = InstrumentMargin::from_requirement(0.5).with_financing(Financing::Borrow { annual_rate: 0.0365 });
+    let portfolio = margined(100_000.0, "TSLA", terms);
+    let start = Utc::now() - Duration::days(10);
+    portfolio.process_fill(&fill("TSLA", OrderSide::Sell, 100.0, 100.0, 0.0, start)).unwrap();
+
+    // The first call only starts the clock
+    portfolio.accrue_financing(start);
+    portfolio.accrue_financing(start + Duration::days(1));
+
+    TestAssertions::assert_approx_eq(portfolio.get_margin_report().financing_paid, 1.0, 1e-9);
+    TestAssertions::assert_approx_eq(portfolio.get_current_state().cash, 100_000.0 - 1.0, 1e-9);
+}
+
+#[rstest]
+#[case(10.0, 0.0001, 8.0)]
+#[case(-10.0, 0.0001, 8.0)]
+#[case(10.0, -0.0002, 4.0)]
+fn test_funding_charge_follows_position_side(#[case] quantity: f64, #[case] rate: f64, #[case] interval_hours: f64) {
+    let funding = Financing::Funding { rate, interval_hours };
+    let charge = funding.charge(quantity, 100.0, interval_hours * 3600.0);
+    TestAssertions::assert_approx_eq(charge, rate * quantity * 100.0, 1e-12);
+}
+
+#[rstest]
+fn test_borrow_fee_not_charged_on_longs() {
+    let borrow = Financing::Borrow { annual_rate: 0.05 };
+    assert_eq!(borrow.charge(10.0, 100.0, 86_400.0), 0.0);
+    assert!(borrow.charge(-10.0, 100.0, 86_400.0) > 0.0);
+}
+
+#[rstest]
+fn test_presets() {
+    let perp = InstrumentMargin::binance_usdm(20.0);
+    TestAssertions::assert_approx_eq(perp.initial_margin, 0.05, 1e-12);
+    assert!(perp.maintenance_margin < perp.initial_margin);
+    assert!(matches!(perp.financing, Financing::Funding { interval_hours, .. } if interval_hours == 8.0));
+
+    let future = InstrumentMargin::nse_futures(0.15);
+    assert_eq!(future.initial_margin, future.maintenance_margin);
+    assert_eq!(future.financing, Financing::None);
+
+    let account = InstrumentMargin::from_requirement(0.5);
+    assert_eq!(account.maintenance_margin, 0.25);
+}
+
+#[rstest]
+fn test_margin_config_default_terms_and_serde() {
+    let config = MarginConfig {
+        default: Some(InstrumentMargin::from_requirement(0.4)),
+        ..MarginConfig::default()
+    }
+    .with_instrument("BTCUSDT", InstrumentMargin::binance_usdm(10.0));
+
+    assert_eq!(config.for_symbol("BTCUSDT"), Some(&InstrumentMargin::binance_usdm(10.0)));
+    assert_eq!(config.for_symbol("AAPL"), Some(&InstrumentMargin::from_requirement(0.4)));
+    assert_eq!(MarginConfig::default().for_symbol("AAPL"), None);
+
+    let json = serde_json::to_string(&config).unwrap();
+    assert_eq!(serde_json::from_str::<MarginConfig>(&json).unwrap(), config);
+
+    let parsed: MarginConfig = serde_json::from_str(
+        r#"{"instruments": {"NIFTY": {"initial_margin": 0.12, "maintenance_margin": 0.12}}}"#,
+    )
+    .unwrap();
+    assert_eq!(parsed.for_symbol("NIFTY"), Some(&InstrumentMargin::nse_futures(0.12)));
+}
@@ -5,4 +5,20 @@ pub mod market_data_tests;
 pub mod execution_tests;
 pub mod portfolio_tests;
 pub mod performance_tests;
-pub mod strategy_tests;
\ No newline at end of file
+pub mod strategy_tests;
+pub mod event_replay_tests;
+pub mod fill_model_tests;
+pub mod commission_tests;
+pub mod optimization_tests;
+pub mod lifecycle_tests;
+pub mod data_source_tests;
+pub mod margin_tests;
+pub mod robustness_tests;
+pub mod gateway_adapter_tests;
+pub mod options_tests;
+pub mod latency_tests;
+pub mod run_store_tests;
+pub mod tearsheet_tests;
+pub mod benchmark_tests;
+pub mod script_strategy_tests;
+pub mod contingent_tests;
\ No newline at end of file
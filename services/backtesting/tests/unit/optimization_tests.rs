@@ -0,0 +1,57 @@
+//! Unit tests for optimization search spaces and objectives
+
+use rstest::*;
+use backtesting::*;
+use backtesting::optimization::*;
+
+fn space() -> ParameterSpace {
+    ParameterSpace::new()
+        .with("fast", ParameterRange::Values { values: vec![5.0, 10.0] })
+        .with("slow", ParameterRange::Linear { min: 20.0, max: 40.0, step: 10.0 })
+}
+
+#[rstest]
+fn test_grid_enumerates_every_combination() {
+    let grid = space().grid().unwrap();
+    assert_eq!(grid.len(), 6);
+    assert_eq!(grid[0].get("fast"), Some(&5.0));
+    assert_eq!(grid[0].get("slow"), Some(&20.0));
+    assert_eq!(grid[5].get("fast"), Some(&10.0));
+    assert_eq!(grid[5].get("slow"), Some(&40.0));
+}
+
+#[rstest]
+fn test_grid_rejects_continuous_and_invalid_ranges() {
+    let continuous = space().with("threshold", ParameterRange::Continuous { min: 0.0, max: 1.0 });
+    assert!(continuous.grid().is_err());
+
+    let invalid = ParameterSpace::new().with("x", ParameterRange::Linear { min: 1.0, max: 2.0, step: 0.0 });
+    assert!(invalid.grid().is_err());
+}
+
+#[rstest]
+fn test_random_search_is_reproducible_and_in_range() {
+    let space = space().with("threshold", ParameterRange::Continuous { min: 0.5, max: 1.5 });
+    let draws = space.random(50, 42).unwrap();
+
+    assert_eq!(draws.len(), 50);
+    assert_eq!(draws, space.random(50, 42).unwrap());
+    assert_ne!(draws, space.random(50, 43).unwrap());
+    for draw in &draws {
+        assert!([5.0, 10.0].contains(&draw["fast"]));
+        assert!([20.0, 30.0, 40.0].contains(&draw["slow"]));
+        assert!((0.5..1.5).contains(&draw["threshold"]));
+    }
+}
+
+#[rstest]
+fn test_objective_scores_non_finite_metrics_lowest() {
+    let metrics = PerformanceMetrics {
+        total_return: 0.12,
+        profit_factor: f64::INFINITY,
+        ..Default::default()
+    };
+
+    assert_eq!(Objective::TotalReturn.score(&metrics), 0.12);
+    assert_eq!(Objective::ProfitFactor.score(&metrics), f64::MIN);
+}
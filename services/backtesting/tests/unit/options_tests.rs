@@ -0,0 +1,194 @@
+//! Unit tests for option contracts, volatility surfaces and model marking
+
+use rstest::*;
+use backtesting::*;
+use backtesting::options::*;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use options_engine::{BlackScholes, IndexOption, OptionType};
+use crate::test_utils::*;
+
+fn utc(year: i32, month: u32, day: u32, hour: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(year, month, day, hour, 0, 0).unwrap()
+}
+
+fn grid() -> VolSurface {
+    VolSurface::Grid {
+        expiry_days: vec![30.0, 90.0],
+        moneyness: vec![0.9, 1.0, 1.1],
+        volatilities: vec![vec![0.24, 0.20, 0.22], vec![0.20, 0.16, 0.18]],
+    }
+}
+
+#[rstest]
+#[case("NIFTY24JAN21500CE", IndexOption::Nifty50, OptionType::Call, 21500.0, utc(2024, 1, 25, 10))]
+#[case("BANKNIFTY24MAR47000PE", IndexOption::BankNifty, OptionType::Put, 47000.0, utc(2024, 3, 27, 10))]
+#[case("BANKNIFTY25JAN49000CE", IndexOption::BankNifty, OptionType::Call, 49000.0, utc(2025, 1, 30, 10))]
+#[case("NIFTY25AUG24500CE", IndexOption::Nifty50, OptionType::Call, 24500.0, utc(2025, 8, 28, 10))]
+#[case("NIFTY25SEP24500CE", IndexOption::Nifty50, OptionType::Call, 24500.0, utc(2025, 9, 30, 10))]
+#[case("NIFTY26MAR23000PE", IndexOption::Nifty50, OptionType::Put, 23000.0, utc(2026, 3, 31, 10))]
+#[case("NIFTY2411821000PE", IndexOption::Nifty50, OptionType::Put, 21000.0, utc(2024, 1, 18, 10))]
+#[case("FINNIFTY24O0821000CE", IndexOption::FinNifty, OptionType::Call, 21000.0, utc(2024, 10, 8, 10))]
+fn test_contract_parsed_from_symbol(
+    #[case] symbol: &str,
+    #[case] index: IndexOption,
+    #[case] option_type: OptionType,
+    #[case] strike: f64,
+    #[case] expiry: DateTime<Utc>,
+) {
+    let contract = OptionContract::parse(symbol, &OptionsConfig::default()).unwrap();
+    assert_eq!(contract.index, index);
+    assert_eq!(contract.option_type, option_type);
+    assert_eq!(contract.strike, strike);
+    assert_eq!(contract.expiry, expiry);
+    assert_eq!(contract.lot_size, index.lot_size());
+}
+
+#[rstest]
+fn test_monthly_expiry_moved_off_holidays() {
+    let config = OptionsConfig {
+        expiry_holidays: vec![chrono::NaiveDate::from_ymd_opt(2024, 12, 25).unwrap()],
+        ..OptionsConfig::default()
+    };
+    let contract = OptionContract::parse("BANKNIFTY24DEC51000CE", &config).unwrap();
+    assert_eq!(contract.expiry, utc(2024, 12, 24, 10));
+    assert_eq!(OptionContract::parse("BANKNIFTY24DEC51000CE", &OptionsConfig::default()).unwrap().expiry, utc(2024, 12, 25, 10));
+}
+
+#[rstest]
+#[case("NIFTY")]
+#[case("AAPL")]
+#[case("NIFTY24JAN21500")]
+#[case("NIFTY24XYZ21500CE")]
+#[case("SENSEX24JAN72000CE")]
+fn test_non_option_symbols_not_parsed(#[case] symbol: &str) {
+    assert!(OptionContract::parse(symbol, &OptionsConfig::default()).is_none());
+}
+
+#[rstest]
+fn test_underlying_mapped_to_backtest_symbol() {
+    let mut config = OptionsConfig::default();
+    config.underlyings.insert("NIFTY".to_string(), "NIFTY 50".to_string());
+
+    assert_eq!(OptionContract::parse("NIFTY24JAN21500CE", &config).unwrap().underlying, "NIFTY 50");
+    assert_eq!(OptionContract::parse("BANKNIFTY24JAN47000CE", &config).unwrap().underlying, "BANKNIFTY");
+}
+
+#[rstest]
+fn test_intrinsic_value_and_time_to_expiry() {
+    let call = OptionContract::parse("NIFTY24JAN21500CE", &OptionsConfig::default()).unwrap();
+    let put = OptionContract::parse("NIFTY24JAN21500PE", &OptionsConfig::default()).unwrap();
+
+    assert_eq!(call.intrinsic_value(21800.0), 300.0);
+    assert_eq!(call.intrinsic_value(21000.0), 0.0);
+    assert_eq!(put.intrinsic_value(21000.0), 500.0);
+
+    TestAssertions::assert_approx_eq(call.years_to_expiry(call.expiry - Duration::days(73)), 0.2, 1e-12);
+    assert_eq!(call.years_to_expiry(call.expiry + Duration::days(1)), 0.0);
+    assert!(!call.is_expired(call.expiry - Duration::seconds(1)));
+    assert!(call.is_expired(call.expiry));
+}
+
+#[rstest]
+fn test_grid_interpolates_smile_and_total_variance() {
+    let surface = grid();
+    surface.validate().unwrap();
+
+    // On the grid
+    TestAssertions::assert_approx_eq(surface.volatility(1.0, 30.0 / 365.0), 0.20, 1e-12);
+    // Halfway along the 30 day smile
+    TestAssertions::assert_approx_eq(surface.volatility(0.95, 30.0 / 365.0), 0.22, 1e-12);
+    // Total variance is linear between expiries
+    let expected = ((0.20f64.powi(2) * 30.0 + 0.16f64.powi(2) * 90.0) / 2.0 / 60.0).sqrt();
+    TestAssertions::assert_approx_eq(surface.volatility(1.0, 60.0 / 365.0), expected, 1e-12);
+    // Flat beyond the edges
+    TestAssertions::assert_approx_eq(surface.volatility(0.5, 1.0 / 365.0), 0.24, 1e-12);
+    TestAssertions::assert_approx_eq(surface.volatility(2.0, 2.0), 0.18, 1e-12);
+}
+
+#[rstest]
+#[case(VolSurface::Flat { volatility: 0.0 })]
+#[case(VolSurface::Flat { volatility: f64::NAN })]
+#[case(VolSurface::Grid { expiry_days: vec![], moneyness: vec![1.0], volatilities: vec![] })]
+#[case(VolSurface::Grid { expiry_days: vec![30.0, 30.0], moneyness: vec![1.0], volatilities: vec![vec![0.2], vec![0.2]] })]
+#[case(VolSurface::Grid { expiry_days: vec![30.0], moneyness: vec![0.9, 1.0], volatilities: vec![vec![0.2]] })]
+#[case(VolSurface::Grid { expiry_days: vec![30.0], moneyness: vec![1.0], volatilities: vec![vec![-0.2]] })]
+fn test_invalid_surfaces_rejected(#[case] surface: VolSurface) {
+    assert!(surface.validate().is_err());
+    assert!(OptionsConfig::default().with_surface("NIFTY", surface).validate().is_err());
+}
+
+#[rstest]
+fn test_mark_prices_unquoted_options_from_surface() {
+    let config = OptionsConfig::default().with_surface("NIFTY", VolSurface::Flat { volatility: 0.2 });
+    let pricer = OptionPricer::new(config, 0.065);
+    let now = utc(2024, 1, 4, 10);
+    let market = MarketSnapshotBuilder::new()
+        .with_timestamp(now)
+        .with_price("NIFTY", 21600.0)
+        .with_price("NIFTY24JAN21500PE", 42.0)
+        .build();
+
+    pricer.mark(&market, ["NIFTY24JAN21500CE", "NIFTY24JAN21500PE", "NIFTY23DEC21500CE", "BANKNIFTY24JAN47000CE", "AAPL"]);
+
+    let expected = BlackScholes::price(OptionType::Call, 21600.0, 21500.0, 0.065, 0.2, 21.0 / 365.0, 0.0);
+    TestAssertions::assert_approx_eq(*market.prices.get("NIFTY24JAN21500CE").unwrap(), expected, 1e-9);
+    // Quotes win over the model
+    assert_eq!(*market.prices.get("NIFTY24JAN21500PE").unwrap(), 42.0);
+    // Expired contracts and options without an underlying price stay unpriced
+    assert!(!market.prices.contains_key("NIFTY23DEC21500CE"));
+    assert!(!market.prices.contains_key("BANKNIFTY24JAN47000CE"));
+    assert!(!market.prices.contains_key("AAPL"));
+}
+
+#[rstest]
+fn test_configured_contracts_marked_without_positions() {
+    let config = OptionsConfig {
+        contracts: vec!["NIFTY24JAN22000CE".to_string()],
+        ..OptionsConfig::default()
+    };
+    let pricer = OptionPricer::new(config, 0.065);
+    let market = MarketSnapshotBuilder::new()
+        .with_timestamp(utc(2024, 1, 4, 10))
+        .with_price("NIFTY", 21600.0)
+        .build();
+
+    pricer.mark(&market, []);
+    assert!(*market.prices.get("NIFTY24JAN22000CE").unwrap() > 0.0);
+}
+
+#[rstest]
+fn test_exposure_weights_greeks_by_position() {
+    let pricer = OptionPricer::new(OptionsConfig::default(), 0.065);
+    let now = utc(2024, 1, 4, 10);
+    let market = MarketSnapshotBuilder::new()
+        .with_timestamp(now)
+        .with_price("NIFTY", 21600.0)
+        .with_price("AAPL", 190.0)
+        .build();
+    let position = |symbol: &str, quantity: f64| Position {
+        symbol: symbol.to_string(),
+        quantity,
+        ..Position::default()
+    };
+    let positions = [
+        position("NIFTY24JAN21500CE", 50.0),
+        position("NIFTY24JAN21500PE", -50.0),
+        position("NIFTY", -25.0),
+        position("AAPL", 10.0),
+    ];
+
+    let exposure = pricer.exposure(&positions, &market);
+    assert_eq!(exposure.keys().collect::<Vec<_>>(), ["NIFTY"]);
+
+    let call = OptionContract::parse("NIFTY24JAN21500CE", &OptionsConfig::default()).unwrap();
+    let put = OptionContract::parse("NIFTY24JAN21500PE", &OptionsConfig::default()).unwrap();
+    let call_greeks = pricer.unit_greeks(&call, 21600.0, now);
+    let put_greeks = pricer.unit_greeks(&put, 21600.0, now);
+    let nifty = exposure["NIFTY"];
+    TestAssertions::assert_approx_eq(nifty.delta, 50.0 * (call_greeks.delta - put_greeks.delta) - 25.0, 1e-9);
+    // Long and short options of the same strike cancel out in gamma and vega
+    TestAssertions::assert_approx_eq(nifty.gamma, 0.0, 1e-9);
+    TestAssertions::assert_approx_eq(nifty.vega, 0.0, 1e-9);
+    // A synthetic long forward has delta close to one per unit
+    TestAssertions::assert_approx_eq(call_greeks.delta - put_greeks.delta, 1.0, 1e-9);
+}
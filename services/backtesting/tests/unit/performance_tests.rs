@@ -2,6 +2,7 @@
 
 use rstest::*;
 use backtesting::*;
+use backtesting::commission::CostBreakdown;
 use chrono::{Utc, Duration};
 use crate::test_utils::*;
 
@@ -92,6 +93,7 @@ fn test_calculate_metrics_with_realistic_equity_curve() {
         commission: 10.0,
         slippage: 5.0,
         timestamp: base_time + Duration::days(1),
+        costs: CostBreakdown::default(),
     };
     portfolio.process_fill(&fill).unwrap();
     portfolio.record_equity(base_time + Duration::days(1)).unwrap();
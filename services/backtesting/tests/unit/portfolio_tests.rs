@@ -2,6 +2,7 @@
 
 use rstest::*;
 use backtesting::*;
+use backtesting::commission::CostBreakdown;
 use chrono::{Utc, Duration};
 use crate::test_utils::*;
 
@@ -42,6 +43,7 @@ fn test_process_buy_fill() {
         commission: 5.0,
         slippage: 2.0,
         timestamp: Utc::now(),
+        costs: CostBreakdown::default(),
     };
     
     let result = portfolio.process_fill(&fill);
@@ -78,6 +80,7 @@ fn test_process_sell_fill() {
         commission: 5.0,
         slippage: 2.0,
         timestamp: Utc::now(),
+        costs: CostBreakdown::default(),
     };
     portfolio.process_fill(&buy_fill).unwrap();
     
@@ -91,6 +94,7 @@ fn test_process_sell_fill() {
         commission: 3.0,
         slippage: 1.0,
         timestamp: Utc::now(),
+        costs: CostBreakdown::default(),
     };
     
     let result = portfolio.process_fill(&sell_fill);
@@ -125,6 +129,7 @@ fn test_complete_position_close() {
         commission: 5.0,
         slippage: 1.0,
         timestamp: Utc::now(),
+        costs: CostBreakdown::default(),
     };
     portfolio.process_fill(&buy_fill).unwrap();
     
@@ -138,6 +143,7 @@ fn test_complete_position_close() {
         commission: 5.0,
         slippage: 1.0,
         timestamp: Utc::now(),
+        costs: CostBreakdown::default(),
     };
     portfolio.process_fill(&sell_fill).unwrap();
     
@@ -164,6 +170,7 @@ fn test_multiple_buy_fills_average_price() {
         commission: 10.0,
         slippage: 5.0,
         timestamp: Utc::now(),
+        costs: CostBreakdown::default(),
     };
     portfolio.process_fill(&buy1).unwrap();
     
@@ -177,6 +184,7 @@ fn test_multiple_buy_fills_average_price() {
         commission: 15.0,
         slippage: 8.0,
         timestamp: Utc::now(),
+        costs: CostBreakdown::default(),
     };
     portfolio.process_fill(&buy2).unwrap();
     
@@ -210,6 +218,7 @@ fn test_insufficient_funds_buy() {
         commission: 100.0,
         slippage: 50.0,
         timestamp: Utc::now(),
+        costs: CostBreakdown::default(),
     };
     
     let result = portfolio.process_fill(&large_buy);
@@ -234,6 +243,7 @@ fn test_update_prices_unrealized_pnl() {
         commission: 5.0,
         slippage: 2.0,
         timestamp: Utc::now(),
+        costs: CostBreakdown::default(),
     };
     portfolio.process_fill(&buy_fill).unwrap();
     
@@ -268,6 +278,7 @@ fn test_update_prices_loss_scenario() {
         commission: 10.0,
         slippage: 5.0,
         timestamp: Utc::now(),
+        costs: CostBreakdown::default(),
     };
     portfolio.process_fill(&buy_fill).unwrap();
     
@@ -306,6 +317,7 @@ fn test_record_equity_curve() {
         commission: 5.0,
         slippage: 2.0,
         timestamp: Utc::now(),
+        costs: CostBreakdown::default(),
     };
     portfolio.process_fill(&buy_fill).unwrap();
     
@@ -364,6 +376,7 @@ fn test_multiple_symbols_portfolio() {
             commission: 10.0,
             slippage: 5.0,
             timestamp: Utc::now(),
+            costs: CostBreakdown::default(),
         };
         portfolio.process_fill(&fill).unwrap();
     }
@@ -402,6 +415,7 @@ fn test_portfolio_total_value_calculation() {
             commission: 5.0,
             slippage: 2.0,
             timestamp: Utc::now(),
+            costs: CostBreakdown::default(),
         },
         Fill {
             order_id: "buy_2".to_string(),
@@ -412,6 +426,7 @@ fn test_portfolio_total_value_calculation() {
             commission: 7.0,
             slippage: 3.0,
             timestamp: Utc::now(),
+            costs: CostBreakdown::default(),
         },
     ];
     
@@ -444,7 +459,7 @@ fn test_portfolio_total_value_calculation() {
 fn test_sell_without_position() {
     let portfolio = PortfolioTracker::new(100_000.0);
     
-    // Try to sell without having a position (short selling not implemented)
+    // Cash accounts cannot sell what they do not hold
     let sell_fill = Fill {
         order_id: "sell_1".to_string(),
         symbol: "NONEXISTENT".to_string(),
@@ -454,16 +469,42 @@ fn test_sell_without_position() {
         commission: 5.0,
         slippage: 2.0,
         timestamp: Utc::now(),
+        costs: CostBreakdown::default(),
     };
     
-    // This should complete without error (portfolio handles missing positions gracefully)
-    let result = portfolio.process_fill(&sell_fill);
-    assert!(result.is_ok(), "Portfolio should handle sell of non-existent position");
+    assert!(portfolio.process_fill(&sell_fill).is_err(), "Naked sell should be refused");
     
     let state = portfolio.get_current_state();
-    // Cash should increase from the sale proceeds
-    let expected_cash = 100_000.0 + 150.0 * 100.0 - 5.0 - 2.0;
-    TestAssertions::assert_approx_eq(state.cash, expected_cash, 0.01);
+    assert_eq!(state.cash, 100_000.0);
+    assert!(state.positions.is_empty());
+    assert!(portfolio.get_transactions().is_empty());
+}
+
+#[rstest]
+fn test_sell_beyond_position_refused() {
+    let portfolio = PortfolioTracker::new(100_000.0);
+    let fill = |side, quantity| Fill {
+        order_id: format!("{:?}_{}", side, quantity),
+        symbol: "AAPL".to_string(),
+        side,
+        quantity,
+        price: 100.0,
+        commission: 0.0,
+        slippage: 0.0,
+        timestamp: Utc::now(),
+        costs: CostBreakdown::default(),
+    };
+    portfolio.process_fill(&fill(OrderSide::Buy, 50.0)).unwrap();
+    let cash = portfolio.get_current_state().cash;
+    
+    assert!(portfolio.process_fill(&fill(OrderSide::Sell, 80.0)).is_err());
+    let state = portfolio.get_current_state();
+    assert_eq!(state.cash, cash);
+    assert_eq!(state.positions[0].quantity, 50.0);
+    
+    let trade = portfolio.process_fill(&fill(OrderSide::Sell, 50.0)).unwrap().unwrap();
+    assert_eq!(trade.quantity, 50.0);
+    assert!(portfolio.get_current_state().positions.is_empty());
 }
 
 #[rstest]
@@ -480,6 +521,7 @@ fn test_position_partial_sales() {
         commission: 10.0,
         slippage: 5.0,
         timestamp: Utc::now(),
+        costs: CostBreakdown::default(),
     };
     portfolio.process_fill(&buy_fill).unwrap();
     
@@ -496,6 +538,7 @@ fn test_position_partial_sales() {
             commission: 5.0,
             slippage: 2.0,
             timestamp: Utc::now(),
+            costs: CostBreakdown::default(),
         };
         portfolio.process_fill(&sell_fill).unwrap();
     }
@@ -531,6 +574,7 @@ fn test_final_state_consistency() {
         commission: 15.0,
         slippage: 8.0,
         timestamp: Utc::now(),
+        costs: CostBreakdown::default(),
     };
     portfolio.process_fill(&buy_fill).unwrap();
     
@@ -562,6 +606,7 @@ fn test_edge_case_zero_quantity_fill() {
         commission: 0.0,
         slippage: 0.0,
         timestamp: Utc::now(),
+        costs: CostBreakdown::default(),
     };
     
     // Should handle zero quantity gracefully
@@ -586,6 +631,7 @@ fn test_very_small_quantities() {
         commission: 0.01,
         slippage: 0.005,
         timestamp: Utc::now(),
+        costs: CostBreakdown::default(),
     };
     
     let result = portfolio.process_fill(&small_fill);
@@ -0,0 +1,158 @@
+//! Unit tests for Monte Carlo robustness analysis
+
+use rstest::*;
+use backtesting::*;
+use backtesting::robustness::*;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use crate::test_utils::*;
+
+fn trade(pnl: f64, day: i64) -> CompletedTrade {
+    let entry_time = Utc::now() - Duration::days(100 - day);
+    CompletedTrade {
+        entry_time,
+        exit_time: entry_time + Duration::hours(6),
+        symbol: "MC".to_string(),
+        side: OrderSide::Buy,
+        entry_price: 100.0,
+        exit_price: 100.0 + pnl / 10.0,
+        quantity: 10.0,
+        pnl,
+        return_pct: pnl / 1_000.0,
+    }
+}
+
+/// Daily equity curve growing by `daily_return` each day
+fn compounding_curve(days: i64, start: f64, daily_return: f64) -> Vec<(DateTime<Utc>, f64)> {
+    let first = Utc::now() - Duration::days(days);
+    (0..=days)
+        .map(|day| (first + Duration::days(day), start * (1.0 + daily_return).powi(day as i32)))
+        .collect()
+}
+
+fn result(equity_curve: Vec<(DateTime<Utc>, f64)>, trades: Vec<CompletedTrade>) -> BacktestResult {
+    BacktestResult {
+        metrics: PerformanceMetrics::default(),
+        equity_curve,
+        trades,
+        final_portfolio: PortfolioState { cash: 0.0, positions: vec![], total_value: 0.0 },
+        costs: Default::default(),
+        margin: Default::default(),
+        options: Default::default(),
+        benchmark: None,
+    }
+}
+
+fn analyzer(simulations: usize, seed: u64) -> MonteCarloAnalyzer {
+    MonteCarloAnalyzer::new(MonteCarloConfig { simulations, seed, ..MonteCarloConfig::default() })
+}
+
+#[rstest]
+fn test_identical_trades_give_a_single_outcome() {
+    let trades: Vec<_> = (0..10).map(|day| trade(100.0, day)).collect();
+    let summary = analyzer(200, 1).trade_bootstrap(&trades, 10_000.0, 0.0).unwrap();
+
+    assert_eq!(summary.simulations, 200);
+    TestAssertions::assert_approx_eq(summary.total_return.lower, 0.1, 1e-12);
+    TestAssertions::assert_approx_eq(summary.total_return.upper, 0.1, 1e-12);
+    assert_eq!(summary.max_drawdown.upper, 0.0);
+    assert_eq!(summary.probability_of_ruin, 0.0);
+}
+
+#[rstest]
+fn test_trade_bootstrap_intervals_are_ordered_and_centred() {
+    let pnls = [300.0, -200.0, 150.0, -100.0, 250.0, -50.0, 400.0, -300.0];
+    let trades: Vec<_> = pnls.iter().enumerate().map(|(day, &pnl)| trade(pnl, day as i64)).collect();
+    let summary = analyzer(2_000, 3).trade_bootstrap(&trades, 10_000.0, 0.0).unwrap();
+
+    for interval in [summary.total_return, summary.max_drawdown, summary.sharpe_ratio] {
+        assert!(interval.lower <= interval.median && interval.median <= interval.upper);
+        assert!(interval.lower < interval.upper, "Mixed trades should spread the outcomes");
+    }
+
+    // Each draw has expected P&L of 56.25, so the mean path returns about 4.5%
+    TestAssertions::assert_approx_eq(summary.total_return.mean, 0.045, 0.01);
+    assert!(summary.max_drawdown.lower >= 0.0);
+}
+
+#[rstest]
+fn test_probability_of_ruin_with_large_losses() {
+    let trades = vec![trade(4_000.0, 0), trade(-4_000.0, 1), trade(-4_000.0, 2), trade(4_000.0, 3)];
+    let summary = analyzer(1_000, 11).trade_bootstrap(&trades, 10_000.0, 0.0).unwrap();
+
+    assert!(summary.probability_of_ruin > 0.0 && summary.probability_of_ruin < 1.0);
+    assert!(summary.max_drawdown.upper >= 0.5);
+}
+
+#[rstest]
+fn test_wiped_out_paths_stop_at_zero_equity() {
+    let trades = vec![trade(-20_000.0, 0)];
+    let summary = analyzer(10, 0).trade_bootstrap(&trades, 10_000.0, 0.0).unwrap();
+
+    assert_eq!(summary.probability_of_ruin, 1.0);
+    assert_eq!(summary.total_return.median, -1.0);
+    assert_eq!(summary.max_drawdown.median, 1.0);
+}
+
+#[rstest]
+fn test_block_bootstrap_of_constant_returns() {
+    let report = analyzer(100, 5)
+        .analyze(&result(compounding_curve(30, 50_000.0, 0.01), vec![]), 0.0)
+        .unwrap();
+
+    assert!(report.trade_bootstrap.is_none());
+    let block = report.block_bootstrap.unwrap();
+    let expected = 1.01_f64.powi(30) - 1.0;
+    TestAssertions::assert_approx_eq(block.total_return.lower, expected, 1e-9);
+    TestAssertions::assert_approx_eq(block.total_return.upper, expected, 1e-9);
+    assert_eq!(block.max_drawdown.upper, 0.0);
+}
+
+#[rstest]
+fn test_analysis_is_reproducible_for_a_seed() {
+    TestRandom::reset();
+    let curve: Vec<_> = TestDataFactory::volatile_data(60, 100.0, 5.0)
+        .into_iter()
+        .map(|(timestamp, bar)| (timestamp, bar.close * 1_000.0))
+        .collect();
+    let trades: Vec<_> = (0..20).map(|day| trade(if day % 3 == 0 { -150.0 } else { 120.0 }, day)).collect();
+    let backtest = result(curve, trades);
+
+    let first = analyzer(300, 42).analyze(&backtest, 0.02).unwrap();
+    let second = analyzer(300, 42).analyze(&backtest, 0.02).unwrap();
+    let other = analyzer(300, 43).analyze(&backtest, 0.02).unwrap();
+
+    assert_eq!(first, second);
+    assert_ne!(first, other);
+}
+
+#[rstest]
+fn test_daily_returns_use_last_point_of_each_day() {
+    let day = Utc.with_ymd_and_hms(2024, 3, 1, 9, 15, 0).unwrap();
+    let curve = vec![
+        (day, 100.0),
+        (day + Duration::hours(1), 150.0),
+        (day + Duration::days(1), 110.0),
+        (day + Duration::days(2), 99.0),
+    ];
+    let returns = daily_returns(&curve);
+
+    // Intraday points only count when they close the day
+    assert_eq!(returns.len(), 2);
+    TestAssertions::assert_approx_eq(returns[0], 110.0 / 150.0 - 1.0, 1e-12);
+    TestAssertions::assert_approx_eq(returns[1], -0.1, 1e-12);
+}
+
+#[rstest]
+#[case(MonteCarloConfig { simulations: 0, ..MonteCarloConfig::default() })]
+#[case(MonteCarloConfig { block_days: 0, ..MonteCarloConfig::default() })]
+#[case(MonteCarloConfig { confidence_level: 1.0, ..MonteCarloConfig::default() })]
+#[case(MonteCarloConfig { ruin_threshold: 0.0, ..MonteCarloConfig::default() })]
+fn test_invalid_settings_are_rejected(#[case] config: MonteCarloConfig) {
+    let backtest = result(compounding_curve(10, 1_000.0, 0.0), vec![]);
+    assert!(MonteCarloAnalyzer::new(config).analyze(&backtest, 0.0).is_err());
+}
+
+#[rstest]
+fn test_empty_equity_curve_is_an_error() {
+    assert!(analyzer(10, 0).analyze(&result(vec![], vec![]), 0.0).is_err());
+}
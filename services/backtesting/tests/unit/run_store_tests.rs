@@ -0,0 +1,145 @@
+//! Unit tests for the run store and run comparisons
+
+use rstest::*;
+use backtesting::*;
+use backtesting::comparison::*;
+use backtesting::grpc_service::BacktestStatus;
+use backtesting::run_store::*;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use crate::test_utils::*;
+
+fn utc(day: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, day, 0, 0, 0).unwrap()
+}
+
+fn result(equity_curve: Vec<(DateTime<Utc>, f64)>, metrics: PerformanceMetrics) -> BacktestResult {
+    BacktestResult {
+        metrics,
+        equity_curve,
+        trades: vec![],
+        final_portfolio: PortfolioState { cash: 0.0, positions: vec![], total_value: 0.0 },
+        costs: Default::default(),
+        margin: Default::default(),
+        options: Default::default(),
+        benchmark: None,
+    }
+}
+
+fn record(backtest_id: &str, created_day: u32, result: Option<BacktestResult>) -> RunRecord {
+    RunRecord {
+        backtest_id: backtest_id.to_string(),
+        status: if result.is_some() { BacktestStatus::Completed } else { BacktestStatus::Failed },
+        message: String::new(),
+        config: TestConfigFactory::basic_config(),
+        strategy_code: r#"{"type": "buy_and_hold", "symbol": "TREND"}"#.to_string(),
+        symbols: vec!["TREND".to_string()],
+        code_version: CODE_VERSION.to_string(),
+        data: vec![],
+        created_at: utc(created_day),
+        completed_at: Some(utc(created_day) + Duration::minutes(1)),
+        result,
+    }
+}
+
+fn completed(backtest_id: &str, capital: f64, total_return: f64, max_drawdown: f64) -> RunRecord {
+    let metrics = PerformanceMetrics { total_return, max_drawdown, total_trades: 4, ..PerformanceMetrics::default() };
+    let curve = vec![(utc(1), capital), (utc(2), capital * (1.0 + total_return))];
+    record(backtest_id, 1, Some(result(curve, metrics)))
+}
+
+#[rstest]
+fn test_records_round_trip_through_store() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = RunStore::open(dir.path().join("runs")).unwrap();
+    let mut saved = completed("bt-1", 100_000.0, 0.1, 0.05);
+    saved.data = DataCoverage::of("TREND", &TestDataFactory::trending_up_data(10, 100.0)).into_iter().collect();
+    store.save(&saved).unwrap();
+
+    let loaded = store.load("bt-1").unwrap().unwrap();
+    assert_eq!(loaded.status, BacktestStatus::Completed);
+    assert_eq!(loaded.strategy_code, saved.strategy_code);
+    assert_eq!(loaded.code_version, CODE_VERSION);
+    assert_eq!(loaded.data, saved.data);
+    assert_eq!(loaded.data[0].bars, 10);
+    assert_eq!(loaded.data_range(), Some((saved.data[0].first_bar, saved.data[0].last_bar)));
+    assert_eq!(loaded.config.initial_capital, saved.config.initial_capital);
+    assert_eq!(loaded.result.unwrap().equity_curve, saved.result.unwrap().equity_curve);
+
+    assert!(store.load("bt-2").unwrap().is_none());
+}
+
+#[rstest]
+fn test_list_newest_first_and_skips_corrupt_records() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = RunStore::open(dir.path()).unwrap();
+    store.save(&record("old", 1, None)).unwrap();
+    store.save(&record("new", 3, None)).unwrap();
+    store.save(&record("middle", 2, None)).unwrap();
+    std::fs::write(dir.path().join("corrupt.json"), "{not json").unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+    let ids: Vec<_> = store.list().unwrap().into_iter().map(|record| record.backtest_id).collect();
+    assert_eq!(ids, ["new", "middle", "old"]);
+
+    // Saving again replaces the earlier record
+    store.save(&record("old", 4, None)).unwrap();
+    assert_eq!(store.list().unwrap()[0].backtest_id, "old");
+    assert_eq!(store.list().unwrap().len(), 3);
+
+    assert!(store.delete("old").unwrap());
+    assert!(!store.delete("old").unwrap());
+    assert_eq!(store.list().unwrap().len(), 2);
+}
+
+#[rstest]
+#[case("../escape")]
+#[case("a/b\\c")]
+#[case("run 1.json")]
+fn test_ids_cannot_leave_store_directory(#[case] backtest_id: &str) {
+    let dir = tempfile::tempdir().unwrap();
+    let store = RunStore::open(dir.path().join("runs")).unwrap();
+    store.save(&record(backtest_id, 1, None)).unwrap();
+
+    let files: Vec<_> = std::fs::read_dir(store.root()).unwrap().map(|entry| entry.unwrap().path()).collect();
+    assert_eq!(files.len(), 1);
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    assert_eq!(store.load(backtest_id).unwrap().unwrap().backtest_id, backtest_id);
+    assert!(store.save(&record("", 1, None)).is_err());
+}
+
+#[rstest]
+fn test_comparison_diffs_against_baseline() {
+    let runs = [
+        completed("baseline", 100_000.0, 0.10, 0.08),
+        completed("better", 50_000.0, 0.25, 0.04),
+        completed("worse", 100_000.0, -0.05, 0.20),
+    ];
+    let comparison = RunComparison::of(&runs).unwrap();
+    assert_eq!(comparison.backtest_ids, ["baseline", "better", "worse"]);
+
+    let total_return = comparison.metric("total_return").unwrap();
+    assert_eq!(total_return.values, [0.10, 0.25, -0.05]);
+    TestAssertions::assert_approx_eq(total_return.differences[1], 0.15, 1e-12);
+    TestAssertions::assert_approx_eq(total_return.differences[2], -0.15, 1e-12);
+    assert_eq!(total_return.differences[0], 0.0);
+    assert_eq!(total_return.best, Some(1));
+    // Lower drawdowns are better
+    assert_eq!(comparison.metric("max_drawdown").unwrap().best, Some(1));
+    // Trade counts have no better direction
+    assert_eq!(comparison.metric("total_trades").unwrap().best, None);
+
+    // Curves start at 1.0 whatever the capital
+    for (curve, run) in comparison.equity_curves.iter().zip(&runs) {
+        assert_eq!(curve.backtest_id, run.backtest_id);
+        assert_eq!(curve.points[0].1, 1.0);
+    }
+    TestAssertions::assert_approx_eq(comparison.equity_curves[1].points[1].1, 1.25, 1e-12);
+}
+
+#[rstest]
+fn test_comparison_requires_results() {
+    assert!(RunComparison::of(&[]).is_err());
+    let runs = [completed("done", 100_000.0, 0.1, 0.05), record("failed", 1, None)];
+    let error = RunComparison::of(&runs).unwrap_err();
+    assert!(error.to_string().contains("failed"));
+}
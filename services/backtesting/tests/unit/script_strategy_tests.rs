@@ -0,0 +1,180 @@
+//! Unit tests for sandboxed scripted strategies
+
+use rstest::*;
+use backtesting::*;
+use backtesting::commission::CostBreakdown;
+use backtesting::lifecycle::*;
+use backtesting::strategies::{ScriptLimits, ScriptStrategy, StrategySpec};
+use chrono::Utc;
+use serde_json::json;
+use crate::test_utils::*;
+
+fn empty_portfolio() -> PortfolioState {
+    PortfolioState { cash: 10_000.0, positions: vec![], total_value: 10_000.0 }
+}
+
+fn script(source: &str) -> ScriptStrategy {
+    ScriptStrategy::new(source, &serde_json::Map::new(), ScriptLimits::default()).unwrap()
+}
+
+/// Runs `hook` against a market where NIFTY trades at 100 and returns the commands issued
+fn run_hook(strategy: &mut ScriptStrategy, hook: impl FnOnce(&mut ScriptStrategy, &mut StrategyContext<'_>)) -> Vec<OrderCommand> {
+    let market = MarketSnapshotBuilder::new().with_price("NIFTY", 100.0).build();
+    let portfolio = empty_portfolio();
+    let mut ctx = StrategyContext::new(&market, &portfolio, &[]);
+    hook(strategy, &mut ctx);
+    ctx.into_commands()
+}
+
+#[rstest]
+fn test_script_orders_are_placed_under_returned_ids() {
+    let mut strategy = script(r#"
+        fn on_bar(ctx) {
+            let price = ctx.price("NIFTY");
+            this.entry = ctx.buy_limit("NIFTY", 10, price - 1.0);
+            let exit = ctx.sell("NIFTY", 2.5);
+            ctx.amend(this.entry, (), price - 2.0);
+            ctx.cancel(exit);
+        }
+    "#);
+    let commands = run_hook(&mut strategy, |strategy, ctx| strategy.on_bar(ctx));
+
+    assert!(strategy.take_error().is_none());
+    assert_eq!(commands.len(), 4);
+    let entry = match &commands[0] {
+        OrderCommand::Place(order) => {
+            assert_eq!((order.side, order.order_type), (OrderSide::Buy, OrderType::Limit));
+            assert_eq!((order.quantity, order.price), (10.0, Some(99.0)));
+            order.id.clone()
+        }
+        other => panic!("Expected a placement, got {:?}", other),
+    };
+    let exit = match &commands[1] {
+        OrderCommand::Place(order) => {
+            assert_eq!((order.side, order.order_type, order.quantity), (OrderSide::Sell, OrderType::Market, 2.5));
+            order.id.clone()
+        }
+        other => panic!("Expected a placement, got {:?}", other),
+    };
+    assert!(matches!(&commands[2], OrderCommand::Amend { order_id, quantity: None, price: Some(p) } if *order_id == entry && *p == 98.0));
+    assert!(matches!(&commands[3], OrderCommand::Cancel { order_id } if *order_id == exit));
+}
+
+#[rstest]
+fn test_state_persists_between_hooks_and_resets_on_start() {
+    let mut strategy = ScriptStrategy::new(
+        r#"
+            fn on_start(ctx) { this.bars = 0; }
+            fn on_bar(ctx) {
+                this.bars += 1;
+                if this.bars == ctx.params.every { ctx.buy("NIFTY", 1); this.bars = 0; }
+            }
+        "#,
+        json!({"every": 2}).as_object().unwrap(),
+        ScriptLimits::default(),
+    )
+    .unwrap();
+
+    let placed: Vec<usize> = (0..2)
+        .flat_map(|_| {
+            run_hook(&mut strategy, |strategy, ctx| strategy.on_start(ctx));
+            (0..3).map(|_| run_hook(&mut strategy, |strategy, ctx| strategy.on_bar(ctx)).len()).collect::<Vec<_>>()
+        })
+        .collect();
+    assert_eq!(placed, [0, 1, 0, 0, 1, 0]);
+    assert!(strategy.take_error().is_none());
+}
+
+#[rstest]
+fn test_fills_and_rejections_are_passed_as_maps() {
+    let mut strategy = script(r#"
+        fn on_fill(fill, ctx) { ctx.sell(fill.symbol, fill.quantity); }
+        fn on_order_rejected(rejection, ctx) { ctx.cancel(rejection.order_id); }
+    "#);
+    let fill = Fill {
+        order_id: "BT_1".to_string(),
+        symbol: "NIFTY".to_string(),
+        side: OrderSide::Buy,
+        quantity: 3.0,
+        price: 100.0,
+        commission: 0.0,
+        slippage: 0.0,
+        timestamp: Utc::now(),
+        costs: CostBreakdown::default(),
+    };
+    let rejection = OrderRejection { order_id: "BT_2".to_string(), reason: RejectReason::Simulated, timestamp: Utc::now() };
+
+    let on_fill = run_hook(&mut strategy, |strategy, ctx| strategy.on_fill(&fill, ctx));
+    assert!(matches!(&on_fill[..], [OrderCommand::Place(order)] if order.side == OrderSide::Sell && order.quantity == 3.0));
+    let on_rejected = run_hook(&mut strategy, |strategy, ctx| strategy.on_order_rejected(&rejection, ctx));
+    assert!(matches!(&on_rejected[..], [OrderCommand::Cancel { order_id }] if order_id == "BT_2"));
+    // Hooks the script leaves out do nothing
+    assert!(run_hook(&mut strategy, |strategy, ctx| strategy.on_bar(ctx)).is_empty());
+}
+
+#[rstest]
+#[case::runaway_loop("fn on_bar(ctx) { loop { ctx.buy(\"NIFTY\", 1); } }", "Too many operations")]
+#[case::deep_recursion("fn deeper(n) { deeper(n + 1) } fn on_bar(ctx) { deeper(0); }", "Stack overflow")]
+#[case::huge_string("fn on_bar(ctx) { let s = \"x\"; loop { s += s; } }", "Length of string")]
+#[case::bad_quantity("fn on_bar(ctx) { ctx.buy(\"NIFTY\", -5); }", "quantity must be positive")]
+#[case::bad_price("fn on_bar(ctx) { ctx.buy_limit(\"NIFTY\", 1, \"cheap\"); }", "price must be a number")]
+fn test_sandbox_violations_fail_the_hook(#[case] source: &str, #[case] message: &str) {
+    let limits = ScriptLimits { max_operations: 10_000, ..Default::default() };
+    let mut strategy = ScriptStrategy::new(source, &serde_json::Map::new(), limits).unwrap();
+    let commands = run_hook(&mut strategy, |strategy, ctx| strategy.on_bar(ctx));
+
+    assert!(commands.is_empty());
+    let error = strategy.take_error().expect("hook should fail");
+    assert!(error.to_string().contains(message), "{:#}", error);
+    assert!(strategy.take_error().is_none());
+}
+
+#[rstest]
+#[case::syntax("fn on_bar(ctx) { let x = ; }")]
+#[case::top_level_code("let x = 1; fn on_bar(ctx) {}")]
+#[case::wrong_arity("fn on_fill(ctx) {}")]
+#[case::no_hooks("fn helper() {}")]
+#[case::eval("fn on_bar(ctx) { eval(\"1\"); }")]
+fn test_invalid_scripts_are_refused(#[case] source: &str) {
+    assert!(ScriptStrategy::new(source, &serde_json::Map::new(), ScriptLimits::default()).is_err());
+}
+
+#[rstest]
+fn test_script_spec_builds_strategy() {
+    let spec = StrategySpec::parse(
+        r#"{"type": "script", "source": "fn on_bar(ctx) {}", "symbols": ["NIFTY"], "limits": {"max_operations": 500}}"#,
+    )
+    .unwrap();
+    assert_eq!(spec.symbols(), vec!["NIFTY".to_string()]);
+    match &spec {
+        StrategySpec::Script { limits, parameters, .. } => {
+            assert_eq!(*limits, ScriptLimits { max_operations: 500, ..Default::default() });
+            assert!(parameters.is_empty());
+        }
+        other => panic!("Expected a script spec, got {:?}", other),
+    }
+    assert!(spec.build().is_ok());
+
+    let broken = StrategySpec::parse(r#"{"type": "script", "source": "fn on_bar(", "symbols": ["NIFTY"]}"#).unwrap();
+    assert!(broken.build().is_err());
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_engine_runs_script_and_fails_on_script_error() {
+    let source = r#"
+        fn on_bar(ctx) {
+            if ctx.position("TEST") == 0.0 && ctx.open_orders.is_empty() { ctx.buy("TEST", 10); }
+        }
+    "#;
+    let engine = BacktestEngine::new(TestConfigFactory::basic_config());
+    engine.load_data("TEST", TestDataFactory::trending_up_data(10, 100.0)).await.unwrap();
+    let result = engine.run(&mut script(source)).await.unwrap();
+    let position = result.final_portfolio.positions.iter().find(|p| p.symbol == "TEST").unwrap();
+    assert_eq!(position.quantity, 10.0);
+
+    let engine = BacktestEngine::new(TestConfigFactory::basic_config());
+    engine.load_data("TEST", TestDataFactory::trending_up_data(10, 100.0)).await.unwrap();
+    let error = engine.run(&mut script("fn on_bar(ctx) { throw \"boom\"; }")).await.unwrap_err();
+    assert!(format!("{:#}", error).contains("boom"), "{:#}", error);
+}
@@ -0,0 +1,110 @@
+//! Unit tests for tearsheet series and exports
+
+use rstest::*;
+use backtesting::*;
+use backtesting::tearsheet::*;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use crate::test_utils::*;
+
+fn utc(month: u32, day: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, month, day, 0, 0, 0).unwrap()
+}
+
+fn trade(return_pct: f64) -> CompletedTrade {
+    let entry_time = utc(1, 2);
+    CompletedTrade {
+        entry_time,
+        exit_time: entry_time + Duration::hours(6),
+        symbol: "TS".to_string(),
+        side: OrderSide::Buy,
+        entry_price: 100.0,
+        exit_price: 100.0 * (1.0 + return_pct),
+        quantity: 10.0,
+        pnl: 1_000.0 * return_pct,
+        return_pct,
+    }
+}
+
+fn result(equity_curve: Vec<(DateTime<Utc>, f64)>, trades: Vec<CompletedTrade>) -> BacktestResult {
+    BacktestResult {
+        metrics: PerformanceMetrics { total_return: 0.1, sharpe_ratio: 1.5, ..PerformanceMetrics::default() },
+        equity_curve,
+        trades,
+        final_portfolio: PortfolioState { cash: 0.0, positions: vec![], total_value: 0.0 },
+        costs: Default::default(),
+        margin: Default::default(),
+        options: Default::default(),
+        benchmark: None,
+    }
+}
+
+#[rstest]
+fn test_drawdown_measured_from_running_peak() {
+    let curve = vec![(utc(1, 1), 100.0), (utc(1, 2), 120.0), (utc(1, 3), 90.0), (utc(1, 4), 130.0)];
+    let drawdown: Vec<f64> = drawdown_series(&curve).into_iter().map(|(_, value)| value).collect();
+    assert_eq!(drawdown[..2], [0.0, 0.0]);
+    TestAssertions::assert_approx_eq(drawdown[2], -0.25, 1e-12);
+    assert_eq!(drawdown[3], 0.0);
+}
+
+#[rstest]
+fn test_monthly_returns_chain_month_ends() {
+    let curve = vec![
+        (utc(1, 2), 100.0),
+        (utc(1, 31), 110.0),
+        (utc(2, 15), 90.0),
+        (utc(2, 29), 99.0),
+        (utc(4, 1), 118.8),
+    ];
+    let returns = monthly_returns(&curve);
+    let months: Vec<_> = returns.iter().map(|r| (r.year, r.month)).collect();
+    assert_eq!(months, [(2024, 1), (2024, 2), (2024, 4)]);
+    TestAssertions::assert_approx_eq(returns[0].return_pct, 0.10, 1e-12);
+    TestAssertions::assert_approx_eq(returns[1].return_pct, -0.10, 1e-12);
+    TestAssertions::assert_approx_eq(returns[2].return_pct, 0.20, 1e-12);
+    assert!(monthly_returns(&[]).is_empty());
+}
+
+#[rstest]
+fn test_trade_distribution_bins_every_trade() {
+    let trades: Vec<_> = [-0.04, -0.01, 0.0, 0.02, 0.06].into_iter().map(trade).collect();
+    let distribution = TradeDistribution::of(&trades, 5);
+
+    assert_eq!(distribution.bins.len(), 5);
+    assert_eq!(distribution.bins.iter().map(|bin| bin.count).collect::<Vec<_>>(), [1, 1, 1, 1, 1]);
+    assert_eq!((distribution.bins[0].lower, distribution.bins[4].upper), (-0.04, 0.06));
+    assert_eq!((distribution.winning, distribution.losing), (2, 2));
+    assert_eq!((distribution.worst, distribution.best), (-0.04, 0.06));
+    TestAssertions::assert_approx_eq(distribution.mean, 0.006, 1e-12);
+
+    // Identical returns fall in a single bin
+    let same = TradeDistribution::of(&[trade(0.01), trade(0.01)], 5);
+    assert_eq!(same.bins.len(), 1);
+    assert_eq!(same.bins[0].count, 2);
+    assert!(TradeDistribution::of(&[], 5).bins.is_empty());
+}
+
+#[rstest]
+fn test_tearsheet_exports_json_and_html() {
+    let config = TestConfigFactory::basic_config();
+    let curve = vec![(utc(1, 2), 100_000.0), (utc(1, 31), 104_000.0), (utc(2, 29), 110_000.0)];
+    let tearsheet = Tearsheet::new("<run & co>", &config, &result(curve, vec![trade(0.03), trade(-0.01)]));
+    assert_eq!(tearsheet.final_equity, 110_000.0);
+    assert_eq!(tearsheet.monthly_returns.len(), 2);
+
+    let json: serde_json::Value = serde_json::from_str(&tearsheet.to_json().unwrap()).unwrap();
+    assert_eq!(json["backtest_id"], "<run & co>");
+    assert_eq!(json["equity"].as_array().unwrap().len(), 3);
+    assert_eq!(json["trade_distribution"]["winning"], 1);
+
+    let html = tearsheet.to_html();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("Backtest &lt;run &amp; co&gt;"));
+    assert!(!html.contains("<run & co>"));
+    for section in ["Equity", "Drawdown", "Monthly returns", "Trade returns"] {
+        assert!(html.contains(&format!("<h2>{}</h2>", section)), "Missing {}", section);
+    }
+    assert_eq!(html.matches("<svg").count(), 3);
+    // Self-contained: nothing loaded from elsewhere
+    assert!(!html.contains("<script") && !html.contains("<link") && !html.contains("src="));
+}
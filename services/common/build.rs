@@ -11,6 +11,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 "../../proto/trading.proto",
                 "../../proto/backtesting.proto",
                 "../../proto/secrets.proto",
+                "../../proto/orderbook.proto",
             ],
             &["../../proto"],
         )?;
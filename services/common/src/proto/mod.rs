@@ -93,9 +93,26 @@ pub use backtesting::v1::{
     GetBacktestResultsRequest, GetBacktestResultsResponse,
     StopBacktestRequest, StopBacktestResponse,
     ListBacktestsRequest, ListBacktestsResponse,
+    CompareBacktestsRequest, CompareBacktestsResponse,
+    ExportTearsheetRequest, ExportTearsheetResponse,
     PerformanceMetrics, EquityPoint, Trade, BacktestSummary,
 };
 
+/// Order book service protobuf definitions
+pub mod orderbook {
+    /// Version 1 of the order book service API
+    #[allow(missing_docs)]
+    #[allow(missing_debug_implementations)]
+    pub mod v1 {
+        tonic::include_proto!("shrivenquant.orderbook.v1");
+    }
+}
+
+pub use orderbook::v1::{
+    order_book_service_client::OrderBookServiceClient,
+    order_book_service_server::{OrderBookService, OrderBookServiceServer},
+};
+
 /// Secrets management service protobuf definitions
 pub mod secrets {
     /// Version 1 of the secrets service API
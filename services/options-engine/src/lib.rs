@@ -10,7 +10,7 @@
 //! - Multi-mode execution (Backtest/Simulation/Paper/Live)
 //! - Zerodha integration for Indian markets
 
-use chrono::{DateTime, Utc, Datelike};
+use chrono::{DateTime, Datelike, NaiveDate, Utc, Weekday};
 use serde::{Deserialize, Serialize};
 use rustc_hash::FxHashMap;
 use std::sync::Arc;
@@ -85,35 +85,129 @@ impl IndexOption {
         Ok(expiries)
     }
     
-    /// Parse option symbol with error context
+    /// Weekday monthly contracts of the index expire on, for the month starting `month_start`
+    /// 
+    /// Follows NSE's changes of expiry day: Bank Nifty moved to Wednesday in
+    /// March 2024, all monthlies moved to Thursday in January 2025 and to
+    /// Tuesday in September 2025.
+    pub fn monthly_expiry_weekday(&self, month_start: NaiveDate) -> Weekday {
+        // (first month the weekday applies, weekday), latest first
+        let schedule: &[((i32, u32), Weekday)] = match self {
+            IndexOption::Nifty50 => &[((2025, 9), Weekday::Tue), ((0, 1), Weekday::Thu)],
+            IndexOption::BankNifty => &[
+                ((2025, 9), Weekday::Tue),
+                ((2025, 1), Weekday::Thu),
+                ((2024, 3), Weekday::Wed),
+                ((0, 1), Weekday::Thu),
+            ],
+            IndexOption::FinNifty => &[((2025, 9), Weekday::Tue), ((2025, 1), Weekday::Thu), ((0, 1), Weekday::Tue)],
+            IndexOption::MidCapNifty => &[
+                ((2025, 9), Weekday::Tue),
+                ((2025, 1), Weekday::Thu),
+                ((2024, 1), Weekday::Mon),
+                ((0, 1), Weekday::Tue),
+            ],
+        };
+        let month = (month_start.year(), month_start.month());
+        schedule
+            .iter()
+            .find(|(from, _)| *from <= month)
+            .map_or(Weekday::Thu, |(_, weekday)| *weekday)
+    }
+    
+    /// Expiry date of the index's monthly contract for `month` of `year`
+    /// 
+    /// The last expiry weekday of the month, moved to the previous trading
+    /// day when it falls on one of `holidays`.
+    pub fn monthly_expiry(&self, year: i32, month: u32, holidays: &[NaiveDate]) -> Option<NaiveDate> {
+        let weekday = self.monthly_expiry_weekday(NaiveDate::from_ymd_opt(year, month, 1)?);
+        let mut date = Self::last_weekday(year, month, weekday)?;
+        while holidays.contains(&date) || matches!(date.weekday(), Weekday::Sat | Weekday::Sun) {
+            date = date.pred_opt()?;
+        }
+        Some(date)
+    }
+    
+    /// Parse an NSE option trading symbol
+    /// 
+    /// Accepts monthly contracts such as `NIFTY24JAN25000CE`, which expire on
+    /// the date given by `monthly_expiry`, and weekly contracts such as
+    /// `NIFTY2411825000CE` (year, month as 1-9/O/N/D, day). The expiry is
+    /// returned at the 15:30 IST close.
     pub fn parse_symbol(symbol: &str) -> Result<(IndexOption, OptionType, f64, DateTime<Utc>)> {
-        // Example: NIFTY24JAN25000CE
-        let parts: Vec<&str> = symbol.split(&['2', 'C', 'P'][..]).collect();
+        Self::parse_symbol_with_holidays(symbol, &[])
+    }
+    
+    /// Parse an NSE option trading symbol, moving monthly expiries off `holidays`
+    pub fn parse_symbol_with_holidays(
+        symbol: &str,
+        holidays: &[NaiveDate],
+    ) -> Result<(IndexOption, OptionType, f64, DateTime<Utc>)> {
+        let (body, option_type) = if let Some(body) = symbol.strip_suffix("CE") {
+            (body, OptionType::Call)
+        } else if let Some(body) = symbol.strip_suffix("PE") {
+            (body, OptionType::Put)
+        } else {
+            return Err(anyhow::anyhow!("Invalid option type")).context("Option type must be CE or PE");
+        };
         
-        let index = match parts.get(0).context("Missing index in symbol")? {
-            &"NIFTY" => IndexOption::Nifty50,
-            &"BANKNIFTY" => IndexOption::BankNifty,
-            &"FINNIFTY" => IndexOption::FinNifty,
+        let root_len = body.find(|c: char| c.is_ascii_digit()).context("Missing expiry in symbol")?;
+        let index = match &body[..root_len] {
+            "NIFTY" => IndexOption::Nifty50,
+            "BANKNIFTY" => IndexOption::BankNifty,
+            "FINNIFTY" => IndexOption::FinNifty,
+            "MIDCPNIFTY" => IndexOption::MidCapNifty,
             _ => return Err(anyhow::anyhow!("Unknown index")),
         };
         
-        let option_type = if symbol.contains("CE") {
-            OptionType::Call
-        } else if symbol.contains("PE") {
-            OptionType::Put
-        } else {
-            return Err(anyhow::anyhow!("Invalid option type")).context("Option type must be CE or PE")?;
+        let rest = &body[root_len..];
+        let year = 2000 + rest.get(..2).context("Missing expiry year")?.parse::<i32>().context("Invalid expiry year")?;
+        let rest = &rest[2..];
+        let (expiry_date, strike_str) = match rest.get(..3).and_then(Self::month_from_abbreviation) {
+            Some(month) => (index.monthly_expiry(year, month, holidays).context("Invalid expiry month")?, &rest[3..]),
+            None => {
+                let month = match rest.chars().next().context("Missing expiry month")? {
+                    digit @ '1'..='9' => digit as u32 - '0' as u32,
+                    'O' => 10,
+                    'N' => 11,
+                    'D' => 12,
+                    _ => return Err(anyhow::anyhow!("Invalid expiry month")),
+                };
+                let day = rest.get(1..3).context("Missing expiry day")?.parse::<u32>().context("Invalid expiry day")?;
+                let date = NaiveDate::from_ymd_opt(year, month, day).context("Invalid expiry date")?;
+                (date, &rest[3..])
+            }
         };
         
         // Parse strike price
-        let strike_str = parts.get(2).context("Missing strike price")?;
         let strike = strike_str.parse::<f64>().context("Invalid strike price format")?;
+        if strike <= 0.0 {
+            return Err(anyhow::anyhow!("Strike price must be positive"));
+        }
         
-        // Parse expiry date (simplified for example)
-        let expiry = Utc::now(); // Would parse actual date from symbol
+        // 15:30 IST is 10:00 UTC
+        let expiry = expiry_date.and_hms_opt(10, 0, 0).context("Invalid expiry time")?.and_utc();
         
         Ok((index, option_type, strike, expiry))
     }
+    
+    fn month_from_abbreviation(month: &str) -> Option<u32> {
+        const MONTHS: [&str; 12] = ["JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC"];
+        MONTHS.iter().position(|m| *m == month).map(|index| index as u32 + 1)
+    }
+    
+    fn last_weekday(year: i32, month: u32, weekday: Weekday) -> Option<NaiveDate> {
+        let next_month = if month == 12 {
+            NaiveDate::from_ymd_opt(year + 1, 1, 1)
+        } else {
+            NaiveDate::from_ymd_opt(year, month + 1, 1)
+        }?;
+        let mut date = next_month.pred_opt()?;
+        while date.weekday() != weekday {
+            date = date.pred_opt()?;
+        }
+        Some(date)
+    }
 }
 
 /// Complete Greeks for option pricing
@@ -0,0 +1,110 @@
+//! Benchmarks comparing order book implementations
+//!
+//! Runs the same workloads against the `BTreeMap` backed `OrderBook` and
+//! the array-backed `LadderBook` through the common `PriceBook` trait.
+
+use criterion::{BenchmarkId, Criterion, black_box, criterion_group, criterion_main};
+use orderbook::core::Order;
+use orderbook::{LadderBook, OrderBook, PriceBook, Side};
+use services_common::{Px, Qty, Ts};
+
+/// Mid price of the generated flow (100.0000)
+const MID: i64 = 1_000_000;
+/// Tick size of the generated flow (0.0500)
+const TICK: i64 = 500;
+/// Orders resting in the book before each measured operation
+const RESTING_ORDERS: u64 = 2_000;
+
+fn order(id: u64, side: Side, ticks_from_mid: i64) -> Order {
+    let offset = (ticks_from_mid + 1) * TICK;
+    let price = match side {
+        Side::Bid => MID - offset,
+        Side::Ask => MID + offset,
+    };
+    Order {
+        id,
+        price: Px::from_i64(price),
+        quantity: Qty::from_i64(10_000 + (id as i64 % 50) * 100),
+        original_quantity: Qty::from_i64(10_000),
+        timestamp: Ts::from_nanos(id),
+        side,
+        is_iceberg: false,
+        visible_quantity: None,
+    }
+}
+
+/// Orders spread over 200 ticks either side of the mid, denser near the top
+fn flow(count: u64) -> Vec<Order> {
+    (0..count)
+        .map(|id| {
+            let side = if id % 2 == 0 { Side::Bid } else { Side::Ask };
+            let distance = ((id * 7919) % 200) as i64;
+            order(id + 1, side, distance * distance / 200)
+        })
+        .collect()
+}
+
+fn books() -> Vec<(&'static str, Box<dyn Fn() -> Box<dyn PriceBook>>)> {
+    vec![
+        ("btree", Box::new(|| Box::new(OrderBook::new("BENCH")) as Box<dyn PriceBook>)),
+        ("ladder", Box::new(|| Box::new(LadderBook::new("BENCH", Px::from_i64(TICK), 512)) as Box<dyn PriceBook>)),
+    ]
+}
+
+fn bench_add_cancel(c: &mut Criterion) {
+    let mut group = c.benchmark_group("add_cancel");
+    let resting = flow(RESTING_ORDERS);
+    for (name, make) in books() {
+        let book = make();
+        for order in &resting {
+            book.add_order(order.clone());
+        }
+        let mut id = RESTING_ORDERS + 1;
+        group.bench_function(BenchmarkId::from_parameter(name), |b| {
+            b.iter(|| {
+                let side = if id % 2 == 0 { Side::Bid } else { Side::Ask };
+                book.add_order(order(id, side, (id % 20) as i64));
+                black_box(book.cancel_order(id));
+                id += 1;
+            });
+        });
+    }
+    group.finish();
+}
+
+fn bench_set_level(c: &mut Criterion) {
+    let mut group = c.benchmark_group("set_level");
+    let resting = flow(RESTING_ORDERS);
+    for (name, make) in books() {
+        let book = make();
+        for order in &resting {
+            book.add_order(order.clone());
+        }
+        let mut step = 0i64;
+        group.bench_function(BenchmarkId::from_parameter(name), |b| {
+            b.iter(|| {
+                let price = Px::from_i64(MID - (step % 50 + 1) * TICK);
+                book.set_level(Side::Bid, price, Qty::from_i64(5_000 + step % 1_000), 3);
+                step += 1;
+            });
+        });
+    }
+    group.finish();
+}
+
+fn bench_queries(c: &mut Criterion) {
+    let mut group = c.benchmark_group("queries");
+    let resting = flow(RESTING_ORDERS);
+    for (name, make) in books() {
+        let book = make();
+        for order in &resting {
+            book.add_order(order.clone());
+        }
+        group.bench_function(BenchmarkId::new("bbo", name), |b| b.iter(|| black_box(book.get_bbo())));
+        group.bench_function(BenchmarkId::new("depth_10", name), |b| b.iter(|| black_box(book.get_depth(10))));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_add_cancel, bench_set_level, bench_queries);
+criterion_main!(benches);
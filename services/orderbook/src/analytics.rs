@@ -10,7 +10,13 @@
 //! - Order Flow Toxicity
 //! - Realized Spread
 //! - Implementation Shortfall
+//! - Multi-level Order Flow Imbalance (Cont-Kukanov-Stoikov OFI)
+//! - Microprice
+//! - Effective spread, realized spread and price impact per trade
+//! - Multi-horizon markouts
 
+use crate::core::OrderBook;
+use crate::events::{OrderBookEvent, TradeEvent};
 use services_common::{Px, Qty, Ts};
 use std::collections::VecDeque;
 use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
@@ -481,4 +487,561 @@ impl ToxicityDetector {
     pub fn get_toxicity(&self) -> f64 {
         self.toxicity_score.load(Ordering::Acquire) as f64
     }
-}
\ No newline at end of file
+}
+
+/// Configuration for order flow analytics
+#[derive(Debug, Clone)]
+pub struct OrderFlowConfig {
+    /// Book levels tracked for OFI
+    pub ofi_levels: usize,
+    /// Rolling window for all metrics in nanoseconds
+    pub window_ns: u64,
+    /// Horizon for realized spread and price impact in nanoseconds
+    pub realized_horizon_ns: u64,
+    /// Markout horizons in nanoseconds
+    pub markout_horizons_ns: Vec<u64>,
+}
+
+impl Default for OrderFlowConfig {
+    fn default() -> Self {
+        Self {
+            ofi_levels: 5,
+            window_ns: 60_000_000_000,
+            realized_horizon_ns: 5_000_000_000,
+            markout_horizons_ns: vec![100_000_000, 1_000_000_000, 5_000_000_000, 30_000_000_000],
+        }
+    }
+}
+
+/// Volume-weighted trade costs over the rolling window, in basis points of the mid
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TradeCostSummary {
+    /// Trades with an effective spread in the window
+    pub trades: usize,
+    /// Mean effective spread, `2 * D * (P - M0)`
+    pub effective_spread_bps: f64,
+    /// Trades whose realized horizon elapsed in the window
+    pub resolved: usize,
+    /// Mean realized spread, `2 * D * (P - Mh)`
+    pub realized_spread_bps: f64,
+    /// Mean price impact, `2 * D * (Mh - M0)`
+    pub price_impact_bps: f64,
+}
+
+/// Mean markout at one horizon over the rolling window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Markout {
+    /// Horizon after the trade in nanoseconds
+    pub horizon_ns: u64,
+    /// Trades marked out in the window
+    pub trades: usize,
+    /// Volume-weighted mid move from the trade price in the aggressor's
+    /// direction, in basis points
+    pub mean_bps: f64,
+}
+
+/// Weighted values within a time window
+#[derive(Debug, Default)]
+struct RollingWindow {
+    entries: VecDeque<(u64, f64, f64)>,
+    weighted_sum: f64,
+    weight: f64,
+}
+
+impl RollingWindow {
+    fn push(&mut self, time: u64, value: f64, weight: f64) {
+        self.entries.push_back((time, value, weight));
+        self.weighted_sum = value.mul_add(weight, self.weighted_sum);
+        self.weight += weight;
+    }
+
+    /// Drops entries older than `cutoff`
+    fn evict(&mut self, cutoff: u64) {
+        while let Some(&(time, value, weight)) = self.entries.front() {
+            if time >= cutoff {
+                break;
+            }
+            self.entries.pop_front();
+            self.weighted_sum = (-value).mul_add(weight, self.weighted_sum);
+            self.weight -= weight;
+        }
+        if self.entries.is_empty() {
+            // Clear accumulated rounding error
+            self.weighted_sum = 0.0;
+            self.weight = 0.0;
+        }
+    }
+
+    const fn sum(&self) -> f64 {
+        self.weighted_sum
+    }
+
+    fn mean(&self) -> f64 {
+        if self.weight > 0.0 { self.weighted_sum / self.weight } else { 0.0 }
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+/// Trade awaiting the mid at its later horizons
+#[derive(Debug)]
+struct PendingTrade {
+    time: u64,
+    price: f64,
+    mid: f64,
+    /// +1 for buyer-initiated, -1 for seller-initiated
+    direction: f64,
+    weight: f64,
+    /// Markout horizons already resolved, in order
+    markouts_done: usize,
+    realized_done: bool,
+}
+
+/// Price levels of one side as `(price, quantity)` from best outward
+type Levels = Vec<(Px, Qty)>;
+
+#[derive(Debug, Default)]
+struct FlowState {
+    bids: Levels,
+    asks: Levels,
+    ofi: Vec<RollingWindow>,
+    effective: RollingWindow,
+    realized: RollingWindow,
+    impact: RollingWindow,
+    markouts: Vec<RollingWindow>,
+    pending: VecDeque<PendingTrade>,
+    /// Mid of the last two-sided book, kept while the book is one-sided
+    last_mid: Option<f64>,
+}
+
+impl FlowState {
+    fn mid(&self) -> Option<f64> {
+        let bid = self.bids.first()?.0.as_i64();
+        let ask = self.asks.first()?.0.as_i64();
+        (bid < ask).then(|| (bid + ask) as f64 / 2.0)
+    }
+
+    fn set_levels(&mut self, bids: Levels, asks: Levels) {
+        self.bids = bids;
+        self.asks = asks;
+        if let Some(mid) = self.mid() {
+            self.last_mid = Some(mid);
+        }
+    }
+}
+
+/// Order flow and trade cost analytics computed incrementally from book events
+///
+/// Feed every event with the book it has just been applied to. OFI
+/// accumulates the Cont-Kukanov-Stoikov flow at each of the top levels;
+/// trades are measured against the prevailing mid for effective spread and,
+/// once each horizon passes, against the mid then prevailing for realized
+/// spread, price impact and markouts. A horizon that passes while the book
+/// is one-sided is scored against the last two-sided mid.
+#[derive(Debug)]
+pub struct OrderFlowAnalytics {
+    config: OrderFlowConfig,
+    horizons: Vec<u64>,
+    state: RwLock<FlowState>,
+}
+
+impl Default for OrderFlowAnalytics {
+    fn default() -> Self {
+        Self::new(OrderFlowConfig::default())
+    }
+}
+
+impl OrderFlowAnalytics {
+    /// Create order flow analytics
+    #[must_use] pub fn new(config: OrderFlowConfig) -> Self {
+        let mut horizons = config.markout_horizons_ns.clone();
+        horizons.sort_unstable();
+        horizons.dedup();
+        let state = FlowState {
+            ofi: (0..config.ofi_levels).map(|_| RollingWindow::default()).collect(),
+            markouts: horizons.iter().map(|_| RollingWindow::default()).collect(),
+            ..FlowState::default()
+        };
+        Self { config, horizons, state: RwLock::new(state) }
+    }
+
+    /// Update with an event already applied to `book`
+    pub fn process_event(&self, event: &OrderBookEvent, book: &OrderBook) {
+        let now = event.exchange_time().as_nanos();
+        let mut state = self.state.write();
+        // Horizons that passed before this event see the mid it replaces
+        self.resolve_pending(&mut state, now, false);
+
+        match event {
+            OrderBookEvent::Trade(trade) => Self::record_trade(&mut state, trade),
+            OrderBookEvent::Snapshot(_) => {
+                // A snapshot may follow a gap, so its difference is not flow
+                let (bids, asks) = self.top_levels(book);
+                state.set_levels(bids, asks);
+            }
+            OrderBookEvent::Order(_) | OrderBookEvent::Delta(_) => self.record_depth(&mut state, book, now),
+            OrderBookEvent::Market(_) => {}
+        }
+        self.evict(&mut state, now);
+    }
+
+    /// Resolve horizons up to `now` with the current mid, for when no events arrive
+    pub fn advance(&self, now: Ts) {
+        let mut state = self.state.write();
+        self.resolve_pending(&mut state, now.as_nanos(), true);
+        self.evict(&mut state, now.as_nanos());
+    }
+
+    /// OFI over the window summed across the top `depth` levels, in quantity units
+    ///
+    /// Positive values mean net buying pressure from bids joining or asks leaving.
+    pub fn ofi(&self, depth: usize) -> f64 {
+        self.state.read().ofi.iter().take(depth).map(RollingWindow::sum).sum()
+    }
+
+    /// OFI over the window at each tracked level, best first
+    pub fn ofi_by_level(&self) -> Vec<f64> {
+        self.state.read().ofi.iter().map(RollingWindow::sum).collect()
+    }
+
+    /// Size-weighted mid of the top of book, leaning towards the side with less quantity
+    pub fn microprice(&self) -> Option<Px> {
+        let state = self.state.read();
+        let (bid, bid_qty) = *state.bids.first()?;
+        let (ask, ask_qty) = *state.asks.first()?;
+        let total = i128::from(bid_qty.as_i64()) + i128::from(ask_qty.as_i64());
+        if total <= 0 {
+            return None;
+        }
+        let weighted = i128::from(bid.as_i64()) * i128::from(ask_qty.as_i64())
+            + i128::from(ask.as_i64()) * i128::from(bid_qty.as_i64());
+        i64::try_from(weighted / total).ok().map(Px::from_i64)
+    }
+
+    /// Trade costs over the window
+    pub fn trade_costs(&self) -> TradeCostSummary {
+        let state = self.state.read();
+        TradeCostSummary {
+            trades: state.effective.len(),
+            effective_spread_bps: state.effective.mean(),
+            resolved: state.realized.len(),
+            realized_spread_bps: state.realized.mean(),
+            price_impact_bps: state.impact.mean(),
+        }
+    }
+
+    /// Markouts over the window at each configured horizon, shortest first
+    pub fn markouts(&self) -> Vec<Markout> {
+        let state = self.state.read();
+        self.horizons
+            .iter()
+            .zip(&state.markouts)
+            .map(|(&horizon_ns, window)| Markout { horizon_ns, trades: window.len(), mean_bps: window.mean() })
+            .collect()
+    }
+
+    fn top_levels(&self, book: &OrderBook) -> (Levels, Levels) {
+        let (bids, asks) = book.get_depth(self.config.ofi_levels);
+        let strip = |levels: Vec<(Px, Qty, u64)>| levels.into_iter().map(|(price, qty, _)| (price, qty)).collect();
+        (strip(bids), strip(asks))
+    }
+
+    fn record_depth(&self, state: &mut FlowState, book: &OrderBook, now: u64) {
+        let (bids, asks) = self.top_levels(book);
+        for (level, window) in state.ofi.iter_mut().enumerate() {
+            let bid_flow = level_flow(state.bids.get(level), bids.get(level), true);
+            let ask_flow = level_flow(state.asks.get(level), asks.get(level), false);
+            let flow = bid_flow - ask_flow;
+            if flow != 0 {
+                window.push(now, Qty::from_i64(flow).as_f64(), 1.0);
+            }
+        }
+        state.set_levels(bids, asks);
+    }
+
+    fn record_trade(state: &mut FlowState, trade: &TradeEvent) {
+        let Some(mid) = state.last_mid else { return };
+        let time = trade.exchange_time.as_nanos();
+        let price = trade.price.as_i64() as f64;
+        let direction = if trade.aggressor_side.is_buy() { 1.0 } else { -1.0 };
+        let weight = trade.quantity.as_f64();
+        state.effective.push(time, 2.0 * direction * (price - mid) / mid * 10_000.0, weight);
+        state.pending.push_back(PendingTrade {
+            time,
+            price,
+            mid,
+            direction,
+            weight,
+            markouts_done: 0,
+            realized_done: false,
+        });
+    }
+
+    /// Resolves horizons ending before `now`, or at `now` if `inclusive`
+    ///
+    /// Trades are only recorded against a two-sided book, so a last mid
+    /// exists whenever any are pending.
+    fn resolve_pending(&self, state: &mut FlowState, now: u64, inclusive: bool) {
+        let Some(mid) = state.last_mid else { return };
+        let elapsed = |trade: &PendingTrade, horizon: u64| {
+            let end = trade.time.saturating_add(horizon);
+            end < now || (inclusive && end == now)
+        };
+        let FlowState { pending, realized, impact, markouts, .. } = state;
+        for trade in pending.iter_mut() {
+            while let Some(&horizon) = self.horizons.get(trade.markouts_done) {
+                if !elapsed(trade, horizon) {
+                    break;
+                }
+                let markout = trade.direction * (mid - trade.price) / trade.price * 10_000.0;
+                if let Some(window) = markouts.get_mut(trade.markouts_done) {
+                    window.push(now, markout, trade.weight);
+                }
+                trade.markouts_done += 1;
+            }
+            if !trade.realized_done && elapsed(trade, self.config.realized_horizon_ns) {
+                realized.push(now, 2.0 * trade.direction * (trade.price - mid) / trade.mid * 10_000.0, trade.weight);
+                impact.push(now, 2.0 * trade.direction * (mid - trade.mid) / trade.mid * 10_000.0, trade.weight);
+                trade.realized_done = true;
+            }
+        }
+        let horizons = self.horizons.len();
+        pending.retain(|trade| !trade.realized_done || trade.markouts_done < horizons);
+    }
+
+    fn evict(&self, state: &mut FlowState, now: u64) {
+        let cutoff = now.saturating_sub(self.config.window_ns);
+        for window in state.ofi.iter_mut().chain(&mut state.markouts) {
+            window.evict(cutoff);
+        }
+        state.effective.evict(cutoff);
+        state.realized.evict(cutoff);
+        state.impact.evict(cutoff);
+    }
+}
+
+/// OFI contribution of one level: quantity added at an improved or unchanged
+/// price counts as inflow, quantity at a price that moved away as outflow
+fn level_flow(before: Option<&(Px, Qty)>, after: Option<&(Px, Qty)>, is_bid: bool) -> i64 {
+    match (before, after) {
+        (None, None) => 0,
+        (None, Some(&(_, qty))) => qty.as_i64(),
+        (Some(&(_, qty)), None) => -qty.as_i64(),
+        (Some(&(old_price, old_qty)), Some(&(new_price, new_qty))) => {
+            let improved = if is_bid { new_price > old_price } else { new_price < old_price };
+            if new_price == old_price {
+                new_qty.as_i64() - old_qty.as_i64()
+            } else if improved {
+                new_qty.as_i64()
+            } else {
+                -old_qty.as_i64()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Side as BookSide;
+    use crate::events::{OrderBookDelta, OrderBookSnapshot, Side};
+    use services_common::Symbol;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    fn snapshot(time: u64) -> OrderBookEvent {
+        OrderBookEvent::Snapshot(OrderBookSnapshot {
+            symbol: Symbol::new(1),
+            bids: Vec::new(),
+            asks: Vec::new(),
+            sequence: 1,
+            exchange_time: Ts::from_nanos(time),
+            local_time: Ts::from_nanos(time),
+            checksum: 0,
+        })
+    }
+
+    fn delta(time: u64) -> OrderBookEvent {
+        OrderBookEvent::Delta(OrderBookDelta {
+            symbol: Symbol::new(1),
+            bid_updates: Vec::new(),
+            ask_updates: Vec::new(),
+            bid_deletions: Vec::new(),
+            ask_deletions: Vec::new(),
+            prev_sequence: 1,
+            sequence: 2,
+            exchange_time: Ts::from_nanos(time),
+            local_time: Ts::from_nanos(time),
+        })
+    }
+
+    fn trade(time: u64, price: i64, side: Side) -> OrderBookEvent {
+        OrderBookEvent::Trade(TradeEvent {
+            trade_id: 1,
+            price: Px::from_i64(price),
+            quantity: Qty::from_units(1),
+            aggressor_side: side,
+            maker_order_id: None,
+            taker_order_id: None,
+            exchange_time: Ts::from_nanos(time),
+            local_time: Ts::from_nanos(time),
+            sequence: 3,
+        })
+    }
+
+    fn quote(book: &OrderBook, bid: (i64, i64), ask: (i64, i64)) {
+        book.load_snapshot(
+            vec![(Px::from_i64(bid.0), Qty::from_units(bid.1), 1)],
+            vec![(Px::from_i64(ask.0), Qty::from_units(ask.1), 1)],
+        );
+    }
+
+    #[test]
+    fn test_ofi_accumulates_level_flow() {
+        let analytics = OrderFlowAnalytics::default();
+        let book = OrderBook::new("TEST");
+        quote(&book, (1_000_000, 10), (1_010_000, 10));
+        analytics.process_event(&snapshot(0), &book);
+        assert!(analytics.ofi(1).abs() < f64::EPSILON);
+
+        // Bid grows at the same price: inflow of 5
+        book.set_level(BookSide::Bid, Px::from_i64(1_000_000), Qty::from_units(15), 1);
+        analytics.process_event(&delta(1), &book);
+        assert!((analytics.ofi(1) - 5.0).abs() < 1e-9);
+
+        // Best ask lifted and replaced at a worse price: ask outflow of 10
+        quote(&book, (1_000_000, 15), (1_020_000, 8));
+        analytics.process_event(&delta(2), &book);
+        assert!((analytics.ofi(1) - 15.0).abs() < 1e-9);
+        assert!((analytics.ofi_by_level()[0] - 15.0).abs() < 1e-9);
+
+        // Flow leaves the window
+        analytics.advance(Ts::from_nanos(120 * SECOND));
+        assert!(analytics.ofi(5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_microprice_leans_towards_thin_side() {
+        let analytics = OrderFlowAnalytics::default();
+        let book = OrderBook::new("TEST");
+        quote(&book, (1_000_000, 30), (1_020_000, 10));
+        analytics.process_event(&snapshot(0), &book);
+        assert_eq!(analytics.microprice(), Some(Px::from_i64(1_015_000)));
+    }
+
+    #[test]
+    fn test_spread_decomposition_and_markouts() {
+        let analytics = OrderFlowAnalytics::new(OrderFlowConfig {
+            markout_horizons_ns: vec![5 * SECOND, SECOND / 2],
+            ..OrderFlowConfig::default()
+        });
+        let book = OrderBook::new("TEST");
+        quote(&book, (990_000, 10), (1_010_000, 10));
+        analytics.process_event(&snapshot(0), &book);
+
+        analytics.process_event(&trade(0, 1_010_000, Side::Buy), &book);
+        let costs = analytics.trade_costs();
+        assert_eq!(costs.trades, 1);
+        assert!((costs.effective_spread_bps - 200.0).abs() < 1e-9);
+        assert_eq!(costs.resolved, 0);
+
+        // The half-second markout sees the mid before this update
+        quote(&book, (1_010_000, 10), (1_030_000, 10));
+        analytics.process_event(&delta(SECOND), &book);
+        analytics.advance(Ts::from_nanos(6 * SECOND));
+
+        let costs = analytics.trade_costs();
+        assert_eq!(costs.resolved, 1);
+        assert!((costs.realized_spread_bps + 200.0).abs() < 1e-9);
+        assert!((costs.price_impact_bps - 400.0).abs() < 1e-9);
+
+        let markouts = analytics.markouts();
+        assert_eq!(markouts.len(), 2);
+        assert_eq!(markouts[0].horizon_ns, SECOND / 2);
+        assert!((markouts[0].mean_bps + 10_000.0 / 101.0).abs() < 1e-9);
+        assert!((markouts[1].mean_bps - 10_000.0 / 101.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_ofi_tracks_each_level() {
+        let analytics = OrderFlowAnalytics::default();
+        let book = OrderBook::new("TEST");
+        book.load_snapshot(
+            vec![(Px::from_i64(1_000_000), Qty::from_units(10), 1), (Px::from_i64(990_000), Qty::from_units(10), 1)],
+            vec![(Px::from_i64(1_010_000), Qty::from_units(10), 1), (Px::from_i64(1_020_000), Qty::from_units(10), 1)],
+        );
+        analytics.process_event(&snapshot(0), &book);
+
+        // Second bid level grows by 6
+        book.set_level(BookSide::Bid, Px::from_i64(990_000), Qty::from_units(16), 1);
+        analytics.process_event(&delta(1), &book);
+        // Second ask level shrinks by 6, also buying pressure
+        book.set_level(BookSide::Ask, Px::from_i64(1_020_000), Qty::from_units(4), 1);
+        analytics.process_event(&delta(2), &book);
+
+        let by_level = analytics.ofi_by_level();
+        assert_eq!(by_level.len(), 5);
+        assert!(by_level[0].abs() < f64::EPSILON);
+        assert!((by_level[1] - 12.0).abs() < 1e-9);
+        assert!(analytics.ofi(1).abs() < f64::EPSILON);
+        assert!((analytics.ofi(2) - 12.0).abs() < 1e-9);
+        assert!((analytics.ofi(5) - 12.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_one_sided_book_scores_with_last_mid() {
+        let analytics = OrderFlowAnalytics::new(OrderFlowConfig {
+            markout_horizons_ns: vec![SECOND / 2],
+            ..OrderFlowConfig::default()
+        });
+        let book = OrderBook::new("TEST");
+        quote(&book, (990_000, 10), (1_010_000, 10));
+        analytics.process_event(&snapshot(0), &book);
+        analytics.process_event(&trade(0, 1_010_000, Side::Buy), &book);
+
+        // The asks empty out before the realized horizon passes
+        book.load_snapshot(vec![(Px::from_i64(1_000_000), Qty::from_units(10), 1)], Vec::new());
+        analytics.process_event(&delta(SECOND), &book);
+        assert_eq!(analytics.trade_costs().resolved, 0);
+
+        // The book only turns two-sided again well after the horizon
+        quote(&book, (1_090_000, 10), (1_110_000, 10));
+        analytics.process_event(&delta(10 * SECOND), &book);
+        analytics.advance(Ts::from_nanos(11 * SECOND));
+
+        let costs = analytics.trade_costs();
+        assert_eq!(costs.resolved, 1);
+        assert!((costs.realized_spread_bps - 200.0).abs() < 1e-9);
+        assert!(costs.price_impact_bps.abs() < 1e-9);
+        assert!((analytics.markouts()[0].mean_bps + 10_000.0 / 101.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_pending_trades_leave_once_every_horizon_resolves() {
+        let analytics = OrderFlowAnalytics::new(OrderFlowConfig {
+            realized_horizon_ns: 2 * SECOND,
+            markout_horizons_ns: vec![SECOND, 3 * SECOND],
+            ..OrderFlowConfig::default()
+        });
+        let pending = || analytics.state.read().pending.len();
+        let book = OrderBook::new("TEST");
+        quote(&book, (990_000, 10), (1_010_000, 10));
+        analytics.process_event(&snapshot(0), &book);
+        analytics.process_event(&trade(0, 1_010_000, Side::Buy), &book);
+        analytics.process_event(&trade(0, 990_000, Side::Sell), &book);
+        assert_eq!(pending(), 2);
+
+        analytics.advance(Ts::from_nanos(2 * SECOND));
+        assert_eq!(pending(), 2, "The three-second markout is still outstanding");
+
+        analytics.process_event(&trade(2 * SECOND + SECOND / 2, 1_010_000, Side::Buy), &book);
+        analytics.advance(Ts::from_nanos(3 * SECOND));
+        assert_eq!(pending(), 1, "Only the later trade is left");
+        assert_eq!(analytics.markouts()[1].trades, 2);
+
+        analytics.advance(Ts::from_nanos(6 * SECOND));
+        assert_eq!(pending(), 0);
+    }
+}
@@ -0,0 +1,658 @@
+//! Call auctions for the opening and closing sessions
+//!
+//! During the call phase an `AuctionBook` accumulates limit and market
+//! orders without matching them, and keeps an indicative equilibrium price
+//! up to date. The equilibrium follows the NSE pre-open rules:
+//!
+//! 1. the price with the largest executable volume;
+//! 2. among those, the price leaving the smallest unmatched quantity;
+//! 3. among those, the price closest to the reference price (previous
+//!    close for the opening, last trade for the closing), or the reference
+//!    price itself if it lies midway between the two closest prices.
+//!
+//! If only market orders are present on both sides the reference price is
+//! used. Uncrossing executes the matched volume at the equilibrium price,
+//! market orders first and then limit orders by price and time priority,
+//! and returns the unmatched remainder for continuous trading.
+
+use crate::core::{Order, OrderBook};
+use crate::events::{Side, TradeEvent};
+use ahash::AHashMap;
+use anyhow::{Result, bail};
+use services_common::{Px, Qty, Ts};
+use std::collections::BTreeMap;
+use tokio::sync::broadcast;
+use tracing::{debug, info};
+
+/// Indicative price updates buffered per subscriber
+const INDICATIVE_CHANNEL_CAPACITY: usize = 1024;
+
+/// Order entered during the call phase
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuctionOrder {
+    /// Order identifier
+    pub id: u64,
+    /// Side of the order
+    pub side: Side,
+    /// Limit price, or `None` for a market order
+    pub price: Option<Px>,
+    /// Remaining quantity
+    pub quantity: Qty,
+    /// Entry time
+    pub time: Ts,
+}
+
+impl AuctionOrder {
+    /// Whether the order would execute at `price`
+    #[must_use] pub fn accepts(&self, price: Px) -> bool {
+        match (self.side, self.price) {
+            (_, None) => true,
+            (Side::Buy, Some(limit)) => limit >= price,
+            (Side::Sell, Some(limit)) => limit <= price,
+        }
+    }
+}
+
+/// Phase of the auction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuctionPhase {
+    /// Accepting orders; nothing executes
+    Call,
+    /// Uncrossed; no more orders are accepted
+    Uncrossed,
+}
+
+/// Equilibrium price and the volume it would execute
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Equilibrium {
+    /// Equilibrium price
+    pub price: Px,
+    /// Quantity that executes at the price
+    pub matched: Qty,
+    /// Buy quantity willing to trade at the price
+    pub buy_quantity: Qty,
+    /// Sell quantity willing to trade at the price
+    pub sell_quantity: Qty,
+}
+
+impl Equilibrium {
+    /// Quantity left unmatched at the price
+    #[must_use] pub const fn imbalance(&self) -> i64 {
+        (self.buy_quantity.as_i64() - self.sell_quantity.as_i64()).abs()
+    }
+
+    /// Side with unmatched quantity, if any
+    #[must_use] pub const fn surplus_side(&self) -> Option<Side> {
+        let demand = self.buy_quantity.as_i64();
+        let supply = self.sell_quantity.as_i64();
+        if demand > supply {
+            Some(Side::Buy)
+        } else if supply > demand {
+            Some(Side::Sell)
+        } else {
+            None
+        }
+    }
+}
+
+/// Indicative equilibrium published during the call phase
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndicativeUpdate {
+    /// Symbol of the auction
+    pub symbol: String,
+    /// Time of the order that changed the indicative price
+    pub time: Ts,
+    /// Indicative equilibrium, or `None` if the book does not cross
+    pub equilibrium: Option<Equilibrium>,
+}
+
+/// Outcome of uncrossing an auction
+#[derive(Debug, Clone)]
+pub struct AuctionResult {
+    /// Equilibrium the auction executed at, or `None` if nothing crossed
+    pub equilibrium: Option<Equilibrium>,
+    /// Executions at the equilibrium price
+    pub trades: Vec<TradeEvent>,
+    /// Unmatched orders in time priority, with market orders converted to
+    /// limit orders at the equilibrium (or reference) price
+    pub residual: Vec<AuctionOrder>,
+}
+
+impl AuctionResult {
+    /// Total quantity executed
+    #[must_use] pub fn volume(&self) -> Qty {
+        self.trades.iter().fold(Qty::ZERO, |total, trade| total.add(trade.quantity))
+    }
+
+    /// Add the unmatched orders to a book for continuous trading
+    pub fn load_residual(&self, book: &OrderBook) {
+        for order in &self.residual {
+            let Some(price) = order.price else { continue };
+            book.add_order(Order {
+                id: order.id,
+                price,
+                quantity: order.quantity,
+                original_quantity: order.quantity,
+                timestamp: order.time,
+                side: match order.side {
+                    Side::Buy => crate::core::Side::Bid,
+                    Side::Sell => crate::core::Side::Ask,
+                },
+                is_iceberg: false,
+                visible_quantity: None,
+            });
+        }
+    }
+}
+
+/// Order book for one call auction
+#[derive(Debug)]
+pub struct AuctionBook {
+    symbol: String,
+    /// Previous close for the opening auction, last trade for the closing
+    reference_price: Px,
+    phase: AuctionPhase,
+    /// Orders by arrival, which breaks ties in time priority
+    orders: BTreeMap<u64, AuctionOrder>,
+    /// Arrival number of each order by identifier
+    arrivals: AHashMap<u64, u64>,
+    next_arrival: u64,
+    indicative: Option<Equilibrium>,
+    updates: broadcast::Sender<IndicativeUpdate>,
+    next_trade_id: u64,
+}
+
+impl AuctionBook {
+    /// Create an auction in the call phase around a reference price
+    #[must_use] pub fn new(symbol: impl Into<String>, reference_price: Px) -> Self {
+        Self {
+            symbol: symbol.into(),
+            reference_price,
+            phase: AuctionPhase::Call,
+            orders: BTreeMap::new(),
+            arrivals: AHashMap::new(),
+            next_arrival: 0,
+            indicative: None,
+            updates: broadcast::channel(INDICATIVE_CHANNEL_CAPACITY).0,
+            next_trade_id: 1,
+        }
+    }
+
+    /// Symbol of the auction
+    #[must_use] pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    /// Current phase
+    #[must_use] pub const fn phase(&self) -> AuctionPhase {
+        self.phase
+    }
+
+    /// Reference price used to break ties
+    #[must_use] pub const fn reference_price(&self) -> Px {
+        self.reference_price
+    }
+
+    /// Indicative equilibrium of the orders entered so far
+    #[must_use] pub const fn indicative(&self) -> Option<Equilibrium> {
+        self.indicative
+    }
+
+    /// Number of orders entered and not cancelled
+    #[must_use] pub fn order_count(&self) -> usize {
+        self.orders.len()
+    }
+
+    /// Subscribe to indicative price updates
+    #[must_use] pub fn subscribe(&self) -> broadcast::Receiver<IndicativeUpdate> {
+        self.updates.subscribe()
+    }
+
+    /// Enter an order
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the auction has uncrossed, the identifier is in
+    /// use or the quantity is not positive.
+    pub fn submit(&mut self, order: AuctionOrder) -> Result<()> {
+        self.ensure_call_phase()?;
+        if order.quantity.as_i64() <= 0 {
+            bail!("Auction order {} has non-positive quantity", order.id);
+        }
+        if self.arrivals.contains_key(&order.id) {
+            bail!("Auction order {} already entered", order.id);
+        }
+        let time = order.time;
+        let arrival = self.next_arrival;
+        self.next_arrival += 1;
+        self.arrivals.insert(order.id, arrival);
+        self.orders.insert(arrival, order);
+        self.refresh_indicative(time);
+        Ok(())
+    }
+
+    /// Cancel an order, returning it
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the auction has uncrossed or the order is unknown.
+    pub fn cancel(&mut self, order_id: u64, time: Ts) -> Result<AuctionOrder> {
+        self.ensure_call_phase()?;
+        let Some(order) = self.arrivals.remove(&order_id).and_then(|arrival| self.orders.remove(&arrival)) else {
+            bail!("Auction order {} not found", order_id);
+        };
+        self.refresh_indicative(time);
+        Ok(order)
+    }
+
+    /// Change an order's price or quantity; it keeps its time priority
+    /// only if the quantity is reduced at the same price
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the auction has uncrossed, the order is unknown
+    /// or the quantity is not positive.
+    pub fn modify(&mut self, order_id: u64, price: Option<Px>, quantity: Qty, time: Ts) -> Result<()> {
+        self.ensure_call_phase()?;
+        if quantity.as_i64() <= 0 {
+            bail!("Auction order {} has non-positive quantity", order_id);
+        }
+        let Some((mut arrival, mut order)) = self
+            .arrivals
+            .get(&order_id)
+            .and_then(|&arrival| Some((arrival, self.orders.remove(&arrival)?)))
+        else {
+            bail!("Auction order {} not found", order_id);
+        };
+        let keeps_priority = order.price == price && quantity <= order.quantity;
+        order.quantity = quantity;
+        if !keeps_priority {
+            order.price = price;
+            order.time = time;
+            arrival = self.next_arrival;
+            self.next_arrival += 1;
+            self.arrivals.insert(order_id, arrival);
+        }
+        self.orders.insert(arrival, order);
+        self.refresh_indicative(time);
+        Ok(())
+    }
+
+    /// End the call phase and execute at the equilibrium price
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the auction has already uncrossed.
+    pub fn uncross(&mut self, time: Ts) -> Result<AuctionResult> {
+        self.ensure_call_phase()?;
+        self.phase = AuctionPhase::Uncrossed;
+
+        let equilibrium = self.equilibrium();
+        let (trades, fills) = equilibrium.map_or_else(
+            || (Vec::new(), AHashMap::new()),
+            |equilibrium| self.allocate(&equilibrium, time),
+        );
+
+        let residual_price = equilibrium.map_or(self.reference_price, |equilibrium| equilibrium.price);
+        let residual = std::mem::take(&mut self.orders)
+            .into_values()
+            .filter_map(|mut order| {
+                let filled = fills.get(&order.id).copied().unwrap_or(Qty::ZERO);
+                order.quantity = order.quantity.sub(filled);
+                if order.quantity.is_zero() {
+                    return None;
+                }
+                order.price.get_or_insert(residual_price);
+                Some(order)
+            })
+            .collect();
+        self.arrivals.clear();
+
+        if let Some(equilibrium) = &equilibrium {
+            info!(
+                "{} auction uncrossed at {:.4}: {} trades, volume {:.2}",
+                self.symbol,
+                equilibrium.price.as_f64(),
+                trades.len(),
+                equilibrium.matched.as_f64(),
+            );
+        } else {
+            info!("{} auction uncrossed with no equilibrium", self.symbol);
+        }
+        Ok(AuctionResult { equilibrium, trades, residual })
+    }
+
+    /// Equilibrium price of the orders entered so far
+    #[must_use] pub fn equilibrium(&self) -> Option<Equilibrium> {
+        let mut market_buy = 0i64;
+        let mut market_sell = 0i64;
+        let mut buys_at: BTreeMap<Px, i64> = BTreeMap::new();
+        let mut sells_at: BTreeMap<Px, i64> = BTreeMap::new();
+        for order in self.orders.values() {
+            let quantity = order.quantity.as_i64();
+            match (order.side, order.price) {
+                (Side::Buy, None) => market_buy += quantity,
+                (Side::Sell, None) => market_sell += quantity,
+                (Side::Buy, Some(price)) => *buys_at.entry(price).or_default() += quantity,
+                (Side::Sell, Some(price)) => *sells_at.entry(price).or_default() += quantity,
+            }
+        }
+
+        let mut prices: Vec<Px> = buys_at.keys().chain(sells_at.keys()).copied().collect();
+        prices.sort_unstable();
+        prices.dedup();
+        if prices.is_empty() {
+            // Market orders only: they execute at the reference price
+            let matched = market_buy.min(market_sell);
+            return (matched > 0).then(|| Equilibrium {
+                price: self.reference_price,
+                matched: Qty::from_i64(matched),
+                buy_quantity: Qty::from_i64(market_buy),
+                sell_quantity: Qty::from_i64(market_sell),
+            });
+        }
+
+        // Cumulative demand at each price counts buys limited at or above it,
+        // supply counts sells limited at or below it
+        let mut demand = vec![market_buy; prices.len()];
+        let mut running = 0;
+        for (index, price) in prices.iter().enumerate().rev() {
+            running += buys_at.get(price).copied().unwrap_or(0);
+            demand[index] += running;
+        }
+        let mut supply = vec![market_sell; prices.len()];
+        running = 0;
+        for (index, price) in prices.iter().enumerate() {
+            running += sells_at.get(price).copied().unwrap_or(0);
+            supply[index] += running;
+        }
+
+        let candidates: Vec<Equilibrium> = prices
+            .iter()
+            .zip(demand.iter().zip(&supply))
+            .map(|(&price, (&buy, &sell))| Equilibrium {
+                price,
+                matched: Qty::from_i64(buy.min(sell)),
+                buy_quantity: Qty::from_i64(buy),
+                sell_quantity: Qty::from_i64(sell),
+            })
+            .collect();
+
+        let max_matched = candidates.iter().map(|candidate| candidate.matched).max()?;
+        if max_matched.is_zero() {
+            return None;
+        }
+        let min_imbalance = candidates
+            .iter()
+            .filter(|candidate| candidate.matched == max_matched)
+            .map(Equilibrium::imbalance)
+            .min()?;
+        let tied: Vec<&Equilibrium> = candidates
+            .iter()
+            .filter(|candidate| candidate.matched == max_matched && candidate.imbalance() == min_imbalance)
+            .collect();
+
+        let reference = self.reference_price.as_i64();
+        let distance = |candidate: &&Equilibrium| (candidate.price.as_i64() - reference).abs();
+        let closest = tied.iter().map(distance).min()?;
+        let below = tied.iter().find(|candidate| distance(candidate) == closest && candidate.price.as_i64() < reference);
+        let above = tied.iter().find(|candidate| distance(candidate) == closest && candidate.price.as_i64() > reference);
+        match (below, above) {
+            // Reference lies midway between the two closest prices and is
+            // taken as the equilibrium itself
+            (Some(_), Some(_)) => Some(self.quantities_at(self.reference_price)),
+            (Some(candidate), None) | (None, Some(candidate)) => Some(**candidate),
+            (None, None) => tied.iter().find(|candidate| distance(candidate) == closest).map(|candidate| **candidate),
+        }
+    }
+
+    /// Quantities willing to trade at an arbitrary price
+    fn quantities_at(&self, price: Px) -> Equilibrium {
+        let (demand, supply) = self.orders.values().filter(|order| order.accepts(price)).fold(
+            (0i64, 0i64),
+            |(demand, supply), order| match order.side {
+                Side::Buy => (demand + order.quantity.as_i64(), supply),
+                Side::Sell => (demand, supply + order.quantity.as_i64()),
+            },
+        );
+        Equilibrium {
+            price,
+            matched: Qty::from_i64(demand.min(supply)),
+            buy_quantity: Qty::from_i64(demand),
+            sell_quantity: Qty::from_i64(supply),
+        }
+    }
+
+    /// Pairs buys and sells at the equilibrium price in priority order,
+    /// returning the trades and the quantity filled per order
+    fn allocate(&mut self, equilibrium: &Equilibrium, time: Ts) -> (Vec<TradeEvent>, AHashMap<u64, Qty>) {
+        let buys = self.priority_queue(Side::Buy, equilibrium.price);
+        let sells = self.priority_queue(Side::Sell, equilibrium.price);
+        // The side left with surplus is treated as the aggressor
+        let aggressor_side = equilibrium.surplus_side().unwrap_or(Side::Buy);
+
+        let mut trades = Vec::new();
+        let mut fills: AHashMap<u64, Qty> = AHashMap::new();
+        let mut remaining = equilibrium.matched.as_i64();
+        let (mut buy_index, mut sell_index) = (0, 0);
+        let (mut buy_left, mut sell_left) = (0i64, 0i64);
+        while remaining > 0 {
+            let (Some(buyer), Some(seller)) = (buys.get(buy_index), sells.get(sell_index)) else { break };
+            if buy_left == 0 {
+                buy_left = buyer.quantity.as_i64();
+            }
+            if sell_left == 0 {
+                sell_left = seller.quantity.as_i64();
+            }
+            let quantity = Qty::from_i64(buy_left.min(sell_left).min(remaining));
+            for id in [buyer.id, seller.id] {
+                let filled = fills.entry(id).or_insert(Qty::ZERO);
+                *filled = filled.add(quantity);
+            }
+            let (maker, taker) = if aggressor_side.is_buy() { (seller.id, buyer.id) } else { (buyer.id, seller.id) };
+            trades.push(TradeEvent {
+                trade_id: self.next_trade_id,
+                price: equilibrium.price,
+                quantity,
+                aggressor_side,
+                maker_order_id: Some(maker),
+                taker_order_id: Some(taker),
+                exchange_time: time,
+                local_time: time,
+                sequence: self.next_trade_id,
+            });
+            self.next_trade_id += 1;
+
+            let executed = quantity.as_i64();
+            remaining -= executed;
+            buy_left -= executed;
+            sell_left -= executed;
+            if buy_left == 0 {
+                buy_index += 1;
+            }
+            if sell_left == 0 {
+                sell_index += 1;
+            }
+        }
+        (trades, fills)
+    }
+
+    /// Orders on one side that execute at `price`: market orders, then
+    /// limit orders by price, each in time priority
+    fn priority_queue(&self, side: Side, price: Px) -> Vec<AuctionOrder> {
+        let mut queue: Vec<(u64, &AuctionOrder)> = self
+            .orders
+            .iter()
+            .filter(|(_, order)| order.side == side && order.accepts(price))
+            .map(|(&arrival, order)| (arrival, order))
+            .collect();
+        queue.sort_by(|(arrival_a, a), (arrival_b, b)| {
+            let by_price = match (a.price, b.price) {
+                (None, None) => std::cmp::Ordering::Equal,
+                (None, Some(_)) => std::cmp::Ordering::Less,
+                (Some(_), None) => std::cmp::Ordering::Greater,
+                (Some(pa), Some(pb)) if side.is_buy() => pb.cmp(&pa),
+                (Some(pa), Some(pb)) => pa.cmp(&pb),
+            };
+            by_price.then_with(|| arrival_a.cmp(arrival_b))
+        });
+        queue.into_iter().map(|(_, order)| order.clone()).collect()
+    }
+
+    fn refresh_indicative(&mut self, time: Ts) {
+        let equilibrium = self.equilibrium();
+        if equilibrium == self.indicative {
+            return;
+        }
+        self.indicative = equilibrium;
+        debug!("{} indicative equilibrium: {:?}", self.symbol, equilibrium);
+        // No subscribers is not an error
+        let _ = self.updates.send(IndicativeUpdate { symbol: self.symbol.clone(), time, equilibrium });
+    }
+
+    fn ensure_call_phase(&self) -> Result<()> {
+        if self.phase != AuctionPhase::Call {
+            bail!("{} auction has already uncrossed", self.symbol);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(id: u64, side: Side, price: Option<i64>, quantity: i64) -> AuctionOrder {
+        AuctionOrder {
+            id,
+            side,
+            price: price.map(Px::from_i64),
+            quantity: Qty::from_i64(quantity),
+            time: Ts::from_nanos(id),
+        }
+    }
+
+    fn auction(reference: i64, orders: Vec<AuctionOrder>) -> Result<AuctionBook> {
+        let mut book = AuctionBook::new("NIFTY", Px::from_i64(reference));
+        for order in orders {
+            book.submit(order)?;
+        }
+        Ok(book)
+    }
+
+    #[test]
+    fn test_maximum_volume_then_reference_price() -> Result<()> {
+        let book = auction(100, vec![
+            order(1, Side::Buy, Some(105), 10),
+            order(2, Side::Buy, Some(103), 20),
+            order(3, Side::Sell, Some(102), 15),
+            order(4, Side::Sell, Some(104), 25),
+        ])?;
+        let equilibrium = book.equilibrium().expect("equilibrium");
+        assert_eq!(equilibrium.price, Px::from_i64(102));
+        assert_eq!(equilibrium.matched, Qty::from_i64(15));
+        assert_eq!(equilibrium.surplus_side(), Some(Side::Buy));
+        Ok(())
+    }
+
+    #[test]
+    fn test_minimum_imbalance_breaks_volume_tie() -> Result<()> {
+        let book = auction(101, vec![
+            order(1, Side::Buy, Some(101), 20),
+            order(2, Side::Sell, Some(100), 20),
+            order(3, Side::Sell, Some(101), 5),
+        ])?;
+        let equilibrium = book.equilibrium().expect("equilibrium");
+        assert_eq!(equilibrium.price, Px::from_i64(100));
+        assert_eq!(equilibrium.imbalance(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reference_midway_is_equilibrium() -> Result<()> {
+        let orders = || vec![order(1, Side::Buy, Some(102), 100), order(2, Side::Sell, Some(98), 100)];
+        assert_eq!(auction(100, orders())?.equilibrium().map(|e| e.price), Some(Px::from_i64(100)));
+        assert_eq!(auction(101, orders())?.equilibrium().map(|e| e.price), Some(Px::from_i64(102)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_market_orders_only_use_reference() -> Result<()> {
+        let book = auction(250, vec![order(1, Side::Buy, None, 30), order(2, Side::Sell, None, 20)])?;
+        let equilibrium = book.equilibrium().expect("equilibrium");
+        assert_eq!(equilibrium.price, Px::from_i64(250));
+        assert_eq!(equilibrium.matched, Qty::from_i64(20));
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_equilibrium_without_cross() -> Result<()> {
+        let book = auction(100, vec![order(1, Side::Buy, Some(99), 10), order(2, Side::Sell, Some(101), 10)])?;
+        assert!(book.equilibrium().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_uncross_allocates_in_priority_order() -> Result<()> {
+        let mut book = auction(100, vec![
+            order(2, Side::Buy, Some(101), 20),
+            order(1, Side::Buy, None, 10),
+            order(3, Side::Sell, Some(100), 25),
+            order(4, Side::Sell, Some(102), 10),
+        ])?;
+        let result = book.uncross(Ts::from_nanos(1_000))?;
+
+        assert_eq!(result.equilibrium.map(|e| e.price), Some(Px::from_i64(100)));
+        assert_eq!(result.volume(), Qty::from_i64(25));
+        let fills: Vec<_> = result
+            .trades
+            .iter()
+            .map(|trade| (trade.taker_order_id, trade.maker_order_id, trade.quantity.as_i64()))
+            .collect();
+        assert_eq!(fills, [(Some(1), Some(3), 10), (Some(2), Some(3), 15)]);
+
+        let residual: Vec<_> = result.residual.iter().map(|o| (o.id, o.price, o.quantity.as_i64())).collect();
+        assert_eq!(residual, [(2, Some(Px::from_i64(101)), 5), (4, Some(Px::from_i64(102)), 10)]);
+
+        assert_eq!(book.phase(), AuctionPhase::Uncrossed);
+        assert!(book.submit(order(5, Side::Buy, Some(100), 1)).is_err());
+
+        let continuous = OrderBook::new("NIFTY");
+        result.load_residual(&continuous);
+        assert_eq!(continuous.get_bbo(), (Some(Px::from_i64(101)), Some(Px::from_i64(102))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_indicative_updates_are_published() -> Result<()> {
+        let mut book = AuctionBook::new("NIFTY", Px::from_i64(100));
+        let mut updates = book.subscribe();
+
+        book.submit(order(1, Side::Buy, Some(101), 10))?;
+        assert!(updates.try_recv().is_err());
+
+        book.submit(order(2, Side::Sell, Some(100), 10))?;
+        let update = updates.try_recv()?;
+        assert_eq!(update.equilibrium.map(|e| e.matched), Some(Qty::from_i64(10)));
+
+        book.cancel(2, Ts::from_nanos(3))?;
+        assert_eq!(updates.try_recv()?.equilibrium, None);
+        assert!(book.cancel(2, Ts::from_nanos(4)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_modify_loses_priority_unless_reduced() -> Result<()> {
+        let mut book = auction(100, vec![
+            order(1, Side::Buy, Some(100), 10),
+            order(2, Side::Buy, Some(100), 10),
+            order(3, Side::Sell, Some(100), 10),
+        ])?;
+        book.modify(1, Some(Px::from_i64(100)), Qty::from_i64(8), Ts::from_nanos(4))?;
+        book.modify(2, Some(Px::from_i64(100)), Qty::from_i64(12), Ts::from_nanos(5))?;
+        let result = book.uncross(Ts::from_nanos(6))?;
+        let takers: Vec<_> = result.trades.iter().map(|trade| (trade.taker_order_id, trade.quantity.as_i64())).collect();
+        assert_eq!(takers, [(Some(1), 8), (Some(2), 2)]);
+        Ok(())
+    }
+}
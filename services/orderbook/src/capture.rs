@@ -0,0 +1,901 @@
+//! Binary capture files for recording and replaying orderbook events
+//!
+//! A capture is an append-only file of length-prefixed records, each holding
+//! one `OrderBookEvent` encoded with bincode and protected by a CRC32:
+//!
+//! ```text
+//! header: [magic: u32][version: u32]
+//! record: [length: u32][crc32: u32][payload: length bytes]
+//! ```
+//!
+//! All integers are little-endian. Alongside the capture, the recorder keeps
+//! a sidecar index (`<capture>.idx`) with an entry every `index_interval`
+//! events, so readers can seek by time without scanning the whole file. The
+//! index is derived data: readers rebuild it from the capture when it is
+//! missing or does not match.
+//!
+//! `CapturePlayer` feeds a capture to a `ReplayEngine` or any other
+//! `EventSubscriber` in real time, at a multiple of real time, or as fast as
+//! events can be read, verifying every record's checksum on the way.
+
+use crate::events::OrderBookEvent;
+use crate::replay::ReplayEngine;
+use anyhow::{Context, Result, bail};
+use services_common::Ts;
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// Magic number for capture files
+const CAPTURE_MAGIC: u32 = 0x5351_4543; // "SQEC" in hex
+
+/// Magic number for capture index files
+const INDEX_MAGIC: u32 = 0x5351_4349; // "SQCI" in hex
+
+/// Version of the capture and index formats
+const FORMAT_VERSION: u32 = 1;
+
+/// Size of the capture and index file headers in bytes
+const HEADER_SIZE: u64 = 8;
+
+/// Size of a record's length and checksum prefix in bytes
+const RECORD_HEADER_SIZE: u64 = 8;
+
+/// Size of an index entry in bytes
+const INDEX_ENTRY_SIZE: usize = 24;
+
+/// Largest record a reader accepts; anything longer is a corrupt length
+const MAX_RECORD_SIZE: u32 = 64 * 1024 * 1024;
+
+/// Events between index entries when none is given
+pub const DEFAULT_INDEX_INTERVAL: u64 = 1024;
+
+/// Errors for individual records, after which reading can continue
+#[derive(Debug, thiserror::Error)]
+pub enum CaptureError {
+    /// Record payload does not match its checksum
+    #[error("Checksum mismatch in record at offset {offset}: expected {expected:#x}, got {actual:#x}")]
+    ChecksumMismatch {
+        /// File offset of the record
+        offset: u64,
+        /// Checksum stored with the record
+        expected: u32,
+        /// Checksum of the payload read
+        actual: u32,
+    },
+    /// Record payload matches its checksum but is not a valid event
+    #[error("Undecodable record at offset {offset}: {reason}")]
+    Decode {
+        /// File offset of the record
+        offset: u64,
+        /// Decoder error
+        reason: String,
+    },
+}
+
+/// Index entry locating an event in a capture
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// Latest exchange time of any event before this one, in nanoseconds
+    pub time_before: u64,
+    /// File offset of the event's record
+    pub offset: u64,
+    /// Number of events before this one
+    pub event: u64,
+}
+
+impl IndexEntry {
+    fn to_bytes(self) -> [u8; INDEX_ENTRY_SIZE] {
+        let mut bytes = [0; INDEX_ENTRY_SIZE];
+        bytes[..8].copy_from_slice(&self.time_before.to_le_bytes());
+        bytes[8..16].copy_from_slice(&self.offset.to_le_bytes());
+        bytes[16..].copy_from_slice(&self.event.to_le_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8; INDEX_ENTRY_SIZE]) -> Self {
+        let word = |i: usize| {
+            let mut word = [0; 8];
+            word.copy_from_slice(&bytes[i..i + 8]);
+            u64::from_le_bytes(word)
+        };
+        Self { time_before: word(0), offset: word(8), event: word(16) }
+    }
+}
+
+/// Path of the index kept alongside a capture
+#[must_use] pub fn index_path(capture: &Path) -> PathBuf {
+    let mut path = OsString::from(capture.as_os_str());
+    path.push(".idx");
+    PathBuf::from(path)
+}
+
+/// Reads until `buf` is full or the input ends, returning the bytes read
+fn read_up_to(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(read)
+}
+
+fn write_header(writer: &mut impl Write, magic: u32) -> io::Result<()> {
+    writer.write_all(&magic.to_le_bytes())?;
+    writer.write_all(&FORMAT_VERSION.to_le_bytes())
+}
+
+fn check_header(reader: &mut impl Read, magic: u32, path: &Path) -> Result<()> {
+    let mut header = [0; 8];
+    reader.read_exact(&mut header).with_context(|| format!("{} has no header", path.display()))?;
+    let found = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+    if found != magic {
+        bail!("{} has invalid magic {:#x}", path.display(), found);
+    }
+    let version = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+    if version != FORMAT_VERSION {
+        bail!("{} has unsupported format version {}", path.display(), version);
+    }
+    Ok(())
+}
+
+/// Where a scan of a capture ended
+#[derive(Debug, Default)]
+struct ScanEnd {
+    /// Offset just past the last complete record
+    offset: u64,
+    /// Complete records, including corrupt ones
+    events: u64,
+    /// Latest exchange time of any readable event, in nanoseconds
+    max_time: u64,
+}
+
+/// Recorder appending events to a capture and its index
+pub struct CaptureRecorder {
+    path: PathBuf,
+    file: BufWriter<File>,
+    index: BufWriter<File>,
+    index_interval: u64,
+    offset: u64,
+    events: u64,
+    max_time: u64,
+    payload: Vec<u8>,
+}
+
+impl CaptureRecorder {
+    /// Create a capture at `path`, replacing any existing file
+    ///
+    /// An index entry is written every `index_interval` events; 0 selects
+    /// `DEFAULT_INDEX_INTERVAL`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the capture or its index cannot be created.
+    pub fn create(path: impl AsRef<Path>, index_interval: u64) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = BufWriter::with_capacity(64 * 1024, File::create(&path)?);
+        write_header(&mut file, CAPTURE_MAGIC)?;
+        let mut index = BufWriter::new(File::create(index_path(&path))?);
+        write_header(&mut index, INDEX_MAGIC)?;
+
+        info!("Recording capture to {}", path.display());
+        Ok(Self {
+            path,
+            file,
+            index,
+            index_interval: if index_interval == 0 { DEFAULT_INDEX_INTERVAL } else { index_interval },
+            offset: HEADER_SIZE,
+            events: 0,
+            max_time: 0,
+            payload: Vec::with_capacity(1024),
+        })
+    }
+
+    /// Open an existing capture to append to it, creating it if missing
+    ///
+    /// A record left incomplete by a crash is truncated away, and the index
+    /// is rebuilt from the records that remain.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file is not a capture, has a corrupt record
+    /// length, or cannot be written.
+    pub fn open(path: impl AsRef<Path>, index_interval: u64) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Self::create(path, index_interval);
+        }
+        let index_interval = if index_interval == 0 { DEFAULT_INDEX_INTERVAL } else { index_interval };
+
+        let (entries, end) = scan_capture(path, index_interval)?;
+        let file = OpenOptions::new().write(true).open(path)?;
+        if file.metadata()?.len() > end.offset {
+            warn!("Truncating incomplete record at offset {} of {}", end.offset, path.display());
+            file.set_len(end.offset)?;
+        }
+        let mut file = BufWriter::with_capacity(64 * 1024, file);
+        file.seek(SeekFrom::Start(end.offset))?;
+
+        let mut index = BufWriter::new(File::create(index_path(path))?);
+        write_header(&mut index, INDEX_MAGIC)?;
+        for entry in &entries {
+            index.write_all(&entry.to_bytes())?;
+        }
+
+        info!("Appending to capture {} after {} events", path.display(), end.events);
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            index,
+            index_interval,
+            offset: end.offset,
+            events: end.events,
+            max_time: end.max_time,
+            payload: Vec::with_capacity(1024),
+        })
+    }
+
+    /// Append an event
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the event cannot be encoded or written.
+    pub fn record(&mut self, event: &OrderBookEvent) -> Result<()> {
+        if self.events.is_multiple_of(self.index_interval) {
+            let entry = IndexEntry { time_before: self.max_time, offset: self.offset, event: self.events };
+            self.index.write_all(&entry.to_bytes())?;
+        }
+
+        self.payload.clear();
+        bincode::serialize_into(&mut self.payload, event)?;
+        let Ok(length) = u32::try_from(self.payload.len()) else {
+            bail!("Event of {} bytes is too large to record", self.payload.len());
+        };
+        if length > MAX_RECORD_SIZE {
+            bail!("Event of {} bytes is too large to record", length);
+        }
+
+        // Record: [length: u32][crc: u32][payload]
+        self.file.write_all(&length.to_le_bytes())?;
+        self.file.write_all(&crc32fast::hash(&self.payload).to_le_bytes())?;
+        self.file.write_all(&self.payload)?;
+
+        self.offset += RECORD_HEADER_SIZE + u64::from(length);
+        self.events += 1;
+        self.max_time = self.max_time.max(event.exchange_time().as_nanos());
+        Ok(())
+    }
+
+    /// Write buffered records and index entries through to disk
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the files cannot be written or synced.
+    pub fn flush(&mut self) -> Result<()> {
+        self.file.flush()?;
+        self.file.get_ref().sync_data()?;
+        self.index.flush()?;
+        Ok(())
+    }
+
+    /// Flush and close the capture, returning the number of events in it
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the final flush fails.
+    pub fn finish(mut self) -> Result<u64> {
+        self.flush()?;
+        debug!("Closed capture {} with {} events", self.path.display(), self.events);
+        Ok(self.events)
+    }
+
+    /// Number of events in the capture
+    #[must_use] pub const fn events(&self) -> u64 {
+        self.events
+    }
+
+    /// Size of the capture in bytes, including buffered records
+    #[must_use] pub const fn size(&self) -> u64 {
+        self.offset
+    }
+}
+
+impl std::fmt::Debug for CaptureRecorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CaptureRecorder")
+            .field("path", &self.path)
+            .field("index_interval", &self.index_interval)
+            .field("offset", &self.offset)
+            .field("events", &self.events)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Sequential reader for a capture
+pub struct CaptureReader {
+    path: PathBuf,
+    reader: BufReader<File>,
+    index: Vec<IndexEntry>,
+    offset: u64,
+    events: u64,
+    payload: Vec<u8>,
+}
+
+impl CaptureReader {
+    /// Open a capture for reading from its first event
+    ///
+    /// The index is loaded from the sidecar file, or rebuilt by scanning the
+    /// capture if the sidecar is missing or points past the end of it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a capture.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut reader = BufReader::with_capacity(64 * 1024, File::open(&path)?);
+        check_header(&mut reader, CAPTURE_MAGIC, &path)?;
+        let len = reader.get_ref().metadata()?.len();
+
+        let index = match load_index(&index_path(&path), len) {
+            Ok(index) => index,
+            Err(e) => {
+                warn!("Rebuilding index for {}: {:#}", path.display(), e);
+                scan_capture(&path, DEFAULT_INDEX_INTERVAL)?.0
+            }
+        };
+
+        Ok(Self { path, reader, index, offset: HEADER_SIZE, events: 0, payload: Vec::with_capacity(1024) })
+    }
+
+    /// Read the next event
+    ///
+    /// Returns `None` at the end of the capture. An incomplete final record,
+    /// as left while a recorder is still writing, also reads as the end; the
+    /// reader stays before it so a later call can read it once complete.
+    ///
+    /// # Errors
+    ///
+    /// A record failing its checksum or decoding returns a `CaptureError`;
+    /// the reader has moved past it, so reading can continue. A record with
+    /// an implausible length cannot be skipped and is fatal, as are I/O
+    /// errors.
+    pub fn next_event(&mut self) -> Result<Option<OrderBookEvent>> {
+        let offset = self.offset;
+        let Some(expected) = self.read_record()? else {
+            return Ok(None);
+        };
+
+        let actual = crc32fast::hash(&self.payload);
+        if actual != expected {
+            return Err(CaptureError::ChecksumMismatch { offset, expected, actual }.into());
+        }
+        let event = bincode::deserialize(&self.payload)
+            .map_err(|e| CaptureError::Decode { offset, reason: e.to_string() })?;
+        Ok(Some(event))
+    }
+
+    /// Reads the next record's payload, returning its stored checksum
+    fn read_record(&mut self) -> Result<Option<u32>> {
+        let mut header = [0; 8];
+        let read = read_up_to(&mut self.reader, &mut header)?;
+        if read == 0 {
+            return Ok(None);
+        }
+        if read < header.len() {
+            return self.incomplete_record();
+        }
+
+        let length = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let expected = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        if length > MAX_RECORD_SIZE {
+            bail!("Record at offset {} of {} claims {} bytes", self.offset, self.path.display(), length);
+        }
+
+        self.payload.resize(length as usize, 0);
+        if read_up_to(&mut self.reader, &mut self.payload)? < self.payload.len() {
+            return self.incomplete_record();
+        }
+
+        self.offset += RECORD_HEADER_SIZE + u64::from(length);
+        self.events += 1;
+        Ok(Some(expected))
+    }
+
+    fn incomplete_record(&mut self) -> Result<Option<u32>> {
+        debug!("Incomplete record at offset {} of {}", self.offset, self.path.display());
+        self.reader.seek(SeekFrom::Start(self.offset))?;
+        Ok(None)
+    }
+
+    /// Position the reader at the first event with an exchange time at or
+    /// after `time`
+    ///
+    /// Uses the index to skip events known to be earlier, then reads forward.
+    /// Corrupt records passed over on the way are skipped. Returns `false` if
+    /// no such event exists, leaving the reader at the end of the capture.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error on I/O errors or a record with an implausible length.
+    pub fn seek(&mut self, time: Ts) -> Result<bool> {
+        let target = time.as_nanos();
+        // Everything before an entry with an earlier `time_before` is too early
+        let skip = self.index.partition_point(|entry| entry.time_before < target);
+        let start = skip.checked_sub(1).map_or(
+            IndexEntry { time_before: 0, offset: HEADER_SIZE, event: 0 },
+            |i| self.index[i],
+        );
+        self.reader.seek(SeekFrom::Start(start.offset))?;
+        self.offset = start.offset;
+        self.events = start.event;
+
+        loop {
+            let (offset, events) = (self.offset, self.events);
+            match self.next_event() {
+                Ok(Some(event)) if event.exchange_time().as_nanos() >= target => {
+                    self.reader.seek(SeekFrom::Start(offset))?;
+                    self.offset = offset;
+                    self.events = events;
+                    return Ok(true);
+                }
+                Ok(Some(_)) => {}
+                Ok(None) => return Ok(false),
+                Err(e) if e.is::<CaptureError>() => debug!("Skipping while seeking: {:#}", e),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Return to the first event
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be seeked.
+    pub fn rewind(&mut self) -> Result<()> {
+        self.reader.seek(SeekFrom::Start(HEADER_SIZE))?;
+        self.offset = HEADER_SIZE;
+        self.events = 0;
+        Ok(())
+    }
+
+    /// Number of events before the reader's position
+    #[must_use] pub const fn position(&self) -> u64 {
+        self.events
+    }
+
+    /// Index entries, in file order
+    #[must_use] pub fn index(&self) -> &[IndexEntry] {
+        &self.index
+    }
+}
+
+impl std::fmt::Debug for CaptureReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CaptureReader")
+            .field("path", &self.path)
+            .field("index_entries", &self.index.len())
+            .field("offset", &self.offset)
+            .field("events", &self.events)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Loads an index, failing if it cannot belong to a capture of `capture_len` bytes
+fn load_index(path: &Path, capture_len: u64) -> Result<Vec<IndexEntry>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    check_header(&mut reader, INDEX_MAGIC, path)?;
+
+    let mut entries: Vec<IndexEntry> = Vec::new();
+    let mut bytes = [0; INDEX_ENTRY_SIZE];
+    loop {
+        let read = read_up_to(&mut reader, &mut bytes)?;
+        if read < INDEX_ENTRY_SIZE {
+            // A partial entry is an interrupted write; the entries before it stand
+            break;
+        }
+        let entry = IndexEntry::from_bytes(&bytes);
+        let ordered = entries.last().is_none_or(|last| {
+            entry.offset > last.offset && entry.event > last.event && entry.time_before >= last.time_before
+        });
+        if !ordered || entry.offset > capture_len {
+            bail!("index entry {:?} does not match the capture", entry);
+        }
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Reads a whole capture, building its index and finding where it ends
+fn scan_capture(path: &Path, index_interval: u64) -> Result<(Vec<IndexEntry>, ScanEnd)> {
+    let mut reader = CaptureReader {
+        path: path.to_path_buf(),
+        reader: BufReader::with_capacity(64 * 1024, File::open(path)?),
+        index: Vec::new(),
+        offset: HEADER_SIZE,
+        events: 0,
+        payload: Vec::with_capacity(1024),
+    };
+    check_header(&mut reader.reader, CAPTURE_MAGIC, path)?;
+
+    let mut entries = Vec::new();
+    let mut max_time = 0;
+    loop {
+        if reader.events.is_multiple_of(index_interval) {
+            entries.push(IndexEntry { time_before: max_time, offset: reader.offset, event: reader.events });
+        }
+        match reader.next_event() {
+            Ok(Some(event)) => max_time = max_time.max(event.exchange_time().as_nanos()),
+            Ok(None) => break,
+            Err(e) if e.is::<CaptureError>() => warn!("{}: {:#}", path.display(), e),
+            Err(e) => return Err(e),
+        }
+    }
+    // The entry pushed for the event that was not there
+    if entries.last().is_some_and(|entry| entry.event == reader.events) {
+        entries.pop();
+    }
+
+    Ok((entries, ScanEnd { offset: reader.offset, events: reader.events, max_time }))
+}
+
+/// Receiver of events played from a capture
+pub trait EventSubscriber {
+    /// Handle an event
+    ///
+    /// # Errors
+    ///
+    /// An error stops playback and is returned from `CapturePlayer::play`.
+    fn on_event(&mut self, event: &OrderBookEvent) -> Result<()>;
+}
+
+impl EventSubscriber for ReplayEngine {
+    fn on_event(&mut self, event: &OrderBookEvent) -> Result<()> {
+        self.process_event(event.clone())
+    }
+}
+
+impl<F: FnMut(&OrderBookEvent) -> Result<()>> EventSubscriber for F {
+    fn on_event(&mut self, event: &OrderBookEvent) -> Result<()> {
+        self(event)
+    }
+}
+
+/// Pace of playback
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlaybackSpeed {
+    /// Events are delivered as far apart as their exchange times
+    RealTime,
+    /// Real time sped up by a factor, e.g. 10.0 for ten times faster
+    Multiple(f64),
+    /// Events are delivered as fast as they can be read
+    Max,
+}
+
+/// Configuration for capture playback
+#[derive(Debug, Clone)]
+pub struct PlayerConfig {
+    /// Pace of playback
+    pub speed: PlaybackSpeed,
+    /// Skip records failing their checksum instead of stopping
+    pub skip_corrupt: bool,
+    /// Start at the first event at or after this exchange time
+    pub start: Option<Ts>,
+    /// Stop before the first event after this exchange time
+    pub end: Option<Ts>,
+}
+
+impl Default for PlayerConfig {
+    fn default() -> Self {
+        Self { speed: PlaybackSpeed::Max, skip_corrupt: false, start: None, end: None }
+    }
+}
+
+/// Summary of a playback
+#[derive(Debug, Clone, Default)]
+pub struct PlaybackStats {
+    /// Events delivered to the subscriber
+    pub events_played: u64,
+    /// Records skipped for failing their checksum or decoding
+    pub corrupt_records: u64,
+    /// Exchange time of the first event delivered
+    pub first_event_time: Option<Ts>,
+    /// Exchange time of the last event delivered
+    pub last_event_time: Option<Ts>,
+    /// Wall-clock time spent playing
+    pub elapsed: Duration,
+}
+
+impl PlaybackStats {
+    /// Events delivered per second of wall-clock time
+    #[must_use] pub fn events_per_second(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 { self.events_played as f64 / secs } else { 0.0 }
+    }
+}
+
+/// Player delivering a capture's events to a subscriber
+#[derive(Debug)]
+pub struct CapturePlayer {
+    reader: CaptureReader,
+    config: PlayerConfig,
+}
+
+impl CapturePlayer {
+    /// Create a player for an open capture
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the speed is a multiple that is not positive and finite.
+    pub fn new(reader: CaptureReader, config: PlayerConfig) -> Result<Self> {
+        if let PlaybackSpeed::Multiple(multiple) = config.speed
+            && !(multiple.is_finite() && multiple > 0.0)
+        {
+            bail!("Playback speed must be positive and finite, got {}", multiple);
+        }
+        Ok(Self { reader, config })
+    }
+
+    /// Open a capture for playback
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the capture cannot be opened or the speed is invalid.
+    pub fn open(path: impl AsRef<Path>, config: PlayerConfig) -> Result<Self> {
+        Self::new(CaptureReader::open(path)?, config)
+    }
+
+    /// Play the capture's events into `subscriber`
+    ///
+    /// Playback starts at the configured start time, or the reader's current
+    /// position without one, and blocks until the end of the capture or the
+    /// configured end time. Pacing follows the events' exchange times;
+    /// events earlier than the one before them are delivered immediately.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the subscriber fails, on I/O errors, or on a
+    /// corrupt record unless `skip_corrupt` is set.
+    pub fn play<S: EventSubscriber + ?Sized>(&mut self, subscriber: &mut S) -> Result<PlaybackStats> {
+        if let Some(start) = self.config.start {
+            self.reader.seek(start)?;
+        }
+        let mut stats = PlaybackStats::default();
+        let started = Instant::now();
+        let end = self.config.end.map(|end| end.as_nanos());
+
+        loop {
+            let event = match self.reader.next_event() {
+                Ok(Some(event)) => event,
+                Ok(None) => break,
+                Err(e) if self.config.skip_corrupt && e.is::<CaptureError>() => {
+                    warn!("Skipping record: {:#}", e);
+                    stats.corrupt_records += 1;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+
+            let time = event.exchange_time();
+            if end.is_some_and(|end| time.as_nanos() > end) {
+                break;
+            }
+            let first = *stats.first_event_time.get_or_insert(time);
+            self.pace(started, time.as_nanos().saturating_sub(first.as_nanos()));
+
+            subscriber.on_event(&event)?;
+            stats.events_played += 1;
+            stats.last_event_time = Some(time);
+        }
+
+        stats.elapsed = started.elapsed();
+        info!(
+            "Played {} events in {:?} ({:.0} events/s, {} corrupt)",
+            stats.events_played,
+            stats.elapsed,
+            stats.events_per_second(),
+            stats.corrupt_records
+        );
+        Ok(stats)
+    }
+
+    /// Sleeps until an event `since_first` nanoseconds after the first is due
+    fn pace(&self, started: Instant, since_first: u64) {
+        let multiple = match self.config.speed {
+            PlaybackSpeed::Max => return,
+            PlaybackSpeed::RealTime => 1.0,
+            PlaybackSpeed::Multiple(multiple) => multiple,
+        };
+        let due = Duration::from_nanos((since_first as f64 / multiple) as u64);
+        if let Some(wait) = due.checked_sub(started.elapsed()) {
+            std::thread::sleep(wait);
+        }
+    }
+
+    /// The underlying reader, e.g. to seek before playing
+    pub const fn reader_mut(&mut self) -> &mut CaptureReader {
+        &mut self.reader
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{OrderUpdate, Side, UpdateType};
+    use crate::replay::ReplayConfig;
+    use services_common::{Px, Qty};
+    use tempfile::TempDir;
+
+    fn order(sequence: u64, time: u64, price: i64) -> OrderBookEvent {
+        OrderBookEvent::Order(OrderUpdate {
+            order_id: sequence,
+            price: Px::from_i64(price),
+            quantity: Qty::from_i64(1_0000),
+            side: if sequence % 2 == 0 { Side::Buy } else { Side::Sell },
+            update_type: UpdateType::Add,
+            exchange_time: Ts::from_nanos(time),
+            local_time: Ts::from_nanos(time + 10),
+            sequence,
+        })
+    }
+
+    /// Capture of `count` orders one millisecond apart, with sequences from 1
+    fn record(dir: &TempDir, count: u64, index_interval: u64) -> PathBuf {
+        let path = dir.path().join("events.cap");
+        let mut recorder = CaptureRecorder::create(&path, index_interval).unwrap();
+        for i in 1..=count {
+            let price = if i % 2 == 0 { 99_0000 - i as i64 } else { 101_0000 + i as i64 };
+            recorder.record(&order(i, i * 1_000_000, price)).unwrap();
+        }
+        assert_eq!(recorder.finish().unwrap(), count);
+        path
+    }
+
+    fn sequences(player: &mut CapturePlayer) -> Vec<u64> {
+        let mut seen = Vec::new();
+        player
+            .play(&mut |event: &OrderBookEvent| {
+                seen.push(event.sequence());
+                Ok(())
+            })
+            .unwrap();
+        seen
+    }
+
+    #[test]
+    fn test_recorded_events_play_back_in_order() {
+        let dir = TempDir::new().unwrap();
+        let path = record(&dir, 50, 8);
+
+        let mut player = CapturePlayer::open(&path, PlayerConfig::default()).unwrap();
+        assert_eq!(player.reader_mut().index().len(), 7);
+        assert_eq!(sequences(&mut player), (1..=50).collect::<Vec<_>>());
+
+        let mut engine = ReplayEngine::new("NIFTY", ReplayConfig::default());
+        player.reader_mut().rewind().unwrap();
+        let stats = player.play(&mut engine).unwrap();
+        assert_eq!(stats.events_played, 50);
+        assert_eq!(stats.first_event_time, Some(Ts::from_nanos(1_000_000)));
+        assert_eq!(stats.last_event_time, Some(Ts::from_nanos(50_000_000)));
+        assert_eq!(engine.with_orderbook(|book| book.get_depth(100).0.len()), 25);
+    }
+
+    #[test]
+    fn test_seek_and_window_use_exchange_time() {
+        let dir = TempDir::new().unwrap();
+        let path = record(&dir, 50, 8);
+        let config = PlayerConfig {
+            start: Some(Ts::from_nanos(20_500_000)),
+            end: Some(Ts::from_nanos(30_000_000)),
+            ..PlayerConfig::default()
+        };
+        let mut player = CapturePlayer::open(&path, config).unwrap();
+        assert_eq!(sequences(&mut player), (21..=30).collect::<Vec<_>>());
+
+        let mut reader = CaptureReader::open(&path).unwrap();
+        assert!(reader.seek(Ts::from_nanos(17_000_000)).unwrap());
+        assert_eq!(reader.position(), 16);
+        assert_eq!(reader.next_event().unwrap().unwrap().sequence(), 17);
+        assert!(!reader.seek(Ts::from_nanos(51_000_000)).unwrap());
+        assert!(reader.next_event().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_missing_or_stale_index_is_rebuilt() {
+        let dir = TempDir::new().unwrap();
+        let path = record(&dir, 50, 8);
+        let built = CaptureReader::open(&path).unwrap().index().to_vec();
+
+        std::fs::remove_file(index_path(&path)).unwrap();
+        let mut reader = CaptureReader::open(&path).unwrap();
+        assert_eq!(reader.index().len(), 1);
+        assert!(reader.seek(Ts::from_nanos(40_000_000)).unwrap());
+        assert_eq!(reader.next_event().unwrap().unwrap().sequence(), 40);
+
+        // An index from a longer capture points past the end of this one
+        let long_dir = TempDir::new().unwrap();
+        let long = record(&long_dir, 5_000, 8);
+        std::fs::copy(index_path(&long), index_path(&path)).unwrap();
+        assert_eq!(CaptureReader::open(&path).unwrap().index()[0], built[0]);
+    }
+
+    #[test]
+    fn test_corrupt_record_fails_checksum() {
+        let dir = TempDir::new().unwrap();
+        let path = record(&dir, 10, 8);
+        let mut bytes = std::fs::read(&path).unwrap();
+        // Flip a payload byte in the third record; records are equal in size
+        let record_size = (bytes.len() - HEADER_SIZE as usize) / 10;
+        bytes[HEADER_SIZE as usize + 2 * record_size + 12] ^= 0xFF;
+        std::fs::write(&path, &bytes).unwrap();
+
+        let mut strict = CapturePlayer::open(&path, PlayerConfig::default()).unwrap();
+        let error = strict.play(&mut |_: &OrderBookEvent| Ok(())).unwrap_err();
+        assert!(matches!(error.downcast_ref(), Some(CaptureError::ChecksumMismatch { .. })));
+
+        let config = PlayerConfig { skip_corrupt: true, ..PlayerConfig::default() };
+        let mut lenient = CapturePlayer::open(&path, config).unwrap();
+        let mut played = Vec::new();
+        let stats = lenient
+            .play(&mut |event: &OrderBookEvent| {
+                played.push(event.sequence());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(stats.corrupt_records, 1);
+        assert_eq!(played, [1, 2, 4, 5, 6, 7, 8, 9, 10]);
+    }
+
+    #[test]
+    fn test_append_truncates_incomplete_record() {
+        let dir = TempDir::new().unwrap();
+        let path = record(&dir, 10, 4);
+        // Half a record, as left by a crash mid-write
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&[40, 0, 0, 0, 1, 2]).unwrap();
+        drop(file);
+
+        let mut reader = CaptureReader::open(&path).unwrap();
+        assert_eq!(std::iter::from_fn(|| reader.next_event().unwrap()).count(), 10);
+
+        let mut recorder = CaptureRecorder::open(&path, 4).unwrap();
+        assert_eq!(recorder.events(), 10);
+        for i in 11..=12 {
+            recorder.record(&order(i, i * 1_000_000, 101_0000)).unwrap();
+        }
+        recorder.finish().unwrap();
+
+        let mut reader = CaptureReader::open(&path).unwrap();
+        assert_eq!(reader.index().iter().map(|entry| entry.event).collect::<Vec<_>>(), [0, 4, 8]);
+        assert!(reader.seek(Ts::from_nanos(11_000_000)).unwrap());
+        assert_eq!(reader.next_event().unwrap().unwrap().sequence(), 11);
+    }
+
+    #[test]
+    fn test_real_time_playback_is_paced() {
+        let dir = TempDir::new().unwrap();
+        // 50 events 1ms apart span 49ms of exchange time
+        let path = record(&dir, 50, 8);
+        let config = PlayerConfig { speed: PlaybackSpeed::Multiple(2.0), ..PlayerConfig::default() };
+        let stats = CapturePlayer::open(&path, config).unwrap().play(&mut |_: &OrderBookEvent| Ok(())).unwrap();
+        assert_eq!(stats.events_played, 50);
+        assert!(stats.elapsed >= Duration::from_micros(24_500));
+    }
+
+    #[test]
+    fn test_player_rejects_invalid_speed() {
+        let dir = TempDir::new().unwrap();
+        let path = record(&dir, 5, 8);
+        for multiple in [0.0, -2.0, f64::NAN, f64::INFINITY] {
+            let config = PlayerConfig { speed: PlaybackSpeed::Multiple(multiple), ..PlayerConfig::default() };
+            let error = CapturePlayer::open(&path, config).unwrap_err();
+            assert!(error.to_string().contains("Playback speed"), "{multiple}: {error}");
+        }
+    }
+}
@@ -0,0 +1,509 @@
+//! Exchange-native book checksum validation
+//!
+//! `OrderBook::get_checksum` is an internal hash; exchanges publish their own
+//! checks with incremental feeds. A `ChecksumValidator` reproduces one
+//! exchange's check against the local book:
+//!
+//! - Kraken: CRC32 over the top 10 asks then bids, each price and quantity
+//!   written without decimal point or leading zeros
+//! - OKX: CRC32 over the top 25 levels, bids and asks interleaved as
+//!   `price:size` strings
+//! - Binance: no checksum, but each depth update's ID range must continue
+//!   from the previous one (or from the snapshot's `lastUpdateId`)
+//!
+//! `BookValidator` runs a validator for one book, counts results in
+//! `PerformanceMetrics::record_checksum` and raises a resync request on the
+//! first mismatch.
+//!
+//! Prices and quantities are held to four decimal places, so checksums only
+//! reproduce for instruments whose exchange values fit that precision.
+
+use crate::core::PriceBook;
+use crate::metrics::PerformanceMetrics;
+use parking_lot::{Mutex, RwLock};
+use services_common::{Px, Qty};
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{info, warn};
+
+/// Decimal places of the fixed-point price and quantity representation
+const FIXED_POINT_DECIMALS: u32 = 4;
+
+/// Check an exchange sends with a book update
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExchangeCheck {
+    /// Checksum of the book after the update; OKX's signed value cast to `u32`
+    Checksum(u32),
+    /// Update ID range of a depth update
+    UpdateIds {
+        /// First update ID in the event (`U`)
+        first: u64,
+        /// Final update ID in the event (`u`)
+        last: u64,
+        /// Final update ID of the previous event (`pu`), sent by futures streams
+        previous: Option<u64>,
+    },
+}
+
+/// Local book does not match the exchange's check
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ChecksumMismatch {
+    /// Computed checksum differs from the exchange's
+    #[error("Checksum mismatch: exchange sent {expected:#010x}, book gives {computed:#010x}")]
+    Checksum {
+        /// Checksum sent by the exchange
+        expected: u32,
+        /// Checksum of the local book
+        computed: u32,
+    },
+    /// Update does not continue from the last one applied
+    #[error("Update {first}..={last} does not continue from update {last_applied}")]
+    UpdateGap {
+        /// Final update ID applied before this update
+        last_applied: u64,
+        /// First update ID of this update
+        first: u64,
+        /// Final update ID of this update
+        last: u64,
+    },
+    /// No snapshot has established where updates continue from
+    #[error("No snapshot to continue updates from")]
+    NotSynced,
+    /// A mismatch was already raised and the book awaits a resync
+    #[error("Book is awaiting resync")]
+    Resyncing,
+    /// Validator cannot check this kind of update
+    #[error("{exchange} validator cannot check {check:?}")]
+    Unsupported {
+        /// Exchange of the validator
+        exchange: &'static str,
+        /// Check it was given
+        check: ExchangeCheck,
+    },
+}
+
+/// Outcome of a check the book passed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckOutcome {
+    /// Book matches the exchange's check
+    Valid,
+    /// Update predates the snapshot the book was loaded from; skip applying it
+    Stale,
+}
+
+/// Reproduces one exchange's book check
+pub trait ChecksumValidator: Send + std::fmt::Debug {
+    /// Exchange whose check this reproduces
+    fn exchange(&self) -> &'static str;
+
+    /// Validate the book against the exchange's check
+    ///
+    /// Checksums are checked after applying the update; update IDs before,
+    /// so that stale updates can be skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns the mismatch if the book does not pass the check.
+    fn validate(&mut self, book: &dyn PriceBook, check: &ExchangeCheck) -> Result<CheckOutcome, ChecksumMismatch>;
+
+    /// Restart after the book was reloaded from a snapshot with the given update ID
+    fn reset(&mut self, _snapshot_update_id: Option<u64>) {}
+}
+
+/// Fixed-point value at `decimals` places as an integer
+fn scaled(value: i64, decimals: u32) -> i128 {
+    let value = i128::from(value);
+    if decimals >= FIXED_POINT_DECIMALS {
+        value * 10i128.pow(decimals - FIXED_POINT_DECIMALS)
+    } else {
+        value / 10i128.pow(FIXED_POINT_DECIMALS - decimals)
+    }
+}
+
+/// Fixed-point value as a decimal string at up to `decimals` places, without trailing zeros
+fn decimal_string(value: i64, decimals: u32) -> String {
+    let scaled = scaled(value, decimals);
+    let divisor = 10i128.pow(decimals);
+    let sign = if scaled < 0 { "-" } else { "" };
+    let (whole, fraction) = (scaled.abs() / divisor, scaled.abs() % divisor);
+    if fraction == 0 {
+        return format!("{sign}{whole}");
+    }
+    let fraction = format!("{:0width$}", fraction, width = decimals as usize);
+    format!("{sign}{whole}.{}", fraction.trim_end_matches('0'))
+}
+
+const fn checksum_result(expected: u32, computed: u32) -> Result<CheckOutcome, ChecksumMismatch> {
+    if expected == computed { Ok(CheckOutcome::Valid) } else { Err(ChecksumMismatch::Checksum { expected, computed }) }
+}
+
+/// Kraken book checksum over the top 10 levels
+#[derive(Debug, Clone)]
+pub struct KrakenValidator {
+    price_decimals: u32,
+    qty_decimals: u32,
+}
+
+impl KrakenValidator {
+    /// Levels per side covered by the checksum
+    pub const DEPTH: usize = 10;
+
+    /// Create a validator for an instrument quoted to the given decimal places
+    #[must_use] pub const fn new(price_decimals: u32, qty_decimals: u32) -> Self {
+        Self { price_decimals, qty_decimals }
+    }
+
+    /// Checksum of the book as Kraken computes it
+    #[must_use] pub fn checksum(&self, book: &dyn PriceBook) -> u32 {
+        let (bids, asks) = book.get_depth(Self::DEPTH);
+        let mut input = String::new();
+        for (price, qty, _) in asks.iter().chain(&bids) {
+            // Dropping the point and leading zeros leaves the scaled integer
+            let _ = write!(input, "{}{}", scaled(price.as_i64(), self.price_decimals), scaled(qty.as_i64(), self.qty_decimals));
+        }
+        crc32fast::hash(input.as_bytes())
+    }
+}
+
+impl ChecksumValidator for KrakenValidator {
+    fn exchange(&self) -> &'static str {
+        "kraken"
+    }
+
+    fn validate(&mut self, book: &dyn PriceBook, check: &ExchangeCheck) -> Result<CheckOutcome, ChecksumMismatch> {
+        match *check {
+            ExchangeCheck::Checksum(expected) => checksum_result(expected, self.checksum(book)),
+            ExchangeCheck::UpdateIds { .. } => Err(ChecksumMismatch::Unsupported { exchange: self.exchange(), check: *check }),
+        }
+    }
+}
+
+/// OKX book checksum over the top 25 levels
+#[derive(Debug, Clone)]
+pub struct OkxValidator {
+    price_decimals: u32,
+    qty_decimals: u32,
+}
+
+impl OkxValidator {
+    /// Levels per side covered by the checksum
+    pub const DEPTH: usize = 25;
+
+    /// Create a validator for an instrument quoted to at most the given decimal places
+    ///
+    /// Values are written as OKX sends them, without trailing zeros.
+    #[must_use] pub const fn new(price_decimals: u32, qty_decimals: u32) -> Self {
+        Self { price_decimals, qty_decimals }
+    }
+
+    /// Checksum of the book as OKX computes it
+    #[must_use] pub fn checksum(&self, book: &dyn PriceBook) -> u32 {
+        let (bids, asks) = book.get_depth(Self::DEPTH);
+        let level = |(price, qty, _): &(Px, Qty, u64)| {
+            format!("{}:{}", decimal_string(price.as_i64(), self.price_decimals), decimal_string(qty.as_i64(), self.qty_decimals))
+        };
+        let mut parts = Vec::with_capacity(bids.len() + asks.len());
+        for index in 0..bids.len().max(asks.len()) {
+            parts.extend(bids.get(index).map(level));
+            parts.extend(asks.get(index).map(level));
+        }
+        crc32fast::hash(parts.join(":").as_bytes())
+    }
+}
+
+impl ChecksumValidator for OkxValidator {
+    fn exchange(&self) -> &'static str {
+        "okx"
+    }
+
+    fn validate(&mut self, book: &dyn PriceBook, check: &ExchangeCheck) -> Result<CheckOutcome, ChecksumMismatch> {
+        match *check {
+            ExchangeCheck::Checksum(expected) => checksum_result(expected, self.checksum(book)),
+            ExchangeCheck::UpdateIds { .. } => Err(ChecksumMismatch::Unsupported { exchange: self.exchange(), check: *check }),
+        }
+    }
+}
+
+/// Binance depth update continuity
+///
+/// Spot streams chain updates by `U == previous u + 1`; futures streams send
+/// the previous event's final ID as `pu`. The first update after a snapshot
+/// must span the snapshot's `lastUpdateId`; updates buffered before it that
+/// end earlier are `Stale`.
+#[derive(Debug, Clone, Default)]
+pub struct BinanceValidator {
+    /// Final update ID reflected in the book
+    last_update_id: Option<u64>,
+    /// Whether the next update is the first after a snapshot
+    after_snapshot: bool,
+}
+
+impl BinanceValidator {
+    /// Create a validator awaiting its first snapshot
+    #[must_use] pub const fn new() -> Self {
+        Self { last_update_id: None, after_snapshot: false }
+    }
+
+    /// Final update ID reflected in the book
+    #[must_use] pub const fn last_update_id(&self) -> Option<u64> {
+        self.last_update_id
+    }
+}
+
+impl ChecksumValidator for BinanceValidator {
+    fn exchange(&self) -> &'static str {
+        "binance"
+    }
+
+    fn validate(&mut self, _book: &dyn PriceBook, check: &ExchangeCheck) -> Result<CheckOutcome, ChecksumMismatch> {
+        let ExchangeCheck::UpdateIds { first, last, previous } = *check else {
+            return Err(ChecksumMismatch::Unsupported { exchange: self.exchange(), check: *check });
+        };
+        let Some(last_applied) = self.last_update_id else {
+            return Err(ChecksumMismatch::NotSynced);
+        };
+        let continues = if self.after_snapshot {
+            // Spot needs the update after the snapshot, futures the snapshot's own
+            let target = if previous.is_some() { last_applied } else { last_applied + 1 };
+            if last < target {
+                return Ok(CheckOutcome::Stale);
+            }
+            first <= target && target <= last
+        } else {
+            previous.map_or_else(|| first == last_applied + 1, |previous| previous == last_applied)
+        };
+        if !continues {
+            return Err(ChecksumMismatch::UpdateGap { last_applied, first, last });
+        }
+        self.last_update_id = Some(last);
+        self.after_snapshot = false;
+        Ok(CheckOutcome::Valid)
+    }
+
+    fn reset(&mut self, snapshot_update_id: Option<u64>) {
+        self.last_update_id = snapshot_update_id;
+        self.after_snapshot = snapshot_update_id.is_some();
+    }
+}
+
+/// Request to reload a book whose checks failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResyncRequest {
+    /// Symbol of the book
+    pub symbol: String,
+    /// Exchange whose check failed
+    pub exchange: &'static str,
+    /// Mismatch that triggered the request
+    pub reason: ChecksumMismatch,
+}
+
+/// Callback asked to reload a book from a fresh exchange snapshot
+pub type ResyncRequester = Box<dyn Fn(&ResyncRequest) + Send + Sync>;
+
+/// Validates one book against its exchange's checks
+pub struct BookValidator {
+    symbol: String,
+    validator: Mutex<Box<dyn ChecksumValidator>>,
+    metrics: Arc<PerformanceMetrics>,
+    resync_requester: RwLock<Option<ResyncRequester>>,
+    /// Set from a mismatch until `resynced`, so one bad book raises one request
+    awaiting_resync: AtomicBool,
+}
+
+impl std::fmt::Debug for BookValidator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BookValidator")
+            .field("symbol", &self.symbol)
+            .field("validator", &self.validator)
+            .field("resync_requester", &"<function>")
+            .field("awaiting_resync", &self.awaiting_resync)
+            .finish_non_exhaustive()
+    }
+}
+
+impl BookValidator {
+    /// Create a validator for a book, counting results in `metrics`
+    pub fn new(symbol: impl Into<String>, validator: Box<dyn ChecksumValidator>, metrics: Arc<PerformanceMetrics>) -> Self {
+        Self {
+            symbol: symbol.into(),
+            validator: Mutex::new(validator),
+            metrics,
+            resync_requester: RwLock::new(None),
+            awaiting_resync: AtomicBool::new(false),
+        }
+    }
+
+    /// Set the callback asked to resync the book after a mismatch
+    pub fn set_resync_requester(&self, requester: ResyncRequester) {
+        *self.resync_requester.write() = Some(requester);
+    }
+
+    /// Whether a mismatch was raised and no resync has completed since
+    pub fn is_awaiting_resync(&self) -> bool {
+        self.awaiting_resync.load(Ordering::Acquire)
+    }
+
+    /// Validate the book against the exchange's check
+    ///
+    /// Checks that run are counted in the metrics; stale updates are not. The
+    /// first mismatch requests a resync; later checks fail with `Resyncing`
+    /// without being counted until `resynced` is called.
+    ///
+    /// # Errors
+    ///
+    /// Returns the mismatch if the book does not pass the check.
+    pub fn validate(&self, book: &dyn PriceBook, check: &ExchangeCheck) -> Result<CheckOutcome, ChecksumMismatch> {
+        if self.is_awaiting_resync() {
+            return Err(ChecksumMismatch::Resyncing);
+        }
+        let mut validator = self.validator.lock();
+        let result = validator.validate(book, check);
+        match &result {
+            Ok(CheckOutcome::Valid) => self.metrics.record_checksum(true),
+            Ok(CheckOutcome::Stale) | Err(ChecksumMismatch::Unsupported { .. }) => {}
+            Err(reason) => {
+                self.metrics.record_checksum(false);
+                warn!("{} book failed {} check: {}", self.symbol, validator.exchange(), reason);
+                if !self.awaiting_resync.swap(true, Ordering::AcqRel) {
+                    let request = ResyncRequest {
+                        symbol: self.symbol.clone(),
+                        exchange: validator.exchange(),
+                        reason: reason.clone(),
+                    };
+                    if let Some(requester) = self.resync_requester.read().as_ref() {
+                        requester(&request);
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// Resume validation after the book was reloaded from a snapshot
+    pub fn resynced(&self, snapshot_update_id: Option<u64>) {
+        self.validator.lock().reset(snapshot_update_id);
+        self.awaiting_resync.store(false, Ordering::Release);
+        info!("{} book resynced", self.symbol);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::OrderBook;
+
+    fn level(price: i64, qty: i64) -> (Px, Qty, u64) {
+        (Px::from_i64(price), Qty::from_i64(qty), 1)
+    }
+
+    fn book() -> OrderBook {
+        let book = OrderBook::new("XBT/USD");
+        // 5541.3 x 2.507 and 5541.2 x 1.5 against 5541.4 x 0.1
+        book.load_snapshot(
+            vec![level(55_413_000, 25_070), level(55_412_000, 15_000)],
+            vec![level(55_414_000, 1_000)],
+        );
+        book
+    }
+
+    #[test]
+    fn test_kraken_checksum_format() {
+        let book = book();
+        let expected = crc32fast::hash(b"55414000010000000554130000250700000554120000150000000");
+        let mut validator = KrakenValidator::new(5, 8);
+        assert_eq!(validator.checksum(&book), expected);
+        assert!(validator.validate(&book, &ExchangeCheck::Checksum(expected)).is_ok());
+        assert!(matches!(
+            validator.validate(&book, &ExchangeCheck::Checksum(expected ^ 1)),
+            Err(ChecksumMismatch::Checksum { .. })
+        ));
+    }
+
+    #[test]
+    fn test_okx_checksum_interleaves_levels() {
+        let book = book();
+        let expected = crc32fast::hash(b"5541.3:2.507:5541.4:0.1:5541.2:1.5");
+        assert_eq!(OkxValidator::new(1, 8).checksum(&book), expected);
+        assert_eq!(decimal_string(-12_345, 4), "-1.2345");
+        assert_eq!(decimal_string(20_000, 2), "2");
+    }
+
+    #[test]
+    fn test_binance_update_continuity() {
+        let book = book();
+        let ids = |first, last| ExchangeCheck::UpdateIds { first, last, previous: None };
+        let mut validator = BinanceValidator::new();
+        assert_eq!(validator.validate(&book, &ids(1, 2)), Err(ChecksumMismatch::NotSynced));
+
+        validator.reset(Some(100));
+        assert_eq!(validator.validate(&book, &ids(95, 100)), Ok(CheckOutcome::Stale));
+        assert_eq!(validator.validate(&book, &ids(95, 105)), Ok(CheckOutcome::Valid));
+        assert!(validator.validate(&book, &ids(106, 110)).is_ok());
+        assert_eq!(
+            validator.validate(&book, &ids(112, 115)),
+            Err(ChecksumMismatch::UpdateGap { last_applied: 110, first: 112, last: 115 })
+        );
+
+        // Futures chain on the previous final ID
+        validator.reset(Some(200));
+        let futures = |first, last, previous| ExchangeCheck::UpdateIds { first, last, previous: Some(previous) };
+        assert_eq!(validator.validate(&book, &futures(190, 199, 189)), Ok(CheckOutcome::Stale));
+        assert!(validator.validate(&book, &futures(198, 205, 197)).is_ok());
+        assert!(validator.validate(&book, &futures(207, 210, 205)).is_ok());
+        assert!(validator.validate(&book, &futures(212, 215, 211)).is_err());
+    }
+
+    #[test]
+    fn test_buffered_updates_before_snapshot_are_stale() {
+        let book = book();
+        let metrics = Arc::new(PerformanceMetrics::new("BTCUSDT"));
+        let validator = BookValidator::new("BTCUSDT", Box::new(BinanceValidator::new()), Arc::clone(&metrics));
+        let requests = Arc::new(Mutex::new(0));
+        let sink = Arc::clone(&requests);
+        validator.set_resync_requester(Box::new(move |_: &ResyncRequest| *sink.lock() += 1));
+        validator.resynced(Some(100));
+
+        // Events buffered while the snapshot was fetched, replayed in order
+        let buffered = [(90, 95), (96, 100), (101, 104), (105, 107)];
+        let outcomes: Vec<_> = buffered
+            .iter()
+            .map(|&(first, last)| validator.validate(&book, &ExchangeCheck::UpdateIds { first, last, previous: None }))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![Ok(CheckOutcome::Stale), Ok(CheckOutcome::Stale), Ok(CheckOutcome::Valid), Ok(CheckOutcome::Valid)]
+        );
+        assert!(!validator.is_awaiting_resync());
+        assert_eq!(*requests.lock(), 0);
+
+        let snapshot = metrics.get_snapshot();
+        assert_eq!(snapshot.checksum_matches, 2);
+        assert_eq!(snapshot.checksum_mismatches, 0);
+    }
+
+    #[test]
+    fn test_mismatch_requests_one_resync_and_counts() {
+        let book = book();
+        let metrics = Arc::new(PerformanceMetrics::new("XBT/USD"));
+        let validator = BookValidator::new("XBT/USD", Box::new(KrakenValidator::new(5, 8)), Arc::clone(&metrics));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&requests);
+        validator.set_resync_requester(Box::new(move |request: &ResyncRequest| sink.lock().push(request.clone())));
+
+        let good = KrakenValidator::new(5, 8).checksum(&book);
+        assert!(validator.validate(&book, &ExchangeCheck::Checksum(good)).is_ok());
+        assert!(validator.validate(&book, &ExchangeCheck::Checksum(good ^ 1)).is_err());
+        assert_eq!(validator.validate(&book, &ExchangeCheck::Checksum(good)), Err(ChecksumMismatch::Resyncing));
+        assert!(validator.is_awaiting_resync());
+        assert_eq!(requests.lock().len(), 1);
+        assert_eq!(requests.lock()[0].exchange, "kraken");
+
+        validator.resynced(None);
+        assert!(validator.validate(&book, &ExchangeCheck::Checksum(good)).is_ok());
+
+        let snapshot = metrics.get_snapshot();
+        assert_eq!(snapshot.checksum_matches, 2);
+        assert_eq!(snapshot.checksum_mismatches, 1);
+    }
+}
@@ -0,0 +1,475 @@
+//! Consolidated book for one instrument traded on several venues
+//!
+//! `ConsolidatedBook` keeps an `OrderBook` per venue and merges them into a
+//! depth view attributing each price level to the venues quoting it, and a
+//! national best bid and offer (NBBO). A venue whose book has not been
+//! updated within the staleness limit is flagged and left out of the NBBO,
+//! so a dead feed cannot hold the consolidated quote at an old price.
+//!
+//! Every change to the NBBO is published as an `NbboUpdate` on a broadcast
+//! channel, for routers and strategies to consume. Staleness is judged
+//! against times supplied by the caller, so replays are deterministic.
+
+use crate::core::{OrderBook, Side};
+use anyhow::{Result, bail};
+use parking_lot::{Mutex, RwLock};
+use services_common::{Px, Qty, Ts};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::broadcast;
+use tracing::{debug, info};
+
+/// NBBO updates buffered per subscriber before the slowest starts lagging
+const NBBO_CHANNEL_CAPACITY: usize = 1024;
+
+/// Venue's share of a price level
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VenueQuantity {
+    /// Venue name
+    pub venue: String,
+    /// Quantity at the price on this venue
+    pub quantity: Qty,
+    /// Orders at the price on this venue
+    pub order_count: u64,
+}
+
+/// Price level of the merged book
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsolidatedLevel {
+    /// Price of the level
+    pub price: Px,
+    /// Quantity across all venues
+    pub quantity: Qty,
+    /// Orders across all venues
+    pub order_count: u64,
+    /// Venues quoting the price, largest quantity first
+    pub venues: Vec<VenueQuantity>,
+}
+
+/// Best price on one side of the consolidated book
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BestQuote {
+    /// Best price across fresh venues
+    pub price: Px,
+    /// Quantity at that price across fresh venues
+    pub quantity: Qty,
+    /// Venues quoting that price, largest quantity first
+    pub venues: Vec<VenueQuantity>,
+}
+
+/// Top of one venue's book
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VenueStatus {
+    /// Venue name
+    pub venue: String,
+    /// Best bid on the venue
+    pub best_bid: Option<Px>,
+    /// Best ask on the venue
+    pub best_ask: Option<Px>,
+    /// Time of the venue's last update
+    pub last_update: Ts,
+    /// Whether the venue missed the staleness limit and is left out of the NBBO
+    pub stale: bool,
+}
+
+/// Consolidated best bid and offer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nbbo {
+    /// Best bid across fresh venues
+    pub bid: Option<BestQuote>,
+    /// Best ask across fresh venues
+    pub ask: Option<BestQuote>,
+    /// Top of each venue's book, by venue name
+    pub venues: Vec<VenueStatus>,
+}
+
+impl Nbbo {
+    /// Whether the best bid is at or above the best ask, e.g. across venues
+    /// that have not yet arbitraged against each other
+    #[must_use] pub fn is_crossed(&self) -> bool {
+        matches!((&self.bid, &self.ask), (Some(bid), Some(ask)) if bid.price >= ask.price)
+    }
+
+    /// Spread between the best ask and bid in price units
+    #[must_use] pub fn spread(&self) -> Option<i64> {
+        Some(self.ask.as_ref()?.price.as_i64() - self.bid.as_ref()?.price.as_i64())
+    }
+}
+
+/// Change to the NBBO
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NbboUpdate {
+    /// Instrument of the book
+    pub instrument: String,
+    /// Time of the update that changed the NBBO
+    pub time: Ts,
+    /// New best bid
+    pub bid: Option<BestQuote>,
+    /// New best ask
+    pub ask: Option<BestQuote>,
+    /// Venue whose update changed the NBBO, or `None` if a venue went stale
+    pub venue: Option<String>,
+}
+
+/// One venue's book and when it last changed
+#[derive(Debug)]
+struct VenueBook {
+    book: Arc<OrderBook>,
+    last_update: AtomicU64,
+}
+
+/// Book for one instrument consolidated across venues
+#[derive(Debug)]
+pub struct ConsolidatedBook {
+    instrument: String,
+    /// Nanoseconds without an update after which a venue is stale
+    stale_after: u64,
+    venues: RwLock<BTreeMap<String, VenueBook>>,
+    /// Best bid and ask last published
+    published: Mutex<(Option<BestQuote>, Option<BestQuote>)>,
+    updates: broadcast::Sender<NbboUpdate>,
+}
+
+impl ConsolidatedBook {
+    /// Create a consolidated book treating venues as stale after
+    /// `stale_after` nanoseconds without an update
+    #[must_use] pub fn new(instrument: impl Into<String>, stale_after: u64) -> Self {
+        Self {
+            instrument: instrument.into(),
+            stale_after,
+            venues: RwLock::new(BTreeMap::new()),
+            published: Mutex::new((None, None)),
+            updates: broadcast::channel(NBBO_CHANNEL_CAPACITY).0,
+        }
+    }
+
+    /// Instrument of the book
+    #[must_use] pub fn instrument(&self) -> &str {
+        &self.instrument
+    }
+
+    /// Add a venue with an empty book, returning the book
+    ///
+    /// The venue is stale until its first update. Adding a venue that is
+    /// already present returns its existing book.
+    pub fn add_venue(&self, venue: impl Into<String>) -> Arc<OrderBook> {
+        let venue = venue.into();
+        let mut venues = self.venues.write();
+        let entry = venues.entry(venue.clone()).or_insert_with(|| {
+            info!("Adding venue {} to consolidated {} book", venue, self.instrument);
+            VenueBook {
+                book: Arc::new(OrderBook::new(format!("{}@{}", self.instrument, venue))),
+                last_update: AtomicU64::new(0),
+            }
+        });
+        Arc::clone(&entry.book)
+    }
+
+    /// Remove a venue, publishing the NBBO without it
+    pub fn remove_venue(&self, venue: &str, now: Ts) -> Option<Arc<OrderBook>> {
+        let removed = self.venues.write().remove(venue)?;
+        info!("Removed venue {} from consolidated {} book", venue, self.instrument);
+        self.publish_if_changed(now, None);
+        Some(removed.book)
+    }
+
+    /// Update a venue's book at exchange time `time`, publishing the NBBO if it changed
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the venue has not been added.
+    pub fn update_venue<R>(&self, venue: &str, time: Ts, update: impl FnOnce(&OrderBook) -> R) -> Result<R> {
+        let result = {
+            let venues = self.venues.read();
+            let Some(entry) = venues.get(venue) else {
+                bail!("Venue {} is not part of the {} book", venue, self.instrument);
+            };
+            let result = update(&entry.book);
+            entry.last_update.fetch_max(time.as_nanos(), Ordering::AcqRel);
+            result
+        };
+        self.publish_if_changed(time, Some(venue));
+        Ok(result)
+    }
+
+    /// Replace a venue's book with a snapshot of levels
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the venue has not been added.
+    pub fn apply_snapshot(
+        &self,
+        venue: &str,
+        bids: Vec<(Px, Qty, u64)>,
+        asks: Vec<(Px, Qty, u64)>,
+        time: Ts,
+    ) -> Result<()> {
+        self.update_venue(venue, time, |book| book.load_snapshot(bids, asks))
+    }
+
+    /// Set a `(price, quantity, order_count)` level on a venue; zero quantity removes it
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the venue has not been added.
+    pub fn set_level(&self, venue: &str, side: Side, level: (Px, Qty, u64), time: Ts) -> Result<()> {
+        let (price, quantity, order_count) = level;
+        self.update_venue(venue, time, |book| book.set_level(side, price, quantity, order_count))
+    }
+
+    /// Publish the NBBO if venues going stale by `now` changed it
+    ///
+    /// Staleness only changes with time, so feeds that stop updating are
+    /// only noticed when this, an update or a query runs.
+    pub fn check_staleness(&self, now: Ts) {
+        self.publish_if_changed(now, None);
+    }
+
+    /// Subscribe to NBBO changes
+    #[must_use] pub fn subscribe(&self) -> broadcast::Receiver<NbboUpdate> {
+        self.updates.subscribe()
+    }
+
+    /// Consolidated best bid and offer at `now`
+    #[must_use] pub fn nbbo(&self, now: Ts) -> Nbbo {
+        let venues = self.venues.read();
+        let mut bid: Option<BestQuote> = None;
+        let mut ask: Option<BestQuote> = None;
+        let mut statuses = Vec::with_capacity(venues.len());
+
+        for (name, entry) in venues.iter() {
+            let last_update = entry.last_update.load(Ordering::Acquire);
+            let stale = self.is_stale(last_update, now.as_nanos());
+            let (bids, asks) = entry.book.get_depth(1);
+            let best_bid = bids.first().copied();
+            let best_ask = asks.first().copied();
+            statuses.push(VenueStatus {
+                venue: name.clone(),
+                best_bid: best_bid.map(|(price, _, _)| price),
+                best_ask: best_ask.map(|(price, _, _)| price),
+                last_update: Ts::from_nanos(last_update),
+                stale,
+            });
+            if stale {
+                continue;
+            }
+            if let Some(level) = best_bid {
+                merge_best(&mut bid, name, level, |new, old| new > old);
+            }
+            if let Some(level) = best_ask {
+                merge_best(&mut ask, name, level, |new, old| new < old);
+            }
+        }
+
+        for quote in bid.iter_mut().chain(ask.iter_mut()) {
+            sort_by_quantity(&mut quote.venues);
+        }
+        Nbbo { bid, ask, venues: statuses }
+    }
+
+    /// Top `levels` prices per side merged across venues, with each venue's share
+    ///
+    /// Stale venues are left out unless `include_stale` is set.
+    #[must_use] pub fn merged_depth(
+        &self,
+        levels: usize,
+        now: Ts,
+        include_stale: bool,
+    ) -> (Vec<ConsolidatedLevel>, Vec<ConsolidatedLevel>) {
+        let venues = self.venues.read();
+        let mut bids: BTreeMap<Px, ConsolidatedLevel> = BTreeMap::new();
+        let mut asks: BTreeMap<Px, ConsolidatedLevel> = BTreeMap::new();
+
+        for (name, entry) in venues.iter() {
+            if !include_stale && self.is_stale(entry.last_update.load(Ordering::Acquire), now.as_nanos()) {
+                continue;
+            }
+            // Each venue's top `levels` include every price of the merged top `levels`
+            let (venue_bids, venue_asks) = entry.book.get_depth(levels);
+            for (merged, side_levels) in [(&mut bids, venue_bids), (&mut asks, venue_asks)] {
+                for (price, quantity, order_count) in side_levels {
+                    let level = merged.entry(price).or_insert_with(|| ConsolidatedLevel {
+                        price,
+                        quantity: Qty::ZERO,
+                        order_count: 0,
+                        venues: Vec::new(),
+                    });
+                    level.quantity = level.quantity.add(quantity);
+                    level.order_count += order_count;
+                    level.venues.push(VenueQuantity { venue: name.clone(), quantity, order_count });
+                }
+            }
+        }
+
+        let finish = |mut level: ConsolidatedLevel| {
+            sort_by_quantity(&mut level.venues);
+            level
+        };
+        (
+            bids.into_values().rev().take(levels).map(finish).collect(),
+            asks.into_values().take(levels).map(finish).collect(),
+        )
+    }
+
+    /// Venue names, sorted
+    #[must_use] pub fn venues(&self) -> Vec<String> {
+        self.venues.read().keys().cloned().collect()
+    }
+
+    /// Book of one venue
+    #[must_use] pub fn venue_book(&self, venue: &str) -> Option<Arc<OrderBook>> {
+        self.venues.read().get(venue).map(|entry| Arc::clone(&entry.book))
+    }
+
+    const fn is_stale(&self, last_update: u64, now: u64) -> bool {
+        last_update == 0 || now.saturating_sub(last_update) > self.stale_after
+    }
+
+    fn publish_if_changed(&self, now: Ts, venue: Option<&str>) {
+        // Held across computing and sending so updates go out in order
+        let mut published = self.published.lock();
+        let nbbo = self.nbbo(now);
+        if published.0 == nbbo.bid && published.1 == nbbo.ask {
+            return;
+        }
+        *published = (nbbo.bid.clone(), nbbo.ask.clone());
+        debug!("NBBO for {} changed: {:?} / {:?}", self.instrument, nbbo.bid, nbbo.ask);
+        // No subscribers is not an error
+        let _ = self.updates.send(NbboUpdate {
+            instrument: self.instrument.clone(),
+            time: now,
+            bid: nbbo.bid,
+            ask: nbbo.ask,
+            venue: venue.map(str::to_string),
+        });
+    }
+}
+
+/// Folds a venue's best level into the best quote so far
+fn merge_best(best: &mut Option<BestQuote>, venue: &str, level: (Px, Qty, u64), better: impl Fn(Px, Px) -> bool) {
+    let (price, quantity, order_count) = level;
+    let share = VenueQuantity { venue: venue.to_string(), quantity, order_count };
+    match best {
+        Some(quote) if quote.price == price => {
+            quote.quantity = quote.quantity.add(quantity);
+            quote.venues.push(share);
+        }
+        Some(quote) if !better(price, quote.price) => {}
+        _ => *best = Some(BestQuote { price, quantity, venues: vec![share] }),
+    }
+}
+
+/// Orders venue shares largest quantity first, then by venue name
+fn sort_by_quantity(venues: &mut [VenueQuantity]) {
+    venues.sort_by(|a, b| b.quantity.cmp(&a.quantity).then_with(|| a.venue.cmp(&b.venue)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    fn px(price: i64) -> Px {
+        Px::from_i64(price)
+    }
+
+    fn qty(quantity: i64) -> Qty {
+        Qty::from_i64(quantity)
+    }
+
+    fn ts(seconds: u64) -> Ts {
+        Ts::from_nanos(seconds * SECOND)
+    }
+
+    fn book_with_venues() -> ConsolidatedBook {
+        let book = ConsolidatedBook::new("NIFTY", 5 * SECOND);
+        book.add_venue("NSE");
+        book.add_venue("BSE");
+        book
+    }
+
+    #[test]
+    fn test_nbbo_picks_best_price_across_venues() -> Result<()> {
+        let book = book_with_venues();
+        book.apply_snapshot("NSE", vec![(px(100), qty(10), 1)], vec![(px(103), qty(10), 1)], ts(1))?;
+        book.apply_snapshot("BSE", vec![(px(101), qty(5), 1)], vec![(px(103), qty(20), 2)], ts(1))?;
+
+        let nbbo = book.nbbo(ts(2));
+        let bid = nbbo.bid.clone().expect("bid");
+        let ask = nbbo.ask.clone().expect("ask");
+        assert_eq!(bid.price, px(101));
+        assert_eq!(bid.venues.len(), 1);
+        assert_eq!(bid.venues[0].venue, "BSE");
+        assert_eq!(ask.price, px(103));
+        assert_eq!(ask.quantity, qty(30));
+        let venues: Vec<_> = ask.venues.iter().map(|share| share.venue.as_str()).collect();
+        assert_eq!(venues, ["BSE", "NSE"]);
+        assert_eq!(nbbo.spread(), Some(2));
+        assert!(!nbbo.is_crossed());
+        Ok(())
+    }
+
+    #[test]
+    fn test_stale_venue_is_flagged_and_excluded() -> Result<()> {
+        let book = book_with_venues();
+        book.apply_snapshot("NSE", vec![(px(100), qty(10), 1)], vec![(px(103), qty(10), 1)], ts(1))?;
+        book.apply_snapshot("BSE", vec![(px(101), qty(5), 1)], vec![(px(102), qty(5), 1)], ts(4))?;
+
+        let nbbo = book.nbbo(ts(8));
+        assert!(nbbo.venues.iter().find(|status| status.venue == "NSE").expect("NSE").stale);
+        assert!(!nbbo.venues.iter().find(|status| status.venue == "BSE").expect("BSE").stale);
+
+        let nbbo = book.nbbo(ts(10));
+        assert!(nbbo.bid.is_none());
+        assert!(nbbo.venues.iter().all(|status| status.stale));
+        Ok(())
+    }
+
+    #[test]
+    fn test_merged_depth_attributes_venues() -> Result<()> {
+        let book = book_with_venues();
+        book.apply_snapshot("NSE", vec![(px(100), qty(10), 1), (px(99), qty(7), 1)], vec![], ts(1))?;
+        book.apply_snapshot("BSE", vec![(px(100), qty(20), 3), (px(98), qty(4), 1)], vec![], ts(1))?;
+
+        let (bids, asks) = book.merged_depth(2, ts(1), false);
+        assert!(asks.is_empty());
+        assert_eq!(bids.len(), 2);
+        assert_eq!(bids[0].price, px(100));
+        assert_eq!(bids[0].quantity, qty(30));
+        assert_eq!(bids[0].order_count, 4);
+        assert_eq!(bids[0].venues[0].venue, "BSE");
+        assert_eq!(bids[1].price, px(99));
+        assert_eq!(bids[1].venues.len(), 1);
+
+        let (bids, _) = book.merged_depth(2, ts(20), false);
+        assert!(bids.is_empty());
+        let (bids, _) = book.merged_depth(2, ts(20), true);
+        assert_eq!(bids.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_nbbo_changes_are_published() -> Result<()> {
+        let book = book_with_venues();
+        let mut updates = book.subscribe();
+
+        book.set_level("NSE", Side::Bid, (px(100), qty(10), 1), ts(1))?;
+        let update = updates.try_recv()?;
+        assert_eq!(update.venue.as_deref(), Some("NSE"));
+        assert_eq!(update.bid.expect("bid").price, px(100));
+
+        // Worse price on another venue leaves the NBBO unchanged
+        book.set_level("BSE", Side::Bid, (px(99), qty(10), 1), ts(2))?;
+        assert!(updates.try_recv().is_err());
+
+        book.check_staleness(ts(7));
+        let update = updates.try_recv()?;
+        assert_eq!(update.venue, None);
+        assert_eq!(update.bid.expect("bid").price, px(99));
+
+        assert!(book.set_level("MCX", Side::Bid, (px(100), qty(1), 1), ts(8)).is_err());
+        Ok(())
+    }
+}
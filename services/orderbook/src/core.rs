@@ -15,6 +15,9 @@ use ahash::AHashMap;
 use smallvec::SmallVec;
 // ArrayVec import removed - not currently used
 
+/// Levels per side covered by the book checksum
+pub(crate) const CHECKSUM_DEPTH: usize = 25;
+
 /// Side of the order book (Bid or Ask)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Side {
@@ -375,16 +378,8 @@ impl OrderBook {
 
     /// Calculate checksum for integrity validation (Binance-style)
     fn update_checksum(&self) {
-        let (bids, asks) = self.get_depth(25);
-        
-        let mut checksum_str = String::new();
-        for (price, qty, _) in bids.iter().chain(asks.iter()).take(50) {
-            checksum_str.push_str(&format!("{:.2}:{:.4}", 
-                price.as_f64(), qty.as_f64()));
-        }
-        
-        let checksum = crc32fast::hash(checksum_str.as_bytes());
-        self.checksum.store(u64::from(checksum), Ordering::Release);
+        let (bids, asks) = self.get_depth(CHECKSUM_DEPTH);
+        self.checksum.store(depth_checksum(&bids, &asks), Ordering::Release);
     }
 
     /// Get current checksum for validation
@@ -417,6 +412,50 @@ impl OrderBook {
         asks.get(&price_key).map(|level| level.get_quantity())
     }
 
+    /// Set the total quantity resting at a price level (L2 update)
+    /// 
+    /// Replaces whatever rests at `price` with `quantity` spread over
+    /// `order_count` synthetic orders, as L2 feeds report level totals
+    /// rather than individual orders. A zero quantity removes the level.
+    pub fn set_level(&self, side: Side, price: Px, quantity: Qty, order_count: u64) {
+        let _seq = self.sequence.fetch_add(1, Ordering::AcqRel);
+        let price_key = self.get_price_key(price, side);
+        let (levels, volume) = match side {
+            Side::Bid => (&self.bids, &self.total_bid_volume),
+            Side::Ask => (&self.asks, &self.total_ask_volume),
+        };
+        
+        let replaced = {
+            let mut levels = levels.write();
+            let replaced = levels.remove(&price_key);
+            if let Some(old) = &replaced {
+                volume.fetch_sub(old.get_quantity().as_i64(), Ordering::Release);
+            }
+            if quantity.as_i64() > 0 {
+                levels.insert(price_key, synthetic_level(price, quantity, order_count, side));
+                volume.fetch_add(quantity.as_i64(), Ordering::Release);
+            }
+            
+            // Keys are ordered best first on both sides
+            let best = levels.keys().next().copied();
+            match side {
+                Side::Bid => self.best_bid.store(best.map_or(0, |k| -k), Ordering::Release),
+                Side::Ask => self.best_ask.store(best.unwrap_or(i64::MAX), Ordering::Release),
+            }
+            replaced
+        };
+        
+        // Orders that were resting at the level can no longer be cancelled by ID
+        if let Some(old) = replaced {
+            let mut map = self.order_map.write();
+            for order in old.orders.read().iter() {
+                map.remove(&order.id);
+            }
+        }
+        
+        self.update_checksum();
+    }
+
     /// Get price key for `BTreeMap` storage
     #[inline]
     const fn get_price_key(&self, price: Px, side: Side) -> i64 {
@@ -466,23 +505,7 @@ impl OrderBook {
         
         for (price, quantity, order_count) in bid_levels {
             let price_key = self.get_price_key(price, Side::Bid);
-            let level = PriceLevel::new(price);
-            
-            // Create synthetic orders to match count and quantity
-            let qty_per_order = quantity.as_i64() / order_count.max(1) as i64;
-            for i in 0..order_count {
-                let order = Order {
-                    id: u64::MAX - i, // Synthetic IDs
-                    price,
-                    quantity: Qty::from_i64(qty_per_order),
-                    original_quantity: Qty::from_i64(qty_per_order),
-                    timestamp: Ts::now(),
-                    side: Side::Bid,
-                    is_iceberg: false,
-                    visible_quantity: None,
-                };
-                level.add_order(order);
-            }
+            let level = synthetic_level(price, quantity, order_count, Side::Bid);
             
             if price.as_i64() > best_bid {
                 best_bid = price.as_i64();
@@ -501,23 +524,7 @@ impl OrderBook {
         
         for (price, quantity, order_count) in ask_levels {
             let price_key = self.get_price_key(price, Side::Ask);
-            let level = PriceLevel::new(price);
-            
-            // Create synthetic orders to match count and quantity
-            let qty_per_order = quantity.as_i64() / order_count.max(1) as i64;
-            for i in 0..order_count {
-                let order = Order {
-                    id: u64::MAX / 2 - i, // Different synthetic IDs for asks
-                    price,
-                    quantity: Qty::from_i64(qty_per_order),
-                    original_quantity: Qty::from_i64(qty_per_order),
-                    timestamp: Ts::now(),
-                    side: Side::Ask,
-                    is_iceberg: false,
-                    visible_quantity: None,
-                };
-                level.add_order(order);
-            }
+            let level = synthetic_level(price, quantity, order_count, Side::Ask);
             
             if price.as_i64() < best_ask {
                 best_ask = price.as_i64();
@@ -538,4 +545,158 @@ impl OrderBook {
         // Update checksum
         self.update_checksum();
     }
-}
\ No newline at end of file
+}
+
+/// Price level holding `quantity` split across `order_count` synthetic orders
+/// 
+/// Any remainder of the split goes to the first order so the orders add up
+/// to `quantity`.
+fn synthetic_level(price: Px, quantity: Qty, order_count: u64, side: Side) -> PriceLevel {
+    let level = PriceLevel::new(price);
+    let order_count = order_count.max(1);
+    let qty_per_order = quantity.as_i64() / order_count as i64;
+    let remainder = quantity.as_i64() - qty_per_order * order_count as i64;
+    // Synthetic IDs, counting down from separate bases for each side
+    let base_id = match side {
+        Side::Bid => u64::MAX,
+        Side::Ask => u64::MAX / 2,
+    };
+    
+    for i in 0..order_count {
+        let order_quantity = Qty::from_i64(qty_per_order + if i == 0 { remainder } else { 0 });
+        level.add_order(Order {
+            id: base_id - i,
+            price,
+            quantity: order_quantity,
+            original_quantity: order_quantity,
+            timestamp: Ts::now(),
+            side,
+            is_iceberg: false,
+            visible_quantity: None,
+        });
+    }
+    
+    level
+}
+
+/// Checksum over the top levels of both sides (Binance-style)
+/// 
+/// Shared by every `PriceBook` so equal books have equal checksums.
+pub(crate) fn depth_checksum(bids: &[(Px, Qty, u64)], asks: &[(Px, Qty, u64)]) -> u64 {
+    let mut checksum_str = String::new();
+    for (price, qty, _) in bids.iter().chain(asks.iter()).take(2 * CHECKSUM_DEPTH) {
+        checksum_str.push_str(&format!("{:.2}:{:.4}", 
+            price.as_f64(), qty.as_f64()));
+    }
+    
+    u64::from(crc32fast::hash(checksum_str.as_bytes()))
+}
+
+/// Operations common to order book implementations
+/// 
+/// Lets replay, analytics and tests run against either the `BTreeMap`
+/// backed `OrderBook` or the array-backed `LadderBook`.
+pub trait PriceBook: Send + Sync {
+    /// Symbol of the book
+    fn symbol(&self) -> &str;
+
+    /// Add an order, returning the update's sequence number
+    fn add_order(&self, order: Order) -> u64;
+
+    /// Cancel an order by ID, returning it
+    fn cancel_order(&self, order_id: u64) -> Option<Order>;
+
+    /// Best bid and ask prices
+    fn get_bbo(&self) -> (Option<Px>, Option<Px>);
+
+    /// Top `levels` levels of each side as `(price, quantity, order_count)`, best first
+    fn get_depth(&self, levels: usize) -> (Vec<(Px, Qty, u64)>, Vec<(Px, Qty, u64)>);
+
+    /// Checksum of the top of book
+    fn get_checksum(&self) -> u64;
+
+    /// Bid quantity at a price
+    fn get_bid_size_at(&self, price: Px) -> Option<Qty>;
+
+    /// Ask quantity at a price
+    fn get_ask_size_at(&self, price: Px) -> Option<Qty>;
+
+    /// Set the total quantity at a price level; zero removes the level
+    fn set_level(&self, side: Side, price: Px, quantity: Qty, order_count: u64);
+
+    /// Remove every level
+    fn clear(&self);
+
+    /// Replace the book with snapshot levels
+    fn load_snapshot(&self, bid_levels: Vec<(Px, Qty, u64)>, ask_levels: Vec<(Px, Qty, u64)>);
+
+    /// Spread between best ask and bid
+    fn get_spread(&self) -> Option<i64> {
+        match self.get_bbo() {
+            (Some(bid), Some(ask)) => Some(ask.as_i64() - bid.as_i64()),
+            _ => None,
+        }
+    }
+
+    /// Average of best bid and ask
+    fn get_mid(&self) -> Option<Px> {
+        match self.get_bbo() {
+            (Some(bid), Some(ask)) => Some(Px::from_i64((bid.as_i64() + ask.as_i64()) / 2)),
+            _ => None,
+        }
+    }
+}
+
+impl PriceBook for OrderBook {
+    fn symbol(&self) -> &str {
+        Self::symbol(self)
+    }
+
+    fn add_order(&self, order: Order) -> u64 {
+        Self::add_order(self, order)
+    }
+
+    fn cancel_order(&self, order_id: u64) -> Option<Order> {
+        Self::cancel_order(self, order_id)
+    }
+
+    fn get_bbo(&self) -> (Option<Px>, Option<Px>) {
+        Self::get_bbo(self)
+    }
+
+    fn get_depth(&self, levels: usize) -> (Vec<(Px, Qty, u64)>, Vec<(Px, Qty, u64)>) {
+        Self::get_depth(self, levels)
+    }
+
+    fn get_checksum(&self) -> u64 {
+        Self::get_checksum(self)
+    }
+
+    fn get_bid_size_at(&self, price: Px) -> Option<Qty> {
+        Self::get_bid_size_at(self, price)
+    }
+
+    fn get_ask_size_at(&self, price: Px) -> Option<Qty> {
+        Self::get_ask_size_at(self, price)
+    }
+
+    fn set_level(&self, side: Side, price: Px, quantity: Qty, order_count: u64) {
+        Self::set_level(self, side, price, quantity, order_count);
+    }
+
+    fn clear(&self) {
+        Self::clear(self);
+    }
+
+    fn load_snapshot(&self, bid_levels: Vec<(Px, Qty, u64)>, ask_levels: Vec<(Px, Qty, u64)>) {
+        Self::load_snapshot(self, bid_levels, ask_levels);
+    }
+
+    fn get_spread(&self) -> Option<i64> {
+        Self::get_spread(self)
+    }
+
+    fn get_mid(&self) -> Option<Px> {
+        Self::get_mid(self)
+    }
+}
@@ -0,0 +1,803 @@
+//! gRPC service exposing order books to other services
+//!
+//! Books are built from the events received through `IngestEvents`, one
+//! `ReplayEngine` per symbol, so out-of-order events are buffered and
+//! replayed in sequence exactly as in offline replay. Trades the book applies
+//! also feed each symbol's `MicrostructureAnalytics` and `ToxicityDetector`.
+//!
+//! Only ingestion creates books; reads and streams for other symbols fail
+//! with `NotFound`.
+//!
+//! A book's sequence is the replay engine's last applied sequence.
+//! `StreamBook` sends a snapshot of the requested depth, then a `BookDelta`
+//! whenever the top levels change; a slow client gets one delta covering
+//! every change since its last message rather than a backlog, so deltas
+//! always apply cleanly to the previous message.
+
+use crate::analytics::{ImbalanceCalculator, MicrostructureAnalytics, ToxicityDetector};
+use crate::events::{
+    LevelUpdate, MarketEvent, MarketEventType, OrderBookDelta, OrderBookEvent, OrderBookSnapshot, OrderUpdate, Side,
+    TradeEvent, UpdateType,
+};
+use crate::replay::{ReplayConfig, ReplayEngine};
+use ahash::AHashMap;
+use anyhow::{Context, Result};
+use parking_lot::RwLock;
+use services_common::proto::orderbook::v1 as pb;
+use services_common::proto::OrderBookService as OrderBookTrait;
+use services_common::{Px, Qty, Symbol, Ts};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::Stream;
+use tonic::{Request, Response, Status, Streaming};
+use tracing::{debug, info, warn};
+
+/// Levels per side when a request leaves the depth at 0
+pub const DEFAULT_DEPTH: usize = 20;
+
+/// Most levels per side a request may ask for
+pub const MAX_DEPTH: usize = 500;
+
+/// Analytics interval when a request leaves it at 0
+pub const DEFAULT_ANALYTICS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Shortest analytics interval a request may ask for
+pub const MIN_ANALYTICS_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Messages buffered per stream before the sender waits for the client
+const STREAM_CHANNEL_CAPACITY: usize = 64;
+
+/// Levels of one side of a book: price, quantity and order count, best first
+type Levels = Vec<(Px, Qty, u64)>;
+
+/// Sequence and time of the last event applied to a book
+#[derive(Debug, Default, Clone, Copy)]
+struct BookClock {
+    sequence: u64,
+    exchange_time: u64,
+}
+
+/// Top of a book at one sequence
+#[derive(Debug)]
+struct DepthView {
+    clock: BookClock,
+    bids: Levels,
+    asks: Levels,
+    checksum: u64,
+}
+
+/// Book and analytics for one symbol
+#[derive(Debug)]
+struct SymbolBook {
+    replay: ReplayEngine,
+    analytics: Arc<MicrostructureAnalytics>,
+    toxicity: Arc<ToxicityDetector>,
+    /// Held for writing while an event is applied, so readers see whole events
+    clock: RwLock<BookClock>,
+    /// Sequence of the last event applied, for stream tasks to wait on
+    changes: watch::Sender<u64>,
+}
+
+impl SymbolBook {
+    fn new(symbol: &str, config: ReplayConfig) -> Self {
+        let replay = ReplayEngine::new(symbol, config);
+        let analytics = Arc::new(MicrostructureAnalytics::new());
+        let toxicity = Arc::new(ToxicityDetector::new());
+        let (trade_analytics, trade_toxicity) = (Arc::clone(&analytics), Arc::clone(&toxicity));
+        replay.set_trade_listener(move |trade| {
+            let is_buy = trade.aggressor_side.is_buy();
+            trade_analytics.update_trade(trade.price, trade.quantity, is_buy, trade.exchange_time);
+            trade_toxicity.update(is_buy, trade.quantity, trade.exchange_time);
+        });
+        Self {
+            replay,
+            analytics,
+            toxicity,
+            clock: RwLock::new(BookClock::default()),
+            changes: watch::channel(0).0,
+        }
+    }
+
+    /// Applies an event, returning the book's sequence afterwards
+    ///
+    /// Events the engine buffers or drops leave the sequence, and the
+    /// streams waiting on it, untouched.
+    fn apply(&self, event: OrderBookEvent) -> Result<u64> {
+        let (sequence, advanced) = {
+            let mut clock = self.clock.write();
+            self.replay.process_event(event)?;
+            let sequence = self.replay.last_sequence();
+            let advanced = sequence != clock.sequence;
+            if advanced {
+                clock.sequence = sequence;
+                clock.exchange_time = self.replay.last_exchange_time().as_nanos();
+            }
+            (sequence, advanced)
+        };
+        if advanced {
+            self.changes.send_replace(sequence);
+        }
+        Ok(sequence)
+    }
+
+    fn depth(&self, levels: usize) -> DepthView {
+        let clock = self.clock.read();
+        let ((bids, asks), checksum) =
+            self.replay.with_orderbook(|book| (book.get_depth(levels), book.get_checksum()));
+        DepthView { clock: *clock, bids, asks, checksum }
+    }
+
+    fn analytics(&self, symbol: &str) -> pb::AnalyticsUpdate {
+        let view = self.depth(10);
+        let imbalance = ImbalanceCalculator::calculate_imbalances(&view.bids, &view.asks);
+        pb::AnalyticsUpdate {
+            symbol: symbol.to_string(),
+            sequence: view.clock.sequence,
+            timestamp_nanos: view.clock.exchange_time,
+            top_level_imbalance: imbalance.top_level_imbalance,
+            three_level_imbalance: imbalance.three_level_imbalance,
+            five_level_imbalance: imbalance.five_level_imbalance,
+            ten_level_imbalance: imbalance.ten_level_imbalance,
+            weighted_mid_price: imbalance.weighted_mid_price.as_i64(),
+            buy_pressure: imbalance.buy_pressure,
+            sell_pressure: imbalance.sell_pressure,
+            vpin: self.analytics.get_vpin(),
+            kyles_lambda: self.analytics.get_kyles_lambda(),
+            flow_imbalance: self.analytics.get_flow_imbalance(),
+            pin: self.analytics.get_pin(),
+            toxicity: self.toxicity.get_toxicity(),
+        }
+    }
+}
+
+/// gRPC service serving books built from ingested events
+pub struct OrderBookService {
+    config: ReplayConfig,
+    books: RwLock<AHashMap<String, Arc<SymbolBook>>>,
+}
+
+impl std::fmt::Debug for OrderBookService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OrderBookService")
+            .field("config", &self.config)
+            .field("symbols", &self.books.read().len())
+            .finish()
+    }
+}
+
+impl Default for OrderBookService {
+    fn default() -> Self {
+        Self::new(ReplayConfig::default())
+    }
+}
+
+impl OrderBookService {
+    /// Create a service whose books replay events under `config`
+    #[must_use] pub fn new(config: ReplayConfig) -> Self {
+        Self { config, books: RwLock::new(AHashMap::new()) }
+    }
+
+    /// Applies an event to the book for `symbol`, creating the book if needed
+    ///
+    /// Returns the book's sequence after the event.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the replay engine refuses the event, e.g. on a
+    /// checksum mismatch.
+    pub fn ingest(&self, symbol: &str, event: OrderBookEvent) -> Result<u64> {
+        self.book(symbol).apply(event)
+    }
+
+    /// Symbols with a book, sorted
+    pub fn symbols(&self) -> Vec<String> {
+        let mut symbols: Vec<_> = self.books.read().keys().cloned().collect();
+        symbols.sort();
+        symbols
+    }
+
+    fn book(&self, symbol: &str) -> Arc<SymbolBook> {
+        if let Some(book) = self.books.read().get(symbol) {
+            return Arc::clone(book);
+        }
+        let mut books = self.books.write();
+        Arc::clone(
+            books
+                .entry(symbol.to_string())
+                .or_insert_with(|| Arc::new(SymbolBook::new(symbol, self.config.clone()))),
+        )
+    }
+
+    fn existing_book(&self, symbol: &str) -> Result<Arc<SymbolBook>, Status> {
+        self.books
+            .read()
+            .get(symbol)
+            .cloned()
+            .ok_or_else(|| Status::not_found(format!("No book for symbol {symbol}")))
+    }
+}
+
+#[tonic::async_trait]
+impl OrderBookTrait for OrderBookService {
+    type StreamBookStream = Pin<Box<dyn Stream<Item = Result<pb::BookUpdate, Status>> + Send>>;
+    type StreamAnalyticsStream = Pin<Box<dyn Stream<Item = Result<pb::AnalyticsUpdate, Status>> + Send>>;
+
+    async fn get_snapshot(
+        &self,
+        request: Request<pb::GetSnapshotRequest>,
+    ) -> Result<Response<pb::BookSnapshot>, Status> {
+        let req = request.into_inner();
+        let depth = requested_depth(req.depth)?;
+        let book = self.existing_book(&req.symbol)?;
+        Ok(Response::new(to_proto_snapshot(&req.symbol, &book.depth(depth))))
+    }
+
+    /// Streams a book that events have been ingested for
+    async fn stream_book(
+        &self,
+        request: Request<pb::StreamBookRequest>,
+    ) -> Result<Response<Self::StreamBookStream>, Status> {
+        let req = request.into_inner();
+        let depth = requested_depth(req.depth)?;
+        let book = self.existing_book(&req.symbol)?;
+        info!("Streaming {} levels of {}", depth, req.symbol);
+
+        // Subscribe before the snapshot so no change can fall between them
+        let mut changes = book.changes.subscribe();
+        let mut last = book.depth(depth);
+        let snapshot = pb::BookUpdate {
+            update: Some(pb::book_update::Update::Snapshot(to_proto_snapshot(&req.symbol, &last))),
+        };
+        let (tx, rx) = mpsc::channel(STREAM_CHANNEL_CAPACITY);
+        tx.send(Ok(snapshot)).await.map_err(|_| Status::cancelled("Client went away"))?;
+
+        tokio::spawn(async move {
+            while changes.changed().await.is_ok() {
+                let view = book.depth(depth);
+                let Some(delta) = diff_views(&req.symbol, &last, &view) else {
+                    continue;
+                };
+                let update = pb::BookUpdate { update: Some(pb::book_update::Update::Delta(delta)) };
+                if tx.send(Ok(update)).await.is_err() {
+                    debug!("Book stream for {} closed", req.symbol);
+                    break;
+                }
+                last = view;
+            }
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+
+    /// Streams analytics for a book that events have been ingested for
+    async fn stream_analytics(
+        &self,
+        request: Request<pb::StreamAnalyticsRequest>,
+    ) -> Result<Response<Self::StreamAnalyticsStream>, Status> {
+        let req = request.into_inner();
+        let period = requested_interval(req.interval_ms)?;
+        let book = self.existing_book(&req.symbol)?;
+        info!("Streaming analytics for {} every {:?}", req.symbol, period);
+
+        let (tx, rx) = mpsc::channel(STREAM_CHANNEL_CAPACITY);
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(period);
+            loop {
+                ticks.tick().await;
+                if tx.send(Ok(book.analytics(&req.symbol))).await.is_err() {
+                    debug!("Analytics stream for {} closed", req.symbol);
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+
+    /// Applies events until the client closes the stream
+    ///
+    /// Malformed events and events the book refuses are counted and skipped
+    /// rather than ending the stream, so one bad message does not stop a feed.
+    async fn ingest_events(
+        &self,
+        request: Request<Streaming<pb::BookEvent>>,
+    ) -> Result<Response<pb::IngestEventsResponse>, Status> {
+        let mut events = request.into_inner();
+        let mut response = pb::IngestEventsResponse::default();
+
+        while let Some(message) = events.message().await? {
+            let applied = event_from_proto(message).and_then(|(symbol, event)| self.ingest(&symbol, event));
+            match applied {
+                Ok(_) => response.events_accepted += 1,
+                Err(e) => {
+                    warn!("Rejected ingested event: {:#}", e);
+                    response.events_rejected += 1;
+                }
+            }
+        }
+
+        info!("Ingest stream closed: {} accepted, {} rejected", response.events_accepted, response.events_rejected);
+        Ok(Response::new(response))
+    }
+}
+
+fn requested_depth(depth: u32) -> Result<usize, Status> {
+    match depth as usize {
+        0 => Ok(DEFAULT_DEPTH),
+        depth if depth > MAX_DEPTH => {
+            Err(Status::invalid_argument(format!("Depth {depth} exceeds the maximum of {MAX_DEPTH}")))
+        }
+        depth => Ok(depth),
+    }
+}
+
+fn requested_interval(interval_ms: u32) -> Result<Duration, Status> {
+    match Duration::from_millis(u64::from(interval_ms)) {
+        Duration::ZERO => Ok(DEFAULT_ANALYTICS_INTERVAL),
+        interval if interval < MIN_ANALYTICS_INTERVAL => Err(Status::invalid_argument(format!(
+            "Interval {interval:?} is below the minimum of {MIN_ANALYTICS_INTERVAL:?}"
+        ))),
+        interval => Ok(interval),
+    }
+}
+
+fn to_proto_levels(levels: &[(Px, Qty, u64)]) -> Vec<pb::PriceLevel> {
+    levels
+        .iter()
+        .map(|(price, quantity, order_count)| pb::PriceLevel {
+            price: price.as_i64(),
+            quantity: quantity.as_i64(),
+            order_count: *order_count,
+        })
+        .collect()
+}
+
+fn to_proto_snapshot(symbol: &str, view: &DepthView) -> pb::BookSnapshot {
+    pb::BookSnapshot {
+        symbol: symbol.to_string(),
+        sequence: view.clock.sequence,
+        timestamp_nanos: view.clock.exchange_time,
+        bids: to_proto_levels(&view.bids),
+        asks: to_proto_levels(&view.asks),
+        checksum: view.checksum,
+    }
+}
+
+/// Levels of `new` that differ from `old`, and prices of `old` levels gone from `new`
+fn diff_levels(old: &[(Px, Qty, u64)], new: &[(Px, Qty, u64)]) -> (Vec<pb::PriceLevel>, Vec<i64>) {
+    let updates: Levels = new.iter().filter(|level| !old.contains(level)).copied().collect();
+    let deletions = old
+        .iter()
+        .filter(|(price, _, _)| !new.iter().any(|(p, _, _)| p == price))
+        .map(|(price, _, _)| price.as_i64())
+        .collect();
+    (to_proto_levels(&updates), deletions)
+}
+
+/// Delta taking a client from `old` to `new`, or `None` if the levels are unchanged
+fn diff_views(symbol: &str, old: &DepthView, new: &DepthView) -> Option<pb::BookDelta> {
+    let (bid_updates, bid_deletions) = diff_levels(&old.bids, &new.bids);
+    let (ask_updates, ask_deletions) = diff_levels(&old.asks, &new.asks);
+    if bid_updates.is_empty() && bid_deletions.is_empty() && ask_updates.is_empty() && ask_deletions.is_empty() {
+        return None;
+    }
+    Some(pb::BookDelta {
+        symbol: symbol.to_string(),
+        prev_sequence: old.clock.sequence,
+        sequence: new.clock.sequence,
+        timestamp_nanos: new.clock.exchange_time,
+        bid_updates,
+        ask_updates,
+        bid_deletions,
+        ask_deletions,
+    })
+}
+
+/// Converts an ingested message into the symbol it is for and its event
+///
+/// # Errors
+///
+/// Returns an error if the message has no symbol or payload, or carries an
+/// unspecified side, update type or market event type.
+pub fn event_from_proto(message: pb::BookEvent) -> Result<(String, OrderBookEvent)> {
+    if message.symbol.is_empty() {
+        anyhow::bail!("Event has no symbol");
+    }
+    let event = match message.event.context("Event has no payload")? {
+        pb::book_event::Event::Order(order) => OrderBookEvent::Order(OrderUpdate {
+            order_id: order.order_id,
+            price: Px::from_i64(order.price),
+            quantity: Qty::from_i64(order.quantity),
+            side: side_from_proto(order.side)?,
+            update_type: update_type_from_proto(order.update_type)?,
+            exchange_time: Ts::from_nanos(order.exchange_time_nanos),
+            local_time: Ts::from_nanos(order.local_time_nanos),
+            sequence: order.sequence,
+        }),
+        pb::book_event::Event::Trade(trade) => OrderBookEvent::Trade(TradeEvent {
+            trade_id: trade.trade_id,
+            price: Px::from_i64(trade.price),
+            quantity: Qty::from_i64(trade.quantity),
+            aggressor_side: side_from_proto(trade.aggressor_side)?,
+            maker_order_id: trade.maker_order_id,
+            taker_order_id: trade.taker_order_id,
+            exchange_time: Ts::from_nanos(trade.exchange_time_nanos),
+            local_time: Ts::from_nanos(trade.local_time_nanos),
+            sequence: trade.sequence,
+        }),
+        pb::book_event::Event::Snapshot(snapshot) => OrderBookEvent::Snapshot(OrderBookSnapshot {
+            symbol: Symbol::new(snapshot.symbol_id),
+            bids: levels_from_proto(&snapshot.bids, Side::Buy),
+            asks: levels_from_proto(&snapshot.asks, Side::Sell),
+            sequence: snapshot.sequence,
+            exchange_time: Ts::from_nanos(snapshot.exchange_time_nanos),
+            local_time: Ts::from_nanos(snapshot.local_time_nanos),
+            checksum: snapshot.checksum,
+        }),
+        pb::book_event::Event::Delta(delta) => OrderBookEvent::Delta(OrderBookDelta {
+            symbol: Symbol::new(delta.symbol_id),
+            bid_updates: levels_from_proto(&delta.bid_updates, Side::Buy),
+            ask_updates: levels_from_proto(&delta.ask_updates, Side::Sell),
+            bid_deletions: delta.bid_deletions.into_iter().map(Px::from_i64).collect(),
+            ask_deletions: delta.ask_deletions.into_iter().map(Px::from_i64).collect(),
+            prev_sequence: delta.prev_sequence,
+            sequence: delta.sequence,
+            exchange_time: Ts::from_nanos(delta.exchange_time_nanos),
+            local_time: Ts::from_nanos(delta.local_time_nanos),
+        }),
+        pb::book_event::Event::Market(market) => OrderBookEvent::Market(MarketEvent {
+            event_type: market_event_type_from_proto(market.event_type)?,
+            symbol: market.symbol_id.map(Symbol::new),
+            timestamp: Ts::from_nanos(market.timestamp_nanos),
+            message: market.message,
+        }),
+    };
+    Ok((message.symbol, event))
+}
+
+/// Converts an event for `symbol` into an ingest message
+pub fn event_to_proto(symbol: &str, event: &OrderBookEvent) -> pb::BookEvent {
+    let event = match event {
+        OrderBookEvent::Order(order) => pb::book_event::Event::Order(pb::OrderEvent {
+            order_id: order.order_id,
+            price: order.price.as_i64(),
+            quantity: order.quantity.as_i64(),
+            side: side_to_proto(order.side) as i32,
+            update_type: update_type_to_proto(order.update_type) as i32,
+            exchange_time_nanos: order.exchange_time.as_nanos(),
+            local_time_nanos: order.local_time.as_nanos(),
+            sequence: order.sequence,
+        }),
+        OrderBookEvent::Trade(trade) => pb::book_event::Event::Trade(pb::TradeEvent {
+            trade_id: trade.trade_id,
+            price: trade.price.as_i64(),
+            quantity: trade.quantity.as_i64(),
+            aggressor_side: side_to_proto(trade.aggressor_side) as i32,
+            maker_order_id: trade.maker_order_id,
+            taker_order_id: trade.taker_order_id,
+            exchange_time_nanos: trade.exchange_time.as_nanos(),
+            local_time_nanos: trade.local_time.as_nanos(),
+            sequence: trade.sequence,
+        }),
+        OrderBookEvent::Snapshot(snapshot) => pb::book_event::Event::Snapshot(pb::SnapshotEvent {
+            symbol_id: snapshot.symbol.0,
+            bids: levels_to_proto(&snapshot.bids),
+            asks: levels_to_proto(&snapshot.asks),
+            sequence: snapshot.sequence,
+            exchange_time_nanos: snapshot.exchange_time.as_nanos(),
+            local_time_nanos: snapshot.local_time.as_nanos(),
+            checksum: snapshot.checksum,
+        }),
+        OrderBookEvent::Delta(delta) => pb::book_event::Event::Delta(pb::DeltaEvent {
+            symbol_id: delta.symbol.0,
+            bid_updates: levels_to_proto(&delta.bid_updates),
+            ask_updates: levels_to_proto(&delta.ask_updates),
+            bid_deletions: delta.bid_deletions.iter().map(Px::as_i64).collect(),
+            ask_deletions: delta.ask_deletions.iter().map(Px::as_i64).collect(),
+            prev_sequence: delta.prev_sequence,
+            sequence: delta.sequence,
+            exchange_time_nanos: delta.exchange_time.as_nanos(),
+            local_time_nanos: delta.local_time.as_nanos(),
+        }),
+        OrderBookEvent::Market(market) => pb::book_event::Event::Market(pb::MarketEvent {
+            event_type: market_event_type_to_proto(market.event_type) as i32,
+            symbol_id: market.symbol.map(|symbol| symbol.0),
+            timestamp_nanos: market.timestamp.as_nanos(),
+            message: market.message.clone(),
+        }),
+    };
+    pb::BookEvent { symbol: symbol.to_string(), event: Some(event) }
+}
+
+fn levels_from_proto(levels: &[pb::PriceLevel], side: Side) -> Vec<LevelUpdate> {
+    levels
+        .iter()
+        .map(|level| LevelUpdate {
+            price: Px::from_i64(level.price),
+            quantity: Qty::from_i64(level.quantity),
+            order_count: level.order_count,
+            side,
+        })
+        .collect()
+}
+
+fn levels_to_proto(levels: &[LevelUpdate]) -> Vec<pb::PriceLevel> {
+    levels
+        .iter()
+        .map(|level| pb::PriceLevel {
+            price: level.price.as_i64(),
+            quantity: level.quantity.as_i64(),
+            order_count: level.order_count,
+        })
+        .collect()
+}
+
+fn side_from_proto(side: i32) -> Result<Side> {
+    match pb::Side::try_from(side) {
+        Ok(pb::Side::Buy) => Ok(Side::Buy),
+        Ok(pb::Side::Sell) => Ok(Side::Sell),
+        _ => anyhow::bail!("Invalid side {}", side),
+    }
+}
+
+const fn side_to_proto(side: Side) -> pb::Side {
+    match side {
+        Side::Buy => pb::Side::Buy,
+        Side::Sell => pb::Side::Sell,
+    }
+}
+
+fn update_type_from_proto(update_type: i32) -> Result<UpdateType> {
+    Ok(match pb::UpdateType::try_from(update_type) {
+        Ok(pb::UpdateType::Add) => UpdateType::Add,
+        Ok(pb::UpdateType::Modify) => UpdateType::Modify,
+        Ok(pb::UpdateType::Delete) => UpdateType::Delete,
+        Ok(pb::UpdateType::Trade) => UpdateType::Trade,
+        Ok(pb::UpdateType::Snapshot) => UpdateType::Snapshot,
+        Ok(pb::UpdateType::Delta) => UpdateType::Delta,
+        Ok(pb::UpdateType::Clear) => UpdateType::Clear,
+        _ => anyhow::bail!("Invalid update type {}", update_type),
+    })
+}
+
+const fn update_type_to_proto(update_type: UpdateType) -> pb::UpdateType {
+    match update_type {
+        UpdateType::Add => pb::UpdateType::Add,
+        UpdateType::Modify => pb::UpdateType::Modify,
+        UpdateType::Delete => pb::UpdateType::Delete,
+        UpdateType::Trade => pb::UpdateType::Trade,
+        UpdateType::Snapshot => pb::UpdateType::Snapshot,
+        UpdateType::Delta => pb::UpdateType::Delta,
+        UpdateType::Clear => pb::UpdateType::Clear,
+    }
+}
+
+fn market_event_type_from_proto(event_type: i32) -> Result<MarketEventType> {
+    Ok(match pb::MarketEventType::try_from(event_type) {
+        Ok(pb::MarketEventType::TradingHalt) => MarketEventType::TradingHalt,
+        Ok(pb::MarketEventType::TradingResume) => MarketEventType::TradingResume,
+        Ok(pb::MarketEventType::MarketOpen) => MarketEventType::MarketOpen,
+        Ok(pb::MarketEventType::MarketClose) => MarketEventType::MarketClose,
+        Ok(pb::MarketEventType::CircuitBreaker) => MarketEventType::CircuitBreaker,
+        Ok(pb::MarketEventType::AuctionStart) => MarketEventType::AuctionStart,
+        Ok(pb::MarketEventType::AuctionEnd) => MarketEventType::AuctionEnd,
+        _ => anyhow::bail!("Invalid market event type {}", event_type),
+    })
+}
+
+const fn market_event_type_to_proto(event_type: MarketEventType) -> pb::MarketEventType {
+    match event_type {
+        MarketEventType::TradingHalt => pb::MarketEventType::TradingHalt,
+        MarketEventType::TradingResume => pb::MarketEventType::TradingResume,
+        MarketEventType::MarketOpen => pb::MarketEventType::MarketOpen,
+        MarketEventType::MarketClose => pb::MarketEventType::MarketClose,
+        MarketEventType::CircuitBreaker => pb::MarketEventType::CircuitBreaker,
+        MarketEventType::AuctionStart => pb::MarketEventType::AuctionStart,
+        MarketEventType::AuctionEnd => pb::MarketEventType::AuctionEnd,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_stream::StreamExt;
+
+    fn level(price: i64, quantity: i64) -> LevelUpdate {
+        LevelUpdate { price: Px::from_i64(price), quantity: Qty::from_i64(quantity), order_count: 1, side: Side::Buy }
+    }
+
+    fn snapshot(sequence: u64) -> OrderBookEvent {
+        OrderBookEvent::Snapshot(OrderBookSnapshot {
+            symbol: Symbol::new(1),
+            bids: vec![level(99_0000, 10_0000), level(98_0000, 20_0000)],
+            asks: vec![level(101_0000, 5_0000), level(102_0000, 15_0000)],
+            sequence,
+            exchange_time: Ts::from_nanos(1_000),
+            local_time: Ts::from_nanos(1_500),
+            checksum: 0,
+        })
+    }
+
+    fn delta(sequence: u64, bid_updates: Vec<LevelUpdate>, ask_deletions: Vec<i64>) -> OrderBookEvent {
+        OrderBookEvent::Delta(OrderBookDelta {
+            symbol: Symbol::new(1),
+            bid_updates,
+            ask_updates: vec![],
+            bid_deletions: vec![],
+            ask_deletions: ask_deletions.into_iter().map(Px::from_i64).collect(),
+            prev_sequence: sequence - 1,
+            sequence,
+            exchange_time: Ts::from_nanos(2_000 + sequence),
+            local_time: Ts::from_nanos(2_500 + sequence),
+        })
+    }
+
+    fn service() -> OrderBookService {
+        OrderBookService::new(ReplayConfig { validate_checksums: false, ..ReplayConfig::default() })
+    }
+
+    fn prices(levels: &[pb::PriceLevel]) -> Vec<(i64, i64)> {
+        levels.iter().map(|level| (level.price, level.quantity)).collect()
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_reflects_ingested_deltas() {
+        let service = service();
+        service.ingest("NIFTY", snapshot(1)).unwrap();
+        service.ingest("NIFTY", delta(2, vec![level(99_0000, 7_0000), level(99_5000, 1_0000)], vec![101_0000])).unwrap();
+
+        let request = Request::new(pb::GetSnapshotRequest { symbol: "NIFTY".to_string(), depth: 2 });
+        let book = service.get_snapshot(request).await.unwrap().into_inner();
+        assert_eq!(book.sequence, 2);
+        assert_eq!(book.timestamp_nanos, 2_002);
+        assert_eq!(prices(&book.bids), [(99_5000, 1_0000), (99_0000, 7_0000)]);
+        assert_eq!(prices(&book.asks), [(102_0000, 15_0000)]);
+
+        let missing = Request::new(pb::GetSnapshotRequest { symbol: "BANKNIFTY".to_string(), depth: 0 });
+        assert_eq!(service.get_snapshot(missing).await.unwrap_err().code(), tonic::Code::NotFound);
+        let too_deep = Request::new(pb::GetSnapshotRequest { symbol: "NIFTY".to_string(), depth: 10_000 });
+        assert_eq!(service.get_snapshot(too_deep).await.unwrap_err().code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_book_stream_sends_snapshot_then_deltas() {
+        let service = service();
+        service.ingest("NIFTY", snapshot(1)).unwrap();
+        let request = Request::new(pb::StreamBookRequest { symbol: "NIFTY".to_string(), depth: 1 });
+        let mut stream = service.stream_book(request).await.unwrap().into_inner();
+
+        let Some(pb::book_update::Update::Snapshot(first)) = stream.next().await.unwrap().unwrap().update else {
+            panic!("Stream should open with a snapshot");
+        };
+        assert_eq!((first.sequence, prices(&first.bids)), (1, vec![(99_0000, 10_0000)]));
+
+        // Changes below the streamed depth produce no delta
+        service.ingest("NIFTY", delta(2, vec![level(97_0000, 1_0000)], vec![])).unwrap();
+        service.ingest("NIFTY", delta(3, vec![], vec![101_0000])).unwrap();
+        let Some(pb::book_update::Update::Delta(change)) = stream.next().await.unwrap().unwrap().update else {
+            panic!("Expected a delta");
+        };
+        assert_eq!((change.prev_sequence, change.sequence), (1, 3));
+        assert!(change.bid_updates.is_empty() && change.bid_deletions.is_empty());
+        assert_eq!(prices(&change.ask_updates), [(102_0000, 15_0000)]);
+        assert_eq!(change.ask_deletions, [101_0000]);
+    }
+
+    #[tokio::test]
+    async fn test_analytics_stream_reports_imbalance_and_flow() {
+        let service = service();
+        service.ingest("NIFTY", snapshot(1)).unwrap();
+        let trade = OrderBookEvent::Trade(TradeEvent {
+            trade_id: 1,
+            price: Px::from_i64(101_0000),
+            quantity: Qty::from_i64(2_0000),
+            aggressor_side: Side::Buy,
+            maker_order_id: None,
+            taker_order_id: None,
+            exchange_time: Ts::from_nanos(3_000),
+            local_time: Ts::from_nanos(3_100),
+            sequence: 2,
+        });
+        service.ingest("NIFTY", trade).unwrap();
+
+        let request = Request::new(pb::StreamAnalyticsRequest { symbol: "NIFTY".to_string(), interval_ms: 10 });
+        let mut stream = service.stream_analytics(request).await.unwrap().into_inner();
+        let update = stream.next().await.unwrap().unwrap();
+        assert_eq!(update.sequence, 2);
+        // 10 bid against 5 ask at the top, 30 against 20 over both levels
+        assert!((update.top_level_imbalance - 100.0 / 3.0).abs() < 1e-9);
+        assert!((update.three_level_imbalance - 20.0).abs() < 1e-9);
+        assert_eq!(update.flow_imbalance, 100.0);
+    }
+
+    #[tokio::test]
+    async fn test_streams_refuse_unknown_symbols_and_tiny_intervals() {
+        let service = service();
+        service.ingest("NIFTY", snapshot(1)).unwrap();
+
+        let book = Request::new(pb::StreamBookRequest { symbol: "BANKNIFTY".to_string(), depth: 0 });
+        assert_eq!(service.stream_book(book).await.err().unwrap().code(), tonic::Code::NotFound);
+        let analytics = Request::new(pb::StreamAnalyticsRequest { symbol: "BANKNIFTY".to_string(), interval_ms: 0 });
+        assert_eq!(service.stream_analytics(analytics).await.err().unwrap().code(), tonic::Code::NotFound);
+        assert_eq!(service.symbols(), ["NIFTY"]);
+
+        let too_fast = Request::new(pb::StreamAnalyticsRequest { symbol: "NIFTY".to_string(), interval_ms: 1 });
+        assert_eq!(service.stream_analytics(too_fast).await.err().unwrap().code(), tonic::Code::InvalidArgument);
+    }
+
+    #[test]
+    fn test_only_applied_trades_reach_analytics() {
+        let service = service();
+        service.ingest("NIFTY", snapshot(1)).unwrap();
+        let trade = |sequence: u64, aggressor_side| {
+            OrderBookEvent::Trade(TradeEvent {
+                trade_id: sequence,
+                price: Px::from_i64(100_0000),
+                quantity: Qty::from_i64(2_0000),
+                aggressor_side,
+                maker_order_id: None,
+                taker_order_id: None,
+                exchange_time: Ts::from_nanos(3_000 + sequence),
+                local_time: Ts::from_nanos(3_100 + sequence),
+                sequence,
+            })
+        };
+        let book = service.existing_book("NIFTY").unwrap();
+        let changes = book.changes.subscribe();
+
+        // A duplicate re-sent after a reconnect is ignored
+        assert_eq!(service.ingest("NIFTY", trade(2, Side::Buy)).unwrap(), 2);
+        assert_eq!(service.ingest("NIFTY", trade(3, Side::Sell)).unwrap(), 3);
+        assert_eq!(service.ingest("NIFTY", trade(3, Side::Sell)).unwrap(), 3);
+        assert_eq!(book.analytics.get_flow_imbalance(), 0.0);
+
+        // A buffered trade counts, and moves the book, once the gap closes
+        assert_eq!(service.ingest("NIFTY", trade(5, Side::Sell)).unwrap(), 3);
+        assert_eq!(*changes.borrow(), 3);
+        assert_eq!(book.analytics.get_flow_imbalance(), 0.0);
+        assert_eq!(service.ingest("NIFTY", trade(4, Side::Buy)).unwrap(), 5);
+        assert_eq!(*changes.borrow(), 5);
+        assert_eq!(book.depth(1).clock.exchange_time, 3_005);
+        assert_eq!(book.analytics.get_flow_imbalance(), 0.0);
+    }
+
+    #[test]
+    fn test_events_round_trip_through_proto() {
+        let events = [
+            snapshot(1),
+            delta(2, vec![level(99_0000, 7_0000)], vec![101_0000]),
+            OrderBookEvent::Order(OrderUpdate {
+                order_id: 7,
+                price: Px::from_i64(100_0000),
+                quantity: Qty::from_i64(3_0000),
+                side: Side::Sell,
+                update_type: UpdateType::Modify,
+                exchange_time: Ts::from_nanos(5),
+                local_time: Ts::from_nanos(6),
+                sequence: 3,
+            }),
+            OrderBookEvent::Market(MarketEvent {
+                event_type: MarketEventType::AuctionEnd,
+                symbol: Some(Symbol::new(1)),
+                timestamp: Ts::from_nanos(9),
+                message: "closing auction".to_string(),
+            }),
+        ];
+        for event in events {
+            let message = event_to_proto("NIFTY", &event);
+            let (symbol, decoded) = event_from_proto(message.clone()).unwrap();
+            assert_eq!(symbol, "NIFTY");
+            assert_eq!(event_to_proto(&symbol, &decoded), message);
+        }
+
+        assert!(event_from_proto(pb::BookEvent { symbol: "NIFTY".to_string(), event: None }).is_err());
+        let unsided = pb::BookEvent {
+            symbol: "NIFTY".to_string(),
+            event: Some(pb::book_event::Event::Order(pb::OrderEvent::default())),
+        };
+        assert!(event_from_proto(unsided).is_err());
+    }
+}
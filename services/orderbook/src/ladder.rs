@@ -0,0 +1,650 @@
+//! Array-based price ladder order book
+//!
+//! `LadderBook` keeps each side's levels in a fixed window of tick-indexed
+//! slots with an occupancy bitmap, so updates touch a slot directly and the
+//! best level is found with a few word scans rather than tree traversal.
+//! It suits instruments whose activity stays within a few hundred ticks of
+//! the mid, such as index futures and liquid crypto pairs.
+//!
+//! The window re-centres on a side's new best price when that price falls
+//! outside it, and on the best overflow level once removals leave that level
+//! as the side's best. Levels outside the window, or off the tick grid, are kept in
+//! an overflow map, so the book stays exact whatever prices arrive.
+
+use crate::core::{CHECKSUM_DEPTH, Order, PriceBook, Side, depth_checksum};
+use ahash::AHashMap;
+use parking_lot::RwLock;
+use services_common::{Px, Qty};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+
+/// Slots per bitmap word
+const WORD_BITS: usize = 64;
+
+/// Price level of the ladder
+#[derive(Debug, Default)]
+struct LadderLevel {
+    quantity: i64,
+    order_count: u64,
+    /// Individual orders at the level, in arrival order; empty for L2 levels
+    orders: Vec<Order>,
+}
+
+/// One side of the ladder
+#[derive(Debug)]
+struct LadderSide {
+    side: Side,
+    /// Tick size in fixed-point price units
+    tick: i64,
+    /// Tick index of slot 0
+    base: i64,
+    slots: Vec<LadderLevel>,
+    /// Bit `i` set when slot `i` holds a level
+    occupied: Vec<u64>,
+    /// Levels outside the window or off the tick grid, by price
+    overflow: BTreeMap<i64, LadderLevel>,
+}
+
+impl LadderSide {
+    fn new(side: Side, tick: i64, ticks: usize) -> Self {
+        Self {
+            side,
+            tick,
+            base: 0,
+            slots: (0..ticks).map(|_| LadderLevel::default()).collect(),
+            occupied: vec![0; ticks / WORD_BITS],
+            overflow: BTreeMap::new(),
+        }
+    }
+
+    const fn is_better(&self, price: i64, than: i64) -> bool {
+        match self.side {
+            Side::Bid => price > than,
+            Side::Ask => price < than,
+        }
+    }
+
+    fn slot_of(&self, price: i64) -> Option<usize> {
+        if price % self.tick != 0 {
+            return None;
+        }
+        usize::try_from(price / self.tick - self.base).ok().filter(|&slot| slot < self.slots.len())
+    }
+
+    const fn price_of(&self, slot: usize) -> i64 {
+        (self.base + slot as i64) * self.tick
+    }
+
+    fn is_occupied(&self, slot: usize) -> bool {
+        self.occupied[slot / WORD_BITS] & (1 << (slot % WORD_BITS)) != 0
+    }
+
+    /// Occupied slots best first, stopping once `visit` returns false
+    fn scan(&self, mut visit: impl FnMut(usize) -> bool) {
+        match self.side {
+            Side::Bid => {
+                for (word, &bits) in self.occupied.iter().enumerate().rev() {
+                    let mut bits = bits;
+                    while bits != 0 {
+                        let bit = WORD_BITS - 1 - bits.leading_zeros() as usize;
+                        if !visit(word * WORD_BITS + bit) {
+                            return;
+                        }
+                        bits &= !(1 << bit);
+                    }
+                }
+            }
+            Side::Ask => {
+                for (word, &bits) in self.occupied.iter().enumerate() {
+                    let mut bits = bits;
+                    while bits != 0 {
+                        let bit = bits.trailing_zeros() as usize;
+                        if !visit(word * WORD_BITS + bit) {
+                            return;
+                        }
+                        bits &= bits - 1;
+                    }
+                }
+            }
+        }
+    }
+
+    fn window_best(&self) -> Option<i64> {
+        let mut best = None;
+        self.scan(|slot| {
+            best = Some(self.price_of(slot));
+            false
+        });
+        best
+    }
+
+    fn overflow_best(&self) -> Option<i64> {
+        match self.side {
+            Side::Bid => self.overflow.keys().next_back().copied(),
+            Side::Ask => self.overflow.keys().next().copied(),
+        }
+    }
+
+    fn best(&self) -> Option<i64> {
+        match (self.window_best(), self.overflow_best()) {
+            (Some(window), Some(overflow)) => Some(if self.is_better(overflow, window) { overflow } else { window }),
+            (window, overflow) => window.or(overflow),
+        }
+    }
+
+    fn level(&self, price: i64) -> Option<&LadderLevel> {
+        self.slot_of(price).map_or_else(
+            || self.overflow.get(&price),
+            |slot| self.is_occupied(slot).then(|| &self.slots[slot]),
+        )
+    }
+
+    fn level_mut(&mut self, price: i64) -> Option<&mut LadderLevel> {
+        match self.slot_of(price) {
+            Some(slot) => self.is_occupied(slot).then(|| &mut self.slots[slot]),
+            None => self.overflow.get_mut(&price),
+        }
+    }
+
+    /// Level at `price`, created empty if absent
+    fn entry(&mut self, price: i64) -> &mut LadderLevel {
+        if self.slot_of(price).is_none() && price % self.tick == 0 && !self.overflow.contains_key(&price) {
+            // Keep the best level in the window
+            if self.best().is_none_or(|best| self.is_better(price, best)) {
+                self.recentre(price / self.tick);
+            }
+        }
+        match self.slot_of(price) {
+            Some(slot) => {
+                self.occupied[slot / WORD_BITS] |= 1 << (slot % WORD_BITS);
+                &mut self.slots[slot]
+            }
+            None => self.overflow.entry(price).or_default(),
+        }
+    }
+
+    fn remove(&mut self, price: i64) -> Option<LadderLevel> {
+        let removed = match self.slot_of(price) {
+            Some(slot) if self.is_occupied(slot) => {
+                self.occupied[slot / WORD_BITS] &= !(1 << (slot % WORD_BITS));
+                Some(std::mem::take(&mut self.slots[slot]))
+            }
+            Some(_) => None,
+            None => self.overflow.remove(&price),
+        };
+        if removed.is_some() {
+            self.recentre_on_overflow_best();
+        }
+        removed
+    }
+
+    /// Brings the best overflow level into the window once it is the side's best
+    fn recentre_on_overflow_best(&mut self) {
+        let Some(overflow) = self.overflow_best() else {
+            return;
+        };
+        // Off-grid levels within the window's range stay in overflow wherever it sits
+        let in_range = usize::try_from(overflow / self.tick - self.base).is_ok_and(|slot| slot < self.slots.len());
+        if !in_range && self.window_best().is_none_or(|window| self.is_better(overflow, window)) {
+            self.recentre(overflow / self.tick);
+        }
+    }
+
+    /// Moves the window so `tick_index` sits with room to improve on either side
+    fn recentre(&mut self, tick_index: i64) {
+        let ticks = self.slots.len();
+        // Bids improve upwards and asks downwards, so leave a quarter of the
+        // window beyond the new best
+        let offset = match self.side {
+            Side::Bid => ticks * 3 / 4,
+            Side::Ask => ticks / 4,
+        };
+        let mut levels = Vec::new();
+        self.scan(|slot| {
+            levels.push(slot);
+            true
+        });
+        let moved: Vec<(i64, LadderLevel)> = levels
+            .into_iter()
+            .map(|slot| (self.price_of(slot), std::mem::take(&mut self.slots[slot])))
+            .collect();
+        self.occupied.fill(0);
+        self.base = tick_index - offset as i64;
+        let overflow = std::mem::take(&mut self.overflow);
+        for (price, level) in moved.into_iter().chain(overflow) {
+            match self.slot_of(price) {
+                Some(slot) => {
+                    self.occupied[slot / WORD_BITS] |= 1 << (slot % WORD_BITS);
+                    self.slots[slot] = level;
+                }
+                None => {
+                    self.overflow.insert(price, level);
+                }
+            }
+        }
+    }
+
+    /// Top `count` levels as `(price, quantity, order_count)`, best first
+    fn depth(&self, count: usize) -> Vec<(Px, Qty, u64)> {
+        if self.overflow.is_empty() {
+            let mut levels = Vec::with_capacity(count);
+            self.scan(|slot| {
+                let level = &self.slots[slot];
+                levels.push((Px::from_i64(self.price_of(slot)), Qty::from_i64(level.quantity), level.order_count));
+                levels.len() < count
+            });
+            return levels;
+        }
+        let mut window = Vec::with_capacity(count);
+        self.scan(|slot| {
+            window.push((self.price_of(slot), &self.slots[slot]));
+            window.len() < count
+        });
+        let overflow: Vec<(i64, &LadderLevel)> = match self.side {
+            Side::Bid => self.overflow.iter().rev().take(count).map(|(&price, level)| (price, level)).collect(),
+            Side::Ask => self.overflow.iter().take(count).map(|(&price, level)| (price, level)).collect(),
+        };
+
+        let mut merged = Vec::with_capacity(count);
+        let (mut window, mut overflow) = (window.into_iter().peekable(), overflow.into_iter().peekable());
+        while merged.len() < count {
+            let next = match (window.peek(), overflow.peek()) {
+                (Some(&(w, _)), Some(&(o, _))) if self.is_better(o, w) => overflow.next(),
+                (Some(_), _) => window.next(),
+                (None, Some(_)) => overflow.next(),
+                (None, None) => break,
+            };
+            if let Some((price, level)) = next {
+                merged.push((Px::from_i64(price), Qty::from_i64(level.quantity), level.order_count));
+            }
+        }
+        merged
+    }
+
+    fn clear(&mut self) {
+        let mut levels = Vec::new();
+        self.scan(|slot| {
+            levels.push(slot);
+            true
+        });
+        for slot in levels {
+            self.slots[slot] = LadderLevel::default();
+        }
+        self.occupied.fill(0);
+        self.overflow.clear();
+    }
+}
+
+/// Order book backed by tick-indexed price ladders
+#[derive(Debug)]
+pub struct LadderBook {
+    symbol: String,
+    bids: RwLock<LadderSide>,
+    asks: RwLock<LadderSide>,
+    /// Best bid price, 0 when empty (atomic for lock-free access)
+    best_bid: AtomicI64,
+    /// Best ask price, `i64::MAX` when empty
+    best_ask: AtomicI64,
+    /// Sequence number for updates
+    sequence: AtomicU64,
+    /// Order ID to side and price, for cancels
+    order_map: RwLock<AHashMap<u64, (Side, Px)>>,
+    checksum: AtomicU64,
+    /// Set when the book changed since the checksum was computed
+    checksum_stale: AtomicBool,
+}
+
+impl LadderBook {
+    /// Create a book with windows of at least `ticks` slots of `tick_size` per side
+    ///
+    /// The window is rounded up to a multiple of 64 slots.
+    pub fn new(symbol: impl Into<String>, tick_size: Px, ticks: usize) -> Self {
+        let tick = tick_size.as_i64().max(1);
+        let ticks = ticks.max(1).div_ceil(WORD_BITS) * WORD_BITS;
+        Self {
+            symbol: symbol.into(),
+            bids: RwLock::new(LadderSide::new(Side::Bid, tick, ticks)),
+            asks: RwLock::new(LadderSide::new(Side::Ask, tick, ticks)),
+            best_bid: AtomicI64::new(0),
+            best_ask: AtomicI64::new(i64::MAX),
+            sequence: AtomicU64::new(0),
+            order_map: RwLock::new(AHashMap::new()),
+            checksum: AtomicU64::new(0),
+            checksum_stale: AtomicBool::new(false),
+        }
+    }
+
+    /// Tick size of the ladder
+    pub fn tick_size(&self) -> Px {
+        Px::from_i64(self.bids.read().tick)
+    }
+
+    /// Levels of a side held outside its window
+    pub fn overflow_levels(&self, side: Side) -> usize {
+        self.side(side).read().overflow.len()
+    }
+
+    const fn side(&self, side: Side) -> &RwLock<LadderSide> {
+        match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        }
+    }
+
+    fn store_best(&self, ladder: &LadderSide) {
+        match ladder.side {
+            Side::Bid => self.best_bid.store(ladder.best().unwrap_or(0), Ordering::Release),
+            Side::Ask => self.best_ask.store(ladder.best().unwrap_or(i64::MAX), Ordering::Release),
+        }
+    }
+
+    /// Marks the checksum for recomputation on the next read, keeping
+    /// formatting and hashing off the update path
+    fn update_checksum(&self) {
+        self.checksum_stale.store(true, Ordering::Release);
+    }
+}
+
+impl PriceBook for LadderBook {
+    fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    fn add_order(&self, order: Order) -> u64 {
+        let seq = self.sequence.fetch_add(1, Ordering::AcqRel);
+        self.order_map.write().insert(order.id, (order.side, order.price));
+        {
+            let mut ladder = self.side(order.side).write();
+            let level = ladder.entry(order.price.as_i64());
+            level.quantity += order.quantity.as_i64();
+            level.order_count += 1;
+            level.orders.push(order);
+            self.store_best(&ladder);
+        }
+        self.update_checksum();
+        seq
+    }
+
+    fn cancel_order(&self, order_id: u64) -> Option<Order> {
+        let _seq = self.sequence.fetch_add(1, Ordering::AcqRel);
+        let (side, price) = self.order_map.read().get(&order_id).copied()?;
+        let removed = {
+            let mut ladder = self.side(side).write();
+            let level = ladder.level_mut(price.as_i64())?;
+            let position = level.orders.iter().position(|order| order.id == order_id)?;
+            let order = level.orders.remove(position);
+            level.quantity -= order.quantity.as_i64();
+            level.order_count -= 1;
+            if level.order_count == 0 {
+                ladder.remove(price.as_i64());
+                self.store_best(&ladder);
+            }
+            order
+        };
+        self.order_map.write().remove(&order_id);
+        self.update_checksum();
+        Some(removed)
+    }
+
+    fn get_bbo(&self) -> (Option<Px>, Option<Px>) {
+        let bid = self.best_bid.load(Ordering::Acquire);
+        let ask = self.best_ask.load(Ordering::Acquire);
+        ((bid > 0).then(|| Px::from_i64(bid)), (ask < i64::MAX).then(|| Px::from_i64(ask)))
+    }
+
+    fn get_depth(&self, levels: usize) -> (Vec<(Px, Qty, u64)>, Vec<(Px, Qty, u64)>) {
+        (self.bids.read().depth(levels), self.asks.read().depth(levels))
+    }
+
+    fn get_checksum(&self) -> u64 {
+        if self.checksum_stale.swap(false, Ordering::AcqRel) {
+            let (bids, asks) = PriceBook::get_depth(self, CHECKSUM_DEPTH);
+            self.checksum.store(depth_checksum(&bids, &asks), Ordering::Release);
+        }
+        self.checksum.load(Ordering::Acquire)
+    }
+
+    fn get_bid_size_at(&self, price: Px) -> Option<Qty> {
+        self.bids.read().level(price.as_i64()).map(|level| Qty::from_i64(level.quantity))
+    }
+
+    fn get_ask_size_at(&self, price: Px) -> Option<Qty> {
+        self.asks.read().level(price.as_i64()).map(|level| Qty::from_i64(level.quantity))
+    }
+
+    fn set_level(&self, side: Side, price: Px, quantity: Qty, order_count: u64) {
+        let _seq = self.sequence.fetch_add(1, Ordering::AcqRel);
+        let replaced = {
+            let mut ladder = self.side(side).write();
+            let replaced = ladder.remove(price.as_i64());
+            if quantity.as_i64() > 0 {
+                let level = ladder.entry(price.as_i64());
+                level.quantity = quantity.as_i64();
+                level.order_count = order_count.max(1);
+            }
+            self.store_best(&ladder);
+            replaced
+        };
+
+        // Orders that were resting at the level can no longer be cancelled by ID
+        if let Some(old) = replaced {
+            let mut map = self.order_map.write();
+            for order in &old.orders {
+                map.remove(&order.id);
+            }
+        }
+        self.update_checksum();
+    }
+
+    fn clear(&self) {
+        self.bids.write().clear();
+        self.asks.write().clear();
+        self.order_map.write().clear();
+        self.best_bid.store(0, Ordering::Release);
+        self.best_ask.store(i64::MAX, Ordering::Release);
+        self.checksum.store(0, Ordering::Release);
+        self.checksum_stale.store(false, Ordering::Release);
+    }
+
+    fn load_snapshot(&self, bid_levels: Vec<(Px, Qty, u64)>, ask_levels: Vec<(Px, Qty, u64)>) {
+        self.clear();
+        for (side, levels) in [(Side::Bid, bid_levels), (Side::Ask, ask_levels)] {
+            let mut ladder = self.side(side).write();
+            for (price, quantity, order_count) in levels {
+                let level = ladder.entry(price.as_i64());
+                *level = LadderLevel {
+                    quantity: quantity.as_i64(),
+                    order_count: order_count.max(1),
+                    orders: Vec::new(),
+                };
+            }
+            self.store_best(&ladder);
+        }
+        self.update_checksum();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::OrderBook;
+    use proptest::prelude::*;
+    use services_common::Ts;
+    use std::collections::HashSet;
+
+    fn order(id: u64, side: Side, price: i64, quantity: i64) -> Order {
+        Order {
+            id,
+            price: Px::from_i64(price),
+            quantity: Qty::from_i64(quantity),
+            original_quantity: Qty::from_i64(quantity),
+            timestamp: Ts::from_nanos(id),
+            side,
+            is_iceberg: false,
+            visible_quantity: None,
+        }
+    }
+
+    fn assert_same(ladder: &LadderBook, tree: &OrderBook) {
+        assert_eq!(PriceBook::get_bbo(ladder), tree.get_bbo());
+        assert_eq!(PriceBook::get_depth(ladder, 50), tree.get_depth(50));
+        assert_eq!(PriceBook::get_checksum(ladder), tree.get_checksum());
+    }
+
+    #[test]
+    fn test_matches_btree_book() {
+        let ladder = LadderBook::new("TEST", Px::from_i64(50), 128);
+        let tree = OrderBook::new("TEST");
+        let orders = [
+            order(1, Side::Bid, 100_000, 10),
+            order(2, Side::Bid, 99_950, 20),
+            order(3, Side::Ask, 100_050, 5),
+            order(4, Side::Ask, 100_100, 7),
+            order(5, Side::Bid, 100_000, 3),
+            // Off the tick grid
+            order(6, Side::Ask, 100_075, 4),
+            // Far outside the window
+            order(7, Side::Bid, 10_000, 1),
+        ];
+        for order in orders {
+            PriceBook::add_order(&ladder, order.clone());
+            tree.add_order(order);
+            assert_same(&ladder, &tree);
+        }
+
+        for id in [1, 3, 7, 42] {
+            assert_eq!(PriceBook::cancel_order(&ladder, id).map(|o| o.id), tree.cancel_order(id).map(|o| o.id));
+            assert_same(&ladder, &tree);
+        }
+
+        PriceBook::set_level(&ladder, Side::Bid, Px::from_i64(100_000), Qty::from_i64(30), 2);
+        tree.set_level(Side::Bid, Px::from_i64(100_000), Qty::from_i64(30), 2);
+        assert_same(&ladder, &tree);
+        assert!(PriceBook::cancel_order(&ladder, 5).is_none());
+
+        PriceBook::set_level(&ladder, Side::Ask, Px::from_i64(100_050), Qty::ZERO, 0);
+        tree.set_level(Side::Ask, Px::from_i64(100_050), Qty::ZERO, 0);
+        assert_same(&ladder, &tree);
+    }
+
+    #[test]
+    fn test_recentres_on_new_best() {
+        let ladder = LadderBook::new("TEST", Px::from_i64(1), 64);
+        PriceBook::add_order(&ladder, order(1, Side::Bid, 1_000, 1));
+        assert_eq!(ladder.overflow_levels(Side::Bid), 0);
+
+        // Moving the best far up pushes the old level out of the window
+        PriceBook::add_order(&ladder, order(2, Side::Bid, 5_000, 1));
+        assert_eq!(ladder.overflow_levels(Side::Bid), 1);
+        assert_eq!(PriceBook::get_bbo(&ladder).0, Some(Px::from_i64(5_000)));
+
+        // Cancelling the best brings the overflow level back into the window
+        PriceBook::cancel_order(&ladder, 2);
+        assert_eq!(PriceBook::get_bbo(&ladder).0, Some(Px::from_i64(1_000)));
+        assert_eq!(ladder.overflow_levels(Side::Bid), 0);
+
+        // A worse price outside the window does not move it
+        PriceBook::add_order(&ladder, order(3, Side::Bid, 500, 1));
+        assert_eq!(ladder.overflow_levels(Side::Bid), 1);
+        let (bids, _) = PriceBook::get_depth(&ladder, 5);
+        let prices: Vec<_> = bids.iter().map(|(price, _, _)| price.as_i64()).collect();
+        assert_eq!(prices, [1_000, 500]);
+    }
+
+    #[test]
+    fn test_recentres_when_levels_walk_away() {
+        let ladder = LadderBook::new("TEST", Px::from_i64(1), 64);
+        PriceBook::set_level(&ladder, Side::Ask, Px::from_i64(1_000), Qty::from_i64(1), 1);
+        PriceBook::set_level(&ladder, Side::Ask, Px::from_i64(2_000), Qty::from_i64(1), 1);
+        assert_eq!(ladder.overflow_levels(Side::Ask), 1);
+
+        // Deleting the only level in the window moves it to the remaining best
+        PriceBook::set_level(&ladder, Side::Ask, Px::from_i64(1_000), Qty::ZERO, 0);
+        assert_eq!(ladder.overflow_levels(Side::Ask), 0);
+        assert_eq!(PriceBook::get_bbo(&ladder).1, Some(Px::from_i64(2_000)));
+    }
+
+    #[test]
+    fn test_snapshot_and_clear() {
+        let ladder = LadderBook::new("TEST", Px::from_i64(10), 64);
+        let tree = OrderBook::new("TEST");
+        let bids = vec![(Px::from_i64(1_000), Qty::from_i64(5), 2), (Px::from_i64(990), Qty::from_i64(8), 1)];
+        let asks = vec![(Px::from_i64(1_010), Qty::from_i64(3), 1), (Px::from_i64(5_000), Qty::from_i64(9), 4)];
+        PriceBook::load_snapshot(&ladder, bids.clone(), asks.clone());
+        tree.load_snapshot(bids, asks);
+        assert_same(&ladder, &tree);
+
+        PriceBook::clear(&ladder);
+        assert_eq!(PriceBook::get_bbo(&ladder), (None, None));
+        assert_eq!(PriceBook::get_depth(&ladder, 10), (Vec::new(), Vec::new()));
+    }
+
+    /// Book operation: add, cancel or set a level
+    #[derive(Debug, Clone)]
+    enum Op {
+        Add(u64, i64, i64, Side),
+        Cancel(u64),
+        SetLevel(Side, i64, i64, u64),
+    }
+
+    fn arb_side() -> impl Strategy<Value = Side> {
+        prop_oneof![Just(Side::Bid), Just(Side::Ask)]
+    }
+
+    /// Prices clustered near a mid with occasional outliers
+    fn arb_price() -> impl Strategy<Value = i64> {
+        prop_oneof![
+            4 => 99_000i64..101_000i64,
+            1 => 1_000i64..1_000_000i64,
+        ]
+    }
+
+    fn arb_op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            4 => (1u64..200u64, arb_price(), 1i64..1_000_000i64, arb_side())
+                .prop_map(|(id, price, qty, side)| Op::Add(id, price, qty, side)),
+            2 => (1u64..200u64).prop_map(Op::Cancel),
+            1 => (arb_side(), arb_price(), 0i64..1_000i64, 0u64..5u64)
+                .prop_map(|(side, price, qty, count)| Op::SetLevel(side, price, qty, count)),
+        ]
+    }
+
+    fn apply(book: &dyn PriceBook, op: &Op) -> Option<u64> {
+        match *op {
+            Op::Add(id, price, qty, side) => {
+                book.add_order(order(id, side, price, qty));
+                None
+            }
+            Op::Cancel(id) => book.cancel_order(id).map(|order| order.id),
+            Op::SetLevel(side, price, qty, count) => {
+                book.set_level(side, Px::from_i64(price), Qty::from_i64(qty), count);
+                None
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn prop_ladder_matches_btree_book(
+            tick in prop_oneof![Just(1i64), Just(5i64), Just(50i64)],
+            ops in prop::collection::vec(arb_op(), 1..200)
+        ) {
+            let tree = OrderBook::new("LADDER_TEST");
+            let ladder = LadderBook::new("LADDER_TEST", Px::from_i64(tick), 256);
+            let mut seen = HashSet::new();
+
+            for op in &ops {
+                // Order IDs are unique within a book
+                if let Op::Add(id, ..) = op
+                    && !seen.insert(*id)
+                {
+                    continue;
+                }
+                prop_assert_eq!(apply(&tree, op), apply(&ladder, op));
+                prop_assert_eq!(PriceBook::get_bbo(&tree), PriceBook::get_bbo(&ladder));
+                prop_assert_eq!(PriceBook::get_depth(&tree, 30), PriceBook::get_depth(&ladder, 30));
+                prop_assert_eq!(PriceBook::get_checksum(&tree), PriceBook::get_checksum(&ladder));
+            }
+        }
+    }
+}
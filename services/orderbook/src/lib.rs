@@ -31,13 +31,31 @@ pub mod analytics;
 pub mod events;
 pub mod replay;
 pub mod metrics;
+pub mod grpc_service;
+pub mod capture;
+pub mod surveillance;
+pub mod consolidated;
+pub mod auction;
+pub mod ladder;
+pub mod checksum;
+
+#[cfg(test)]
+#[path = "../tests/property/test_invariants.rs"]
+mod property_invariants;
 
 // Re-exports for convenience
-pub use crate::core::{OrderBook, Side};
-pub use crate::analytics::{MicrostructureAnalytics, ImbalanceCalculator, ImbalanceMetrics, ToxicityDetector};
+pub use crate::core::{OrderBook, PriceBook, Side};
+pub use crate::analytics::{MicrostructureAnalytics, ImbalanceCalculator, ImbalanceMetrics, ToxicityDetector, OrderFlowAnalytics, OrderFlowConfig};
 pub use crate::events::{OrderBookEvent, OrderUpdate, TradeEvent, OrderBookSnapshot, OrderBookDelta, MarketEvent};
 pub use crate::replay::{ReplayEngine, ReplayConfig};
 pub use crate::metrics::{PerformanceMetrics, MetricsSnapshot};
+pub use crate::grpc_service::OrderBookService;
+pub use crate::capture::{CapturePlayer, CaptureReader, CaptureRecorder, PlaybackSpeed, PlayerConfig};
+pub use crate::surveillance::{AlertKind, SurveillanceAlert, SurveillanceConfig, SurveillanceEngine};
+pub use crate::consolidated::{ConsolidatedBook, Nbbo, NbboUpdate};
+pub use crate::auction::{AuctionBook, AuctionOrder, AuctionResult, Equilibrium};
+pub use crate::ladder::LadderBook;
+pub use crate::checksum::{BookValidator, CheckOutcome, ChecksumMismatch, ChecksumValidator, ExchangeCheck, ResyncRequest};
 
 /// Maximum number of price levels to track per side
 const _MAX_DEPTH_LEVELS: usize = 100;
@@ -12,11 +12,14 @@ use anyhow::Result;
 use clap::{Parser, Subcommand};
 use orderbook::{
     core::{OrderBook, Order, Side},
-    events::{OrderBookEvent, OrderUpdate, TradeEvent, OrderBookSnapshot, OrderBookDelta, MarketEvent},
+    events::{OrderBookEvent, OrderUpdate, TradeEvent, OrderBookSnapshot, OrderBookDelta, MarketEvent, Side as EventSide},
     analytics::{MicrostructureAnalytics, ImbalanceCalculator, ToxicityDetector},
     replay::{ReplayEngine, ReplayConfig},
     metrics::{PerformanceMetrics, MetricsSnapshot},
+    grpc_service::OrderBookService,
+    auction::{AuctionBook, AuctionOrder},
 };
+use services_common::proto::OrderBookServiceServer;
 use services_common::types::{Px, Qty, Ts};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -961,46 +964,54 @@ impl OrderBookManager {
         info!("🔔 Simulating opening auction...");
         
         let start = Instant::now();
-        let mut order_id = 1u64;
-        let mut auction_orders = Vec::new();
+        let previous_close = Px::from_i64(100000);
+        let mut auction = AuctionBook::new(book.symbol(), previous_close);
+        let mut indicative = auction.subscribe();
         
         // Pre-open phase: collect orders without matching
         info!("  Phase 1: Pre-open order collection");
         
-        for i in 0..50 {
-            let price = Px::from(99500 + rand::random::<i64>() % 1000);
-            let side = if i % 2 == 0 { Side::Bid } else { Side::Ask };
-            let quantity = Qty::from(1000 + rand::random::<i64>() % 5000);
-            
-            auction_orders.push((price, side, quantity));
+        for order_id in 1..=50u64 {
+            let side = if order_id % 2 == 0 { EventSide::Buy } else { EventSide::Sell };
+            // Roughly one in ten orders is a market order
+            let price = (rand::random::<u8>() % 10 != 0).then(|| Px::from_i64(99500 + rand::random::<i64>().rem_euclid(1000)));
+            let quantity = Qty::from_i64(1000 + rand::random::<i64>().rem_euclid(5000));
             
-            book.add_order(Order {
+            auction.submit(AuctionOrder {
                 id: order_id,
+                side,
                 price,
                 quantity,
-                original_quantity: quantity,
-                timestamp: Ts::from(start.elapsed().as_nanos() as u64),
-                side,
-                is_iceberg: false,
-                visible_quantity: None,
-            });
-            order_id += 1;
+                time: Ts::from_nanos(start.elapsed().as_nanos() as u64),
+            })?;
         }
         
-        info!("  Collected {} auction orders", auction_orders.len());
+        info!("  Collected {} auction orders", auction.order_count());
+        while let Ok(update) = indicative.try_recv() {
+            if let Some(equilibrium) = update.equilibrium {
+                debug!("  Indicative price {:.4}, matched {}", equilibrium.price.as_f64(), equilibrium.matched.as_i64());
+            }
+        }
         
-        // Calculate theoretical opening price
-        tokio::time::sleep(Duration::from_secs(2)).await;
+        // Uncross at the equilibrium price
+        tokio::time::sleep(Duration::from_secs(2.min(duration))).await;
         info!("  Phase 2: Price discovery");
         
-        let (best_bid, best_ask) = book.get_bbo();
-        if let (Some(bid), Some(ask)) = (best_bid, best_ask) {
-            let theoretical_open = (bid.as_i64() + ask.as_i64()) / 2;
-            info!("  Theoretical opening price: {:.4}", theoretical_open as f64 / 10000.0);
+        let result = auction.uncross(Ts::from_nanos(start.elapsed().as_nanos() as u64))?;
+        match result.equilibrium {
+            Some(equilibrium) => info!(
+                "  Opening price: {:.4}, {} trades, volume {}, imbalance {}",
+                equilibrium.price.as_f64(),
+                result.trades.len(),
+                result.volume().as_i64(),
+                equilibrium.imbalance(),
+            ),
+            None => info!("  No equilibrium; book did not cross"),
         }
         
-        // Continuous trading begins
+        // Continuous trading begins with the unmatched orders
         info!("  Phase 3: Continuous trading begins");
+        result.load_residual(book);
         
         info!("✅ Opening auction completed");
         
@@ -1008,41 +1019,56 @@ impl OrderBookManager {
     }
     
     /// Simulate closing auction
-    async fn simulate_closing_auction(&self, book: &OrderBook, duration: u64) -> Result<()> {
+    async fn simulate_closing_auction(&self, book: &OrderBook, _duration: u64) -> Result<()> {
         info!("🔔 Simulating closing auction...");
         
         // Similar to opening but with MOC (Market on Close) orders
         info!("  Collecting MOC orders...");
         
         let start = Instant::now();
-        let mut order_id = 1u64;
+        let last_trade = book.get_mid().unwrap_or_else(|| Px::from_i64(100000));
+        let mut auction = AuctionBook::new(book.symbol(), last_trade);
         let mut moc_volume = 0i64;
         
-        for _ in 0..30 {
-            let side = if rand::random::<bool>() { Side::Bid } else { Side::Ask };
-            let quantity = Qty::from(5000 + rand::random::<i64>() % 10000);
+        for order_id in 1..=30u64 {
+            let side = if rand::random::<bool>() { EventSide::Buy } else { EventSide::Sell };
+            let quantity = Qty::from_i64(5000 + rand::random::<i64>().rem_euclid(10000));
             moc_volume += quantity.as_i64();
             
-            book.add_order(Order {
+            auction.submit(AuctionOrder {
                 id: order_id,
-                price: Px::from(100000), // MOC orders at market
+                side,
+                price: None,
                 quantity,
-                original_quantity: quantity,
-                timestamp: Ts::from(start.elapsed().as_nanos() as u64),
+                time: Ts::from_nanos(start.elapsed().as_nanos() as u64),
+            })?;
+        }
+        
+        // Limit orders around the last trade absorb the MOC imbalance
+        for order_id in 31..=60u64 {
+            let side = if order_id % 2 == 0 { EventSide::Buy } else { EventSide::Sell };
+            let price = Px::from_i64(last_trade.as_i64() - 500 + rand::random::<i64>().rem_euclid(1000));
+            let quantity = Qty::from_i64(1000 + rand::random::<i64>().rem_euclid(5000));
+            
+            auction.submit(AuctionOrder {
+                id: order_id,
                 side,
-                is_iceberg: false,
-                visible_quantity: None,
-            });
-            order_id += 1;
+                price: Some(price),
+                quantity,
+                time: Ts::from_nanos(start.elapsed().as_nanos() as u64),
+            })?;
         }
         
         info!("  MOC Volume: {:.2}", moc_volume as f64);
         info!("  Calculating closing price...");
         
-        let (best_bid, best_ask) = book.get_bbo();
-        if let (Some(bid), Some(ask)) = (best_bid, best_ask) {
-            let closing_price = (bid.as_i64() + ask.as_i64()) / 2;
-            info!("  Official closing price: {:.4}", closing_price as f64 / 10000.0);
+        let result = auction.uncross(Ts::from_nanos(start.elapsed().as_nanos() as u64))?;
+        if let Some(equilibrium) = result.equilibrium {
+            info!(
+                "  Official closing price: {:.4}, volume {}",
+                equilibrium.price.as_f64(),
+                result.volume().as_i64(),
+            );
         }
         
         info!("✅ Closing auction completed");
@@ -1304,8 +1330,7 @@ async fn main() -> Result<()> {
             info!("✅ Order Book service ready");
             info!("📊 Monitoring started - Press Ctrl+C to exit");
             
-            // Keep running
-            tokio::signal::ctrl_c().await?;
+            serve_grpc(&bind).await?;
             
             info!("👋 Shutting down gracefully...");
         }
@@ -1321,8 +1346,7 @@ async fn main() -> Result<()> {
             info!("✅ Order Book service ready");
             info!("📊 Monitoring started - Press Ctrl+C to exit");
 
-            // Keep running
-            tokio::signal::ctrl_c().await?;
+            serve_grpc("0.0.0.0:50052").await?;
 
             info!("👋 Shutting down gracefully...");
         }
@@ -1331,5 +1355,20 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// Serve the order book gRPC API on `bind` until Ctrl+C
+async fn serve_grpc(bind: &str) -> Result<()> {
+    let addr = bind.parse()?;
+    let service = OrderBookServiceServer::new(OrderBookService::default());
+    
+    tonic::transport::Server::builder()
+        .add_service(service)
+        .serve_with_shutdown(addr, async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+    
+    Ok(())
+}
+
 // Re-export rand for simulations
 use rand;
\ No newline at end of file
@@ -8,10 +8,10 @@
 
 use crate::core::{OrderBook, Order, Side};
 use crate::events::{OrderBookEvent, OrderUpdate, TradeEvent, OrderBookSnapshot, OrderBookDelta, UpdateType};
-use services_common::Ts;
+use crate::events::LevelUpdate;
+use services_common::{Qty, Symbol, Ts};
 use anyhow::{Result, bail};
 use std::collections::{BTreeMap, VecDeque};
-use std::sync::atomic::{AtomicU64, Ordering};
 use parking_lot::RwLock;
 use tracing::{info, warn, error, debug};
 
@@ -42,26 +42,92 @@ impl Default for ReplayConfig {
     }
 }
 
+/// Request for a snapshot to recover from a sequence gap
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotRequest {
+    /// Symbol of the book being rebuilt
+    pub symbol: String,
+    /// Last sequence applied to the book
+    pub last_sequence: u64,
+    /// Sequence of the earliest event buffered after the gap
+    pub first_buffered: u64,
+    /// Events buffered waiting for the gap to close
+    pub buffered: usize,
+}
+
+/// Callback asked for a snapshot when a sequence gap opens
+///
+/// It should arrange for a snapshot to reach the engine, as a
+/// `OrderBookEvent::Snapshot` or through `ReplayEngine::store_snapshot`. It
+/// is called without engine locks held, so it may do so synchronously.
+pub type SnapshotRequester = Box<dyn Fn(&SnapshotRequest) + Send + Sync>;
+
+/// Callback told of each trade applied to the book
+///
+/// It is called with engine locks held and must not call back into the engine.
+pub type TradeListener = Box<dyn Fn(&TradeEvent) + Send + Sync>;
+
+/// Gap currently open in the event sequence
+#[derive(Debug, Clone, Copy)]
+struct OpenGap {
+    /// Whether a snapshot has been requested since the book last moved
+    snapshot_requested: bool,
+}
+
 /// Replay engine for deterministic orderbook reconstruction
-#[derive(Debug)]
+///
+/// Events are applied in sequence. An event that does not follow the last
+/// one applied opens a gap: it is buffered, along with everything after it,
+/// until the missing events arrive or a snapshot newer than the book does.
+/// Gaps wider than `max_sequence_gap`, or that buffer more events than
+/// that, ask the `SnapshotRequester` for a snapshot.
 pub struct ReplayEngine {
     /// Configuration
     config: ReplayConfig,
+    /// Symbol of the book
+    symbol: String,
+    /// Exchange symbol ID, from the latest snapshot or delta
+    symbol_id: RwLock<Symbol>,
     /// Current orderbook state
     orderbook: RwLock<OrderBook>,
-    /// Event buffer for out-of-order events
+    /// Event buffer for out-of-order events, keyed by the sequence each follows
     event_buffer: RwLock<BTreeMap<u64, OrderBookEvent>>,
     /// Last processed sequence number
     last_sequence: RwLock<u64>,
+    /// Exchange time of the last sequenced event applied
+    last_exchange_time: RwLock<Ts>,
+    /// Gap waiting to be closed, if any
+    open_gap: RwLock<Option<OpenGap>>,
     /// Snapshot manager
-    #[allow(dead_code)]
     snapshot_manager: SnapshotManager,
+    /// Callback for snapshot requests
+    snapshot_requester: RwLock<Option<SnapshotRequester>>,
+    /// Callback for applied trades
+    trade_listener: RwLock<Option<TradeListener>>,
     /// Latency tracker
     latency_tracker: LatencyTracker,
     /// Statistics
     stats: RwLock<ReplayStats>,
-    /// Sequence counter for generating order IDs
-    sequence: AtomicU64,
+}
+
+impl std::fmt::Debug for ReplayEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReplayEngine")
+            .field("config", &self.config)
+            .field("symbol", &self.symbol)
+            .field("symbol_id", &self.symbol_id)
+            .field("orderbook", &self.orderbook)
+            .field("event_buffer", &self.event_buffer)
+            .field("last_sequence", &self.last_sequence)
+            .field("last_exchange_time", &self.last_exchange_time)
+            .field("open_gap", &self.open_gap)
+            .field("snapshot_manager", &self.snapshot_manager)
+            .field("snapshot_requester", &"<function>")
+            .field("trade_listener", &"<function>")
+            .field("latency_tracker", &self.latency_tracker)
+            .field("stats", &self.stats)
+            .finish()
+    }
 }
 
 impl ReplayEngine {
@@ -70,13 +136,18 @@ impl ReplayEngine {
         let symbol = symbol.into();
         Self {
             config: config.clone(),
+            symbol: symbol.clone(),
+            symbol_id: RwLock::new(Symbol::new(0)),
             orderbook: RwLock::new(OrderBook::new(symbol)),
             event_buffer: RwLock::new(BTreeMap::new()),
             last_sequence: RwLock::new(0),
+            last_exchange_time: RwLock::new(Ts::from_nanos(0)),
+            open_gap: RwLock::new(None),
             snapshot_manager: SnapshotManager::new(config.snapshot_interval),
+            snapshot_requester: RwLock::new(None),
+            trade_listener: RwLock::new(None),
             latency_tracker: LatencyTracker::new(),
             stats: RwLock::new(ReplayStats::default()),
-            sequence: AtomicU64::new(1000000), // Start with high number to avoid conflicts
         }
     }
 
@@ -91,52 +162,66 @@ impl ReplayEngine {
                 self.latency_tracker.record(latency);
             }
 
-        // Check sequence ordering
-        let last_seq = *self.last_sequence.read();
-        
         if sequence == 0 {
             // Market events don't have sequences, process immediately
             return self.apply_event(event);
         }
         
+        let last_seq = *self.last_sequence.read();
         if sequence <= last_seq {
             warn!("Duplicate or out-of-order event: {} <= {}", sequence, last_seq);
             return Ok(());
         }
         
-        if sequence == last_seq + 1 {
+        if let OrderBookEvent::Snapshot(snapshot) = event {
+            // A newer snapshot replaces the book whatever came before it
+            self.snapshot_manager.store_snapshot(snapshot.clone());
+            self.stats.write().snapshots_stored += 1;
+            self.apply_event(OrderBookEvent::Snapshot(snapshot))?;
+            self.resume_after_snapshot()?;
+            return Ok(());
+        }
+        
+        let follows = Self::follows(&event);
+        if follows <= last_seq {
             // In-order event, process immediately
+            let exchange_time = event.exchange_time();
             self.apply_event(event)?;
-            *self.last_sequence.write() = sequence;
+            self.advance(sequence, exchange_time);
             
             // Process any buffered events that are now in sequence
             self.process_buffered_events()?;
         } else {
             // Out-of-order event, buffer it
-            let gap = sequence - last_seq - 1;
-            
-            if gap > self.config.max_sequence_gap {
-                warn!("Large sequence gap detected: {} events missing", gap);
-                // Request snapshot to recover
-                self.request_snapshot()?;
-            }
-            
-            // Buffer the event
             let mut buffer = self.event_buffer.write();
             if buffer.len() >= self.config.buffer_size {
                 error!("Event buffer full, dropping oldest events");
                 // Remove oldest events
                 let keys: Vec<_> = buffer.keys().take(100).copied().collect();
+                self.stats.write().events_dropped += keys.len() as u64;
                 for key in keys {
                     buffer.remove(&key);
                 }
             }
-            buffer.insert(sequence, event);
+            buffer.insert(follows, event);
+            self.stats.write().events_buffered += 1;
         }
         
+        self.check_gap();
         Ok(())
     }
 
+    /// Sequence an event continues from
+    /// 
+    /// A delta may cover several sequence numbers, so it follows its
+    /// `prev_sequence`; other events follow the sequence before their own.
+    const fn follows(event: &OrderBookEvent) -> u64 {
+        match event {
+            OrderBookEvent::Delta(delta) => delta.prev_sequence,
+            other => other.sequence().saturating_sub(1),
+        }
+    }
+
     /// Apply an event to the orderbook
     fn apply_event(&self, event: OrderBookEvent) -> Result<()> {
         let mut stats = self.stats.write();
@@ -151,10 +236,15 @@ impl ReplayEngine {
                 stats.trades_processed += 1;
             }
             OrderBookEvent::Snapshot(snapshot) => {
-                self.apply_snapshot(snapshot)?;
+                *self.symbol_id.write() = snapshot.symbol;
+                if let Err(e) = self.apply_snapshot(snapshot) {
+                    stats.checksum_errors += 1;
+                    return Err(e);
+                }
                 stats.snapshots_processed += 1;
             }
             OrderBookEvent::Delta(delta) => {
+                *self.symbol_id.write() = delta.symbol;
                 self.apply_delta(delta)?;
                 stats.deltas_processed += 1;
             }
@@ -224,6 +314,9 @@ impl ReplayEngine {
     fn apply_trade(&self, trade: TradeEvent) -> Result<()> {
         // Update analytics with trade
         debug!("Trade: {} @ {} for {}", trade.trade_id, trade.price, trade.quantity);
+        if let Some(listener) = self.trade_listener.read().as_ref() {
+            listener(&trade);
+        }
         
         // In a real implementation, we'd update order quantities
         // and potentially remove filled orders
@@ -263,79 +356,38 @@ impl ReplayEngine {
         
         // Update sequence
         *self.last_sequence.write() = snapshot.sequence;
+        *self.last_exchange_time.write() = snapshot.exchange_time;
         
         Ok(())
     }
 
     /// Apply an incremental update
     fn apply_delta(&self, delta: OrderBookDelta) -> Result<()> {
-        // Verify sequence continuity
+        // Verify sequence continuity: the delta must cover the next sequence
         let last_seq = *self.last_sequence.read();
-        if delta.prev_sequence != last_seq {
-            bail!("Sequence gap in delta: expected {}, got {}", last_seq, delta.prev_sequence);
+        if delta.prev_sequence > last_seq || delta.sequence <= last_seq {
+            bail!("Sequence gap in delta: expected {}, got {}..{}", last_seq, delta.prev_sequence, delta.sequence);
         }
         
         let book = self.orderbook.read();
         
-        // Apply bid updates - add or modify orders at price levels
+        // Level updates carry the new total at each price
         for update in delta.bid_updates {
             debug!("Bid update: {} @ {} x{}", update.price, update.quantity, update.order_count);
-            
-            // Create synthetic order for the update
-            let order = Order {
-                id: self.sequence.fetch_add(1, Ordering::AcqRel),
-                price: update.price,
-                quantity: update.quantity,
-                original_quantity: update.quantity,
-                timestamp: Ts::now(),
-                side: Side::Bid,
-                is_iceberg: false,
-                visible_quantity: None,
-            };
-            book.add_order(order);
+            book.set_level(Side::Bid, update.price, update.quantity, update.order_count);
         }
-        
-        // Apply ask updates - add or modify orders at price levels
         for update in delta.ask_updates {
             debug!("Ask update: {} @ {} x{}", update.price, update.quantity, update.order_count);
-            
-            // Create synthetic order for the update
-            let order = Order {
-                id: self.sequence.fetch_add(1, Ordering::AcqRel),
-                price: update.price,
-                quantity: update.quantity,
-                original_quantity: update.quantity,
-                timestamp: Ts::now(),
-                side: Side::Ask,
-                is_iceberg: false,
-                visible_quantity: None,
-            };
-            book.add_order(order);
+            book.set_level(Side::Ask, update.price, update.quantity, update.order_count);
         }
         
-        // Remove deleted bid levels - find and cancel all orders at these prices
         for price in delta.bid_deletions {
             debug!("Removing bid level at {}", price);
-            let (bid_levels, _) = book.get_depth(100);
-            for (level_price, _, _) in bid_levels {
-                if level_price == price {
-                    // Would need to track order IDs per level for proper deletion
-                    // For now, this demonstrates the concept
-                    break;
-                }
-            }
+            book.set_level(Side::Bid, price, Qty::ZERO, 0);
         }
-        
-        // Remove deleted ask levels
         for price in delta.ask_deletions {
             debug!("Removing ask level at {}", price);
-            let (_, ask_levels) = book.get_depth(100);
-            for (level_price, _, _) in ask_levels {
-                if level_price == price {
-                    // Would need to track order IDs per level for proper deletion
-                    break;
-                }
-            }
+            book.set_level(Side::Ask, price, Qty::ZERO, 0);
         }
         
         *self.last_sequence.write() = delta.sequence;
@@ -343,19 +395,42 @@ impl ReplayEngine {
         Ok(())
     }
 
+    /// Records an applied event as the last one
+    fn advance(&self, sequence: u64, exchange_time: Ts) {
+        *self.last_sequence.write() = sequence;
+        *self.last_exchange_time.write() = exchange_time;
+        self.store_periodic_snapshot(sequence, exchange_time);
+    }
+
     /// Process buffered events that are now in sequence
+    /// 
+    /// Buffered events the book has already moved past, e.g. because a
+    /// snapshot overtook them, are discarded.
     fn process_buffered_events(&self) -> Result<()> {
         loop {
-            let next_seq = *self.last_sequence.read() + 1;
+            let last_seq = *self.last_sequence.read();
             
             let event = {
                 let mut buffer = self.event_buffer.write();
-                buffer.remove(&next_seq)
+                let mut next = None;
+                while let Some(entry) = buffer.first_entry() {
+                    if *entry.key() > last_seq {
+                        break;
+                    }
+                    let event = entry.remove();
+                    if event.sequence() > last_seq {
+                        next = Some(event);
+                        break;
+                    }
+                    debug!("Discarding buffered event {} behind the book", event.sequence());
+                }
+                next
             };
             
             if let Some(event) = event {
+                let (sequence, exchange_time) = (event.sequence(), event.exchange_time());
                 self.apply_event(event)?;
-                *self.last_sequence.write() = next_seq;
+                self.advance(sequence, exchange_time);
             } else {
                 break;
             }
@@ -364,18 +439,179 @@ impl ReplayEngine {
         Ok(())
     }
 
+    /// Continue from the buffer after a snapshot moved the book
+    fn resume_after_snapshot(&self) -> Result<()> {
+        if let Some(gap) = self.open_gap.write().as_mut() {
+            // The book moved, so a gap left open deserves a fresh request
+            gap.snapshot_requested = false;
+        }
+        self.process_buffered_events()?;
+        self.check_gap();
+        Ok(())
+    }
+
+    /// Opens, closes or escalates the gap after the book or buffer changed
+    fn check_gap(&self) {
+        let last_seq = *self.last_sequence.read();
+        let (first_buffered, buffered) = {
+            let buffer = self.event_buffer.read();
+            let first = buffer.iter().next().map(|(&follows, event)| (follows, event.sequence()));
+            (first, buffer.len())
+        };
+        
+        let request = {
+            let mut open_gap = self.open_gap.write();
+            let Some((follows, first_buffered)) = first_buffered else {
+                if open_gap.take().is_some() {
+                    info!("Sequence gap closed at {}", last_seq);
+                    self.stats.write().gaps_recovered += 1;
+                }
+                return;
+            };
+            
+            let gap = open_gap.get_or_insert_with(|| {
+                warn!("Sequence gap after {}: next buffered event is {}", last_seq, first_buffered);
+                self.stats.write().sequence_gaps += 1;
+                OpenGap { snapshot_requested: false }
+            });
+            let missing = follows - last_seq;
+            let max_gap = self.config.max_sequence_gap;
+            if gap.snapshot_requested || (missing <= max_gap && buffered as u64 <= max_gap) {
+                return;
+            }
+            gap.snapshot_requested = true;
+            SnapshotRequest { symbol: self.symbol.clone(), last_sequence: last_seq, first_buffered, buffered }
+        };
+        
+        self.request_snapshot(&request);
+    }
+
     /// Request a snapshot to recover from gaps
-    fn request_snapshot(&self) -> Result<()> {
-        warn!("Requesting snapshot to recover from sequence gap");
-        // In real implementation, would send snapshot request to exchange
+    fn request_snapshot(&self, request: &SnapshotRequest) {
+        warn!(
+            "Requesting snapshot to recover from sequence gap: {} missing after {}",
+            request.first_buffered.saturating_sub(request.last_sequence + 1),
+            request.last_sequence
+        );
+        self.stats.write().snapshot_requests += 1;
+        if let Some(requester) = self.snapshot_requester.read().as_ref() {
+            requester(request);
+        }
+    }
+
+    /// Set the callback asked for snapshots when a sequence gap opens
+    pub fn set_snapshot_requester(&self, requester: impl Fn(&SnapshotRequest) + Send + Sync + 'static) {
+        *self.snapshot_requester.write() = Some(Box::new(requester));
+    }
+
+    /// Set the callback told of each trade applied to the book
+    ///
+    /// Trades that are buffered are reported once the gap closes; duplicates
+    /// and trades a snapshot overtakes are never reported.
+    pub fn set_trade_listener(&self, listener: impl Fn(&TradeEvent) + Send + Sync + 'static) {
+        *self.trade_listener.write() = Some(Box::new(listener));
+    }
+
+    /// Store a snapshot obtained outside the event stream
+    /// 
+    /// If a gap is open and the snapshot is newer than the book, the book is
+    /// rebuilt from it and buffered events after it are applied.
+    pub fn store_snapshot(&self, snapshot: OrderBookSnapshot) -> Result<()> {
+        let sequence = snapshot.sequence;
+        self.snapshot_manager.store_snapshot(snapshot);
+        self.stats.write().snapshots_stored += 1;
+        
+        if self.open_gap.read().is_some() && sequence > *self.last_sequence.read() {
+            self.rebuild_from_snapshot(sequence + 1)?;
+        }
         Ok(())
     }
 
+    /// Rebuild the book from the latest stored snapshot before `sequence`
+    /// 
+    /// Buffered events that follow the snapshot are applied on top of it.
+    /// Returns the sequence the book is at afterwards, or `None` if no
+    /// snapshot before `sequence` is stored.
+    pub fn rebuild_from_snapshot(&self, sequence: u64) -> Result<Option<u64>> {
+        let Some(snapshot) = self.snapshot_manager.get_snapshot_before(sequence) else {
+            return Ok(None);
+        };
+        info!("Rebuilding {} from snapshot at sequence {}", self.symbol, snapshot.sequence);
+        
+        *self.symbol_id.write() = snapshot.symbol;
+        if let Err(e) = self.apply_snapshot(snapshot) {
+            self.stats.write().checksum_errors += 1;
+            return Err(e);
+        }
+        self.stats.write().rebuilds += 1;
+        self.resume_after_snapshot()?;
+        
+        Ok(Some(*self.last_sequence.read()))
+    }
+
+    /// Stores a snapshot of the book every `snapshot_interval` sequences
+    fn store_periodic_snapshot(&self, sequence: u64, exchange_time: Ts) {
+        let latest = self.snapshot_manager.latest_sequence().unwrap_or(0);
+        if self.config.snapshot_interval == 0 || !self.snapshot_manager.needs_snapshot(sequence, latest) {
+            return;
+        }
+        
+        let book = self.orderbook.read();
+        let (bids, asks) = book.get_depth(usize::MAX);
+        let levels = |levels: Vec<(services_common::Px, Qty, u64)>, side| {
+            levels
+                .into_iter()
+                .map(|(price, quantity, order_count)| LevelUpdate { price, quantity, order_count, side })
+                .collect()
+        };
+        let snapshot = OrderBookSnapshot {
+            symbol: *self.symbol_id.read(),
+            bids: levels(bids, crate::events::Side::Buy),
+            asks: levels(asks, crate::events::Side::Sell),
+            sequence,
+            exchange_time,
+            local_time: exchange_time,
+            checksum: book.get_checksum() as u32,
+        };
+        drop(book);
+        
+        debug!("Storing snapshot of {} at sequence {}", self.symbol, sequence);
+        self.snapshot_manager.store_snapshot(snapshot);
+        self.stats.write().snapshots_stored += 1;
+    }
+
+    /// Whether a sequence gap is waiting to be closed
+    pub fn has_open_gap(&self) -> bool {
+        self.open_gap.read().is_some()
+    }
+
+    /// Number of events buffered waiting for a gap to close
+    pub fn buffered_events(&self) -> usize {
+        self.event_buffer.read().len()
+    }
+
+    /// Last sequence applied to the book
+    pub fn last_sequence(&self) -> u64 {
+        *self.last_sequence.read()
+    }
+
+    /// Exchange time of the last sequenced event applied to the book
+    pub fn last_exchange_time(&self) -> Ts {
+        *self.last_exchange_time.read()
+    }
+
     /// Get current replay statistics
     pub fn get_stats(&self) -> ReplayStats {
         self.stats.read().clone()
     }
 
+    /// Run `f` against the orderbook being rebuilt
+    /// 
+    /// The book is read in place; `f` sees it between events.
+    pub fn with_orderbook<R>(&self, f: impl FnOnce(&OrderBook) -> R) -> R {
+        f(&self.orderbook.read())
+    }
+
     /// Get current orderbook state
     pub fn get_orderbook(&self) -> OrderBook {
         // In real implementation, would return a reference or snapshot
@@ -426,9 +662,14 @@ impl SnapshotManager {
             .map(|(_, snapshot)| snapshot.clone())
     }
 
+    /// Sequence of the most recent stored snapshot
+    pub fn latest_sequence(&self) -> Option<u64> {
+        self.snapshots.read().keys().next_back().copied()
+    }
+
     /// Check if a snapshot is needed
     pub const fn needs_snapshot(&self, current_sequence: u64, last_snapshot_sequence: u64) -> bool {
-        current_sequence - last_snapshot_sequence >= self.snapshot_interval
+        current_sequence.saturating_sub(last_snapshot_sequence) >= self.snapshot_interval
     }
 }
 
@@ -532,4 +773,179 @@ pub struct ReplayStats {
     pub checksum_errors: u64,
     /// Events buffered
     pub events_buffered: u64,
-}
\ No newline at end of file
+    /// Buffered events dropped because the buffer was full
+    pub events_dropped: u64,
+    /// Snapshots requested to close gaps
+    pub snapshot_requests: u64,
+    /// Gaps closed, by missing events arriving or a snapshot
+    pub gaps_recovered: u64,
+    /// Snapshots stored, from the event stream, callers or the book itself
+    pub snapshots_stored: u64,
+    /// Rebuilds from stored snapshots
+    pub rebuilds: u64,
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Side as EventSide;
+    use services_common::Px;
+    use std::sync::{Arc, Weak};
+
+    fn config(max_sequence_gap: u64, snapshot_interval: u64) -> ReplayConfig {
+        ReplayConfig { max_sequence_gap, validate_checksums: false, snapshot_interval, ..ReplayConfig::default() }
+    }
+
+    /// Bid at a price unique to the sequence, so levels show which orders applied
+    fn order(sequence: u64) -> OrderBookEvent {
+        OrderBookEvent::Order(OrderUpdate {
+            order_id: sequence,
+            price: Px::from_i64(1_000_000 - sequence as i64 * 100),
+            quantity: Qty::from_i64(10_000),
+            side: EventSide::Buy,
+            update_type: UpdateType::Add,
+            exchange_time: Ts::from_nanos(sequence * 1_000),
+            local_time: Ts::from_nanos(sequence * 1_000),
+            sequence,
+        })
+    }
+
+    fn snapshot(sequence: u64, bid_levels: usize) -> OrderBookSnapshot {
+        let bids = (0..bid_levels)
+            .map(|i| LevelUpdate {
+                price: Px::from_i64(500_000 - i as i64 * 100),
+                quantity: Qty::from_i64(20_000),
+                order_count: 1,
+                side: EventSide::Buy,
+            })
+            .collect();
+        OrderBookSnapshot {
+            symbol: Symbol::new(7),
+            bids,
+            asks: vec![],
+            sequence,
+            exchange_time: Ts::from_nanos(sequence * 1_000),
+            local_time: Ts::from_nanos(sequence * 1_000),
+            checksum: 0,
+        }
+    }
+
+    fn bid_levels(engine: &ReplayEngine) -> usize {
+        engine.with_orderbook(|book| book.get_depth(usize::MAX).0.len())
+    }
+
+    #[test]
+    fn test_small_gap_fills_from_buffer() {
+        let engine = ReplayEngine::new("NIFTY", config(5, 0));
+        engine.set_snapshot_requester(|_| panic!("No snapshot should be requested"));
+        for sequence in [1, 3, 4, 2] {
+            engine.process_event(order(sequence)).unwrap();
+        }
+
+        let stats = engine.get_stats();
+        assert_eq!((stats.sequence_gaps, stats.gaps_recovered, stats.snapshot_requests), (1, 1, 0));
+        assert_eq!((engine.last_sequence(), engine.buffered_events()), (4, 0));
+        assert!(!engine.has_open_gap());
+        assert_eq!(bid_levels(&engine), 4);
+    }
+
+    #[test]
+    fn test_wide_gap_requests_snapshot_and_recovers() {
+        let engine = ReplayEngine::new("NIFTY", config(5, 0));
+        let requests = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let seen = Arc::clone(&requests);
+        engine.set_snapshot_requester(move |request| seen.lock().push(request.clone()));
+
+        engine.process_event(order(1)).unwrap();
+        for sequence in 20..=25 {
+            engine.process_event(order(sequence)).unwrap();
+        }
+        assert!(engine.has_open_gap());
+        assert_eq!(
+            *requests.lock(),
+            [SnapshotRequest { symbol: "NIFTY".to_string(), last_sequence: 1, first_buffered: 20, buffered: 1 }]
+        );
+
+        // The snapshot overtakes buffered events 20 and 21; 22 to 25 follow it
+        engine.process_event(OrderBookEvent::Snapshot(snapshot(21, 3))).unwrap();
+        assert!(!engine.has_open_gap());
+        assert_eq!(engine.last_sequence(), 25);
+        assert_eq!(bid_levels(&engine), 3 + 4);
+
+        let stats = engine.get_stats();
+        assert_eq!((stats.sequence_gaps, stats.gaps_recovered, stats.snapshot_requests), (1, 1, 1));
+        assert_eq!((stats.events_buffered, stats.orders_processed), (6, 5));
+        assert_eq!(requests.lock().len(), 1);
+    }
+
+    #[test]
+    fn test_requester_may_supply_snapshot_synchronously() {
+        let engine = Arc::new(ReplayEngine::new("NIFTY", config(2, 0)));
+        let weak: Weak<ReplayEngine> = Arc::downgrade(&engine);
+        engine.set_snapshot_requester(move |request| {
+            let engine = weak.upgrade().unwrap();
+            engine.store_snapshot(snapshot(request.first_buffered - 1, 2)).unwrap();
+        });
+
+        engine.process_event(order(1)).unwrap();
+        engine.process_event(order(10)).unwrap();
+        engine.process_event(order(11)).unwrap();
+
+        assert!(!engine.has_open_gap());
+        assert_eq!(engine.last_sequence(), 11);
+        assert_eq!(bid_levels(&engine), 2 + 2);
+        let stats = engine.get_stats();
+        assert_eq!((stats.rebuilds, stats.gaps_recovered, stats.snapshots_stored), (1, 1, 1));
+    }
+
+    #[test]
+    fn test_periodic_snapshots_allow_rebuild() {
+        let engine = ReplayEngine::new("NIFTY", config(5, 10));
+        for sequence in 1..=25 {
+            engine.process_event(order(sequence)).unwrap();
+        }
+        assert_eq!(engine.get_stats().snapshots_stored, 2);
+        assert_eq!(bid_levels(&engine), 25);
+
+        assert_eq!(engine.rebuild_from_snapshot(20).unwrap(), Some(10));
+        assert_eq!(bid_levels(&engine), 10);
+        assert_eq!(engine.rebuild_from_snapshot(10).unwrap(), None);
+
+        // Replaying from the rebuilt book catches up again
+        for sequence in 11..=25 {
+            engine.process_event(order(sequence)).unwrap();
+        }
+        assert_eq!((engine.last_sequence(), bid_levels(&engine)), (25, 25));
+    }
+
+    #[test]
+    fn test_delta_covering_several_sequences_applies_in_order() {
+        let engine = ReplayEngine::new("NIFTY", config(5, 0));
+        engine.process_event(OrderBookEvent::Snapshot(snapshot(10, 1))).unwrap();
+        let delta = |prev_sequence, sequence, price| {
+            OrderBookEvent::Delta(OrderBookDelta {
+                symbol: Symbol::new(7),
+                bid_updates: vec![LevelUpdate {
+                    price: Px::from_i64(price),
+                    quantity: Qty::from_i64(10_000),
+                    order_count: 1,
+                    side: EventSide::Buy,
+                }],
+                ask_updates: vec![],
+                bid_deletions: vec![],
+                ask_deletions: vec![],
+                prev_sequence,
+                sequence,
+                exchange_time: Ts::from_nanos(sequence),
+                local_time: Ts::from_nanos(sequence),
+            })
+        };
+
+        // Arrives early and waits for the delta it follows
+        engine.process_event(delta(15, 18, 400_000)).unwrap();
+        assert!(engine.has_open_gap());
+        engine.process_event(delta(10, 15, 450_000)).unwrap();
+
+        assert!(!engine.has_open_gap());
+        assert_eq!((engine.last_sequence(), bid_levels(&engine)), (18, 3));
+    }
+}
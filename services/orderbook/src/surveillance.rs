@@ -0,0 +1,955 @@
+//! Order-lifecycle market surveillance
+//!
+//! `SurveillanceEngine` follows every order in an L3 event stream from
+//! placement to fill or cancel, and every trade's maker and taker, to spot
+//! the behaviour exchanges police:
+//! - Spoofing: a large order cancelled unfilled soon after its owner traded
+//!   on the other side
+//! - Layering: several unfilled orders at different prices on one side
+//!   cancelled together around trades on the other side
+//! - Quote stuffing: bursts of messages from one participant
+//! - Excessive cancel-to-trade ratios
+//! - Momentum ignition: a run of aggressive trades moving the price,
+//!   followed by trading the other way
+//! - Wash trades: both sides of a trade from the same participant
+//!
+//! Order IDs carry no owner, so orders are attributed to participants with
+//! `assign_participant`, typically our own strategies' order IDs as they
+//! are acknowledged. Unattributed orders still count towards market-wide
+//! statistics but raise no participant-level alerts.
+
+use crate::events::{OrderBookEvent, OrderUpdate, Side, TradeEvent, UpdateType};
+use ahash::AHashMap;
+use services_common::{Px, Qty, Ts};
+use std::collections::{BTreeMap, VecDeque};
+use std::mem::Discriminant;
+use tracing::warn;
+
+/// Thresholds for surveillance checks; times are in nanoseconds
+#[derive(Debug, Clone)]
+pub struct SurveillanceConfig {
+    /// Smallest order treated as a potential spoof
+    pub spoof_min_quantity: Qty,
+    /// Longest an order may rest and still count as a spoof
+    pub spoof_max_lifetime: u64,
+    /// Unfilled same-side cancels within `layering_window` that count as layering
+    pub layering_min_orders: usize,
+    /// Distinct prices those cancels must span
+    pub layering_min_levels: usize,
+    /// Window for layering cancels and the opposite-side trades around them
+    pub layering_window: u64,
+    /// Messages from one participant within `stuffing_window` that count as stuffing
+    pub stuffing_max_messages: usize,
+    /// Window for counting messages
+    pub stuffing_window: u64,
+    /// Cancels per execution above which a participant is flagged
+    pub max_cancel_to_trade_ratio: f64,
+    /// Orders a participant must place before its cancel ratio is judged
+    pub cancel_ratio_min_orders: u64,
+    /// Aggressive same-side trades that make a momentum run
+    pub ignition_min_trades: usize,
+    /// Price move of a momentum run, in basis points
+    pub ignition_min_move_bps: f64,
+    /// Longest gap between trades in a momentum run
+    pub ignition_window: u64,
+    /// Longest wait after a run for the reversing trade
+    pub ignition_reversal_window: u64,
+    /// Minimum time between repeated rate alerts for one participant
+    pub alert_cooldown: u64,
+}
+
+impl Default for SurveillanceConfig {
+    fn default() -> Self {
+        Self {
+            spoof_min_quantity: Qty::from_units(1_000),
+            spoof_max_lifetime: 2_000_000_000,
+            layering_min_orders: 3,
+            layering_min_levels: 3,
+            layering_window: 5_000_000_000,
+            stuffing_max_messages: 500,
+            stuffing_window: 1_000_000_000,
+            max_cancel_to_trade_ratio: 50.0,
+            cancel_ratio_min_orders: 100,
+            ignition_min_trades: 3,
+            ignition_min_move_bps: 20.0,
+            ignition_window: 1_000_000_000,
+            ignition_reversal_window: 10_000_000_000,
+            alert_cooldown: 60_000_000_000,
+        }
+    }
+}
+
+/// How strongly an alert suggests abuse
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Worth reviewing in aggregate
+    Low,
+    /// Pattern matches; review the evidence
+    Medium,
+    /// Pattern matches with profit-taking on the other side
+    High,
+}
+
+/// What was detected, with the evidence for it
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlertKind {
+    /// Large order cancelled unfilled after its owner traded on the other side
+    Spoofing {
+        /// Cancelled order
+        order_id: u64,
+        /// Side of the cancelled order
+        side: Side,
+        /// Price of the cancelled order
+        price: Px,
+        /// Quantity of the cancelled order
+        quantity: Qty,
+        /// Time from placement to cancel in nanoseconds
+        lifetime: u64,
+        /// Distance behind the touch when placed, in basis points; negative
+        /// if the order improved the touch
+        distance_from_touch_bps: Option<f64>,
+        /// Quantity the owner executed on the other side while it rested
+        opposite_executions: Qty,
+    },
+    /// Unfilled orders at several prices on one side cancelled together
+    Layering {
+        /// Side of the cancelled orders
+        side: Side,
+        /// Cancelled orders
+        order_ids: Vec<u64>,
+        /// Distinct prices of the cancelled orders
+        price_levels: usize,
+        /// Total quantity cancelled
+        cancelled_quantity: Qty,
+        /// Quantity the owner executed on the other side in the window
+        opposite_executions: Qty,
+    },
+    /// Burst of messages from one participant
+    QuoteStuffing {
+        /// Messages in the window
+        messages: usize,
+        /// Of which cancels
+        cancels: usize,
+        /// Window length in nanoseconds
+        window: u64,
+    },
+    /// Far more cancels than executions
+    ExcessiveCancelRatio {
+        /// Orders cancelled
+        cancels: u64,
+        /// Executions, as maker or taker
+        executions: u64,
+        /// Cancels per execution, counting no executions as one
+        ratio: f64,
+    },
+    /// Run of aggressive trades moving the price, then trading the other way
+    MomentumIgnition {
+        /// Side of the aggressive run
+        side: Side,
+        /// Trades in the run
+        trade_ids: Vec<u64>,
+        /// Price of the first trade in the run
+        start_price: Px,
+        /// Price of the last trade in the run
+        end_price: Px,
+        /// Move from start to end in the run's direction, in basis points
+        move_bps: f64,
+        /// Trade on the other side that followed the run
+        reversal_trade_id: u64,
+    },
+    /// Maker and taker of a trade are the same participant
+    WashTrade {
+        /// Trade identifier
+        trade_id: u64,
+        /// Passive order
+        maker_order_id: u64,
+        /// Aggressive order
+        taker_order_id: u64,
+        /// Traded price
+        price: Px,
+        /// Traded quantity
+        quantity: Qty,
+    },
+}
+
+/// Alert raised by the surveillance engine
+#[derive(Debug, Clone, PartialEq)]
+pub struct SurveillanceAlert {
+    /// What was detected, with evidence
+    pub kind: AlertKind,
+    /// Participant responsible
+    pub participant: String,
+    /// Exchange time of the event that completed the pattern
+    pub time: Ts,
+    /// How strongly the alert suggests abuse
+    pub severity: Severity,
+}
+
+/// Order activity of a participant, or of the whole market
+#[derive(Debug, Clone)]
+pub struct ParticipantStats {
+    /// Orders placed
+    pub orders_placed: u64,
+    /// Modifications to resting orders
+    pub orders_modified: u64,
+    /// Orders cancelled with quantity remaining
+    pub orders_cancelled: u64,
+    /// Orders filled completely
+    pub orders_filled: u64,
+    /// Trades taken part in, as maker or taker
+    pub executions: u64,
+    /// Quantity placed
+    pub placed_quantity: Qty,
+    /// Quantity executed
+    pub executed_quantity: Qty,
+    /// Quantity cancelled
+    pub cancelled_quantity: Qty,
+    /// Summed lifetime of cancelled orders in nanoseconds
+    pub cancelled_lifetime: u64,
+    /// Trades against themselves
+    pub self_trades: u64,
+}
+
+impl Default for ParticipantStats {
+    fn default() -> Self {
+        Self {
+            orders_placed: 0,
+            orders_modified: 0,
+            orders_cancelled: 0,
+            orders_filled: 0,
+            executions: 0,
+            placed_quantity: Qty::ZERO,
+            executed_quantity: Qty::ZERO,
+            cancelled_quantity: Qty::ZERO,
+            cancelled_lifetime: 0,
+            self_trades: 0,
+        }
+    }
+}
+
+impl ParticipantStats {
+    /// Cancels per execution, counting no executions as one
+    #[must_use] pub fn cancel_to_trade_ratio(&self) -> f64 {
+        self.orders_cancelled as f64 / self.executions.max(1) as f64
+    }
+
+    /// Orders placed per execution, counting no executions as one
+    #[must_use] pub fn order_to_trade_ratio(&self) -> f64 {
+        self.orders_placed as f64 / self.executions.max(1) as f64
+    }
+
+    /// Mean time cancelled orders rested, in nanoseconds
+    #[must_use] pub const fn mean_cancel_lifetime(&self) -> Option<u64> {
+        self.cancelled_lifetime.checked_div(self.orders_cancelled)
+    }
+}
+
+/// Order being followed through its life
+#[derive(Debug, Clone)]
+struct TrackedOrder {
+    participant: Option<String>,
+    side: Side,
+    price: Px,
+    quantity: Qty,
+    remaining: Qty,
+    filled: Qty,
+    placed_at: u64,
+    distance_from_touch_bps: Option<f64>,
+}
+
+/// Order cancelled unfilled, remembered for layering checks
+#[derive(Debug, Clone)]
+struct Cancel {
+    time: u64,
+    order_id: u64,
+    side: Side,
+    price: Px,
+    quantity: Qty,
+}
+
+/// Execution by a participant
+#[derive(Debug, Clone, Copy)]
+struct Execution {
+    time: u64,
+    side: Side,
+    quantity: Qty,
+}
+
+/// Consecutive aggressive trades on one side
+#[derive(Debug, Clone)]
+struct AggressiveRun {
+    side: Side,
+    trade_ids: Vec<u64>,
+    start_price: Px,
+    end_price: Px,
+    last_time: u64,
+}
+
+/// Recent activity of one participant
+#[derive(Debug, Default)]
+struct Activity {
+    stats: ParticipantStats,
+    /// Message times, and whether each was a cancel
+    messages: VecDeque<(u64, bool)>,
+    cancels: VecDeque<Cancel>,
+    executions: VecDeque<Execution>,
+    run: Option<AggressiveRun>,
+    last_alerts: AHashMap<Discriminant<AlertKind>, u64>,
+}
+
+impl Activity {
+    /// Quantity executed on `side` between `from` and `to`
+    fn executed_between(&self, side: Side, from: u64, to: u64) -> Qty {
+        let quantity = self
+            .executions
+            .iter()
+            .filter(|execution| execution.side == side && (from..=to).contains(&execution.time))
+            .map(|execution| execution.quantity.as_i64())
+            .sum();
+        Qty::from_i64(quantity)
+    }
+
+    /// Whether a rate alert of this kind is due, recording it if so
+    fn alert_due(&mut self, kind: &AlertKind, time: u64, cooldown: u64) -> bool {
+        let key = std::mem::discriminant(kind);
+        if self.last_alerts.get(&key).is_some_and(|&last| time.saturating_sub(last) < cooldown) {
+            return false;
+        }
+        self.last_alerts.insert(key, time);
+        true
+    }
+}
+
+/// Surveillance engine fed with an orderbook event stream
+#[derive(Debug)]
+pub struct SurveillanceEngine {
+    config: SurveillanceConfig,
+    orders: AHashMap<u64, TrackedOrder>,
+    owners: AHashMap<u64, String>,
+    participants: AHashMap<String, Activity>,
+    market: ParticipantStats,
+    /// Resting quantity by price, for the touch
+    bids: BTreeMap<Px, i64>,
+    asks: BTreeMap<Px, i64>,
+    alerts_raised: u64,
+}
+
+impl SurveillanceEngine {
+    /// Create an engine with the given thresholds
+    #[must_use] pub fn new(config: SurveillanceConfig) -> Self {
+        Self {
+            config,
+            orders: AHashMap::new(),
+            owners: AHashMap::new(),
+            participants: AHashMap::new(),
+            market: ParticipantStats::default(),
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            alerts_raised: 0,
+        }
+    }
+
+    /// Attribute an order to a participant
+    ///
+    /// May be called before or after the order's first event; later events
+    /// for the order are attributed from then on.
+    pub fn assign_participant(&mut self, order_id: u64, participant: impl Into<String>) {
+        let participant = participant.into();
+        if let Some(order) = self.orders.get_mut(&order_id) {
+            order.participant = Some(participant.clone());
+        }
+        self.owners.insert(order_id, participant);
+    }
+
+    /// Process an event, returning any alerts it completes
+    pub fn process_event(&mut self, event: &OrderBookEvent) -> Vec<SurveillanceAlert> {
+        let mut alerts = Vec::new();
+        match event {
+            OrderBookEvent::Order(update) => self.on_order(update, &mut alerts),
+            OrderBookEvent::Trade(trade) => self.on_trade(trade, &mut alerts),
+            OrderBookEvent::Snapshot(snapshot) => {
+                // L2 data only moves the touch; lifecycles need order events
+                self.bids = snapshot.bids.iter().map(|level| (level.price, level.quantity.as_i64())).collect();
+                self.asks = snapshot.asks.iter().map(|level| (level.price, level.quantity.as_i64())).collect();
+            }
+            OrderBookEvent::Delta(delta) => {
+                for level in &delta.bid_updates {
+                    set_level(&mut self.bids, level.price, level.quantity.as_i64());
+                }
+                for level in &delta.ask_updates {
+                    set_level(&mut self.asks, level.price, level.quantity.as_i64());
+                }
+                for price in &delta.bid_deletions {
+                    self.bids.remove(price);
+                }
+                for price in &delta.ask_deletions {
+                    self.asks.remove(price);
+                }
+            }
+            OrderBookEvent::Market(_) => {}
+        }
+
+        for alert in &alerts {
+            warn!("Surveillance alert for {}: {:?}", alert.participant, alert.kind);
+        }
+        self.alerts_raised += alerts.len() as u64;
+        alerts
+    }
+
+    /// Activity of a participant
+    #[must_use] pub fn participant_stats(&self, participant: &str) -> Option<&ParticipantStats> {
+        self.participants.get(participant).map(|activity| &activity.stats)
+    }
+
+    /// Activity of all orders, attributed or not
+    #[must_use] pub const fn market_stats(&self) -> &ParticipantStats {
+        &self.market
+    }
+
+    /// Orders currently resting
+    #[must_use] pub fn live_orders(&self) -> usize {
+        self.orders.len()
+    }
+
+    /// Alerts raised so far
+    #[must_use] pub const fn alerts_raised(&self) -> u64 {
+        self.alerts_raised
+    }
+
+    fn on_order(&mut self, update: &OrderUpdate, alerts: &mut Vec<SurveillanceAlert>) {
+        let time = update.exchange_time.as_nanos();
+        match update.update_type {
+            UpdateType::Add => {
+                let participant = self.owners.get(&update.order_id).cloned();
+                let order = TrackedOrder {
+                    distance_from_touch_bps: self.distance_from_touch(update.side, update.price),
+                    participant,
+                    side: update.side,
+                    price: update.price,
+                    quantity: update.quantity,
+                    remaining: update.quantity,
+                    filled: Qty::ZERO,
+                    placed_at: time,
+                };
+                self.rest(order.side, order.price, order.remaining.as_i64());
+                self.market.orders_placed += 1;
+                self.market.placed_quantity = self.market.placed_quantity.add(order.quantity);
+                if let Some(participant) = order.participant.clone() {
+                    let activity = self.participants.entry(participant.clone()).or_default();
+                    activity.stats.orders_placed += 1;
+                    activity.stats.placed_quantity = activity.stats.placed_quantity.add(order.quantity);
+                    self.record_message(&participant, time, false, alerts);
+                }
+                self.orders.insert(update.order_id, order);
+            }
+            UpdateType::Modify => {
+                let Some(order) = self.orders.get_mut(&update.order_id) else {
+                    return;
+                };
+                let (side, old_price, old_remaining) = (order.side, order.price, order.remaining.as_i64());
+                order.remaining = update.quantity;
+                order.price = update.price;
+                let participant = order.participant.clone();
+                self.rest(side, old_price, -old_remaining);
+                self.rest(side, update.price, update.quantity.as_i64());
+                self.market.orders_modified += 1;
+                if let Some(participant) = participant {
+                    self.participants.entry(participant.clone()).or_default().stats.orders_modified += 1;
+                    self.record_message(&participant, time, false, alerts);
+                }
+            }
+            UpdateType::Delete => {
+                if let Some(order) = self.orders.remove(&update.order_id) {
+                    self.owners.remove(&update.order_id);
+                    self.rest(order.side, order.price, -order.remaining.as_i64());
+                    self.on_cancel(update.order_id, order, time, alerts);
+                }
+            }
+            UpdateType::Clear => {
+                self.orders.clear();
+                self.bids.clear();
+                self.asks.clear();
+            }
+            UpdateType::Trade | UpdateType::Snapshot | UpdateType::Delta => {}
+        }
+    }
+
+    fn on_cancel(&mut self, order_id: u64, order: TrackedOrder, time: u64, alerts: &mut Vec<SurveillanceAlert>) {
+        let lifetime = time.saturating_sub(order.placed_at);
+        self.market.orders_cancelled += 1;
+        self.market.cancelled_quantity = self.market.cancelled_quantity.add(order.remaining);
+        self.market.cancelled_lifetime += lifetime;
+        let Some(participant) = order.participant.clone() else {
+            return;
+        };
+
+        let config = &self.config;
+        let activity = self.participants.entry(participant.clone()).or_default();
+        activity.stats.orders_cancelled += 1;
+        activity.stats.cancelled_quantity = activity.stats.cancelled_quantity.add(order.remaining);
+        activity.stats.cancelled_lifetime += lifetime;
+
+        if order.filled.is_zero() {
+            // Spoofing: large, short-lived, never filled, and paired with
+            // trading on the other side while it rested
+            let opposite = activity.executed_between(order.side.opposite(), order.placed_at, time);
+            if order.quantity >= config.spoof_min_quantity && lifetime <= config.spoof_max_lifetime && !opposite.is_zero() {
+                alerts.push(SurveillanceAlert {
+                    kind: AlertKind::Spoofing {
+                        order_id,
+                        side: order.side,
+                        price: order.price,
+                        quantity: order.quantity,
+                        lifetime,
+                        distance_from_touch_bps: order.distance_from_touch_bps,
+                        opposite_executions: opposite,
+                    },
+                    participant: participant.clone(),
+                    time: Ts::from_nanos(time),
+                    severity: Severity::High,
+                });
+            }
+
+            // Layering: unfilled orders at several prices cancelled together
+            activity.cancels.push_back(Cancel {
+                time,
+                order_id,
+                side: order.side,
+                price: order.price,
+                quantity: order.remaining,
+            });
+            let since = time.saturating_sub(config.layering_window);
+            while activity.cancels.front().is_some_and(|cancel| cancel.time < since) {
+                activity.cancels.pop_front();
+            }
+            let layer: Vec<_> = activity.cancels.iter().filter(|cancel| cancel.side == order.side).collect();
+            let mut prices: Vec<_> = layer.iter().map(|cancel| cancel.price).collect();
+            prices.sort_unstable();
+            prices.dedup();
+            let opposite = activity.executed_between(order.side.opposite(), since, time);
+            if layer.len() >= config.layering_min_orders
+                && prices.len() >= config.layering_min_levels
+                && !opposite.is_zero()
+            {
+                let cancelled = layer.iter().map(|cancel| cancel.quantity.as_i64()).sum();
+                alerts.push(SurveillanceAlert {
+                    kind: AlertKind::Layering {
+                        side: order.side,
+                        order_ids: layer.iter().map(|cancel| cancel.order_id).collect(),
+                        price_levels: prices.len(),
+                        cancelled_quantity: Qty::from_i64(cancelled),
+                        opposite_executions: opposite,
+                    },
+                    participant: participant.clone(),
+                    time: Ts::from_nanos(time),
+                    severity: Severity::High,
+                });
+                // Each cancel belongs to one layering alert
+                activity.cancels.retain(|cancel| cancel.side != order.side);
+            }
+        }
+
+        // Cancel-to-trade ratio, once there is enough history to judge
+        let stats = &activity.stats;
+        if stats.orders_placed >= config.cancel_ratio_min_orders
+            && stats.cancel_to_trade_ratio() > config.max_cancel_to_trade_ratio
+        {
+            let kind = AlertKind::ExcessiveCancelRatio {
+                cancels: stats.orders_cancelled,
+                executions: stats.executions,
+                ratio: stats.cancel_to_trade_ratio(),
+            };
+            if activity.alert_due(&kind, time, config.alert_cooldown) {
+                alerts.push(SurveillanceAlert {
+                    kind,
+                    participant: participant.clone(),
+                    time: Ts::from_nanos(time),
+                    severity: Severity::Low,
+                });
+            }
+        }
+
+        self.record_message(&participant, time, true, alerts);
+    }
+
+    fn on_trade(&mut self, trade: &TradeEvent, alerts: &mut Vec<SurveillanceAlert>) {
+        // Orders may trade without resting first, so fall back to their owners
+        let maker = trade.maker_order_id.and_then(|id| self.fill(id, trade.quantity).or_else(|| self.owner(id)));
+        let taker = trade.taker_order_id.and_then(|id| self.fill(id, trade.quantity).or_else(|| self.owner(id)));
+        self.market.executions += 1;
+        self.market.executed_quantity = self.market.executed_quantity.add(trade.quantity);
+
+        if let (Some(maker), Some(taker)) = (&maker, &taker)
+            && maker == taker
+        {
+            self.market.self_trades += 1;
+            self.participants.entry(maker.clone()).or_default().stats.self_trades += 1;
+            alerts.push(SurveillanceAlert {
+                kind: AlertKind::WashTrade {
+                    trade_id: trade.trade_id,
+                    maker_order_id: trade.maker_order_id.unwrap_or_default(),
+                    taker_order_id: trade.taker_order_id.unwrap_or_default(),
+                    price: trade.price,
+                    quantity: trade.quantity,
+                },
+                participant: maker.clone(),
+                time: trade.exchange_time,
+                severity: Severity::High,
+            });
+        }
+
+        if let Some(maker) = maker {
+            self.record_execution(&maker, trade, false, alerts);
+        }
+        if let Some(taker) = taker {
+            self.record_execution(&taker, trade, true, alerts);
+        }
+    }
+
+    fn owner(&self, order_id: u64) -> Option<String> {
+        self.owners.get(&order_id).cloned()
+    }
+
+    /// Applies a fill to a tracked order, returning its participant
+    fn fill(&mut self, order_id: u64, quantity: Qty) -> Option<String> {
+        let order = self.orders.get_mut(&order_id)?;
+        let filled = quantity.as_i64().min(order.remaining.as_i64());
+        order.remaining = Qty::from_i64(order.remaining.as_i64() - filled);
+        order.filled = order.filled.add(Qty::from_i64(filled));
+        let (side, price, participant) = (order.side, order.price, order.participant.clone());
+        let done = order.remaining.is_zero();
+        self.rest(side, price, -filled);
+
+        if done {
+            self.orders.remove(&order_id);
+            self.owners.remove(&order_id);
+            self.market.orders_filled += 1;
+            if let Some(participant) = &participant {
+                self.participants.entry(participant.clone()).or_default().stats.orders_filled += 1;
+            }
+        }
+        participant
+    }
+
+    /// Records a participant's side of a trade, as taker if `aggressive`
+    fn record_execution(
+        &mut self,
+        participant: &str,
+        trade: &TradeEvent,
+        aggressive: bool,
+        alerts: &mut Vec<SurveillanceAlert>,
+    ) {
+        let time = trade.exchange_time.as_nanos();
+        let side = if aggressive { trade.aggressor_side } else { trade.aggressor_side.opposite() };
+        let config = &self.config;
+        let activity = self.participants.entry(participant.to_string()).or_default();
+        activity.stats.executions += 1;
+        activity.stats.executed_quantity = activity.stats.executed_quantity.add(trade.quantity);
+        activity.executions.push_back(Execution { time, side, quantity: trade.quantity });
+        let horizon = config.spoof_max_lifetime.max(config.layering_window);
+        while activity.executions.front().is_some_and(|execution| execution.time + horizon < time) {
+            activity.executions.pop_front();
+        }
+
+        // A trade against the direction of a qualifying run completes momentum ignition
+        if let Some(run) = activity.run.take_if(|run| run.side != side) {
+            let move_bps = run_move_bps(&run);
+            if run.trade_ids.len() >= config.ignition_min_trades
+                && move_bps >= config.ignition_min_move_bps
+                && time.saturating_sub(run.last_time) <= config.ignition_reversal_window
+            {
+                alerts.push(SurveillanceAlert {
+                    kind: AlertKind::MomentumIgnition {
+                        side: run.side,
+                        trade_ids: run.trade_ids,
+                        start_price: run.start_price,
+                        end_price: run.end_price,
+                        move_bps,
+                        reversal_trade_id: trade.trade_id,
+                    },
+                    participant: participant.to_string(),
+                    time: trade.exchange_time,
+                    severity: Severity::High,
+                });
+            }
+        }
+
+        if aggressive {
+            match &mut activity.run {
+                Some(run) if time.saturating_sub(run.last_time) <= config.ignition_window => {
+                    run.trade_ids.push(trade.trade_id);
+                    run.end_price = trade.price;
+                    run.last_time = time;
+                }
+                run => {
+                    *run = Some(AggressiveRun {
+                        side,
+                        trade_ids: vec![trade.trade_id],
+                        start_price: trade.price,
+                        end_price: trade.price,
+                        last_time: time,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Counts a message towards quote stuffing
+    fn record_message(&mut self, participant: &str, time: u64, cancel: bool, alerts: &mut Vec<SurveillanceAlert>) {
+        let config = &self.config;
+        let Some(activity) = self.participants.get_mut(participant) else {
+            return;
+        };
+        activity.messages.push_back((time, cancel));
+        let since = time.saturating_sub(config.stuffing_window);
+        while activity.messages.front().is_some_and(|&(at, _)| at < since) {
+            activity.messages.pop_front();
+        }
+        if activity.messages.len() <= config.stuffing_max_messages {
+            return;
+        }
+
+        let kind = AlertKind::QuoteStuffing {
+            messages: activity.messages.len(),
+            cancels: activity.messages.iter().filter(|&&(_, cancel)| cancel).count(),
+            window: config.stuffing_window,
+        };
+        if activity.alert_due(&kind, time, config.alert_cooldown) {
+            alerts.push(SurveillanceAlert {
+                kind,
+                participant: participant.to_string(),
+                time: Ts::from_nanos(time),
+                severity: Severity::Medium,
+            });
+        }
+    }
+
+    /// Adds resting quantity at a price, removing the level when it empties
+    fn rest(&mut self, side: Side, price: Px, quantity: i64) {
+        let levels = if side.is_buy() { &mut self.bids } else { &mut self.asks };
+        let total = levels.get(&price).copied().unwrap_or(0) + quantity;
+        set_level(levels, price, total);
+    }
+
+    /// How far behind its side's touch a new order sits, in basis points
+    fn distance_from_touch(&self, side: Side, price: Px) -> Option<f64> {
+        let (touch, behind) = if side.is_buy() {
+            let touch = *self.bids.keys().next_back()?;
+            (touch, touch.as_i64() - price.as_i64())
+        } else {
+            let touch = *self.asks.keys().next()?;
+            (touch, price.as_i64() - touch.as_i64())
+        };
+        (touch.as_i64() != 0).then(|| behind as f64 / touch.as_i64() as f64 * 10_000.0)
+    }
+}
+
+impl Default for SurveillanceEngine {
+    fn default() -> Self {
+        Self::new(SurveillanceConfig::default())
+    }
+}
+
+fn set_level(levels: &mut BTreeMap<Px, i64>, price: Px, quantity: i64) {
+    if quantity > 0 {
+        levels.insert(price, quantity);
+    } else {
+        levels.remove(&price);
+    }
+}
+
+/// Price move of a run in its own direction, in basis points
+fn run_move_bps(run: &AggressiveRun) -> f64 {
+    let start = run.start_price.as_i64();
+    if start == 0 {
+        return 0.0;
+    }
+    let moved = run.end_price.as_i64() - start;
+    let moved = if run.side.is_buy() { moved } else { -moved };
+    moved as f64 / start as f64 * 10_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: u64 = 1_000_000;
+
+    fn px(price: f64) -> Px {
+        Px::new(price)
+    }
+
+    fn order(update_type: UpdateType, order_id: u64, side: Side, price: f64, units: i64, time: u64) -> OrderBookEvent {
+        OrderBookEvent::Order(OrderUpdate {
+            order_id,
+            price: px(price),
+            quantity: Qty::from_units(units),
+            side,
+            update_type,
+            exchange_time: Ts::from_nanos(time),
+            local_time: Ts::from_nanos(time),
+            sequence: 0,
+        })
+    }
+
+    fn trade(trade_id: u64, aggressor: Side, maker: u64, taker: u64, price: f64, units: i64, time: u64) -> OrderBookEvent {
+        OrderBookEvent::Trade(TradeEvent {
+            trade_id,
+            price: px(price),
+            quantity: Qty::from_units(units),
+            aggressor_side: aggressor,
+            maker_order_id: Some(maker),
+            taker_order_id: Some(taker),
+            exchange_time: Ts::from_nanos(time),
+            local_time: Ts::from_nanos(time),
+            sequence: 0,
+        })
+    }
+
+    fn run(engine: &mut SurveillanceEngine, events: &[OrderBookEvent]) -> Vec<SurveillanceAlert> {
+        events.iter().flat_map(|event| engine.process_event(event)).collect()
+    }
+
+    #[test]
+    fn test_spoof_requires_opposite_side_trading() {
+        let mut engine = SurveillanceEngine::default();
+        for id in [1, 2, 10] {
+            engine.assign_participant(id, "strat-a");
+        }
+        let alerts = run(
+            &mut engine,
+            &[
+                order(UpdateType::Add, 100, Side::Sell, 100.5, 10, 0),
+                order(UpdateType::Add, 101, Side::Buy, 99.9, 10, 0),
+                // Large bid behind the touch, then strat-a sells into the book
+                order(UpdateType::Add, 1, Side::Buy, 99.8, 5_000, MS),
+                trade(1, Side::Sell, 101, 10, 99.9, 10, 100 * MS),
+                order(UpdateType::Delete, 1, Side::Buy, 99.8, 0, 300 * MS),
+                // Same shape without the sell is only a cancel
+                order(UpdateType::Add, 2, Side::Buy, 99.8, 5_000, 10_000 * MS),
+                order(UpdateType::Delete, 2, Side::Buy, 99.8, 0, 10_300 * MS),
+            ],
+        );
+
+        assert_eq!(alerts.len(), 1);
+        let AlertKind::Spoofing { order_id, lifetime, distance_from_touch_bps, opposite_executions, .. } =
+            &alerts[0].kind
+        else {
+            panic!("Expected spoofing, got {:?}", alerts[0].kind);
+        };
+        assert_eq!((*order_id, *lifetime), (1, 299 * MS));
+        assert!((distance_from_touch_bps.unwrap() - 10.01).abs() < 0.01);
+        assert_eq!(*opposite_executions, Qty::from_units(10));
+        assert_eq!((alerts[0].participant.as_str(), alerts[0].severity), ("strat-a", Severity::High));
+
+        let stats = engine.participant_stats("strat-a").unwrap();
+        assert_eq!((stats.orders_placed, stats.orders_cancelled, stats.executions), (2, 2, 1));
+        assert_eq!(stats.mean_cancel_lifetime(), Some(300 * MS - MS / 2));
+        assert_eq!(engine.market_stats().orders_filled, 1);
+    }
+
+    #[test]
+    fn test_layering_cancels_across_levels() {
+        let mut engine = SurveillanceEngine::default();
+        for id in 1..=4 {
+            engine.assign_participant(id, "strat-b");
+        }
+        let mut events = vec![order(UpdateType::Add, 100, Side::Buy, 99.0, 10, 0)];
+        for (id, price) in [(1, 101.0), (2, 101.5), (3, 102.0)] {
+            events.push(order(UpdateType::Add, id, Side::Sell, price, 50, id * MS));
+        }
+        events.push(trade(1, Side::Buy, 100, 4, 99.0, 10, 10 * MS));
+        for id in 1..=3 {
+            events.push(order(UpdateType::Delete, id, Side::Sell, 0.0, 0, (20 + id) * MS));
+        }
+
+        let alerts = run(&mut engine, &events);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(
+            alerts[0].kind,
+            AlertKind::Layering {
+                side: Side::Sell,
+                order_ids: vec![1, 2, 3],
+                price_levels: 3,
+                cancelled_quantity: Qty::from_units(150),
+                opposite_executions: Qty::from_units(10),
+            }
+        );
+    }
+
+    #[test]
+    fn test_wash_trade_and_partial_fills() {
+        let mut engine = SurveillanceEngine::default();
+        engine.assign_participant(1, "strat-c");
+        engine.assign_participant(2, "strat-c");
+        let alerts = run(
+            &mut engine,
+            &[
+                order(UpdateType::Add, 1, Side::Sell, 100.0, 30, 0),
+                trade(1, Side::Buy, 1, 2, 100.0, 10, MS),
+                trade(2, Side::Buy, 1, 3, 100.0, 20, 2 * MS),
+            ],
+        );
+
+        assert_eq!(alerts.len(), 1);
+        assert!(matches!(alerts[0].kind, AlertKind::WashTrade { trade_id: 1, maker_order_id: 1, taker_order_id: 2, .. }));
+        let stats = engine.participant_stats("strat-c").unwrap();
+        assert_eq!((stats.self_trades, stats.executions, stats.orders_filled), (1, 3, 1));
+        assert_eq!(engine.live_orders(), 0);
+    }
+
+    #[test]
+    fn test_momentum_ignition_needs_reversal() {
+        let mut engine = SurveillanceEngine::default();
+        for id in 10..=13 {
+            engine.assign_participant(id, "strat-d");
+        }
+        let alerts = run(
+            &mut engine,
+            &[
+                trade(1, Side::Buy, 1, 10, 100.0, 5, 0),
+                trade(2, Side::Buy, 2, 11, 100.15, 5, 100 * MS),
+                trade(3, Side::Buy, 3, 12, 100.3, 5, 200 * MS),
+                trade(4, Side::Buy, 13, 4, 100.35, 15, 2_000 * MS),
+            ],
+        );
+
+        assert_eq!(alerts.len(), 1);
+        let AlertKind::MomentumIgnition { side, trade_ids, move_bps, reversal_trade_id, .. } = &alerts[0].kind else {
+            panic!("Expected momentum ignition, got {:?}", alerts[0].kind);
+        };
+        assert_eq!((*side, trade_ids.as_slice(), *reversal_trade_id), (Side::Buy, [1, 2, 3].as_slice(), 4));
+        assert!((move_bps - 30.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_rate_alerts_respect_cooldown() {
+        let config = SurveillanceConfig {
+            stuffing_max_messages: 10,
+            cancel_ratio_min_orders: 5,
+            max_cancel_to_trade_ratio: 3.0,
+            ..SurveillanceConfig::default()
+        };
+        let mut engine = SurveillanceEngine::new(config);
+        let mut events = Vec::new();
+        for id in 1..=20 {
+            engine.assign_participant(id, "strat-e");
+            events.push(order(UpdateType::Add, id, Side::Buy, 90.0 + id as f64 * 0.01, 1, id * MS));
+            events.push(order(UpdateType::Delete, id, Side::Buy, 0.0, 0, id * MS + 1));
+        }
+
+        let alerts = run(&mut engine, &events);
+        let kinds: Vec<_> = alerts.iter().map(|alert| std::mem::discriminant(&alert.kind)).collect();
+        let stuffing = std::mem::discriminant(&AlertKind::QuoteStuffing { messages: 0, cancels: 0, window: 0 });
+        let ratio = std::mem::discriminant(&AlertKind::ExcessiveCancelRatio { cancels: 0, executions: 0, ratio: 0.0 });
+        assert_eq!(kinds.iter().filter(|&&kind| kind == stuffing).count(), 1);
+        assert_eq!(kinds.iter().filter(|&&kind| kind == ratio).count(), 1);
+        assert_eq!(engine.alerts_raised(), 2);
+
+        let first_ratio = alerts.iter().find(|alert| std::mem::discriminant(&alert.kind) == ratio).unwrap();
+        assert_eq!(
+            first_ratio.kind,
+            AlertKind::ExcessiveCancelRatio { cancels: 5, executions: 0, ratio: 5.0 }
+        );
+    }
+}
@@ -1,8 +1,9 @@
 //! Test module organization for the orderbook service
 //! 
 //! This module provides a centralized way to organize and run all tests
-//! for the orderbook service, including unit tests, integration tests,
-//! and property-based tests.
+//! for the orderbook service, including unit tests and integration tests.
+//! The property-based invariants in `property/` run with the library's
+//! unit tests so they cover every `PriceBook` implementation.
 
 // Re-export test modules for easy access
 pub mod unit {
@@ -14,10 +15,6 @@ pub mod unit {
     pub mod test_integration;
 }
 
-pub mod property {
-    pub mod test_invariants;
-}
-
 #[cfg(test)]
 mod test_runner {
     /// Run all unit tests
@@ -26,13 +23,6 @@ mod test_runner {
         // This ensures all unit test modules are compiled and linked
         println!("All unit test modules are available for execution");
     }
-    
-    /// Run all property-based tests  
-    #[test]
-    fn run_all_property_tests() {
-        // This ensures all property test modules are compiled and linked
-        println!("All property test modules are available for execution");
-    }
 }
 
 /// Test configuration and utilities
@@ -247,11 +237,11 @@ pub mod generators {
 
 /// Test assertions and validators
 pub mod assertions {
-    use orderbook::OrderBook;
+    use orderbook::PriceBook;
     use services_common::{Px, Qty};
     
     /// Assert that orderbook satisfies basic invariants
-    pub fn assert_orderbook_invariants(book: &OrderBook) {
+    pub fn assert_orderbook_invariants(book: &(impl PriceBook + ?Sized)) {
         let (best_bid, best_ask) = book.get_bbo();
         
         // If both sides exist, spread should be non-negative
@@ -294,7 +284,7 @@ pub mod assertions {
     }
     
     /// Assert that two orderbooks have the same state
-    pub fn assert_orderbooks_equal(book1: &OrderBook, book2: &OrderBook) {
+    pub fn assert_orderbooks_equal(book1: &(impl PriceBook + ?Sized), book2: &(impl PriceBook + ?Sized)) {
         let (bid1, ask1) = book1.get_bbo();
         let (bid2, ask2) = book2.get_bbo();
         
@@ -2,11 +2,11 @@
 
 use crate::{OrderType, Side, SignalType, TimeInForce, TradingEvent};
 use anyhow::Result;
-use services_common::{Qty, Symbol};
+use services_common::{Qty, Symbol, Ts};
 use parking_lot::RwLock;
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 use tracing::info;
 
 /// Signal aggregator for combining strategy signals
@@ -17,7 +17,7 @@ use tracing::info;
 /// to different signal types to compute aggregate trading decisions.
 /// 
 /// # Features
-/// - Time-based signal expiry (default 5 seconds)
+/// - Time-based signal expiry (default 5 seconds of signal time)
 /// - Weighted signal aggregation by signal type
 /// - Confidence threshold filtering
 /// - Position sizing based on signal strength
@@ -52,7 +52,7 @@ struct SignalEntry {
     side: Side,
     strength: f64,
     confidence: f64,
-    timestamp: Instant,
+    timestamp: Ts,
 }
 
 impl SignalAggregator {
@@ -116,16 +116,16 @@ impl SignalAggregator {
             signal_type,
             strength,
             confidence,
-            ..
+            timestamp,
         } = signal {
             // Store signal
             {
                 let mut signals = self.recent_signals.write();
                 let symbol_signals = signals.entry(symbol).or_insert_with(Vec::new);
                 
-                // Remove expired signals
-                let now = Instant::now();
-                symbol_signals.retain(|s| now.duration_since(s.timestamp) < self.signal_expiry);
+                // Remove signals that expired by the new signal's timestamp
+                let expiry = self.signal_expiry.as_nanos() as u64;
+                symbol_signals.retain(|s| timestamp.as_nanos().saturating_sub(s.timestamp.as_nanos()) < expiry);
                 
                 // Add new signal
                 symbol_signals.push(SignalEntry {
@@ -133,7 +133,7 @@ impl SignalAggregator {
                     side,
                     strength,
                     confidence,
-                    timestamp: now,
+                    timestamp,
                 });
             }
             
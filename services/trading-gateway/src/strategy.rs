@@ -25,7 +25,10 @@ use tracing::info;
 /// - Long MA: 50-period moving average
 /// - Signal strength: Percentage difference between MAs
 /// - Signal confidence: Strength scaled to 0-100%
-/// - Rate limiting: Maximum 1 signal per second per symbol
+/// - Rate limiting: Maximum 1 signal per second of market time
+///
+/// Rate limiting and signal timestamps follow the `MarketUpdate` timestamps
+/// rather than the wall clock, so replayed data behaves as it did live.
 /// 
 /// # Risk Management
 /// - Requires minimum 50 price points for signal generation
@@ -41,8 +44,8 @@ pub struct MomentumStrategy {
     ma_long: Arc<RwLock<f64>>,
     /// Signal generation count
     signals_generated: AtomicU64,
-    /// Last signal time
-    last_signal: Arc<RwLock<Option<Instant>>>,
+    /// Market time of the last signal
+    last_signal: Arc<RwLock<Option<Ts>>>,
     /// Health metrics
     health: Arc<RwLock<ComponentHealth>>,
 }
@@ -198,15 +201,15 @@ impl TradingStrategy for MomentumStrategy {
             
             // Check for signal
             if let Some((side, strength, confidence)) = self.detect_signal(*mid) {
-                // Rate limit signals (max 1 per second)
+                // Rate limit signals (max 1 per second of market time)
                 {
                     let mut last_signal = self.last_signal.write();
                     if let Some(last) = *last_signal {
-                        if last.elapsed().as_secs() < 1 {
+                        if timestamp.as_nanos().saturating_sub(last.as_nanos()) < 1_000_000_000 {
                             return Ok(None);
                         }
                     }
-                    *last_signal = Some(Instant::now());
+                    *last_signal = Some(*timestamp);
                 }
                 
                 let signal_id = self.signals_generated.fetch_add(1, Ordering::SeqCst);
@@ -229,7 +232,7 @@ impl TradingStrategy for MomentumStrategy {
                     signal_type: SignalType::Momentum,
                     strength,
                     confidence,
-                    timestamp: Ts::now(),
+                    timestamp: *timestamp,
                 }));
             }
         }
@@ -379,7 +382,7 @@ impl TradingStrategy for ArbitrageStrategy {
     async fn on_market_update(&mut self, event: &TradingEvent) -> Result<Option<TradingEvent>> {
         let start = Instant::now();
         
-        if let TradingEvent::MarketUpdate { symbol, bid, ask, .. } = event {
+        if let TradingEvent::MarketUpdate { symbol, bid, ask, timestamp, .. } = event {
             // Detect arbitrage opportunity
             if let Some((side, strength, confidence)) = self.detect_arbitrage(*bid, *ask) {
                 let signal_id = self.signals_generated.fetch_add(1, Ordering::SeqCst);
@@ -401,7 +404,7 @@ impl TradingStrategy for ArbitrageStrategy {
                     signal_type: SignalType::Arbitrage,
                     strength,
                     confidence,
-                    timestamp: Ts::now(),
+                    timestamp: *timestamp,
                 }));
             }
         }